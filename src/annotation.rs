@@ -0,0 +1,67 @@
+// Import external modules or crates needed in annotation.rs
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in annotation.rs module
+pub enum AnnotationKind {
+    #[default]
+    Text,
+    Arrow,
+    Marker,
+    VLine, // Vertical line through the position's X, spanning the plot
+}
+
+/// Implementation block defining methods for this type
+impl AnnotationKind {
+    pub const ALL: [AnnotationKind; 4] = [
+        AnnotationKind::Text,
+        AnnotationKind::Arrow,
+        AnnotationKind::Marker,
+        AnnotationKind::VLine,
+    ];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            AnnotationKind::Text => "Text",
+            AnnotationKind::Arrow => "Arrow",
+            AnnotationKind::Marker => "Marker",
+            AnnotationKind::VLine => "Vertical line",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Data structure used in annotation.rs module
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    pub position: [f64; 2], // Data coordinates of the label, arrow tip or marker
+    #[serde(default)]
+    pub tail: Option<[f64; 2]>, // Arrow start (data coordinates); the text is drawn here
+    #[serde(default)]
+    pub text: String,
+    pub color: [u8; 3],
+}
+
+/// Implementation block defining methods for this type
+impl Annotation {
+/// Function: explain its purpose and key arguments
+    pub fn new(kind: AnnotationKind, position: [f64; 2], text: String, color: [u8; 3]) -> Self {
+        Self {
+            kind,
+            position,
+            tail: None,
+            text,
+            color,
+        }
+    }
+
+    // Where the annotation's text goes: the arrow tail for arrows, the position otherwise
+/// Function: explain its purpose and key arguments
+    pub fn label_position(&self) -> [f64; 2] {
+        match (self.kind, self.tail) {
+            (AnnotationKind::Arrow, Some(tail)) => tail,
+            _ => self.position,
+        }
+    }
+}
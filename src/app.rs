@@ -1,17 +1,77 @@
 // Import external modules or crates needed in app.rs
+use crate::annotation::{Annotation, AnnotationKind};
+// Import external modules or crates needed in app.rs
+use crate::command_palette::{Command, CommandPalette, ToolWindow};
+// Import external modules or crates needed in app.rs
+use crate::config::{save_preferences, save_recent_files, Preferences, RecentFiles};
+// Import external modules or crates needed in app.rs
 use crate::data_editor::DataEditor;
 // Import external modules or crates needed in app.rs
-use crate::dataset::Dataset;
+use crate::dataset::{
+    distribution_positions, fill_baseline, stacked_datasets, visible_datasets, AxisMap, ColorBy, Dataset, FillMode,
+    LinePattern, MarkerStyle, PlotType, DISTRIBUTION_WIDTH, FILL_OPACITY, STDIN_PATH,
+};
+// Import external modules or crates needed in app.rs
+use crate::heatmap::Heatmap;
+// Import external modules or crates needed in app.rs
+use crate::history::{History, Snapshot};
+// Import external modules or crates needed in app.rs
+use crate::palette::{Colormap, Palette};
+// Import external modules or crates needed in app.rs
+use crate::inset::{Inset, InsetGrab, MIN_PLACEMENT_SIZE};
+// Import external modules or crates needed in app.rs
+use crate::io::{
+    is_parquet, Delimiter, Hdf5Import, Hdf5Node, SheetTable, SpreadsheetImport,
+    TextImport, TextImportSettings,
+};
+// Import external modules or crates needed in app.rs
+use crate::display_cache::DisplayCache;
+// Import external modules or crates needed in app.rs
+use crate::diagnostics::FrameDiagnostics;
+// Import external modules or crates needed in app.rs
+use crate::loader::{LoadedContent, PendingLoad};
+// Import external modules or crates needed in app.rs
+use crate::export_job::ExportJob;
+// Import external modules or crates needed in app.rs
+use crate::folder::{Assignment, FolderImport};
+// Import external modules or crates needed in app.rs
+use crate::remote::{is_url, CACHE_MAX_AGE};
+// Import external modules or crates needed in app.rs
+use crate::notifications::Notifications;
+// Import external modules or crates needed in app.rs
+use crate::stdin::{StdinStream, StreamUpdate};
+// Import external modules or crates needed in app.rs
+use crate::watch::{read_source, set_sources, FileWatcher, TailReader, TailUpdate, LIVE_REPAINT_DELAY};
+// Import external modules or crates needed in app.rs
+use crate::session::{load_session, pick_session_file, pick_session_save_path, save_session};
+// Import external modules or crates needed in app.rs
+use crate::templates::{delete_template, load_templates, save_template, StyleTemplate};
+// Import external modules or crates needed in app.rs
+use crate::units::{conversion, find_unit, relabel, units_of, Quantity, Unit};
+// Import external modules or crates needed in app.rs
+use crate::timestamps::format_timestamp;
 // Import external modules or crates needed in app.rs
 use crate::utils::*;
 // Import external modules or crates needed in app.rs
+use crate::fit_report::{pick_fit_report_path, save_fit_report};
+// Import external modules or crates needed in app.rs
 use eframe::{egui, App, Frame};
 // Import external modules or crates needed in app.rs
-use egui_plot::{HLine, Legend, Line, LineStyle, Plot, PlotPoints, VLine};
+use egui_plot::{Arrows, AxisHints, Bar, BarChart, BoxElem, BoxPlot, BoxSpread, Corner, HLine, HPlacement, Legend, Line, LineStyle, MarkerShape, Plot, PlotBounds, PlotImage, PlotPoint, PlotPoints, PlotTransform, Polygon, Points, Text, VLine};
 // Import external modules or crates needed in app.rs
 use rand::Rng;
+// Import external modules or crates needed in app.rs
+use serde::{Deserialize, Serialize};
+// Import external modules or crates needed in app.rs
+use std::borrow::Cow;
+// Import external modules or crates needed in app.rs
+use std::collections::{HashMap, HashSet};
+// Import external modules or crates needed in app.rs
+use std::path::{Path, PathBuf};
+// Import external modules or crates needed in app.rs
+use std::sync::Arc;
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 /// Enum representing a set of related values in app.rs module
 pub enum FontSize {
     Small,
@@ -43,1031 +103,7298 @@ impl FontSize {
     }
 }
 
-#[derive(Debug, Clone)]
-/// Data structure used in app.rs module
-pub struct SubplotConfig {
-    pub show_grid: bool,
-    pub show_legend: bool,
-    pub legend_title: String,
-    pub use_custom_bounds: bool,
-    pub custom_x_min: String,
-    pub custom_x_max: String,
-    pub custom_y_min: String,
-    pub custom_y_max: String,
-    pub x_padding_percent: f64,
-    pub y_padding_percent: f64,
-    pub custom_x_ticks: String,
-    pub custom_y_ticks: String,
-    pub use_custom_x_ticks: bool,
-    pub use_custom_y_ticks: bool,
-    pub title: String,
+// Where a subplot's legend goes: a corner of the plot area, or a strip to the right of it
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in app.rs module
+pub enum LegendPosition {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    OutsideRight,
 }
 
 /// Implementation block defining methods for this type
-impl Default for SubplotConfig {
+impl LegendPosition {
+    pub const ALL: [LegendPosition; 5] = [
+        LegendPosition::TopLeft,
+        LegendPosition::TopRight,
+        LegendPosition::BottomLeft,
+        LegendPosition::BottomRight,
+        LegendPosition::OutsideRight,
+    ];
+
 /// Function: explain its purpose and key arguments
-    fn default() -> Self {
-        Self {
-            show_grid: false,
-            show_legend: true,
-            legend_title: "Datasets".to_string(),
-            use_custom_bounds: false,
-            custom_x_min: String::new(),
-            custom_x_max: String::new(),
-            custom_y_min: String::new(),
-            custom_y_max: String::new(),
-            x_padding_percent: 5.0,
-            y_padding_percent: 5.0,
-            custom_x_ticks: String::new(),
-            custom_y_ticks: String::new(),
-            use_custom_x_ticks: false,
-            use_custom_y_ticks: false,
-            title: String::new(),
+    pub fn to_string(self) -> &'static str {
+        match self {
+            LegendPosition::TopLeft => "Top left",
+            LegendPosition::TopRight => "Top right",
+            LegendPosition::BottomLeft => "Bottom left",
+            LegendPosition::BottomRight => "Bottom right",
+            LegendPosition::OutsideRight => "Outside right",
         }
     }
-}
-
-#[derive(Debug, Clone)]
-/// Data structure used in app.rs module
-pub struct Subplot {
-    pub id: String,
-    pub datasets: Vec<Dataset>,
-    pub config: SubplotConfig,
-}
 
-/// Implementation block defining methods for this type
-impl Subplot {
+    // Corner of the plot area as egui_plot names it; None outside the plot
 /// Function: explain its purpose and key arguments
-    pub fn new(id: String) -> Self {
-        Self {
-            id,
-            datasets: Vec::new(),
-            config: SubplotConfig::default(),
+    pub fn corner(self) -> Option<Corner> {
+        match self {
+            LegendPosition::TopLeft => Some(Corner::LeftTop),
+            LegendPosition::TopRight => Some(Corner::RightTop),
+            LegendPosition::BottomLeft => Some(Corner::LeftBottom),
+            LegendPosition::BottomRight => Some(Corner::RightBottom),
+            LegendPosition::OutsideRight => None,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 /// Enum representing a set of related values in app.rs module
-pub enum SubplotLayout {
-    Single,      // 1x1
-    Horizontal2, // 1x2
-    Vertical2,   // 2x1
-    Grid2x2,     // 2x2
-    Grid3x1,     // 3x1
-    Grid1x3,     // 1x3
-    Grid3x2,     // 3x2
-    Grid2x3,     // 2x3
+pub enum ScaleKind {
+    #[default]
+    Linear,
+    Log10,
+    Time, // Linear in seconds since the epoch, labelled with dates and times (UTC)
 }
 
 /// Implementation block defining methods for this type
-impl SubplotLayout {
+impl ScaleKind {
+    pub const ALL: [ScaleKind; 3] = [ScaleKind::Linear, ScaleKind::Log10, ScaleKind::Time];
+
 /// Function: explain its purpose and key arguments
-    pub fn to_string(&self) -> &'static str {
+    pub fn to_string(self) -> &'static str {
         match self {
-            SubplotLayout::Single => "Single (1x1)",
-            SubplotLayout::Horizontal2 => "Horizontal (1x2)",
-            SubplotLayout::Vertical2 => "Vertical (2x1)",
-            SubplotLayout::Grid2x2 => "Grid (2x2)",
-            SubplotLayout::Grid3x1 => "Grid (3x1)",
-            SubplotLayout::Grid1x3 => "Grid (1x3)",
-            SubplotLayout::Grid3x2 => "Grid (3x2)",
-            SubplotLayout::Grid2x3 => "Grid (2x3)",
+            ScaleKind::Linear => "Linear",
+            ScaleKind::Log10 => "Log10",
+            ScaleKind::Time => "Date/time",
         }
     }
 
 /// Function: explain its purpose and key arguments
-    pub fn dimensions(&self) -> (usize, usize) {
-        match self {
-            SubplotLayout::Single => (1, 1),
-            SubplotLayout::Horizontal2 => (1, 2),
-            SubplotLayout::Vertical2 => (2, 1),
-            SubplotLayout::Grid2x2 => (2, 2),
-            SubplotLayout::Grid3x1 => (3, 1),
-            SubplotLayout::Grid1x3 => (1, 3),
-            SubplotLayout::Grid3x2 => (3, 2),
-            SubplotLayout::Grid2x3 => (2, 3),
-        }
+    pub fn is_log(self) -> bool {
+        self == ScaleKind::Log10
     }
 
 /// Function: explain its purpose and key arguments
-    pub fn subplot_count(&self) -> usize {
-// Variable declaration
-        let (rows, cols) = self.dimensions();
-        rows * cols
+    pub fn is_time(self) -> bool {
+        self == ScaleKind::Time
     }
 }
 
+// How one axis maps data values to plot coordinates: its scale, and whether it runs
+// backwards. egui_plot cannot reverse an axis, so a reversed axis is drawn in negated plot
+// coordinates and labelled with the values they stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 /// Data structure used in app.rs module
-pub struct PlotterApp {
-    // Subplot system
-    pub subplots: Vec<Subplot>,
-    pub subplot_layout: SubplotLayout,
-    pub active_subplot: usize,
-    pub show_subplot_controls: bool,
-
-    // Global settings
-    pub next_name_index: usize,
-    pub error_message: Option<String>,
-    pub dark_mode: bool,
-    pub screenshot_requested: bool,
-    pub tick_font_size: FontSize,
-
-    // UI state
-    pub show_axis_controls: bool,
-    pub show_data_manipulation: bool,
-    pub show_color_picker: bool,
-    pub show_legend_controls: bool,
-
-    // Data manipulation fields
-    pub rolling_window_size: usize,
-    pub selected_dataset_for_processing: usize,
-    pub selected_dataset_for_color: usize,
-    pub data_editor: DataEditor,
+pub struct AxisScale {
+    pub kind: ScaleKind,
+    pub reversed: bool,
 }
 
 /// Implementation block defining methods for this type
-impl Default for PlotterApp {
-/// Function: explain its purpose and key arguments
-    fn default() -> Self {
-// Variable declaration
-        let mut app = Self {
-            subplots: Vec::new(),
-            subplot_layout: SubplotLayout::Single,
-            active_subplot: 0,
-            show_subplot_controls: false,
-            next_name_index: 1,
-            error_message: None,
-            dark_mode: true,
-            screenshot_requested: false,
-            tick_font_size: FontSize::Medium,
-            show_axis_controls: false,
-            show_data_manipulation: false,
-            show_color_picker: false,
-            show_legend_controls: false,
-            rolling_window_size: 10,
-            selected_dataset_for_processing: 0,
-            selected_dataset_for_color: 0,
-            data_editor: DataEditor::default(),
-        };
+impl AxisScale {
+    pub const LINEAR: AxisScale = AxisScale { kind: ScaleKind::Linear, reversed: false };
 
-        // Initialize with one subplot
-        app.ensure_subplots_match_layout();
-        app
+/// Function: explain its purpose and key arguments
+    pub fn new(kind: ScaleKind, reversed: bool) -> Self {
+        Self { kind, reversed }
     }
-}
 
-/// Implementation block defining methods for this type
-impl PlotterApp {
 /// Function: explain its purpose and key arguments
-    fn ensure_subplots_match_layout(&mut self) {
-// Variable declaration
-        let required_count = self.subplot_layout.subplot_count();
+    pub fn is_log(self) -> bool {
+        self.kind.is_log()
+    }
 
-        // Remove excess subplots
-        if self.subplots.len() > required_count {
-            self.subplots.truncate(required_count);
+    // A data value as readouts show it: a number, or a date and time on time axes
+/// Function: explain its purpose and key arguments
+    pub fn format_value(self, value: f64) -> String {
+        match self.kind {
+            ScaleKind::Time => format_timestamp(value),
+            _ => format_number(value),
         }
+    }
 
-        // Add missing subplots
-        while self.subplots.len() < required_count {
+    // Map a data value to plot coordinates; None when it cannot be shown (<= 0 on a log axis)
+/// Function: explain its purpose and key arguments
+    pub fn apply(self, value: f64) -> Option<f64> {
 // Variable declaration
-            let id = format!("subplot_{}", self.subplots.len());
-            self.subplots.push(Subplot::new(id));
-        }
+        let coordinate = match self.kind {
+            ScaleKind::Linear | ScaleKind::Time => Some(value),
+            ScaleKind::Log10 => (value > 0.0).then(|| value.log10()),
+        }?;
+        Some(if self.reversed { -coordinate } else { coordinate })
+    }
 
-        // Ensure active subplot is valid
-        if self.active_subplot >= self.subplots.len() {
-            self.active_subplot = 0;
+    // Map a plot coordinate back to the data value
+/// Function: explain its purpose and key arguments
+    pub fn invert(self, value: f64) -> f64 {
+// Variable declaration
+        let coordinate = if self.reversed { -value } else { value };
+        match self.kind {
+            ScaleKind::Linear | ScaleKind::Time => coordinate,
+            ScaleKind::Log10 => 10f64.powf(coordinate),
         }
     }
 
+    // Plot coordinates of the data values `low` and `high`, smallest first (a reversed axis
+    // swaps them); None when either cannot be shown
 /// Function: explain its purpose and key arguments
-    pub fn get_active_subplot_mut(&mut self) -> Option<&mut Subplot> {
-        self.subplots.get_mut(self.active_subplot)
+    pub fn apply_range(self, low: f64, high: f64) -> Option<(f64, f64)> {
+// Variable declaration
+        let (a, b) = (self.apply(low)?, self.apply(high)?);
+        Some((a.min(b), a.max(b)))
     }
 
+    // Plot coordinate of box or violin slot `position` (see distribution_positions). Slots
+    // are numbered in plot coordinates on every scale, so on a log axis they keep their
+    // spacing and width instead of crowding together around log10(1).
 /// Function: explain its purpose and key arguments
-    pub fn get_active_subplot(&self) -> Option<&Subplot> {
-        self.subplots.get(self.active_subplot)
+    pub fn slot(self, position: f64) -> f64 {
+        if self.reversed { -position } else { position }
     }
 }
 
-/// Implementation block defining methods for this type
-impl App for PlotterApp {
+// Line and markers of a dataset colored by value. Consecutive segments in the same one of
+// COLOR_BANDS color bands are drawn as one line, so long lines stay cheap to draw.
 /// Function: explain its purpose and key arguments
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
-        if self.dark_mode {
-            ctx.set_visuals(egui::Visuals::dark())
-        } else {
-            ctx.set_visuals(egui::Visuals::light());
-        }
-
-        // Main application window
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                if ui.button("Open File(s)").clicked() {
-                    if let Some(paths) = pick_multiple_files() {
+fn draw_value_colored(
+    plot_ui: &mut egui_plot::PlotUi,
+    ds: &Dataset,
+    range: (f64, f64),
+    (x_scale, y_scale): (AxisScale, AxisScale),
+    stride: usize,
+) {
+    const COLOR_BANDS: f64 = 48.0;
 // Variable declaration
-                        let mut successful_loads = 0;
+    let band = |i: usize| {
+        ds.color_value(i)
+            .map(|v| if range.1 > range.0 { ((v - range.0) / (range.1 - range.0) * COLOR_BANDS).round() as i64 } else { 0 })
+    };
 // Variable declaration
-                        let mut failed_files = Vec::new();
-
-                        for path in paths {
+    let color_of = |i: usize| {
 // Variable declaration
-                            let load_result = match path.extension().and_then(|ext| ext.to_str()) {
-                                Some("csv") => match load_csv_points(&path) {
-                                    Ok(points) => {
+        let [r, g, b] = ds.point_color(i, range);
+        egui::Color32::from_rgb(r, g, b)
+    };
+    // Points missing a value stay in as None so the line breaks there
 // Variable declaration
-                                        let file_name = path
-                                            .file_stem()
-                                            .and_then(|stem| stem.to_str())
-                                            .unwrap_or("unknown")
-                                            .to_string();
-                                        Some((points, file_name))
-                                    }
-                                    Err(e) => {
-                                        failed_files
-                                            .push((path.clone(), format!("CSV error: {}", e)));
-                                        None
-                                    }
-                                },
-                                Some("xvg") => match load_xvg_points(&path) {
-                                    Ok(points) => {
-// Variable declaration
-                                        let file_name = path
-                                            .file_stem()
-                                            .and_then(|stem| stem.to_str())
-                                            .unwrap_or("unknown")
-                                            .to_string();
-                                        Some((points, file_name))
-                                    }
-                                    Err(e) => {
-                                        failed_files
-                                            .push((path.clone(), format!("XVG error: {}", e)));
-                                        None
-                                    }
-                                },
-                                _ => {
-                                    failed_files
-                                        .push((path.clone(), "Unsupported file type".to_string()));
-                                    None
-                                }
-                            };
+    let points: Vec<(usize, Option<[f64; 2]>)> = ds
+        .points
+        .iter()
+        .enumerate()
+        .step_by(stride.max(1))
+        .filter_map(|(i, p)| match scale_point(&p, x_scale, y_scale) {
+            Some(p) => Some((i, Some(p))),
+            None => p.iter().any(|v| !v.is_finite()).then_some((i, None)),
+        })
+        .collect();
 
-                            if let Some((points, file_name)) = load_result {
+    if ds.plot_type.draws_line() {
 // Variable declaration
-                                let color = get_default_color(
-                                    self.get_active_subplot().map_or(0, |s| s.datasets.len()) % 8,
-                                );
-
-                                if let Some(subplot) = self.get_active_subplot_mut() {
-                                    subplot.datasets.push(Dataset {
-                                        name: file_name,
-                                        points,
-                                        color,
-                                    });
-                                }
-                                successful_loads += 1;
-                            }
-                        }
-
-                        // Update error message based on results
-                        if successful_loads > 0 && failed_files.is_empty() {
-                            self.error_message =
-                                Some(format!("Successfully loaded {} files", successful_loads));
-                        } else if successful_loads > 0 && !failed_files.is_empty() {
-                            self.error_message = Some(format!(
-                                "Loaded {} files successfully, {} failed",
-                                successful_loads,
-                                failed_files.len()
-                            ));
-                        } else if !failed_files.is_empty() {
+        let mut draw_run = |run: Vec<[f64; 2]>, start: usize| {
+            plot_ui.line(
+                Line::new(PlotPoints::new(run))
+                    .name(&ds.name)
+                    .color(color_of(start))
+                    .width(ds.line_width)
+                    .style(ds.line_style.to_plot_style()),
+            );
+        };
 // Variable declaration
-                            let error_summary = failed_files
-                                .iter()
-                                .take(3) // Show only first 3 errors to avoid cluttering
-                                .map(|(path, err)| {
-                                    format!(
-                                        "{}: {}",
-                                        path.file_name().unwrap_or_default().to_string_lossy(),
-                                        err
-                                    )
-                                })
-                                .collect::<Vec<_>>()
-                                .join("; ");
-
+        let mut run: Vec<[f64; 2]> = Vec::new();
 // Variable declaration
-                            let additional = if failed_files.len() > 3 {
-                                format!(" (and {} more)", failed_files.len() - 3)
-                            } else {
-                                String::new()
-                            };
-
-                            self.error_message = Some(format!(
-                                "Failed to load files: {}{}",
-                                error_summary, additional
-                            ));
-                        }
-                    }
+        let mut run_start = None;
+        for &(i, p) in &points {
+// Variable declaration
+            let Some(p) = p else {
+                if let Some(start) = run_start.take().filter(|_| run.len() > 1) {
+                    draw_run(std::mem::take(&mut run), start);
                 }
-
-                if ui.button("Export Plot as PNG").clicked() {
-                    match export_subplots_as_png(
-                        &self.subplots,
-                        &self.subplot_layout,
-                        self.dark_mode,
-                        &self.tick_font_size,
-                    ) {
-                        Ok(()) => {
-                            self.error_message = Some("Plot exported successfully!".to_string())
-                        }
-                        Err(e) => {
-                            self.error_message = Some(format!("Failed to export plot: {}", e))
-                        }
-                    }
+                run.clear();
+                continue;
+            };
+            run.push(p);
+            match run_start {
+                Some(start) if band(i) == band(start) => {}
+                Some(start) => {
+                    // The run ends at this point and the next one starts from it
+                    draw_run(std::mem::replace(&mut run, vec![p]), start);
+                    run_start = Some(i);
                 }
+                None => run_start = Some(i),
+            }
+        }
+        if let Some(start) = run_start.filter(|_| run.len() > 1) {
+            draw_run(run, start);
+        }
+    }
 
-                if ui.button("Clear Active Subplot").clicked() {
-                    if let Some(subplot) = self.get_active_subplot_mut() {
-                        subplot.datasets.clear();
-                    }
-                }
+    if ds.plot_type.draws_markers() {
+// Variable declaration
+        let mut bands: std::collections::BTreeMap<Option<i64>, (usize, Vec<[f64; 2]>)> = Default::default();
+        for (i, p) in points.iter().filter_map(|&(i, p)| Some((i, p?))) {
+            bands.entry(band(i)).or_insert_with(|| (i, Vec::new())).1.push(p);
+        }
+        for (first, band_points) in bands.into_values() {
+            plot_ui.points(
+                Points::new(PlotPoints::new(band_points))
+                    .name(&ds.name)
+                    .color(color_of(first))
+                    .shape(ds.marker.to_plot_shape())
+                    .filled(true)
+                    .radius(ds.marker_size),
+            );
+        }
+    }
+}
 
-                if ui.button("Clear All Subplots").clicked() {
-                    for subplot in &mut self.subplots {
-                        subplot.datasets.clear();
-                    }
-                }
+// Box plot or violin of a dataset's Y values centred on plot coordinate `x`. Violins
+// are filled slice by slice (each slice is convex) and show the quartiles and median inside.
+/// Function: explain its purpose and key arguments
+fn draw_distribution(
+    plot_ui: &mut egui_plot::PlotUi,
+    ds: &Dataset,
+    Distribution { summary, density }: &Distribution,
+    x: f64,
+) {
+// Variable declaration
+    let color = egui::Color32::from_rgb(ds.color[0], ds.color[1], ds.color[2]);
+// Variable declaration
+    let fill = color.gamma_multiply(FILL_OPACITY as f32);
 
-                ui.separator();
+    if ds.plot_type == PlotType::Violin {
+// Variable declaration
+        let peak = density.iter().map(|d| d[1]).fold(0.0, f64::max);
+// Variable declaration
+        let half_width = |d: f64| d / peak * DISTRIBUTION_WIDTH / 2.0;
+        if density.len() > 1 && peak > 0.0 {
+            for pair in density.windows(2) {
+// Variable declaration
+                let ([y0, d0], [y1, d1]) = (pair[0], pair[1]);
+// Variable declaration
+                let slice = vec![
+                    [x - half_width(d0), y0],
+                    [x + half_width(d0), y0],
+                    [x + half_width(d1), y1],
+                    [x - half_width(d1), y1],
+                ];
+                plot_ui.polygon(Polygon::new(PlotPoints::new(slice)).fill_color(fill).stroke(egui::Stroke::NONE));
+            }
+            // Outline: up the right side and back down the left
+// Variable declaration
+            let outline: Vec<[f64; 2]> = density
+                .iter()
+                .map(|&[y, d]| [x + half_width(d), y])
+                .chain(density.iter().rev().map(|&[y, d]| [x - half_width(d), y]))
+                .chain(density.first().map(|&[y, d]| [x + half_width(d), y]))
+                .collect();
+            plot_ui.line(Line::new(PlotPoints::new(outline)).name(&ds.name).color(color).width(ds.line_width));
+        }
+        plot_ui.line(
+            Line::new(PlotPoints::new(vec![[x, summary.q1], [x, summary.q3]]))
+                .name(&ds.name)
+                .color(color)
+                .width(ds.line_width * 3.0),
+        );
+        plot_ui.points(
+            Points::new(PlotPoints::new(vec![[x, summary.median]]))
+                .name(&ds.name)
+                .color(egui::Color32::WHITE)
+                .shape(MarkerShape::Circle)
+                .filled(true)
+                .radius(3.0),
+        );
+        return;
+    }
 
-                // Global controls
-                if let Some(subplot) = self.get_active_subplot_mut() {
-                    ui.checkbox(&mut subplot.config.show_grid, "Grid");
-                    ui.checkbox(&mut subplot.config.show_legend, "Legend");
-                }
+// Variable declaration
+    let spread = BoxSpread::new(summary.lower_whisker, summary.q1, summary.median, summary.q3, summary.upper_whisker);
+    plot_ui.box_plot(
+        BoxPlot::new(vec![BoxElem::new(x, spread)
+            .name(&ds.name)
+            .box_width(DISTRIBUTION_WIDTH)
+            .whisker_width(DISTRIBUTION_WIDTH / 2.0)
+            .fill(fill)
+            .stroke(egui::Stroke::new(ds.line_width, color))])
+        .name(&ds.name)
+        .color(color),
+    );
+    if !summary.outliers.is_empty() {
+        plot_ui.points(
+            Points::new(summary.outliers.iter().map(|&y| [x, y]).collect::<PlotPoints>())
+                .name(&ds.name)
+                .color(color)
+                .shape(MarkerShape::Circle)
+                .radius(2.5),
+        );
+    }
+}
 
-                // Toggle for subplot layout controls
-                if ui.button("🔲 Subplots").clicked() {
-                    self.show_subplot_controls = !self.show_subplot_controls;
-                }
-
-                // Toggle for axis controls window
-                if ui.button("⚙ Axis Controls").clicked() {
-                    self.show_axis_controls = !self.show_axis_controls;
-                }
-
-                // Toggle for data manipulation window
-                if ui.button("📊 Data Processing").clicked() {
-                    self.show_data_manipulation = !self.show_data_manipulation;
-                }
-
-                // Toggle for color picker window
-                if ui.button("🎨 Colors").clicked() {
-                    self.show_color_picker = !self.show_color_picker;
-                }
-
-                // Toggle for legend controls window
-                if ui.button("📝 Legend & Fonts").clicked() {
-                    self.show_legend_controls = !self.show_legend_controls;
-                }
+// Polygons drawn for one filled area when the display is downsampled
+const MAX_FILL_SEGMENTS: usize = 2_000;
 
-                if ui.button("📊 Data Editor").clicked() {
-                    self.data_editor.show_editor = !self.data_editor.show_editor;
-                }
+// How far from a point, in screen pixels, a click still picks it
+const PICK_RADIUS: f64 = 10.0;
 
-                ui.horizontal(|ui| {
-                    ui.label("Dark Mode:");
+// Translucent area between a dataset's line and its baseline (see fill_baseline), one quad
+// per segment because egui only fills convex polygons. Where the line crosses the baseline
+// the quad is split into two triangles at the crossing. `bottom` stands in for a baseline
+// a log Y axis cannot show.
+/// Function: explain its purpose and key arguments
+fn draw_fill(
+    plot_ui: &mut egui_plot::PlotUi,
+    points: &[[f64; 2]],
+    color: [u8; 3],
+    baseline: &[Option<f64>],
+    (x_scale, y_scale): (AxisScale, AxisScale),
+    stride: usize,
+    bottom: f64,
+) {
 // Variable declaration
-                    let switch_size = egui::vec2(40.0, 20.0);
+    let fill = egui::Color32::from_rgb(color[0], color[1], color[2]).gamma_multiply(FILL_OPACITY as f32);
 // Variable declaration
-                    let (rect, response) =
-                        ui.allocate_exact_size(switch_size, egui::Sense::click());
-                    if response.clicked() {
-                        self.dark_mode = !self.dark_mode;
-                    }
-
+    let edge = |i: usize| -> Option<(f64, f64, f64)> {
 // Variable declaration
-                    let bg_color = if self.dark_mode {
-                        egui::Color32::from_rgb(0, 120, 215)
-                    } else {
-                        egui::Color32::from_rgb(200, 200, 200)
-                    };
-
-                    ui.painter()
-                        .rect_filled(rect, switch_size.y * 0.5, bg_color);
-
+        let [x, upper] = scale_point(&points[i], x_scale, y_scale)?;
+        Some((x, upper, y_scale.apply(baseline[i]?).unwrap_or(bottom)))
+    };
 // Variable declaration
-                    let handle_radius = switch_size.y * 0.4;
+    let mut indices: Vec<usize> = (0..points.len()).step_by(stride.max(1)).collect();
+    if indices.last().is_some_and(|&last| last + 1 != points.len()) {
+        indices.push(points.len() - 1);
+    }
+    for pair in indices.windows(2) {
 // Variable declaration
-                    let handle_center = if self.dark_mode {
-                        egui::pos2(rect.max.x - handle_radius * 1.2, rect.center().y)
-                    } else {
-                        egui::pos2(rect.min.x + handle_radius * 1.2, rect.center().y)
-                    };
-
-                    ui.painter()
-                        .circle_filled(handle_center, handle_radius, egui::Color32::WHITE);
-                });
+        let (Some((x0, upper0, lower0)), Some((x1, upper1, lower1))) = (edge(pair[0]), edge(pair[1])) else {
+            continue;
+        };
+// Variable declaration
+        let (d0, d1) = (upper0 - lower0, upper1 - lower1);
+// Variable declaration
+        let shapes = if d0 * d1 < 0.0 {
+// Variable declaration
+            let t = d0 / (d0 - d1);
+// Variable declaration
+            let crossing = [x0 + (x1 - x0) * t, lower0 + (lower1 - lower0) * t];
+            vec![
+                vec![[x0, lower0], crossing, [x0, upper0]],
+                vec![crossing, [x1, lower1], [x1, upper1]],
+            ]
+        } else {
+            vec![vec![[x0, lower0], [x1, lower1], [x1, upper1], [x0, upper0]]]
+        };
+        for shape in shapes {
+            plot_ui.polygon(Polygon::new(PlotPoints::new(shape)).fill_color(fill).stroke(egui::Stroke::NONE));
+        }
+    }
+}
 
-                ui.separator();
-                if ui.button("Add random").clicked() {
+// Colorbars for the value-colored datasets of a subplot, drawn over the plot's lower right
+// corner: the colormap from low (bottom) to high (top) with the end values beside it
+/// Function: explain its purpose and key arguments
+fn draw_colorbars(ui: &egui::Ui, plot_rect: egui::Rect, colorbars: &[(Colormap, (f64, f64))]) {
+    const STEPS: usize = 32;
 // Variable declaration
-                    let mut rng = rand::rng();
+    let painter = ui.painter_at(plot_rect);
 // Variable declaration
-                    let mut pts = Vec::new();
+    let text_color = ui.visuals().text_color();
 // Variable declaration
-                    let n = 120usize;
-                    for i in 0..n {
+    let font = egui::FontId::proportional(11.0);
 // Variable declaration
-                        let x = i as f64 / n as f64 * 10.0;
+    let height = (plot_rect.height() * 0.4).clamp(40.0, 160.0);
+    for (k, (colormap, (low, high))) in colorbars.iter().enumerate() {
 // Variable declaration
-                        let y = rng.random_range(-2.0..2.0);
-                        pts.push([x, y]);
-                    }
+        let bar = egui::Rect::from_min_size(
+            egui::pos2(plot_rect.right() - 70.0 - 80.0 * k as f32, plot_rect.bottom() - height - 20.0),
+            egui::vec2(12.0, height),
+        );
+        painter.rect_filled(bar.expand(3.0), 2.0, ui.visuals().extreme_bg_color.gamma_multiply(0.8));
+        for step in 0..STEPS {
 // Variable declaration
-                    let name = format!("random{}", self.next_name_index);
-                    self.next_name_index += 1;
+            let [r, g, b] = colormap.sample(1.0 - (step as f64 + 0.5) / STEPS as f64);
 // Variable declaration
-                    let color = get_default_color(
-                        self.get_active_subplot().map_or(0, |s| s.datasets.len()) % 8,
-                    );
-                    if let Some(subplot) = self.get_active_subplot_mut() {
-                        subplot.datasets.push(Dataset {
-                            name,
-                            points: pts,
-                            color,
-                        });
-                    }
-                }
-            });
-
-            if let Some(ref error) = self.error_message {
-                ui.colored_label(egui::Color32::RED, error);
-            }
-        });
-
-        // Subplot controls window
-        if self.show_subplot_controls {
-            egui::Window::new("Subplot Layout")
-                .resizable(true)
-                .default_width(350.0)
-                .default_height(400.0)
-                .show(ctx, |ui| {
-                    ui.heading("Layout Configuration");
-                    ui.separator();
-
-                    ui.horizontal(|ui| {
-                        ui.label("Layout:");
-                        egui::ComboBox::from_label("")
-                            .selected_text(self.subplot_layout.to_string())
-                            .show_ui(ui, |ui| {
-                                for layout in &[
-                                    SubplotLayout::Single,
-                                    SubplotLayout::Horizontal2,
-                                    SubplotLayout::Vertical2,
-                                    SubplotLayout::Grid2x2,
-                                    SubplotLayout::Grid3x1,
-                                    SubplotLayout::Grid1x3,
-                                    SubplotLayout::Grid3x2,
-                                    SubplotLayout::Grid2x3,
-                                ] {
-                                    if ui.selectable_value(&mut self.subplot_layout, *layout, layout.to_string()).clicked() {
-                                        self.ensure_subplots_match_layout();
-                                    }
-                                }
-                            });
-                    });
-
-                    ui.add_space(10.0);
-                    ui.separator();
-                    ui.heading("Active Subplot");
-
-                    ui.horizontal(|ui| {
-                        ui.label("Active:");
-                        for (i, _) in self.subplots.iter().enumerate() {
-                            if ui.selectable_label(self.active_subplot == i, format!("{}", i + 1)).clicked() {
-                                self.active_subplot = i;
-                            }
-                        }
-                    });
-
-                    ui.add_space(10.0);
-
-                    if let Some(subplot) = self.get_active_subplot() {
-                        ui.label(format!("Active subplot: {} (ID: {})", self.active_subplot + 1, subplot.id));
-                        ui.label(format!("Datasets: {}", subplot.datasets.len()));
-                    }
-
-                    ui.add_space(10.0);
-                    ui.separator(); 
-                    // Subplot titles
-                    ui.heading("Subplot Titles");
-                    for (i, subplot) in self.subplots.iter_mut().enumerate() {
-                        ui.horizontal(|ui| {
-                            ui.label(format!("Subplot {}:", i + 1));
-                            ui.text_edit_singleline(&mut subplot.config.title);
-                        });
-                    }
-
-                    ui.add_space(10.0);
-                    ui.separator();
-                    ui.small("Changes to layout will reorganize your data. Active subplot receives new data and operations.");
-                });
+            let top = bar.top() + bar.height() * step as f32 / STEPS as f32;
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(bar.x_range(), top..=top + bar.height() / STEPS as f32 + 0.5),
+                0.0,
+                egui::Color32::from_rgb(r, g, b),
+            );
         }
+        painter.rect_stroke(bar, 0.0, egui::Stroke::new(1.0, text_color));
+        painter.text(bar.right_top() + egui::vec2(4.0, 0.0), egui::Align2::LEFT_CENTER, format_number(*high), font.clone(), text_color);
+        painter.text(bar.right_bottom() + egui::vec2(4.0, 0.0), egui::Align2::LEFT_CENTER, format_number(*low), font.clone(), text_color);
+    }
+}
 
-        // Data editor window
-        if self.data_editor.show_editor {
-            if let Some(subplot) = self.get_active_subplot() {
+// Tick labels of the right axis, painted between the plot frame and the edge of `plot_rect`.
+// egui_plot gives every Y axis the left axis' grid marks, so the right one gets its own
+// round values here instead (see secondary_axis_marks).
+/// Function: explain its purpose and key arguments
+fn draw_secondary_axis_labels(
+    ui: &egui::Ui,
+    plot_rect: egui::Rect,
+    transform: &PlotTransform,
+    axis: SecondaryAxis,
+    config: &SubplotConfig,
+) {
 // Variable declaration
-                let mut datasets = subplot.datasets.clone();
-                self.data_editor.show_data_editor_window(ctx, &mut datasets);
-
-                if let Some(subplot_mut) = self.get_active_subplot_mut() {
-                    subplot_mut.datasets = datasets;
-                }
-            }
+    let frame = *transform.frame();
+// Variable declaration
+    let (min_y, max_y) = (transform.bounds().min()[1], transform.bounds().max()[1]);
+// Variable declaration
+    let painter = ui.painter_at(egui::Rect::from_x_y_ranges(frame.right()..=plot_rect.right(), plot_rect.y_range()));
+// Variable declaration
+    let font = egui::TextStyle::Body.resolve(ui.style());
+// Variable declaration
+    let pixels = frame.height().round() as u32;
+    for (mark, text) in secondary_axis_marks(axis, (min_y, max_y), pixels, config) {
+// Variable declaration
+        let spacing = mark_spacing_px(&mark, (min_y, max_y), pixels);
+        if spacing <= Y_LABEL_SPACING_PX || spacing < font.size as f64 {
+            continue;
         }
+        // Faded in like egui_plot's own tick labels as they get further apart
+// Variable declaration
+        let strength = ((spacing - Y_LABEL_SPACING_PX) / 10.0).min(1.0) as f32;
+        painter.text(
+            egui::pos2(frame.right() + 4.0, transform.position_from_point_y(mark.value)),
+            egui::Align2::LEFT_CENTER,
+            text,
+            font.clone(),
+            ui.visuals().text_color().gamma_multiply(strength.sqrt()),
+        );
+    }
+}
 
-        // Show other control windows
-        self.show_control_windows(ctx);
+// Name of the text style of legend entries, sized from the subplot's legend font size
+const LEGEND_TEXT_STYLE: &str = "legend";
 
-        // Main plot area with subplots
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Multi-plot area – pan with mouse, zoom with scroll");
-            ui.add_space(6.0);
+// Space between the legend box and its contents, and between the box and the plot edge
+const LEGEND_PADDING: f32 = 6.0;
 
+// Layout of the legend drawn by hand (in several columns, or outside the plot, which
+// egui_plot's own legend cannot do). Entries fill each column top to bottom. Returns the
+// laid-out names, entries per column, the width of each column and the size of the box.
+/// Function: explain its purpose and key arguments
+fn layout_plot_legend(
+    ui: &egui::Ui,
+    entries: &[(String, egui::Color32)],
+    config: &SubplotConfig,
+) -> (Vec<Arc<egui::Galley>>, usize, Vec<f32>, egui::Vec2) {
 // Variable declaration
-            let (rows, cols) = self.subplot_layout.dimensions();
+    let font = egui::FontId::proportional(config.legend_font_size);
+// Variable declaration
+    let text_color = ui.visuals().text_color();
+// Variable declaration
+    let galleys: Vec<Arc<egui::Galley>> = entries
+        .iter()
+        .map(|(name, _)| ui.fonts(|fonts| fonts.layout_no_wrap(name.clone(), font.clone(), text_color)))
+        .collect();
+// Variable declaration
+    let rows = entries.len().div_ceil(config.legend_columns.max(1)).max(1);
+    // Each entry starts with a swatch as wide as the font is tall
+// Variable declaration
+    let column_widths: Vec<f32> = galleys
+        .chunks(rows)
+        .map(|column| config.legend_font_size + column.iter().map(|g| g.size().x).fold(0.0, f32::max))
+        .collect();
+// Variable declaration
+    let size = egui::vec2(
+        column_widths.iter().sum::<f32>() + LEGEND_PADDING * 2.0 * column_widths.len() as f32,
+        rows as f32 * config.legend_font_size * 1.4 + LEGEND_PADDING * 2.0,
+    );
+    (galleys, rows, column_widths, size)
+}
 
-            // Create subplot grid
-            egui::Grid::new("subplot_grid")
-                .num_columns(cols)
-                .spacing([10.0, 10.0])
-                .show(ui, |ui| {
-                    for row in 0..rows {
-                        for col in 0..cols {
+// Draw the hand-drawn legend in `area`: in the chosen corner of the plot area, or at the
+// top of the strip right of the plot when the legend goes outside
+/// Function: explain its purpose and key arguments
+fn draw_plot_legend(ui: &egui::Ui, area: egui::Rect, entries: &[(String, egui::Color32)], config: &SubplotConfig) {
 // Variable declaration
-                            let subplot_index = row * cols + col;
-                            if subplot_index < self.subplots.len() {
+    let (galleys, rows, column_widths, size) = layout_plot_legend(ui, entries, config);
 // Variable declaration
-                                let is_active = subplot_index == self.active_subplot;
-                                self.render_subplot(ui, subplot_index, is_active);
-                            }
-                        }
-                        ui.end_row();
-                    }
-                });
-        });
+    let inner = area.shrink(LEGEND_PADDING);
+// Variable declaration
+    let top_left = match config.legend_position {
+        LegendPosition::TopLeft | LegendPosition::OutsideRight => inner.left_top(),
+        LegendPosition::TopRight => egui::pos2(inner.right() - size.x, inner.top()),
+        LegendPosition::BottomLeft => egui::pos2(inner.left(), inner.bottom() - size.y),
+        LegendPosition::BottomRight => inner.right_bottom() - size,
+    };
+// Variable declaration
+    let frame = egui::Rect::from_min_size(top_left, size);
+// Variable declaration
+    let painter = ui.painter_at(area);
+    painter.rect(
+        frame,
+        ui.visuals().window_rounding,
+        ui.visuals().extreme_bg_color.gamma_multiply(config.legend_opacity),
+        if config.legend_border { ui.visuals().window_stroke() } else { egui::Stroke::NONE },
+    );
+// Variable declaration
+    let row_height = config.legend_font_size * 1.4;
+// Variable declaration
+    let mut x = frame.left() + LEGEND_PADDING;
+    for ((names, colors), width) in galleys.chunks(rows).zip(entries.chunks(rows)).zip(&column_widths) {
+        for (row, (galley, (_, color))) in names.iter().zip(colors).enumerate() {
+// Variable declaration
+            let middle = frame.top() + LEGEND_PADDING + (row as f32 + 0.5) * row_height;
+            painter.circle_filled(egui::pos2(x + config.legend_font_size * 0.4, middle), config.legend_font_size * 0.3, *color);
+            painter.galley(
+                egui::pos2(x + config.legend_font_size, middle - galley.size().y / 2.0),
+                galley.clone(),
+                ui.visuals().text_color(),
+            );
+        }
+        x += width + LEGEND_PADDING * 2.0;
     }
 }
 
-/// Implementation block defining methods for this type
-impl PlotterApp {
+// Draw one annotation in the interactive plot; positions are data values mapped through the axis scales
 /// Function: explain its purpose and key arguments
-    fn render_subplot(&mut self, ui: &mut egui::Ui, subplot_index: usize, is_active: bool) {
-        // Get subplot data first to avoid borrowing conflicts
+fn draw_annotation(plot_ui: &mut egui_plot::PlotUi, annotation: &Annotation, x_scale: AxisScale, y_scale: AxisScale) {
 // Variable declaration
-        let subplot_title = if let Some(subplot) = self.subplots.get(subplot_index) {
-            if !subplot.config.title.is_empty() {
-                format!("Subplot {}: {}", subplot_index + 1, subplot.config.title)
-            } else {
-                format!("Subplot {}", subplot_index + 1)
+    let color = egui::Color32::from_rgb(annotation.color[0], annotation.color[1], annotation.color[2]);
+// Variable declaration
+    let Some(position) = scale_point(&annotation.position, x_scale, y_scale) else {
+        return;
+    };
+
+    match annotation.kind {
+        AnnotationKind::Text => {}
+        AnnotationKind::Arrow => {
+            if let Some(tail) = annotation.tail.and_then(|t| scale_point(&t, x_scale, y_scale)) {
+                plot_ui.arrows(
+                    Arrows::new(PlotPoints::new(vec![tail]), PlotPoints::new(vec![position]))
+                        .tip_length(10.0)
+                        .color(color),
+                );
             }
-        } else {
-            return;
-        };
+        }
+        AnnotationKind::Marker => {
+            plot_ui.points(
+                Points::new(PlotPoints::new(vec![position]))
+                    .shape(MarkerShape::Diamond)
+                    .filled(true)
+                    .radius(5.0)
+                    .color(color),
+            );
+        }
+        AnnotationKind::VLine => {
+            plot_ui.vline(VLine::new(position[0]).color(color).style(LineStyle::dashed_loose()));
+        }
+    }
 
+    if !annotation.text.is_empty() {
+        if let Some(label) = scale_point(&annotation.label_position(), x_scale, y_scale) {
+            // Plain labels are centred on their point; arrow and marker labels sit beside it
 // Variable declaration
-        let subplot_datasets: Vec<(String, [u8; 3])> =
-            if let Some(subplot) = self.subplots.get(subplot_index) {
-                subplot
-                    .datasets
-                    .iter()
-                    .map(|ds| (ds.name.clone(), ds.color))
-                    .collect()
+            let anchor = if annotation.kind == AnnotationKind::Text {
+                egui::Align2::CENTER_CENTER
             } else {
-                Vec::new()
+                egui::Align2::LEFT_BOTTOM
             };
+            plot_ui.text(
+                Text::new(PlotPoint::new(label[0], label[1]), annotation.text.clone())
+                    .color(color)
+                    .anchor(anchor),
+            );
+        }
+    }
+}
 
-        ui.vertical(|ui| {
-            // Subplot header with selection
-            ui.horizontal(|ui| {
-                if ui.selectable_label(is_active, &subplot_title).clicked() {
-                    self.active_subplot = subplot_index;
-                }
-
-                if is_active {
-                    ui.colored_label(egui::Color32::from_rgb(0, 200, 0), " (Active)");
-                }
-            });
-
-            // Dataset list for this subplot
-            ui.horizontal(|ui| {
-                ui.vertical(|ui| {
-                    ui.set_width(150.0);
-                    ui.label("Datasets:");
-
+// Log axes are drawn in log10 coordinates with decade grid marks and time axes with dates
+// and times; minor grid lines can be left out on any kind of axis
+/// Function: explain its purpose and key arguments
+fn with_axis_scales(plot: Plot, config: &SubplotConfig) -> Plot {
 // Variable declaration
-                    let mut remove_index: Option<usize> = None;
-                    for (i, (name, color)) in subplot_datasets.iter().enumerate() {
-                        ui.horizontal(|ui| {
-                            // Clickable color square
+    let (x_scale, y_scale) = (config.x_axis(), config.y_axis());
 // Variable declaration
-                            let color_size = egui::vec2(12.0, 12.0);
+    let (min_spacing, minor) = (config.grid_spacing as f64, config.grid_minor);
 // Variable declaration
-                            let egui_color = egui::Color32::from_rgb(color[0], color[1], color[2]);
-
-                            if ui
-                                .add(egui::Button::new("").fill(egui_color).min_size(color_size))
-                                .on_hover_text("Click to change color")
-                                .clicked()
-                            {
-                                self.selected_dataset_for_color = i;
-                                self.active_subplot = subplot_index;
-                                self.show_color_picker = true;
-                            }
+    let mut plot = plot
+        .x_grid_spacer(move |input| plot_grid_marks(input, x_scale, min_spacing, X_LABEL_SPACING_PX, minor))
+        .y_grid_spacer(move |input| plot_grid_marks(input, y_scale, min_spacing, Y_LABEL_SPACING_PX, minor));
+    if x_scale != AxisScale::LINEAR {
+        plot = plot.x_axis_formatter(move |mark, _, _| axis_tick_label(&mark, x_scale));
+    }
+    if y_scale != AxisScale::LINEAR {
+        plot = plot.y_axis_formatter(move |mark, _, _| axis_tick_label(&mark, y_scale));
+    }
+    plot
+}
 
-                            ui.label(name);
-                            if ui.small_button("×").clicked() {
-                                remove_index = Some(i);
-                                self.active_subplot = subplot_index;
-                            }
-                        });
-                    }
+// Texture of a heatmap with its plot centre, size and texture corners (swapped to mirror it)
+type HeatmapImage = (egui::TextureId, PlotPoint, egui::Vec2, egui::Rect);
 
-                    // Apply removal after iteration
-                    if let Some(remove_idx) = remove_index {
-                        if let Some(subplot_mut) = self.subplots.get_mut(subplot_index) {
-                            subplot_mut.datasets.remove(remove_idx);
-                        }
-                    }
-                });
-
-                ui.separator();
-
-                // Plot area
-                ui.vertical(|ui| {
+// Heatmaps and datasets of a subplot in plot coordinates, shared by the plot and its zoom
+// inset. `budget` caps the points drawn per dataset when the display is downsampled;
+// exports and processing always use all points.
+/// Function: explain its purpose and key arguments
+fn draw_plot_items(
+    plot_ui: &mut egui_plot::PlotUi,
+    datasets: &[Cow<'_, Dataset>],
+    heatmap_images: &[HeatmapImage],
+    (x_scale, y_scale): (AxisScale, AxisScale),
+    display_cache: &mut DisplayCache,
+    budget: Option<usize>,
+) {
 // Variable declaration
-                    let plot_width = match self.subplot_layout {
-                        SubplotLayout::Single => 800.0,
-                        SubplotLayout::Horizontal2 | SubplotLayout::Vertical2 => 400.0,
-                        SubplotLayout::Grid2x2 => 350.0,
-                        SubplotLayout::Grid3x1 | SubplotLayout::Grid1x3 => 250.0,
-                        SubplotLayout::Grid3x2 | SubplotLayout::Grid2x3 => 200.0,
-                    };
-
+    let bounds = plot_ui.plot_bounds();
 // Variable declaration
-                    let plot_height = match self.subplot_layout {
-                        SubplotLayout::Single => 400.0,
-                        SubplotLayout::Horizontal2 | SubplotLayout::Vertical2 => 300.0,
-                        SubplotLayout::Grid2x2 => 250.0,
-                        SubplotLayout::Grid3x1 | SubplotLayout::Grid1x3 => 200.0,
-                        SubplotLayout::Grid3x2 | SubplotLayout::Grid2x3 => 150.0,
-                    };
-
-                    if let Some(subplot) = self.subplots.get(subplot_index) {
+    let x_cap = bounds.width() * 0.005;
 // Variable declaration
-                        let mut plot = Plot::new(&format!("plot_{}", subplot_index))
-                            .height(plot_height)
-                            .width(plot_width)
-                            .show_axes([true, true])
-                            .show_grid([subplot.config.show_grid, subplot.config.show_grid]);
-
-                        // Apply custom bounds if configured
-                        if subplot.config.use_custom_bounds {
-                            if let (Ok(min_x), Ok(max_x)) = (
-                                subplot.config.custom_x_min.parse::<f64>(),
-                                subplot.config.custom_x_max.parse::<f64>(),
-                            ) {
-                                if let (Ok(min_y), Ok(max_y)) = (
-                                    subplot.config.custom_y_min.parse::<f64>(),
-                                    subplot.config.custom_y_max.parse::<f64>(),
-                                ) {
+    let y_cap = bounds.height() * 0.005;
+    for (texture_id, center, size, uv) in heatmap_images {
+        plot_ui.image(PlotImage::new(*texture_id, *center, *size).uv(*uv));
+    }
+    // Shaded bands and filled areas go underneath all the lines
+    for (index, ds) in datasets.iter().enumerate() {
 // Variable declaration
-                                    let x_range = max_x - min_x;
+        let stride = budget.map_or(1, |max| decimation_stride(ds.points.len(), max.min(MAX_FILL_SEGMENTS)));
+        if let Some((upper, lower)) = ds.band_edges() {
+            draw_fill(plot_ui, &upper, ds.color, &lower, (x_scale, y_scale), stride, bounds.min()[1]);
+        }
+        if let Some(baseline) = fill_baseline(datasets, index) {
+            draw_fill(plot_ui, display_cache.all_points(ds), ds.color, &baseline, (x_scale, y_scale), stride, bounds.min()[1]);
+        }
+    }
+    for (ds, position) in datasets.iter().zip(distribution_positions(datasets)) {
+        if let Some(position) = position {
+            if let Some(distribution) = display_cache.distribution(ds, y_scale) {
+                draw_distribution(plot_ui, ds, distribution, x_scale.slot(position));
+            }
+            continue;
+        }
 // Variable declaration
-                                    let y_range = max_y - min_y;
+        let color =
+            egui::Color32::from_rgb(ds.color[0], ds.color[1], ds.color[2]);
 // Variable declaration
-                                    let x_padding =
-                                        x_range * (subplot.config.x_padding_percent / 100.0);
+        let error_bar_stride =
+            budget.map_or(1, |max| decimation_stride(ds.points.len(), max));
+        if ds.has_error_bars() {
+            for (i, point) in ds.points.iter().enumerate().step_by(error_bar_stride) {
+                // Bars are built in plot coordinates; on log axes a lower
+                // end at or below zero is clamped to the visible edge
 // Variable declaration
-                                    let y_padding =
-                                        y_range * (subplot.config.y_padding_percent / 100.0);
-
-                                    plot = plot
-                                        .include_x(min_x - x_padding)
-                                        .include_x(max_x + x_padding)
-                                        .include_y(min_y - y_padding)
-                                        .include_y(max_y + y_padding);
-                                }
-                            }
-                        } else {
-                            // FIXED: Automatically include data bounds when custom bounds are not set
-                            if !subplot.datasets.is_empty() {
-                                if let Some((min_x, max_x, min_y, max_y)) =
-                                    get_data_bounds(&subplot.datasets)
-                                {
-                                    // Add some padding (5% by default)
+                let Some(p) = scale_point(&point, x_scale, y_scale) else {
+                    continue;
+                };
+                if let Some(&dy) = ds.y_err.as_ref().and_then(|e| e.get(i)) {
 // Variable declaration
-                                    let x_range = max_x - min_x;
+                    let low = y_scale.apply(point[1] - dy).unwrap_or(bounds.min()[1]);
 // Variable declaration
-                                    let y_range = max_y - min_y;
-
-                                    // Handle case where range is zero (single point or constant values)
+                    let high = y_scale.apply(point[1] + dy).unwrap_or(p[1]);
 // Variable declaration
-                                    let x_padding =
-                                        if x_range > 0.0 { x_range * 0.05 } else { 1.0 };
+                    let bar = vec![
+                        [p[0] - x_cap, low],
+                        [p[0] + x_cap, low],
+                        [p[0], low],
+                        [p[0], high],
+                        [p[0] - x_cap, high],
+                        [p[0] + x_cap, high],
+                    ];
+                    plot_ui.line(
+                        Line::new(PlotPoints::new(bar)).name(&ds.name).color(color),
+                    );
+                }
+                if let Some(&dx) = ds.x_err.as_ref().and_then(|e| e.get(i)) {
 // Variable declaration
-                                    let y_padding =
-                                        if y_range > 0.0 { y_range * 0.05 } else { 1.0 };
+                    let low = x_scale.apply(point[0] - dx).unwrap_or(bounds.min()[0]);
+// Variable declaration
+                    let high = x_scale.apply(point[0] + dx).unwrap_or(p[0]);
+// Variable declaration
+                    let bar = vec![
+                        [low, p[1] - y_cap],
+                        [low, p[1] + y_cap],
+                        [low, p[1]],
+                        [high, p[1]],
+                        [high, p[1] - y_cap],
+                        [high, p[1] + y_cap],
+                    ];
+                    plot_ui.line(
+                        Line::new(PlotPoints::new(bar)).name(&ds.name).color(color),
+                    );
+                }
+            }
+        }
+// Variable declaration
+        // Value-colored lines keep every point's index, so they are thinned by stride
+        // instead of through the display cache
+// Variable declaration
+        let value_range = ds.color_range();
+        if let Some(range) = value_range {
+            draw_value_colored(plot_ui, ds, range, (x_scale, y_scale), error_bar_stride);
+        }
+// Variable declaration
+        let display_points = display_cache.display_points(ds, budget);
+// Variable declaration
+        let plot_points = || -> PlotPoints {
+            display_points
+                .iter()
+                .filter_map(|p| scale_point(p, x_scale, y_scale))
+                .collect()
+        };
+        if ds.plot_type.draws_line() && value_range.is_none() {
+            // One line per run between points that are missing a value
+            for run in display_points.split(|p| p.iter().any(|v| !v.is_finite())) {
+// Variable declaration
+                let run: PlotPoints = run.iter().filter_map(|p| scale_point(p, x_scale, y_scale)).collect();
+                if run.points().is_empty() {
+                    continue;
+                }
+// Variable declaration
+                let line = Line::new(run)
+                    .name(&ds.name)
+                    .color(color)
+                    .width(ds.line_width)
+                    .style(ds.line_style.to_plot_style());
+                plot_ui.line(line);
+            }
+        }
+        if ds.plot_type.draws_bars() {
+            // Bars rise from zero, or from the bottom edge on a log Y axis
+// Variable declaration
+            let base = if y_scale.is_log() { bounds.min()[1] } else { 0.0 };
+// Variable declaration
+            let points: Vec<[f64; 2]> = display_points
+                .iter()
+                .filter_map(|p| scale_point(p, x_scale, y_scale))
+                .collect();
+// Variable declaration
+            let bars = points
+                .iter()
+                .map(|p| Bar::new(p[0], p[1] - base).base_offset(base))
+                .collect();
+            plot_ui.bar_chart(
+                BarChart::new(bars)
+                    .width(bar_width(points.iter().copied()))
+                    .name(&ds.name)
+                    .color(color),
+            );
+        }
+        if ds.plot_type.draws_markers() && value_range.is_none() {
+// Variable declaration
+            let points = Points::new(plot_points())
+                .name(&ds.name)
+                .color(color)
+                .shape(ds.marker.to_plot_shape())
+                .filled(true)
+                .radius(ds.marker_size);
+            plot_ui.points(points);
+        }
+    }
+}
 
-                                    plot = plot
-                                        .include_x(min_x - x_padding)
-                                        .include_x(max_x + x_padding)
-                                        .include_y(min_y - y_padding)
-                                        .include_y(max_y + y_padding);
-                                }
-                            }
-                        }
+// X column combo and Y column checkboxes shared by the import dialogs
+/// Function: explain its purpose and key arguments
+fn column_mapping_ui(ui: &mut egui::Ui, id: &str, labels: &[String], x_col: &mut usize, y_cols: &mut [bool]) {
+    ui.horizontal(|ui| {
+        ui.label("X column:");
+        egui::ComboBox::from_id_source(format!("{}_x_combo", id))
+            .selected_text(labels.get(*x_col).map_or("", String::as_str))
+            .show_ui(ui, |ui| {
+                for (col, label) in labels.iter().enumerate() {
+                    ui.selectable_value(x_col, col, label);
+                }
+            });
+    });
 
-                        if subplot.config.show_legend {
-                            plot = plot.legend(Legend::default());
-                        }
+    ui.label("Y columns:");
+    egui::ScrollArea::vertical()
+        .id_source(format!("{}_y_scroll", id))
+        .max_height(200.0)
+        .show(ui, |ui| {
+            for (col, selected) in y_cols.iter_mut().enumerate() {
+                if let (true, Some(label)) = (col != *x_col, labels.get(col)) {
+                    ui.checkbox(selected, label);
+                }
+            }
+        });
+}
 
-                        plot.show(ui, |plot_ui| {
-                            for ds in &subplot.datasets {
+// Declare the unit of one axis and offer conversions to the other units of its quantity;
+// returns the unit picked to convert to
+/// Function: explain its purpose and key arguments
+fn axis_unit_ui(ui: &mut egui::Ui, label: &str, id: &str, unit: &mut String) -> Option<&'static Unit> {
 // Variable declaration
-                                let color =
-                                    egui::Color32::from_rgb(ds.color[0], ds.color[1], ds.color[2]);
+    let mut convert_to = None;
+    ui.horizontal(|ui| {
+        ui.label(label);
+        egui::ComboBox::from_id_source(id)
+            .selected_text(if unit.is_empty() { "None" } else { unit.as_str() })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(unit, String::new(), "None");
+                for quantity in Quantity::ALL {
+                    ui.separator();
+                    ui.small(quantity.to_string());
+                    for option in units_of(quantity) {
+                        ui.selectable_value(unit, option.symbol.to_string(), option.symbol);
+                    }
+                }
+            });
 // Variable declaration
-                                let line = Line::new(PlotPoints::new(ds.points.clone()))
-                                    .name(&ds.name)
-                                    .color(color);
-                                plot_ui.line(line);
-                            }
-                        });
+        let declared = find_unit(unit);
+        ui.add_enabled_ui(declared.is_some(), |ui| {
+            ui.menu_button("Convert to…", |ui| {
+                for option in declared.into_iter().flat_map(|declared| units_of(declared.quantity)) {
+                    if option.symbol != unit.as_str() && ui.button(option.symbol).clicked() {
+                        convert_to = Some(option);
+                        ui.close_menu();
                     }
-                });
+                }
             });
         });
-    }
+    });
+    convert_to
+}
 
-/// Function: explain its purpose and key arguments
-    fn show_control_windows(&mut self, ctx: &egui::Context) {
-        // Axis controls window
-        if self.show_axis_controls {
-            egui::Window::new("Axis Controls")
-                .resizable(true)
-                .default_width(400.0)
-                .default_height(300.0)
-                .show(ctx, |ui| {
-                    if let Some(subplot) = self.get_active_subplot_mut() {
-                        ui.checkbox(
-                            &mut subplot.config.use_custom_bounds,
-                            "Override Automatic Axis Ranges",
-                        );
+// Width of the dataset list beside each plot
+const DATASET_LIST_WIDTH: f32 = 150.0;
 
-                        if subplot.config.use_custom_bounds {
-                            ui.separator();
+// Smallest plot drawn when the window is too small for the layout
+const MIN_PLOT_SIZE: egui::Vec2 = egui::vec2(160.0, 100.0);
 
-                            // Auto-fill button
-                            if ui.button("Auto-fill from data").clicked() {
-                                if let Some((min_x, max_x, min_y, max_y)) =
-                                    get_data_bounds(&subplot.datasets)
-                                {
-                                    subplot.config.custom_x_min = min_x.to_string();
-                                    subplot.config.custom_x_max = max_x.to_string();
-                                    subplot.config.custom_y_min = format!("{:.3}", min_y);
-                                    subplot.config.custom_y_max = format!("{:.3}", max_y);
-                                }
-                            }
+// Distance in points within which the mouse grabs the resize handle of a zoom inset region
+const INSET_HANDLE_RADIUS: f32 = 8.0;
 
-                            ui.separator();
+// Width taken by the separator between the dataset list and the plot (egui's default)
+const SEPARATOR_WIDTH: f32 = 6.0;
 
-                            // X-axis controls
-                            ui.group(|ui| {
-                                ui.label("X-Axis Range");
-                                ui.horizontal(|ui| {
-                                    ui.label("Min:");
-                                    ui.text_edit_singleline(&mut subplot.config.custom_x_min);
-                                    ui.label("Max:");
-                                    ui.text_edit_singleline(&mut subplot.config.custom_x_max);
-                                });
-                                ui.horizontal(|ui| {
-                                    ui.label("Padding:");
-                                    ui.add(
-                                        egui::Slider::new(
-                                            &mut subplot.config.x_padding_percent,
-                                            0.0..=20.0,
-                                        )
-                                        .suffix("%"),
-                                    );
-                                });
+// Gap between neighbouring subplot cells
+const SUBPLOT_SPACING: f32 = 10.0;
 
-                                ui.checkbox(
-                                    &mut subplot.config.use_custom_x_ticks,
-                                    "Custom X-axis ticks",
-                                );
-                                if subplot.config.use_custom_x_ticks {
-                                    ui.label("X-axis tick values (comma-separated):");
-                                    ui.text_edit_multiline(&mut subplot.config.custom_x_ticks);
-                                    ui.small("Example: 0, 250, 500");
-                                }
-                            });
+// Plot size in a subplot cell of `cell_size` points: what is left next to the dataset list
+// and below the header row
+/// Function: explain its purpose and key arguments
+fn plot_size_in_cell(ui: &egui::Ui, cell_size: egui::Vec2) -> egui::Vec2 {
+// Variable declaration
+    let spacing = ui.spacing().item_spacing;
+// Variable declaration
+    let side = DATASET_LIST_WIDTH + SEPARATOR_WIDTH + 2.0 * spacing.x;
+// Variable declaration
+    let header = ui.spacing().interact_size.y + spacing.y;
+    (cell_size - egui::vec2(side, header)).max(MIN_PLOT_SIZE)
+}
 
-                            ui.separator();
+// One line per group or dataset, indented by depth; sets `clicked` to the path picked
+/// Function: explain its purpose and key arguments
+fn hdf5_tree_ui(ui: &mut egui::Ui, node: &Hdf5Node, selected: Option<&SheetTable>, depth: usize, clicked: &mut Option<String>) {
+// Variable declaration
+    let label = if node.is_group {
+        format!("📁 {}", if node.path == "/" { "/" } else { node.name() })
+    } else {
+        format!("📄 {} {:?}", node.name(), node.shape)
+    };
+// Variable declaration
+    let is_selected = selected.is_some_and(|table| table.name == node.path);
+    ui.horizontal(|ui| {
+        ui.add_space(depth as f32 * 14.0);
+        if ui.selectable_label(is_selected, label).clicked() {
+            *clicked = Some(node.path.clone());
+        }
+    });
+    for child in &node.children {
+        hdf5_tree_ui(ui, child, selected, depth + 1, clicked);
+    }
+}
 
-                            // Y-axis controls
-                            ui.group(|ui| {
-                                ui.label("Y-Axis Range");
-                                ui.horizontal(|ui| {
-                                    ui.label("Min:");
-                                    ui.text_edit_singleline(&mut subplot.config.custom_y_min);
-                                    ui.label("Max:");
-                                    ui.text_edit_singleline(&mut subplot.config.custom_y_max);
-                                });
-                                ui.horizontal(|ui| {
-                                    ui.label("Padding:");
-                                    ui.add(
-                                        egui::Slider::new(
-                                            &mut subplot.config.y_padding_percent,
-                                            0.0..=20.0,
-                                        )
-                                        .suffix("%"),
-                                    );
-                                });
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// Data structure used in app.rs module
+pub struct SubplotConfig {
+    pub show_grid: bool,
+    pub grid_minor: bool, // Also draw the faint lines between labelled ones
+    pub grid_style: LinePattern, // Of exported grid lines; the interactive grid is always solid
+    pub grid_opacity: f32, // Multiplies the strength of exported grid lines
+    pub grid_spacing: f32, // Closest spacing of grid lines in points; larger values give a sparser grid
+    pub show_legend: bool,
+    pub legend_title: String,
+    pub legend_position: LegendPosition,
+    pub legend_opacity: f32, // Of the legend background, 0 (transparent) to 1
+    pub legend_border: bool,
+    pub legend_columns: usize,
+    pub legend_font_size: f32, // Of the entry names, in points (pixels in exports before scaling)
+    pub use_custom_bounds: bool,
+    pub custom_x_min: String,
+    pub custom_x_max: String,
+    pub custom_y_min: String,
+    pub custom_y_max: String,
+    pub x_padding_percent: f64,
+    pub y_padding_percent: f64,
+    pub custom_x_ticks: String,
+    pub custom_y_ticks: String,
+    pub use_custom_x_ticks: bool,
+    pub use_custom_y_ticks: bool,
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub x_scale: ScaleKind,
+    pub y_scale: ScaleKind,
+    pub reverse_x: bool, // Values grow to the left
+    pub reverse_y: bool, // Values grow downwards
+    pub equal_aspect: bool, // One unit of X as long as one unit of Y, in plot coordinates
+    pub scroll_to_newest: bool, // Keep the X-axis on the latest data of followed files
+    pub scroll_window: f64, // Width of X shown while scrolling; 0 shows all data
+    pub palette: Option<Palette>, // Overrides the app-wide palette for this subplot
+    pub y2_label: String, // Title of the right-hand Y axis of datasets with `secondary_y`
+    pub use_custom_y2_bounds: bool,
+    pub custom_y2_min: String,
+    pub custom_y2_max: String,
+    pub x_unit: String, // Declared unit symbol (see units.rs); empty when none is declared
+    pub y_unit: String, // Unit of the left-hand Y axis
+    pub inset: Option<Inset>, // Zoomed view of part of the plot, drawn inside it
+}
 
-                                ui.checkbox(
-                                    &mut subplot.config.use_custom_y_ticks,
-                                    "Custom Y-axis ticks",
-                                );
-                                if subplot.config.use_custom_y_ticks {
-                                    ui.label("Y-axis tick values (comma-separated):");
-                                    ui.text_edit_multiline(&mut subplot.config.custom_y_ticks);
-                                    ui.small("Example: 0.0, 0.5, 1.0");
-                                }
-                            });
-                        }
-                    } else {
-                        ui.label("No active subplot selected.");
-                    }
-                });
+/// Implementation block defining methods for this type
+impl Default for SubplotConfig {
+/// Function: explain its purpose and key arguments
+    fn default() -> Self {
+        Self {
+            show_grid: false,
+            grid_minor: true,
+            grid_style: LinePattern::Solid,
+            grid_opacity: 1.0,
+            grid_spacing: 8.0,
+            show_legend: true,
+            legend_title: "Datasets".to_string(),
+            legend_position: LegendPosition::default(),
+            legend_opacity: 0.75,
+            legend_border: true,
+            legend_columns: 1,
+            legend_font_size: 12.0,
+            use_custom_bounds: false,
+            custom_x_min: String::new(),
+            custom_x_max: String::new(),
+            custom_y_min: String::new(),
+            custom_y_max: String::new(),
+            x_padding_percent: 5.0,
+            y_padding_percent: 5.0,
+            custom_x_ticks: String::new(),
+            custom_y_ticks: String::new(),
+            use_custom_x_ticks: false,
+            use_custom_y_ticks: false,
+            title: String::new(),
+            x_label: String::new(),
+            y_label: String::new(),
+            x_scale: ScaleKind::Linear,
+            y_scale: ScaleKind::Linear,
+            reverse_x: false,
+            reverse_y: false,
+            equal_aspect: false,
+            scroll_to_newest: false,
+            scroll_window: 0.0,
+            palette: None,
+            y2_label: String::new(),
+            use_custom_y2_bounds: false,
+            custom_y2_min: String::new(),
+            custom_y2_max: String::new(),
+            x_unit: String::new(),
+            y_unit: String::new(),
+            inset: None,
+        }
+    }
+}
+
+/// Implementation block defining methods for this type
+impl SubplotConfig {
+    // How data values map to plot coordinates along X
+/// Function: explain its purpose and key arguments
+    pub fn x_axis(&self) -> AxisScale {
+        AxisScale::new(self.x_scale, self.reverse_x)
+    }
+
+    // How data values map to plot coordinates along Y
+/// Function: explain its purpose and key arguments
+    pub fn y_axis(&self) -> AxisScale {
+        AxisScale::new(self.y_scale, self.reverse_y)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Data structure used in app.rs module
+pub struct Subplot {
+    pub id: String,
+    pub datasets: Vec<Dataset>,
+    #[serde(default)]
+    pub config: SubplotConfig,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    #[serde(default)]
+    pub heatmaps: Vec<Heatmap>, // Drawn underneath the datasets
+    #[serde(skip)]
+    pub view_bounds: Option<(f64, f64, f64, f64)>, // (min x, max x, min y, max y) on screen in the last frame, in plot coordinates
+}
+
+/// Implementation block defining methods for this type
+impl Subplot {
+/// Function: explain its purpose and key arguments
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            datasets: Vec::new(),
+            config: SubplotConfig::default(),
+            annotations: Vec::new(),
+            heatmaps: Vec::new(),
+            view_bounds: None,
+        }
+    }
+
+    // Change the values along one axis everywhere they are kept: datasets (only those on
+    // the left axis for Y), heatmaps, annotations, the zoom inset's region, and custom
+    // ranges and ticks. Axis titles and units are left to the caller.
+/// Function: explain its purpose and key arguments
+    pub fn map_axis(&mut self, map: &AxisMap) {
+// Variable declaration
+        let axis = map.axis;
+        for dataset in self.datasets.iter_mut().filter(|ds| axis == 0 || !ds.secondary_y) {
+            dataset.apply_axis_map(map);
+        }
+        for heatmap in &mut self.heatmaps {
+// Variable declaration
+            let coordinates = if axis == 0 { &mut heatmap.x } else { &mut heatmap.y };
+            for value in coordinates.iter_mut() {
+                *value = map.map(*value);
+            }
+        }
+        for annotation in &mut self.annotations {
+            annotation.position[axis] = map.map(annotation.position[axis]);
+            if let Some(tail) = annotation.tail.as_mut() {
+                tail[axis] = map.map(tail[axis]);
+            }
+        }
+// Variable declaration
+        let config = &mut self.config;
+        if let Some(inset) = config.inset.as_mut() {
+// Variable declaration
+            let range = if axis == 0 { &mut inset.x_range } else { &mut inset.y_range };
+            // A negative factor swaps the ends
+// Variable declaration
+            let [low, high] = range.map(|value| map.map(value));
+            *range = [low.min(high), low.max(high)];
+        }
+// Variable declaration
+        let map_text = |text: &mut String| {
+            if let Ok(value) = text.trim().parse::<f64>() {
+                *text = map.map(value).to_string();
+            }
+        };
+// Variable declaration
+        let map_ticks = |ticks: &mut String| {
+            if !ticks.trim().is_empty() {
+                *ticks = parse_custom_ticks(ticks)
+                    .into_iter()
+                    .map(|tick| map.map(tick).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+            }
+        };
+        if axis == 0 {
+            map_text(&mut config.custom_x_min);
+            map_text(&mut config.custom_x_max);
+            map_ticks(&mut config.custom_x_ticks);
+            config.scroll_window *= map.factor;
+        } else {
+            map_text(&mut config.custom_y_min);
+            map_text(&mut config.custom_y_max);
+            map_ticks(&mut config.custom_y_ticks);
+        }
+    }
+}
+
+// Named grids offered in the layout menu and the command palette; sessions saved before
+// free-form layouts store one of these names
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Enum representing a set of related values in app.rs module
+pub enum LayoutPreset {
+    Single,      // 1x1
+    Horizontal2, // 1x2
+    Vertical2,   // 2x1
+    Grid2x2,     // 2x2
+    Grid3x1,     // 3x1
+    Grid1x3,     // 1x3
+    Grid3x2,     // 3x2
+    Grid2x3,     // 2x3
+}
+
+/// Implementation block defining methods for this type
+impl LayoutPreset {
+    pub const ALL: [LayoutPreset; 8] = [
+        LayoutPreset::Single,
+        LayoutPreset::Horizontal2,
+        LayoutPreset::Vertical2,
+        LayoutPreset::Grid2x2,
+        LayoutPreset::Grid3x1,
+        LayoutPreset::Grid1x3,
+        LayoutPreset::Grid3x2,
+        LayoutPreset::Grid2x3,
+    ];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            LayoutPreset::Single => "Single (1x1)",
+            LayoutPreset::Horizontal2 => "Horizontal (1x2)",
+            LayoutPreset::Vertical2 => "Vertical (2x1)",
+            LayoutPreset::Grid2x2 => "Grid (2x2)",
+            LayoutPreset::Grid3x1 => "Grid (3x1)",
+            LayoutPreset::Grid1x3 => "Grid (1x3)",
+            LayoutPreset::Grid3x2 => "Grid (3x2)",
+            LayoutPreset::Grid2x3 => "Grid (2x3)",
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn dimensions(self) -> (usize, usize) {
+        match self {
+            LayoutPreset::Single => (1, 1),
+            LayoutPreset::Horizontal2 => (1, 2),
+            LayoutPreset::Vertical2 => (2, 1),
+            LayoutPreset::Grid2x2 => (2, 2),
+            LayoutPreset::Grid3x1 => (3, 1),
+            LayoutPreset::Grid1x3 => (1, 3),
+            LayoutPreset::Grid3x2 => (3, 2),
+            LayoutPreset::Grid2x3 => (2, 3),
+        }
+    }
+}
+
+// Grid of `rows` × `cols` tracks whose sizes follow relative weights. Subplots fill the
+// grid in reading order, each taking the first free spot its span fits, so without spans
+// there is one subplot per cell.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredLayout")]
+/// Data structure used in app.rs module
+pub struct SubplotLayout {
+    pub rows: usize,
+    pub cols: usize,
+    pub row_weights: Vec<f32>, // Relative row heights; missing entries count as 1
+    pub col_weights: Vec<f32>, // Relative column widths; missing entries count as 1
+    pub spans: Vec<[usize; 2]>, // [rows, columns] covered by each subplot in order; missing entries are 1x1
+}
+
+// Layouts as read from sessions and history: a preset name from older files or a full grid
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Enum representing a set of related values in app.rs module
+enum StoredLayout {
+    Preset(LayoutPreset),
+    Grid {
+        rows: usize,
+        cols: usize,
+        #[serde(default)]
+        row_weights: Vec<f32>,
+        #[serde(default)]
+        col_weights: Vec<f32>,
+        #[serde(default)]
+        spans: Vec<[usize; 2]>,
+    },
+}
+
+/// Implementation block defining methods for this type
+impl From<StoredLayout> for SubplotLayout {
+/// Function: explain its purpose and key arguments
+    fn from(stored: StoredLayout) -> Self {
+        match stored {
+            StoredLayout::Preset(preset) => preset.into(),
+            StoredLayout::Grid { rows, cols, row_weights, col_weights, spans } => SubplotLayout {
+                rows: rows.clamp(1, SubplotLayout::MAX_TRACKS),
+                cols: cols.clamp(1, SubplotLayout::MAX_TRACKS),
+                row_weights,
+                col_weights,
+                spans,
+            },
+        }
+    }
+}
+
+/// Implementation block defining methods for this type
+impl From<LayoutPreset> for SubplotLayout {
+/// Function: explain its purpose and key arguments
+    fn from(preset: LayoutPreset) -> Self {
+// Variable declaration
+        let (rows, cols) = preset.dimensions();
+        SubplotLayout::grid(rows, cols)
+    }
+}
+
+/// Implementation block defining methods for this type
+impl Default for SubplotLayout {
+/// Function: explain its purpose and key arguments
+    fn default() -> Self {
+        SubplotLayout::grid(1, 1)
+    }
+}
+
+// Position of one subplot in the grid, in tracks
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in app.rs module
+pub struct GridCell {
+    pub row: usize,
+    pub col: usize,
+    pub row_span: usize,
+    pub col_span: usize,
+}
+
+/// Implementation block defining methods for this type
+impl SubplotLayout {
+    // Most rows or columns a layout can have
+    pub const MAX_TRACKS: usize = 8;
+
+    // Evenly sized grid with one subplot per cell
+/// Function: explain its purpose and key arguments
+    pub fn grid(rows: usize, cols: usize) -> SubplotLayout {
+        SubplotLayout {
+            rows: rows.clamp(1, Self::MAX_TRACKS),
+            cols: cols.clamp(1, Self::MAX_TRACKS),
+            row_weights: Vec::new(),
+            col_weights: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    // Preset name for plain grids that match one, otherwise the grid size
+/// Function: explain its purpose and key arguments
+    pub fn label(&self) -> String {
+        match self.preset() {
+            Some(preset) => preset.to_string().to_string(),
+            None if *self == SubplotLayout::grid(self.rows, self.cols) => format!("Grid ({}x{})", self.rows, self.cols),
+            None => format!("Custom ({}x{})", self.rows, self.cols),
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn preset(&self) -> Option<LayoutPreset> {
+        LayoutPreset::ALL.into_iter().find(|preset| SubplotLayout::from(*preset) == *self)
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn row_weight(&self, row: usize) -> f32 {
+        self.row_weights.get(row).copied().filter(|weight| *weight > 0.0).unwrap_or(1.0)
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn col_weight(&self, col: usize) -> f32 {
+        self.col_weights.get(col).copied().filter(|weight| *weight > 0.0).unwrap_or(1.0)
+    }
+
+    // Rows and columns covered by the subplot at `index`, never more than the grid has
+/// Function: explain its purpose and key arguments
+    pub fn span(&self, index: usize) -> (usize, usize) {
+// Variable declaration
+        let [rows, cols] = self.spans.get(index).copied().unwrap_or([1, 1]);
+        (rows.clamp(1, self.rows), cols.clamp(1, self.cols))
+    }
+
+    // Place the subplots in reading order; a subplot whose span no longer fits anywhere
+    // ends the layout, as do the cells that stay empty after it
+/// Function: explain its purpose and key arguments
+    pub fn cells(&self) -> Vec<GridCell> {
+// Variable declaration
+        let mut taken = vec![vec![false; self.cols]; self.rows];
+// Variable declaration
+        let mut cells = Vec::new();
+        loop {
+// Variable declaration
+            let (row_span, col_span) = self.span(cells.len());
+// Variable declaration
+            let free = |row: usize, col: usize, taken: &[Vec<bool>]| {
+                row + row_span <= self.rows
+                    && col + col_span <= self.cols
+                    && (row..row + row_span).all(|r| (col..col + col_span).all(|c| !taken[r][c]))
+            };
+// Variable declaration
+            let spot = (0..self.rows)
+                .flat_map(|row| (0..self.cols).map(move |col| (row, col)))
+                .find(|(row, col)| free(*row, *col, &taken));
+// Variable declaration
+            let Some((row, col)) = spot else {
+                break;
+            };
+            for taken_row in &mut taken[row..row + row_span] {
+                taken_row[col..col + col_span].fill(true);
+            }
+            cells.push(GridCell { row, col, row_span, col_span });
+        }
+        cells
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn subplot_count(&self) -> usize {
+        self.cells().len()
+    }
+
+    // Sizes of the rows sharing `available` in proportion to their weights
+/// Function: explain its purpose and key arguments
+    pub fn row_sizes(&self, available: f64) -> Vec<f64> {
+// Variable declaration
+        let total: f64 = (0..self.rows).map(|row| self.row_weight(row) as f64).sum();
+        (0..self.rows).map(|row| available * self.row_weight(row) as f64 / total).collect()
+    }
+
+    // Sizes of the columns sharing `available` in proportion to their weights
+/// Function: explain its purpose and key arguments
+    pub fn col_sizes(&self, available: f64) -> Vec<f64> {
+// Variable declaration
+        let total: f64 = (0..self.cols).map(|col| self.col_weight(col) as f64).sum();
+        (0..self.cols).map(|col| available * self.col_weight(col) as f64 / total).collect()
+    }
+
+    // Room needed so the smallest row and column are at least `min_size` tall and wide,
+    // not counting the gaps between them
+/// Function: explain its purpose and key arguments
+    pub fn min_area(&self, min_size: (f64, f64)) -> (f64, f64) {
+// Variable declaration
+        let rows = self.row_sizes(1.0);
+// Variable declaration
+        let cols = self.col_sizes(1.0);
+        (
+            cols.iter().map(|share| min_size.0 / share).fold(0.0, f64::max),
+            rows.iter().map(|share| min_size.1 / share).fold(0.0, f64::max),
+        )
+    }
+
+    // [left, top, width, height] of every subplot for the given track sizes, with
+    // `spacing` between neighbouring tracks
+/// Function: explain its purpose and key arguments
+    pub fn cell_rects(&self, row_sizes: &[f64], col_sizes: &[f64], spacing: f64) -> Vec<[f64; 4]> {
+// Variable declaration
+        let offset = |sizes: &[f64], index: usize| sizes[..index].iter().sum::<f64>() + spacing * index as f64;
+// Variable declaration
+        let extent = |sizes: &[f64], start: usize, span: usize| {
+            sizes[start..start + span].iter().sum::<f64>() + spacing * (span - 1) as f64
+        };
+        self.cells()
+            .iter()
+            .map(|cell| {
+                [
+                    offset(col_sizes, cell.col),
+                    offset(row_sizes, cell.row),
+                    extent(col_sizes, cell.col, cell.col_span),
+                    extent(row_sizes, cell.row, cell.row_span),
+                ]
+            })
+            .collect()
+    }
+
+    // Subplot directly under the one at `index`, sharing its first column
+/// Function: explain its purpose and key arguments
+    pub fn subplot_below(&self, index: usize) -> Option<usize> {
+// Variable declaration
+        let cells = self.cells();
+// Variable declaration
+        let above = cells.get(index)?;
+        cells.iter().position(|cell| {
+            cell.row == above.row + above.row_span && (cell.col..cell.col + cell.col_span).contains(&above.col)
+        })
+    }
+
+    // Smallest plain grid with at least `count` subplots: a single row up to three, then
+    // as square as possible
+/// Function: explain its purpose and key arguments
+    pub fn fitting(count: usize) -> Option<SubplotLayout> {
+// Variable declaration
+        let cols = if count <= 3 { count.max(1) } else { (count as f64).sqrt().ceil() as usize };
+// Variable declaration
+        let rows = count.max(1).div_ceil(cols);
+        (rows <= Self::MAX_TRACKS && cols <= Self::MAX_TRACKS).then(|| SubplotLayout::grid(rows, cols))
+    }
+
+    // Layout with one more row and the same columns, so existing subplots keep their place
+/// Function: explain its purpose and key arguments
+    pub fn with_extra_row(&self) -> Option<SubplotLayout> {
+        if self.rows >= Self::MAX_TRACKS {
+            return None;
+        }
+// Variable declaration
+        let mut layout = self.clone();
+        layout.rows += 1;
+        Some(layout)
+    }
+}
+
+// File format of exported figures: a raster image, a vector page, or a matplotlib script
+// that redraws the figure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in app.rs module
+pub enum ExportFormat {
+    #[default]
+    Png,
+    Pdf,
+    PythonScript,
+}
+
+/// Implementation block defining methods for this type
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Png, ExportFormat::Pdf, ExportFormat::PythonScript];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "PNG image",
+            ExportFormat::Pdf => "PDF document (vector)",
+            ExportFormat::PythonScript => "Python script (matplotlib)",
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::PythonScript => "py",
+        }
+    }
+
+    // Format of a file chosen by name; None when the extension is neither
+/// Function: explain its purpose and key arguments
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+// Variable declaration
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|format| format.extension() == extension)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Data structure used in app.rs module
+pub struct ExportSettings {
+    pub width: u32,  // Size of the exported figure before scaling, in pixels
+    pub height: u32,
+    pub dpi: u32,    // Stored in the PNG so other programs know the physical size; sets the PDF page size
+    pub scale: f32,  // Enlarges the whole rendered figure, text and lines included
+    #[serde(default)]
+    pub format: ExportFormat, // Offered first in the export dialog
+}
+
+/// Implementation block defining methods for this type
+impl Default for ExportSettings {
+/// Function: explain its purpose and key arguments
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 800,
+            dpi: 100,
+            scale: 1.0,
+            format: ExportFormat::Png,
+        }
+    }
+}
+
+/// Implementation block defining methods for this type
+impl ExportSettings {
+    // Final image size in pixels after scaling
+/// Function: explain its purpose and key arguments
+    pub fn pixel_size(&self) -> (u32, u32) {
+        (
+            (self.width as f32 * self.scale).round().max(1.0) as u32,
+            (self.height as f32 * self.scale).round().max(1.0) as u32,
+        )
+    }
+}
+
+// What the mouse points at: its position and, with the crosshair on, the data point it
+// snapped to, both as data values (the snapped point as stored, before stacking or axis placement)
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in app.rs module
+pub struct CursorReadout {
+    pub subplot: usize,
+    pub cursor: [f64; 2],
+    pub nearest: Option<(String, [f64; 2])>, // (dataset name, point)
+    pub scales: (AxisScale, AxisScale),      // How the values are written out
+}
+
+/// Implementation block defining methods for this type
+impl CursorReadout {
+    // One line for the status bar
+/// Function: explain its purpose and key arguments
+    pub fn status_text(&self) -> String {
+// Variable declaration
+        let (x_scale, y_scale) = self.scales;
+// Variable declaration
+        let cursor = format!(
+            "Subplot {}: cursor x = {}, y = {}",
+            self.subplot + 1,
+            x_scale.format_value(self.cursor[0]),
+            y_scale.format_value(self.cursor[1])
+        );
+        match &self.nearest {
+            Some((name, [x, y])) => format!(
+                "{}   |   {}: x = {}, y = {}",
+                cursor,
+                name,
+                x_scale.format_value(*x),
+                y_scale.format_value(*y)
+            ),
+            None => cursor,
+        }
+    }
+}
+
+/// Data structure used in app.rs module
+pub struct PlotterApp {
+    // Subplot system
+    pub subplots: Vec<Subplot>,
+    pub hidden_subplots: Vec<Subplot>, // Subplots left over after the layout shrank, brought back when it grows
+    pub subplot_layout: SubplotLayout,
+    pub active_subplot: usize,
+    pub show_subplot_controls: bool,
+
+    // Global settings
+    pub next_name_index: usize,
+    pub notifications: Notifications, // Toasts about what just happened, shown in the top-right corner
+    pub dark_mode: bool,
+    pub screenshot_requested: bool,
+    pub tick_font_size: FontSize,
+    pub palette: Palette, // Colors of new datasets in subplots without their own palette
+
+    // UI state
+    pub show_axis_controls: bool,
+    pub show_data_manipulation: bool,
+    pub show_comparison: bool,
+    pub show_color_picker: bool,
+    pub show_legend_controls: bool,
+    pub subplot_rects: Vec<egui::Rect>, // Screen area of each subplot from the last frame (drop targets)
+    pub show_history: bool,
+    pub show_annotations: bool,
+    pub show_export_settings: bool,
+    pub export_settings: ExportSettings,
+    pub show_preferences: bool,
+    pub preferences: Preferences, // As saved in the config file; new subplots take their padding from it
+    pub show_templates: bool,
+    pub templates: Option<Vec<StyleTemplate>>, // Saved style templates; None until read from the config directory
+    pub template_name: String,
+    pub spreadsheet_imports: Vec<SpreadsheetImport>, // Opened workbooks waiting for sheet/column choice
+    pub hdf5_imports: Vec<Hdf5Import>, // Opened HDF5 files waiting for dataset/column choice
+    pub text_imports: Vec<TextImport>, // Opened CSV/text tables waiting in the import preview
+    pub file_watcher: Option<FileWatcher>, // Started once a dataset follows its file
+    pub tail_readers: Vec<TailReader>, // Read positions in followed text files
+    pub stdin_stream: Option<StdinStream>, // Data still arriving on standard input
+    pub pending_loads: Vec<PendingLoad>, // Files opened in the GUI that are still being read
+    pub recent_files: RecentFiles,       // Offered in the Recent menu, saved in the config file
+    pub folder_import: Option<FolderImport>, // Open Folder window, while it is up
+    pub show_open_url: bool,
+    pub url_input: String,
+    pub url_refresh: bool, // Download again even when a recent copy is cached
+    pub exports: Vec<ExportJob>, // Figures still being written
+
+    // Annotation placement: clicking a plot adds an annotation of this kind
+    pub annotation_tool: Option<AnnotationKind>,
+    pub annotation_text: String,
+    pub annotation_color: [u8; 3],
+    pub pending_arrow_tail: Option<(usize, [f64; 2])>, // (subplot, data point) of a half-placed arrow
+    pub inset_grab: Option<(usize, InsetGrab)>, // (subplot, part) of a zoom inset region being dragged
+
+    // X-range selection: dragging across a plot in selection mode records an interval
+    pub x_selection_mode: bool,
+    pub x_selection: Option<(usize, [f64; 2])>, // (subplot, [drag start, drag end]) in data coordinates
+    pub restrict_to_selection: bool, // Data Processing only uses points inside the selection
+
+    // Manual point entry: clicking a plot adds a point to this (subplot, dataset)
+    pub add_point_target: Option<(usize, usize)>,
+
+    // Crosshair that snaps to the data point nearest to the mouse
+    pub show_crosshair: bool,
+    pub cursor_readout: Option<CursorReadout>, // From the last frame; shown in the status bar
+    pub view_x_ranges: HashMap<usize, (f64, f64)>, // X range each subplot showed in the last frame
+
+    // Point picked by clicking a plot (or a row of the Data Editor), shown in the Point Inspector
+    pub picked_point: Option<(usize, usize, usize)>, // (subplot, dataset, point)
+
+    // Undo/redo of dataset and subplot changes
+    pub history: History,
+
+    // Ctrl+P list of every action, filtered as you type
+    pub command_palette: CommandPalette,
+
+    // Data manipulation fields
+    pub rolling_window_size: usize,
+    pub savitzky_golay_window: usize, // Odd number of points
+    pub savitzky_golay_order: usize,
+    pub gaussian_sigma: f64, // Kernel width in X units
+    pub fft_window: FftWindow,
+    pub normalization: Normalization,
+    pub normalize_in_place: bool, // Change the selected dataset instead of adding a new one
+    pub histogram_of_x: bool, // Bin X values instead of Y values
+    pub histogram_by_width: bool, // Use a fixed bin width instead of a bin count
+    pub histogram_bin_count: usize,
+    pub histogram_bin_width: f64,
+    pub replica_datasets: Vec<usize>, // Datasets of the active subplot chosen for replica averaging
+    pub replica_subplot: String, // Id of the subplot replica_datasets was chosen in
+    pub resample_onto: Option<usize>, // Dataset whose X values to resample onto; None for a uniform grid
+    pub resample_count: usize, // Points of the uniform grid
+    pub resample_method: Interpolation,
+    pub replica_spread: ReplicaSpread,
+    pub math_operand: usize, // Dataset B of the Dataset Math tool (A is the processing dataset)
+    pub math_operation: DatasetOperation,
+    pub math_expression: String,
+    pub baseline_mode: BaselineMode,
+    pub baseline_anchors: [f64; 2], // Region of the constant baseline, or the anchors of the linear one
+    pub baseline_window: usize, // Points of the rolling-minimum window
+    pub downsample_display: bool,
+    pub max_display_points: usize, // Per-dataset point budget for the interactive plot
+    pub display_cache: DisplayCache,
+    pub diagnostics: FrameDiagnostics, // Frame rate and CPU time overlay
+    pub heatmap_textures: HashMap<u64, (Colormap, egui::TextureHandle)>, // By heatmap revision
+    pub selected_dataset_for_processing: usize,
+    pub last_statistics: Option<(String, SeriesStatistics)>, // (dataset description, result)
+    pub last_block_average: Option<(String, BlockAverage)>, // (dataset description, result)
+    pub peak_settings: PeakSettings,
+    pub last_peaks: Option<(String, Vec<Peak>)>, // (dataset description, peaks found)
+    pub crossing_threshold: f64,
+    pub mark_crossings: bool, // Add a vertical-line annotation at every crossing
+    pub last_crossings: Option<(String, Vec<Crossing>)>, // (dataset and threshold, crossings found)
+    pub comparison_datasets: [usize; 2], // A and B in the active subplot
+    pub last_comparison: Option<(String, ComparisonMetrics)>, // (datasets compared, result)
+    pub selected_dataset_for_color: usize,
+    pub data_editor: DataEditor,
+}
+
+/// Implementation block defining methods for this type
+impl Default for PlotterApp {
+/// Function: explain its purpose and key arguments
+    fn default() -> Self {
+// Variable declaration
+        let mut app = Self {
+            subplots: Vec::new(),
+            hidden_subplots: Vec::new(),
+            subplot_layout: SubplotLayout::default(),
+            active_subplot: 0,
+            show_subplot_controls: false,
+            next_name_index: 1,
+            notifications: Notifications::default(),
+            dark_mode: true,
+            screenshot_requested: false,
+            tick_font_size: FontSize::Medium,
+            palette: Palette::default(),
+            show_axis_controls: false,
+            show_data_manipulation: false,
+            show_comparison: false,
+            show_color_picker: false,
+            show_legend_controls: false,
+            subplot_rects: Vec::new(),
+            show_history: false,
+            show_annotations: false,
+            show_export_settings: false,
+            export_settings: ExportSettings::default(),
+            show_preferences: false,
+            preferences: Preferences::default(),
+            show_templates: false,
+            templates: None,
+            template_name: String::new(),
+            spreadsheet_imports: Vec::new(),
+            hdf5_imports: Vec::new(),
+            text_imports: Vec::new(),
+            file_watcher: None,
+            tail_readers: Vec::new(),
+            stdin_stream: None,
+            pending_loads: Vec::new(),
+            recent_files: RecentFiles::default(),
+            folder_import: None,
+            show_open_url: false,
+            url_input: String::new(),
+            url_refresh: false,
+            exports: Vec::new(),
+            annotation_tool: None,
+            annotation_text: String::new(),
+            annotation_color: [220, 50, 50],
+            pending_arrow_tail: None,
+            inset_grab: None,
+            x_selection_mode: false,
+            add_point_target: None,
+            x_selection: None,
+            restrict_to_selection: true,
+            show_crosshair: false,
+            cursor_readout: None,
+            view_x_ranges: HashMap::new(),
+            picked_point: None,
+            history: History::default(),
+            command_palette: CommandPalette::default(),
+            rolling_window_size: 10,
+            savitzky_golay_window: 11,
+            savitzky_golay_order: 2,
+            gaussian_sigma: 1.0,
+            fft_window: FftWindow::Hann,
+            normalization: Normalization::MinMax,
+            normalize_in_place: false,
+            histogram_of_x: false,
+            histogram_by_width: false,
+            histogram_bin_count: 20,
+            histogram_bin_width: 1.0,
+            replica_datasets: Vec::new(),
+            replica_subplot: String::new(),
+            resample_onto: None,
+            resample_count: 100,
+            resample_method: Interpolation::Linear,
+            replica_spread: ReplicaSpread::StdDev,
+            math_operand: 0,
+            math_operation: DatasetOperation::Difference,
+            math_expression: "y*4.184 + 3".to_string(),
+            baseline_mode: BaselineMode::Constant,
+            baseline_anchors: [0.0, 1.0],
+            baseline_window: 50,
+            downsample_display: true,
+            max_display_points: 20_000,
+            display_cache: DisplayCache::default(),
+            diagnostics: FrameDiagnostics::default(),
+            heatmap_textures: HashMap::new(),
+            selected_dataset_for_processing: 0,
+            last_statistics: None,
+            last_block_average: None,
+            peak_settings: PeakSettings {
+                minima: false,
+                height: None,
+                prominence: 0.0,
+                distance: 0.0,
+            },
+            last_peaks: None,
+            crossing_threshold: 0.5,
+            mark_crossings: false,
+            last_crossings: None,
+            comparison_datasets: [0, 1],
+            last_comparison: None,
+            selected_dataset_for_color: 0,
+            data_editor: DataEditor::default(),
+        };
+
+        // Initialize with one subplot
+        app.ensure_subplots_match_layout();
+        app
+    }
+}
+
+/// Implementation block defining methods for this type
+impl PlotterApp {
+    // The subplot's own palette, or the app-wide one
+/// Function: explain its purpose and key arguments
+    pub fn subplot_palette(&self, subplot_index: usize) -> Palette {
+        self.subplots
+            .get(subplot_index)
+            .and_then(|subplot| subplot.config.palette)
+            .unwrap_or(self.palette)
+    }
+
+    // Color for the next dataset added to a subplot
+/// Function: explain its purpose and key arguments
+    pub fn next_color(&self, subplot_index: usize) -> [u8; 3] {
+// Variable declaration
+        let count = self.subplots.get(subplot_index).map_or(0, |s| s.datasets.len());
+        self.subplot_palette(subplot_index).color(count)
+    }
+
+    // Give every dataset of the subplot its palette color again, in list order
+/// Function: explain its purpose and key arguments
+    fn recolor_subplot(&mut self, subplot_index: usize) {
+// Variable declaration
+        let palette = self.subplot_palette(subplot_index);
+        if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+            for (i, dataset) in subplot.datasets.iter_mut().enumerate() {
+                dataset.color = palette.color(i);
+            }
+        }
+    }
+
+    // Give the layout as many subplots as it has cells. Subplots that no longer fit are set
+    // aside rather than dropped, and come back first, in order, when the layout grows again.
+/// Function: explain its purpose and key arguments
+    pub fn ensure_subplots_match_layout(&mut self) {
+// Variable declaration
+        let required_count = self.subplot_layout.subplot_count();
+
+        // Hide excess subplots
+        if self.subplots.len() > required_count {
+// Variable declaration
+            let excess = self.subplots.split_off(required_count);
+// Variable declaration
+            let with_data = excess.iter().filter(|s| !s.datasets.is_empty() || !s.heatmaps.is_empty()).count();
+            if with_data > 0 {
+                self.notifications.warning(format!(
+                    "{} subplot(s) with data hidden; they come back when the layout grows",
+                    with_data
+                ));
+            }
+            self.hidden_subplots.splice(0..0, excess);
+        }
+
+        // Bring back hidden subplots, then add new ones
+// Variable declaration
+        let restored = self.hidden_subplots.len().min(required_count - self.subplots.len());
+        self.subplots.extend(self.hidden_subplots.drain(..restored));
+        while self.subplots.len() < required_count {
+// Variable declaration
+            let id = format!("subplot_{}", self.subplots.len());
+// Variable declaration
+            let mut subplot = Subplot::new(id);
+            subplot.config.x_padding_percent = self.preferences.x_padding_percent;
+            subplot.config.y_padding_percent = self.preferences.y_padding_percent;
+            self.subplots.push(subplot);
+        }
+
+        // Ensure active subplot is valid
+        if self.active_subplot >= self.subplots.len() {
+            self.active_subplot = 0;
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn get_active_subplot_mut(&mut self) -> Option<&mut Subplot> {
+        self.subplots.get_mut(self.active_subplot)
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn get_active_subplot(&self) -> Option<&Subplot> {
+        self.subplots.get(self.active_subplot)
+    }
+
+    // Capture the current subplots so a change can be undone later
+/// Function: explain its purpose and key arguments
+    fn snapshot(&self, label: &str) -> Snapshot {
+        Snapshot {
+            label: label.to_string(),
+            subplots: self.subplots.clone(),
+            hidden_subplots: self.hidden_subplots.clone(),
+            subplot_layout: self.subplot_layout.clone(),
+            active_subplot: self.active_subplot,
+        }
+    }
+
+    // Record the current state under `label`; call right before mutating it
+/// Function: explain its purpose and key arguments
+    pub fn record_history(&mut self, label: &str) {
+// Variable declaration
+        let snapshot = self.snapshot(label);
+        self.history.record(snapshot);
+    }
+
+    // Change a setting of the active subplot as one undo step of its own, for discrete
+    // actions such as toggling the grid
+/// Function: explain its purpose and key arguments
+    fn edit_active_config(&mut self, label: &str, edit: impl FnOnce(&mut SubplotConfig)) {
+        if self.get_active_subplot().is_none() {
+            return;
+        }
+        self.record_history(label);
+        if let Some(subplot) = self.get_active_subplot_mut() {
+            edit(&mut subplot.config);
+        }
+    }
+
+    // Record one step of a continuous edit (typing, dragging a value); the steps of one
+    // edit share an undo entry (see History::record_coalescing)
+/// Function: explain its purpose and key arguments
+    pub fn record_history_coalescing(&mut self, label: &str) {
+        // Taken out for the call so the snapshot can be built from the rest of the app
+// Variable declaration
+        let mut history = std::mem::take(&mut self.history);
+        history.record_coalescing(label, || self.snapshot(label));
+        self.history = history;
+    }
+
+    // Rescale everything the active subplot draws along `axis` from its declared unit to
+    // `to`: datasets (only those on the left axis for Y), heatmaps, annotations, custom
+    // ranges and ticks, and the X selection. The axis title follows.
+/// Function: explain its purpose and key arguments
+    pub fn convert_axis_unit(&mut self, axis: usize, to: &'static Unit) {
+// Variable declaration
+        let Some(subplot) = self.get_active_subplot() else {
+            return;
+        };
+// Variable declaration
+        let declared = if axis == 0 { &subplot.config.x_unit } else { &subplot.config.y_unit };
+// Variable declaration
+        let Some(map) = find_unit(declared).and_then(|from| conversion(from, to, axis)) else {
+            return;
+        };
+        self.record_history("Convert units");
+// Variable declaration
+        let active = self.active_subplot;
+        if axis == 0 {
+            if let Some((_, range)) = self.x_selection.as_mut().filter(|(i, _)| *i == active) {
+                *range = range.map(|x| map.map(x));
+            }
+        }
+// Variable declaration
+        let Some(subplot) = self.get_active_subplot_mut() else {
+            return;
+        };
+        subplot.map_axis(&map);
+// Variable declaration
+        let config = &mut subplot.config;
+        if axis == 0 {
+            config.x_label = relabel(&config.x_label, &config.x_unit, to.symbol);
+            config.x_unit = to.symbol.to_string();
+        } else {
+            config.y_label = relabel(&config.y_label, &config.y_unit, to.symbol);
+            config.y_unit = to.symbol.to_string();
+        }
+    }
+
+    // Textures of a subplot's heatmaps with their plot centre and size. The cells are drawn
+    // evenly spaced between the outer edges, which is how matrix and XPM files store them.
+    // Heatmaps are only drawn on linear axes; on reversed axes the texture is mirrored.
+/// Function: explain its purpose and key arguments
+    fn heatmap_images(&mut self, ctx: &egui::Context, subplot_index: usize) -> Vec<HeatmapImage> {
+        // Forget the textures of heatmaps that were removed or changed
+// Variable declaration
+        let live: HashSet<u64> = self
+            .subplots
+            .iter()
+            .flat_map(|subplot| subplot.heatmaps.iter().map(Heatmap::revision))
+            .collect();
+        self.heatmap_textures.retain(|revision, _| live.contains(revision));
+
+// Variable declaration
+        let Some(subplot) = self.subplots.get(subplot_index) else {
+            return Vec::new();
+        };
+        if subplot.config.x_scale.is_log() || subplot.config.y_scale.is_log() {
+            return Vec::new();
+        }
+// Variable declaration
+        let (x_axis, y_axis) = (subplot.config.x_axis(), subplot.config.y_axis());
+// Variable declaration
+        let mirror = |reversed: bool| if reversed { (1.0, 0.0) } else { (0.0, 1.0) };
+// Variable declaration
+        let ((u0, u1), (v0, v1)) = (mirror(x_axis.reversed), mirror(y_axis.reversed));
+// Variable declaration
+        let uv = egui::Rect::from_min_max(egui::pos2(u0, v0), egui::pos2(u1, v1));
+// Variable declaration
+        let mut images = Vec::new();
+        for heatmap in &subplot.heatmaps {
+// Variable declaration
+            let Some(range) = heatmap.value_range() else {
+                continue;
+            };
+// Variable declaration
+            let cached = self
+                .heatmap_textures
+                .get(&heatmap.revision())
+                .filter(|(colormap, _)| *colormap == heatmap.colormap);
+// Variable declaration
+            let texture_id = match cached {
+                Some((_, texture)) => texture.id(),
+                None => {
+// Variable declaration
+                    let (cols, rows) = (heatmap.x.len(), heatmap.y.len());
+                    // Image rows run from the top, heatmap rows from the lowest Y
+// Variable declaration
+                    let pixels = (0..rows)
+                        .rev()
+                        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+                        .map(|(row, col)| {
+                            heatmap
+                                .cell_color(row, col, range)
+                                .map_or(egui::Color32::TRANSPARENT, |c| egui::Color32::from_rgb(c[0], c[1], c[2]))
+                        })
+                        .collect();
+// Variable declaration
+                    let image = egui::ColorImage { size: [cols, rows], pixels };
+// Variable declaration
+                    let texture = ctx.load_texture(
+                        format!("heatmap_{}", heatmap.revision()),
+                        image,
+                        egui::TextureOptions::NEAREST,
+                    );
+// Variable declaration
+                    let id = texture.id();
+                    self.heatmap_textures.insert(heatmap.revision(), (heatmap.colormap, texture));
+                    id
+                }
+            };
+// Variable declaration
+            let (min_x, max_x, min_y, max_y) = heatmap.bounds();
+// Variable declaration
+            let (Some(center_x), Some(center_y)) = (x_axis.apply((min_x + max_x) / 2.0), y_axis.apply((min_y + max_y) / 2.0)) else {
+                continue;
+            };
+            images.push((
+                texture_id,
+                PlotPoint::new(center_x, center_y),
+                egui::vec2((max_x - min_x) as f32, (max_y - min_y) as f32),
+                uv,
+            ));
+        }
+        images
+    }
+
+/// Function: explain its purpose and key arguments
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.subplots = snapshot.subplots;
+        self.hidden_subplots = snapshot.hidden_subplots;
+        self.subplot_layout = snapshot.subplot_layout;
+        self.active_subplot = snapshot.active_subplot;
+        self.ensure_subplots_match_layout();
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn undo(&mut self) {
+// Variable declaration
+        let current = self.snapshot("");
+        if let Some(previous) = self.history.undo(current) {
+            self.notifications.info(format!("Undo: {}", previous.label));
+            self.restore_snapshot(previous);
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn redo(&mut self) {
+// Variable declaration
+        let current = self.snapshot("");
+        if let Some(next) = self.history.redo(current) {
+            self.notifications.info(format!("Redo: {}", next.label));
+            self.restore_snapshot(next);
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn set_layout(&mut self, layout: SubplotLayout) {
+        if layout != self.subplot_layout {
+            self.record_history("Change layout");
+            self.subplot_layout = layout;
+            self.ensure_subplots_match_layout();
+        }
+    }
+
+    // Rows, columns, track weights and subplot spans of the layout. Edits go to a copy that
+    // replaces the layout once per frame, so dragging a value is a single undo step.
+/// Function: explain its purpose and key arguments
+    fn show_grid_editor(&mut self, ui: &mut egui::Ui) {
+// Variable declaration
+        let mut layout = self.subplot_layout.clone();
+        ui.horizontal(|ui| {
+            ui.label("Rows:");
+            ui.add(egui::DragValue::new(&mut layout.rows).clamp_range(1..=SubplotLayout::MAX_TRACKS));
+            ui.label("Columns:");
+            ui.add(egui::DragValue::new(&mut layout.cols).clamp_range(1..=SubplotLayout::MAX_TRACKS));
+            if ui
+                .add_enabled(layout != SubplotLayout::grid(layout.rows, layout.cols), egui::Button::new("Even grid"))
+                .on_hover_text("Equal row and column sizes, one subplot per cell")
+                .clicked()
+            {
+                layout = SubplotLayout::grid(layout.rows, layout.cols);
+            }
+        });
+
+        ui.label("Relative sizes:");
+        egui::Grid::new("layout_weights_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Row heights");
+            ui.horizontal(|ui| {
+// Variable declaration
+                let mut weights: Vec<f32> = (0..layout.rows).map(|row| layout.row_weight(row)).collect();
+// Variable declaration
+                let mut changed = false;
+                for weight in &mut weights {
+                    changed |= ui.add(egui::DragValue::new(weight).speed(0.05).clamp_range(0.1..=10.0)).changed();
+                }
+                if changed {
+                    layout.row_weights = weights;
+                }
+            });
+            ui.end_row();
+            ui.label("Column widths");
+            ui.horizontal(|ui| {
+// Variable declaration
+                let mut weights: Vec<f32> = (0..layout.cols).map(|col| layout.col_weight(col)).collect();
+// Variable declaration
+                let mut changed = false;
+                for weight in &mut weights {
+                    changed |= ui.add(egui::DragValue::new(weight).speed(0.05).clamp_range(0.1..=10.0)).changed();
+                }
+                if changed {
+                    layout.col_weights = weights;
+                }
+            });
+            ui.end_row();
+        });
+
+        ui.label("Spans (rows × columns):");
+        egui::Grid::new("layout_spans_grid").num_columns(2).show(ui, |ui| {
+            for index in 0..layout.subplot_count() {
+// Variable declaration
+                let (mut rows, mut cols) = layout.span(index);
+                ui.label(format!("Subplot {}", index + 1));
+                ui.horizontal(|ui| {
+// Variable declaration
+                    let rows_changed = ui.add(egui::DragValue::new(&mut rows).clamp_range(1..=layout.rows)).changed();
+                    ui.label("×");
+// Variable declaration
+                    let cols_changed = ui.add(egui::DragValue::new(&mut cols).clamp_range(1..=layout.cols)).changed();
+                    if rows_changed || cols_changed {
+                        if layout.spans.len() <= index {
+                            layout.spans.resize(index + 1, [1, 1]);
+                        }
+                        layout.spans[index] = [rows, cols];
+                    }
+                });
+                ui.end_row();
+            }
+        });
+
+        // Drop entries that only repeat the defaults so plain grids still match their preset
+        if layout.row_weights.iter().all(|weight| *weight == 1.0) {
+            layout.row_weights.clear();
+        }
+        if layout.col_weights.iter().all(|weight| *weight == 1.0) {
+            layout.col_weights.clear();
+        }
+        while layout.spans.last() == Some(&[1, 1]) {
+            layout.spans.pop();
+        }
+        self.set_layout(layout);
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn clear_active_subplot(&mut self) {
+        self.record_history("Clear subplot");
+        if let Some(subplot) = self.get_active_subplot_mut() {
+            subplot.datasets.clear();
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn open_files_dialog(&mut self) {
+        if let Some(paths) = pick_multiple_files() {
+            self.load_files_into_subplot(paths, self.active_subplot);
+        }
+    }
+
+    // Pick a folder and show the window that chooses which of its files to load
+/// Function: explain its purpose and key arguments
+    fn open_folder_dialog(&mut self) {
+        if let Some(dir) = pick_folder() {
+            self.folder_import = Some(FolderImport::open(dir));
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn export_plot(&mut self) {
+        self.start_export(self.export_settings.format);
+    }
+
+    // Export with the save dialog set to a matplotlib script, whatever format is preferred
+/// Function: explain its purpose and key arguments
+    fn export_plot_script(&mut self) {
+        self.start_export(ExportFormat::PythonScript);
+    }
+
+    // Ask for a file name, offering `format` first, and write the figure on a worker thread;
+    // a notification reports the outcome once it is done (see poll_exports)
+/// Function: explain its purpose and key arguments
+    fn start_export(&mut self, format: ExportFormat) {
+        if self.subplots.is_empty() {
+            self.notifications.error("Failed to export plot: No subplots to export");
+            return;
+        }
+// Variable declaration
+        let Some(path) = pick_figure_save_path("subplots", format) else {
+            return;
+        };
+        self.exports.push(ExportJob::spawn(
+            path,
+            self.subplots.clone(),
+            self.subplot_layout.clone(),
+            self.dark_mode,
+            self.tick_font_size.clone(),
+            self.export_settings,
+        ));
+    }
+
+    // Report the exports that finished since the last frame
+/// Function: explain its purpose and key arguments
+    fn poll_exports(&mut self, ctx: &egui::Context) {
+        if self.exports.is_empty() {
+            return;
+        }
+// Variable declaration
+        let mut finished = Vec::new();
+        self.exports.retain(|job| match job.poll() {
+            Some(result) => {
+                finished.push((job.path.clone(), result));
+                false
+            }
+            None => true,
+        });
+        if !self.exports.is_empty() {
+            ctx.request_repaint_after(LIVE_REPAINT_DELAY);
+        }
+        for (path, result) in finished {
+            match result {
+                Ok(()) => self.notifications.success(format!("Plot exported to {}", path.display())),
+                Err(e) => self.notifications.error(format!("Failed to export {}: {}", file_display_name(&path), e)),
+            }
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn copy_plot_image(&mut self) {
+// Variable declaration
+        let result = render_subplots_image(
+            &self.subplots,
+            &self.subplot_layout,
+            self.dark_mode,
+            &self.tick_font_size,
+            &self.export_settings,
+        )
+        .and_then(|img| copy_image_to_clipboard(&img));
+        match result {
+            Ok(()) => self.notifications.success("Plot copied to clipboard"),
+            Err(e) => self.notifications.error(format!("Failed to copy plot: {}", e)),
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn save_session_dialog(&mut self) {
+        if let Some(path) = pick_session_save_path() {
+            match save_session(self, &path) {
+                Ok(()) => self.notifications.success(format!("Session saved to {}", path.display())),
+                Err(e) => self.notifications.error(format!("Failed to save session: {}", e)),
+            }
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn open_session_dialog(&mut self) {
+        if let Some(path) = pick_session_file() {
+            match load_session(&path) {
+                Ok(session) => {
+                    self.record_history("Open session");
+                    session.apply_to(self);
+                    self.notifications.success(format!("Session loaded from {}", path.display()));
+                }
+                Err(e) => self.notifications.error(format!("Failed to open session: {}", e)),
+            }
+        }
+    }
+
+    // Carry out a command picked in the command palette
+/// Function: explain its purpose and key arguments
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::OpenFiles => self.open_files_dialog(),
+            Command::OpenFolder => self.open_folder_dialog(),
+            Command::OpenUrl => self.show_open_url = true,
+            Command::OpenSession => self.open_session_dialog(),
+            Command::SaveSession => self.save_session_dialog(),
+            Command::ExportPlot => self.export_plot(),
+            Command::ExportScript => self.export_plot_script(),
+            Command::ExportFitReport => self.export_fit_report(),
+            Command::CopyPlot => self.copy_plot_image(),
+            Command::PasteData => self.paste_data_as_dataset(None),
+            Command::NewEmptyDataset => self.new_empty_dataset(),
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::ClearActiveSubplot => self.clear_active_subplot(),
+            Command::DuplicateSubplot => self.duplicate_subplot(self.active_subplot),
+            Command::ToggleWindow(window) => {
+// Variable declaration
+                let shown = match window {
+                    ToolWindow::Subplots => &mut self.show_subplot_controls,
+                    ToolWindow::AxisControls => &mut self.show_axis_controls,
+                    ToolWindow::DataProcessing => &mut self.show_data_manipulation,
+                    ToolWindow::Comparison => &mut self.show_comparison,
+                    ToolWindow::Colors => &mut self.show_color_picker,
+                    ToolWindow::LegendAndFonts => &mut self.show_legend_controls,
+                    ToolWindow::Annotations => &mut self.show_annotations,
+                    ToolWindow::History => &mut self.show_history,
+                    ToolWindow::ExportSettings => &mut self.show_export_settings,
+                    ToolWindow::Preferences => &mut self.show_preferences,
+                    ToolWindow::Templates => &mut self.show_templates,
+                    ToolWindow::DataEditor => &mut self.data_editor.show_editor,
+                };
+                *shown = !*shown;
+            }
+            Command::SetLayout(preset) => self.set_layout(preset.into()),
+            Command::ToggleGrid => self.edit_active_config("Toggle grid", |config| config.show_grid = !config.show_grid),
+            Command::ToggleLegend => {
+                self.edit_active_config("Toggle legend", |config| config.show_legend = !config.show_legend)
+            }
+            Command::ToggleCrosshair => self.show_crosshair = !self.show_crosshair,
+            Command::ToggleDiagnostics => self.diagnostics.visible = !self.diagnostics.visible,
+            Command::ToggleDarkMode => self.dark_mode = !self.dark_mode,
+            Command::RollingAverage => self.add_rolling_average_dataset(),
+            Command::SavitzkyGolay => {
+// Variable declaration
+                let (window, order) = (self.savitzky_golay_window, self.savitzky_golay_order);
+                self.add_derived_dataset("Savitzky–Golay smoothing", "savgol", |points| {
+                    compute_savitzky_golay(points, window, order)
+                });
+            }
+            Command::GaussianSmoothing => {
+// Variable declaration
+                let sigma = self.gaussian_sigma;
+                self.add_derived_dataset("Gaussian smoothing", "gaussian", |points| {
+                    compute_gaussian_smoothing(points, sigma)
+                });
+            }
+            Command::Derivative => self.add_derived_dataset("Derivative", "derivative", compute_derivative),
+            Command::Integral => self.add_derived_dataset("Integral", "integral", compute_cumulative_integral),
+            Command::PowerSpectrum => {
+// Variable declaration
+                let window = self.fft_window;
+                self.add_derived_dataset("Power spectrum", "psd", |points| compute_power_spectrum(points, window));
+            }
+            Command::Normalize => self.normalize_processing_dataset(),
+            Command::FindPeaks => self.add_peaks_dataset(),
+            Command::FindCrossings => self.find_threshold_crossings(),
+            Command::BlockAverage => self.add_block_average_dataset(),
+            Command::Statistics => self.compute_dataset_statistics(),
+            Command::BoxPlots => self.show_subplot_as_distributions(PlotType::Box),
+            Command::ViolinPlots => self.show_subplot_as_distributions(PlotType::Violin),
+        }
+    }
+
+    // Handle a click on a subplot while an annotation tool is selected. Arrows take two
+    // clicks: the first sets the tail, the second the tip.
+/// Function: explain its purpose and key arguments
+    pub fn place_annotation(&mut self, subplot_index: usize, point: [f64; 2]) {
+// Variable declaration
+        let Some(kind) = self.annotation_tool else {
+            return;
+        };
+        self.active_subplot = subplot_index;
+
+// Variable declaration
+        let mut annotation = Annotation::new(kind, point, self.annotation_text.clone(), self.annotation_color);
+        if kind == AnnotationKind::Arrow {
+            match self.pending_arrow_tail.take() {
+                Some((index, tail)) if index == subplot_index => annotation.tail = Some(tail),
+                _ => {
+                    self.pending_arrow_tail = Some((subplot_index, point));
+                    self.notifications.info("Click where the arrow should point");
+                    return;
+                }
+            }
+        }
+
+        self.record_history("Add annotation");
+        if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+            subplot.annotations.push(annotation);
+        }
+        self.notifications.success(format!("{} annotation added", kind.to_string()));
+    }
+
+    // Add a dataset without points to the active subplot, open it in the Data Editor and
+    // start add-point mode, so it can be typed in or clicked onto the plot
+/// Function: explain its purpose and key arguments
+    pub fn new_empty_dataset(&mut self) {
+// Variable declaration
+        let name = format!("dataset{}", self.next_name_index);
+        self.next_name_index += 1;
+// Variable declaration
+        let color = self.next_color(self.active_subplot);
+        self.record_history("New empty dataset");
+// Variable declaration
+        let subplot_index = self.active_subplot;
+        if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+            subplot.datasets.push(Dataset::new(name.clone(), Vec::new(), color));
+// Variable declaration
+            let dataset_index = subplot.datasets.len() - 1;
+            self.data_editor.open_dataset(&subplot.datasets, dataset_index);
+            self.add_point_target = Some((subplot_index, dataset_index));
+            self.annotation_tool = None;
+            self.pending_arrow_tail = None;
+            self.x_selection_mode = false;
+            self.notifications.info(format!("Click the plot to add points to {}, or type them into the Data Editor", name));
+        }
+    }
+
+    // Handle a click on a subplot in add-point mode: the clicked position becomes a point
+    // of the target dataset
+/// Function: explain its purpose and key arguments
+    pub fn add_clicked_point(&mut self, point: [f64; 2]) {
+// Variable declaration
+        let Some((subplot_index, dataset_index)) = self.add_point_target else {
+            return;
+        };
+        // The dataset may have been removed since (undo, clearing the subplot)
+        if self.subplots.get(subplot_index).and_then(|s| s.datasets.get(dataset_index)).is_none() {
+            self.add_point_target = None;
+            return;
+        }
+        self.active_subplot = subplot_index;
+        self.record_history("Add point");
+// Variable declaration
+        let dataset = &mut self.subplots[subplot_index].datasets[dataset_index];
+// Variable declaration
+        let index = dataset.add_point(point);
+        dataset.follow = false;
+        self.data_editor.reload_dataset(&self.subplots[subplot_index].datasets, dataset_index);
+        self.data_editor.select_point(dataset_index, index);
+        self.picked_point = Some((subplot_index, dataset_index, index));
+    }
+
+    // Copy the shown datasets of a subplot, limited to the X range in view, to the
+    // clipboard as tab-separated columns
+/// Function: explain its purpose and key arguments
+    fn copy_visible_data(&mut self, subplot_index: usize) {
+// Variable declaration
+        let Some(subplot) = self.subplots.get(subplot_index) else {
+            return;
+        };
+// Variable declaration
+        let range = self.view_x_ranges.get(&subplot_index).copied();
+// Variable declaration
+        let series: Vec<(&str, Vec<[f64; 2]>)> = subplot
+            .datasets
+            .iter()
+            .filter(|ds| ds.visible)
+            .map(|ds| (ds.name.as_str(), points_in_x_range(&ds.points, range)))
+            .filter(|(_, points)| !points.is_empty())
+            .collect();
+        if series.is_empty() {
+            self.notifications.warning("No data in view to copy");
+            return;
+        }
+// Variable declaration
+        let count = series.len();
+        match copy_text_to_clipboard(series_to_tsv(&series)) {
+            Ok(()) => self.notifications.success(format!("Copied {} datasets to clipboard", count)),
+            Err(e) => self.notifications.error(format!("Failed to copy data: {}", e)),
+        }
+    }
+
+    // Handle a plain click on a subplot: pick the (dataset, point) under the mouse, or
+    // clear the pick when the click missed every point
+/// Function: explain its purpose and key arguments
+    pub fn pick_point(&mut self, subplot_index: usize, picked: Option<(usize, usize)>) {
+        self.active_subplot = subplot_index;
+        self.picked_point = picked.map(|(dataset, point)| (subplot_index, dataset, point));
+        if let Some((dataset, point)) = picked {
+            self.data_editor.select_point(dataset, point);
+        }
+    }
+
+    // Drop the picked point when the datasets of its subplot were rearranged
+/// Function: explain its purpose and key arguments
+    fn forget_picked_point(&mut self, subplot_index: usize) {
+        if self.picked_point.is_some_and(|(i, _, _)| i == subplot_index) {
+            self.picked_point = None;
+        }
+    }
+
+    // Move a dataset to the end of another subplot's list, which becomes the active one
+/// Function: explain its purpose and key arguments
+    pub fn move_dataset(&mut self, from: usize, index: usize, to: usize) {
+        if from == to || to >= self.subplots.len() || self.subplots.get(from).is_none_or(|s| index >= s.datasets.len()) {
+            return;
+        }
+        self.record_history("Move dataset");
+// Variable declaration
+        let dataset = self.subplots[from].datasets.remove(index);
+// Variable declaration
+        let name = dataset.name.clone();
+        self.subplots[to].datasets.push(dataset);
+        self.forget_picked_point(from);
+        self.active_subplot = to;
+        self.notifications.success(format!("Moved {} to subplot {}", name, to + 1));
+    }
+
+    // Copy a dataset to the end of another subplot's list, which becomes the active one
+/// Function: explain its purpose and key arguments
+    pub fn copy_dataset(&mut self, from: usize, index: usize, to: usize) {
+        if from == to || to >= self.subplots.len() {
+            return;
+        }
+// Variable declaration
+        let Some(dataset) = self.subplots.get(from).and_then(|s| s.datasets.get(index)).cloned() else {
+            return;
+        };
+        self.record_history("Copy dataset");
+        self.notifications.success(format!("Copied {} to subplot {}", dataset.name, to + 1));
+        self.subplots[to].datasets.push(dataset);
+        self.active_subplot = to;
+    }
+
+    // Copy a subplot with its datasets, heatmaps, annotations and settings into the first
+    // empty subplot after it, adding a row to the layout when none is left
+/// Function: explain its purpose and key arguments
+    pub fn duplicate_subplot(&mut self, index: usize) {
+// Variable declaration
+        let Some(source) = self.subplots.get(index).cloned() else {
+            return;
+        };
+// Variable declaration
+        let count = self.subplots.len();
+// Variable declaration
+        let empty = (1..count)
+            .map(|offset| (index + offset) % count)
+            .find(|&i| self.subplots[i].datasets.is_empty() && self.subplots[i].heatmaps.is_empty());
+// Variable declaration
+        let grown = match empty {
+            Some(_) => None,
+            None => match self.subplot_layout.with_extra_row() {
+                Some(layout) => Some(layout),
+                None => {
+                    self.notifications.warning("No room for another subplot in the layout");
+                    return;
+                }
+            },
+        };
+
+        self.record_history("Duplicate subplot");
+        if let Some(layout) = grown {
+            self.subplot_layout = layout;
+            self.ensure_subplots_match_layout();
+        }
+// Variable declaration
+        let target = empty.unwrap_or(count);
+// Variable declaration
+        let id = self.subplots[target].id.clone();
+        self.subplots[target] = Subplot { id, ..source };
+        self.active_subplot = target;
+        self.notifications.success(format!("Subplot {} duplicated into subplot {}", index + 1, target + 1));
+    }
+
+    // Values of the picked point and its neighbours, with buttons to edit or delete it.
+    // Changes are written to the Data Editor sheet when it shows the same subplot.
+/// Function: explain its purpose and key arguments
+    fn show_point_inspector_window(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let Some((subplot_index, dataset_index, point_index)) = self.picked_point else {
+            return;
+        };
+// Variable declaration
+        let Some(dataset) = self
+            .subplots
+            .get(subplot_index)
+            .and_then(|subplot| subplot.datasets.get(dataset_index))
+            .filter(|dataset| point_index < dataset.points.len())
+        else {
+            // The point is gone (undo, reload, removed dataset)
+            self.picked_point = None;
+            return;
+        };
+// Variable declaration
+        let name = dataset.name.clone();
+// Variable declaration
+        let len = dataset.points.len();
+// Variable declaration
+        let first = point_index.saturating_sub(2);
+// Variable declaration
+        let neighbours: Vec<(usize, [f64; 2])> =
+            (first..(point_index + 3).min(len)).map(|i| (i, dataset.points.point(i))).collect();
+// Variable declaration
+        let mut point = dataset.points.point(point_index);
+
+// Variable declaration
+        let mut open = true;
+// Variable declaration
+        let mut edited = false;
+// Variable declaration
+        let mut delete = false;
+// Variable declaration
+        let mut select = None;
+        egui::Window::new("Point Inspector")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                ui.label(format!("{} (subplot {})", name, subplot_index + 1));
+                ui.small(format!("Point {} of {}", point_index + 1, len));
+                ui.separator();
+
+                egui::Grid::new("point_inspector_grid").striped(true).show(ui, |ui| {
+                    ui.strong("#");
+                    ui.strong("x");
+                    ui.strong("y");
+                    ui.end_row();
+                    for (i, [x, y]) in &neighbours {
+                        if ui.selectable_label(*i == point_index, format!("{}", i + 1)).clicked() {
+                            select = Some(*i);
+                        }
+                        ui.label(format_number(*x));
+                        ui.label(format_number(*y));
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    for (label, value) in ["x:", "y:"].into_iter().zip(point.iter_mut()) {
+                        ui.label(label);
+// Variable declaration
+                        let speed = (value.abs() * 1e-3).max(1e-6);
+                        edited |= ui.add(egui::DragValue::new(value).speed(speed)).changed();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(point_index > 0, egui::Button::new("◀ Previous")).clicked() {
+                        select = Some(point_index - 1);
+                    }
+                    if ui.add_enabled(point_index + 1 < len, egui::Button::new("Next ▶")).clicked() {
+                        select = Some(point_index + 1);
+                    }
+                    if ui.button("🗑 Delete Point").clicked() {
+                        delete = true;
+                    }
+                });
+            });
+
+        if !open {
+            self.picked_point = None;
+            return;
+        }
+        if let Some(i) = select {
+            self.picked_point = Some((subplot_index, dataset_index, i));
+            if subplot_index == self.active_subplot {
+                self.data_editor.select_point(dataset_index, i);
+            }
+        }
+        if !edited && !delete {
+            return;
+        }
+
+        if delete {
+            self.record_history("Delete point");
+        } else {
+            self.record_history_coalescing("Edit point");
+        }
+        if let Some(dataset) = self.subplots[subplot_index].datasets.get_mut(dataset_index) {
+            if delete {
+                dataset.remove_point(point_index);
+                self.picked_point = (!dataset.points.is_empty())
+                    .then(|| (subplot_index, dataset_index, point_index.min(dataset.points.len() - 1)));
+            } else {
+                dataset.set_point(point_index, point);
+            }
+            // Hand-edited points would be overwritten by the next reload
+            dataset.follow = false;
+        }
+        if subplot_index == self.active_subplot {
+            self.data_editor.reload_dataset(&self.subplots[subplot_index].datasets, dataset_index);
+        }
+    }
+
+    // X interval that Data Processing is limited to: the selection on the active subplot,
+    // if there is one and restriction is turned on
+/// Function: explain its purpose and key arguments
+    pub fn processing_x_range(&self) -> Option<(f64, f64)> {
+        self.x_selection
+            .filter(|(index, _)| self.restrict_to_selection && *index == self.active_subplot)
+            .map(|(_, [start, end])| (start.min(end), start.max(end)))
+    }
+
+    // Put a fit residuals dataset in the subplot below the active one, adding a row to
+    // the layout when needed; falls back to the active subplot when the layout is full
+/// Function: explain its purpose and key arguments
+    pub fn add_residuals_below(&mut self, residuals: Dataset) {
+        self.record_history("Create residuals");
+
+        if self.subplot_layout.subplot_below(self.active_subplot).is_none() {
+            if let Some(layout) = self.subplot_layout.with_extra_row() {
+                self.subplot_layout = layout;
+                self.ensure_subplots_match_layout();
+            }
+        }
+
+// Variable declaration
+        let below = self.subplot_layout.subplot_below(self.active_subplot).unwrap_or(usize::MAX);
+// Variable declaration
+        let x_label = self.get_active_subplot().map(|s| s.config.x_label.clone()).unwrap_or_default();
+        if let Some(subplot) = self.subplots.get_mut(below) {
+            if subplot.config.title.is_empty() {
+                subplot.config.title = "Residuals".to_string();
+            }
+            if subplot.config.x_label.is_empty() {
+                subplot.config.x_label = x_label;
+            }
+            if subplot.config.y_label.is_empty() {
+                subplot.config.y_label = "Residual".to_string();
+            }
+            subplot.datasets.push(residuals);
+            self.notifications.success(format!("Residuals added to subplot {}", below + 1));
+        } else if let Some(subplot) = self.get_active_subplot_mut() {
+            subplot.datasets.push(residuals);
+            self.notifications.warning("No room for a subplot below; residuals added to the active subplot");
+        }
+    }
+
+    // Start reading data files for the given subplot, each on its own worker thread; they are
+    // added, and the outcome reported in notifications, as they finish (see poll_pending_loads)
+/// Function: explain its purpose and key arguments
+    pub fn load_files_into_subplot(&mut self, paths: Vec<PathBuf>, subplot_index: usize) {
+        self.recent_files.add(&paths);
+        self.store_recent_files();
+        self.pending_loads.extend(paths.into_iter().map(|path| PendingLoad::spawn(path, subplot_index)));
+    }
+
+    // Start reading a batch of files straight into datasets, skipping the import dialogs;
+    // file `i` goes to subplot `subplot_for(i)`
+/// Function: explain its purpose and key arguments
+    pub fn load_files_directly(&mut self, paths: Vec<PathBuf>, subplot_for: impl Fn(usize) -> usize) {
+        self.recent_files.add(&paths);
+        self.store_recent_files();
+        self.pending_loads.extend(PendingLoad::spawn_direct_batch(
+            paths.into_iter().enumerate().map(|(i, path)| (path, subplot_for(i))).collect(),
+        ));
+    }
+
+    // Write the Recent menu to the config file, so it is there next time
+/// Function: explain its purpose and key arguments
+    fn store_recent_files(&mut self) {
+        if let Err(e) = save_recent_files(&self.recent_files) {
+            self.notifications.warning(format!("Failed to save the list of recent files: {}", e));
+        }
+    }
+
+    // Add the files whose worker threads finished reading since the last frame. Files are
+    // added in the order they were opened, so a file read quickly waits for the ones before
+    // it; that keeps the datasets, and the colors they get, in the order of the file names.
+/// Function: explain its purpose and key arguments
+    fn poll_pending_loads(&mut self, ctx: &egui::Context) {
+        if self.pending_loads.is_empty() {
+            return;
+        }
+// Variable declaration
+        let ready = self.pending_loads.iter_mut().position(|load| !load.is_done()).unwrap_or(self.pending_loads.len());
+// Variable declaration
+        let finished: Vec<_> = self
+            .pending_loads
+            .drain(..ready)
+            .map(|load| (load.path.clone(), load.subplot_index, load.into_outcome()))
+            .collect();
+        // Check again shortly for the files still being read
+        if !self.pending_loads.is_empty() {
+            ctx.request_repaint_after(LIVE_REPAINT_DELAY);
+        }
+        if finished.is_empty() {
+            return;
+        }
+
+// Variable declaration
+        let before = self.snapshot("Load files");
+// Variable declaration
+        let mut successful_loads = 0;
+// Variable declaration
+        let mut dialogs_opened = 0;
+// Variable declaration
+        let mut forgotten_files = 0;
+
+        for (path, subplot_index, result) in finished {
+            match result {
+                // Workbooks, Parquet and HDF5 files and text tables need columns chosen first
+                Ok(LoadedContent::Spreadsheet(import)) => {
+                    self.spreadsheet_imports.push(import);
+                    dialogs_opened += 1;
+                }
+                Ok(LoadedContent::Hdf5(import)) => {
+                    self.hdf5_imports.push(import);
+                    dialogs_opened += 1;
+                }
+                Ok(LoadedContent::Text(import)) => {
+                    self.text_imports.push(import);
+                    dialogs_opened += 1;
+                }
+                // GROMACS matrices become heatmaps
+                Ok(LoadedContent::Heatmap(heatmap, metadata)) => {
+                    if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+                        metadata.apply_to(&mut subplot.config);
+                        subplot.heatmaps.push(heatmap);
+                    }
+                    successful_loads += 1;
+                }
+                Ok(LoadedContent::Datasets(read_path, loaded)) => {
+// Variable declaration
+                    let file_name = file_display_name(&read_path);
+// Variable declaration
+                    let palette = self.subplot_palette(subplot_index);
+// Variable declaration
+                    let first_color =
+                        self.subplots.get(subplot_index).map_or(0, |s| s.datasets.len());
+                    if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+                        loaded.metadata.apply_to(&mut subplot.config);
+// Variable declaration
+                        let mut datasets =
+                            datasets_from_series(loaded.series, &file_name, palette, first_color);
+                        for warning in &loaded.metadata.warnings {
+                            self.notifications.warning(format!("{}: {}", file_name, warning));
+                        }
+                        set_sources(&mut datasets, &read_path, None);
+                        subplot.datasets.extend(datasets);
+                    }
+                    successful_loads += 1;
+                }
+                Err(e) => {
+                    self.notifications.error(format!("Failed to load {}: {}", file_display_name(&path), e));
+                    self.recent_files.forget(&path);
+                    forgotten_files += 1;
+                }
+            }
+        }
+
+        if successful_loads > 0 {
+            self.history.record(before);
+        }
+        if forgotten_files > 0 {
+            self.store_recent_files();
+        }
+
+        // Each failure has its own notification (see above), so they do not hide each other
+        if successful_loads > 0 {
+            self.notifications.success(format!("Loaded {} file(s)", successful_loads));
+        }
+        if dialogs_opened > 0 {
+            self.notifications.info("Choose the data and columns to import");
+        }
+    }
+}
+
+/// Implementation block defining methods for this type
+impl App for PlotterApp {
+/// Function: explain its purpose and key arguments
+    fn update(&mut self, ctx: &egui::Context, frame: &mut Frame) {
+        self.diagnostics.record(ctx.input(|i| i.time), frame.info().cpu_usage);
+        if self.dark_mode {
+            ctx.set_visuals(egui::Visuals::dark())
+        } else {
+            ctx.set_visuals(egui::Visuals::light());
+        }
+
+        // Files dragged onto the window from the OS
+        self.handle_dropped_files(ctx);
+
+        // Reload datasets whose followed source file changed
+        self.reload_followed_files(ctx);
+
+        // Add lines that arrived on standard input
+        self.poll_stdin();
+
+        // Add files that finished loading in the background
+        self.poll_pending_loads(ctx);
+        self.poll_exports(ctx);
+
+        // Ctrl+P opens (or closes) the command palette, even while a text field has focus
+        if ctx.input_mut(|i| {
+            i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::P))
+        }) {
+            self.command_palette.toggle();
+        }
+        if let Some(command) = self.command_palette.show(ctx) {
+            self.run_command(command);
+        }
+
+        // Keyboard undo/redo (left to text fields while one has focus)
+        if !ctx.wants_keyboard_input() {
+            // Check Ctrl+Shift+Z first: Ctrl+Z would also match it
+// Variable declaration
+            let redo = ctx.input_mut(|i| {
+                i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                    egui::Key::Z,
+                )) || i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::COMMAND,
+                    egui::Key::Y,
+                ))
+            });
+// Variable declaration
+            let undo = ctx.input_mut(|i| {
+                i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::COMMAND,
+                    egui::Key::Z,
+                ))
+            });
+            if redo {
+                self.redo();
+            } else if undo {
+                self.undo();
+            }
+
+            // Ctrl+Shift+V reaches us as a paste event (with the clipboard text) while Shift is held
+// Variable declaration
+            let pasted = ctx.input_mut(|i| {
+                if !(i.modifiers.command && i.modifiers.shift) {
+                    return None;
+                }
+// Variable declaration
+                let index = i.events.iter().position(|event| matches!(event, egui::Event::Paste(_)))?;
+                match i.events.remove(index) {
+                    egui::Event::Paste(text) => Some(text),
+                    _ => None,
+                }
+            });
+            if let Some(text) = pasted {
+                self.paste_data_as_dataset(Some(text));
+            }
+        }
+
+        // Subplot settings edited in place by the widgets below; compared at the end of the frame
+// Variable declaration
+        let configs_before: Vec<SubplotConfig> =
+            self.subplots.iter().map(|s| s.config.clone()).collect();
+// Variable declaration
+        let history_generation = self.history.generation();
+
+        // Main application window
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Open File(s)").clicked() {
+                    self.open_files_dialog();
+                }
+
+                if ui
+                    .button("📁 Open Folder…")
+                    .on_hover_text("Load every matching data file in a folder, each as its own dataset")
+                    .clicked()
+                {
+                    self.open_folder_dialog();
+                }
+
+                if ui.button("🌐 Open URL…").on_hover_text("Download a data file over HTTP(S) and plot it").clicked() {
+                    self.show_open_url = !self.show_open_url;
+                }
+
+                ui.add_enabled_ui(!self.recent_files.files.is_empty(), |ui| {
+                    ui.menu_button("📄 Recent", |ui| {
+// Variable declaration
+                        let mut reopen = None;
+                        for path in &self.recent_files.files {
+                            if ui
+                                .button(path.file_name().unwrap_or_default().to_string_lossy())
+                                .on_hover_text(path.display().to_string())
+                                .clicked()
+                            {
+                                reopen = Some(path.clone());
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        if ui.button("Clear list").clicked() {
+                            self.recent_files = RecentFiles::default();
+                            self.store_recent_files();
+                            ui.close_menu();
+                        }
+                        if let Some(path) = reopen {
+                            self.load_files_into_subplot(vec![path], self.active_subplot);
+                        }
+                    });
+                })
+                .response
+                .on_disabled_hover_text("Files opened here are listed for quick re-opening");
+
+                if ui.button("Export Plot").on_hover_text("Save all subplots as a PNG image, PDF document or matplotlib script").clicked() {
+                    self.export_plot();
+                }
+
+                if ui
+                    .button("Export as Python Script")
+                    .on_hover_text("Save a standalone matplotlib script that redraws the figure, with the data embedded")
+                    .clicked()
+                {
+                    self.export_plot_script();
+                }
+
+                if ui.button("⚙ Export Settings").clicked() {
+                    self.show_export_settings = !self.show_export_settings;
+                }
+
+                if ui.button("🔧 Preferences").clicked() {
+                    self.show_preferences = !self.show_preferences;
+                }
+
+                if ui.button("🖌 Templates").on_hover_text("Save and apply named figure styles").clicked() {
+                    self.show_templates = !self.show_templates;
+                }
+
+                if ui.button("📋 Copy Plot").on_hover_text("Copy all subplots to the clipboard as an image").clicked() {
+                    self.copy_plot_image();
+                }
+
+                if ui
+                    .button("📥 Paste Data")
+                    .on_hover_text("Add the table on the clipboard to the active subplot (Ctrl+Shift+V)")
+                    .clicked()
+                {
+                    self.paste_data_as_dataset(None);
+                }
+
+                if ui.button("💾 Save Session").clicked() {
+                    self.save_session_dialog();
+                }
+
+                if ui.button("📂 Open Session").clicked() {
+                    self.open_session_dialog();
+                }
+
+                if ui.button("Clear Active Subplot").clicked() {
+                    self.clear_active_subplot();
+                }
+
+                if ui.button("Clear All Subplots").clicked() {
+                    self.record_history("Clear all subplots");
+                    for subplot in &mut self.subplots {
+                        subplot.datasets.clear();
+                    }
+                }
+
+                ui.separator();
+
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("↶ Undo"))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("↷ Redo"))
+                    .on_hover_text("Ctrl+Y / Ctrl+Shift+Z")
+                    .clicked()
+                {
+                    self.redo();
+                }
+
+                // Toggle for history window
+                if ui.button("🕘 History").clicked() {
+                    self.show_history = !self.show_history;
+                }
+
+                if ui.button("🔍 Commands").on_hover_text("Search all actions (Ctrl+P)").clicked() {
+                    self.command_palette.toggle();
+                }
+
+                ui.separator();
+
+                // Global controls, each change an undo step of its own
+                if let Some((mut show_grid, mut show_legend)) =
+                    self.get_active_subplot().map(|subplot| (subplot.config.show_grid, subplot.config.show_legend))
+                {
+                    if ui.checkbox(&mut show_grid, "Grid").changed() {
+                        self.edit_active_config("Toggle grid", |config| config.show_grid = show_grid);
+                    }
+                    if ui.checkbox(&mut show_legend, "Legend").changed() {
+                        self.edit_active_config("Toggle legend", |config| config.show_legend = show_legend);
+                    }
+                }
+                ui.toggle_value(&mut self.show_crosshair, "✛ Crosshair")
+                    .on_hover_text("Read off the data point nearest to the mouse");
+
+                // Toggle for subplot layout controls
+                if ui.button("🔲 Subplots").clicked() {
+                    self.show_subplot_controls = !self.show_subplot_controls;
+                }
+
+                // Toggle for axis controls window
+                if ui.button("⚙ Axis Controls").clicked() {
+                    self.show_axis_controls = !self.show_axis_controls;
+                }
+
+                // Toggle for data manipulation window
+                if ui.button("📊 Data Processing").clicked() {
+                    self.show_data_manipulation = !self.show_data_manipulation;
+                }
+
+                if ui.button("📐 Compare").on_hover_text("RMSD and correlation between two datasets").clicked() {
+                    self.show_comparison = !self.show_comparison;
+                }
+
+                // Toggle for color picker window
+                if ui.button("🎨 Colors").clicked() {
+                    self.show_color_picker = !self.show_color_picker;
+                }
+
+                // Toggle for legend controls window
+                if ui.button("📝 Legend & Fonts").clicked() {
+                    self.show_legend_controls = !self.show_legend_controls;
+                }
+
+                // Toggle for annotations window
+                if ui.button("📌 Annotations").clicked() {
+                    self.show_annotations = !self.show_annotations;
+                }
+
+                if ui.button("📊 Data Editor").clicked() {
+                    self.data_editor.show_editor = !self.data_editor.show_editor;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Dark Mode:");
+// Variable declaration
+                    let switch_size = egui::vec2(40.0, 20.0);
+// Variable declaration
+                    let (rect, response) =
+                        ui.allocate_exact_size(switch_size, egui::Sense::click());
+                    if response.clicked() {
+                        self.dark_mode = !self.dark_mode;
+                    }
+
+// Variable declaration
+                    let bg_color = if self.dark_mode {
+                        egui::Color32::from_rgb(0, 120, 215)
+                    } else {
+                        egui::Color32::from_rgb(200, 200, 200)
+                    };
+
+                    ui.painter()
+                        .rect_filled(rect, switch_size.y * 0.5, bg_color);
+
+// Variable declaration
+                    let handle_radius = switch_size.y * 0.4;
+// Variable declaration
+                    let handle_center = if self.dark_mode {
+                        egui::pos2(rect.max.x - handle_radius * 1.2, rect.center().y)
+                    } else {
+                        egui::pos2(rect.min.x + handle_radius * 1.2, rect.center().y)
+                    };
+
+                    ui.painter()
+                        .circle_filled(handle_center, handle_radius, egui::Color32::WHITE);
+                });
+
+                ui.separator();
+                if ui.button("➕ New empty dataset").on_hover_text("Type points into the Data Editor or click them onto the plot").clicked() {
+                    self.new_empty_dataset();
+                }
+                // Add-point mode, while it is on
+// Variable declaration
+                let adding_to = self.add_point_target.and_then(|(subplot_index, dataset_index)| {
+                    self.subplots.get(subplot_index)?.datasets.get(dataset_index).map(|dataset| dataset.name.clone())
+                });
+                if let Some(name) = adding_to {
+                    if ui.button(format!("⏹ Stop adding points to {}", name)).clicked() {
+                        self.add_point_target = None;
+                    }
+                }
+                if ui.button("Add random").clicked() {
+// Variable declaration
+                    let mut rng = rand::rng();
+// Variable declaration
+                    let mut pts = Vec::new();
+// Variable declaration
+                    let n = 120usize;
+                    for i in 0..n {
+// Variable declaration
+                        let x = i as f64 / n as f64 * 10.0;
+// Variable declaration
+                        let y = rng.random_range(-2.0..2.0);
+                        pts.push([x, y]);
+                    }
+// Variable declaration
+                    let name = format!("random{}", self.next_name_index);
+                    self.next_name_index += 1;
+// Variable declaration
+                    let color = self.next_color(self.active_subplot);
+                    self.record_history("Add random dataset");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        subplot.datasets.push(Dataset::new(name, pts, color));
+                    }
+                }
+            });
+        });
+
+        // Subplot controls window
+        if self.show_subplot_controls {
+            egui::Window::new("Subplot Layout")
+                .resizable(true)
+                .default_width(350.0)
+                .default_height(400.0)
+                .show(ctx, |ui| {
+                    ui.heading("Layout Configuration");
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.label("Layout:");
+                        egui::ComboBox::from_label("")
+                            .selected_text(self.subplot_layout.label())
+                            .show_ui(ui, |ui| {
+// Variable declaration
+                                let current = self.subplot_layout.preset();
+                                for preset in LayoutPreset::ALL {
+                                    if ui.selectable_label(current == Some(preset), preset.to_string()).clicked() {
+                                        self.set_layout(preset.into());
+                                    }
+                                }
+                            });
+                    });
+                    ui.add_space(6.0);
+                    self.show_grid_editor(ui);
+                    if !self.hidden_subplots.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.small(format!(
+                                "{} subplot(s) hidden by a smaller layout; they return when it grows.",
+                                self.hidden_subplots.len()
+                            ));
+                            if ui.small_button("Discard").clicked() {
+                                self.record_history("Discard hidden subplots");
+                                self.hidden_subplots.clear();
+                            }
+                        });
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.heading("Active Subplot");
+
+                    ui.horizontal(|ui| {
+                        ui.label("Active:");
+                        for (i, _) in self.subplots.iter().enumerate() {
+                            if ui.selectable_label(self.active_subplot == i, format!("{}", i + 1)).clicked() {
+                                self.active_subplot = i;
+                            }
+                        }
+                    });
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    // Subplot titles
+                    ui.heading("Subplot Titles");
+                    for (i, subplot) in self.subplots.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Subplot {}:", i + 1));
+                            ui.text_edit_singleline(&mut subplot.config.title);
+                        });
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.small("Changes to layout will reorganize your data. Active subplot receives new data and operations.");
+                });
+        }
+
+        // Data editor window
+        if self.data_editor.show_editor {
+            if let Some(subplot) = self.get_active_subplot() {
+// Variable declaration
+                let mut datasets = subplot.datasets.clone();
+                self.data_editor.fit_x_range = self.processing_x_range();
+                self.data_editor.palette = self.subplot_palette(self.active_subplot);
+                self.data_editor.show_data_editor_window(ctx, &mut datasets);
+
+                // Only write back real edits so cached display data stays valid
+                if self.get_active_subplot().is_some_and(|s| s.datasets != datasets) {
+                    self.record_history_coalescing("Edit data");
+                    if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                        subplot_mut.datasets = datasets;
+                    }
+                }
+            }
+
+            if let Some(residuals) = self.data_editor.pending_residuals.take() {
+                self.add_residuals_below(residuals);
+            }
+            if std::mem::take(&mut self.data_editor.report_requested) {
+                self.export_fit_report();
+            }
+            match self.data_editor.pending_notification.take() {
+                Some(Ok(message)) => self.notifications.success(message),
+                Some(Err(message)) => self.notifications.error(message),
+                None => {}
+            }
+            if let Some((dataset, point)) = self.data_editor.pending_pick.take() {
+                self.picked_point = Some((self.active_subplot, dataset, point));
+            }
+        }
+
+        self.show_point_inspector_window(ctx);
+
+        // Show other control windows
+        self.show_control_windows(ctx);
+
+        // Status bar: the active subplot and its data, what the mouse points at and how the
+        // last operation went
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("Subplot {} of {}", self.active_subplot + 1, self.subplots.len()));
+                if let Some(subplot) = self.get_active_subplot() {
+                    ui.separator();
+                    ui.label(format!("{} dataset(s)", subplot.datasets.len()));
+                    ui.separator();
+                    ui.label(format!(
+                        "{} point(s)",
+                        subplot.datasets.iter().map(|dataset| dataset.points.len()).sum::<usize>()
+                    ));
+                }
+                ui.separator();
+                match &self.cursor_readout {
+                    Some(readout) => ui.monospace(readout.status_text()),
+                    None => ui.weak("Move the mouse over a plot to read off values"),
+                };
+                if let Some((level, text)) = self.notifications.last() {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.colored_label(level.color(ui.visuals().dark_mode), text);
+                    });
+                }
+            });
+        });
+
+        // One spinner per file still being read or written
+        if !self.pending_loads.is_empty() || !self.exports.is_empty() {
+            egui::TopBottomPanel::bottom("background_tasks").show(ctx, |ui| {
+                for load in &self.pending_loads {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!(
+                            "Loading {} into subplot {}",
+                            load.path.file_name().unwrap_or_default().to_string_lossy(),
+                            load.subplot_index + 1
+                        ));
+                    });
+                }
+                for job in &self.exports {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!(
+                            "Exporting {} ({:.0} s)",
+                            job.path.file_name().unwrap_or_default().to_string_lossy(),
+                            job.elapsed_seconds()
+                        ));
+                    });
+                }
+            });
+        }
+
+        // Main plot area with subplots
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Multi-plot area – pan with mouse, zoom with scroll");
+            ui.add_space(6.0);
+
+            // Share the rest of the panel between the rows and columns by their weights, so the
+            // plots follow the window size. Sizes are in points, which egui already scales on
+            // HiDPI displays.
+// Variable declaration
+            let (rows, cols) = self.subplot_layout.dimensions();
+// Variable declaration
+            let origin = ui.cursor().min;
+// Variable declaration
+            let available = ui.available_size();
+// Variable declaration
+            let cell_rects = self.subplot_layout.cell_rects(
+                &self.subplot_layout.row_sizes((available.y - SUBPLOT_SPACING * (rows - 1) as f32) as f64),
+                &self.subplot_layout.col_sizes((available.x - SUBPLOT_SPACING * (cols - 1) as f32) as f64),
+                SUBPLOT_SPACING as f64,
+            );
+
+            self.subplot_rects.clear();
+            self.display_cache.begin_frame();
+// Variable declaration
+            let previous_readout = self.cursor_readout.take();
+
+            // Place each subplot in its cell; content that does not fit is clipped rather than
+            // drawn over the neighbouring cells
+            for (subplot_index, [left, top, width, height]) in cell_rects.into_iter().enumerate().take(self.subplots.len()) {
+// Variable declaration
+                let cell = egui::Rect::from_min_size(
+                    origin + egui::vec2(left as f32, top as f32),
+                    egui::vec2(width as f32, height as f32),
+                );
+// Variable declaration
+                let is_active = subplot_index == self.active_subplot;
+// Variable declaration
+                let rect = ui
+                    .allocate_ui_at_rect(cell, |ui| {
+                        ui.set_clip_rect(cell.intersect(ui.clip_rect()));
+                        self.render_subplot(ui, subplot_index, is_active, cell.size())
+                    })
+                    .response
+                    .rect;
+                self.subplot_rects.push(rect);
+            }
+            // The status bar was drawn before the plots; show the new readout next frame
+            if self.cursor_readout != previous_readout {
+                ctx.request_repaint();
+            }
+        });
+
+        // Record in-place settings edits (titles, labels, axis ranges, ...) unless this
+        // frame already touched the history
+// Variable declaration
+        let configs_changed = configs_before.len() == self.subplots.len()
+            && configs_before
+                .iter()
+                .zip(&self.subplots)
+                .any(|(before, subplot)| *before != subplot.config);
+        if configs_changed && self.history.generation() == history_generation {
+// Variable declaration
+            let label = "Edit plot settings";
+// Variable declaration
+            let mut history = std::mem::take(&mut self.history);
+            history.record_coalescing(label, || {
+// Variable declaration
+                let mut snapshot = self.snapshot(label);
+                for (subplot, config) in snapshot.subplots.iter_mut().zip(configs_before) {
+                    subplot.config = config;
+                }
+                snapshot
+            });
+            self.history = history;
+        }
+
+        self.diagnostics.show(ctx);
+        self.notifications.show(ctx);
+    }
+}
+
+/// Implementation block defining methods for this type
+impl PlotterApp {
+    // Highlight the drop target while files hover the window, then load them on drop
+/// Function: explain its purpose and key arguments
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let (hovering, dropped, pointer) = ctx.input(|i| {
+            (
+                !i.raw.hovered_files.is_empty(),
+                i.raw
+                    .dropped_files
+                    .iter()
+                    .filter_map(|file| file.path.clone())
+                    .collect::<Vec<_>>(),
+                i.pointer.latest_pos(),
+            )
+        });
+
+        // Subplot under the pointer, falling back to the active one
+// Variable declaration
+        let target = pointer
+            .and_then(|pos| self.subplot_rects.iter().position(|rect| rect.contains(pos)))
+            .unwrap_or(self.active_subplot);
+
+        if hovering {
+// Variable declaration
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("file_drop_overlay"),
+            ));
+// Variable declaration
+            let area = self
+                .subplot_rects
+                .get(target)
+                .copied()
+                .unwrap_or_else(|| ctx.screen_rect());
+            painter.rect_filled(area, 4.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                area.center(),
+                egui::Align2::CENTER_CENTER,
+                format!("Drop files to load into subplot {}", target + 1),
+                egui::FontId::proportional(18.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        if !dropped.is_empty() {
+            self.active_subplot = target.min(self.subplots.len().saturating_sub(1));
+            self.load_files_into_subplot(dropped, self.active_subplot);
+        }
+    }
+
+    // Datasets for data piped on standard input, added to the active subplot
+/// Function: explain its purpose and key arguments
+    pub fn add_stdin_datasets(&mut self, loaded: LoadedFile) {
+// Variable declaration
+        let first_color = self.get_active_subplot().map_or(0, |s| s.datasets.len());
+// Variable declaration
+        let palette = self.subplot_palette(self.active_subplot);
+        if let Some(subplot) = self.get_active_subplot_mut() {
+            loaded.metadata.apply_to(&mut subplot.config);
+// Variable declaration
+            let mut datasets = datasets_from_series(loaded.series, "stdin", palette, first_color);
+            set_sources(&mut datasets, Path::new(STDIN_PATH), None);
+            subplot.datasets.extend(datasets);
+        }
+    }
+
+    // Add the lines that arrived on standard input since the last frame
+/// Function: explain its purpose and key arguments
+    fn poll_stdin(&mut self) {
+// Variable declaration
+        let Some(stream) = self.stdin_stream.as_mut() else {
+            return;
+        };
+// Variable declaration
+        let update = stream.poll();
+// Variable declaration
+        let finished = stream.finished;
+        match update {
+            Ok(Some(StreamUpdate::Started(loaded))) => self.add_stdin_datasets(loaded),
+            Ok(Some(StreamUpdate::Appended(series))) => {
+                for dataset in self.subplots.iter_mut().flat_map(|subplot| subplot.datasets.iter_mut()) {
+// Variable declaration
+                    let Some(index) = dataset.source.as_ref().filter(|s| s.is_stdin()).map(|s| s.series) else {
+                        continue;
+                    };
+                    if let Some(series) = series.get(index) {
+                        dataset.extend_points(
+                            &series.points,
+                            series.x_err.as_deref(),
+                            series.y_err.as_deref(),
+                            series.color_values.as_deref(),
+                        );
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                self.notifications.error(format!("Could not read standard input: {}", e));
+                self.stdin_stream = None;
+                return;
+            }
+        }
+        if finished {
+            self.stdin_stream = None;
+        }
+    }
+
+    // Keep the file watcher in step with the datasets that follow their file and read the
+    // files that changed. Reloads are not undo steps: the file is what the dataset shows.
+/// Function: explain its purpose and key arguments
+    fn reload_followed_files(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let followed: HashSet<PathBuf> = self
+            .subplots
+            .iter()
+            .flat_map(|subplot| subplot.datasets.iter())
+            .filter(|dataset| dataset.follow)
+            .filter_map(|dataset| dataset.source.as_ref())
+            .filter(|source| !source.is_stdin())
+            .map(|source| source.path.clone())
+            .collect();
+        if followed.is_empty() && self.file_watcher.is_none() {
+            return;
+        }
+
+        if self.file_watcher.is_none() {
+            match FileWatcher::new(ctx.clone()) {
+                Ok(watcher) => self.file_watcher = Some(watcher),
+                Err(e) => {
+                    // Stop following so this is not retried every frame
+                    for dataset in self.subplots.iter_mut().flat_map(|subplot| subplot.datasets.iter_mut()) {
+                        dataset.follow = false;
+                    }
+                    self.notifications.error(format!("Cannot watch files: {}", e));
+                    return;
+                }
+            }
+        }
+// Variable declaration
+        let Some(watcher) = self.file_watcher.as_mut() else {
+            return;
+        };
+        if let Err(e) = watcher.set_files(&followed) {
+            self.notifications.error(format!("Cannot watch files: {}", e));
+        }
+        // Forget read positions of files nothing follows any more
+        self.tail_readers.retain(|reader| followed.contains(&reader.path));
+// Variable declaration
+        let changed = watcher.changed_files();
+        if changed.is_empty() {
+            return;
+        }
+
+        // Each file is read once, however many datasets come from it
+// Variable declaration
+        let mut updates: Vec<(PathBuf, Option<TextImportSettings>, Result<TailUpdate, String>)> = Vec::new();
+        for source in self
+            .subplots
+            .iter()
+            .flat_map(|subplot| subplot.datasets.iter())
+            .filter(|dataset| dataset.follow)
+            .filter_map(|dataset| dataset.source.as_ref())
+            .filter(|source| changed.contains(&source.path))
+        {
+            if updates
+                .iter()
+                .any(|(path, settings, _)| *path == source.path && *settings == source.text_import)
+            {
+                continue;
+            }
+// Variable declaration
+            let reader = self
+                .tail_readers
+                .iter_mut()
+                .find(|reader| reader.path == source.path && reader.text_import == source.text_import);
+// Variable declaration
+            let update = match reader {
+                Some(reader) => reader.read_new(),
+                None if TailReader::can_tail(&source.path, source.text_import.as_ref()) => {
+                    TailReader::open(&source.path, source.text_import.as_ref()).map(|(reader, series)| {
+                        self.tail_readers.push(reader);
+                        TailUpdate::Replaced(series)
+                    })
+                }
+                None => read_source(source).map(TailUpdate::Replaced),
+            };
+            updates.push((source.path.clone(), source.text_import.clone(), update.map_err(|e| e.to_string())));
+        }
+
+        for dataset in self.subplots.iter_mut().flat_map(|subplot| subplot.datasets.iter_mut()) {
+// Variable declaration
+            let Some(source) = dataset.source.clone() else {
+                continue;
+            };
+            if !dataset.follow {
+                continue;
+            }
+            // A file that could not be read keeps its previous points until the next change
+// Variable declaration
+            let update = updates
+                .iter()
+                .find(|(path, settings, _)| *path == source.path && *settings == source.text_import)
+                .and_then(|(_, _, update)| update.as_ref().ok());
+            match update {
+                Some(TailUpdate::Replaced(series)) => {
+                    if let Some(series) = series.get(source.series) {
+                        dataset.set_points(series.points.clone());
+                        dataset.x_err = series.x_err.clone();
+                        dataset.y_err = series.y_err.clone();
+                        if series.color_values.is_some() {
+                            dataset.color_values = series.color_values.clone();
+                        }
+                        dataset.discard_mismatched_errors();
+                    }
+                }
+                Some(TailUpdate::Appended(series)) => {
+                    if let Some(series) = series.get(source.series) {
+                        dataset.extend_points(
+                            &series.points,
+                            series.x_err.as_deref(),
+                            series.y_err.as_deref(),
+                            series.color_values.as_deref(),
+                        );
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if let Some((path, _, Err(e))) = updates.iter().find(|(_, _, update)| update.is_err()) {
+            self.notifications.error(format!("Could not reload {}: {}", file_display_name(path), e));
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn render_subplot(&mut self, ui: &mut egui::Ui, subplot_index: usize, is_active: bool, cell_size: egui::Vec2) {
+        // Get subplot data first to avoid borrowing conflicts
+// Variable declaration
+        let subplot_title = if let Some(subplot) = self.subplots.get(subplot_index) {
+            if !subplot.config.title.is_empty() {
+                format!("Subplot {}: {}", subplot_index + 1, subplot.config.title)
+            } else {
+                format!("Subplot {}", subplot_index + 1)
+            }
+        } else {
+            return;
+        };
+
+// Variable declaration
+        let subplot_datasets: Vec<(String, [u8; 3], Option<bool>, bool)> =
+            if let Some(subplot) = self.subplots.get(subplot_index) {
+                subplot
+                    .datasets
+                    .iter()
+                    .map(|ds| {
+                        (
+                            ds.name.clone(),
+                            ds.color,
+                            ds.source.as_ref().filter(|source| !source.is_stdin()).map(|_| ds.follow),
+                            ds.visible,
+                        )
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+// Variable declaration
+        let subplot_heatmaps: Vec<(String, String, Colormap)> = self
+            .subplots
+            .get(subplot_index)
+            .map(|subplot| {
+                subplot
+                    .heatmaps
+                    .iter()
+                    .map(|h| (h.name.clone(), h.label.clone(), h.colormap))
+                    .collect()
+            })
+            .unwrap_or_default();
+// Variable declaration
+        let heatmap_images = self.heatmap_images(ui.ctx(), subplot_index);
+
+        ui.vertical(|ui| {
+            // Subplot header with selection
+            ui.horizontal(|ui| {
+                if ui.selectable_label(is_active, &subplot_title).clicked() {
+                    self.active_subplot = subplot_index;
+                }
+
+                if is_active {
+                    ui.colored_label(egui::Color32::from_rgb(0, 200, 0), " (Active)");
+                }
+
+                if ui
+                    .small_button("⧉")
+                    .on_hover_text("Duplicate this subplot into the next empty one (adding a row if none is left)")
+                    .clicked()
+                {
+                    self.duplicate_subplot(subplot_index);
+                }
+                if ui
+                    .small_button("💾")
+                    .on_hover_text("Export this subplot as PNG, PDF or a matplotlib script")
+                    .clicked()
+                {
+                    if let Some(subplot) = self.subplots.get(subplot_index) {
+                        match export_single_subplot_as_figure(
+                            subplot,
+                            subplot_index + 1,
+                            self.dark_mode,
+                            &self.tick_font_size,
+                            &self.export_settings,
+                        ) {
+                            Ok(()) => self.notifications.success("Subplot exported successfully!"),
+                            Err(e) => self.notifications.error(format!("Failed to export subplot: {}", e)),
+                        }
+                    }
+                }
+                if ui
+                    .small_button("📋")
+                    .on_hover_text("Copy this subplot to the clipboard as an image")
+                    .clicked()
+                {
+                    if let Some(subplot) = self.subplots.get(subplot_index) {
+// Variable declaration
+                        let result = render_single_subplot_image(
+                            subplot,
+                            subplot_index + 1,
+                            self.dark_mode,
+                            &self.tick_font_size,
+                            &self.export_settings,
+                        )
+                        .and_then(|img| copy_image_to_clipboard(&img));
+                        match result {
+                            Ok(()) => self.notifications.success("Subplot copied to clipboard"),
+                            Err(e) => self.notifications.error(format!("Failed to copy subplot: {}", e)),
+                        }
+                    }
+                }
+                if ui
+                    .small_button("📄")
+                    .on_hover_text("Copy the data in view as tab-separated text")
+                    .clicked()
+                {
+                    self.copy_visible_data(subplot_index);
+                }
+            });
+
+            // Dataset list for this subplot
+            ui.horizontal(|ui| {
+                ui.vertical(|ui| {
+                    ui.set_width(DATASET_LIST_WIDTH);
+                    ui.label("Datasets:");
+
+// Variable declaration
+                    let mut remove_index: Option<usize> = None;
+// Variable declaration
+                    let mut toggle_follow: Option<usize> = None;
+// Variable declaration
+                    let mut toggle_visible: Option<usize> = None;
+// Variable declaration
+                    let mut swap: Option<(usize, usize)> = None;
+// Variable declaration
+                    let mut move_to: Option<(usize, usize)> = None; // (dataset, target subplot)
+// Variable declaration
+                    let mut copy_to: Option<(usize, usize)> = None;
+// Variable declaration
+                    let mut copy_index: Option<usize> = None;
+// Variable declaration
+                    let subplot_count = self.subplots.len();
+                    for (i, (name, color, follow, visible)) in subplot_datasets.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .selectable_label(*visible, "👁")
+                                .on_hover_text("Show in the plot and export")
+                                .clicked()
+                            {
+                                toggle_visible = Some(i);
+                            }
+
+                            // Clickable color square
+// Variable declaration
+                            let color_size = egui::vec2(12.0, 12.0);
+// Variable declaration
+                            let egui_color = egui::Color32::from_rgb(color[0], color[1], color[2]);
+
+                            if ui
+                                .add(egui::Button::new("").fill(egui_color).min_size(color_size))
+                                .on_hover_text("Click to change color")
+                                .clicked()
+                            {
+                                self.selected_dataset_for_color = i;
+                                self.active_subplot = subplot_index;
+                                self.show_color_picker = true;
+                            }
+
+                            if *visible {
+                                ui.label(name);
+                            } else {
+                                ui.weak(name);
+                            }
+                            // Only datasets loaded from a file can follow it
+                            if let Some(follow) = follow {
+                                if ui
+                                    .selectable_label(*follow, "⟳")
+                                    .on_hover_text("Follow file: reload when it changes")
+                                    .clicked()
+                                {
+                                    toggle_follow = Some(i);
+                                }
+                            }
+                            // Draw order: later datasets are drawn on top
+                            if ui.add_enabled(i > 0, egui::Button::new("⏶").small()).on_hover_text("Move up").clicked() {
+                                swap = Some((i - 1, i));
+                            }
+                            if ui
+                                .add_enabled(i + 1 < subplot_datasets.len(), egui::Button::new("⏷").small())
+                                .on_hover_text("Move down")
+                                .clicked()
+                            {
+                                swap = Some((i, i + 1));
+                            }
+                            if subplot_count > 1 {
+                                ui.menu_button("➡", |ui| {
+                                    for target in (0..subplot_count).filter(|&target| target != subplot_index) {
+                                        if ui.button(format!("Move to subplot {}", target + 1)).clicked() {
+                                            move_to = Some((i, target));
+                                            ui.close_menu();
+                                        }
+                                    }
+                                    ui.separator();
+                                    for target in (0..subplot_count).filter(|&target| target != subplot_index) {
+                                        if ui.button(format!("Copy to subplot {}", target + 1)).clicked() {
+                                            copy_to = Some((i, target));
+                                            ui.close_menu();
+                                        }
+                                    }
+                                })
+                                .response
+                                .on_hover_text("Move or copy to another subplot");
+                            }
+                            if ui.small_button("📋").on_hover_text("Copy data as tab-separated text").clicked() {
+                                copy_index = Some(i);
+                            }
+                            if ui.small_button("×").clicked() {
+                                remove_index = Some(i);
+                                self.active_subplot = subplot_index;
+                            }
+                        });
+                    }
+
+                    if let Some((a, b)) = swap {
+                        self.record_history("Reorder datasets");
+                        if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+                            subplot.datasets.swap(a, b);
+                        }
+                        self.forget_picked_point(subplot_index);
+                    }
+
+                    if let Some((index, target)) = move_to {
+                        self.move_dataset(subplot_index, index, target);
+                    }
+
+                    if let Some((index, target)) = copy_to {
+                        self.copy_dataset(subplot_index, index, target);
+                    }
+
+                    if let Some(index) = copy_index {
+// Variable declaration
+                        let tsv = self
+                            .subplots
+                            .get(subplot_index)
+                            .and_then(|subplot| subplot.datasets.get(index))
+                            .map(|ds| series_to_tsv(&[(ds.name.as_str(), ds.points.to_vec())]));
+                        if let Some(tsv) = tsv {
+                            match copy_text_to_clipboard(tsv) {
+                                Ok(()) => self.notifications.success("Dataset copied to clipboard"),
+                                Err(e) => self.notifications.error(format!("Failed to copy dataset: {}", e)),
+                            }
+                        }
+                    }
+
+                    if let Some(index) = toggle_visible {
+                        self.record_history("Toggle visibility");
+                        if let Some(dataset) = self
+                            .subplots
+                            .get_mut(subplot_index)
+                            .and_then(|subplot| subplot.datasets.get_mut(index))
+                        {
+                            dataset.visible = !dataset.visible;
+                        }
+                    }
+
+                    if let Some(index) = toggle_follow {
+                        if let Some(dataset) = self
+                            .subplots
+                            .get_mut(subplot_index)
+                            .and_then(|subplot| subplot.datasets.get_mut(index))
+                        {
+                            dataset.follow = !dataset.follow;
+                            // Catch up with changes made while the dataset was not following
+                            if dataset.follow {
+                                if let Some(series) = dataset
+                                    .source
+                                    .as_ref()
+                                    .and_then(|source| read_source(source).ok()?.into_iter().nth(source.series))
+                                {
+                                    dataset.set_points(series.points);
+                                    dataset.x_err = series.x_err;
+                                    dataset.y_err = series.y_err;
+                                }
+                            }
+                        }
+                    }
+
+                    // Apply removal after iteration
+                    if let Some(remove_idx) = remove_index {
+                        self.record_history("Remove dataset");
+                        if let Some(subplot_mut) = self.subplots.get_mut(subplot_index) {
+                            subplot_mut.datasets.remove(remove_idx);
+                        }
+                    }
+
+                    if !subplot_heatmaps.is_empty() {
+                        ui.label("Heatmaps:");
+                    }
+// Variable declaration
+                    let mut remove_heatmap: Option<usize> = None;
+// Variable declaration
+                    let mut next_colormap: Option<usize> = None;
+                    for (i, (name, label, colormap)) in subplot_heatmaps.iter().enumerate() {
+                        ui.horizontal(|ui| {
+// Variable declaration
+                            let [r, g, b] = colormap.sample(0.5);
+                            if ui
+                                .add(
+                                    egui::Button::new("")
+                                        .fill(egui::Color32::from_rgb(r, g, b))
+                                        .min_size(egui::vec2(12.0, 12.0)),
+                                )
+                                .on_hover_text(format!("Colormap: {} (click for the next one)", colormap.to_string()))
+                                .clicked()
+                            {
+                                next_colormap = Some(i);
+                            }
+                            if label.is_empty() {
+                                ui.label(name);
+                            } else {
+                                ui.label(name).on_hover_text(label);
+                            }
+                            if ui.small_button("×").clicked() {
+                                remove_heatmap = Some(i);
+                                self.active_subplot = subplot_index;
+                            }
+                        });
+                    }
+                    if let Some(index) = next_colormap {
+                        self.record_history("Change heatmap colormap");
+                        if let Some(heatmap) =
+                            self.subplots.get_mut(subplot_index).and_then(|subplot| subplot.heatmaps.get_mut(index))
+                        {
+// Variable declaration
+                            let position = Colormap::ALL.iter().position(|c| *c == heatmap.colormap).unwrap_or(0);
+                            heatmap.colormap = Colormap::ALL[(position + 1) % Colormap::ALL.len()];
+                        }
+                    }
+                    if let Some(index) = remove_heatmap {
+                        self.record_history("Remove heatmap");
+                        if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+                            subplot.heatmaps.remove(index);
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                // Plot area
+                ui.vertical(|ui| {
+// Variable declaration
+                    let egui::Vec2 { x: plot_width, y: plot_height } = plot_size_in_cell(ui, cell_size);
+
+// Variable declaration
+                    let mut clicked_at = None;
+// Variable declaration
+                    let mut cursor_readout = None;
+// Variable declaration
+                    let mut picked_at = None;
+// Variable declaration
+                    let mut view_x_range = None;
+// Variable declaration
+                    let mut view_bounds = None;
+// Variable declaration
+                    let mut selection_drag = None;
+// Variable declaration
+                    let mut inset_edit = None;
+// Variable declaration
+                    let mut placement_delta: Option<egui::Vec2> = None;
+                    if let Some(subplot) = self.subplots.get(subplot_index) {
+// Variable declaration
+                        let x_scale = subplot.config.x_axis();
+// Variable declaration
+                        let y_scale = subplot.config.y_axis();
+                        // Hidden datasets are left out, stacked datasets are drawn on top of each
+                        // other, and datasets on the right axis are moved into the coordinates of
+                        // the left one. `shown` maps drawn datasets back to the subplot's.
+// Variable declaration
+                        let mut datasets = visible_datasets(&subplot.datasets);
+// Variable declaration
+                        let shown: Vec<usize> = (0..subplot.datasets.len()).filter(|&i| subplot.datasets[i].visible).collect();
+                        stacked_datasets(&mut datasets);
+// Variable declaration
+                        let secondary = secondary_axis(&datasets, &subplot.config);
+                        place_secondary_datasets(&mut datasets, secondary, y_scale);
+                        // egui_plot's legend only shows a single column inside the plot; other
+                        // layouts are drawn by hand, outside the plot in a strip taken from its width
+// Variable declaration
+                        let hand_drawn_legend = subplot.config.show_legend
+                            && (subplot.config.legend_columns > 1 || subplot.config.legend_position.corner().is_none());
+// Variable declaration
+                        let legend_entries: Vec<(String, egui::Color32)> = datasets
+                            .iter()
+                            .filter(|ds| hand_drawn_legend && !ds.name.is_empty())
+                            .map(|ds| (ds.name.clone(), egui::Color32::from_rgb(ds.color[0], ds.color[1], ds.color[2])))
+                            .collect();
+// Variable declaration
+                        let legend_strip = if subplot.config.legend_position == LegendPosition::OutsideRight
+                            && !legend_entries.is_empty()
+                        {
+                            layout_plot_legend(ui, &legend_entries, &subplot.config).3.x + LEGEND_PADDING * 2.0
+                        } else {
+                            0.0
+                        };
+// Variable declaration
+                        let inset_grab = self.inset_grab.filter(|(i, _)| *i == subplot_index).map(|(_, grab)| grab);
+// Variable declaration
+                        // Reversing an axis mirrors its plot coordinates, so each direction keeps its own view
+// Variable declaration
+                        let mut plot = Plot::new(format!("plot_{}_{}{}", subplot_index, x_scale.reversed, y_scale.reversed))
+                            .height(plot_height)
+                            .width((plot_width - legend_strip).max(plot_width * 0.3))
+                            .show_axes([true, true])
+                            .show_grid([subplot.config.show_grid, subplot.config.show_grid])
+                            .grid_spacing(egui::Rangef::new(subplot.config.grid_spacing, 300f32.max(subplot.config.grid_spacing + 1.0)))
+                            .allow_drag(!self.x_selection_mode && inset_grab.is_none()) // Dragging selects or moves the inset region instead of panning
+                            .show_x(!self.show_crosshair) // The crosshair has its own readout
+                            .show_y(!self.show_crosshair);
+                        if subplot.config.equal_aspect {
+                            plot = plot.data_aspect(1.0);
+                        }
+
+                        // The right axis only reserves space here; its tick labels are painted
+                        // after the plot (see draw_secondary_axis_labels)
+                        if secondary.is_some() {
+                            plot = plot.custom_y_axes(vec![
+                                AxisHints::new_y(),
+                                AxisHints::new_y()
+                                    .placement(HPlacement::Right)
+                                    .label(subplot.config.y2_label.clone())
+                                    .formatter(|_, _, _| String::new()),
+                            ]);
+                        }
+
+                        plot = with_axis_scales(plot, &subplot.config);
+                        if x_scale != AxisScale::LINEAR || y_scale != AxisScale::LINEAR {
+                            plot = plot.label_formatter(move |name, value| {
+// Variable declaration
+                                let coordinates = format!(
+                                    "x = {}\ny = {}",
+                                    x_scale.format_value(x_scale.invert(value.x)),
+                                    y_scale.format_value(y_scale.invert(value.y))
+                                );
+                                if name.is_empty() {
+                                    coordinates
+                                } else {
+                                    format!("{}\n{}", name, coordinates)
+                                }
+                            });
+                        }
+
+                        // Apply custom bounds if configured (entered as data values)
+                        if subplot.config.use_custom_bounds {
+                            if let (Some(min_x), Some(max_x)) = (
+                                subplot.config.custom_x_min.parse::<f64>().ok().and_then(|v| x_scale.apply(v)),
+                                subplot.config.custom_x_max.parse::<f64>().ok().and_then(|v| x_scale.apply(v)),
+                            ) {
+                                if let (Some(min_y), Some(max_y)) = (
+                                    subplot.config.custom_y_min.parse::<f64>().ok().and_then(|v| y_scale.apply(v)),
+                                    subplot.config.custom_y_max.parse::<f64>().ok().and_then(|v| y_scale.apply(v)),
+                                ) {
+// Variable declaration
+                                    let x_range = max_x - min_x;
+// Variable declaration
+                                    let y_range = max_y - min_y;
+// Variable declaration
+                                    let x_padding =
+                                        x_range * (subplot.config.x_padding_percent / 100.0);
+// Variable declaration
+                                    let y_padding =
+                                        y_range * (subplot.config.y_padding_percent / 100.0);
+
+                                    plot = plot
+                                        .include_x(min_x - x_padding)
+                                        .include_x(max_x + x_padding)
+                                        .include_y(min_y - y_padding)
+                                        .include_y(max_y + y_padding);
+                                }
+                            }
+                        } else {
+                            if !x_scale.is_log() && !y_scale.is_log() {
+                                for heatmap in &subplot.heatmaps {
+// Variable declaration
+                                    let Some((min_x, max_x, min_y, max_y)) = scaled_heatmap_bounds(heatmap, x_scale, y_scale) else {
+                                        continue;
+                                    };
+                                    plot = plot.include_x(min_x).include_x(max_x).include_y(min_y).include_y(max_y);
+                                }
+                            }
+                            // FIXED: Automatically include data bounds when custom bounds are not set
+                            if !subplot.datasets.is_empty() {
+                                if let Some((min_x, max_x, min_y, max_y)) =
+                                    get_scaled_data_bounds(&datasets, x_scale, y_scale)
+                                        .filter(|(min_x, max_x, min_y, max_y)| min_x <= max_x && min_y <= max_y)
+                                {
+                                    // Add some padding (5% by default)
+// Variable declaration
+                                    let x_range = max_x - min_x;
+// Variable declaration
+                                    let y_range = max_y - min_y;
+
+                                    // Handle case where range is zero (single point or constant values)
+// Variable declaration
+                                    let x_padding =
+                                        if x_range > 0.0 { x_range * 0.05 } else { 1.0 };
+// Variable declaration
+                                    let y_padding =
+                                        if y_range > 0.0 { y_range * 0.05 } else { 1.0 };
+
+                                    plot = plot
+                                        .include_x(min_x - x_padding)
+                                        .include_x(max_x + x_padding)
+                                        .include_y(min_y - y_padding)
+                                        .include_y(max_y + y_padding);
+                                }
+                            }
+                        }
+
+                        if !subplot.config.x_label.is_empty() {
+                            plot = plot.x_axis_label(subplot.config.x_label.clone());
+                        }
+                        if !subplot.config.y_label.is_empty() {
+                            plot = plot.y_axis_label(subplot.config.y_label.clone());
+                        }
+
+                        // Single-column legend inside the plot, styled through the plot's text style and frame stroke
+                        if let Some(corner) = subplot.config.legend_position.corner().filter(|_| subplot.config.show_legend && !hand_drawn_legend) {
+                            ui.style_mut().text_styles.insert(
+                                egui::TextStyle::Name(LEGEND_TEXT_STYLE.into()),
+                                egui::FontId::proportional(subplot.config.legend_font_size),
+                            );
+                            if !subplot.config.legend_border {
+                                ui.style_mut().visuals.window_stroke = egui::Stroke::NONE;
+                            }
+                            plot = plot.legend(
+                                Legend::default()
+                                    .position(corner)
+                                    .background_alpha(subplot.config.legend_opacity)
+                                    .text_style(egui::TextStyle::Name(LEGEND_TEXT_STYLE.into())),
+                            );
+                        }
+
+// Variable declaration
+                        let newest_bounds = if subplot.config.scroll_to_newest && !subplot.config.use_custom_bounds {
+                            newest_data_bounds(&datasets, x_scale, y_scale, subplot.config.scroll_window)
+                        } else {
+                            None
+                        };
+
+// Variable declaration
+                        let colorbars: Vec<(Colormap, (f64, f64))> = subplot
+                            .heatmaps
+                            .iter()
+                            .filter_map(|heatmap| Some((heatmap.colormap, heatmap.value_range()?)))
+                            .chain(datasets.iter().filter_map(|ds| Some((ds.colormap, ds.color_range()?))))
+                            .collect();
+// Variable declaration
+                        let budget = self.downsample_display.then_some(self.max_display_points);
+// Variable declaration
+                        let inset_region = subplot.config.inset.and_then(|inset| inset.plot_region(x_scale, y_scale));
+// Variable declaration
+                        let inset_color = ui.visuals().strong_text_color();
+// Variable declaration
+                        let crosshair_color = ui.visuals().weak_text_color();
+// Variable declaration
+                        let highlight_color = ui.visuals().selection.stroke.color;
+// Variable declaration
+                        let plot_response = plot.show(ui, |plot_ui| {
+                            if let Some((min_x, max_x, min_y, max_y)) = newest_bounds {
+                                plot_ui.set_plot_bounds(PlotBounds::from_min_max([min_x, min_y], [max_x, max_y]));
+                            }
+                            draw_plot_items(
+                                plot_ui,
+                                &datasets,
+                                &heatmap_images,
+                                (x_scale, y_scale),
+                                &mut self.display_cache,
+                                budget,
+                            );
+                            for annotation in &subplot.annotations {
+                                draw_annotation(plot_ui, annotation, x_scale, y_scale);
+                            }
+                            // Start of an arrow that is waiting for its tip
+                            if let Some((_, tail)) = self.pending_arrow_tail.filter(|(i, _)| *i == subplot_index) {
+                                if let Some(tail) = scale_point(&tail, x_scale, y_scale) {
+                                    plot_ui.points(
+                                        Points::new(PlotPoints::new(vec![tail]))
+                                            .shape(MarkerShape::Cross)
+                                            .radius(5.0)
+                                            .color(egui::Color32::from_rgb(
+                                                self.annotation_color[0],
+                                                self.annotation_color[1],
+                                                self.annotation_color[2],
+                                            )),
+                                    );
+                                }
+                            }
+
+                            // Edges of the selected X range
+                            if let Some((_, range)) = self.x_selection.filter(|(i, _)| *i == subplot_index) {
+                                for x in range.iter().filter_map(|x| x_scale.apply(*x)) {
+                                    plot_ui.vline(
+                                        VLine::new(x)
+                                            .color(egui::Color32::from_rgb(100, 150, 255))
+                                            .width(1.5)
+                                            .style(LineStyle::dashed_loose()),
+                                    );
+                                }
+                            }
+
+                            // Outline of the region the inset zooms into. Pressing the mouse on it
+                            // grabs it, so the drag that follows moves the region (or resizes it from
+                            // the corner handle) instead of panning the plot.
+// Variable declaration
+                            let mut grab_started = None;
+// Variable declaration
+                            let mut region_delta = None;
+                            if let Some((x0, x1, y0, y1)) = inset_region {
+                                plot_ui.line(
+                                    Line::new(PlotPoints::new(vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1], [x0, y0]]))
+                                        .color(inset_color)
+                                        .width(1.0),
+                                );
+// Variable declaration
+                                let transform = *plot_ui.transform();
+// Variable declaration
+                                let handle = transform.position_from_point(&PlotPoint::new(x1, y0));
+// Variable declaration
+                                let region = egui::Rect::from_two_pos(transform.position_from_point(&PlotPoint::new(x0, y1)), handle);
+                                plot_ui.points(
+                                    Points::new(PlotPoints::new(vec![[x1, y0]]))
+                                        .shape(MarkerShape::Square)
+                                        .radius(3.0)
+                                        .color(inset_color),
+                                );
+// Variable declaration
+                                let grab_at = |pos: egui::Pos2| {
+                                    if pos.distance(handle) <= INSET_HANDLE_RADIUS {
+                                        Some(InsetGrab::Resize)
+                                    } else if region.contains(pos) {
+                                        Some(InsetGrab::Move)
+                                    } else {
+                                        None
+                                    }
+                                };
+// Variable declaration
+                                let (hover, pressed) = plot_ui.ctx().input(|i| (i.pointer.hover_pos(), i.pointer.primary_pressed()));
+// Variable declaration
+                                let hovered = hover.filter(|_| plot_ui.response().hovered()).and_then(grab_at);
+                                if self.annotation_tool.is_none() && !self.x_selection_mode {
+                                    match inset_grab.or(hovered) {
+                                        Some(InsetGrab::Resize) => plot_ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeNwSe),
+                                        Some(InsetGrab::Move) => plot_ui.ctx().set_cursor_icon(egui::CursorIcon::Grab),
+                                        None => {}
+                                    }
+                                    if pressed {
+                                        grab_started = hovered;
+                                    }
+                                }
+                                if inset_grab.is_some() && plot_ui.response().dragged() {
+// Variable declaration
+                                    let delta = plot_ui.pointer_coordinate_drag_delta();
+                                    region_delta = Some([delta.x as f64, delta.y as f64]);
+                                }
+                            }
+
+// Variable declaration
+                            let adding_points = self.add_point_target.is_some_and(|(index, _)| index == subplot_index);
+// Variable declaration
+                            let clicked = if (self.annotation_tool.is_some() || adding_points) && plot_ui.response().clicked() {
+                                plot_ui.pointer_coordinate()
+                            } else {
+                                None
+                            };
+                            if adding_points && plot_ui.response().hovered() {
+                                plot_ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
+                            }
+                            // (is drag start, pointer X) while a selection is being dragged out
+// Variable declaration
+                            let response = plot_ui.response();
+// Variable declaration
+                            let dragged_to = if !self.x_selection_mode {
+                                None
+                            } else if response.drag_started() {
+                                plot_ui.pointer_coordinate().map(|p| (true, p.x))
+                            } else if response.dragged() {
+                                plot_ui.pointer_coordinate().map(|p| (false, p.x))
+                            } else {
+                                None
+                            };
+                            // A plain click picks the point under the mouse, or clears the pick
+// Variable declaration
+                            let pick_clicked =
+                                self.annotation_tool.is_none() && !adding_points && !self.x_selection_mode && response.clicked();
+
+                            // The mouse position is always read off for the status bar; the crosshair
+                            // also snaps to the nearest point and draws lines through it, or through
+                            // the mouse itself when there is no point to snap to
+// Variable declaration
+                            let readout = plot_ui.pointer_coordinate().map(|pointer| {
+// Variable declaration
+                                let nearest = if self.show_crosshair {
+// Variable declaration
+                                    let [dx, dy] = plot_ui.transform().dpos_dvalue();
+                                    nearest_point(
+                                        &datasets,
+                                        (x_scale, y_scale),
+                                        [pointer.x, pointer.y],
+                                        [1.0 / dx.abs(), 1.0 / dy.abs()],
+                                        f64::INFINITY,
+                                    )
+                                } else {
+                                    None
+                                };
+                                if self.show_crosshair {
+// Variable declaration
+                                    let snapped = nearest
+                                        .and_then(|(i, j)| scale_point(&datasets[i].points.point(j), x_scale, y_scale))
+                                        .unwrap_or([pointer.x, pointer.y]);
+                                    plot_ui.vline(VLine::new(snapped[0]).color(crosshair_color).width(1.0));
+                                    plot_ui.hline(HLine::new(snapped[1]).color(crosshair_color).width(1.0));
+                                    if let Some((i, _)) = nearest {
+// Variable declaration
+                                        let [r, g, b] = datasets[i].color;
+                                        plot_ui.points(
+                                            Points::new(PlotPoints::new(vec![snapped]))
+                                                .shape(MarkerShape::Circle)
+                                                .radius(6.0)
+                                                .filled(false)
+                                                .color(egui::Color32::from_rgb(r, g, b)),
+                                        );
+                                    }
+                                }
+                                CursorReadout {
+                                    subplot: subplot_index,
+                                    cursor: [x_scale.invert(pointer.x), y_scale.invert(pointer.y)],
+                                    nearest: nearest.map(|(i, j)| {
+                                        (subplot.datasets[shown[i]].name.clone(), subplot.datasets[shown[i]].points.point(j))
+                                    }),
+                                    scales: (x_scale, y_scale),
+                                }
+                            });
+
+// Variable declaration
+                            let picked = if pick_clicked {
+                                plot_ui.pointer_coordinate().map(|pointer| {
+// Variable declaration
+                                    let [dx, dy] = plot_ui.transform().dpos_dvalue();
+                                    nearest_point(
+                                        &datasets,
+                                        (x_scale, y_scale),
+                                        [pointer.x, pointer.y],
+                                        [1.0 / dx.abs(), 1.0 / dy.abs()],
+                                        PICK_RADIUS,
+                                    )
+                                    .map(|(i, j)| (shown[i], j))
+                                })
+                            } else {
+                                None
+                            };
+                            if let Some((_, d, p)) = self.picked_point.filter(|(i, _, _)| *i == subplot_index) {
+                                if let Some(point) = shown
+                                    .iter()
+                                    .position(|&i| i == d)
+                                    .and_then(|i| datasets.get(i))
+                                    .and_then(|ds| ds.points.get(p))
+                                    .and_then(|point| scale_point(&point, x_scale, y_scale))
+                                {
+                                    plot_ui.points(
+                                        Points::new(PlotPoints::new(vec![point]))
+                                            .shape(MarkerShape::Circle)
+                                            .radius(8.0)
+                                            .filled(false)
+                                            .color(highlight_color),
+                                    );
+                                }
+                            }
+                            (clicked, dragged_to, readout, picked, grab_started, region_delta)
+                        });
+                        draw_colorbars(ui, plot_response.response.rect, &colorbars);
+                        if let Some(axis) = secondary {
+                            draw_secondary_axis_labels(ui, plot_response.response.rect, &plot_response.transform, axis, &subplot.config);
+                        }
+                        if !legend_entries.is_empty() {
+// Variable declaration
+                            let plot_rect = plot_response.response.rect;
+// Variable declaration
+                            let area = if legend_strip > 0.0 {
+// Variable declaration
+                                let strip = egui::Rect::from_min_max(
+                                    plot_rect.right_top(),
+                                    egui::pos2(plot_rect.right() + legend_strip, plot_rect.bottom()),
+                                );
+                                ui.allocate_rect(strip, egui::Sense::hover());
+                                strip
+                            } else {
+                                *plot_response.transform.frame()
+                            };
+                            draw_plot_legend(ui, area, &legend_entries, &subplot.config);
+                        }
+
+                        // Zoom inset over the plot, drawn from the same datasets; dragging it moves
+                        // it around the plot area
+// Variable declaration
+                        let frame = *plot_response.transform.frame();
+                        if let (Some(inset), Some((x0, x1, y0, y1))) = (subplot.config.inset, inset_region) {
+// Variable declaration
+                            let (left, top, width, height) = inset.rect_in((
+                                frame.left() as f64,
+                                frame.top() as f64,
+                                frame.width() as f64,
+                                frame.height() as f64,
+                            ));
+// Variable declaration
+                            let rect = egui::Rect::from_min_size(
+                                egui::pos2(left as f32, top as f32),
+                                egui::vec2(width as f32, height as f32),
+                            );
+// Variable declaration
+                            let mut inset_ui = ui.child_ui(rect, *ui.layout());
+// Variable declaration
+                            let inset_plot = Plot::new(format!("inset_{}", subplot_index))
+                                .width(rect.width())
+                                .height(rect.height())
+                                .show_grid([subplot.config.show_grid, subplot.config.show_grid])
+                                .grid_spacing(egui::Rangef::new(subplot.config.grid_spacing, 300f32.max(subplot.config.grid_spacing + 1.0)))
+                                .allow_drag(false)
+                                .allow_zoom(false)
+                                .allow_scroll(false)
+                                .allow_boxed_zoom(false)
+                                .allow_double_click_reset(false)
+                                .show_x(false)
+                                .show_y(false);
+// Variable declaration
+                            let inset_response = with_axis_scales(inset_plot, &subplot.config).show(&mut inset_ui, |plot_ui| {
+                                plot_ui.set_plot_bounds(PlotBounds::from_min_max([x0, y0], [x1, y1]));
+                                draw_plot_items(plot_ui, &datasets, &heatmap_images, (x_scale, y_scale), &mut self.display_cache, budget);
+                            });
+                            if inset_response.response.dragged() {
+                                placement_delta = Some(inset_response.response.drag_delta() / frame.size());
+                            }
+                        }
+
+// Variable declaration
+                        let (clicked, dragged_to, readout, picked, grab_started, region_delta) = plot_response.inner;
+                        // The grab lasts until the mouse button is released
+                        if let Some(grab) = grab_started {
+                            self.inset_grab = Some((subplot_index, grab));
+                        } else if inset_grab.is_some() && !plot_response.response.is_pointer_button_down_on() {
+                            self.inset_grab = None;
+                        }
+                        if let (Some(grab), Some(delta), Some(mut inset)) = (inset_grab, region_delta, subplot.config.inset) {
+                            inset.drag(grab, delta, x_scale, y_scale);
+                            inset_edit = Some(inset);
+                        }
+                        picked_at = picked;
+                        if let Some((name, [x, y])) = readout.as_ref().and_then(|r| r.nearest.as_ref()) {
+                            egui::show_tooltip_at_pointer(ui.ctx(), egui::Id::new(("crosshair", subplot_index)), |ui| {
+                                ui.strong(name);
+                                ui.label(format!("x = {}\ny = {}", x_scale.format_value(*x), y_scale.format_value(*y)));
+                            });
+                        }
+                        cursor_readout = readout;
+// Variable declaration
+                        let view = plot_response.transform.bounds();
+// Variable declaration
+                        let (first, last) = (x_scale.invert(view.min()[0]), x_scale.invert(view.max()[0]));
+                        view_x_range = Some((first.min(last), first.max(last)));
+                        view_bounds = Some((view.min()[0], view.max()[0], view.min()[1], view.max()[1]));
+                        clicked_at = clicked.map(|p| [x_scale.invert(p.x), y_scale.invert(p.y)]);
+                        selection_drag = dragged_to.map(|(started, x)| (started, x_scale.invert(x)));
+                    }
+
+                    if cursor_readout.is_some() {
+                        self.cursor_readout = cursor_readout;
+                    }
+                    if let Some(range) = view_x_range {
+                        self.view_x_ranges.insert(subplot_index, range);
+                    }
+                    // Exports reproduce what is on screen
+                    if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+                        subplot.view_bounds = view_bounds.or(subplot.view_bounds);
+                        if inset_edit.is_some() {
+                            subplot.config.inset = inset_edit;
+                        }
+                        if let (Some(inset), Some(delta)) = (subplot.config.inset.as_mut(), placement_delta) {
+                            inset.placement[0] += delta.x;
+                            inset.placement[1] += delta.y;
+                            inset.clamp_placement();
+                        }
+                    }
+                    if let Some(point) = clicked_at {
+                        if self.add_point_target.is_some_and(|(index, _)| index == subplot_index) {
+                            self.add_clicked_point(point);
+                        } else {
+                            self.place_annotation(subplot_index, point);
+                        }
+                    }
+                    if let Some(picked) = picked_at {
+                        self.pick_point(subplot_index, picked);
+                    }
+                    match selection_drag {
+                        Some((true, x)) => {
+                            self.active_subplot = subplot_index;
+                            self.x_selection = Some((subplot_index, [x, x]));
+                        }
+                        Some((false, x)) => {
+                            if let Some((_, range)) =
+                                self.x_selection.as_mut().filter(|(i, _)| *i == subplot_index)
+                            {
+                                range[1] = x;
+                            }
+                        }
+                        None => {}
+                    }
+                });
+            });
+        });
+    }
+
+/// Function: explain its purpose and key arguments
+    fn show_control_windows(&mut self, ctx: &egui::Context) {
+        // Axis controls window
+        if self.show_axis_controls {
+// Variable declaration
+            let mut unit_conversion = None;
+            egui::Window::new("Axis Controls")
+                .resizable(true)
+                .default_width(400.0)
+                .default_height(300.0)
+                .show(ctx, |ui| {
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        // Axis titles
+                        ui.group(|ui| {
+                            ui.label("Axis Titles");
+                            ui.horizontal(|ui| {
+                                ui.label("X label:");
+                                ui.text_edit_singleline(&mut subplot.config.x_label);
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Y label:");
+                                ui.text_edit_singleline(&mut subplot.config.y_label);
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Right Y label:");
+                                ui.text_edit_singleline(&mut subplot.config.y2_label);
+                            });
+                        });
+
+                        ui.separator();
+
+                        // Converting rescales the data and switches the unit in the axis title
+                        ui.group(|ui| {
+                            ui.label("Units");
+                            if let Some(unit) = axis_unit_ui(ui, "X:", "x_unit_combo", &mut subplot.config.x_unit) {
+                                unit_conversion = Some((0, unit));
+                            }
+                            if let Some(unit) = axis_unit_ui(ui, "Y:", "y_unit_combo", &mut subplot.config.y_unit) {
+                                unit_conversion = Some((1, unit));
+                            }
+                        });
+
+                        ui.separator();
+
+                        // Axis scales
+                        ui.group(|ui| {
+                            ui.label("Axis Scale");
+                            ui.horizontal(|ui| {
+                                ui.label("X:");
+                                egui::ComboBox::from_id_source("x_scale_combo")
+                                    .selected_text(subplot.config.x_scale.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for option in ScaleKind::ALL {
+                                            ui.selectable_value(&mut subplot.config.x_scale, option, option.to_string());
+                                        }
+                                    });
+                                ui.label("Y:");
+                                egui::ComboBox::from_id_source("y_scale_combo")
+                                    .selected_text(subplot.config.y_scale.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for option in ScaleKind::ALL {
+                                            ui.selectable_value(&mut subplot.config.y_scale, option, option.to_string());
+                                        }
+                                    });
+                            });
+                            if subplot.config.x_scale.is_log() || subplot.config.y_scale.is_log() {
+                                ui.small("Points at or below zero are not shown on log axes.");
+                            }
+                            if subplot.config.x_scale.is_time() || subplot.config.y_scale.is_time() {
+                                ui.small("Date/time axes read values as seconds since 1970-01-01 and show them in UTC.");
+                            }
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut subplot.config.reverse_x, "Reverse X");
+                                ui.checkbox(&mut subplot.config.reverse_y, "Reverse Y");
+                                ui.checkbox(&mut subplot.config.equal_aspect, "Equal aspect (1:1)")
+                                    .on_hover_text("One unit spans the same length on both axes, also while zooming and in exports");
+                            });
+                        });
+
+                        ui.separator();
+
+                        // Grid lines follow the axis grid marks; minor ones are those too close to be labelled
+                        ui.group(|ui| {
+                            ui.label("Grid");
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut subplot.config.show_grid, "Show grid");
+                                ui.checkbox(&mut subplot.config.grid_minor, "Minor lines")
+                                    .on_hover_text("Lines between the labelled ones");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Min spacing:");
+                                ui.add(egui::Slider::new(&mut subplot.config.grid_spacing, 4.0..=100.0).suffix(" pt"))
+                                    .on_hover_text("Lines closer together than this are left out");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Export style:");
+                                egui::ComboBox::from_id_source("grid_style_combo")
+                                    .selected_text(subplot.config.grid_style.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for style in [LinePattern::Solid, LinePattern::Dashed, LinePattern::Dotted] {
+                                            ui.selectable_value(&mut subplot.config.grid_style, style, style.to_string());
+                                        }
+                                    });
+                                ui.label("Opacity:");
+                                ui.add(egui::Slider::new(&mut subplot.config.grid_opacity, 0.0..=1.0));
+                            });
+                        });
+
+                        ui.separator();
+
+                        // Datasets are put on the right axis in the Dataset Colors window
+                        ui.group(|ui| {
+                            ui.label("Right Y-Axis");
+                            if !subplot.datasets.iter().any(|ds| ds.secondary_y) {
+                                ui.small("No dataset uses the right axis (see Dataset Colors).");
+                            }
+                            ui.checkbox(&mut subplot.config.use_custom_y2_bounds, "Custom range");
+                            if subplot.config.use_custom_y2_bounds {
+                                ui.horizontal(|ui| {
+                                    ui.label("Min:");
+                                    ui.text_edit_singleline(&mut subplot.config.custom_y2_min);
+                                    ui.label("Max:");
+                                    ui.text_edit_singleline(&mut subplot.config.custom_y2_max);
+                                });
+                            }
+                        });
+
+                        ui.separator();
+
+                        // The region and the inset can also be dragged on the plot itself
+                        ui.group(|ui| {
+                            ui.label("Zoom Inset");
+// Variable declaration
+                            let mut show_inset = subplot.config.inset.is_some();
+                            if ui.checkbox(&mut show_inset, "Show zoom inset").changed() {
+                                subplot.config.inset = show_inset.then(|| {
+                                    Inset::around(
+                                        subplot.view_bounds.unwrap_or((0.0, 1.0, 0.0, 1.0)),
+                                        subplot.config.x_axis(),
+                                        subplot.config.y_axis(),
+                                    )
+                                });
+                            }
+                            if let Some(inset) = subplot.config.inset.as_mut() {
+                                ui.horizontal(|ui| {
+                                    ui.label("X range:");
+                                    ui.add(egui::DragValue::new(&mut inset.x_range[0]).speed(0.01));
+                                    ui.add(egui::DragValue::new(&mut inset.x_range[1]).speed(0.01));
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Y range:");
+                                    ui.add(egui::DragValue::new(&mut inset.y_range[0]).speed(0.01));
+                                    ui.add(egui::DragValue::new(&mut inset.y_range[1]).speed(0.01));
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Width:");
+                                    ui.add(egui::Slider::new(&mut inset.placement[2], MIN_PLACEMENT_SIZE..=1.0));
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Height:");
+                                    ui.add(egui::Slider::new(&mut inset.placement[3], MIN_PLACEMENT_SIZE..=1.0));
+                                });
+                                inset.clamp_placement();
+                                ui.small("Drag the outlined region on the plot to move it, or its corner to resize it; drag the inset to move it.");
+                            }
+                        });
+
+                        ui.separator();
+
+                        // Mostly useful with datasets that follow a growing file
+                        ui.checkbox(&mut subplot.config.scroll_to_newest, "Scroll X-axis to newest data");
+                        if subplot.config.scroll_to_newest {
+                            ui.horizontal(|ui| {
+                                ui.label("Window width:");
+                                ui.add(
+                                    egui::DragValue::new(&mut subplot.config.scroll_window)
+                                        .speed(1.0)
+                                        .clamp_range(0.0..=f64::MAX),
+                                );
+                                ui.small("(0 = all data)");
+                            });
+                        }
+
+                        ui.separator();
+
+                        ui.checkbox(
+                            &mut subplot.config.use_custom_bounds,
+                            "Override Automatic Axis Ranges",
+                        );
+
+                        if subplot.config.use_custom_bounds {
+                            ui.separator();
+
+                            // Auto-fill button
+                            if ui.button("Auto-fill from data").clicked() {
+                                if let Some((min_x, max_x, min_y, max_y)) =
+                                    get_data_bounds(&subplot.datasets)
+                                {
+                                    subplot.config.custom_x_min = min_x.to_string();
+                                    subplot.config.custom_x_max = max_x.to_string();
+                                    subplot.config.custom_y_min = format!("{:.3}", min_y);
+                                    subplot.config.custom_y_max = format!("{:.3}", max_y);
+                                }
+                            }
+
+                            ui.separator();
+
+                            // X-axis controls
+                            ui.group(|ui| {
+                                ui.label("X-Axis Range");
+                                ui.horizontal(|ui| {
+                                    ui.label("Min:");
+                                    ui.text_edit_singleline(&mut subplot.config.custom_x_min);
+                                    ui.label("Max:");
+                                    ui.text_edit_singleline(&mut subplot.config.custom_x_max);
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Padding:");
+                                    ui.add(
+                                        egui::Slider::new(
+                                            &mut subplot.config.x_padding_percent,
+                                            0.0..=20.0,
+                                        )
+                                        .suffix("%"),
+                                    );
+                                });
+
+                                ui.checkbox(
+                                    &mut subplot.config.use_custom_x_ticks,
+                                    "Custom X-axis ticks",
+                                );
+                                if subplot.config.use_custom_x_ticks {
+                                    ui.label("X-axis tick values (comma-separated):");
+                                    ui.text_edit_multiline(&mut subplot.config.custom_x_ticks);
+                                    ui.small("Example: 0, 250, 500");
+                                }
+                            });
+
+                            ui.separator();
+
+                            // Y-axis controls
+                            ui.group(|ui| {
+                                ui.label("Y-Axis Range");
+                                ui.horizontal(|ui| {
+                                    ui.label("Min:");
+                                    ui.text_edit_singleline(&mut subplot.config.custom_y_min);
+                                    ui.label("Max:");
+                                    ui.text_edit_singleline(&mut subplot.config.custom_y_max);
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Padding:");
+                                    ui.add(
+                                        egui::Slider::new(
+                                            &mut subplot.config.y_padding_percent,
+                                            0.0..=20.0,
+                                        )
+                                        .suffix("%"),
+                                    );
+                                });
+
+                                ui.checkbox(
+                                    &mut subplot.config.use_custom_y_ticks,
+                                    "Custom Y-axis ticks",
+                                );
+                                if subplot.config.use_custom_y_ticks {
+                                    ui.label("Y-axis tick values (comma-separated):");
+                                    ui.text_edit_multiline(&mut subplot.config.custom_y_ticks);
+                                    ui.small("Example: 0.0, 0.5, 1.0");
+                                }
+                            });
+                        }
+                    } else {
+                        ui.label("No active subplot selected.");
+                    }
+                });
+            if let Some((axis, unit)) = unit_conversion {
+                self.convert_axis_unit(axis, unit);
+            }
+        }
+
+        // Data manipulation window (similar to before, but operates on active subplot)
+        if self.show_data_manipulation {
+            self.show_data_manipulation_window(ctx);
+        }
+
+        if self.show_comparison {
+            self.show_comparison_window(ctx);
+        }
+
+        // Color picker window (similar to before, but for active subplot)
+        if self.show_color_picker {
+            self.show_color_picker_window(ctx);
+        }
+
+        // Legend controls window
+        if self.show_legend_controls {
+            self.show_legend_controls_window(ctx);
+        }
+
+        // Undo history window
+        if self.show_history {
+            self.show_history_window(ctx);
+        }
+
+        // Annotations window
+        if self.show_annotations {
+            self.show_annotations_window(ctx);
+        }
+
+        // Export settings window
+        if self.show_export_settings {
+            self.show_export_settings_window(ctx);
+        }
+
+        // Preferences window
+        if self.show_preferences {
+            self.show_preferences_window(ctx);
+        }
+
+        // Style templates window
+        if self.show_templates {
+            self.show_templates_window(ctx);
+        }
+
+        // Sheet and column picker for opened workbooks, one at a time
+        if !self.spreadsheet_imports.is_empty() {
+            self.show_spreadsheet_import_window(ctx);
+        }
+
+        // Group/dataset browser for opened HDF5 files, one at a time
+        if !self.hdf5_imports.is_empty() {
+            self.show_hdf5_import_window(ctx);
+        }
+
+        // Preview and column mapping for opened text tables, one at a time
+        if !self.text_imports.is_empty() {
+            self.show_text_import_window(ctx);
+        }
+
+        if self.folder_import.is_some() {
+            self.show_folder_import_window(ctx);
+        }
+
+        if self.show_open_url {
+            self.show_open_url_window(ctx);
+        }
+    }
+
+    // Address of a data file on a web server; it is downloaded on a worker thread and then
+    // opened like a local file (see PendingLoad::spawn_url)
+/// Function: explain its purpose and key arguments
+    fn show_open_url_window(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let mut open = self.show_open_url;
+// Variable declaration
+        let mut fetch_now = false;
+        egui::Window::new("Open URL")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Address of a CSV, XVG, JSON or other data file:");
+// Variable declaration
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.url_input)
+                        .hint_text("https://example.org/data/rmsd.xvg")
+                        .desired_width(380.0),
+                );
+                fetch_now |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.checkbox(&mut self.url_refresh, "Download again even if fetched recently")
+                    .on_hover_text(format!(
+                        "Downloads are kept for {} minutes and reused when the same address is opened again",
+                        CACHE_MAX_AGE.as_secs() / 60
+                    ));
+// Variable declaration
+                let valid = is_url(&self.url_input);
+                if !valid && !self.url_input.trim().is_empty() {
+                    ui.small("The address has to start with http:// or https://");
+                }
+                fetch_now &= valid;
+                if ui.add_enabled(valid, egui::Button::new("Open")).clicked() {
+                    fetch_now = true;
+                }
+            });
+        if fetch_now {
+            self.pending_loads.push(PendingLoad::spawn_url(
+                self.url_input.trim().to_string(),
+                self.active_subplot,
+                self.url_refresh,
+            ));
+            open = false;
+        }
+        self.show_open_url = open;
+    }
+
+    // Pattern, recursion and subplot choice for a folder picked with Open Folder
+/// Function: explain its purpose and key arguments
+    fn show_folder_import_window(&mut self, ctx: &egui::Context) {
+        // File names listed before the rest are summed up
+        const LISTED_FILES: usize = 12;
+// Variable declaration
+        let Some(import) = &mut self.folder_import else {
+            return;
+        };
+// Variable declaration
+        let (active, subplot_count) = (self.active_subplot, self.subplots.len());
+// Variable declaration
+        let mut open = true;
+// Variable declaration
+        let mut load_now = false;
+// Variable declaration
+        let mut cancelled = false;
+        egui::Window::new("Open Folder")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(format!("Folder: {}", import.dir.display()));
+// Variable declaration
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Files matching:");
+                    changed |= ui
+                        .text_edit_singleline(&mut import.pattern)
+                        .on_hover_text("* matches any run of characters and ? any single one, e.g. *.xvg or rmsd_??.csv")
+                        .changed();
+                });
+                changed |= ui.checkbox(&mut import.recursive, "Include subfolders").changed();
+                if changed {
+                    import.refresh();
+                }
+
+                ui.separator();
+                ui.label("Put the files into:");
+                for assignment in Assignment::ALL {
+                    ui.radio_value(&mut import.assignment, assignment, assignment.label());
+                }
+                if import.assignment == Assignment::ByName {
+                    ui.horizontal(|ui| {
+                        ui.label("Groups:");
+                        ui.text_edit_singleline(&mut import.group_patterns).on_hover_text(
+                            "Comma-separated patterns, e.g. rmsd_*, gyrate_*; files matching none are grouped by the start of their name",
+                        );
+                    });
+                }
+                if import.assignment != Assignment::Active {
+                    ui.checkbox(&mut import.add_subplots, "Add subplots when needed")
+                        .on_hover_text("Otherwise files beyond the last subplot start over at the first");
+                }
+
+                ui.separator();
+                match &import.error {
+                    Some(error) => {
+                        ui.colored_label(egui::Color32::from_rgb(220, 60, 60), error);
+                    }
+                    None => {
+                        // Where the files would end up now, as loading them would place them
+// Variable declaration
+                        let plan: Vec<usize> = import
+                            .plan(active)
+                            .into_iter()
+                            .map(|i| if import.add_subplots { i } else { i % subplot_count })
+                            .collect();
+// Variable declaration
+                        let subplots_used = plan.iter().collect::<HashSet<_>>().len();
+                        ui.label(format!(
+                            "{} matching data file(s) into {} subplot(s)",
+                            import.matches.len(),
+                            subplots_used
+                        ));
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            for (path, subplot_index) in import.matches.iter().zip(&plan).take(LISTED_FILES) {
+                                ui.small(format!(
+                                    "{}  →  subplot {}",
+                                    path.strip_prefix(&import.dir).unwrap_or(path).display(),
+                                    subplot_index + 1
+                                ));
+                            }
+                            if import.matches.len() > LISTED_FILES {
+                                ui.small(format!("… and {} more", import.matches.len() - LISTED_FILES));
+                            }
+                        });
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!import.matches.is_empty(), egui::Button::new("Load")).clicked() {
+                        load_now = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if load_now {
+// Variable declaration
+            let (wanted, add_subplots) = (import.plan(active), import.add_subplots);
+// Variable declaration
+            let paths = std::mem::take(&mut import.matches);
+// Variable declaration
+            let needed = wanted.iter().max().map_or(0, |last| last + 1);
+            if add_subplots && needed > self.subplots.len() {
+                if let Some(layout) = SubplotLayout::fitting(needed) {
+                    self.set_layout(layout);
+                }
+            }
+            // Files beyond the last subplot start over at the first
+// Variable declaration
+            let subplot_count = self.subplots.len();
+            self.load_files_directly(paths, |i| wanted[i] % subplot_count);
+        }
+        if load_now || cancelled || !open {
+            self.folder_import = None;
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn show_spreadsheet_import_window(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let mut finished = false;
+// Variable declaration
+        let mut import_now = false;
+        {
+// Variable declaration
+            let import = &mut self.spreadsheet_imports[0];
+// Variable declaration
+            let title = if is_parquet(&import.path) { "Import Parquet" } else { "Import Spreadsheet" };
+            egui::Window::new(title)
+                .resizable(true)
+                .default_width(350.0)
+                .show(ctx, |ui| {
+                    ui.label(format!("File: {}", file_display_name(&import.path)));
+                    ui.separator();
+
+// Variable declaration
+                    let mut sheet = import.sheet;
+                    // Parquet files and single-sheet workbooks have nothing to choose here
+                    if import.sheets.len() > 1 {
+                        ui.horizontal(|ui| {
+                            ui.label("Sheet:");
+                            egui::ComboBox::from_id_source("spreadsheet_sheet_combo")
+                                .selected_text(import.current_sheet().map_or("", |s| s.name.as_str()))
+                                .show_ui(ui, |ui| {
+                                    for (i, table) in import.sheets.iter().enumerate() {
+                                        ui.selectable_value(&mut sheet, i, &table.name);
+                                    }
+                                });
+                        });
+                    }
+                    if sheet != import.sheet {
+                        import.select_sheet(sheet);
+                    }
+
+// Variable declaration
+                    let Some(table) = import.sheets.get(import.sheet) else {
+                        return;
+                    };
+                    if table.columns.len() < 2 {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 165, 0),
+                            "This sheet needs at least two columns (X and Y).",
+                        );
+                    } else {
+                        column_mapping_ui(ui, "spreadsheet", &table.column_labels(), &mut import.x_col, &mut import.y_cols);
+                    }
+                    ui.small(format!("{} data rows", table.row_count()));
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Import").clicked() {
+                            import_now = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            finished = true;
+                        }
+                    });
+                });
+        }
+
+        if import_now {
+// Variable declaration
+            let import = &self.spreadsheet_imports[0];
+// Variable declaration
+            let series = import.selected_series();
+            if !series.iter().any(LoadedSeries::has_values) {
+                self.notifications.warning("No numeric X/Y pairs in the selected columns");
+                return;
+            }
+            // Name after the sheet as well when the workbook has several
+// Variable declaration
+            let file_name = match (import.sheets.len() > 1, import.current_sheet()) {
+                (true, Some(sheet)) => format!("{}_{}", file_display_name(&import.path), sheet.name),
+                _ => file_display_name(&import.path),
+            };
+// Variable declaration
+            let subplot_index = import.subplot_index;
+// Variable declaration
+            let label = if is_parquet(&import.path) { "Import Parquet file" } else { "Import spreadsheet" };
+            self.import_series(series, &file_name, subplot_index, label, None);
+            finished = true;
+        }
+
+        if finished {
+            self.spreadsheet_imports.remove(0);
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn show_text_import_window(&mut self, ctx: &egui::Context) {
+        // Rows shown in the preview table
+        const PREVIEW_ROWS: usize = 10;
+// Variable declaration
+        let mut finished = false;
+// Variable declaration
+        let mut import_now = false;
+// Variable declaration
+        let mut import_heatmap = false;
+// Variable declaration
+        let mut parse_error = None;
+        {
+// Variable declaration
+            let import = &mut self.text_imports[0];
+            egui::Window::new("Import Preview")
+                .resizable(true)
+                .default_width(500.0)
+                .show(ctx, |ui| {
+                    ui.label(format!("File: {}", file_display_name(&import.path)));
+                    ui.horizontal(|ui| {
+                        ui.label("Dataset name:");
+                        ui.text_edit_singleline(&mut import.name);
+                    });
+                    ui.separator();
+
+// Variable declaration
+                    let (old_skip, old_delimiter, old_header) = (import.skip_rows, import.delimiter, import.has_header);
+                    ui.horizontal(|ui| {
+                        ui.label("Skip first lines:");
+                        ui.add(egui::DragValue::new(&mut import.skip_rows).clamp_range(0..=1000));
+                        ui.label("Delimiter:");
+                        egui::ComboBox::from_id_source("text_import_delimiter")
+                            .selected_text(import.delimiter.label())
+                            .show_ui(ui, |ui| {
+                                for delimiter in Delimiter::ALL {
+                                    ui.selectable_value(&mut import.delimiter, delimiter, delimiter.label());
+                                }
+                            });
+                        ui.checkbox(&mut import.has_header, "Header row");
+                    });
+                    // A new skip count or delimiter changes what the first row is, so guess the header again
+// Variable declaration
+                    let result = if import.skip_rows != old_skip || import.delimiter != old_delimiter {
+                        import.reparse(true)
+                    } else if import.has_header != old_header {
+                        import.reparse(false)
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(e) = result {
+                        parse_error = Some(e.to_string());
+                    }
+
+// Variable declaration
+                    let labels = import.column_labels();
+                    if labels.len() < 2 {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 165, 0),
+                            "Need at least two columns (X and Y); try another delimiter or skip count.",
+                        );
+                    } else {
+                        column_mapping_ui(ui, "text_import", &labels, &mut import.x_col, &mut import.y_cols);
+                        ui.horizontal(|ui| {
+                            ui.label("Color lines by:");
+                            egui::ComboBox::from_id_source("text_import_color_col")
+                                .selected_text(import.color_col.and_then(|col| labels.get(col)).map_or("None", String::as_str))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut import.color_col, None, "None");
+                                    for (col, label) in labels.iter().enumerate() {
+                                        ui.selectable_value(&mut import.color_col, Some(col), label);
+                                    }
+                                });
+                        });
+                    }
+
+                    ui.separator();
+                    ui.label(format!("Preview ({} data rows):", import.rows.len()));
+                    egui::ScrollArea::both()
+                        .id_source("text_import_preview")
+                        .max_height(220.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("text_import_preview_grid")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for (col, label) in labels.iter().enumerate() {
+// Variable declaration
+                                        let role = if col == import.x_col {
+                                            " (X)"
+                                        } else if import.y_cols.get(col).copied().unwrap_or(false) {
+                                            " (Y)"
+                                        } else if import.color_col == Some(col) {
+                                            " (color)"
+                                        } else {
+                                            ""
+                                        };
+                                        ui.strong(format!("{}{}", label, role));
+                                    }
+                                    ui.end_row();
+                                    for row in import.rows.iter().take(PREVIEW_ROWS) {
+                                        for col in 0..labels.len() {
+                                            ui.label(row.get(col).map_or("", String::as_str));
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(labels.len() >= 2, egui::Button::new("Import")).clicked() {
+                            import_now = true;
+                        }
+                        if ui
+                            .add_enabled(!import.rows.is_empty(), egui::Button::new("Import as Heatmap"))
+                            .on_hover_text("Every cell is a value; a numeric header row and first column give X and Y")
+                            .clicked()
+                        {
+                            import_heatmap = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            finished = true;
+                        }
+                    });
+                });
+        }
+
+        if let Some(e) = parse_error {
+            self.notifications.error(format!("Could not parse {}: {}", file_display_name(&self.text_imports[0].path), e));
+        }
+
+        if import_now {
+// Variable declaration
+            let import = &self.text_imports[0];
+// Variable declaration
+            let series = import.selected_series();
+            if !series.iter().any(LoadedSeries::has_values) {
+                self.notifications.warning("No numeric X/Y pairs in the selected columns");
+                return;
+            }
+// Variable declaration
+            let name = if import.name.trim().is_empty() {
+                file_display_name(&import.path)
+            } else {
+                import.name.trim().to_string()
+            };
+// Variable declaration
+            let source = (import.path.clone(), Some(import.settings()));
+// Variable declaration
+            let subplot_index = import.subplot_index;
+// Variable declaration
+            let metadata = import.metadata();
+            self.import_series(series, &name, subplot_index, "Import file", Some(source));
+            if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+                metadata.apply_to(&mut subplot.config);
+            }
+            finished = true;
+        }
+
+        if import_heatmap {
+// Variable declaration
+            let import = &self.text_imports[0];
+            match import.to_heatmap() {
+                Ok(heatmap) => {
+// Variable declaration
+                    let subplot_index = import.subplot_index.min(self.subplots.len() - 1);
+                    self.record_history("Import heatmap");
+                    self.notifications.success(format!("Imported {} as a heatmap", heatmap.name));
+                    self.subplots[subplot_index].heatmaps.push(heatmap);
+                    finished = true;
+                }
+                Err(e) => self.notifications.error(format!("Could not import as a heatmap: {}", e)),
+            }
+        }
+
+        if finished {
+            self.text_imports.remove(0);
+        }
+    }
+
+    // Add the table in `text` (or, when None, on the clipboard) to the active subplot
+/// Function: explain its purpose and key arguments
+    fn paste_data_as_dataset(&mut self, text: Option<String>) {
+// Variable declaration
+        let text = match text {
+            Some(text) => Ok(text),
+            None => arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()),
+        };
+        match text.map_err(|e| e.into()).and_then(|text| series_from_pasted_text(&text)) {
+            Ok(series) => self.import_series(series, "pasted", self.active_subplot, "Paste data", None),
+            Err(e) => self.notifications.error(format!("Could not paste data: {}", e)),
+        }
+    }
+
+    // Add imported series to a subplot as datasets (one undo step) and report it. With a
+    // source file (and preview settings) the datasets can later follow that file.
+/// Function: explain its purpose and key arguments
+    fn import_series(
+        &mut self,
+        series: Vec<LoadedSeries>,
+        file_name: &str,
+        subplot_index: usize,
+        label: &str,
+        source: Option<(PathBuf, Option<TextImportSettings>)>,
+    ) {
+// Variable declaration
+        let subplot_index = subplot_index.min(self.subplots.len().saturating_sub(1));
+// Variable declaration
+        let first_color = self.subplots.get(subplot_index).map_or(0, |s| s.datasets.len());
+// Variable declaration
+        let mut datasets =
+            datasets_from_series(series, file_name, self.subplot_palette(subplot_index), first_color);
+        if let Some((path, settings)) = source {
+            set_sources(&mut datasets, &path, settings);
+        }
+// Variable declaration
+        let count = datasets.len();
+        self.record_history(label);
+        if let Some(subplot) = self.subplots.get_mut(subplot_index) {
+            subplot.datasets.extend(datasets);
+        }
+        self.notifications.success(format!("Imported {} datasets from {}", count, file_name));
+    }
+
+/// Function: explain its purpose and key arguments
+    fn show_hdf5_import_window(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let mut finished = false;
+// Variable declaration
+        let mut import_now = false;
+// Variable declaration
+        let mut clicked_path = None;
+        {
+// Variable declaration
+            let import = &mut self.hdf5_imports[0];
+            egui::Window::new("Import HDF5")
+                .resizable(true)
+                .default_width(550.0)
+                .show(ctx, |ui| {
+                    ui.label(format!("File: {}", file_display_name(&import.path)));
+                    ui.separator();
+
+                    ui.columns(2, |columns| {
+                        columns[0].label("Groups and datasets:");
+                        egui::ScrollArea::vertical()
+                            .id_source("hdf5_tree_scroll")
+                            .max_height(300.0)
+                            .show(&mut columns[0], |ui| {
+                                hdf5_tree_ui(ui, &import.tree, import.table.as_ref(), 0, &mut clicked_path);
+                            });
+
+                        // Column mapping for the selected object
+// Variable declaration
+                        let ui = &mut columns[1];
+                        if let Some(error) = &import.error {
+                            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                        }
+                        match &import.table {
+                            Some(table) if table.columns.len() >= 2 => {
+                                ui.label(format!("Selected: {}", table.name));
+                                column_mapping_ui(ui, "hdf5", &table.column_labels(), &mut import.x_col, &mut import.y_cols);
+                                ui.small(format!("{} data rows", table.row_count()));
+                            }
+                            Some(_) => {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 165, 0),
+                                    "This object needs at least two columns (X and Y).",
+                                );
+                            }
+                            None => {
+                                ui.label("Select a group or dataset to plot.");
+                            }
+                        }
+                    });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(import.table.is_some(), egui::Button::new("Import"))
+                            .clicked()
+                        {
+                            import_now = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            finished = true;
+                        }
+                    });
+                });
+        }
+
+        if let Some(object_path) = clicked_path {
+            self.hdf5_imports[0].select(&object_path);
+        }
+
+        if import_now {
+// Variable declaration
+            let import = &self.hdf5_imports[0];
+// Variable declaration
+            let series = import.selected_series();
+            if !series.iter().any(LoadedSeries::has_values) {
+                self.notifications.warning("No numeric X/Y pairs in the selected columns");
+                return;
+            }
+// Variable declaration
+            let object_name = import
+                .table
+                .as_ref()
+                .map_or("", |table| table.name.rsplit('/').next().unwrap_or(""));
+            // Name after the group or dataset as well, unless it is the root group
+// Variable declaration
+            let file_name = if object_name.is_empty() {
+                file_display_name(&import.path)
+            } else {
+                format!("{}_{}", file_display_name(&import.path), object_name)
+            };
+// Variable declaration
+            let subplot_index = import.subplot_index;
+            self.import_series(series, &file_name, subplot_index, "Import HDF5", None);
+            finished = true;
+        }
+
+        if finished {
+            self.hdf5_imports.remove(0);
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn show_export_settings_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Export Settings")
+            .resizable(false)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+// Variable declaration
+                let settings = &mut self.export_settings;
+                egui::Grid::new("export_settings_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Width (px):");
+                        ui.add(egui::DragValue::new(&mut settings.width).clamp_range(400..=10_000));
+                        ui.end_row();
+
+                        ui.label("Height (px):");
+                        ui.add(egui::DragValue::new(&mut settings.height).clamp_range(300..=10_000));
+                        ui.end_row();
+
+                        ui.label("DPI:");
+                        ui.add(egui::DragValue::new(&mut settings.dpi).clamp_range(36..=2400));
+                        ui.end_row();
+
+                        ui.label("Scale factor:");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.scale)
+                                .speed(0.05)
+                                .clamp_range(0.25..=8.0),
+                        );
+                        ui.end_row();
+
+                        ui.label("Format:");
+                        egui::ComboBox::from_id_source("export_format_combo")
+                            .selected_text(settings.format.to_string())
+                            .show_ui(ui, |ui| {
+                                for format in ExportFormat::ALL {
+                                    ui.selectable_value(&mut settings.format, format, format.to_string());
+                                }
+                            });
+                        ui.end_row();
+                    });
+
+                ui.horizontal(|ui| {
+                    for dpi in [150, 300, 600] {
+                        if ui.button(format!("{} dpi", dpi)).clicked() {
+                            settings.dpi = dpi;
+                        }
+                    }
+                });
+
+                ui.separator();
+// Variable declaration
+                let (width, height) = settings.pixel_size();
+                ui.label(format!("Output: {} × {} px", width, height));
+                ui.label(format!(
+                    "Printed size: {:.2} × {:.2} in at {} dpi",
+                    width as f64 / settings.dpi as f64,
+                    height as f64 / settings.dpi as f64,
+                    settings.dpi
+                ));
+                ui.small("Applies to PNG and PDF export, single-subplot export and clipboard copies. PDF pages and script figures have the printed size.");
+
+                if ui.button("Close").clicked() {
+                    self.show_export_settings = false;
+                }
+            });
+    }
+
+    // Edits the saved preferences; Save writes the config file and applies them now
+/// Function: explain its purpose and key arguments
+    fn show_preferences_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Preferences")
+            .resizable(false)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+// Variable declaration
+                let preferences = &mut self.preferences;
+                egui::Grid::new("preferences_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Theme:");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut preferences.dark_mode, true, "Dark");
+                            ui.selectable_value(&mut preferences.dark_mode, false, "Light");
+                        });
+                        ui.end_row();
+
+                        ui.label("Font size:");
+                        egui::ComboBox::from_id_source("preferences_font_size")
+                            .selected_text(preferences.font_size.to_string())
+                            .show_ui(ui, |ui| {
+                                for size in [FontSize::Small, FontSize::Medium, FontSize::Large, FontSize::ExtraLarge] {
+// Variable declaration
+                                    let label = size.to_string();
+                                    ui.selectable_value(&mut preferences.font_size, size, label);
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Palette:");
+                        egui::ComboBox::from_id_source("preferences_palette")
+                            .selected_text(preferences.palette.to_string())
+                            .show_ui(ui, |ui| {
+                                for palette in Palette::ALL {
+                                    ui.selectable_value(&mut preferences.palette, palette, palette.to_string());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Export width (px):");
+                        ui.add(egui::DragValue::new(&mut preferences.export_width).clamp_range(400..=10_000));
+                        ui.end_row();
+
+                        ui.label("Export height (px):");
+                        ui.add(egui::DragValue::new(&mut preferences.export_height).clamp_range(300..=10_000));
+                        ui.end_row();
+
+                        ui.label("X padding (%):");
+                        ui.add(egui::DragValue::new(&mut preferences.x_padding_percent).speed(0.5).clamp_range(0.0..=50.0));
+                        ui.end_row();
+
+                        ui.label("Y padding (%):");
+                        ui.add(egui::DragValue::new(&mut preferences.y_padding_percent).speed(0.5).clamp_range(0.0..=50.0));
+                        ui.end_row();
+                    });
+                ui.small("Padding applies to subplots created from now on.");
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        match save_preferences(&self.preferences) {
+                            Ok(path) => {
+                                self.dark_mode = self.preferences.dark_mode;
+                                self.tick_font_size = self.preferences.font_size.clone();
+                                self.palette = self.preferences.palette;
+                                self.export_settings.width = self.preferences.export_width;
+                                self.export_settings.height = self.preferences.export_height;
+                                self.notifications.success(format!("Preferences saved to {}", path.display()));
+                            }
+                            Err(e) => {
+                                self.notifications.error(format!("Failed to save preferences: {}", e))
+                            }
+                        }
+                    }
+                    if ui.button("Defaults").clicked() {
+                        self.preferences = Preferences::default();
+                    }
+                    if ui.button("Close").clicked() {
+                        self.show_preferences = false;
+                    }
+                });
+            });
+    }
+
+    // Saves the current figure style under a name and applies saved ones to every subplot
+/// Function: explain its purpose and key arguments
+    fn show_templates_window(&mut self, ctx: &egui::Context) {
+        if self.templates.is_none() {
+            match load_templates() {
+                Ok(templates) => self.templates = Some(templates),
+                Err(e) => {
+                    self.templates = Some(Vec::new());
+                    self.notifications.error(format!("Failed to read templates: {}", e));
+                }
+            }
+        }
+// Variable declaration
+        let mut save_clicked = false;
+// Variable declaration
+        let mut apply: Option<StyleTemplate> = None;
+// Variable declaration
+        let mut delete: Option<String> = None;
+        egui::Window::new("Style Templates")
+            .resizable(false)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.small(
+                    "A template keeps the theme, font size, palette and export settings, and the grid, legend \
+                     and padding of the active subplot. Titles, labels and ranges are left alone.",
+                );
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.template_name).hint_text("Template name").desired_width(180.0));
+                    save_clicked = ui
+                        .add_enabled(!self.template_name.trim().is_empty(), egui::Button::new("Save current style"))
+                        .on_hover_text("Replaces a saved template of the same name")
+                        .clicked();
+                });
+
+                ui.separator();
+// Variable declaration
+                let templates = self.templates.as_deref().unwrap_or_default();
+                if templates.is_empty() {
+                    ui.weak("No saved templates yet");
+                }
+                egui::Grid::new("templates_grid").num_columns(3).striped(true).show(ui, |ui| {
+                    for template in templates {
+                        ui.label(&template.name).on_hover_text(format!(
+                            "{} theme, {} font, {} palette, {} × {} px at {} dpi",
+                            if template.dark_mode { "Dark" } else { "Light" },
+                            template.font_size.to_string(),
+                            template.palette.to_string(),
+                            template.export_settings.width,
+                            template.export_settings.height,
+                            template.export_settings.dpi
+                        ));
+                        if ui.button("Apply").on_hover_text("Restyle every subplot with this template").clicked() {
+                            apply = Some(template.clone());
+                        }
+                        if ui.small_button("🗑").on_hover_text("Delete this template").clicked() {
+                            delete = Some(template.name.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                if ui.button("Close").clicked() {
+                    self.show_templates = false;
+                }
+            });
+
+        if save_clicked {
+// Variable declaration
+            let template = StyleTemplate::from_app(&self.template_name, self);
+            match save_template(&template) {
+                Ok(path) => self.notifications.success(format!("Template saved to {}", path.display())),
+                Err(e) => self.notifications.error(format!("Failed to save template: {}", e)),
+            }
+            self.templates = None;
+        }
+        if let Some(template) = apply {
+            self.apply_template(&template);
+        }
+        if let Some(name) = delete {
+            if let Err(e) = delete_template(&name) {
+                self.notifications.error(format!("Failed to delete template: {}", e));
+            }
+            self.templates = None;
+        }
+    }
+
+    // Take over the template's theme, fonts, palette and export settings, and restyle and
+    // recolor every subplot
+/// Function: explain its purpose and key arguments
+    pub fn apply_template(&mut self, template: &StyleTemplate) {
+        self.record_history(&format!("Apply template {}", template.name));
+        self.dark_mode = template.dark_mode;
+        self.tick_font_size = template.font_size.clone();
+        self.palette = template.palette;
+        self.export_settings = template.export_settings;
+        for index in 0..self.subplots.len() {
+            template.subplot.apply_to(&mut self.subplots[index].config);
+            self.recolor_subplot(index);
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn show_annotations_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Annotations")
+            .resizable(true)
+            .default_width(350.0)
+            .default_height(350.0)
+            .show(ctx, |ui| {
+                ui.heading("Place by Clicking");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Tool:");
+                    ui.radio_value(&mut self.annotation_tool, None, "Off");
+                    for kind in AnnotationKind::ALL {
+                        ui.radio_value(&mut self.annotation_tool, Some(kind), kind.to_string());
+                    }
+                });
+                if self.annotation_tool != Some(AnnotationKind::Arrow) {
+                    self.pending_arrow_tail = None;
+                }
+                if self.annotation_tool.is_some() {
+                    self.add_point_target = None;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Text:");
+                    ui.text_edit_singleline(&mut self.annotation_text);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Color:");
+                    ui.color_edit_button_srgb(&mut self.annotation_color);
+                });
+                ui.small("Click a plot to add the annotation there. Arrows take two clicks: text end first, then the tip.");
+
+                ui.add_space(10.0);
+                ui.heading("Annotations (Active Subplot)");
+                ui.separator();
+
+// Variable declaration
+                let mut remove_index = None;
+                if let Some(subplot) = self.get_active_subplot_mut() {
+                    if subplot.annotations.is_empty() {
+                        ui.label("No annotations in active subplot.");
+                    }
+                    for (i, annotation) in subplot.annotations.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(annotation.color[0], annotation.color[1], annotation.color[2]),
+                                annotation.kind.to_string(),
+                            );
+                            ui.text_edit_singleline(&mut annotation.text);
+                            ui.label(format!(
+                                "({}, {})",
+                                format_number(annotation.position[0]),
+                                format_number(annotation.position[1])
+                            ));
+                            if ui.small_button("×").clicked() {
+                                remove_index = Some(i);
+                            }
+                        });
+                    }
+                }
+
+                // Apply removal after iteration
+                if let Some(index) = remove_index {
+                    self.record_history("Remove annotation");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        subplot.annotations.remove(index);
+                    }
+                }
+            });
+    }
+
+    // List undo/redo entries; clicking one steps the history to that point
+/// Function: explain its purpose and key arguments
+    fn show_history_window(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let mut undo_steps = 0;
+// Variable declaration
+        let mut redo_steps = 0;
+
+        egui::Window::new("History")
+            .resizable(true)
+            .default_width(250.0)
+            .default_height(300.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    // Redo entries above the current state, furthest first
+// Variable declaration
+                    let redo_labels = self.history.redo_labels();
+                    for (i, label) in redo_labels.iter().enumerate().rev() {
+                        if ui
+                            .add(egui::Label::new(egui::RichText::new(*label).weak()).sense(egui::Sense::click()))
+                            .on_hover_text("Click to redo up to here")
+                            .clicked()
+                        {
+                            redo_steps = i + 1;
+                        }
+                    }
+
+                    ui.colored_label(egui::Color32::from_rgb(0, 200, 0), "▶ Current state");
+
+                    // Undo entries below, most recent first
+                    for (i, label) in self.history.undo_labels().iter().enumerate() {
+                        if ui
+                            .add(egui::Label::new(*label).sense(egui::Sense::click()))
+                            .on_hover_text("Click to undo back to before this change")
+                            .clicked()
+                        {
+                            undo_steps = i + 1;
+                        }
+                    }
+                });
+            });
+
+        // Apply after the window closure to avoid borrowing conflicts
+        for _ in 0..undo_steps {
+            self.undo();
+        }
+        for _ in 0..redo_steps {
+            self.redo();
+        }
+    }
+
+    // Two datasets of the active subplot side by side: how far apart they are and how
+    // well they move together, for checking a model against a reference or one replica
+    // against another
+/// Function: explain its purpose and key arguments
+    fn show_comparison_window(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let dataset_names: Vec<String> = self
+            .get_active_subplot()
+            .map_or(Vec::new(), |subplot| subplot.datasets.iter().map(|d| d.name.clone()).collect());
+// Variable declaration
+        let mut open = self.show_comparison;
+// Variable declaration
+        let mut compare_clicked = false;
+// Variable declaration
+        let mut copy_clicked = false;
+// Variable declaration
+        let mut annotate_clicked = false;
+        egui::Window::new("Compare Datasets")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                if dataset_names.len() < 2 {
+                    ui.label("The active subplot needs at least two datasets to compare.");
+                    return;
+                }
+                ui.label("Dataset B is interpolated onto the X values of A; only their overlap is compared.");
+                egui::Grid::new("comparison_grid").num_columns(2).show(ui, |ui| {
+                    for (label, slot) in ["A:", "B:"].into_iter().zip(0..2) {
+                        if self.comparison_datasets[slot] >= dataset_names.len() {
+                            self.comparison_datasets[slot] = slot;
+                        }
+                        ui.label(label);
+                        egui::ComboBox::from_id_source(("comparison_combo", slot))
+                            .width(240.0)
+                            .selected_text(dataset_names[self.comparison_datasets[slot]].as_str())
+                            .show_ui(ui, |ui| {
+                                for (i, name) in dataset_names.iter().enumerate() {
+                                    ui.selectable_value(&mut self.comparison_datasets[slot], i, name);
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+                if let Some((low, high)) = self.processing_x_range() {
+                    ui.small(format!(
+                        "Restricted to the selection, x = {} to {} (see Data Processing)",
+                        format_number(low),
+                        format_number(high)
+                    ));
+                }
+                compare_clicked = ui.button("📐 Compare").clicked();
+
+                if let Some((description, metrics)) = &self.last_comparison {
+                    ui.group(|ui| {
+                        ui.label(description);
+                        egui::Grid::new("comparison_results_grid").num_columns(2).striped(true).show(ui, |ui| {
+// Variable declaration
+                            let optional = |value: Option<f64>| {
+                                value.map_or("undefined (a curve is flat)".to_string(), |value| format!("{:.4}", value))
+                            };
+                            for (name, value) in [
+                                ("Points compared", metrics.count.to_string()),
+                                (
+                                    "X overlap",
+                                    format!("{} to {}", format_number(metrics.x_min), format_number(metrics.x_max)),
+                                ),
+                                ("RMSD", format_number(metrics.rmsd)),
+                                ("Pearson r", optional(metrics.pearson)),
+                                ("Spearman ρ", optional(metrics.spearman)),
+                                (
+                                    "Max |A − B|",
+                                    format!(
+                                        "{} at x = {}",
+                                        format_number(metrics.max_deviation),
+                                        format_number(metrics.max_deviation_at[0])
+                                    ),
+                                ),
+                            ] {
+                                ui.label(name);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        copy_clicked = ui
+                            .button("📋 Copy Results")
+                            .on_hover_text("Copy the metrics at full precision as tab-separated name and value")
+                            .clicked();
+                        annotate_clicked = ui
+                            .button("📌 Add as Annotation")
+                            .on_hover_text("Label the active subplot with the metrics, where the curves are furthest apart")
+                            .clicked();
+                    });
+                }
+            });
+        self.show_comparison = open;
+
+        if compare_clicked {
+            self.compare_selected_datasets();
+        }
+        if let Some((description, metrics)) = self.last_comparison.clone() {
+            if copy_clicked {
+                match copy_text_to_clipboard(format!("{}\n{}", description, metrics.to_tsv())) {
+                    Ok(()) => self.notifications.success("Comparison copied to clipboard"),
+                    Err(e) => self.notifications.error(format!("Failed to copy comparison: {}", e)),
+                }
+            }
+            if annotate_clicked {
+// Variable declaration
+                let color = self
+                    .get_active_subplot()
+                    .and_then(|subplot| subplot.datasets.get(self.comparison_datasets[0]))
+                    .map_or([0, 0, 0], |dataset| dataset.color);
+                self.record_history("Annotate comparison");
+                if let Some(subplot) = self.get_active_subplot_mut() {
+                    subplot.annotations.push(Annotation::new(
+                        AnnotationKind::Text,
+                        metrics.max_deviation_at,
+                        metrics.summary(),
+                        color,
+                    ));
+                }
+            }
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn show_data_manipulation_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Data Processing")
+            .resizable(true)
+            .default_width(350.0)
+            .default_height(250.0)
+            .show(ctx, |ui| {
+// Variable declaration
+                let subplot_info = if let Some(subplot) = self.get_active_subplot() {
+                    if subplot.datasets.is_empty() {
+                        ui.label(
+                            "No datasets in active subplot. Load data first to enable processing.",
+                        );
+                        return;
+                    }
+// Variable declaration
+                    let dataset_names: Vec<String> =
+                        subplot.datasets.iter().map(|d| d.name.clone()).collect();
+                    Some((dataset_names, subplot.datasets.len(), subplot.id.clone()))
+                } else {
+                    ui.label("No active subplot selected.");
+                    return;
+                };
+
+// Variable declaration
+                let (dataset_names, dataset_count, subplot_id) = subplot_info.unwrap();
+
+                ui.heading("X Range Selection");
+                ui.separator();
+
+                if ui
+                    .toggle_value(&mut self.x_selection_mode, "↔ Select X range")
+                    .on_hover_text("Drag across a plot to select an X interval (panning is off while active)")
+                    .changed()
+                    && self.x_selection_mode
+                {
+                    self.annotation_tool = None;
+                    self.pending_arrow_tail = None;
+                    self.add_point_target = None;
+                }
+                if let Some((_, [start, end])) =
+                    self.x_selection.filter(|(i, _)| *i == self.active_subplot)
+                {
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "Selected: x = {} to {}",
+                            format_number(start.min(end)),
+                            format_number(start.max(end))
+                        ));
+                        if ui.small_button("Clear").clicked() {
+                            self.x_selection = None;
+                        }
+                    });
+                    ui.checkbox(
+                        &mut self.restrict_to_selection,
+                        "Apply processing, fitting and export to the selection only",
+                    );
+                } else {
+                    ui.small("No selection in the active subplot; operations use all points.");
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Rolling Average");
+                ui.separator();
+
+                // Dataset selection
+                ui.horizontal(|ui| {
+                    ui.label("Dataset:");
+                    if self.selected_dataset_for_processing < dataset_names.len() {
+                        egui::ComboBox::from_label("")
+                            .selected_text(&dataset_names[self.selected_dataset_for_processing])
+                            .show_ui(ui, |ui| {
+                                for (i, name) in dataset_names.iter().enumerate() {
+                                    ui.selectable_value(
+                                        &mut self.selected_dataset_for_processing,
+                                        i,
+                                        name,
+                                    );
+                                }
+                            });
+                    }
+                });
+
+                ui.add_space(10.0);
+
+                // Window size setting
+                ui.horizontal(|ui| {
+                    ui.label("Window size:");
+                    ui.add(
+                        egui::Slider::new(&mut self.rolling_window_size, 2..=100).text("points"),
+                    );
+                });
+
+                ui.add_space(10.0);
+
+                // Show preview info
+                if let Some(subplot) = self.get_active_subplot() {
+                    if let Some(dataset) =
+                        subplot.datasets.get(self.selected_dataset_for_processing)
+                    {
+// Variable declaration
+                        let point_count =
+                            finite_points_in_x_range(&dataset.points, self.processing_x_range()).len();
+                        ui.label(format!("Original dataset: {} points", point_count));
+
+                        if point_count >= self.rolling_window_size {
+// Variable declaration
+                            let result_points = point_count - self.rolling_window_size + 1;
+                            ui.label(format!(
+                                "Rolling average will have: {} points",
+                                result_points
+                            ));
+                        } else {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                "Warning: Window size larger than dataset!",
+                            );
+                        }
+                    }
+                }
+
+                ui.add_space(15.0);
+
+                // Compute button
+                if ui.button("🔄 Compute Rolling Average").clicked() {
+                    self.add_rolling_average_dataset();
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Smoothing");
+                ui.separator();
+
+                ui.label("Peak-preserving alternatives to the rolling average, keeping every point:");
+                ui.horizontal(|ui| {
+                    ui.label("Window:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.savitzky_golay_window)
+                            .clamp_range(3..=10_001)
+                            .speed(2.0),
+                    );
+                    ui.label("Order:");
+                    ui.add(egui::DragValue::new(&mut self.savitzky_golay_order).clamp_range(0..=10));
+                    if ui
+                        .button("〰 Savitzky–Golay")
+                        .on_hover_text("Local polynomial least-squares fit (the window must be odd)")
+                        .clicked()
+                    {
+// Variable declaration
+                        let (window, order) = (self.savitzky_golay_window, self.savitzky_golay_order);
+                        self.add_derived_dataset("Savitzky–Golay smoothing", "savgol", |points| {
+                            compute_savitzky_golay(points, window, order)
+                        });
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("σ:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.gaussian_sigma)
+                            .speed(0.01)
+                            .clamp_range(f64::MIN_POSITIVE..=f64::MAX),
+                    );
+                    if ui
+                        .button("🔔 Gaussian")
+                        .on_hover_text("Gaussian-weighted average with standard deviation σ in X units")
+                        .clicked()
+                    {
+// Variable declaration
+                        let sigma = self.gaussian_sigma;
+                        self.add_derived_dataset("Gaussian smoothing", "gaussian", |points| {
+                            compute_gaussian_smoothing(points, sigma)
+                        });
+                    }
+                });
+
+                ui.add_space(15.0);
+                ui.heading("Normalization");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("normalization_combo")
+                        .selected_text(self.normalization.to_string())
+                        .show_ui(ui, |ui| {
+                            for normalization in Normalization::ALL {
+                                ui.selectable_value(&mut self.normalization, normalization, normalization.to_string());
+                            }
+                        });
+                    ui.checkbox(&mut self.normalize_in_place, "In place")
+                        .on_hover_text("Change the selected dataset (all of its points) instead of adding a new one");
+                    if ui.button("📐 Normalize").clicked() {
+                        self.normalize_processing_dataset();
+                    }
+                });
+
+                ui.add_space(15.0);
+                ui.heading("Derivative & Integral");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("📈 Derivative (dy/dx)")
+                        .on_hover_text("Central differences")
+                        .clicked()
+                    {
+                        self.add_derived_dataset("Derivative", "derivative", compute_derivative);
+                    }
+                    if ui
+                        .button("∫ Cumulative Integral")
+                        .on_hover_text("Trapezoidal rule, starting from 0")
+                        .clicked()
+                    {
+                        self.add_derived_dataset("Integral", "integral", compute_cumulative_integral);
+                    }
+                });
+
+                ui.add_space(15.0);
+                ui.heading("Power Spectrum");
+                ui.separator();
+
+                ui.label("Power spectral density of an evenly sampled dataset (frequency in 1 / X units):");
+                ui.horizontal(|ui| {
+                    ui.label("Window:");
+                    for window in FftWindow::ALL {
+                        ui.radio_value(&mut self.fft_window, window, window.to_string());
+                    }
+                });
+                if ui
+                    .button("🎵 Compute Spectrum")
+                    .on_hover_text("FFT after removing the mean, zero-padded to a power of two")
+                    .clicked()
+                {
+// Variable declaration
+                    let window = self.fft_window;
+                    self.add_derived_dataset("Power spectrum", "psd", |points| compute_power_spectrum(points, window));
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Resample");
+                ui.separator();
+
+                ui.label("Interpolate the selected dataset onto new X values:");
+                if self.resample_onto.is_some_and(|i| i >= dataset_count) {
+                    self.resample_onto = None;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Onto:");
+                    egui::ComboBox::from_id_source("resample_onto_combo")
+                        .selected_text(match self.resample_onto {
+                            Some(i) => format!("X values of {}", dataset_names[i]),
+                            None => "Uniform grid".to_string(),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.resample_onto, None, "Uniform grid");
+                            for (i, name) in dataset_names.iter().enumerate() {
+                                ui.selectable_value(&mut self.resample_onto, Some(i), format!("X values of {}", name));
+                            }
+                        });
+                    if self.resample_onto.is_none() {
+                        ui.label("Points:");
+                        ui.add(egui::DragValue::new(&mut self.resample_count).clamp_range(2..=1_000_000));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Method:");
+                    for method in Interpolation::ALL {
+                        ui.radio_value(&mut self.resample_method, method, method.to_string());
+                    }
+                });
+                if ui.button("〽 Resample").clicked() {
+                    self.add_resampled_dataset();
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Dataset Math");
+                ui.separator();
+
+                ui.label("New dataset from the selected dataset (A) and another one (B), interpolated onto A's X values:");
+                if self.math_operand >= dataset_count {
+                    self.math_operand = 0;
+                }
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("math_operation_combo")
+                        .selected_text(self.math_operation.to_string())
+                        .show_ui(ui, |ui| {
+                            for operation in DatasetOperation::ALL {
+                                ui.selectable_value(&mut self.math_operation, operation, operation.to_string());
+                            }
+                        });
+                    ui.label("B:");
+                    egui::ComboBox::from_id_source("math_operand_combo")
+                        .selected_text(dataset_names.get(self.math_operand).map_or("", |name| name.as_str()))
+                        .show_ui(ui, |ui| {
+                            for (i, name) in dataset_names.iter().enumerate() {
+                                ui.selectable_value(&mut self.math_operand, i, name);
+                            }
+                        });
+                });
+                if self.math_operation == DatasetOperation::Expression {
+                    ui.horizontal(|ui| {
+                        ui.label("f =");
+                        ui.text_edit_singleline(&mut self.math_expression).on_hover_text(
+                            "Variables: x, y (Y of A) and b (Y of B). Example: y*4.184 + 3 or y - b",
+                        );
+                    });
+                }
+                if ui.button("🧮 Compute").clicked() {
+                    self.add_dataset_math();
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Baseline Correction");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Baseline:");
+                    for mode in BaselineMode::ALL {
+                        ui.radio_value(&mut self.baseline_mode, mode, mode.to_string());
+                    }
+                });
+                if self.baseline_mode == BaselineMode::RollingMinimum {
+                    ui.horizontal(|ui| {
+                        ui.label("Window size:");
+                        ui.add(egui::DragValue::new(&mut self.baseline_window).clamp_range(1..=1_000_000));
+                        ui.label("points");
+                    });
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.label(if self.baseline_mode == BaselineMode::Constant {
+                            "Region: x ="
+                        } else {
+                            "Anchors: x ="
+                        });
+                        ui.add(egui::DragValue::new(&mut self.baseline_anchors[0]).speed(0.1));
+                        ui.label("and");
+                        ui.add(egui::DragValue::new(&mut self.baseline_anchors[1]).speed(0.1));
+                        if let Some((_, [start, end])) =
+                            self.x_selection.filter(|(i, _)| *i == self.active_subplot)
+                        {
+                            if ui.small_button("Use selection").clicked() {
+                                self.baseline_anchors = [start.min(end), start.max(end)];
+                            }
+                        }
+                    });
+                }
+                if ui.button("📏 Subtract Baseline").clicked() {
+                    self.add_baseline_corrected_dataset();
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Histogram");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Values:");
+                    ui.radio_value(&mut self.histogram_of_x, false, "Y");
+                    ui.radio_value(&mut self.histogram_of_x, true, "X");
+                });
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.histogram_by_width, false, "Bin count:");
+                    ui.add_enabled(
+                        !self.histogram_by_width,
+                        egui::DragValue::new(&mut self.histogram_bin_count).clamp_range(1..=10_000),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.histogram_by_width, true, "Bin width:");
+                    ui.add_enabled(
+                        self.histogram_by_width,
+                        egui::DragValue::new(&mut self.histogram_bin_width)
+                            .speed(0.01)
+                            .clamp_range(f64::MIN_POSITIVE..=f64::MAX),
+                    );
+                });
+                if ui.button("📊 Compute Histogram").clicked() {
+                    self.add_histogram_dataset();
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Replica Average");
+                ui.separator();
+
+                ui.label("Mean of replicate runs with a shaded band (interpolated onto a common X grid if needed):");
+                // Indices picked in another subplot do not refer to these datasets
+                if self.replica_subplot != subplot_id {
+                    self.replica_datasets.clear();
+                    self.replica_subplot = subplot_id;
+                }
+                self.replica_datasets.retain(|&i| i < dataset_count);
+                ui.horizontal_wrapped(|ui| {
+                    for (i, name) in dataset_names.iter().enumerate() {
+// Variable declaration
+                        let mut selected = self.replica_datasets.contains(&i);
+                        if ui.checkbox(&mut selected, name).changed() {
+                            if selected {
+                                self.replica_datasets.push(i);
+                            } else {
+                                self.replica_datasets.retain(|&j| j != i);
+                            }
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Band:");
+                    for spread in [ReplicaSpread::StdDev, ReplicaSpread::StdError] {
+                        ui.radio_value(&mut self.replica_spread, spread, spread.to_string());
+                    }
+                });
+                if ui
+                    .add_enabled(self.replica_datasets.len() >= 2, egui::Button::new("〰 Average Replicas"))
+                    .clicked()
+                {
+                    self.add_replica_average();
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Distributions");
+                ui.separator();
+
+                ui.label("Compare the Y values of every dataset in the active subplot side by side:");
+                ui.horizontal(|ui| {
+                    if ui.button("📦 Box Plots").clicked() {
+                        self.show_subplot_as_distributions(PlotType::Box);
+                    }
+                    if ui
+                        .button("🎻 Violin Plots")
+                        .on_hover_text("Kernel density estimate of each dataset")
+                        .clicked()
+                    {
+                        self.show_subplot_as_distributions(PlotType::Violin);
+                    }
+                });
+
+                ui.add_space(15.0);
+                ui.heading("Peak Detection");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.peak_settings.minima, false, "Maxima");
+                    ui.radio_value(&mut self.peak_settings.minima, true, "Minima");
+                });
+                ui.horizontal(|ui| {
+// Variable declaration
+                    let mut use_height = self.peak_settings.height.is_some();
+                    ui.checkbox(
+                        &mut use_height,
+                        if self.peak_settings.minima { "Max height:" } else { "Min height:" },
+                    );
+// Variable declaration
+                    let mut height = self.peak_settings.height.unwrap_or(0.0);
+                    ui.add_enabled(use_height, egui::DragValue::new(&mut height).speed(0.01));
+                    self.peak_settings.height = use_height.then_some(height);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Min prominence:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.peak_settings.prominence)
+                            .speed(0.01)
+                            .clamp_range(0.0..=f64::MAX),
+                    );
+                    ui.label("Min distance (X):");
+                    ui.add(
+                        egui::DragValue::new(&mut self.peak_settings.distance)
+                            .speed(0.01)
+                            .clamp_range(0.0..=f64::MAX),
+                    );
+                });
+                if ui.button("⛰ Find Peaks").clicked() {
+                    self.add_peaks_dataset();
+                }
+// Variable declaration
+                let mut export_peaks = false;
+                if let Some((description, peaks)) = &self.last_peaks {
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}: {} peaks", description, peaks.len()));
+                            export_peaks = ui.small_button("💾 Export CSV").clicked();
+                        });
+                        egui::ScrollArea::vertical()
+                            .id_source("peaks_table")
+                            .max_height(150.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("peaks_grid").striped(true).show(ui, |ui| {
+                                    ui.strong("X");
+                                    ui.strong("Y");
+                                    ui.strong("Prominence");
+                                    ui.end_row();
+                                    for peak in peaks {
+                                        ui.label(format_number(peak.x));
+                                        ui.label(format_number(peak.y));
+                                        ui.label(format_number(peak.prominence));
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                    });
+                }
+                if export_peaks {
+                    self.export_peaks();
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Threshold Crossings");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Y threshold:");
+                    ui.add(egui::DragValue::new(&mut self.crossing_threshold).speed(0.01));
+                    ui.checkbox(&mut self.mark_crossings, "Mark with vertical lines");
+                    if ui.button("✂ Find Crossings").clicked() {
+                        self.find_threshold_crossings();
+                    }
+                });
+                if let Some((description, crossings)) = &self.last_crossings {
+                    ui.group(|ui| {
+                        ui.label(description);
+                        if let Some(first) = crossings.first() {
+                            ui.label(format!("First crossing at x = {}", format_number(first.x)));
+                        }
+                        egui::ScrollArea::vertical()
+                            .id_source("crossings_table")
+                            .max_height(150.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("crossings_grid").striped(true).show(ui, |ui| {
+                                    ui.strong("#");
+                                    ui.strong("X");
+                                    ui.strong("Direction");
+                                    ui.end_row();
+                                    for (i, crossing) in crossings.iter().enumerate() {
+                                        ui.label((i + 1).to_string());
+                                        ui.label(format_number(crossing.x));
+                                        ui.label(if crossing.rising { "Rising" } else { "Falling" });
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                    });
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Block Averaging");
+                ui.separator();
+
+                ui.label("Standard error of the mean Y of a correlated time series, against block size:");
+                if ui
+                    .button("🧱 Block Average")
+                    .on_hover_text("Adds the convergence curve as a new dataset")
+                    .clicked()
+                {
+                    self.add_block_average_dataset();
+                }
+                if let Some((description, result)) = &self.last_block_average {
+                    ui.group(|ui| {
+                        ui.label(description);
+                        ui.label(format!("Mean Y: {}", format_number(result.mean)));
+                        ui.label(format!("Naive standard error: {}", format_number(result.naive_sem)));
+                        ui.label(format!("Plateau standard error: {}", format_number(result.plateau_sem)));
+                        ui.label(format!(
+                            "Statistical inefficiency: {}",
+                            format_number((result.plateau_sem / result.naive_sem).powi(2))
+                        ));
+                        if !result.converged {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                "The curve has not levelled off; the series may be too short for an honest error.",
+                            );
+                        }
+                    });
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Statistics & Export");
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Σ Compute Statistics").clicked() {
+                        self.compute_dataset_statistics();
+                    }
+                    if ui
+                        .button("💾 Export Points as CSV")
+                        .on_hover_text("Writes the points of the selected dataset (within the selection, if restricted)")
+                        .clicked()
+                    {
+                        self.export_processing_points();
+                    }
+                });
+                if let Some((description, stats)) = &self.last_statistics {
+                    ui.group(|ui| {
+                        ui.label(description);
+                        ui.label(format!("Points: {}", stats.count));
+                        ui.label(format!("X range: {} to {}", format_number(stats.x_min), format_number(stats.x_max)));
+                        ui.label(format!("Mean Y: {}", format_number(stats.mean)));
+                        ui.label(format!("Std. dev. Y: {}", format_number(stats.std_dev)));
+                        ui.label(format!("Min / max Y: {} / {}", format_number(stats.min), format_number(stats.max)));
+                    });
+                }
+
+                ui.add_space(15.0);
+                ui.heading("Display Downsampling");
+                ui.separator();
+
+                ui.checkbox(
+                    &mut self.downsample_display,
+                    "Downsample large datasets in the plot view",
+                );
+                ui.add_enabled_ui(self.downsample_display, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Max points per dataset:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.max_display_points)
+                                .speed(100.0)
+                                .clamp_range(1_000..=1_000_000),
+                        );
+                    });
+                });
+                ui.small("Min/max decimation per bucket keeps peaks visible. Exports and processing always use the full data.");
+                ui.checkbox(&mut self.diagnostics.visible, "Show frame diagnostics")
+                    .on_hover_text("Frame rate, CPU time per frame and what caused the last repaint");
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.small(
+                    "Processing results are added as new datasets in the active subplot.",
+                );
+            });
+    }
+
+/// Function: explain its purpose and key arguments
+    fn add_rolling_average_dataset(&mut self) {
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+// Variable declaration
+        let source_points = finite_points_in_x_range(&source_dataset.points, self.processing_x_range());
+        if source_points.len() < self.rolling_window_size {
+            self.notifications.error("Window size must be smaller than or equal to dataset size.");
+            return;
+        }
+        match compute_rolling_average(&source_points, self.rolling_window_size) {
+            Ok(rolling_avg_points) => {
+// Variable declaration
+                let new_name = format!("{}_rolling_avg_{}", source_dataset.name, self.rolling_window_size);
+// Variable declaration
+                let new_dataset = Dataset::new(new_name, rolling_avg_points, self.next_color(self.active_subplot));
+                self.record_history("Rolling average");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(new_dataset);
+                }
+                self.notifications.success("Rolling average computed! Added to active subplot.");
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error computing rolling average: {}", e));
+            }
+        }
+    }
+
+    // Run `compute` on the dataset selected for processing and add the result as a new dataset
+/// Function: explain its purpose and key arguments
+    fn add_derived_dataset(
+        &mut self,
+        operation: &str,
+        suffix: &str,
+        compute: impl FnOnce(&[[f64; 2]]) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>>,
+    ) {
+// Variable declaration
+        let result = if let Some(subplot) = self.get_active_subplot() {
+            if let Some(source_dataset) = subplot.datasets.get(self.selected_dataset_for_processing) {
+                compute(&finite_points_in_x_range(&source_dataset.points, self.processing_x_range())).map(|points| {
+                    (
+                        format!("{}_{}", source_dataset.name, suffix),
+                        points,
+                        self.next_color(self.active_subplot),
+                    )
+                })
+            } else {
+                return;
+            }
+        } else {
+            return;
+        };
+
+        match result {
+            Ok((name, points, color)) => {
+                self.record_history(operation);
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(Dataset::new(name, points, color));
+                }
+                self.notifications.success(format!("{} computed! Added to active subplot.", operation));
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error computing {}: {}", operation.to_lowercase(), e));
+            }
+        }
+    }
+
+    // Bin the X or Y values of the dataset selected for processing and add the counts
+    // as a new bar dataset
+/// Function: explain its purpose and key arguments
+    fn add_histogram_dataset(&mut self) {
+// Variable declaration
+        let bins = if self.histogram_by_width {
+            HistogramBins::Width(self.histogram_bin_width)
+        } else {
+            HistogramBins::Count(self.histogram_bin_count)
+        };
+// Variable declaration
+        let axis = if self.histogram_of_x { 0 } else { 1 };
+// Variable declaration
+        let result = if let Some(subplot) = self.get_active_subplot() {
+            if let Some(source_dataset) = subplot.datasets.get(self.selected_dataset_for_processing) {
+// Variable declaration
+                let values: Vec<f64> = finite_points_in_x_range(&source_dataset.points, self.processing_x_range())
+                    .iter()
+                    .map(|p| p[axis])
+                    .collect();
+                compute_histogram(&values, bins).map(|points| {
+                    (
+                        format!("{}_hist_{}", source_dataset.name, if axis == 0 { "x" } else { "y" }),
+                        points,
+                        self.next_color(self.active_subplot),
+                    )
+                })
+            } else {
+                return;
+            }
+        } else {
+            return;
+        };
+
+        match result {
+            Ok((name, points, color)) => {
+// Variable declaration
+                let mut dataset = Dataset::new(name, points, color);
+                dataset.plot_type = PlotType::Bars;
+                self.record_history("Histogram");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(dataset);
+                }
+                self.notifications.success("Histogram computed! Added to active subplot.");
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error computing histogram: {}", e));
+            }
+        }
+    }
+
+    // Normalize the dataset selected for processing: either a new dataset (within the
+    // selection, if restricted) or, in place, the whole dataset with its error bars
+/// Function: explain its purpose and key arguments
+    fn normalize_processing_dataset(&mut self) {
+// Variable declaration
+        let range = if self.normalize_in_place { None } else { self.processing_x_range() };
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+// Variable declaration
+        let source_points = points_in_x_range(&source_dataset.points, range);
+// Variable declaration
+        let map = match normalization_map(&finite_points_in_x_range(&source_dataset.points, range), self.normalization) {
+            Ok(map) => map,
+            Err(e) => {
+                self.notifications.error(format!("Error normalizing: {}", e));
+                return;
+            }
+        };
+        if self.normalize_in_place {
+            self.record_history("Normalize");
+// Variable declaration
+            let index = self.selected_dataset_for_processing;
+            if let Some(dataset) = self.get_active_subplot_mut().and_then(|subplot| subplot.datasets.get_mut(index)) {
+                dataset.apply_axis_map(&map);
+                // The next reload would bring back the values before normalizing
+                dataset.follow = false;
+            }
+            self.notifications.success("Dataset normalized in place.");
+        } else {
+// Variable declaration
+            let dataset = Dataset::new(
+                format!("{}_{}", source_dataset.name, self.normalization.suffix()),
+                map.apply(&source_points),
+                self.next_color(self.active_subplot),
+            );
+            self.record_history("Normalize");
+            if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                subplot_mut.datasets.push(dataset);
+            }
+            self.notifications.success("Normalized! Added to active subplot.");
+        }
+    }
+
+    // Resample the dataset selected for processing (within the selection, if restricted)
+    // onto a uniform grid over its X range or onto the X values of another dataset
+/// Function: explain its purpose and key arguments
+    fn add_resampled_dataset(&mut self) {
+// Variable declaration
+        let Some(subplot) = self.get_active_subplot() else {
+            return;
+        };
+// Variable declaration
+        let Some(source_dataset) = subplot.datasets.get(self.selected_dataset_for_processing) else {
+            return;
+        };
+// Variable declaration
+        let source_points = finite_points_in_x_range(&source_dataset.points, self.processing_x_range());
+// Variable declaration
+        let (name, grid) = match self.resample_onto.and_then(|i| subplot.datasets.get(i)) {
+            Some(target) => (
+                format!("{}_on_{}", source_dataset.name, target.name),
+                target.points.iter().map(|p| p[0]).collect::<Vec<f64>>(),
+            ),
+            None => {
+// Variable declaration
+                let (min_x, max_x) = source_points
+                    .iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p[0]), hi.max(p[0])));
+                (
+                    format!("{}_resampled_{}", source_dataset.name, self.resample_count),
+                    uniform_grid(min_x, max_x, self.resample_count),
+                )
+            }
+        };
+        match resample_points(&source_points, &grid, self.resample_method) {
+            Ok(points) => {
+// Variable declaration
+                let dataset = Dataset::new(name, points, self.next_color(self.active_subplot));
+                self.record_history("Resample");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(dataset);
+                }
+                self.notifications.success("Resampled! Added to active subplot.");
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error resampling: {}", e));
+            }
+        }
+    }
+
+    // Combine the dataset selected for processing (within the selection, if restricted)
+    // with dataset B, or apply the expression to it
+/// Function: explain its purpose and key arguments
+    fn add_dataset_math(&mut self) {
+// Variable declaration
+        let Some(subplot) = self.get_active_subplot() else {
+            return;
+        };
+// Variable declaration
+        let Some(source_dataset) = subplot.datasets.get(self.selected_dataset_for_processing) else {
+            return;
+        };
+// Variable declaration
+        let source_points = points_in_x_range(&source_dataset.points, self.processing_x_range());
+// Variable declaration
+        let operand = subplot.datasets.get(self.math_operand);
+// Variable declaration
+        let uses_b = operation_uses_b(self.math_operation, &self.math_expression);
+// Variable declaration
+        let name = match operand.filter(|_| uses_b) {
+            Some(operand) => format!("{}_{}_{}", source_dataset.name, self.math_operation.name_part(), operand.name),
+            None => format!("{}_{}", source_dataset.name, self.math_operation.name_part()),
+        };
+// Variable declaration
+        let operand_points = operand.map_or(Vec::new(), |ds| ds.points.to_vec());
+        match combine_datasets(&source_points, &operand_points, self.math_operation, &self.math_expression) {
+            Ok(points) => {
+// Variable declaration
+                let dataset = Dataset::new(name, points, self.next_color(self.active_subplot));
+                self.record_history("Dataset math");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(dataset);
+                }
+                self.notifications.success("Computed! Added to active subplot.");
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error in dataset math: {}", e));
+            }
+        }
+    }
+
+    // Subtract a baseline from the dataset selected for processing (within the selection,
+    // if restricted)
+/// Function: explain its purpose and key arguments
+    fn add_baseline_corrected_dataset(&mut self) {
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+// Variable declaration
+        let source_points = finite_points_in_x_range(&source_dataset.points, self.processing_x_range());
+// Variable declaration
+        let name = format!("{}_baseline_corrected", source_dataset.name);
+        match subtract_baseline(&source_points, self.baseline_mode, self.baseline_anchors, self.baseline_window) {
+            Ok(points) => {
+// Variable declaration
+                let dataset = Dataset::new(name, points, self.next_color(self.active_subplot));
+                self.record_history("Baseline correction");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(dataset);
+                }
+                self.notifications.success("Baseline subtracted! Added to active subplot.");
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error subtracting baseline: {}", e));
+            }
+        }
+    }
+
+    // Mean of the datasets chosen for replica averaging (within the selection, if
+    // restricted), added to the active subplot with its spread as a band
+/// Function: explain its purpose and key arguments
+    fn add_replica_average(&mut self) {
+// Variable declaration
+        let Some(subplot) = self.get_active_subplot() else {
+            return;
+        };
+// Variable declaration
+        let mut indices = self.replica_datasets.clone();
+        indices.sort();
+// Variable declaration
+        let replicas: Vec<Vec<[f64; 2]>> = indices
+            .iter()
+            .filter_map(|&i| subplot.datasets.get(i))
+            .map(|ds| finite_points_in_x_range(&ds.points, self.processing_x_range()))
+            .collect();
+// Variable declaration
+        let name = format!(
+            "{}_mean_of_{}",
+            subplot.datasets.get(indices[0]).map_or("replica", |ds| ds.name.as_str()),
+            replicas.len()
+        );
+        match average_replicas(&replicas, self.replica_spread) {
+            Ok((points, band)) => {
+// Variable declaration
+                let mut dataset = Dataset::new(name, points, self.next_color(self.active_subplot));
+                dataset.band = Some(band);
+                self.record_history("Replica average");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(dataset);
+                }
+                self.notifications.success(format!("Averaged {} replicas! Added to active subplot.", replicas.len()));
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error averaging replicas: {}", e));
+            }
+        }
+    }
+
+    // Draw every dataset of the active subplot as a box plot or violin (one undo step)
+/// Function: explain its purpose and key arguments
+    fn show_subplot_as_distributions(&mut self, plot_type: PlotType) {
+        self.record_history(plot_type.to_string());
+        if let Some(subplot) = self.get_active_subplot_mut() {
+            for dataset in &mut subplot.datasets {
+                dataset.plot_type = plot_type.clone();
+            }
+        }
+    }
+
+    // Summarize the dataset selected for processing (within the selection, if restricted)
+/// Function: explain its purpose and key arguments
+    fn compute_dataset_statistics(&mut self) {
+// Variable declaration
+        let range = self.processing_x_range();
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+
+        match compute_statistics(&finite_points_in_x_range(&source_dataset.points, range)) {
+            Ok(stats) => {
+// Variable declaration
+                let description = match range {
+                    Some((low, high)) => format!(
+                        "{} (x = {} to {})",
+                        source_dataset.name,
+                        format_number(low),
+                        format_number(high)
+                    ),
+                    None => source_dataset.name.clone(),
+                };
+                self.last_statistics = Some((description, stats));
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error computing statistics: {}", e));
+            }
+        }
+    }
+
+    // Compare datasets A and B chosen in the comparison window (within the selection, if
+    // restricted)
+/// Function: explain its purpose and key arguments
+    fn compare_selected_datasets(&mut self) {
+// Variable declaration
+        let range = self.processing_x_range();
+// Variable declaration
+        let [a, b] = self.comparison_datasets;
+// Variable declaration
+        let Some((dataset_a, dataset_b)) = self
+            .get_active_subplot()
+            .and_then(|subplot| Some((subplot.datasets.get(a)?, subplot.datasets.get(b)?)))
+        else {
+            return;
+        };
+        match compare_datasets(&finite_points_in_x_range(&dataset_a.points, range), &finite_points_in_x_range(&dataset_b.points, range)) {
+            Ok(metrics) => {
+                self.last_comparison = Some((format!("{} vs {}", dataset_a.name, dataset_b.name), metrics));
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error comparing datasets: {}", e));
+            }
+        }
+    }
+
+    // Find peaks in the dataset selected for processing (within the selection, if
+    // restricted): keep the list and add them as a marker dataset
+/// Function: explain its purpose and key arguments
+    fn add_peaks_dataset(&mut self) {
+// Variable declaration
+        let range = self.processing_x_range();
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+        match find_peaks(&finite_points_in_x_range(&source_dataset.points, range), self.peak_settings) {
+            Ok(peaks) if peaks.is_empty() => {
+                self.notifications.warning("No peaks pass the thresholds.");
+            }
+            Ok(peaks) => {
+// Variable declaration
+                let suffix = if self.peak_settings.minima { "minima" } else { "peaks" };
+// Variable declaration
+                let mut dataset = Dataset::new(
+                    format!("{}_{}", source_dataset.name, suffix),
+                    peaks.iter().map(|peak| [peak.x, peak.y]).collect(),
+                    self.next_color(self.active_subplot),
+                );
+                dataset.plot_type = PlotType::Scatter;
+// Variable declaration
+                let description = source_dataset.name.clone();
+                self.record_history("Find peaks");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(dataset);
+                }
+                self.notifications.success(format!("Found {} peaks! Added to active subplot.", peaks.len()));
+                self.last_peaks = Some((description, peaks));
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error finding peaks: {}", e));
+            }
+        }
+    }
+
+    // Save the last list of peaks to a CSV file chosen by the user
+/// Function: explain its purpose and key arguments
+    fn export_peaks(&mut self) {
+// Variable declaration
+        let Some((description, peaks)) = &self.last_peaks else {
+            return;
+        };
+        if let Some(path) = pick_csv_save_path(&format!("{}_peaks.csv", description)) {
+            match save_peaks_csv(&path, peaks) {
+                Ok(()) => self.notifications.success(format!("Exported {} peaks to {}", peaks.len(), path.display())),
+                Err(e) => self.notifications.error(format!("Failed to export peaks: {}", e)),
+            }
+        }
+    }
+
+    // Find where the dataset selected for processing (within the selection, if restricted)
+    // crosses the threshold; optionally mark each crossing with a vertical line
+/// Function: explain its purpose and key arguments
+    fn find_threshold_crossings(&mut self) {
+// Variable declaration
+        let threshold = self.crossing_threshold;
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+        match find_crossings(&finite_points_in_x_range(&source_dataset.points, self.processing_x_range()), threshold) {
+            Ok(crossings) => {
+// Variable declaration
+                let description = format!("{} crossing y = {}", source_dataset.name, format_number(threshold));
+// Variable declaration
+                let color = source_dataset.color;
+                if self.mark_crossings && !crossings.is_empty() {
+                    self.record_history("Mark crossings");
+                    if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                        for crossing in &crossings {
+                            subplot_mut.annotations.push(Annotation::new(
+                                AnnotationKind::VLine,
+                                [crossing.x, threshold],
+                                format_number(crossing.x),
+                                color,
+                            ));
+                        }
+                    }
+                }
+                match crossings.len() {
+                    0 => self.notifications.warning("The dataset never crosses the threshold."),
+                    n => self.notifications.success(format!("Found {} crossings.", n)),
+                }
+                self.last_crossings = Some((description, crossings));
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error finding crossings: {}", e));
+            }
+        }
+    }
+
+    // Block average the Y values of the dataset selected for processing (within the
+    // selection, if restricted): keep the summary and add the convergence curve
+/// Function: explain its purpose and key arguments
+    fn add_block_average_dataset(&mut self) {
+// Variable declaration
+        let range = self.processing_x_range();
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+// Variable declaration
+        let values: Vec<f64> = finite_points_in_x_range(&source_dataset.points, range).iter().map(|p| p[1]).collect();
+        match block_average(&values) {
+            Ok(result) => {
+// Variable declaration
+                let description = match range {
+                    Some((low, high)) => format!(
+                        "{} (x = {} to {})",
+                        source_dataset.name,
+                        format_number(low),
+                        format_number(high)
+                    ),
+                    None => source_dataset.name.clone(),
+                };
+// Variable declaration
+                let mut dataset = Dataset::new(
+                    format!("{}_block_sem", source_dataset.name),
+                    result.curve.clone(),
+                    self.next_color(self.active_subplot),
+                );
+                dataset.plot_type = PlotType::LineAndMarkers;
+                self.record_history("Block averaging");
+                if let Some(subplot_mut) = self.get_active_subplot_mut() {
+                    subplot_mut.datasets.push(dataset);
+                }
+                self.notifications.success(format!(
+                    "Block averaging: standard error plateau {}. Curve added to active subplot.",
+                    format_number(result.plateau_sem)
+                ));
+                self.last_block_average = Some((description, result));
+            }
+            Err(e) => {
+                self.notifications.error(format!("Error block averaging: {}", e));
+            }
+        }
+    }
+
+    // Save the points of the dataset selected for processing (within the selection, if
+    // restricted) to a CSV file chosen by the user
+/// Function: explain its purpose and key arguments
+    fn export_processing_points(&mut self) {
+// Variable declaration
+        let Some(source_dataset) = self
+            .get_active_subplot()
+            .and_then(|subplot| subplot.datasets.get(self.selected_dataset_for_processing))
+        else {
+            return;
+        };
+// Variable declaration
+        let points = points_in_x_range(&source_dataset.points, self.processing_x_range());
+        if points.is_empty() {
+            self.notifications.warning("No points to export in the selected range");
+            return;
         }
 
-        // Data manipulation window (similar to before, but operates on active subplot)
-        if self.show_data_manipulation {
-            self.show_data_manipulation_window(ctx);
+        if let Some(path) = pick_csv_save_path(&format!("{}.csv", source_dataset.name)) {
+            match save_points_csv(&path, &points) {
+                Ok(()) => self.notifications.success(format!("Exported {} points to {}", points.len(), path.display())),
+                Err(e) => self.notifications.error(format!("Failed to export points: {}", e)),
+            }
         }
+    }
 
-        // Color picker window (similar to before, but for active subplot)
-        if self.show_color_picker {
-            self.show_color_picker_window(ctx);
+    // Write every fit of the session to a text, CSV or Markdown file, by the extension chosen
+/// Function: explain its purpose and key arguments
+    fn export_fit_report(&mut self) {
+// Variable declaration
+        let fit_count = self.data_editor.fit_results.len()
+            + self.data_editor.global_fit_results.iter().map(|global| global.fits.len()).sum::<usize>();
+        if fit_count == 0 {
+            self.notifications.warning("No fit results to export; fit a dataset in the Curve Fitting window first");
+            return;
         }
 
-        // Legend controls window
-        if self.show_legend_controls {
-            self.show_legend_controls_window(ctx);
+        if let Some(path) = pick_fit_report_path() {
+            match save_fit_report(&path, &self.data_editor.fit_results, &self.data_editor.global_fit_results) {
+                Ok(()) => self.notifications.success(format!("Exported {} fits to {}", fit_count, path.display())),
+                Err(e) => self.notifications.error(format!("Failed to export fit report: {}", e)),
+            }
         }
     }
 
+    // App-wide palette and the active subplot's override. Choosing one recolors the
+    // datasets it applies to, so the GUI and exported images stay alike.
 /// Function: explain its purpose and key arguments
-    fn show_data_manipulation_window(&mut self, ctx: &egui::Context) {
-        egui::Window::new("Data Processing")
-            .resizable(true)
-            .default_width(350.0)
-            .default_height(250.0)
-            .show(ctx, |ui| {
-// Variable declaration
-                let subplot_info = if let Some(subplot) = self.get_active_subplot() {
-                    if subplot.datasets.is_empty() {
-                        ui.label(
-                            "No datasets in active subplot. Load data first to enable processing.",
-                        );
-                        return;
-                    }
+    fn palette_controls(&mut self, ui: &mut egui::Ui) {
 // Variable declaration
-                    let dataset_names: Vec<String> =
-                        subplot.datasets.iter().map(|d| d.name.clone()).collect();
-                    Some((dataset_names, subplot.datasets.len()))
-                } else {
-                    ui.label("No active subplot selected.");
-                    return;
-                };
-
+        let mut global = self.palette;
 // Variable declaration
-                let (dataset_names, dataset_count) = subplot_info.unwrap();
-
-                ui.heading("Rolling Average");
-                ui.separator();
-
-                // Dataset selection
-                ui.horizontal(|ui| {
-                    ui.label("Dataset:");
-                    if self.selected_dataset_for_processing < dataset_names.len() {
-                        egui::ComboBox::from_label("")
-                            .selected_text(&dataset_names[self.selected_dataset_for_processing])
-                            .show_ui(ui, |ui| {
-                                for (i, name) in dataset_names.iter().enumerate() {
-                                    ui.selectable_value(
-                                        &mut self.selected_dataset_for_processing,
-                                        i,
-                                        name,
-                                    );
-                                }
-                            });
+        let mut subplot_palette = self.get_active_subplot().and_then(|s| s.config.palette);
+        egui::Grid::new("palette_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Palette:");
+            egui::ComboBox::from_id_source("global_palette_combo")
+                .selected_text(global.to_string())
+                .show_ui(ui, |ui| {
+                    for palette in Palette::ALL {
+                        ui.selectable_value(&mut global, palette, palette.to_string());
                     }
                 });
+            ui.end_row();
 
-                ui.add_space(10.0);
-
-                // Window size setting
-                ui.horizontal(|ui| {
-                    ui.label("Window size:");
-                    ui.add(
-                        egui::Slider::new(&mut self.rolling_window_size, 2..=100).text("points"),
-                    );
+            ui.label(format!("Subplot {}:", self.active_subplot + 1));
+            egui::ComboBox::from_id_source("subplot_palette_combo")
+                .selected_text(subplot_palette.map_or("Same as palette", Palette::to_string))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut subplot_palette, None, "Same as palette");
+                    for palette in Palette::ALL {
+                        ui.selectable_value(&mut subplot_palette, Some(palette), palette.to_string());
+                    }
                 });
+            ui.end_row();
+        });
 
-                ui.add_space(10.0);
-
-                // Show preview info
-                if let Some(subplot) = self.get_active_subplot() {
-                    if let Some(dataset) =
-                        subplot.datasets.get(self.selected_dataset_for_processing)
-                    {
-                        ui.label(format!("Original dataset: {} points", dataset.points.len()));
-
-                        if dataset.points.len() >= self.rolling_window_size {
-// Variable declaration
-                            let result_points = dataset.points.len() - self.rolling_window_size + 1;
-                            ui.label(format!(
-                                "Rolling average will have: {} points",
-                                result_points
-                            ));
-                        } else {
-                            ui.colored_label(
-                                egui::Color32::from_rgb(255, 165, 0),
-                                "Warning: Window size larger than dataset!",
-                            );
-                        }
-                    }
+        if global != self.palette {
+            self.record_history("Change palette");
+            self.palette = global;
+            for index in 0..self.subplots.len() {
+                if self.subplots[index].config.palette.is_none() {
+                    self.recolor_subplot(index);
                 }
+            }
+        }
+        if self.get_active_subplot().is_some_and(|s| s.config.palette != subplot_palette) {
+            self.record_history("Change subplot palette");
+            if let Some(subplot) = self.get_active_subplot_mut() {
+                subplot.config.palette = subplot_palette;
+            }
+            self.recolor_subplot(self.active_subplot);
+        }
 
-                ui.add_space(15.0);
-
-                // Compute button
-                if ui.button("🔄 Compute Rolling Average").clicked() {
-                    if let Some(subplot) = self.get_active_subplot() {
-                        if let Some(source_dataset) =
-                            subplot.datasets.get(self.selected_dataset_for_processing)
-                        {
-                            if source_dataset.points.len() >= self.rolling_window_size {
-                                match compute_rolling_average(
-                                    &source_dataset.points,
-                                    self.rolling_window_size,
-                                ) {
-                                    Ok(rolling_avg_points) => {
-// Variable declaration
-                                        let new_name = format!(
-                                            "{}_rolling_avg_{}",
-                                            source_dataset.name, self.rolling_window_size
-                                        );
+        // Swatches of the palette the active subplot uses
+        ui.horizontal(|ui| {
+            for color in self.subplot_palette(self.active_subplot).colors() {
 // Variable declaration
-                                        let new_dataset = Dataset {
-                                            name: new_name,
-                                            points: rolling_avg_points,
-                                            color: get_default_color(dataset_count % 8),
-                                        };
-                                        if let Some(subplot_mut) = self.get_active_subplot_mut() {
-                                            subplot_mut.datasets.push(new_dataset);
-                                        }
-                                        self.error_message = Some(format!(
-                                            "Rolling average computed! Added to active subplot."
-                                        ));
-                                    }
-                                    Err(e) => {
-                                        self.error_message =
-                                            Some(format!("Error computing rolling average: {}", e));
-                                    }
-                                }
-                            } else {
-                                self.error_message = Some(
-                                    "Window size must be smaller than or equal to dataset size."
-                                        .to_string(),
-                                );
-                            }
-                        }
-                    }
-                }
-
-                ui.add_space(10.0);
-                ui.separator();
-                ui.small(
-                    "The rolling average will be added as a new dataset in the active subplot.",
-                );
-            });
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(16.0, 12.0), egui::Sense::hover());
+                ui.painter().rect_filled(rect, 2.0, egui::Color32::from_rgb(color[0], color[1], color[2]));
+            }
+        });
     }
 
 /// Function: explain its purpose and key arguments
@@ -1077,6 +7404,9 @@ impl PlotterApp {
             .default_width(300.0)
             .default_height(400.0)
             .show(ctx, |ui| {
+                self.palette_controls(ui);
+                ui.separator();
+
                 // Get subplot info first to avoid borrowing conflicts
 // Variable declaration
                 let subplot_info = if let Some(subplot) = self.get_active_subplot() {
@@ -1087,28 +7417,72 @@ impl PlotterApp {
                         return;
                     }
 // Variable declaration
-                    let dataset_info: Vec<(String, [u8; 3])> = subplot
+                    let dataset_info: Vec<(String, [u8; 3], PlotType, LinePattern, f32)> = subplot
+                        .datasets
+                        .iter()
+                        .map(|ds| {
+                            (
+                                ds.name.clone(),
+                                ds.color,
+                                ds.plot_type.clone(),
+                                ds.line_style,
+                                ds.line_width,
+                            )
+                        })
+                        .collect();
+// Variable declaration
+                    let coloring_info: Vec<(Option<ColorBy>, Colormap, bool)> = subplot
                         .datasets
                         .iter()
-                        .map(|ds| (ds.name.clone(), ds.color))
+                        .map(|ds| (ds.color_by, ds.colormap, ds.color_values.is_some()))
                         .collect();
-                    Some(dataset_info)
+// Variable declaration
+                    let fill_info: Vec<FillMode> = subplot.datasets.iter().map(|ds| ds.fill.clone()).collect();
+// Variable declaration
+                    let axis_info: Vec<bool> = subplot.datasets.iter().map(|ds| ds.secondary_y).collect();
+// Variable declaration
+                    let marker_info: Vec<(MarkerStyle, f32)> =
+                        subplot.datasets.iter().map(|ds| (ds.marker, ds.marker_size)).collect();
+// Variable declaration
+                    let gap_info: Vec<(bool, bool)> =
+                        subplot.datasets.iter().map(|ds| (ds.connect_gaps, ds.has_gaps())).collect();
+// Variable declaration
+                    let storage_info: Vec<(bool, usize)> =
+                        subplot.datasets.iter().map(|ds| (ds.points.single_precision(), ds.points.memory_bytes())).collect();
+                    Some((dataset_info, coloring_info, fill_info, axis_info, marker_info, gap_info, storage_info))
                 } else {
                     ui.label("No active subplot selected.");
                     return;
                 };
 
 // Variable declaration
-                let dataset_info = subplot_info.unwrap();
+                let (dataset_info, coloring_info, fill_info, axis_info, marker_info, gap_info, storage_info) =
+                    subplot_info.unwrap();
+// Variable declaration
+                let mut selected_coloring_changed = None;
 // Variable declaration
                 let mut selected_color_changed = None;
+// Variable declaration
+                let mut selected_plot_type_changed = None;
+// Variable declaration
+                let mut selected_line_style_changed = None;
+// Variable declaration
+                let mut selected_marker_changed = None;
+// Variable declaration
+                let mut selected_gaps_changed = None;
+// Variable declaration
+                let mut selected_precision_changed = None;
+// Variable declaration
+                let mut selected_fill_changed = None;
+// Variable declaration
+                let mut selected_axis_changed = None;
 // Variable declaration
                 let mut reset_colors = false;
 
                 ui.heading("Dataset Colors (Active Subplot)");
                 ui.separator();
 
-                for (i, (name, color)) in dataset_info.iter().enumerate() {
+                for (i, (name, color, plot_type, line_style, line_width)) in dataset_info.iter().enumerate() {
                     ui.horizontal(|ui| {
                         // Color square button
 // Variable declaration
@@ -1140,6 +7514,209 @@ impl PlotterApp {
                                 selected_color_changed =
                                     Some((i, [egui_color.r(), egui_color.g(), egui_color.b()]));
                             }
+
+                            ui.horizontal(|ui| {
+                                ui.label("Plot type:");
+// Variable declaration
+                                let mut new_plot_type = plot_type.clone();
+                                egui::ComboBox::from_id_source("plot_type_combo")
+                                    .selected_text(new_plot_type.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for option in [
+                                            PlotType::Line,
+                                            PlotType::Scatter,
+                                            PlotType::LineAndMarkers,
+                                            PlotType::Bars,
+                                            PlotType::Box,
+                                            PlotType::Violin,
+                                        ] {
+// Variable declaration
+                                            let label = option.to_string();
+                                            ui.selectable_value(&mut new_plot_type, option, label);
+                                        }
+                                    });
+                                if new_plot_type != *plot_type {
+                                    selected_plot_type_changed = Some((i, new_plot_type));
+                                }
+                            });
+
+                            // Line styling (only meaningful when the dataset draws a line)
+                            ui.add_enabled_ui(plot_type.draws_line(), |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Line style:");
+// Variable declaration
+                                    let mut new_line_style = *line_style;
+// Variable declaration
+                                    let mut new_line_width = *line_width;
+                                    egui::ComboBox::from_id_source("line_style_combo")
+                                        .selected_text(new_line_style.to_string())
+                                        .show_ui(ui, |ui| {
+                                            for option in [
+                                                LinePattern::Solid,
+                                                LinePattern::Dashed,
+                                                LinePattern::Dotted,
+                                            ] {
+                                                ui.selectable_value(
+                                                    &mut new_line_style,
+                                                    option,
+                                                    option.to_string(),
+                                                );
+                                            }
+                                        });
+                                    ui.label("Width:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut new_line_width)
+                                            .speed(0.1)
+                                            .clamp_range(0.5..=8.0),
+                                    );
+                                    if new_line_style != *line_style || new_line_width != *line_width {
+                                        selected_line_style_changed =
+                                            Some((i, new_line_style, new_line_width));
+                                    }
+                                });
+                            });
+
+                            // Blank or NaN Y values break the line unless it is joined across them
+// Variable declaration
+                            let (connect_gaps, has_gaps) = gap_info[i];
+// Variable declaration
+                            let mut new_connect_gaps = connect_gaps;
+                            if ui
+                                .add_enabled(
+                                    plot_type.draws_line() && has_gaps,
+                                    egui::Checkbox::new(&mut new_connect_gaps, "Connect across missing values"),
+                                )
+                                .on_disabled_hover_text("Only for lines through data with blank or NaN values")
+                                .changed()
+                            {
+                                selected_gaps_changed = Some((i, new_connect_gaps));
+                            }
+
+                            // Very large traces can be kept in single precision to save memory
+// Variable declaration
+                            let (single_precision, memory) = storage_info[i];
+// Variable declaration
+                            let mut new_single_precision = single_precision;
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .checkbox(&mut new_single_precision, "32-bit storage")
+                                    .on_hover_text(
+                                        "Keep the values as 32-bit floats: half the memory, about 7 significant digits. \
+                                         Large values such as timestamps lose their fine detail.",
+                                    )
+                                    .changed()
+                                {
+                                    selected_precision_changed = Some((i, new_single_precision));
+                                }
+                                ui.weak(format!("{:.1} MB of point data", memory as f64 / 1e6));
+                            });
+
+                            // Marker shape and size (only meaningful when the dataset draws markers)
+                            ui.add_enabled_ui(plot_type.draws_markers(), |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Marker:");
+// Variable declaration
+                                    let (mut new_marker, mut new_marker_size) = marker_info[i];
+                                    egui::ComboBox::from_id_source("marker_combo")
+                                        .selected_text(new_marker.to_string())
+                                        .show_ui(ui, |ui| {
+                                            for option in MarkerStyle::ALL {
+                                                ui.selectable_value(&mut new_marker, option, option.to_string());
+                                            }
+                                        });
+                                    ui.label("Size:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut new_marker_size)
+                                            .speed(0.1)
+                                            .clamp_range(1.0..=12.0),
+                                    );
+                                    if (new_marker, new_marker_size) != marker_info[i] {
+                                        selected_marker_changed = Some((i, new_marker, new_marker_size));
+                                    }
+                                });
+                            });
+
+                            // Area under the line: to zero, to another dataset, or stacked
+// Variable declaration
+                            let mut new_fill = fill_info[i].clone();
+                            ui.horizontal(|ui| {
+                                ui.label("Fill:");
+                                egui::ComboBox::from_id_source("fill_combo")
+                                    .selected_text(match &new_fill {
+                                        FillMode::ToDataset(other) => format!("To {}", other),
+                                        fill => fill.to_string().to_string(),
+                                    })
+                                    .show_ui(ui, |ui| {
+                                        for option in [FillMode::None, FillMode::ToZero, FillMode::Stacked] {
+// Variable declaration
+                                            let label = option.to_string();
+                                            ui.selectable_value(&mut new_fill, option, label);
+                                        }
+                                        for (j, (other, ..)) in dataset_info.iter().enumerate() {
+                                            if j != i {
+                                                ui.selectable_value(
+                                                    &mut new_fill,
+                                                    FillMode::ToDataset(other.clone()),
+                                                    format!("To {}", other),
+                                                );
+                                            }
+                                        }
+                                    });
+                            });
+                            if new_fill != fill_info[i] {
+                                selected_fill_changed = Some((i, new_fill));
+                            }
+
+// Variable declaration
+                            let mut secondary_y = axis_info[i];
+                            if ui
+                                .checkbox(&mut secondary_y, "Right Y axis")
+                                .on_hover_text("Plot against a second Y axis with its own range")
+                                .changed()
+                            {
+                                selected_axis_changed = Some((i, secondary_y));
+                            }
+
+                            // Color along a colormap instead of the single color above
+// Variable declaration
+                            let (color_by, colormap, has_values) = coloring_info[i];
+// Variable declaration
+                            let mut new_color_by = color_by;
+// Variable declaration
+                            let mut new_colormap = colormap;
+                            ui.horizontal(|ui| {
+                                ui.label("Color by:");
+                                egui::ComboBox::from_id_source("color_by_combo")
+                                    .selected_text(new_color_by.map_or("Single color", ColorBy::to_string))
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut new_color_by, None, "Single color");
+                                        ui.selectable_value(
+                                            &mut new_color_by,
+                                            Some(ColorBy::PointIndex),
+                                            ColorBy::PointIndex.to_string(),
+                                        );
+                                        ui.add_enabled_ui(has_values, |ui| {
+                                            ui.selectable_value(
+                                                &mut new_color_by,
+                                                Some(ColorBy::Values),
+                                                ColorBy::Values.to_string(),
+                                            )
+                                            .on_disabled_hover_text("Choose a color column when importing the file");
+                                        });
+                                    });
+                                if new_color_by.is_some() {
+                                    egui::ComboBox::from_id_source("colormap_combo")
+                                        .selected_text(new_colormap.to_string())
+                                        .show_ui(ui, |ui| {
+                                            for option in Colormap::ALL {
+                                                ui.selectable_value(&mut new_colormap, option, option.to_string());
+                                            }
+                                        });
+                                }
+                            });
+                            if new_color_by != color_by || new_colormap != colormap {
+                                selected_coloring_changed = Some((i, new_color_by, new_colormap));
+                            }
                         });
                     }
 
@@ -1148,12 +7725,13 @@ impl PlotterApp {
 
                 ui.separator();
 
-                if ui.button("Reset to Default Colors").clicked() {
+                if ui.button("Reset to Palette Colors").clicked() {
                     reset_colors = true;
                 }
 
                 // Apply changes after UI is done
                 if let Some((index, new_color)) = selected_color_changed {
+                    self.record_history_coalescing("Change color");
                     if let Some(subplot) = self.get_active_subplot_mut() {
                         if let Some(dataset) = subplot.datasets.get_mut(index) {
                             dataset.color = new_color;
@@ -1161,13 +7739,85 @@ impl PlotterApp {
                     }
                 }
 
-                if reset_colors {
+                if let Some((index, new_plot_type)) = selected_plot_type_changed {
+                    self.record_history("Change plot type");
                     if let Some(subplot) = self.get_active_subplot_mut() {
-                        for (i, dataset) in subplot.datasets.iter_mut().enumerate() {
-                            dataset.color = get_default_color(i % 8);
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.plot_type = new_plot_type;
+                        }
+                    }
+                }
+
+                if let Some((index, new_line_style, new_line_width)) = selected_line_style_changed {
+                    self.record_history("Change line style");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.line_style = new_line_style;
+                            dataset.line_width = new_line_width;
+                        }
+                    }
+                }
+
+                if let Some((index, new_marker, new_marker_size)) = selected_marker_changed {
+                    self.record_history("Change marker");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.marker = new_marker;
+                            dataset.marker_size = new_marker_size;
+                        }
+                    }
+                }
+
+                if let Some((index, connect_gaps)) = selected_gaps_changed {
+                    self.record_history("Change gap handling");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.connect_gaps = connect_gaps;
+                        }
+                    }
+                }
+
+                if let Some((index, single_precision)) = selected_precision_changed {
+                    self.record_history("Change storage precision");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.set_single_precision(single_precision);
+                        }
+                    }
+                }
+
+                if let Some((index, fill)) = selected_fill_changed {
+                    self.record_history("Change fill");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.fill = fill;
+                        }
+                    }
+                }
+
+                if let Some((index, secondary_y)) = selected_axis_changed {
+                    self.record_history("Change Y axis");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.secondary_y = secondary_y;
+                        }
+                    }
+                }
+
+                if let Some((index, color_by, colormap)) = selected_coloring_changed {
+                    self.record_history("Change value coloring");
+                    if let Some(subplot) = self.get_active_subplot_mut() {
+                        if let Some(dataset) = subplot.datasets.get_mut(index) {
+                            dataset.color_by = color_by;
+                            dataset.colormap = colormap;
                         }
                     }
                 }
+
+                if reset_colors {
+                    self.record_history("Reset colors");
+                    self.recolor_subplot(self.active_subplot);
+                }
             });
     }
 
@@ -1210,6 +7860,27 @@ impl PlotterApp {
                         ui.label("Legend title:");
                         ui.text_edit_singleline(&mut subplot.config.legend_title);
                     });
+                    ui.horizontal(|ui| {
+                        ui.label("Position:");
+                        egui::ComboBox::from_id_source("legend_position_combo")
+                            .selected_text(subplot.config.legend_position.to_string())
+                            .show_ui(ui, |ui| {
+                                for position in LegendPosition::ALL {
+                                    ui.selectable_value(&mut subplot.config.legend_position, position, position.to_string());
+                                }
+                            });
+                        ui.label("Columns:");
+                        ui.add(egui::DragValue::new(&mut subplot.config.legend_columns).clamp_range(1..=8));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Background opacity:");
+                        ui.add(egui::Slider::new(&mut subplot.config.legend_opacity, 0.0..=1.0));
+                        ui.checkbox(&mut subplot.config.legend_border, "Border");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Entry font size:");
+                        ui.add(egui::Slider::new(&mut subplot.config.legend_font_size, 6.0..=32.0).suffix(" pt"));
+                    });
 
                     ui.add_space(10.0);
 
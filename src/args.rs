@@ -1,15 +1,63 @@
 // Import external modules or crates needed in args.rs
-use clap::Parser;
+use crate::app::{Subplot, SubplotLayout};
+// Import external modules or crates needed in args.rs
+use crate::folder::Assignment;
+// Import external modules or crates needed in args.rs
+use crate::utils::{get_data_bounds, parse_color};
+// Import external modules or crates needed in args.rs
+use clap::{Parser, ValueEnum};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+/// Enum representing a set of related values in args.rs module
+pub enum StdinFormat {
+    Csv,
+    Xvg,
+}
 
 #[derive(Parser)]
 #[command(name = "CactusPlot")]
 #[command(about = "A simple but elegant plotting application")]
 /// Data structure used in args.rs module
 pub struct Args {
-    /// Input data files
+    /// Input data files; use - to read data piped on standard input
     #[arg(value_name = "FILE", required = false)]
     pub files: Vec<String>,
 
+    /// Load a file with its own options, e.g. rmsd.csv:name=RMSD:color=red:subplot=2:columns=0:3
+    /// (columns=X:Y1,Y2,... or a bare trailing X:Y1,Y2,... picks columns of a text file; color applies to all its series)
+    #[arg(long = "series", value_name = "SPEC", value_parser = parse_series_spec)]
+    pub series: Vec<SeriesSpec>,
+
+    /// Plot a matrix file (matrix CSV/text table or GROMACS .xpm) as a heatmap in the active subplot
+    #[arg(long = "heatmap", value_name = "FILE")]
+    pub heatmaps: Vec<String>,
+
+    /// Load every data file matching a pattern such as 'data/*.xvg', each as its own dataset
+    /// (quote it so the shell does not expand it; wildcards only in the file name)
+    #[arg(long, value_name = "PATTERN")]
+    pub glob: Option<String>,
+
+    /// With --glob, also look in the folders below
+    #[arg(long, action, requires = "glob")]
+    pub recursive: bool,
+
+    /// With --glob, how the files are shared out between subplots (subplots are added as needed)
+    #[arg(long, value_enum, value_name = "STRATEGY", default_value = "active", requires = "glob")]
+    pub assign: Assignment,
+
+    /// With --assign by-name, files matching this pattern share a subplot; repeat for more groups
+    #[arg(long = "group", value_name = "PATTERN", requires = "glob")]
+    pub groups: Vec<String>,
+
+    /// Download a CSV, XVG, JSON or other data file over HTTP(S) and plot it (repeatable);
+    /// downloads are cached for a few minutes
+    #[arg(long = "url", value_name = "URL")]
+    pub urls: Vec<String>,
+
+    /// Format of data piped on standard input (implies reading it)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub stdin_format: Option<StdinFormat>,
+
     /// Show grid on plot
     #[arg(long, action)]
     pub grid: bool,
@@ -17,4 +65,210 @@ pub struct Args {
     /// Hide the legend
     #[arg(long, action)]
     pub no_legend: bool,
-}
\ No newline at end of file
+
+    /// Subplot arrangement as ROWSxCOLS, e.g. 2x2 or 1x3
+    #[arg(long, value_name = "ROWSxCOLS", value_parser = parse_layout)]
+    pub layout: Option<SubplotLayout>,
+
+    /// Title of every subplot
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// X-axis label of every subplot
+    #[arg(long, value_name = "LABEL")]
+    pub xlabel: Option<String>,
+
+    /// Y-axis label of every subplot
+    #[arg(long, value_name = "LABEL")]
+    pub ylabel: Option<String>,
+
+    /// Lower end of the X-axis (limits not given are taken from the data)
+    #[arg(long, value_name = "VALUE", allow_negative_numbers = true)]
+    pub xmin: Option<f64>,
+
+    /// Upper end of the X-axis
+    #[arg(long, value_name = "VALUE", allow_negative_numbers = true)]
+    pub xmax: Option<f64>,
+
+    /// Lower end of the Y-axis
+    #[arg(long, value_name = "VALUE", allow_negative_numbers = true)]
+    pub ymin: Option<f64>,
+
+    /// Upper end of the Y-axis
+    #[arg(long, value_name = "VALUE", allow_negative_numbers = true)]
+    pub ymax: Option<f64>,
+
+    /// Load the files opened most recently in the window again
+    #[arg(long, action)]
+    pub reopen_last: bool,
+
+    /// Open a saved session (.cactus) at startup
+    #[arg(long, value_name = "SESSION")]
+    pub session: Option<String>,
+
+    /// Render the plot to this PNG, PDF or matplotlib .py file (by extension) and exit without opening a window
+    #[arg(long, value_name = "FILE")]
+    pub export: Option<String>,
+}
+// One --series argument: a file plus the options that apply to it
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in args.rs module
+pub struct SeriesSpec {
+    pub file: String,
+    pub name: Option<String>,
+    pub color: Option<[u8; 3]>,
+    pub subplot: Option<usize>, // 1-based, as typed
+    pub columns: Option<(usize, Vec<usize>)>, // X column and Y columns, 0-based
+}
+
+// Parse "file:key=value:key=value". A bare number after columns=X continues the value,
+// so columns=0:3 reads as X = 0 and Y = 3; bare numbers straight after the file name are
+// its columns, so data.csv:0:3 means the same. Options are taken off the end, so the file
+// name may itself contain colons (C:\data.csv:0:3).
+/// Function: explain its purpose and key arguments
+fn parse_series_spec(text: &str) -> Result<SeriesSpec, String> {
+// Variable declaration
+    let is_option = |part: &str| match part.split_once('=') {
+        Some((key, _)) => !key.trim().is_empty() && key.trim().chars().all(|c| c.is_ascii_alphabetic()),
+        None => !part.trim().is_empty() && part.chars().all(|c| c.is_ascii_digit() || c == ',' || c.is_whitespace()),
+    };
+// Variable declaration
+    let mut file = text;
+// Variable declaration
+    let mut parts = Vec::new();
+    while let [part, rest] = file.rsplitn(2, ':').collect::<Vec<_>>()[..] {
+        if !is_option(part) {
+            break;
+        }
+        parts.push(part);
+        file = rest;
+    }
+    if file.is_empty() {
+        return Err("missing file name".to_string());
+    }
+// Variable declaration
+    let mut options: Vec<(String, String)> = Vec::new();
+    for part in parts.into_iter().rev() {
+        match (part.split_once('='), options.last_mut()) {
+            (Some((key, value)), _) => options.push((key.trim().to_lowercase(), value.to_string())),
+            (None, Some((key, value))) if key == "columns" => {
+                value.push(':');
+                value.push_str(part);
+            }
+            (None, None) => options.push(("columns".to_string(), part.to_string())),
+            (None, _) => return Err(format!("expected key=value, found '{}'", part)),
+        }
+    }
+
+// Variable declaration
+    let mut spec = SeriesSpec {
+        file: file.to_string(),
+        name: None,
+        color: None,
+        subplot: None,
+        columns: None,
+    };
+    for (key, value) in options {
+        match key.as_str() {
+            "name" => spec.name = Some(value),
+            "color" | "colour" => {
+                spec.color = Some(parse_color(&value).ok_or_else(|| format!("unknown color '{}'", value))?);
+            }
+            "subplot" => {
+                spec.subplot = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|n| *n >= 1)
+                        .ok_or_else(|| format!("subplot must be a number from 1, found '{}'", value))?,
+                );
+            }
+            "columns" => spec.columns = Some(parse_columns(&value)?),
+            _ => return Err(format!("unknown option '{}' (use name, color, subplot or columns)", key)),
+        }
+    }
+    Ok(spec)
+}
+
+// "X:Y" or "X:Y1,Y2,..." as 0-based column indices
+/// Function: explain its purpose and key arguments
+fn parse_columns(text: &str) -> Result<(usize, Vec<usize>), String> {
+// Variable declaration
+    let invalid = || format!("columns must look like 0:1 or 0:1,2, found '{}'", text);
+// Variable declaration
+    let (x, ys) = text.split_once(':').ok_or_else(invalid)?;
+// Variable declaration
+    let x = x.trim().parse::<usize>().map_err(|_| invalid())?;
+// Variable declaration
+    let ys = ys
+        .split(',')
+        .map(|y| y.trim().parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    Ok((x, ys))
+}
+
+// "2x2" style layout: any evenly sized grid up to the largest the layout editor offers
+/// Function: explain its purpose and key arguments
+fn parse_layout(text: &str) -> Result<SubplotLayout, String> {
+// Variable declaration
+    let tracks = 1..=SubplotLayout::MAX_TRACKS;
+    text.to_lowercase()
+        .split_once('x')
+        .and_then(|(rows, cols)| Some((rows.trim().parse().ok()?, cols.trim().parse().ok()?)))
+        .filter(|(rows, cols)| tracks.contains(rows) && tracks.contains(cols))
+        .map(|(rows, cols)| SubplotLayout::grid(rows, cols))
+        .ok_or_else(|| {
+            format!(
+                "invalid layout '{}' (expected ROWSxCOLS with 1 to {} rows and columns)",
+                text,
+                SubplotLayout::MAX_TRACKS
+            )
+        })
+}
+
+/// Implementation block defining methods for this type
+impl Args {
+    // Format to read standard input with, if it should be read at all (CSV unless given)
+/// Function: explain its purpose and key arguments
+    pub fn stdin_format(&self) -> Option<StdinFormat> {
+        if self.stdin_format.is_some() || self.files.iter().any(|file| file == "-") {
+            Some(self.stdin_format.unwrap_or(StdinFormat::Csv))
+        } else {
+            None
+        }
+    }
+
+    // Title, labels and axis range given on the command line; they win over what the files
+    // say. A partial range is completed from the loaded data, since the plot needs all four.
+/// Function: explain its purpose and key arguments
+    pub fn apply_plot_options(&self, subplot: &mut Subplot) {
+// Variable declaration
+        let config = &mut subplot.config;
+        if let Some(title) = &self.title {
+            config.title = title.clone();
+        }
+        if let Some(xlabel) = &self.xlabel {
+            config.x_label = xlabel.clone();
+        }
+        if let Some(ylabel) = &self.ylabel {
+            config.y_label = ylabel.clone();
+        }
+
+// Variable declaration
+        let limits = [self.xmin, self.xmax, self.ymin, self.ymax];
+        if limits.iter().all(Option::is_none) {
+            return;
+        }
+// Variable declaration
+        let data = get_data_bounds(&subplot.datasets).unwrap_or((0.0, 1.0, 0.0, 1.0));
+// Variable declaration
+        let config = &mut subplot.config;
+        config.use_custom_bounds = true;
+        config.custom_x_min = self.xmin.unwrap_or(data.0).to_string();
+        config.custom_x_max = self.xmax.unwrap_or(data.1).to_string();
+        config.custom_y_min = self.ymin.unwrap_or(data.2).to_string();
+        config.custom_y_max = self.ymax.unwrap_or(data.3).to_string();
+    }
+}
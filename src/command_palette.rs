@@ -0,0 +1,281 @@
+// Import external modules or crates needed in command_palette.rs
+use crate::app::LayoutPreset;
+// Import external modules or crates needed in command_palette.rs
+use eframe::egui;
+// Import external modules or crates needed in command_palette.rs
+use std::cmp::Reverse;
+
+// Tool windows the palette can open or close
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Enum representing a set of related values in command_palette.rs module
+pub enum ToolWindow {
+    Subplots,
+    AxisControls,
+    DataProcessing,
+    Comparison,
+    Colors,
+    LegendAndFonts,
+    Annotations,
+    History,
+    ExportSettings,
+    Preferences,
+    DataEditor,
+    Templates,
+}
+
+/// Implementation block defining methods for this type
+impl ToolWindow {
+    pub const ALL: [ToolWindow; 12] = [
+        ToolWindow::Subplots,
+        ToolWindow::AxisControls,
+        ToolWindow::DataProcessing,
+        ToolWindow::Comparison,
+        ToolWindow::Colors,
+        ToolWindow::LegendAndFonts,
+        ToolWindow::Annotations,
+        ToolWindow::History,
+        ToolWindow::ExportSettings,
+        ToolWindow::Preferences,
+        ToolWindow::DataEditor,
+        ToolWindow::Templates,
+    ];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            ToolWindow::Subplots => "Subplots",
+            ToolWindow::AxisControls => "Axis Controls",
+            ToolWindow::DataProcessing => "Data Processing",
+            ToolWindow::Comparison => "Compare Datasets",
+            ToolWindow::Colors => "Colors",
+            ToolWindow::LegendAndFonts => "Legend & Fonts",
+            ToolWindow::Annotations => "Annotations",
+            ToolWindow::History => "History",
+            ToolWindow::ExportSettings => "Export Settings",
+            ToolWindow::Preferences => "Preferences",
+            ToolWindow::DataEditor => "Data Editor",
+            ToolWindow::Templates => "Style Templates",
+        }
+    }
+}
+
+// Everything the palette can run. Processing commands act on the dataset selected in
+// the Data Processing window with the settings last chosen there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in command_palette.rs module
+pub enum Command {
+    OpenFiles,
+    OpenFolder,
+    OpenUrl,
+    OpenSession,
+    SaveSession,
+    ExportPlot,
+    ExportScript,
+    ExportFitReport,
+    CopyPlot,
+    PasteData,
+    NewEmptyDataset,
+    Undo,
+    Redo,
+    ClearActiveSubplot,
+    DuplicateSubplot,
+    ToggleWindow(ToolWindow),
+    SetLayout(LayoutPreset),
+    ToggleGrid,
+    ToggleLegend,
+    ToggleCrosshair,
+    ToggleDiagnostics,
+    ToggleDarkMode,
+    RollingAverage,
+    SavitzkyGolay,
+    GaussianSmoothing,
+    Derivative,
+    Integral,
+    PowerSpectrum,
+    Normalize,
+    FindPeaks,
+    FindCrossings,
+    BlockAverage,
+    Statistics,
+    BoxPlots,
+    ViolinPlots,
+}
+
+/// Function: explain its purpose and key arguments
+pub fn all_commands() -> Vec<(String, Command)> {
+// Variable declaration
+    let mut commands: Vec<(String, Command)> = [
+        ("Open file(s)…", Command::OpenFiles),
+        ("Open folder…", Command::OpenFolder),
+        ("Open URL…", Command::OpenUrl),
+        ("Open session…", Command::OpenSession),
+        ("Save session…", Command::SaveSession),
+        ("Export plot (PNG or PDF)…", Command::ExportPlot),
+        ("Export as Python script (matplotlib)…", Command::ExportScript),
+        ("Export fit report…", Command::ExportFitReport),
+        ("Copy plot to clipboard", Command::CopyPlot),
+        ("Paste data as new dataset", Command::PasteData),
+        ("New empty dataset", Command::NewEmptyDataset),
+        ("Undo", Command::Undo),
+        ("Redo", Command::Redo),
+        ("Clear active subplot", Command::ClearActiveSubplot),
+        ("Duplicate active subplot", Command::DuplicateSubplot),
+        ("Toggle grid", Command::ToggleGrid),
+        ("Toggle legend", Command::ToggleLegend),
+        ("Toggle crosshair", Command::ToggleCrosshair),
+        ("Toggle frame diagnostics (FPS, CPU)", Command::ToggleDiagnostics),
+        ("Toggle dark mode", Command::ToggleDarkMode),
+        ("Add rolling average", Command::RollingAverage),
+        ("Add Savitzky–Golay smoothing", Command::SavitzkyGolay),
+        ("Add Gaussian smoothing", Command::GaussianSmoothing),
+        ("Add derivative", Command::Derivative),
+        ("Add cumulative integral", Command::Integral),
+        ("Add power spectrum", Command::PowerSpectrum),
+        ("Normalize dataset", Command::Normalize),
+        ("Find peaks", Command::FindPeaks),
+        ("Find threshold crossings", Command::FindCrossings),
+        ("Block average", Command::BlockAverage),
+        ("Compute statistics", Command::Statistics),
+        ("Show as box plots", Command::BoxPlots),
+        ("Show as violin plots", Command::ViolinPlots),
+    ]
+    .into_iter()
+    .map(|(label, command)| (label.to_string(), command))
+    .collect();
+    commands.extend(
+        ToolWindow::ALL
+            .into_iter()
+            .map(|window| (format!("Toggle {} window", window.to_string()), Command::ToggleWindow(window))),
+    );
+    commands.extend(
+        LayoutPreset::ALL
+            .into_iter()
+            .map(|layout| (format!("Layout: {}", layout.to_string()), Command::SetLayout(layout))),
+    );
+    commands
+}
+
+// How well `query` matches `label`: its characters must appear in order (ignoring case).
+// Runs of consecutive characters and matches at the start of words score higher; None
+// when there is no match.
+/// Function: explain its purpose and key arguments
+pub fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+// Variable declaration
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+// Variable declaration
+    let mut score = 0;
+// Variable declaration
+    let mut position = 0;
+// Variable declaration
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+// Variable declaration
+        let found = position + label[position..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !label[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    // Prefer shorter labels among equally good matches
+    Some(score * 100 - label.len() as i32)
+}
+
+// Ctrl+P popup: type to filter, arrow keys to choose, Enter to run, Escape to close
+#[derive(Default)]
+/// Data structure used in command_palette.rs module
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+}
+
+/// Implementation block defining methods for this type
+impl CommandPalette {
+/// Function: explain its purpose and key arguments
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    // Draw the palette; returns the command chosen this frame, if any
+/// Function: explain its purpose and key arguments
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+// Variable declaration
+        let mut matches: Vec<(i32, String, Command)> = all_commands()
+            .into_iter()
+            .filter_map(|(label, command)| Some((fuzzy_score(&self.query, &label)?, label, command)))
+            .collect();
+        matches.sort_by_key(|(score, ..)| Reverse(*score));
+
+// Variable declaration
+        let (down, up, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+// Variable declaration
+        let mut chosen = None;
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .fixed_size(egui::vec2(420.0, 320.0))
+            .show(ctx, |ui| {
+// Variable declaration
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command…")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (i, (_, label, command)) in matches.iter().enumerate() {
+// Variable declaration
+                        let row = ui.selectable_label(i == self.selected, label);
+                        if i == self.selected && (down || up) {
+                            row.scroll_to_me(None);
+                        }
+                        if row.clicked() {
+                            chosen = Some(*command);
+                        }
+                    }
+                    if matches.is_empty() {
+                        ui.weak("No matching command");
+                    }
+                });
+            });
+
+        if enter {
+            chosen = chosen.or_else(|| matches.get(self.selected).map(|(_, _, command)| *command));
+        }
+        if chosen.is_some() || escape {
+            self.open = false;
+        }
+        chosen
+    }
+}
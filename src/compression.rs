@@ -0,0 +1,86 @@
+// Import external modules or crates needed in compression.rs
+use flate2::read::MultiGzDecoder;
+// Import external modules or crates needed in compression.rs
+use std::fs::File;
+// Import external modules or crates needed in compression.rs
+use std::io::{BufReader, Read};
+// Import external modules or crates needed in compression.rs
+use std::path::{Path, PathBuf};
+// Import external modules or crates needed in compression.rs
+use xz2::read::XzDecoder;
+
+// Extensions of compressed files, which are decompressed while they are read
+pub const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "xz"];
+
+// Formats that are read with random access, which a decompressing stream cannot offer
+const RANDOM_ACCESS_EXTENSIONS: &[&str] =
+    &["xlsx", "xlsm", "xlsb", "xls", "ods", "parquet", "pq", "h5", "hdf5", "he5", "hdf", "npz"];
+
+// Lowercase extension of `path`, or None when it has none
+/// Function: explain its purpose and key arguments
+fn extension(path: &Path) -> Option<String> {
+    path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase)
+}
+
+/// Function: explain its purpose and key arguments
+pub fn is_compressed(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| COMPRESSED_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// The file name without its compression extension: data.xvg.gz becomes data.xvg
+/// Function: explain its purpose and key arguments
+pub fn uncompressed_path(path: &Path) -> PathBuf {
+    if is_compressed(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+// Lowercase extension that says what the data is, looking past .gz or .xz
+/// Function: explain its purpose and key arguments
+pub fn data_extension(path: &Path) -> Option<String> {
+    extension(&uncompressed_path(path))
+}
+
+// Refuse compressed files of formats that need random access, rather than let their
+// reader fail on the compressed bytes with a confusing message
+/// Function: explain its purpose and key arguments
+pub fn check_readable(path: &Path) -> Result<(), String> {
+    match data_extension(path) {
+        Some(ext) if is_compressed(path) && RANDOM_ACCESS_EXTENSIONS.contains(&ext.as_str()) => {
+            Err(format!(".{} files cannot be read compressed; decompress the file first", ext))
+        }
+        _ => Ok(()),
+    }
+}
+
+// The contents of `path`, decompressed on the fly when it is a .gz or .xz file, so large
+// compressed outputs never have to be unpacked on disk
+/// Function: explain its purpose and key arguments
+pub fn open_data(path: &Path) -> std::io::Result<Box<dyn Read>> {
+// Variable declaration
+    let file = BufReader::new(File::open(path)?);
+    Ok(match extension(path).as_deref() {
+        // Concatenated gzip members (as written by pigz or by appending) are read as one stream
+        Some("gz") => Box::new(MultiGzDecoder::new(file)),
+        Some("xz") => Box::new(XzDecoder::new_multi_decoder(file)),
+        _ => Box::new(file),
+    })
+}
+
+/// Function: explain its purpose and key arguments
+pub fn read_data(path: &Path) -> std::io::Result<Vec<u8>> {
+// Variable declaration
+    let mut bytes = Vec::new();
+    open_data(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Function: explain its purpose and key arguments
+pub fn read_data_to_string(path: &Path) -> std::io::Result<String> {
+// Variable declaration
+    let mut text = String::new();
+    open_data(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
@@ -0,0 +1,261 @@
+// Import external modules or crates needed in config.rs
+use crate::app::{FontSize, PlotterApp};
+// Import external modules or crates needed in config.rs
+use crate::palette::Palette;
+// Import external modules or crates needed in config.rs
+use std::path::PathBuf;
+// Import external modules or crates needed in config.rs
+use toml_edit::{value, Array, Document, Item};
+
+// User preferences kept in ~/.config/cactusplot/config.toml between launches.
+// A missing file or key falls back to the built-in default.
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in config.rs module
+pub struct Preferences {
+    pub dark_mode: bool,
+    pub font_size: FontSize,
+    pub palette: Palette,
+    pub export_width: u32,
+    pub export_height: u32,
+    pub x_padding_percent: f64, // Padding of new subplots
+    pub y_padding_percent: f64,
+}
+
+/// Implementation block defining methods for this type
+impl Default for Preferences {
+/// Function: explain its purpose and key arguments
+    fn default() -> Self {
+        Self {
+            dark_mode: true,
+            font_size: FontSize::Medium,
+            palette: Palette::default(),
+            export_width: 1200,
+            export_height: 800,
+            x_padding_percent: 5.0,
+            y_padding_percent: 5.0,
+        }
+    }
+}
+
+/// Implementation block defining methods for this type
+impl Preferences {
+    // Use the preferences for a freshly started app (a session opened later overrides them)
+/// Function: explain its purpose and key arguments
+    pub fn apply_to(&self, app: &mut PlotterApp) {
+        app.dark_mode = self.dark_mode;
+        app.tick_font_size = self.font_size.clone();
+        app.palette = self.palette;
+        app.export_settings.width = self.export_width;
+        app.export_settings.height = self.export_height;
+        for subplot in &mut app.subplots {
+            subplot.config.x_padding_percent = self.x_padding_percent;
+            subplot.config.y_padding_percent = self.y_padding_percent;
+        }
+        app.preferences = self.clone();
+    }
+
+/// Function: explain its purpose and key arguments
+    fn from_document(doc: &Document) -> Self {
+// Variable declaration
+        let defaults = Self::default();
+// Variable declaration
+        let float = |key: &str| {
+            doc.get(key)
+                .and_then(|item| item.as_float().or_else(|| item.as_integer().map(|v| v as f64)))
+                .filter(|v| *v >= 0.0)
+        };
+// Variable declaration
+        let size = |key: &str| {
+            doc.get(key)
+                .and_then(Item::as_integer)
+                .and_then(|v| u32::try_from(v).ok())
+                .filter(|v| *v > 0)
+        };
+        Self {
+            dark_mode: doc.get("dark_mode").and_then(Item::as_bool).unwrap_or(defaults.dark_mode),
+            font_size: doc
+                .get("font_size")
+                .and_then(Item::as_str)
+                .and_then(font_size_from_name)
+                .unwrap_or(defaults.font_size),
+            palette: doc
+                .get("palette")
+                .and_then(Item::as_str)
+                .and_then(Palette::from_key)
+                .unwrap_or(defaults.palette),
+            export_width: size("export_width").unwrap_or(defaults.export_width),
+            export_height: size("export_height").unwrap_or(defaults.export_height),
+            x_padding_percent: float("x_padding_percent").unwrap_or(defaults.x_padding_percent),
+            y_padding_percent: float("y_padding_percent").unwrap_or(defaults.y_padding_percent),
+        }
+    }
+
+    // Write the values into `doc`, leaving comments and unknown keys alone
+/// Function: explain its purpose and key arguments
+    fn write_to(&self, doc: &mut Document) {
+        doc["dark_mode"] = value(self.dark_mode);
+        doc["font_size"] = value(font_size_name(&self.font_size));
+        doc["palette"] = value(self.palette.key());
+        doc["export_width"] = value(self.export_width as i64);
+        doc["export_height"] = value(self.export_height as i64);
+        doc["x_padding_percent"] = value(self.x_padding_percent);
+        doc["y_padding_percent"] = value(self.y_padding_percent);
+    }
+}
+
+// Most files listed in the Recent menu
+const MAX_RECENT_FILES: usize = 10;
+
+// Data files opened in the window, kept in the config file next to the preferences but
+// saved as soon as they change rather than from the preferences window
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Data structure used in config.rs module
+pub struct RecentFiles {
+    pub files: Vec<PathBuf>,       // Most recent first
+    pub last_opened: Vec<PathBuf>, // Files opened together most recently, for --reopen-last
+}
+
+/// Implementation block defining methods for this type
+impl RecentFiles {
+    // Put `paths` at the top of the list, as the files opened last
+/// Function: explain its purpose and key arguments
+    pub fn add(&mut self, paths: &[PathBuf]) {
+        self.files.retain(|file| !paths.contains(file));
+        for path in paths.iter().rev() {
+            self.files.insert(0, path.clone());
+        }
+        self.files.truncate(MAX_RECENT_FILES);
+        self.last_opened = paths.to_vec();
+    }
+
+    // Drop a file that could not be read, so it is not offered again
+/// Function: explain its purpose and key arguments
+    pub fn forget(&mut self, path: &PathBuf) {
+        self.files.retain(|file| file != path);
+        self.last_opened.retain(|file| file != path);
+    }
+
+/// Function: explain its purpose and key arguments
+    fn from_document(doc: &Document) -> Self {
+// Variable declaration
+        let paths = |key: &str| -> Vec<PathBuf> {
+            doc.get(key)
+                .and_then(Item::as_array)
+                .map(|array| array.iter().filter_map(|v| v.as_str()).map(PathBuf::from).collect())
+                .unwrap_or_default()
+        };
+        Self {
+            files: paths("recent_files"),
+            last_opened: paths("last_opened"),
+        }
+    }
+
+    // Paths that are not valid UTF-8 cannot be written to TOML and are left out
+/// Function: explain its purpose and key arguments
+    fn write_to(&self, doc: &mut Document) {
+// Variable declaration
+        let array = |paths: &[PathBuf]| paths.iter().filter_map(|path| path.to_str()).collect::<Array>();
+        doc["recent_files"] = value(array(&self.files));
+        doc["last_opened"] = value(array(&self.last_opened));
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn font_size_name(size: &FontSize) -> &'static str {
+    match size {
+        FontSize::Small => "small",
+        FontSize::Medium => "medium",
+        FontSize::Large => "large",
+        FontSize::ExtraLarge => "extra_large",
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn font_size_from_name(name: &str) -> Option<FontSize> {
+    match name.trim().to_lowercase().replace(['-', ' '], "_").as_str() {
+        "small" => Some(FontSize::Small),
+        "medium" => Some(FontSize::Medium),
+        "large" => Some(FontSize::Large),
+        "extra_large" | "extralarge" => Some(FontSize::ExtraLarge),
+        _ => None,
+    }
+}
+
+// $XDG_CONFIG_HOME/cactusplot, or ~/.config/cactusplot
+/// Function: explain its purpose and key arguments
+pub fn config_dir() -> Option<PathBuf> {
+// Variable declaration
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("cactusplot"))
+}
+
+// $XDG_CACHE_HOME/cactusplot, or ~/.cache/cactusplot; for files that can be fetched again
+/// Function: explain its purpose and key arguments
+pub fn cache_dir() -> Option<PathBuf> {
+// Variable declaration
+    let cache_home = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache_home.join("cactusplot"))
+}
+
+/// Function: explain its purpose and key arguments
+pub fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// Function: explain its purpose and key arguments
+fn read_document() -> Result<Document, Box<dyn std::error::Error>> {
+// Variable declaration
+    let path = config_path().ok_or("Cannot find the home directory")?;
+    if !path.exists() {
+        return Ok(Document::new());
+    }
+// Variable declaration
+    let contents = std::fs::read_to_string(&path)?;
+    contents
+        .parse::<Document>()
+        .map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+// Defaults when there is no config file yet
+/// Function: explain its purpose and key arguments
+pub fn load_preferences() -> Result<Preferences, Box<dyn std::error::Error>> {
+    Ok(Preferences::from_document(&read_document()?))
+}
+
+/// Function: explain its purpose and key arguments
+pub fn save_preferences(preferences: &Preferences) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    update_document(|doc| preferences.write_to(doc))
+}
+
+// An empty list when there is no config file yet
+/// Function: explain its purpose and key arguments
+pub fn load_recent_files() -> Result<RecentFiles, Box<dyn std::error::Error>> {
+    Ok(RecentFiles::from_document(&read_document()?))
+}
+
+/// Function: explain its purpose and key arguments
+pub fn save_recent_files(recent: &RecentFiles) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    update_document(|doc| recent.write_to(doc))
+}
+
+// Change some keys of the config file and write it back
+/// Function: explain its purpose and key arguments
+fn update_document(change: impl FnOnce(&mut Document)) -> Result<PathBuf, Box<dyn std::error::Error>> {
+// Variable declaration
+    let path = config_path().ok_or("Cannot find the home directory")?;
+    // Keep whatever else the user wrote in the file; start over if it no longer parses
+// Variable declaration
+    let mut doc = read_document().unwrap_or_default();
+    change(&mut doc);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, doc.to_string())?;
+    Ok(path)
+}
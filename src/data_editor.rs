@@ -1,11 +1,28 @@
 // Import external modules or crates needed in data_editor.rs
-use crate::dataset::Dataset;
+use crate::dataset::{Dataset, LinePattern, PlotType};
 // Import external modules or crates needed in data_editor.rs
-use crate::utils::get_default_color;
+use crate::palette::Palette;
+// Import external modules or crates needed in data_editor.rs
+use crate::points::PointStore;
+// Import external modules or crates needed in data_editor.rs
+use crate::utils::{finite_points_in_x_range, format_number, pick_csv_save_path, student_t_critical, student_t_p_value};
+// Import external modules or crates needed in data_editor.rs
+use crate::expression::Expression;
+// Import external modules or crates needed in data_editor.rs
+use crate::io::{TextImport, TEXT_TABLE_EXTENSIONS};
 // Import external modules or crates needed in data_editor.rs
 use eframe::egui;
 // Import external modules or crates needed in data_editor.rs
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+// Import external modules or crates needed in data_editor.rs
+use std::path::{Path, PathBuf};
+
+// Size of the cells of the Data Editor grid
+const CELL_WIDTH: f32 = 90.0;
+const ROW_LABEL_WIDTH: f32 = 50.0;
+const ROW_HEIGHT: f32 = 22.0;
+// Largest |x/τ| the exponential fit starts from, so its start amplitude stays finite
+const MAX_START_EXPONENT: f64 = 50.0;
 
 #[derive(Debug, Clone)]
 /// Data structure used in data_editor.rs module
@@ -35,6 +52,12 @@ pub struct SpreadsheetData {
     pub num_cols: usize,
     pub column_headers: Vec<String>,
     pub dataset_columns: Vec<Option<usize>>, // Maps column index to dataset index
+    pub column_formulas: Vec<String>, // "=C1*2 + C2" computes the column from others; empty for typed values
+    pub formula_errors: HashMap<usize, String>, // Why a column's formula could not be evaluated
+    pub row_filter: String, // e.g. "C2 > 0.5": only matching rows are shown and feed the datasets
+    pub filter_error: Option<String>, // Why the row filter could not be used
+    pub hidden_rows: HashSet<usize>, // Rows the filter leaves out
+    pub row_origins: HashMap<usize, usize>, // Row a sorted row was at before sorting (absent: not moved)
 }
 
 /// Implementation block defining methods for this type
@@ -45,9 +68,12 @@ impl Default for SpreadsheetData {
         let mut headers = Vec::new();
 // Variable declaration
         let mut dataset_columns = Vec::new();
+// Variable declaration
+        let mut column_formulas = Vec::new();
         for i in 0..10 {
             headers.push(format!("Col {}", i + 1));
             dataset_columns.push(None);
+            column_formulas.push(String::new());
         }
         
         Self {
@@ -56,16 +82,323 @@ impl Default for SpreadsheetData {
             num_cols: 10,
             column_headers: headers,
             dataset_columns,
+            column_formulas,
+            formula_errors: HashMap::new(),
+            row_filter: String::new(),
+            filter_error: None,
+            hidden_rows: HashSet::new(),
+            row_origins: HashMap::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// Per-point values of a loaded dataset that have no column in the grid (uncertainties,
+// color values, band widths). They are kept by the row each point was loaded into, so
+// they stay with their point when rows are sorted and come back when a filter that left
+// the point out is cleared.
+#[derive(Debug, Clone, Default)]
+/// Data structure used in data_editor.rs module
+pub struct PointExtras {
+    point_at: HashMap<usize, usize>, // Original row -> index into the vectors below
+    x_err: Option<Vec<f64>>,
+    y_err: Option<Vec<f64>>,
+    color_values: Option<Vec<f64>>,
+    band: Option<Vec<f64>>,
+}
+
+/// Implementation block defining methods for this type
+impl PointExtras {
+    // The values of the points in `rows` (original rows, in dataset order); a vector is
+    // dropped when any of the rows has no value in it, e.g. a row typed in by hand
+/// Function: explain its purpose and key arguments
+    fn apply_to(&self, dataset: &mut Dataset, rows: &[usize]) {
+// Variable declaration
+        let indices: Option<Vec<usize>> = rows.iter().map(|row| self.point_at.get(row).copied()).collect();
+// Variable declaration
+        let pick = |values: &Option<Vec<f64>>| -> Option<Vec<f64>> {
+// Variable declaration
+            let values = values.as_ref()?;
+            indices.as_ref()?.iter().map(|i| values.get(*i).copied()).collect()
+        };
+        dataset.x_err = pick(&self.x_err);
+        dataset.y_err = pick(&self.y_err);
+        dataset.color_values = pick(&self.color_values);
+        dataset.band = pick(&self.band);
+    }
+}
+
+// A parsed column formula: the expression and the column behind each of its parameters
+/// Data structure used in data_editor.rs module
+struct ColumnFormula {
+    expression: Expression,
+    inputs: Vec<usize>, // Column read for each parameter of the expression, in order
+}
+
+/// Implementation block defining methods for this type
+impl ColumnFormula {
+    // Value in `row`, or None when an input cell is empty or not a number, or the result
+    // is not finite (e.g. division by zero)
+/// Function: explain its purpose and key arguments
+    fn value_at(&self, cells: &HashMap<(usize, usize), DataCell>, row: usize) -> Option<f64> {
+// Variable declaration
+        let inputs: Option<Vec<f64>> = self
+            .inputs
+            .iter()
+            .map(|input| cells.get(&(row, *input)).and_then(|cell| cell.parsed_value))
+            .collect();
+        inputs
+            .map(|inputs| self.expression.evaluate((row + 1) as f64, &inputs))
+            .filter(|value| value.is_finite())
+    }
+}
+
+// Parse a column formula. Column n is written Cn or coln (C1 is the first column) and x
+// stands for the row number, counted from 1. None when the text is empty or only "=".
+/// Function: explain its purpose and key arguments
+fn parse_column_formula(text: &str, num_cols: usize) -> Result<Option<ColumnFormula>, String> {
+// Variable declaration
+    let body = text.trim().trim_start_matches('=').trim();
+    if body.is_empty() {
+        return Ok(None);
+    }
+// Variable declaration
+    let expression = Expression::parse(body).map_err(|e| e.to_string())?;
+// Variable declaration
+    let inputs = expression
+        .parameters
+        .iter()
+        .map(|name| {
+// Variable declaration
+            let number = name
+                .to_lowercase()
+                .strip_prefix("col")
+                .or_else(|| name.strip_prefix(['C', 'c']))
+                .and_then(|number| number.parse::<usize>().ok())
+                .ok_or_else(|| format!("Unknown name '{}'; refer to columns as C1, C2, …", name))?;
+            if number == 0 || number > num_cols {
+                return Err(format!("There is no column {}", name));
+            }
+            Ok(number - 1)
+        })
+        .collect::<Result<Vec<usize>, String>>()?;
+    Ok(Some(ColumnFormula { expression, inputs }))
+}
+
+// Comparison a row filter makes between its two sides
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in data_editor.rs module
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+/// Implementation block defining methods for this type
+impl Comparison {
+    // Two-character operators first, so ">=" is not read as ">"
+    const OPERATORS: [(&'static str, Comparison); 7] = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ];
+
+/// Function: explain its purpose and key arguments
+    fn holds(self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+// A row filter such as "C2 > 0.5" or "abs(C3 - C1) <= 2": two column formulas compared
+/// Data structure used in data_editor.rs module
+struct RowFilter {
+    left: ColumnFormula,
+    comparison: Comparison,
+    right: ColumnFormula,
+}
+
+// Parse a row filter; None when the text is empty
+/// Function: explain its purpose and key arguments
+fn parse_row_filter(text: &str, num_cols: usize) -> Result<Option<RowFilter>, String> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+// Variable declaration
+    let (position, operator, comparison) = (0..text.len())
+        .filter(|i| text.is_char_boundary(*i))
+        .find_map(|i| {
+            Comparison::OPERATORS
+                .iter()
+                .find(|(operator, _)| text[i..].starts_with(operator))
+                .map(|(operator, comparison)| (i, *operator, *comparison))
+        })
+        .ok_or("Compare two values with <, <=, >, >=, == or !=, e.g. C2 > 0.5")?;
+// Variable declaration
+    let side = |text: &str| parse_column_formula(text, num_cols)?.ok_or_else(|| "Missing value to compare".to_string());
+    Ok(Some(RowFilter {
+        left: side(&text[..position])?,
+        comparison,
+        right: side(&text[position + operator.len()..])?,
+    }))
+}
+
+/// Implementation block defining methods for this type
+impl SpreadsheetData {
+    // Recompute every column that has a formula. A formula is evaluated after the formulas
+    // of the columns it reads, so chains like C3 = C2*2 with C2 = C1+1 work in any column
+    // order; columns that depend on each other in a circle are reported instead.
+/// Function: explain its purpose and key arguments
+    pub fn recalculate_formulas(&mut self) {
+        self.formula_errors.clear();
+// Variable declaration
+        let mut waiting = Vec::new();
+        for (col, text) in self.column_formulas.iter().enumerate().take(self.num_cols) {
+            match parse_column_formula(text, self.num_cols) {
+                Ok(Some(formula)) => waiting.push((col, formula)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.formula_errors.insert(col, e);
+                }
+            }
+        }
+
+        // Evaluate, one at a time, a formula none of whose inputs is still waiting
+        while let Some(i) = waiting
+            .iter()
+            .position(|(_, formula)| formula.inputs.iter().all(|input| waiting.iter().all(|(col, _)| col != input)))
+        {
+// Variable declaration
+            let (col, formula) = waiting.remove(i);
+            self.evaluate_formula(col, &formula);
+        }
+        for (col, _) in waiting {
+            self.formula_errors.insert(col, "Circular reference between column formulas".to_string());
+        }
+        // Computed values can change which rows pass the filter
+        self.apply_row_filter();
+    }
+
+    // Work out again which rows the filter hides. Rows where either side has no value
+    // (empty or text cells) are hidden; an unusable filter hides nothing.
+/// Function: explain its purpose and key arguments
+    pub fn apply_row_filter(&mut self) {
+        self.hidden_rows.clear();
+        match parse_row_filter(&self.row_filter, self.num_cols) {
+            Ok(filter) => {
+                self.filter_error = None;
+                if let Some(filter) = filter {
+                    self.hidden_rows = (0..self.num_rows)
+                        .filter(|row| {
+                            !matches!(
+                                (filter.left.value_at(&self.cells, *row), filter.right.value_at(&self.cells, *row)),
+                                (Some(left), Some(right)) if filter.comparison.holds(left, right)
+                            )
+                        })
+                        .collect();
+                }
+            }
+            Err(e) => self.filter_error = Some(e),
+        }
+    }
+
+    // (X, Y) points from two columns, in row order, from the rows where both cells hold a
+    // number and the filter does not hide
+/// Function: explain its purpose and key arguments
+    pub fn column_points(&self, x_col: usize, y_col: usize) -> Vec<[f64; 2]> {
+        (0..self.num_rows)
+            .filter(|row| !self.hidden_rows.contains(row))
+            .filter_map(|row| {
+                Some([
+                    self.cells.get(&(row, x_col))?.parsed_value?,
+                    self.cells.get(&(row, y_col))?.parsed_value?,
+                ])
+            })
+            .collect()
+    }
+
+    // Reorder whole rows by the numbers in `col`; rows where it is empty or not a number
+    // keep their order after the others. Formula columns move with their rows and are
+    // then recalculated.
+/// Function: explain its purpose and key arguments
+    pub fn sort_rows(&mut self, col: usize, descending: bool) {
+// Variable declaration
+        let key = |row: usize| self.cells.get(&(row, col)).and_then(|cell| cell.parsed_value);
+// Variable declaration
+        let mut order: Vec<usize> = (0..self.num_rows).collect();
+        order.sort_by(|a, b| match (key(*a), key(*b)) {
+            (Some(a), Some(b)) if descending => b.total_cmp(&a),
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+// Variable declaration
+        let new_row: HashMap<usize, usize> = order.iter().enumerate().map(|(new, old)| (*old, new)).collect();
+        self.cells = std::mem::take(&mut self.cells)
+            .into_iter()
+            .map(|((row, col), cell)| ((new_row.get(&row).copied().unwrap_or(row), col), cell))
+            .collect();
+        self.row_origins = order
+            .iter()
+            .enumerate()
+            .map(|(new, old)| (new, self.row_origin(*old)))
+            .filter(|(new, origin)| new != origin)
+            .collect();
+        self.recalculate_formulas();
+    }
+
+    // Row `row` was at before any sorting
+/// Function: explain its purpose and key arguments
+    pub fn row_origin(&self, row: usize) -> usize {
+        self.row_origins.get(&row).copied().unwrap_or(row)
+    }
+
+    // Fill one column from its formula, leaving empty the rows where it has no value
+/// Function: explain its purpose and key arguments
+    fn evaluate_formula(&mut self, col: usize, formula: &ColumnFormula) {
+        for row in 0..self.num_rows {
+            match formula.value_at(&self.cells, row) {
+                Some(value) => {
+                    self.cells.insert(
+                        (row, col),
+                        DataCell {
+                            value: value.to_string(),
+                            parsed_value: Some(value),
+                            ..DataCell::default()
+                        },
+                    );
+                }
+                None => {
+                    self.cells.remove(&(row, col));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 /// Enum representing a set of related values in data_editor.rs module
 pub enum FitModel {
     Linear,
     Sigmoid,
     Hill,
+    Exponential,
+    Gaussian,
+    Polynomial, // Degree is chosen separately (DataEditor::polynomial_degree)
+    Custom,     // Formula typed by the user (DataEditor::custom_fit_expression)
 }
 
 /// Implementation block defining methods for this type
@@ -76,8 +409,62 @@ impl FitModel {
             FitModel::Linear => "Linear (y = ax + b)",
             FitModel::Sigmoid => "Sigmoid (y = a / (1 + exp(-b(x-c))))",
             FitModel::Hill => "Hill (y = (a * x^n) / (k^n + x^n))",
+            FitModel::Exponential => "Exponential decay (y = a * exp(-x/t) + c)",
+            FitModel::Gaussian => "Gaussian (y = a * exp(-(x-m)^2 / (2s^2)) + c)",
+            FitModel::Polynomial => "Polynomial (y = c0 + c1*x + ... + cn*x^n)",
+            FitModel::Custom => "Custom expression",
         }
     }
+
+    // Parameter names and formula of the model; None for Polynomial and Custom, whose
+    // parameters depend on settings in the dialog
+/// Function: explain its purpose and key arguments
+    fn formula(&self) -> Option<ModelFormula> {
+        Some(match self {
+            FitModel::Linear => ModelFormula { names: &["slope", "intercept"], function: |x, p| p[0] * x + p[1] },
+            FitModel::Sigmoid => ModelFormula {
+                names: &["amplitude", "steepness", "inflection", "offset"],
+                function: |x, p| p[3] + p[0] / (1.0 + (-p[1] * (x - p[2])).exp()),
+            },
+            FitModel::Hill => ModelFormula {
+                names: &["max_response", "k_half", "hill_coeff"],
+                // Defined for x > 0 only
+                function: |x, p| if x > 0.0 { p[0] * x.powf(p[2]) / (p[1].powf(p[2]) + x.powf(p[2])) } else { 0.0 },
+            },
+            FitModel::Exponential => ModelFormula {
+                names: &["amplitude", "tau", "offset"],
+                function: |x, p| p[0] * (-x / p[1]).exp() + p[2],
+            },
+            FitModel::Gaussian => ModelFormula {
+                names: &["amplitude", "center", "sigma", "offset"],
+                function: |x, p| p[0] * (-(x - p[1]).powi(2) / (2.0 * p[2] * p[2])).exp() + p[3],
+            },
+            FitModel::Polynomial | FitModel::Custom => return None,
+        })
+    }
+}
+
+// Parameter names of a fit model and its formula y = f(x, parameters), which takes the
+// parameters in the order of the names. The fits and the global fit all use this table.
+#[derive(Debug, Clone, Copy)]
+/// Data structure used in data_editor.rs module
+struct ModelFormula {
+    names: &'static [&'static str],
+    function: fn(f64, &[f64]) -> f64,
+}
+
+/// Implementation block defining methods for this type
+impl ModelFormula {
+/// Function: explain its purpose and key arguments
+    fn parameter_names(&self) -> Vec<String> {
+        self.names.iter().map(|name| name.to_string()).collect()
+    }
+}
+
+// y = c0 + c1 x + ... + cn x^n
+/// Function: explain its purpose and key arguments
+fn polynomial(x: f64, coefficients: &[f64]) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
 }
 
 #[derive(Debug, Clone)]
@@ -88,9 +475,53 @@ pub struct FitResult {
     pub parameter_names: Vec<String>,
     pub r_squared: f64,
     pub fitted_points: Vec<[f64; 2]>,
+    pub residuals: Vec<[f64; 2]>, // (x, y_data - y_fit) at every data point
+    pub source_name: String,      // Name of the dataset that was fitted
     pub equation_string: String,
+    pub linear: Option<LinearFitStatistics>, // Uncertainties, for linear fits only
+    pub covariance: Option<Vec<Vec<f64>>>, // Of the parameters, for least-squares fits; zero for fixed ones
+    pub chi_square: Option<(f64, f64)>, // (χ², χ² per degree of freedom), for fits weighted by error bars
+}
+
+// Uncertainty of a least-squares straight line, assuming independent, normally
+// distributed scatter: of the same size at every point, or as given by the error bars
+// for a weighted fit
+#[derive(Debug, Clone)]
+/// Data structure used in data_editor.rs module
+pub struct LinearFitStatistics {
+    pub slope_error: f64,     // Standard error of the slope
+    pub intercept_error: f64, // Standard error of the intercept
+    pub p_value: f64,         // Two-sided, for a slope of zero
+    pub degrees_of_freedom: usize,
+    pub confidence_band: Vec<f64>, // 95% half-width of the fitted line at each fitted point
+    pub prediction_band: Vec<f64>, // 95% half-width for a new measurement at each fitted point
+}
+
+// One model fitted to several datasets at once, with some parameters taking a single
+// value for all of them (e.g. a shared Kd across replicate titrations)
+#[derive(Debug, Clone)]
+/// Data structure used in data_editor.rs module
+pub struct GlobalFitResult {
+    pub model: FitModel,
+    pub parameter_names: Vec<String>,
+    pub shared: Vec<bool>,    // Whether each parameter is shared between the datasets
+    pub fits: Vec<FitResult>, // One per dataset, with the shared values filled in
+    pub chi_square: Option<(f64, f64)>, // Pooled over all datasets, for fits weighted by error bars
 }
 
+// How the optimizer treats one parameter of a model
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Data structure used in data_editor.rs module
+pub struct ParameterSetting {
+    pub initial: Option<f64>, // Starting value; None lets the model estimate one from the data
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+    pub fixed: bool, // Held at its starting value
+}
+
+// y = f(x, parameters) of a fit model
+type ModelFunction = Box<dyn Fn(f64, &[f64]) -> f64>;
+
 #[derive(Debug, Clone, PartialEq)]
 /// Enum representing a set of related values in data_editor.rs module
 pub enum MouseAction {
@@ -157,16 +588,23 @@ pub struct DataEditor {
     pub clipboard_data: Vec<Vec<String>>,
     pub is_dragging: bool,
     pub edit_mode_cell: Option<(usize, usize)>,
+    pub reveal_active_cell: bool, // Scroll to the active cell (and focus it when editing) on the next frame
     
     // Multi-dataset support
     pub loaded_datasets: Vec<usize>, // Dataset indices that are loaded in columns
     pub auto_update_plots: bool,
     pub column_dataset_mapping: HashMap<usize, usize>, // column -> dataset_index
+    pub point_extras: HashMap<usize, PointExtras>, // dataset_index -> values the grid does not show
     
     // Data transformation
     pub show_transform_dialog: bool,
     pub selected_row: Option<usize>,
     pub transform_x_column: usize,
+    pub sort_column: usize, // Column the Sort buttons order the rows by
+    pub show_columns_dialog: bool,
+    pub columns_x: usize, // X column of the datasets made from columns
+    pub columns_y: HashSet<usize>, // Y columns, one dataset each
+    pub column_point_counts: Vec<usize>, // Points each column gives against columns_x, counted when it is chosen
     pub transform_y_column: usize,
     pub new_dataset_name: String,
     pub paste_buffer: String,
@@ -175,9 +613,27 @@ pub struct DataEditor {
     // Curve fitting
     pub show_fitting_dialog: bool,
     pub selected_fit_model: FitModel,
+    pub polynomial_degree: usize,
+    pub custom_fit_expression: String,
+    pub parameter_settings: HashMap<(FitModel, String), ParameterSetting>, // By model and parameter name
     pub fit_results: Vec<FitResult>,
+    pub global_fit: bool, // Fit the model to several datasets at once
+    pub global_datasets: Vec<usize>, // Datasets of a global fit
+    pub shared_parameters: HashSet<String>, // Names of the parameters a global fit shares
+    pub global_fit_results: Vec<GlobalFitResult>,
+    pub fit_error: Option<String>, // Why the last fit failed
+    pub residuals_below: bool, // Put residuals datasets in the subplot below instead of this one
+    pub weighted_fit: bool, // Weighted least squares when the dataset has Y error bars
+    pub confidence_band: bool, // Add the 95% confidence band of linear fits as a shaded dataset
+    pub prediction_band: bool, // Add the 95% prediction band of linear fits as a shaded dataset
+    pub pending_residuals: Option<Dataset>, // Residuals waiting for the app to place them below
+    pub report_requested: bool, // Export Fit Report was clicked; the app asks for a file and writes it
+    pub fit_x_range: Option<(f64, f64)>, // Set by the app: only fit points with X in this interval
     pub fitting_dataset_index: usize,
     pub show_paste_dialog: bool,
+    pub palette: Palette, // Set by the app: colors of datasets created here
+    pub pending_pick: Option<(usize, usize)>, // (dataset, point) clicked in the sheet, for the app's point inspector
+    pub pending_notification: Option<Result<String, String>>, // Outcome of a grid import or export, for the app to show
 }
 
 /// Implementation block defining methods for this type
@@ -195,21 +651,46 @@ impl Default for DataEditor {
             clipboard_data: Vec::new(),
             is_dragging: false,
             edit_mode_cell: None,
+            reveal_active_cell: false,
             loaded_datasets: Vec::new(),
             auto_update_plots: true,
             column_dataset_mapping: HashMap::new(),
+            point_extras: HashMap::new(),
             show_transform_dialog: false,
             selected_row: None,
             transform_x_column: 0,
+            sort_column: 0,
+            show_columns_dialog: false,
+            columns_x: 0,
+            columns_y: HashSet::new(),
+            column_point_counts: Vec::new(),
             transform_y_column: 1,
             new_dataset_name: "New Dataset".to_string(),
             paste_buffer: String::new(),
             selected_x_data: Vec::new(),
             show_fitting_dialog: false,
             selected_fit_model: FitModel::Linear,
+            polynomial_degree: 2,
+            custom_fit_expression: "a*exp(-x/t) + c".to_string(),
+            parameter_settings: HashMap::new(),
             fit_results: Vec::new(),
+            global_fit: false,
+            global_datasets: Vec::new(),
+            shared_parameters: HashSet::new(),
+            global_fit_results: Vec::new(),
+            fit_error: None,
+            residuals_below: true,
+            weighted_fit: true,
+            confidence_band: true,
+            prediction_band: false,
+            pending_residuals: None,
+            report_requested: false,
+            fit_x_range: None,
             fitting_dataset_index: 0,
             show_paste_dialog: false,
+            palette: Palette::default(),
+            pending_pick: None,
+            pending_notification: None,
         }
     }
 }
@@ -222,7 +703,12 @@ impl DataEditor {
             return;
         }
 
+        // Before any widget is drawn, so the edited cell does not see Enter or Tab itself
+// Variable declaration
+        let used_tab = self.handle_keyboard(ctx, datasets);
+
         egui::Window::new("Enhanced Data Editor")
+            .id(editor_window_id())
             .resizable(true)
             .default_width(900.0)
             .default_height(700.0)
@@ -279,6 +765,11 @@ impl DataEditor {
                     if ui.button("📊 Transform Row to Dataset").clicked() {
                         self.show_transform_dialog = true;
                     }
+
+                    if ui.button("📑 Create Datasets from Columns…").clicked() {
+                        self.show_columns_dialog = true;
+                        self.column_point_counts.clear();
+                    }
                     
                     if ui.button("📈 Fit Curve").clicked() {
                         self.show_fitting_dialog = true;
@@ -295,6 +786,31 @@ impl DataEditor {
                     if ui.button("+ Add Column").clicked() {
                         self.add_column();
                     }
+
+                    ui.separator();
+                    if ui
+                        .button("📂 Import CSV into grid")
+                        .on_hover_text("Replace the grid with a CSV or other text table; plotted datasets are not changed")
+                        .clicked()
+                    {
+                        if let Some(path) = pick_grid_file() {
+                            self.pending_notification = Some(
+                                self.import_csv_into_grid(&path)
+                                    .map(|rows| format!("Imported {} rows from {}", rows, path.display()))
+                                    .map_err(|e| format!("Failed to import {}: {}", path.display(), e)),
+                            );
+                        }
+                    }
+
+                    if ui.button("💾 Export grid as CSV").clicked() {
+                        if let Some(path) = pick_csv_save_path("grid.csv") {
+                            self.pending_notification = Some(
+                                self.export_grid_csv(&path)
+                                    .map(|rows| format!("Exported {} rows to {}", rows, path.display()))
+                                    .map_err(|e| format!("Failed to export the grid: {}", e)),
+                            );
+                        }
+                    }
                     
                     // Selection operations
                     ui.separator();
@@ -335,13 +851,64 @@ impl DataEditor {
                     });
                 }
                 
+                // Sorting and filtering rows
+                ui.horizontal(|ui| {
+                    ui.label("Sort by:");
+                    self.sort_column = self.sort_column.min(self.spreadsheet_data.num_cols.saturating_sub(1));
+                    egui::ComboBox::from_id_source("sort_column")
+                        .selected_text(self.spreadsheet_data.column_headers[self.sort_column].as_str())
+                        .show_ui(ui, |ui| {
+                            for (i, header) in self.spreadsheet_data.column_headers.iter().enumerate() {
+                                ui.selectable_value(&mut self.sort_column, i, header);
+                            }
+                        });
+// Variable declaration
+                    let mut sort = None;
+                    if ui.button("⬆ Ascending").clicked() {
+                        sort = Some(false);
+                    }
+                    if ui.button("⬇ Descending").clicked() {
+                        sort = Some(true);
+                    }
+                    if let Some(descending) = sort {
+                        self.spreadsheet_data.sort_rows(self.sort_column, descending);
+                        self.current_selection = None;
+                        self.edit_mode_cell = None;
+                        if self.auto_update_plots {
+                            self.update_datasets_from_spreadsheet(datasets);
+                        }
+                    }
+
+                    ui.separator();
+                    ui.label("Filter rows:");
+                    if ui
+                        .add(egui::TextEdit::singleline(&mut self.spreadsheet_data.row_filter).hint_text("C2 > 0.5"))
+                        .on_hover_text("Only rows where the comparison holds are shown and used for the datasets")
+                        .changed()
+                    {
+                        self.spreadsheet_data.apply_row_filter();
+                        if self.auto_update_plots {
+                            self.update_datasets_from_spreadsheet(datasets);
+                        }
+                    }
+                    if let Some(error) = &self.spreadsheet_data.filter_error {
+                        ui.colored_label(egui::Color32::RED, "!").on_hover_text(error);
+                    } else if !self.spreadsheet_data.hidden_rows.is_empty() {
+                        ui.label(format!("{} rows hidden", self.spreadsheet_data.hidden_rows.len()));
+                    }
+                });
+
                 // Selection info
                 if let Some(selection) = &self.current_selection {
                     ui.label(format!(
                         "Selection: ({},{}) to ({},{})", 
                         selection.start_row + 1, selection.start_col + 1,
                         selection.end_row + 1, selection.end_col + 1
-                    ));
+                    ))
+                    .on_hover_text(
+                        "Arrows move (Shift+arrows select), Enter edits and commits, Tab moves right, \
+                         Escape stops editing, Delete clears, Ctrl+D fills down",
+                    );
                 }
                 
                 ui.separator();
@@ -349,146 +916,217 @@ impl DataEditor {
                 // Enhanced spreadsheet view
                 egui::ScrollArea::both()
                     .max_height(400.0)
-                    .show(ui, |ui| {
-                        self.show_enhanced_spreadsheet(ui, datasets);
+                    .show_viewport(ui, |ui, viewport| {
+                        self.show_enhanced_spreadsheet(ui, viewport, datasets);
                     });
             });
         
+        // egui gives the focus to the first widget when Tab is pressed, before the key can be
+        // taken here; a Tab that moved the active cell takes it away again
+        if used_tab {
+            ctx.memory_mut(|m| m.stop_text_input());
+        }
+
         // Show dialogs
         self.show_transform_dialog_window(ctx, datasets);
+        self.show_columns_dialog_window(ctx, datasets);
         self.show_fitting_dialog_window(ctx, datasets);
     }
     
+    // Grid of cells. Every cell has the same width and every data row the same height, so
+    // the rows in `viewport` (the visible part of the scrolled content) can be found from
+    // the scroll position alone; only those are drawn, which keeps large sheets responsive.
 /// Function: explain its purpose and key arguments
-    fn show_enhanced_spreadsheet(&mut self, ui: &mut egui::Ui, datasets: &mut Vec<Dataset>) {
-        egui::Grid::new("enhanced_data_spreadsheet")
-            .num_columns(self.spreadsheet_data.num_cols + 1)
-            .spacing([2.0, 2.0])
-            .striped(true)
-            .show(ui, |ui| {
-                // Header row with dataset indicators
-                ui.label("Row");
+    fn show_enhanced_spreadsheet(&mut self, ui: &mut egui::Ui, viewport: egui::Rect, datasets: &mut Vec<Dataset>) {
 // Variable declaration
-                let mut header_updates = Vec::new();
-                
-                for (col_idx, header) in self.spreadsheet_data.column_headers.iter().enumerate() {
-                    if col_idx >= self.spreadsheet_data.num_cols {
-                        break;
+        let content_top = ui.max_rect().top();
+        ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+// Variable declaration
+        let num_cols = self.spreadsheet_data.num_cols;
+
+        // Header row with dataset indicators
+        ui.horizontal(|ui| {
+            ui.add_sized([ROW_LABEL_WIDTH, ROW_HEIGHT], egui::Label::new("Row"));
+            for col_idx in 0..num_cols {
+                ui.vertical(|ui| {
+                    ui.set_width(CELL_WIDTH);
+                    // Dataset indicator
+                    if let Some(dataset) = self.column_dataset_mapping.get(&col_idx).and_then(|&i| datasets.get(i)) {
+// Variable declaration
+                        let color = egui::Color32::from_rgb(dataset.color[0], dataset.color[1], dataset.color[2]);
+                        ui.add(egui::Label::new(egui::RichText::new(format!("📊 {}", dataset.name)).color(color)).truncate(true));
                     }
-                    
-                    ui.vertical(|ui| {
-                        // Dataset indicator
-                        if let Some(&dataset_idx) = self.column_dataset_mapping.get(&col_idx) {
-                            if let Some(dataset) = datasets.get(dataset_idx) {
-                                ui.colored_label(
-                                    egui::Color32::from_rgb(dataset.color[0], dataset.color[1], dataset.color[2]),
-                                    format!("📊 {}", dataset.name)
-                                );
-                            }
-                        }
-                        
-                        // Column header
+                    // Column header
+                    if let Some(header) = self.spreadsheet_data.column_headers.get_mut(col_idx) {
+                        ui.add_sized([CELL_WIDTH, ROW_HEIGHT], egui::TextEdit::singleline(header));
+                    }
+                });
+            }
+        });
+
+        // Formula row: a column defined as an expression of other columns
 // Variable declaration
-                        let mut header_text = header.clone();
-                        if ui.text_edit_singleline(&mut header_text).changed() {
-                            header_updates.push((col_idx, header_text));
-                        }
-                    });
-                }
-                
-                // Apply header updates
-                for (col_idx, new_header) in header_updates {
-                    self.spreadsheet_data.column_headers[col_idx] = new_header;
+        let mut formulas_changed = false;
+        ui.horizontal(|ui| {
+            ui.add_sized([ROW_LABEL_WIDTH, ROW_HEIGHT], egui::Label::new("ƒ")).on_hover_text(
+                "Column formulas, e.g. =C1*2 + C2. Cn is column n, x the row number; \
+                 the column is recalculated whenever a cell changes",
+            );
+            for col_idx in 0..num_cols {
+// Variable declaration
+                let error = self.spreadsheet_data.formula_errors.get(&col_idx).cloned();
+                if let Some(formula) = self.spreadsheet_data.column_formulas.get_mut(col_idx) {
+// Variable declaration
+                    let mut edit = egui::TextEdit::singleline(formula).hint_text("=");
+                    if error.is_some() {
+                        edit = edit.text_color(egui::Color32::RED);
+                    }
+// Variable declaration
+                    let response = ui.add_sized([CELL_WIDTH, ROW_HEIGHT], edit);
+                    formulas_changed |= response.changed();
+                    if let Some(error) = error {
+                        response.on_hover_text(error);
+                    }
                 }
-                
-                ui.end_row();
-                
-                // Data rows with enhanced mouse interaction
-                for row in 0..self.spreadsheet_data.num_rows {
-                    // Row header
+            }
+        });
+        if formulas_changed {
+            self.spreadsheet_data.recalculate_formulas();
+            if self.auto_update_plots {
+                self.update_datasets_from_spreadsheet(datasets);
+            }
+        }
+
 // Variable declaration
-                    let row_selected = self.current_selection.as_ref()
-                        .map_or(false, |sel| sel.contains(row, 0));
-                    
+        let rows: Vec<usize> = (0..self.spreadsheet_data.num_rows)
+            .filter(|row| !self.spreadsheet_data.hidden_rows.contains(row))
+            .collect();
+// Variable declaration
+        let spacing = ui.spacing().item_spacing;
+// Variable declaration
+        let row_pitch = ROW_HEIGHT + spacing.y;
 // Variable declaration
-                    let row_header_response = ui.selectable_label(
-                        row_selected,
-                        format!("{}", row + 1)
+        let rows_top = ui.cursor().top();
+// Variable declaration
+        let row_width = ROW_LABEL_WIDTH + num_cols as f32 * (CELL_WIDTH + spacing.x);
+
+        // The cell the keyboard moved to may not be drawn yet, so scroll to where it will be.
+        // Scrolling is animated: this repeats until the cell is drawn (see show_spreadsheet_row).
+        if self.reveal_active_cell {
+// Variable declaration
+            let target = self
+                .active_cell()
+                .and_then(|(row, col)| Some((rows.iter().position(|r| *r == row)?, col)));
+            match target {
+                Some((index, col)) => {
+// Variable declaration
+                    let min = egui::pos2(
+                        ui.cursor().left() + ROW_LABEL_WIDTH + spacing.x + col as f32 * (CELL_WIDTH + spacing.x),
+                        rows_top + index as f32 * row_pitch,
                     );
-                    
-                    if row_header_response.clicked() {
-                        self.handle_row_header_click(row);
-                    }
-                    
-                    // Data cells with enhanced interaction
-                    for col in 0..self.spreadsheet_data.num_cols {
+                    ui.scroll_to_rect(egui::Rect::from_min_size(min, egui::vec2(CELL_WIDTH, ROW_HEIGHT)), None);
+                }
+                None => self.reveal_active_cell = false, // Hidden by the filter
+            }
+        }
+
+        // Data rows in view; the rows above and below are empty space of the same height
 // Variable declaration
-                        let cell_key = (row, col);
+        let offset = rows_top - content_top;
 // Variable declaration
-                        let mut cell = self.spreadsheet_data.cells
-                            .get(&cell_key)
-                            .cloned()
-                            .unwrap_or_default();
-                        
+        let last = ((((viewport.max.y - offset) / row_pitch).ceil().max(0.0) as usize) + 1).min(rows.len());
 // Variable declaration
-                        let is_selected = self.current_selection.as_ref()
-                            .map_or(false, |sel| sel.contains(row, col));
-                        
+        let first = (((viewport.min.y - offset) / row_pitch).floor().max(0.0) as usize).min(last);
+        ui.add_space(first as f32 * row_pitch);
+        for (index, &row) in rows.iter().enumerate().take(last).skip(first) {
+            if index % 2 == 1 {
+                ui.painter().rect_filled(
+                    egui::Rect::from_min_size(ui.cursor().min, egui::vec2(row_width, ROW_HEIGHT)),
+                    0.0,
+                    ui.visuals().faint_bg_color,
+                );
+            }
+            ui.horizontal(|ui| self.show_spreadsheet_row(ui, row, datasets));
+        }
+        ui.add_space((rows.len() - last) as f32 * row_pitch);
+    }
+
+    // One data row: its number, which selects the whole row, and its cells
+/// Function: explain its purpose and key arguments
+    fn show_spreadsheet_row(&mut self, ui: &mut egui::Ui, row: usize, datasets: &mut Vec<Dataset>) {
+        // Row header
+// Variable declaration
+        let row_selected = self.current_selection.as_ref().is_some_and(|sel| sel.contains(row, 0));
+        if ui
+            .add_sized([ROW_LABEL_WIDTH, ROW_HEIGHT], egui::SelectableLabel::new(row_selected, (row + 1).to_string()))
+            .clicked()
+        {
+            self.handle_row_header_click(row);
+        }
+
+        // Data cells with enhanced interaction
+        for col in 0..self.spreadsheet_data.num_cols {
 // Variable declaration
-                        let is_editing = self.edit_mode_cell == Some((row, col));
-                        
-                        // Visual styling for selection
+            let cell_key = (row, col);
 // Variable declaration
-                        let mut response = if is_editing {
-                            ui.text_edit_singleline(&mut cell.value)
-                        } else if is_selected {
-                            ui.colored_label(egui::Color32::LIGHT_BLUE, &cell.value)
-                                .on_hover_text("Selected cell")
-                        } else {
-                            ui.label(&cell.value)
-                        };
-                        
-                        // Handle mouse interactions
-                        if response.clicked() {
-                            self.handle_cell_click(row, col);
-                        }
-                        
-                        if response.drag_started() {
-                            self.start_drag_selection(row, col);
-                        }
-                        
-                        if response.dragged() && self.is_dragging {
-                            self.extend_drag_selection(row, col);
-                        }
-                        
-                        if response.drag_stopped() {
-                            self.end_drag_selection();
-                        }
-                        
-                        // Update cell data if changed
-                        if response.changed() {
-                            cell.parsed_value = cell.value.trim().parse::<f64>().ok();
-                            self.spreadsheet_data.cells.insert(cell_key, cell.clone());
-                            
-                            // Auto-update plots if enabled
-                            if self.auto_update_plots {
-                                self.update_datasets_from_spreadsheet(datasets);
-                            }
-                        }
-                        
-                        // Visual feedback for parsing errors
-                        if !cell.value.is_empty() && cell.parsed_value.is_none() {
-                            ui.colored_label(egui::Color32::RED, "!");
-                        }
-                        
-                        // Show dataset mapping indicator
-                        if self.column_dataset_mapping.contains_key(&col) {
-                            ui.colored_label(egui::Color32::GREEN, "●");
-                        }
-                    }
-                    ui.end_row();
+            let mut cell = self.spreadsheet_data.cells.get(&cell_key).cloned().unwrap_or_default();
+// Variable declaration
+            let is_selected = self.current_selection.as_ref().is_some_and(|sel| sel.contains(row, col));
+// Variable declaration
+            let is_invalid = !cell.value.is_empty() && cell.parsed_value.is_none();
+
+            // Selected cells in blue, text that is not a number in red
+// Variable declaration
+            let mut response = if self.edit_mode_cell == Some(cell_key) {
+                ui.add_sized([CELL_WIDTH, ROW_HEIGHT], egui::TextEdit::singleline(&mut cell.value).id(cell_edit_id()))
+            } else {
+// Variable declaration
+                let mut text = egui::RichText::new(&cell.value);
+                if is_selected {
+                    text = text.color(egui::Color32::LIGHT_BLUE);
+                } else if is_invalid {
+                    text = text.color(egui::Color32::RED);
                 }
-            });
+// Variable declaration
+                let label = egui::Label::new(text).selectable(false).truncate(true).sense(egui::Sense::click_and_drag());
+                ui.add_sized([CELL_WIDTH, ROW_HEIGHT], label)
+            };
+            if is_invalid {
+                response = response.on_hover_text("Not a number: this row is left out of the dataset");
+            }
+            if self.reveal_active_cell && self.active_cell() == Some(cell_key) {
+                if self.edit_mode_cell == Some(cell_key) {
+                    response.request_focus();
+                }
+                self.reveal_active_cell = false;
+            }
+
+            // Handle mouse interactions
+            if response.clicked() {
+                self.handle_cell_click(row, col);
+            }
+            if response.drag_started() {
+                self.start_drag_selection(row, col);
+            }
+            if response.dragged() && self.is_dragging {
+                self.extend_drag_selection(row, col);
+            }
+            if response.drag_stopped() {
+                self.end_drag_selection();
+            }
+
+            // Update cell data if changed
+            if response.changed() {
+                cell.parsed_value = cell.value.trim().parse::<f64>().ok();
+                self.spreadsheet_data.cells.insert(cell_key, cell);
+                self.spreadsheet_data.recalculate_formulas();
+
+                // Auto-update plots if enabled
+                if self.auto_update_plots {
+                    self.update_datasets_from_spreadsheet(datasets);
+                }
+            }
+        }
     }
     
 /// Function: explain its purpose and key arguments
@@ -497,10 +1135,13 @@ impl DataEditor {
             MouseAction::Select => {
                 self.current_selection = Some(Selection::new(row, col));
                 self.edit_mode_cell = None;
+                self.pending_pick = self.point_at(row, col);
             },
             MouseAction::Edit => {
                 self.edit_mode_cell = Some((row, col));
                 self.current_selection = Some(Selection::new(row, col));
+                self.pending_pick = self.point_at(row, col);
+                self.reveal_active_cell = true;
             },
             MouseAction::Copy => {
                 if let Some(selection) = &self.current_selection {
@@ -519,13 +1160,242 @@ impl DataEditor {
                     }
                 } else {
                     self.spreadsheet_data.cells.remove(&(row, col));
+                    self.spreadsheet_data.recalculate_formulas();
                 }
             },
         }
     }
     
+    // Cell the keyboard acts on: the moving end of the selection
 /// Function: explain its purpose and key arguments
-    fn handle_row_header_click(&mut self, row: usize) {
+    fn active_cell(&self) -> Option<(usize, usize)> {
+        self.current_selection.as_ref().map(|selection| (selection.end_row, selection.end_col))
+    }
+
+    // Spreadsheet keys. Arrows move the active cell (with Shift they extend the selection),
+    // Enter starts editing it, Tab moves right, Delete clears the selection and Ctrl+D
+    // fills it down from its top row. While editing, Enter and Tab commit the edit and move
+    // down or right (Shift goes the other way) and Escape stops editing; the typed value
+    // is already stored. Keys count only while the editor is the front window and no
+    // other text field (a header, formula or the filter) has focus. Returns whether Tab
+    // was used.
+/// Function: explain its purpose and key arguments
+    fn handle_keyboard(&mut self, ctx: &egui::Context, datasets: &mut Vec<Dataset>) -> bool {
+// Variable declaration
+        let Some((row, col)) = self.active_cell() else {
+            return false;
+        };
+// Variable declaration
+        let editor_layer = egui::LayerId::new(egui::Order::Middle, editor_window_id());
+        if ctx.top_layer_id() != Some(editor_layer) || ctx.memory(|m| m.focused()).is_some_and(|id| id != cell_edit_id()) {
+            return false;
+        }
+// Variable declaration
+        let pressed = |modifiers: egui::Modifiers, key: egui::Key| ctx.input_mut(|i| i.consume_key(modifiers, key));
+        // Shift variants are checked first: the plain key would also match them
+// Variable declaration
+        let (shift, none) = (egui::Modifiers::SHIFT, egui::Modifiers::NONE);
+// Variable declaration
+        let last_col = self.spreadsheet_data.num_cols.saturating_sub(1);
+
+        // Tab and Shift+Tab move the active cell whether or not it is being edited
+        if pressed(shift, egui::Key::Tab) {
+            self.move_active_cell(row, col.saturating_sub(1), false);
+            return true;
+        }
+        if pressed(none, egui::Key::Tab) {
+            self.move_active_cell(row, (col + 1).min(last_col), false);
+            return true;
+        }
+
+        if self.edit_mode_cell.is_some() {
+            if pressed(shift, egui::Key::Enter) {
+                self.move_active_cell(self.step_row(row, false), col, false);
+            } else if pressed(none, egui::Key::Enter) {
+                self.move_active_cell(self.step_row(row, true), col, false);
+            } else if pressed(none, egui::Key::Escape) {
+                self.edit_mode_cell = None;
+            }
+            return false;
+        }
+
+        for key in [egui::Key::ArrowUp, egui::Key::ArrowDown, egui::Key::ArrowLeft, egui::Key::ArrowRight] {
+// Variable declaration
+            let extend = pressed(shift, key);
+            if !extend && !pressed(none, key) {
+                continue;
+            }
+// Variable declaration
+            let (row, col) = self.active_cell().unwrap_or((row, col));
+// Variable declaration
+            let (row, col) = match key {
+                egui::Key::ArrowUp => (self.step_row(row, false), col),
+                egui::Key::ArrowDown => (self.step_row(row, true), col),
+                egui::Key::ArrowLeft => (row, col.saturating_sub(1)),
+                _ => (row, (col + 1).min(last_col)),
+            };
+            self.move_active_cell(row, col, extend);
+        }
+
+        if pressed(none, egui::Key::Enter) {
+            self.current_selection = Some(Selection::new(row, col));
+            self.edit_mode_cell = Some((row, col));
+            self.reveal_active_cell = true;
+        } else if pressed(none, egui::Key::Delete) || pressed(none, egui::Key::Backspace) {
+            self.clear_selection();
+            if self.auto_update_plots {
+                self.update_datasets_from_spreadsheet(datasets);
+            }
+        } else if pressed(egui::Modifiers::COMMAND, egui::Key::D) {
+            self.fill_down();
+            if self.auto_update_plots {
+                self.update_datasets_from_spreadsheet(datasets);
+            }
+        }
+        false
+    }
+
+    // Nearest row below (or above) `row` that the filter does not hide; `row` itself at the edge
+/// Function: explain its purpose and key arguments
+    fn step_row(&self, row: usize, down: bool) -> usize {
+// Variable declaration
+        let hidden = &self.spreadsheet_data.hidden_rows;
+        if down {
+            (row + 1..self.spreadsheet_data.num_rows).find(|r| !hidden.contains(r)).unwrap_or(row)
+        } else {
+            (0..row).rev().find(|r| !hidden.contains(r)).unwrap_or(row)
+        }
+    }
+
+    // Make (row, col) the active cell, extending the selection to it or selecting only it
+/// Function: explain its purpose and key arguments
+    fn move_active_cell(&mut self, row: usize, col: usize, extend: bool) {
+        match &mut self.current_selection {
+            Some(selection) if extend => selection.extend_to(row, col),
+            _ => self.current_selection = Some(Selection::new(row, col)),
+        }
+        self.edit_mode_cell = None;
+        self.pending_pick = self.point_at(row, col);
+        self.reveal_active_cell = true;
+    }
+
+    // Copy the top row of the selection into its other rows, skipping rows the filter hides
+/// Function: explain its purpose and key arguments
+    fn fill_down(&mut self) {
+// Variable declaration
+        let Some(selection) = &self.current_selection else {
+            return;
+        };
+// Variable declaration
+        let (min_row, max_row) = (selection.start_row.min(selection.end_row), selection.start_row.max(selection.end_row));
+// Variable declaration
+        let (min_col, max_col) = (selection.start_col.min(selection.end_col), selection.start_col.max(selection.end_col));
+        for col in min_col..=max_col {
+// Variable declaration
+            let top = self.spreadsheet_data.cells.get(&(min_row, col)).cloned();
+            for row in (min_row + 1..=max_row).filter(|row| !self.spreadsheet_data.hidden_rows.contains(row)) {
+                match &top {
+                    Some(cell) => self.spreadsheet_data.cells.insert((row, col), cell.clone()),
+                    None => self.spreadsheet_data.cells.remove(&(row, col)),
+                };
+            }
+        }
+        self.spreadsheet_data.recalculate_formulas();
+    }
+
+    // (dataset, point) shown in a cell of a loaded dataset's columns
+/// Function: explain its purpose and key arguments
+    fn point_at(&self, row: usize, col: usize) -> Option<(usize, usize)> {
+// Variable declaration
+        let dataset_idx = *self.column_dataset_mapping.get(&col)?;
+        self.spreadsheet_data.cells.contains_key(&(row, col)).then_some((dataset_idx, row))
+    }
+
+    // (X column, Y column) a dataset was loaded into
+/// Function: explain its purpose and key arguments
+    fn dataset_columns(&self, dataset_idx: usize) -> Option<(usize, usize)> {
+// Variable declaration
+        let mut cols: Vec<usize> = self
+            .column_dataset_mapping
+            .iter()
+            .filter(|(_, &mapped)| mapped == dataset_idx)
+            .map(|(&col, _)| col)
+            .collect();
+        cols.sort();
+        (cols.len() >= 2).then(|| (cols[0], cols[1]))
+    }
+
+    // Show the editor with a dataset loaded into its columns, e.g. a new empty one to be
+    // typed in
+/// Function: explain its purpose and key arguments
+    pub fn open_dataset(&mut self, datasets: &[Dataset], dataset_idx: usize) {
+        self.show_editor = true;
+        if !self.loaded_datasets.contains(&dataset_idx) {
+            self.load_dataset_to_column(datasets, dataset_idx);
+        }
+    }
+
+    // Select the row of a point picked on the plot, if its dataset is loaded
+/// Function: explain its purpose and key arguments
+    pub fn select_point(&mut self, dataset_idx: usize, point: usize) {
+        if let Some((x_col, y_col)) = self.dataset_columns(dataset_idx) {
+            self.current_selection = Some(Selection {
+                start_row: point,
+                start_col: x_col,
+                end_row: point,
+                end_col: y_col,
+            });
+            self.edit_mode_cell = None;
+        }
+    }
+
+    // Remember the per-point values of a dataset whose point i goes into row i
+/// Function: explain its purpose and key arguments
+    fn keep_point_extras(&mut self, dataset: &Dataset, dataset_idx: usize) {
+// Variable declaration
+        let point_at = (0..dataset.points.len()).map(|i| (self.spreadsheet_data.row_origin(i), i)).collect();
+        self.point_extras.insert(
+            dataset_idx,
+            PointExtras {
+                point_at,
+                x_err: dataset.x_err.clone(),
+                y_err: dataset.y_err.clone(),
+                color_values: dataset.color_values.clone(),
+                band: dataset.band.clone(),
+            },
+        );
+    }
+
+    // Rewrite a loaded dataset's columns after its points were changed outside the sheet
+/// Function: explain its purpose and key arguments
+    pub fn reload_dataset(&mut self, datasets: &[Dataset], dataset_idx: usize) {
+// Variable declaration
+        let (Some((x_col, y_col)), Some(dataset)) = (self.dataset_columns(dataset_idx), datasets.get(dataset_idx)) else {
+            return;
+        };
+        for row in 0..self.spreadsheet_data.num_rows {
+            self.spreadsheet_data.cells.remove(&(row, x_col));
+            self.spreadsheet_data.cells.remove(&(row, y_col));
+        }
+        self.keep_point_extras(dataset, dataset_idx);
+        for (row, point) in dataset.points.iter().enumerate() {
+            self.spreadsheet_data.num_rows = self.spreadsheet_data.num_rows.max(row + 1);
+            for (col, value) in [(x_col, point[0]), (y_col, point[1])] {
+                self.spreadsheet_data.cells.insert(
+                    (row, col),
+                    DataCell {
+                        value: value.to_string(),
+                        parsed_value: Some(value),
+                        ..DataCell::default()
+                    },
+                );
+            }
+        }
+        self.spreadsheet_data.recalculate_formulas();
+    }
+
+/// Function: explain its purpose and key arguments
+    fn handle_row_header_click(&mut self, row: usize) {
         // Select entire row
         self.current_selection = Some(Selection {
             start_row: row,
@@ -608,6 +1478,7 @@ impl DataEditor {
                     }
                 }
             }
+            self.spreadsheet_data.recalculate_formulas();
         }
     }
     
@@ -628,6 +1499,7 @@ impl DataEditor {
                     self.spreadsheet_data.cells.remove(&(row, col));
                 }
             }
+            self.spreadsheet_data.recalculate_formulas();
         }
     }
     
@@ -656,6 +1528,7 @@ impl DataEditor {
             self.column_dataset_mapping.insert(target_col + 1, dataset_idx);
             
             // Load data
+            self.keep_point_extras(dataset, dataset_idx);
             for (row, point) in dataset.points.iter().enumerate() {
                 // Expand rows if needed
                 if row >= self.spreadsheet_data.num_rows {
@@ -678,6 +1551,7 @@ impl DataEditor {
             }
             
             self.loaded_datasets.push(dataset_idx);
+            self.spreadsheet_data.recalculate_formulas();
         }
     }
     
@@ -702,6 +1576,8 @@ impl DataEditor {
         
         // Remove from loaded datasets
         self.loaded_datasets.retain(|&idx| idx != dataset_idx);
+        self.point_extras.remove(&dataset_idx);
+        self.spreadsheet_data.recalculate_formulas();
     }
     
 /// Function: explain its purpose and key arguments
@@ -718,6 +1594,8 @@ impl DataEditor {
             if let Some(dataset) = datasets.get_mut(dataset_idx) {
 // Variable declaration
                 let mut new_points = Vec::new();
+// Variable declaration
+                let mut origins = Vec::new();
                 
                 // Assume X column comes first, Y second
                 if cols.len() >= 2 {
@@ -729,20 +1607,27 @@ impl DataEditor {
 // Variable declaration
                     let y_col = sorted_cols[1];
                     
-                    // Collect data from spreadsheet
-                    for row in 0..self.spreadsheet_data.num_rows {
+                    // Collect data from spreadsheet, without the rows the filter hides
+                    for row in (0..self.spreadsheet_data.num_rows).filter(|row| !self.spreadsheet_data.hidden_rows.contains(row)) {
                         if let (Some(x_cell), Some(y_cell)) = (
                             self.spreadsheet_data.cells.get(&(row, x_col)),
                             self.spreadsheet_data.cells.get(&(row, y_col))
                         ) {
                             if let (Some(x), Some(y)) = (x_cell.parsed_value, y_cell.parsed_value) {
                                 new_points.push([x, y]);
+                                origins.push(self.spreadsheet_data.row_origin(row));
                             }
                         }
                     }
                 }
                 
-                dataset.points = new_points;
+                dataset.set_points(new_points);
+                if let Some(extras) = self.point_extras.get(&dataset_idx) {
+                    extras.apply_to(dataset, &origins);
+                }
+                dataset.discard_mismatched_errors();
+                // Hand-edited points would be overwritten by the next reload
+                dataset.follow = false;
             }
         }
     }
@@ -750,6 +1635,7 @@ impl DataEditor {
 /// Function: explain its purpose and key arguments
     fn add_row(&mut self) {
         self.spreadsheet_data.num_rows += 1;
+        self.spreadsheet_data.recalculate_formulas();
     }
     
 /// Function: explain its purpose and key arguments
@@ -759,17 +1645,85 @@ impl DataEditor {
         let new_col_idx = self.spreadsheet_data.column_headers.len();
         self.spreadsheet_data.column_headers.push(format!("Col {}", new_col_idx + 1));
         self.spreadsheet_data.dataset_columns.push(None);
+        self.spreadsheet_data.column_formulas.push(String::new());
     }
     
 /// Function: explain its purpose and key arguments
     fn clear_all_data(&mut self) {
         self.spreadsheet_data.cells.clear();
+        for formula in &mut self.spreadsheet_data.column_formulas {
+            formula.clear();
+        }
+        self.spreadsheet_data.formula_errors.clear();
+        self.spreadsheet_data.row_filter.clear();
+        self.spreadsheet_data.apply_row_filter();
+        self.spreadsheet_data.row_origins.clear();
         self.column_dataset_mapping.clear();
         self.loaded_datasets.clear();
+        self.point_extras.clear();
         self.current_selection = None;
         self.edit_mode_cell = None;
     }
     
+    // Replace the grid with the rows of a text table, the delimiter and header row detected
+    // as in the import preview. Datasets loaded into the grid are unlinked, not changed.
+/// Function: explain its purpose and key arguments
+    fn import_csv_into_grid(&mut self, path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+// Variable declaration
+        let import = TextImport::open(path.to_path_buf(), 0)?;
+// Variable declaration
+        let width = import.rows.iter().map(Vec::len).chain([import.headers.len()]).max().unwrap_or(0);
+        if width == 0 {
+            return Err("the file holds no data".into());
+        }
+
+        self.clear_all_data();
+        while self.spreadsheet_data.num_cols < width {
+            self.add_column();
+        }
+        for (col, header) in self.spreadsheet_data.column_headers.iter_mut().enumerate() {
+            *header = import.headers.get(col).cloned().unwrap_or_else(|| format!("Col {}", col + 1));
+        }
+        self.spreadsheet_data.num_rows = self.spreadsheet_data.num_rows.max(import.rows.len());
+        for (row, fields) in import.rows.iter().enumerate() {
+            for (col, field) in fields.iter().enumerate().filter(|(_, field)| !field.is_empty()) {
+                self.spreadsheet_data.cells.insert(
+                    (row, col),
+                    DataCell {
+                        value: field.clone(),
+                        parsed_value: field.parse::<f64>().ok(),
+                        ..DataCell::default()
+                    },
+                );
+            }
+        }
+        Ok(import.rows.len())
+    }
+
+    // Write the used part of the grid (up to the last non-empty row and column) as CSV,
+    // with the column headers as the first line. Returns the number of data rows.
+/// Function: explain its purpose and key arguments
+    fn export_grid_csv(&self, path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+// Variable declaration
+        let cells = &self.spreadsheet_data.cells;
+// Variable declaration
+        let used = cells.iter().filter(|(_, cell)| !cell.value.is_empty()).map(|(key, _)| *key);
+// Variable declaration
+        let (rows, cols) = used.fold((0, 0), |(rows, cols), (row, col)| (rows.max(row + 1), cols.max(col + 1)));
+        if cols == 0 {
+            return Err("the grid is empty".into());
+        }
+
+// Variable declaration
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record((0..cols).map(|col| self.spreadsheet_data.column_headers.get(col).map_or("", String::as_str)))?;
+        for row in 0..rows {
+            writer.write_record((0..cols).map(|col| cells.get(&(row, col)).map_or("", |cell| cell.value.as_str())))?;
+        }
+        writer.flush()?;
+        Ok(rows)
+    }
+
 /// Function: explain its purpose and key arguments
     fn parse_pasted_data(&mut self) {
         // Clone the buffer to avoid borrowing conflicts
@@ -791,11 +1745,7 @@ impl DataEditor {
         
         for (row_offset, line) in lines.iter().enumerate() {
 // Variable declaration
-            let cells: Vec<&str> = if line.contains('\t') {
-                line.split('\t').collect()
-            } else {
-                line.split(',').collect()
-            };
+            let cells = split_pasted_line(line);
             
 // Variable declaration
             let target_row = start_row + row_offset;
@@ -818,11 +1768,7 @@ impl DataEditor {
         // Second pass: insert data
         for (row_offset, line) in lines.iter().enumerate() {
 // Variable declaration
-            let cells: Vec<&str> = if line.contains('\t') {
-                line.split('\t').collect()
-            } else {
-                line.split(',').collect()
-            };
+            let cells = split_pasted_line(line);
             
             for (col_offset, cell_value) in cells.iter().enumerate() {
 // Variable declaration
@@ -840,6 +1786,7 @@ impl DataEditor {
         }
         
         self.paste_buffer.clear();
+        self.spreadsheet_data.recalculate_formulas();
     }
 /// Function: explain its purpose and key arguments
     fn show_transform_dialog_window(&mut self, ctx: &egui::Context, datasets: &mut Vec<Dataset>) {
@@ -960,13 +1907,9 @@ impl DataEditor {
 
         if !points.is_empty() {
 // Variable declaration
-            let color = get_default_color(datasets.len() % 8);
+            let color = self.palette.color(datasets.len());
 // Variable declaration
-            let dataset = Dataset {
-                name: self.new_dataset_name.clone(),
-                points,
-                color,
-            };
+            let dataset = Dataset::new(self.new_dataset_name.clone(), points, color);
             datasets.push(dataset);
 
             self.show_transform_dialog = false;
@@ -974,6 +1917,117 @@ impl DataEditor {
         }
     }
 
+    // Wizard that turns grid columns into datasets: one X column shared by any number of Y
+    // columns, each becoming a dataset named after its header
+/// Function: explain its purpose and key arguments
+    fn show_columns_dialog_window(&mut self, ctx: &egui::Context, datasets: &mut Vec<Dataset>) {
+        if !self.show_columns_dialog {
+            return;
+        }
+
+// Variable declaration
+        let mut open = true;
+        egui::Window::new("Create Datasets from Columns")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+// Variable declaration
+                let num_cols = self.spreadsheet_data.num_cols;
+                self.columns_x = self.columns_x.min(num_cols.saturating_sub(1));
+                self.columns_y.retain(|col| *col < num_cols && *col != self.columns_x);
+// Variable declaration
+                let names: Vec<String> = (0..num_cols).map(|col| self.column_name(col)).collect();
+
+// Variable declaration
+                let mut x_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("X Column:");
+                    egui::ComboBox::from_id_source("columns_x")
+                        .selected_text(names.get(self.columns_x).cloned().unwrap_or_default())
+                        .show_ui(ui, |ui| {
+                            for (col, name) in names.iter().enumerate() {
+                                x_changed |= ui.selectable_value(&mut self.columns_x, col, name).changed();
+                            }
+                        });
+                });
+                // Counting means a pass over the whole grid per column, too slow to redo every frame
+                if x_changed || self.column_point_counts.len() != num_cols {
+// Variable declaration
+                    let data = &self.spreadsheet_data;
+                    self.column_point_counts = (0..num_cols).map(|col| data.column_points(self.columns_x, col).len()).collect();
+                }
+
+                ui.separator();
+                ui.label("Y Columns (one dataset each, named after the column):");
+                egui::ScrollArea::vertical().max_height(250.0).show(ui, |ui| {
+// Variable declaration
+                    let x_col = self.columns_x;
+                    for col in (0..num_cols).filter(|col| *col != x_col) {
+// Variable declaration
+                        let count = self.column_point_counts[col];
+// Variable declaration
+                        let mut selected = self.columns_y.contains(&col);
+                        ui.add_enabled_ui(count > 0, |ui| {
+                            if ui.checkbox(&mut selected, format!("{} ({} points)", names[col], count)).changed() {
+                                if selected {
+                                    self.columns_y.insert(col);
+                                } else {
+                                    self.columns_y.remove(&col);
+                                }
+                            }
+                        });
+                    }
+                });
+                if !self.spreadsheet_data.hidden_rows.is_empty() {
+                    ui.small("Rows hidden by the filter are left out.");
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!self.columns_y.is_empty(), egui::Button::new("Create Datasets")).clicked() {
+// Variable declaration
+                        let created = self.create_datasets_from_columns(datasets);
+                        self.pending_notification = Some(Ok(format!("Created {} datasets from columns", created)));
+                        self.show_columns_dialog = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_columns_dialog = false;
+                    }
+                });
+            });
+        self.show_columns_dialog &= open;
+    }
+
+    // Header of a column, or "Col n" when the header was cleared
+/// Function: explain its purpose and key arguments
+    fn column_name(&self, col: usize) -> String {
+        match self.spreadsheet_data.column_headers.get(col).map(|header| header.trim()) {
+            Some(header) if !header.is_empty() => header.to_string(),
+            _ => format!("Col {}", col + 1),
+        }
+    }
+
+    // Add one dataset per chosen Y column, in column order. Returns how many were added.
+/// Function: explain its purpose and key arguments
+    fn create_datasets_from_columns(&mut self, datasets: &mut Vec<Dataset>) -> usize {
+// Variable declaration
+        let mut y_cols: Vec<usize> = self.columns_y.iter().copied().collect();
+        y_cols.sort();
+// Variable declaration
+        let first = datasets.len();
+        for y_col in y_cols {
+// Variable declaration
+            let points = self.spreadsheet_data.column_points(self.columns_x, y_col);
+            if !points.is_empty() {
+// Variable declaration
+                let color = self.palette.color(datasets.len());
+                datasets.push(Dataset::new(self.column_name(y_col), points, color));
+            }
+        }
+        datasets.len() - first
+    }
+
 /// Function: explain its purpose and key arguments
     fn show_fitting_dialog_window(&mut self, ctx: &egui::Context, datasets: &mut Vec<Dataset>) {
         if !self.show_fitting_dialog {
@@ -988,7 +2042,28 @@ impl DataEditor {
                 ui.heading("Fit Mathematical Models");
                 ui.separator();
 
+                ui.checkbox(&mut self.global_fit, "Global fit: one model for several datasets at once")
+                    .on_hover_text("Chosen parameters take one value for all datasets, the others one per dataset");
+
                 // Dataset selection
+                if self.global_fit {
+                    self.global_datasets.retain(|&i| i < datasets.len());
+                    ui.label("Datasets:");
+                    ui.horizontal_wrapped(|ui| {
+                        for (i, dataset) in datasets.iter().enumerate() {
+// Variable declaration
+                            let mut chosen = self.global_datasets.contains(&i);
+                            if ui.checkbox(&mut chosen, &dataset.name).changed() {
+                                if chosen {
+                                    self.global_datasets.push(i);
+                                    self.global_datasets.sort();
+                                } else {
+                                    self.global_datasets.retain(|&j| j != i);
+                                }
+                            }
+                        }
+                    });
+                } else {
                 ui.horizontal(|ui| {
                     ui.label("Dataset:");
                     if !datasets.is_empty() {
@@ -1004,6 +2079,7 @@ impl DataEditor {
                             });
                     }
                 });
+                }
 
                 // Model selection
                 ui.horizontal(|ui| {
@@ -1014,33 +2090,165 @@ impl DataEditor {
                             ui.selectable_value(&mut self.selected_fit_model, FitModel::Linear, FitModel::Linear.to_string());
                             ui.selectable_value(&mut self.selected_fit_model, FitModel::Sigmoid, FitModel::Sigmoid.to_string());
                             ui.selectable_value(&mut self.selected_fit_model, FitModel::Hill, FitModel::Hill.to_string());
+                            ui.selectable_value(&mut self.selected_fit_model, FitModel::Exponential, FitModel::Exponential.to_string());
+                            ui.selectable_value(&mut self.selected_fit_model, FitModel::Gaussian, FitModel::Gaussian.to_string());
+                            ui.selectable_value(&mut self.selected_fit_model, FitModel::Polynomial, FitModel::Polynomial.to_string());
+                            ui.selectable_value(&mut self.selected_fit_model, FitModel::Custom, FitModel::Custom.to_string());
                         });
                 });
 
+                if self.selected_fit_model == FitModel::Custom {
+                    ui.horizontal(|ui| {
+                        ui.label("y =");
+                        ui.text_edit_singleline(&mut self.custom_fit_expression);
+                    });
+                    ui.small("Use x, numbers, + - * / ^, exp, ln, log10, sqrt, abs, sin, cos, tan, pi. Other names are fit parameters.");
+                    match Expression::parse(&self.custom_fit_expression) {
+                        Ok(expression) => {
+                            if expression.parameters.is_empty() {
+                                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "No free parameters to fit.");
+                            }
+                        }
+                        Err(e) => {
+                            ui.colored_label(egui::Color32::RED, format!("Invalid expression: {}", e));
+                        }
+                    }
+                }
+
+                if self.selected_fit_model == FitModel::Linear && !self.global_fit {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.confidence_band, "95% confidence band")
+                            .on_hover_text("Where the true line lies");
+                        ui.checkbox(&mut self.prediction_band, "95% prediction band")
+                            .on_hover_text("Where a new measurement would fall");
+                    });
+                }
+
+                if self.selected_fit_model == FitModel::Polynomial {
+                    ui.horizontal(|ui| {
+                        ui.label("Degree:");
+                        ui.add(egui::DragValue::new(&mut self.polynomial_degree).clamp_range(1..=8));
+                    });
+                }
+
+                egui::CollapsingHeader::new("Parameters: starting values, bounds, fixed")
+                    .default_open(matches!(self.selected_fit_model, FitModel::Sigmoid | FitModel::Hill))
+                    .show(ui, |ui| {
+                        self.show_parameter_settings(ui);
+                    });
+
+                // Weighting needs Y error bars on the dataset being fitted
+                if self.global_fit {
+                    ui.label("Shared parameters:");
+                    ui.horizontal_wrapped(|ui| {
+                        for name in self.parameter_names() {
+// Variable declaration
+                            let mut shared = self.shared_parameters.contains(&name);
+                            if ui.checkbox(&mut shared, &name).changed() {
+                                if shared {
+                                    self.shared_parameters.insert(name);
+                                } else {
+                                    self.shared_parameters.remove(&name);
+                                }
+                            }
+                        }
+                    });
+                }
+
+// Variable declaration
+                let has_y_errors = if self.global_fit {
+                    self.global_datasets.iter().any(|&i| datasets.get(i).is_some_and(|d| d.y_err.is_some()))
+                } else {
+                    datasets.get(self.fitting_dataset_index).is_some_and(|d| d.y_err.is_some())
+                };
+                ui.add_enabled(has_y_errors, egui::Checkbox::new(&mut self.weighted_fit, "Weight points by 1/σ² of their Y error bars"))
+                    .on_disabled_hover_text("The dataset has no Y error bars");
+
+                if let Some((low, high)) = self.fit_x_range {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(100, 150, 255),
+                        format!(
+                            "Fitting only the selected range x = {} to {}",
+                            format_number(low),
+                            format_number(high)
+                        ),
+                    );
+                }
+
                 ui.separator();
 
                 // Fit button
                 if ui.button("ðŸ”¬ Perform Fit").clicked() {
-                    if let Some(dataset) = datasets.get(self.fitting_dataset_index) {
-                        if let Some(fit_result) = self.perform_curve_fit(dataset) {
+                    self.fit_error = None;
+                    if self.global_fit {
+// Variable declaration
+                        let sources: Vec<Dataset> =
+                            self.global_datasets.iter().filter_map(|&i| datasets.get(i)).map(|d| self.dataset_to_fit(d)).collect();
+                        match self.perform_global_fit(&sources) {
+                            Ok(result) => {
+                                // One curve per dataset, in the dataset's color
+                                for (fit, source) in result.fits.iter().zip(&sources) {
+                                    datasets.push(Dataset::new(
+                                        format!("{}_global_fit", source.name),
+                                        fit.fitted_points.clone(),
+                                        source.color,
+                                    ));
+                                }
+                                self.global_fit_results.push(result);
+                            }
+                            Err(e) => self.fit_error = Some(e),
+                        }
+                    } else if let Some(source) = datasets.get(self.fitting_dataset_index) {
+// Variable declaration
+                        let dataset = self.dataset_to_fit(source);
+                        if let Some(fit_result) = self.perform_curve_fit(&dataset) {
                             self.fit_results.push(fit_result.clone());
 
                             // Add fitted curve as new dataset
 // Variable declaration
-                            let fitted_dataset = Dataset {
-                                name: format!("{}_fitted", dataset.name),
-                                points: fit_result.fitted_points,
-                                color: get_default_color((datasets.len() + 1) % 8),
-                            };
+                            let fitted_dataset = Dataset::new(
+                                format!("{}_fitted", dataset.name),
+                                fit_result.fitted_points,
+                                self.palette.color(datasets.len() + 1),
+                            );
+                            // Bands are shaded around the fitted line in its color, drawn dotted so
+                            // the fit itself stays on top
+                            if let Some(linear) = &fit_result.linear {
+                                for (wanted, suffix, widths) in [
+                                    (self.prediction_band, "95pi", &linear.prediction_band),
+                                    (self.confidence_band, "95ci", &linear.confidence_band),
+                                ] {
+                                    if wanted {
+// Variable declaration
+                                        let mut band = Dataset::new(
+                                            format!("{}_fit_{}", dataset.name, suffix),
+                                            fitted_dataset.points.to_vec(),
+                                            fitted_dataset.color,
+                                        );
+                                        band.band = Some(widths.clone());
+                                        band.line_style = LinePattern::Dotted;
+                                        band.line_width = 1.0;
+                                        datasets.push(band);
+                                    }
+                                }
+                            }
                             datasets.push(fitted_dataset);
+                        } else {
+                            self.fit_error = Some(format!("Could not fit {} to {}", self.selected_fit_model.to_string(), dataset.name));
                         }
                     }
                 }
+                if let Some(error) = &self.fit_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
 
                 ui.separator();
 
                 // Results display
                 ui.heading("Fit Results:");
+                ui.checkbox(&mut self.residuals_below, "Place residuals in the subplot below");
+// Variable declaration
+                let mut residuals_requested = None;
                 egui::ScrollArea::vertical()
                     .max_height(200.0)
                     .show(ui, |ui| {
@@ -1048,18 +2256,99 @@ impl DataEditor {
                             ui.group(|ui| {
                                 ui.label(format!("Fit {}: {}", i + 1, result.model.to_string()));
                                 ui.label(format!("RÂ² = {:.4}", result.r_squared));
+                                if let Some((chi_square, reduced)) = result.chi_square {
+                                    ui.label(format!("χ² = {:.4}, reduced χ² = {:.4} (weighted by 1/σ²)", chi_square, reduced))
+                                        .on_hover_text("A reduced χ² near 1 means the scatter about the fit matches the error bars");
+                                }
                                 ui.label(&result.equation_string);
 
-                                for (param_name, param_value) in result.parameter_names.iter().zip(&result.parameters) {
-                                    ui.label(format!("{} = {:.6}", param_name, param_value));
+                                if let Some(linear) = &result.linear {
+                                    for (param_name, param_value, error) in [
+                                        ("slope", result.parameters[0], linear.slope_error),
+                                        ("intercept", result.parameters[1], linear.intercept_error),
+                                    ] {
+                                        ui.label(format!("{} = {:.6} ± {:.6}", param_name, param_value, error));
+                                    }
+                                    ui.label(format!(
+                                        "p = {:.3e} (slope ≠ 0, {} degrees of freedom)",
+                                        linear.p_value, linear.degrees_of_freedom
+                                    ));
+                                } else {
+                                    for (param_name, param_value) in result.parameter_names.iter().zip(&result.parameters) {
+                                        ui.label(format!("{} = {:.6}", param_name, param_value));
+                                    }
+                                }
+
+                                if ui.button("Create residuals dataset").clicked() {
+                                    residuals_requested = Some(i);
+                                }
+                            });
+                        }
+
+                        for (i, result) in self.global_fit_results.iter().enumerate() {
+                            ui.group(|ui| {
+                                ui.label(format!(
+                                    "Global fit {}: {} over {} datasets",
+                                    i + 1,
+                                    result.model.to_string(),
+                                    result.fits.len()
+                                ));
+                                if let Some((chi_square, reduced)) = result.chi_square {
+                                    ui.label(format!("χ² = {:.4}, reduced χ² = {:.4} (weighted by 1/σ²)", chi_square, reduced));
+                                }
+// Variable declaration
+                                let first = &result.fits[0];
+                                for (j, name) in result.parameter_names.iter().enumerate().filter(|(j, _)| result.shared[*j]) {
+                                    ui.label(format!("{} = {:.6} (shared)", name, first.parameters[j]));
+                                }
+                                for fit in &result.fits {
+// Variable declaration
+                                    let own: Vec<String> = result
+                                        .parameter_names
+                                        .iter()
+                                        .zip(&fit.parameters)
+                                        .zip(&result.shared)
+                                        .filter(|(_, shared)| !**shared)
+                                        .map(|((name, value), _)| format!("{} = {:.6}", name, value))
+                                        .collect();
+                                    ui.label(format!("{}: R² = {:.4}  {}", fit.source_name, fit.r_squared, own.join(", ")));
                                 }
                             });
                         }
                     });
 
+                // Apply after the results loop to avoid borrowing conflicts
+                if let Some(result) = residuals_requested.and_then(|i| self.fit_results.get(i)) {
+// Variable declaration
+                    let mut residuals = Dataset::new(
+                        format!("{}_residuals", result.source_name),
+                        result.residuals.clone(),
+                        self.palette.color(datasets.len()),
+                    );
+                    residuals.plot_type = PlotType::Scatter;
+                    if self.residuals_below {
+                        self.pending_residuals = Some(residuals);
+                    } else {
+                        datasets.push(residuals);
+                    }
+                }
+
                 ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !self.fit_results.is_empty() || !self.global_fit_results.is_empty(),
+                            egui::Button::new("💾 Export Fit Report"),
+                        )
+                        .on_hover_text("Save all fit results as text (.txt), CSV (.csv) or Markdown (.md)")
+                        .clicked()
+                    {
+                        self.report_requested = true;
+                    }
+
                     if ui.button("Clear Results").clicked() {
                         self.fit_results.clear();
+                        self.global_fit_results.clear();
+                        self.fit_error = None;
                     }
 
                     if ui.button("Close").clicked() {
@@ -1069,51 +2358,354 @@ impl DataEditor {
             });
     }
 
+    // Table of the selected model's parameters, each with an optional starting value and
+    // bounds, and a toggle holding it at its starting value
 /// Function: explain its purpose and key arguments
-    fn perform_curve_fit(&self, dataset: &Dataset) -> Option<FitResult> {
-        if dataset.points.len() < 3 {
-            return None; // Need at least 3 points for fitting
+    fn show_parameter_settings(&mut self, ui: &mut egui::Ui) {
+// Variable declaration
+        let names = self.parameter_names();
+        if names.is_empty() {
+            ui.weak("The model has no parameters to set");
+            return;
         }
-
-        match self.selected_fit_model {
-            FitModel::Linear => self.fit_linear(dataset),
-            FitModel::Sigmoid => self.fit_sigmoid(dataset),
-            FitModel::Hill => self.fit_hill(dataset),
+        egui::Grid::new("fit_parameter_grid").num_columns(5).striped(true).show(ui, |ui| {
+            for header in ["Parameter", "Start", "Lower", "Upper", "Fixed"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for name in names {
+// Variable declaration
+                let setting = self.parameter_settings.entry((self.selected_fit_model.clone(), name.clone())).or_default();
+                ui.label(&name);
+                for (value, default, unset) in [
+                    (&mut setting.initial, 1.0, "auto"),
+                    (&mut setting.lower, 0.0, "none"),
+                    (&mut setting.upper, 0.0, "none"),
+                ] {
+                    ui.horizontal(|ui| {
+// Variable declaration
+                        let mut set = value.is_some();
+                        if ui.checkbox(&mut set, "").changed() {
+                            *value = set.then_some(default);
+                        }
+                        match value {
+                            Some(value) => {
+                                ui.add(egui::DragValue::new(value).speed(0.01));
+                            }
+                            None => {
+                                ui.weak(unset);
+                            }
+                        }
+                    });
+                }
+                ui.checkbox(&mut setting.fixed, "")
+                    .on_hover_text("Keep the starting value (estimated from the data when not set)");
+                ui.end_row();
+            }
+        });
+        if matches!(self.selected_fit_model, FitModel::Linear | FitModel::Polynomial) {
+            ui.small("These fits are solved exactly, so starting values only matter with bounds or fixed parameters.");
         }
     }
 
+    // Starting values and limits for the selected model's parameters: the starting value
+    // set in the dialog, else `estimate`, moved inside the bounds. A fixed parameter is
+    // limited to its starting value.
 /// Function: explain its purpose and key arguments
-    fn fit_linear(&self, dataset: &Dataset) -> Option<FitResult> {
+    fn constrain(&self, estimate: Vec<f64>) -> (Vec<f64>, Vec<(f64, f64)>) {
 // Variable declaration
-        let n = dataset.points.len() as f64;
+        let names = self.parameter_names();
+        estimate
+            .into_iter()
+            .enumerate()
+            .map(|(j, value)| {
 // Variable declaration
-        let sum_x: f64 = dataset.points.iter().map(|p| p[0]).sum();
+                let setting = names
+                    .get(j)
+                    .and_then(|name| self.parameter_settings.get(&(self.selected_fit_model.clone(), name.clone())))
+                    .cloned()
+                    .unwrap_or_default();
 // Variable declaration
-        let sum_y: f64 = dataset.points.iter().map(|p| p[1]).sum();
+                let (lower, upper) = (setting.lower.unwrap_or(f64::NEG_INFINITY), setting.upper.unwrap_or(f64::INFINITY));
 // Variable declaration
-        let sum_xy: f64 = dataset.points.iter().map(|p| p[0] * p[1]).sum();
+                let (lower, upper) = (lower.min(upper), lower.max(upper));
 // Variable declaration
-        let sum_x2: f64 = dataset.points.iter().map(|p| p[0] * p[0]).sum();
-
-// Variable declaration
-        let slope = (n * sum_xy - sum_x * sum_y) / (n * sum_x2 - sum_x * sum_x);
-// Variable declaration
-        let intercept = (sum_y - slope * sum_x) / n;
+                let start = setting.initial.unwrap_or(value).clamp(lower, upper);
+                (start, if setting.fixed { (start, start) } else { (lower, upper) })
+            })
+            .unzip()
+    }
 
-        // Calculate RÂ²
+    // Copy of `source` holding the points to fit: those inside the fit range, with their
+    // Y error bars for weighting
+/// Function: explain its purpose and key arguments
+    fn dataset_to_fit(&self, source: &Dataset) -> Dataset {
+// Variable declaration
+        let mut dataset = Dataset::new(
+            source.name.clone(),
+            finite_points_in_x_range(&source.points, self.fit_x_range),
+            source.color,
+        );
+        // Same points as above: in the fit range and not blank (NaN)
+// Variable declaration
+        let keep = |p: &[f64; 2]| {
+            p[0].is_finite() && p[1].is_finite() && self.fit_x_range.is_none_or(|(a, b)| p[0] >= a.min(b) && p[0] <= a.max(b))
+        };
+        dataset.y_err = source.y_err.as_ref().map(|errors| {
+            source.points.iter().zip(errors).filter(|(p, _)| keep(p)).map(|(_, e)| *e).collect()
+        });
+        dataset.discard_mismatched_errors();
+        dataset
+    }
+
+    // Names of the selected model's parameters, in the order the fits report them
+/// Function: explain its purpose and key arguments
+    fn parameter_names(&self) -> Vec<String> {
+        match self.selected_fit_model {
+            FitModel::Polynomial => (0..=self.polynomial_degree.max(1)).map(|k| format!("c{}", k)).collect(),
+            FitModel::Custom => Expression::parse(&self.custom_fit_expression).map_or(Vec::new(), |expression| expression.parameters),
+            ref model => model.formula().map_or(Vec::new(), |formula| formula.parameter_names()),
+        }
+    }
+
+    // y = f(x, parameters) for the selected model, with the parameters in the order of
+    // parameter_names
+/// Function: explain its purpose and key arguments
+    fn model_function(&self) -> Option<ModelFunction> {
+        Some(match self.selected_fit_model {
+            FitModel::Polynomial => Box::new(polynomial),
+            FitModel::Custom => {
+// Variable declaration
+                let expression = Expression::parse(&self.custom_fit_expression).ok()?;
+                Box::new(move |x, p| expression.evaluate(x, p))
+            }
+            ref model => Box::new(model.formula()?.function),
+        })
+    }
+
+    // Fit the selected model to all `sources` together by least squares. Parameters marked
+    // as shared take one value for every dataset; the rest get one value per dataset. Each
+    // dataset is first fitted on its own, and those results (averaged, for the shared
+    // parameters) are the starting point.
+/// Function: explain its purpose and key arguments
+    fn perform_global_fit(&self, sources: &[Dataset]) -> Result<GlobalFitResult, String> {
+        if sources.len() < 2 {
+            return Err("Choose at least two datasets for a global fit".to_string());
+        }
+// Variable declaration
+        let model = self.model_function().ok_or("The fit expression is not valid")?;
+// Variable declaration
+        let single_fits = sources
+            .iter()
+            .map(|source| {
+                self.perform_curve_fit(source)
+                    .ok_or_else(|| format!("Could not fit {} on its own to find starting values", source.name))
+            })
+            .collect::<Result<Vec<FitResult>, String>>()?;
+// Variable declaration
+        let parameter_names = single_fits[0].parameter_names.clone();
+// Variable declaration
+        let shared: Vec<bool> = parameter_names.iter().map(|name| self.shared_parameters.contains(name)).collect();
+
+        // Bounds and fixed values set in the dialog hold for every dataset
+// Variable declaration
+        let (_, parameter_limits) = self.constrain(single_fits[0].parameters.clone());
+
+        // Position of each dataset's parameters in the combined parameter list: the shared
+        // ones first, then the remaining ones of every dataset in turn
+// Variable declaration
+        let mut start = Vec::new();
+// Variable declaration
+        let mut limits = Vec::new();
+// Variable declaration
+        let mut positions = vec![vec![0; parameter_names.len()]; sources.len()];
+        for j in (0..parameter_names.len()).filter(|j| shared[*j]) {
+            for dataset_positions in positions.iter_mut() {
+                dataset_positions[j] = start.len();
+            }
+            start.push(single_fits.iter().map(|fit| fit.parameters[j]).sum::<f64>() / single_fits.len() as f64);
+            limits.push(parameter_limits[j]);
+        }
+        for (fit, dataset_positions) in single_fits.iter().zip(positions.iter_mut()) {
+            for j in (0..parameter_names.len()).filter(|j| !shared[*j]) {
+                dataset_positions[j] = start.len();
+                start.push(fit.parameters[j]);
+                limits.push(parameter_limits[j]);
+            }
+        }
+// Variable declaration
+        let unpack = |combined: &[f64], d: usize| -> Vec<f64> { positions[d].iter().map(|&i| combined[i]).collect() };
+
+        // Weighted only when every dataset has error bars, as weights of 1/σ² and of 1 do not mix
+// Variable declaration
+        let error_weights: Option<Vec<Vec<f64>>> = sources.iter().map(|source| self.fit_weights(source)).collect();
+// Variable declaration
+        let weights: Vec<Vec<f64>> = error_weights
+            .clone()
+            .unwrap_or_else(|| sources.iter().map(|source| vec![1.0; source.points.len()]).collect());
+// Variable declaration
+        let LeastSquaresFit { parameters: combined, covariance } = least_squares(start, &limits, &|combined| {
+            sources
+                .iter()
+                .zip(&weights)
+                .enumerate()
+                .flat_map(|(d, (source, weights))| {
+// Variable declaration
+                    let params = unpack(combined, d);
+                    source
+                        .points
+                        .iter()
+                        .zip(weights)
+                        .map(|(pt, w)| w.sqrt() * (pt[1] - model(pt[0], &params)))
+                        .collect::<Vec<f64>>()
+                })
+                .collect()
+        })
+        .ok_or("The global fit did not converge from the single-dataset fits")?;
+
+// Variable declaration
+        let fits: Vec<FitResult> = sources
+            .iter()
+            .enumerate()
+            .map(|(d, source)| {
+// Variable declaration
+                let params = unpack(&combined, d);
+                // Each dataset's block of the combined covariance, shared parameters included
+// Variable declaration
+                let covariance = covariance.as_ref().map(|covariance| {
+                    positions[d].iter().map(|&j| positions[d].iter().map(|&k| covariance[j][k]).collect()).collect()
+                });
+                FitResult {
+                    model: self.selected_fit_model.clone(),
+                    parameter_names: parameter_names.clone(),
+                    r_squared: r_squared(&source.points, |x| model(x, &params)),
+                    fitted_points: sample_fit_curve(&source.points, |x| model(x, &params)),
+                    residuals: fit_residuals(&source.points, |x| model(x, &params)),
+                    source_name: source.name.clone(),
+                    equation_string: self.selected_fit_model.to_string().to_string(),
+                    linear: None,
+                    covariance,
+                    chi_square: None,
+                    parameters: params,
+                }
+            })
+            .collect();
+
+        // Pooled over every point, with one degree of freedom used by each fitted value
+// Variable declaration
+        let chi_square = error_weights.is_some().then(|| {
+// Variable declaration
+            let total: f64 = fits
+                .iter()
+                .zip(&weights)
+                .map(|(fit, weights)| fit.residuals.iter().zip(weights).map(|(r, w)| w * r[1] * r[1]).sum::<f64>())
+                .sum();
+// Variable declaration
+            let count: usize = weights.iter().map(|weights| weights.iter().filter(|w| **w > 0.0).count()).sum();
+// Variable declaration
+            let degrees_of_freedom = count.saturating_sub(combined.len()).max(1);
+            (total, total / degrees_of_freedom as f64)
+        });
+        Ok(GlobalFitResult {
+            model: self.selected_fit_model.clone(),
+            parameter_names,
+            shared,
+            fits,
+            chi_square,
+        })
+    }
+
+/// Function: explain its purpose and key arguments
+    fn perform_curve_fit(&self, dataset: &Dataset) -> Option<FitResult> {
+        if dataset.points.len() < 3 {
+            return None; // Need at least 3 points for fitting
+        }
+
+// Variable declaration
+        let error_weights = self.fit_weights(dataset);
+// Variable declaration
+        let weights = error_weights.clone().unwrap_or_else(|| vec![1.0; dataset.points.len()]);
+// Variable declaration
+        let mut result = match self.selected_fit_model {
+            FitModel::Linear => self.fit_linear(dataset, &weights),
+            FitModel::Sigmoid => self.fit_sigmoid(dataset, &weights),
+            FitModel::Hill => self.fit_hill(dataset, &weights),
+            FitModel::Exponential => self.fit_exponential(dataset, &weights),
+            FitModel::Gaussian => self.fit_gaussian(dataset, &weights),
+            FitModel::Polynomial => self.fit_polynomial(dataset, &weights),
+            FitModel::Custom => self.fit_custom(dataset, &weights),
+        }?;
+
+        // The residuals come one per data point, in order
+        if error_weights.is_some() {
+// Variable declaration
+            let chi_square: f64 = result.residuals.iter().zip(&weights).map(|(r, w)| w * r[1] * r[1]).sum();
+// Variable declaration
+            let count = weights.iter().filter(|w| **w > 0.0).count();
+// Variable declaration
+            let degrees_of_freedom = count.saturating_sub(result.parameters.len()).max(1);
+            result.chi_square = Some((chi_square, chi_square / degrees_of_freedom as f64));
+        }
+        Some(result)
+    }
+
+    // Weight 1/σ² of every point from the Y error bars, when the fit is weighted and the
+    // dataset has them. Points whose error bar is missing or not positive get no weight,
+    // so they are left out rather than taken as exact.
+/// Function: explain its purpose and key arguments
+    fn fit_weights(&self, dataset: &Dataset) -> Option<Vec<f64>> {
+// Variable declaration
+        let errors = dataset.y_err.as_ref().filter(|errors| self.weighted_fit && errors.len() == dataset.points.len())?;
+        Some(
+            errors
+                .iter()
+                .map(|sigma| if sigma.is_finite() && *sigma > 0.0 { 1.0 / (sigma * sigma) } else { 0.0 })
+                .collect(),
+        )
+    }
+
+/// Function: explain its purpose and key arguments
+    fn fit_linear(&self, dataset: &Dataset, weights: &[f64]) -> Option<FitResult> {
+        // Weighted sums; every weight is 1 for an unweighted fit
+// Variable declaration
+        let weighted = |f: &dyn Fn(&[f64; 2]) -> f64| dataset.points.iter().zip(weights).map(|(p, w)| w * f(&p)).sum::<f64>();
+// Variable declaration
+        let sum_w = weighted(&|_| 1.0);
+// Variable declaration
+        let x_mean = weighted(&|p| p[0]) / sum_w;
+// Variable declaration
+        let y_mean = weighted(&|p| p[1]) / sum_w;
+        // Centered sums keep the slope accurate when X is far from zero
 // Variable declaration
-        let y_mean = sum_y / n;
+        let sxx = weighted(&|p| (p[0] - x_mean).powi(2));
 // Variable declaration
-        let ss_tot: f64 = dataset.points.iter().map(|p| (p[1] - y_mean).powi(2)).sum();
+        let sxy = weighted(&|p| (p[0] - x_mean) * (p[1] - y_mean));
+
+// Variable declaration
+        let slope = sxy / sxx;
+// Variable declaration
+        let intercept = y_mean - slope * x_mean;
+
+        // With bounds or a fixed parameter (e.g. an intercept held at 0) the exact solution
+        // is only the starting point; the uncertainties below then no longer apply
+// Variable declaration
+        let (start, limits) = self.constrain(vec![slope, intercept]);
 // Variable declaration
-        let ss_res: f64 = dataset.points.iter().map(|p| {
+        let constrained = has_limits(&limits);
 // Variable declaration
-            let y_pred = slope * p[0] + intercept;
-            (p[1] - y_pred).powi(2)
-        }).sum();
+        let formula = FitModel::Linear.formula()?;
+// Variable declaration
+        let (slope, intercept, covariance) = if constrained {
+// Variable declaration
+            let fit = refine_parameters(&dataset.points, weights, start, &limits, &formula.function)?;
+            (fit.parameters[0], fit.parameters[1], fit.covariance)
+        } else {
+            (slope, intercept, None)
+        };
 
+        // Calculate R² (unweighted)
 // Variable declaration
-        let r_squared = 1.0 - (ss_res / ss_tot);
+        let r_squared = r_squared(&dataset.points, |x| slope * x + intercept);
 
         // Generate fitted points
 // Variable declaration
@@ -1131,19 +2723,65 @@ impl DataEditor {
             fitted_points.push([x, y]);
         }
 
+        // Standard errors with n - 2 degrees of freedom, scaled by the reduced χ² of the line
+        // like those of every other model (see parameter_covariance): error bars only set how
+        // much each point counts, and the typical one, scaled alike, stands in for a new
+        // point's scatter.
+// Variable declaration
+        let count = weights.iter().filter(|w| **w > 0.0).count();
+        if count < 3 {
+            return None;
+        }
+// Variable declaration
+        let degrees_of_freedom = count - 2;
+// Variable declaration
+        let df = degrees_of_freedom as f64;
+// Variable declaration
+        let variance_scale = weighted(&|p| (p[1] - slope * p[0] - intercept).powi(2)) / df;
+// Variable declaration
+        let new_point_variance = variance_scale * count as f64 / sum_w;
+// Variable declaration
+        let slope_error = (variance_scale / sxx).sqrt();
+// Variable declaration
+        let t_critical = student_t_critical(0.95, df);
+        // Half-width of the band at x; `spread` is 0 for the line itself and the variance of
+        // one measurement for a new point
+// Variable declaration
+        let half_width =
+            |x: f64, spread: f64| t_critical * (spread + variance_scale * (1.0 / sum_w + (x - x_mean).powi(2) / sxx)).sqrt();
+// Variable declaration
+        let linear = (!constrained).then(|| LinearFitStatistics {
+            slope_error,
+            intercept_error: (variance_scale * (1.0 / sum_w + x_mean * x_mean / sxx)).sqrt(),
+            p_value: student_t_p_value(slope / slope_error, df),
+            degrees_of_freedom,
+            confidence_band: fitted_points.iter().map(|p| half_width(p[0], 0.0)).collect(),
+            prediction_band: fitted_points.iter().map(|p| half_width(p[0], new_point_variance)).collect(),
+        });
+
         Some(FitResult {
             model: FitModel::Linear,
             parameters: vec![slope, intercept],
-            parameter_names: vec!["slope".to_string(), "intercept".to_string()],
+            parameter_names: formula.parameter_names(),
             r_squared,
             fitted_points,
+            residuals: fit_residuals(&dataset.points, |x| slope * x + intercept),
+            source_name: dataset.name.clone(),
+            linear,
+            covariance,
+            chi_square: None,
             equation_string: format!("y = {:.4}x + {:.4}", slope, intercept),
         })
     }
 
 /// Function: explain its purpose and key arguments
-    fn fit_sigmoid(&self, dataset: &Dataset) -> Option<FitResult> {
-        // Simplified sigmoid fitting using linearization
+    fn fit_sigmoid(&self, dataset: &Dataset, weights: &[f64]) -> Option<FitResult> {
+        // y = offset + a / (1 + exp(-b(x - c))), starting from the range of the values and
+        // the middle of the X values unless starting values are set
+// Variable declaration
+        let formula = FitModel::Sigmoid.formula()?;
+// Variable declaration
+        let model = formula.function;
 // Variable declaration
         let y_min = dataset.points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
 // Variable declaration
@@ -1164,113 +2802,576 @@ impl DataEditor {
 // Variable declaration
         let c = x_mid; // inflection point
 
-        // Generate fitted points
+// Variable declaration
+        let (start, limits) = self.constrain(vec![a, b, c, y_offset]);
+// Variable declaration
+        let LeastSquaresFit { parameters: params, covariance } = refine_parameters(&dataset.points, weights, start, &limits, &model)?;
+
+        Some(FitResult {
+            model: FitModel::Sigmoid,
+            equation_string: format!(
+                "y = {:.4} + {:.4} / (1 + exp(-{:.4}(x - {:.4})))",
+                params[3], params[0], params[1], params[2]
+            ),
+            parameter_names: formula.parameter_names(),
+            r_squared: r_squared(&dataset.points, |x| model(x, &params)),
+            fitted_points: sample_fit_curve(&dataset.points, |x| model(x, &params)),
+            residuals: fit_residuals(&dataset.points, |x| model(x, &params)),
+            source_name: dataset.name.clone(),
+            linear: None,
+            covariance,
+            chi_square: None,
+            parameters: params,
+        })
+    }
+
+/// Function: explain its purpose and key arguments
+    fn fit_hill(&self, dataset: &Dataset, weights: &[f64]) -> Option<FitResult> {
+        // y = (a * x^n) / (k^n + x^n), defined for x > 0, starting from the largest value,
+        // the X where the values are nearest half of it and n = 2 unless starting values are set
+// Variable declaration
+        let formula = FitModel::Hill.formula()?;
+// Variable declaration
+        let model = formula.function;
+
+// Variable declaration
+        let y_max = dataset.points.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max);
+// Variable declaration
+        let a = y_max; // maximum response
+
+        // Find approximate K (half-maximal concentration)
+// Variable declaration
+        let half_max = a / 2.0;
+// Variable declaration
+        let k = dataset.points.iter()
+            .min_by(|p1, p2| (p1[1] - half_max).abs().total_cmp(&(p2[1] - half_max).abs()))
+            .map(|p| p[0])
+            .unwrap_or(1.0);
+
+// Variable declaration
+        let n = 2.0; // Hill coefficient (cooperativity)
+
+// Variable declaration
+        let (start, limits) = self.constrain(vec![a, k, n]);
+// Variable declaration
+        let LeastSquaresFit { parameters: params, covariance } = refine_parameters(&dataset.points, weights, start, &limits, &model)?;
+
+        Some(FitResult {
+            model: FitModel::Hill,
+            equation_string: format!(
+                "y = ({:.4} * x^{:.2}) / ({:.4}^{:.2} + x^{:.2})",
+                params[0], params[2], params[1], params[2], params[2]
+            ),
+            parameter_names: formula.parameter_names(),
+            r_squared: r_squared(&dataset.points, |x| model(x, &params)),
+            fitted_points: sample_fit_curve(&dataset.points, |x| model(x, &params)),
+            residuals: fit_residuals(&dataset.points, |x| model(x, &params)),
+            source_name: dataset.name.clone(),
+            linear: None,
+            covariance,
+            chi_square: None,
+            parameters: params,
+        })
+    }
+
+/// Function: explain its purpose and key arguments
+    fn fit_exponential(&self, dataset: &Dataset, weights: &[f64]) -> Option<FitResult> {
+        // y = a * exp(-x / t) + c, starting from the first and last values of the series
+// Variable declaration
+        let formula = FitModel::Exponential.formula()?;
+// Variable declaration
+        let model = formula.function;
+// Variable declaration
+        let mut sorted = dataset.points.to_vec();
+        sorted.sort_by(|p1, p2| p1[0].total_cmp(&p2[0]));
+// Variable declaration
+        let (first, last) = (sorted[0], sorted[sorted.len() - 1]);
+// Variable declaration
+        let c = last[1];
+// Variable declaration
+        let t = ((last[0] - first[0]) / 3.0).max(f64::EPSILON);
+        // The amplitude is the value at x = 0, far outside the data when X starts far from 0
+        // compared with its span (e.g. x = 1000 to 1001), so guessing it from the first value
+        // is badly conditioned or overflows. Fit first with X shifted to start at 0, then
+        // carry the amplitude back to x = 0.
+// Variable declaration
+        let shifted = PointStore::from(dataset.points.iter().map(|p| [p[0] - first[0], p[1]]).collect::<Vec<_>>());
+// Variable declaration
+        let unbounded = [(f64::NEG_INFINITY, f64::INFINITY); 3];
+// Variable declaration
+        let estimate = refine_parameters(&shifted, weights, vec![first[1] - c, t, c], &unbounded, &model)
+            .map(|fit| fit.parameters)
+            .map(|p| vec![p[0] * (first[0] / p[1]).exp(), p[1], p[2]])
+            .filter(|p| p[0] != 0.0 && p.iter().all(|v| v.is_finite()));
+        // No finite amplitude at x = 0 (the decay is too fast for the offset): start from a
+        // slower decay that keeps it finite
+// Variable declaration
+        let estimate = estimate.unwrap_or_else(|| {
+// Variable declaration
+            let t = t.max(first[0].abs() / MAX_START_EXPONENT);
+            vec![(first[1] - c) / (-first[0] / t).exp(), t, c]
+        });
+
+// Variable declaration
+        let (start, limits) = self.constrain(estimate);
+// Variable declaration
+        let LeastSquaresFit { parameters: params, covariance } = refine_parameters(&dataset.points, weights, start, &limits, &model)?;
+
+        Some(FitResult {
+            model: FitModel::Exponential,
+            equation_string: format!("y = {:.4} * exp(-x / {:.4}) + {:.4}", params[0], params[1], params[2]),
+            parameter_names: formula.parameter_names(),
+            r_squared: r_squared(&dataset.points, |x| model(x, &params)),
+            fitted_points: sample_fit_curve(&dataset.points, |x| model(x, &params)),
+            residuals: fit_residuals(&dataset.points, |x| model(x, &params)),
+            source_name: dataset.name.clone(),
+            linear: None,
+            covariance,
+            chi_square: None,
+            parameters: params,
+        })
+    }
+
+/// Function: explain its purpose and key arguments
+    fn fit_gaussian(&self, dataset: &Dataset, weights: &[f64]) -> Option<FitResult> {
+        // y = a * exp(-(x - m)^2 / (2 s^2)) + c, starting from the highest point and the
+        // width at half maximum
+// Variable declaration
+        let formula = FitModel::Gaussian.formula()?;
+// Variable declaration
+        let model = formula.function;
+// Variable declaration
+        let c = dataset.points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+// Variable declaration
+        let peak = dataset.points.iter().max_by(|p1, p2| p1[1].total_cmp(&p2[1]))?;
+// Variable declaration
+        let a = peak[1] - c;
+// Variable declaration
+        let above_half: Vec<f64> = dataset.points.iter()
+            .filter(|p| p[1] >= c + a / 2.0)
+            .map(|p| p[0])
+            .collect();
+// Variable declaration
+        let fwhm = above_half.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+            - above_half.iter().copied().fold(f64::INFINITY, f64::min);
 // Variable declaration
         let x_min = dataset.points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min);
 // Variable declaration
         let x_max = dataset.points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max);
+// Variable declaration
+        let s = if fwhm > 0.0 { fwhm / 2.355 } else { ((x_max - x_min) / 6.0).max(f64::EPSILON) };
 
 // Variable declaration
-        let mut fitted_points = Vec::new();
-        for i in 0..100 {
+        let (start, limits) = self.constrain(vec![a, peak[0], s, c]);
 // Variable declaration
-            let x = x_min + (x_max - x_min) * (i as f64 / 99.0);
+        let LeastSquaresFit { parameters: mut params, covariance } =
+            refine_parameters(&dataset.points, weights, start, &limits, &model)?;
+        params[2] = params[2].abs();
+
+        Some(FitResult {
+            model: FitModel::Gaussian,
+            equation_string: format!(
+                "y = {:.4} * exp(-(x - {:.4})^2 / (2 * {:.4}^2)) + {:.4}",
+                params[0], params[1], params[2], params[3]
+            ),
+            parameter_names: formula.parameter_names(),
+            r_squared: r_squared(&dataset.points, |x| model(x, &params)),
+            fitted_points: sample_fit_curve(&dataset.points, |x| model(x, &params)),
+            residuals: fit_residuals(&dataset.points, |x| model(x, &params)),
+            source_name: dataset.name.clone(),
+            linear: None,
+            covariance,
+            chi_square: None,
+            parameters: params,
+        })
+    }
+
+/// Function: explain its purpose and key arguments
+    fn fit_polynomial(&self, dataset: &Dataset, weights: &[f64]) -> Option<FitResult> {
+        // (Weighted) least squares through the normal equations; coefficients are c0..cn
 // Variable declaration
-            let y = y_offset + a / (1.0 + (-b * (x - c)).exp());
-            fitted_points.push([x, y]);
+        let degree = self.polynomial_degree.max(1);
+        if dataset.points.len() <= degree {
+            return None;
         }
 
-        // Calculate RÂ²
 // Variable declaration
-        let y_mean = dataset.points.iter().map(|p| p[1]).sum::<f64>() / dataset.points.len() as f64;
+        let terms = degree + 1;
+// Variable declaration
+        let mut normal = vec![vec![0.0; terms]; terms];
+// Variable declaration
+        let mut rhs = vec![0.0; terms];
+        for (p, w) in dataset.points.iter().zip(weights) {
 // Variable declaration
-        let ss_tot: f64 = dataset.points.iter().map(|p| (p[1] - y_mean).powi(2)).sum();
+            let powers: Vec<f64> = (0..terms).map(|k| p[0].powi(k as i32)).collect();
+            for j in 0..terms {
+                rhs[j] += w * powers[j] * p[1];
+                for k in 0..terms {
+                    normal[j][k] += w * powers[j] * powers[k];
+                }
+            }
+        }
 // Variable declaration
-        let ss_res: f64 = dataset.points.iter().map(|p| {
+        let coefficients = solve_linear_system(normal.clone(), rhs)?;
+        // Bounds and fixed coefficients need the iterative solver, started from the exact solution
 // Variable declaration
-            let y_pred = y_offset + a / (1.0 + (-b * (p[0] - c)).exp());
-            (p[1] - y_pred).powi(2)
-        }).sum();
+        let (start, limits) = self.constrain(coefficients.clone());
+// Variable declaration
+        let LeastSquaresFit { parameters: coefficients, covariance } = if has_limits(&limits) {
+            refine_parameters(&dataset.points, weights, start, &limits, &polynomial)?
+        } else {
+            // The normal matrix is J^T J of the weighted residuals
+// Variable declaration
+            let residuals: Vec<f64> = dataset
+                .points
+                .iter()
+                .zip(weights)
+                .map(|(p, w)| w.sqrt() * (p[1] - polynomial(p[0], &coefficients)))
+                .collect();
+// Variable declaration
+            let count = weights.iter().filter(|w| **w > 0.0).count();
+            LeastSquaresFit { covariance: parameter_covariance(normal, &residuals, count), parameters: coefficients }
+        };
+// Variable declaration
+        let model = |x: f64| polynomial(x, &coefficients);
 
 // Variable declaration
-        let r_squared = 1.0 - (ss_res / ss_tot);
+        let mut equation_string = format!("y = {:.4}", coefficients[0]);
+        for (k, c) in coefficients.iter().enumerate().skip(1) {
+// Variable declaration
+            let sign = if *c < 0.0 { '-' } else { '+' };
+            if k == 1 {
+                equation_string.push_str(&format!(" {} {:.4}x", sign, c.abs()));
+            } else {
+                equation_string.push_str(&format!(" {} {:.4}x^{}", sign, c.abs(), k));
+            }
+        }
 
         Some(FitResult {
-            model: FitModel::Sigmoid,
-            parameters: vec![a, b, c, y_offset],
-            parameter_names: vec!["amplitude".to_string(), "steepness".to_string(), "inflection".to_string(), "offset".to_string()],
-            r_squared,
-            fitted_points,
-            equation_string: format!("y = {:.4} + {:.4} / (1 + exp(-{:.4}(x - {:.4})))", y_offset, a, b, c),
+            model: FitModel::Polynomial,
+            parameter_names: (0..terms).map(|k| format!("c{}", k)).collect(),
+            r_squared: r_squared(&dataset.points, model),
+            fitted_points: sample_fit_curve(&dataset.points, model),
+            residuals: fit_residuals(&dataset.points, model),
+            source_name: dataset.name.clone(),
+            linear: None,
+            covariance,
+            chi_square: None,
+            equation_string,
+            parameters: coefficients.clone(),
         })
     }
 
 /// Function: explain its purpose and key arguments
-    fn fit_hill(&self, dataset: &Dataset) -> Option<FitResult> {
-        // Simplified Hill equation fitting
-        // y = (a * x^n) / (k^n + x^n)
+    fn fit_custom(&self, dataset: &Dataset, weights: &[f64]) -> Option<FitResult> {
+        // Fit the free parameters of the user's formula, starting from the values set in the
+        // dialog (1 where none is set)
+// Variable declaration
+        let expression = Expression::parse(&self.custom_fit_expression).ok()?;
+// Variable declaration
+        let (start, limits) = self.constrain(vec![1.0; expression.parameters.len()]);
+// Variable declaration
+        let model = |x: f64, p: &[f64]| expression.evaluate(x, p);
+// Variable declaration
+        let LeastSquaresFit { parameters: params, covariance } = refine_parameters(&dataset.points, weights, start, &limits, &model)?;
+
+        Some(FitResult {
+            model: FitModel::Custom,
+            equation_string: format!("y = {}", expression.text),
+            parameter_names: expression.parameters.clone(),
+            r_squared: r_squared(&dataset.points, |x| model(x, &params)),
+            fitted_points: sample_fit_curve(&dataset.points, |x| model(x, &params)),
+            residuals: fit_residuals(&dataset.points, |x| model(x, &params)),
+            source_name: dataset.name.clone(),
+            linear: None,
+            covariance,
+            chi_square: None,
+            parameters: params,
+        })
+    }
+}
 
+// Coefficient of determination of `model` against the points
+/// Function: explain its purpose and key arguments
+fn r_squared(points: &PointStore, model: impl Fn(f64) -> f64) -> f64 {
 // Variable declaration
-        let y_max = dataset.points.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max);
+    let y_mean = points.iter().map(|p| p[1]).sum::<f64>() / points.len() as f64;
 // Variable declaration
-        let a = y_max; // maximum response
+    let ss_tot: f64 = points.iter().map(|p| (p[1] - y_mean).powi(2)).sum();
+// Variable declaration
+    let ss_res: f64 = points.iter().map(|p| (p[1] - model(p[0])).powi(2)).sum();
+    1.0 - (ss_res / ss_tot)
+}
 
-        // Find approximate K (half-maximal concentration)
+// Difference between the data and `model` at every data point
+/// Function: explain its purpose and key arguments
+fn fit_residuals(points: &PointStore, model: impl Fn(f64) -> f64) -> Vec<[f64; 2]> {
+    points.iter().map(|p| [p[0], p[1] - model(p[0])]).collect()
+}
+
+// Evaluate `model` at 100 evenly spaced X values across the range of the points
+/// Function: explain its purpose and key arguments
+fn sample_fit_curve(points: &PointStore, model: impl Fn(f64) -> f64) -> Vec<[f64; 2]> {
 // Variable declaration
-        let half_max = a / 2.0;
+    let x_min = points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min);
 // Variable declaration
-        let k = dataset.points.iter()
-            .min_by(|p1, p2| (p1[1] - half_max).abs().partial_cmp(&(p2[1] - half_max).abs()).unwrap())
-            .map(|p| p[0])
-            .unwrap_or(1.0);
+    let x_max = points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max);
+    (0..100)
+        .map(|i| {
+// Variable declaration
+            let x = x_min + (x_max - x_min) * (i as f64 / 99.0);
+            [x, model(x)]
+        })
+        .filter(|p| p[1].is_finite())
+        .collect()
+}
+
+// Refine `params` for a nonlinear model y = model(x, params) by least squares, each
+// squared residual multiplied by the point's weight. Returns None when no finite fit can
+// be reached from the starting values.
+/// Function: explain its purpose and key arguments
+fn refine_parameters(
+    points: &PointStore,
+    weights: &[f64],
+    params: Vec<f64>,
+    limits: &[(f64, f64)],
+    model: &dyn Fn(f64, &[f64]) -> f64,
+) -> Option<LeastSquaresFit> {
+    least_squares(params, limits, &|p| {
+        points.iter().zip(weights).map(|(pt, w)| w.sqrt() * (pt[1] - model(pt[0], p))).collect()
+    })
+}
+
+// Whether any parameter has bounds or is fixed, so the fit has to respect them. Limits that
+// are not numbers count too, so least_squares can turn them down.
+/// Function: explain its purpose and key arguments
+fn has_limits(limits: &[(f64, f64)]) -> bool {
+    limits.iter().any(|&(lower, upper)| lower != f64::NEG_INFINITY || upper != f64::INFINITY)
+}
+
+// Parameters found by least_squares, with their covariance where it can be estimated
+#[derive(Debug, Clone)]
+/// Data structure used in data_editor.rs module
+struct LeastSquaresFit {
+    parameters: Vec<f64>,
+    covariance: Option<Vec<Vec<f64>>>,
+}
 
+// Covariance of the parameters of a least-squares fit from J^T J at the solution: its
+// inverse scaled by the reduced χ² of the (weighted) `residuals`, `count` of which carry
+// weight. Fits weighted by 1/σ² are scaled too, so every model (and fit_linear) reports
+// its errors the same way. None when J^T J is singular or no degrees of freedom are left.
+/// Function: explain its purpose and key arguments
+fn parameter_covariance(jtj: Vec<Vec<f64>>, residuals: &[f64], count: usize) -> Option<Vec<Vec<f64>>> {
 // Variable declaration
-        let n = 2.0; // Hill coefficient (cooperativity)
+    let n = jtj.len();
+// Variable declaration
+    let degrees_of_freedom = count.checked_sub(n).filter(|&df| df > 0)?;
+// Variable declaration
+    let reduced_chi_square = residuals.iter().map(|r| r * r).sum::<f64>() / degrees_of_freedom as f64;
+    // One column of the inverse per unit vector
+// Variable declaration
+    let columns = (0..n)
+        .map(|k| solve_linear_system(jtj.clone(), (0..n).map(|j| if j == k { 1.0 } else { 0.0 }).collect()))
+        .collect::<Option<Vec<Vec<f64>>>>()?;
+    Some((0..n).map(|j| (0..n).map(|k| columns[k][j] * reduced_chi_square).collect()).collect())
+}
 
-        // Generate fitted points
+// Minimize the sum of squares of `residuals(params)` by Levenberg-Marquardt with a
+// forward-difference Jacobian. The residuals are data minus model (already weighted),
+// from one series or from several fitted together. Every parameter stays within its
+// (lower, upper) limits; steps that leave them are cut back to the bound, and parameters
+// whose limits coincide are not varied at all. The covariance comes from J^T J at the
+// solution (see parameter_covariance); fixed parameters get none. Returns None when no
+// finite fit can be reached from the starting values, or a limit is not a number (e.g. a
+// fixed parameter whose estimate failed).
+/// Function: explain its purpose and key arguments
+fn least_squares(
+    params: Vec<f64>,
+    limits: &[(f64, f64)],
+    residuals: &dyn Fn(&[f64]) -> Vec<f64>,
+) -> Option<LeastSquaresFit> {
+    if limits.iter().any(|(lower, upper)| lower.is_nan() || upper.is_nan() || lower > upper) {
+        return None;
+    }
 // Variable declaration
-        let x_min = dataset.points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min).max(0.001);
+    let clamp = |p: &[f64]| -> Vec<f64> { p.iter().zip(limits).map(|(v, (lower, upper))| v.clamp(*lower, *upper)).collect() };
 // Variable declaration
-        let x_max = dataset.points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max);
+    let mut params = clamp(&params);
+// Variable declaration
+    let free: Vec<usize> = (0..params.len()).filter(|&j| limits[j].0 < limits[j].1).collect();
+// Variable declaration
+    let sum_of_squares = |r: &[f64]| r.iter().map(|r| r * r).sum::<f64>();
+// Variable declaration
+    let mut current = residuals(&params);
+// Variable declaration
+    let mut cost = sum_of_squares(&current);
+    if !cost.is_finite() || params.iter().any(|p| !p.is_finite()) {
+        return None;
+    }
+    if free.is_empty() {
+        return Some(LeastSquaresFit { parameters: params, covariance: None });
+    }
 
 // Variable declaration
-        let mut fitted_points = Vec::new();
-        for i in 0..100 {
+    let n = free.len();
+    // Gauss-Newton system J^T J step = J^T r at `params`, and how many residuals the free
+    // parameters move (points without weight do not count)
 // Variable declaration
-            let x = x_min + (x_max - x_min) * (i as f64 / 99.0);
-            if x > 0.0 {
+    let normal_equations = |params: &[f64], current: &[f64]| {
+        // Change of the model with each free parameter at every point (the residuals fall
+        // by as much as the model rises)
 // Variable declaration
-                let y = (a * x.powf(n)) / (k.powf(n) + x.powf(n));
-                fitted_points.push([x, y]);
+        let gradients: Vec<Vec<f64>> = free
+            .iter()
+            .map(|&j| {
+// Variable declaration
+                let h = 1e-7 * params[j].abs().max(1e-7);
+// Variable declaration
+                let mut shifted = params.to_vec();
+                shifted[j] += h;
+                residuals(&shifted).iter().zip(current).map(|(moved, r)| (r - moved) / h).collect()
+            })
+            .collect();
+// Variable declaration
+        let mut jtj = vec![vec![0.0; n]; n];
+// Variable declaration
+        let mut jtr = vec![0.0; n];
+        for j in 0..n {
+            jtr[j] = gradients[j].iter().zip(current).map(|(g, r)| g * r).sum();
+            for k in 0..n {
+                jtj[j][k] = gradients[j].iter().zip(&gradients[k]).map(|(a, b)| a * b).sum();
             }
         }
+// Variable declaration
+        let count = (0..current.len()).filter(|&i| gradients.iter().any(|g| g[i] != 0.0)).count();
+        (jtj, jtr, count)
+    };
+// Variable declaration
+    let mut lambda = 1e-3;
+    for _ in 0..200 {
+// Variable declaration
+        let (jtj, jtr, _) = normal_equations(&params, &current);
 
-        // Calculate RÂ²
+        // Increase damping until a step lowers the cost
 // Variable declaration
-        let y_mean = dataset.points.iter().map(|p| p[1]).sum::<f64>() / dataset.points.len() as f64;
+        let mut improved = None;
+        while lambda < 1e12 {
 // Variable declaration
-        let ss_tot: f64 = dataset.points.iter().map(|p| (p[1] - y_mean).powi(2)).sum();
+            let mut damped = jtj.clone();
+            for (j, row) in damped.iter_mut().enumerate() {
+                row[j] += lambda * jtj[j][j].max(1e-12);
+            }
+            if let Some(step) = solve_linear_system(damped, jtr.clone()) {
 // Variable declaration
-        let ss_res: f64 = dataset.points.iter().map(|p| {
+                let mut candidate = params.clone();
+                for (&j, s) in free.iter().zip(&step) {
+                    candidate[j] += s;
+                }
 // Variable declaration
-            let y_pred = if p[0] > 0.0 {
-                (a * p[0].powf(n)) / (k.powf(n) + p[0].powf(n))
-            } else {
-                0.0
-            };
-            (p[1] - y_pred).powi(2)
-        }).sum();
+                let candidate = clamp(&candidate);
+// Variable declaration
+                let candidate_residuals = residuals(&candidate);
+// Variable declaration
+                let candidate_cost = sum_of_squares(&candidate_residuals);
+                if candidate_cost.is_finite() && candidate_cost < cost {
+                    improved = Some((candidate, candidate_residuals, candidate_cost));
+                    lambda = (lambda / 10.0).max(1e-12);
+                    break;
+                }
+            }
+            lambda *= 10.0;
+        }
+
+// Variable declaration
+        let Some((candidate, candidate_residuals, candidate_cost)) = improved else {
+            break;
+        };
+// Variable declaration
+        let converged = cost - candidate_cost <= 1e-12 * cost;
+        params = candidate;
+        current = candidate_residuals;
+        cost = candidate_cost;
+        if converged {
+            break;
+        }
+    }
 
+    // Covariance of the free parameters, spread out to all of them
+// Variable declaration
+    let (jtj, _, count) = normal_equations(&params, &current);
 // Variable declaration
-        let r_squared = 1.0 - (ss_res / ss_tot);
+    let covariance = parameter_covariance(jtj, &current, count).map(|free_covariance| {
+// Variable declaration
+        let mut covariance = vec![vec![0.0; params.len()]; params.len()];
+        for (a, &j) in free.iter().enumerate() {
+            for (b, &k) in free.iter().enumerate() {
+                covariance[j][k] = free_covariance[a][b];
+            }
+        }
+        covariance
+    });
+    Some(LeastSquaresFit { parameters: params, covariance })
+}
 
-        Some(FitResult {
-            model: FitModel::Hill,
-            parameters: vec![a, k, n],
-            parameter_names: vec!["max_response".to_string(), "k_half".to_string(), "hill_coeff".to_string()],
-            r_squared,
-            fitted_points,
-            equation_string: format!("y = ({:.4} * x^{:.2}) / ({:.4}^{:.2} + x^{:.2})", a, n, k, n, n),
-        })
+// Cells of one line of pasted text: tab-separated (as spreadsheets copy), else comma-separated
+/// Function: explain its purpose and key arguments
+fn editor_window_id() -> egui::Id {
+    egui::Id::new("enhanced_data_editor")
+}
+
+// The one text field a cell is edited in, so the keyboard handling can tell it from others
+/// Function: explain its purpose and key arguments
+fn cell_edit_id() -> egui::Id {
+    egui::Id::new("data_editor_cell_edit")
+}
+
+// Text table to read into the Data Editor grid
+/// Function: explain its purpose and key arguments
+fn pick_grid_file() -> Option<PathBuf> {
+    rfd::FileDialog::new().add_filter("Text table", TEXT_TABLE_EXTENSIONS).pick_file()
+}
+
+/// Function: explain its purpose and key arguments
+pub fn split_pasted_line(line: &str) -> Vec<&str> {
+    if line.contains('\t') {
+        line.split('\t').collect()
+    } else {
+        line.split(',').collect()
     }
-}
\ No newline at end of file
+}
+
+// Solve the square system a * x = b by Gaussian elimination with partial pivoting
+/// Function: explain its purpose and key arguments
+pub fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+// Variable declaration
+    let n = b.len();
+    for col in 0..n {
+// Variable declaration
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-300 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+// Variable declaration
+        let pivot_row = a[col].clone();
+        for row in (col + 1)..n {
+// Variable declaration
+            let factor = a[row][col] / pivot_row[col];
+            for (k, value) in pivot_row.iter().enumerate().skip(col) {
+                a[row][k] -= factor * value;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+// Variable declaration
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+// Variable declaration
+        let sum: f64 = ((row + 1)..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    x.iter().all(|v| v.is_finite()).then_some(x)
+}
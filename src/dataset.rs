@@ -1,12 +1,434 @@
 /* dataset definitions extracted from old_main.rs */
 
+// Import external modules or crates needed in dataset.rs
+use serde::{Deserialize, Serialize};
+// Import external modules or crates needed in dataset.rs
+use egui_plot::{LineStyle, MarkerShape};
+// Import external modules or crates needed in dataset.rs
+use std::sync::atomic::{AtomicU64, Ordering};
+// Import external modules or crates needed in dataset.rs
+use std::borrow::{Borrow, Cow};
+// Import external modules or crates needed in dataset.rs
+use std::hash::{DefaultHasher, Hash, Hasher};
+// Import external modules or crates needed in dataset.rs
+use std::path::{Path, PathBuf};
+// Import external modules or crates needed in dataset.rs
+use crate::io::TextImportSettings;
+// Import external modules or crates needed in dataset.rs
+use crate::palette::Colormap;
+// Import external modules or crates needed in dataset.rs
+use crate::points::PointStore;
+
+// Default stroke width for dataset lines, in screen points
+pub const DEFAULT_LINE_WIDTH: f32 = 1.5;
+
+// Default marker radius, in screen points
+pub const DEFAULT_MARKER_SIZE: f32 = 3.0;
+
+// Source path of datasets read from data piped on standard input
+pub const STDIN_PATH: &str = "-";
+
+// Opacity of the area filled under a line, so lines and fills behind it stay visible
+pub const FILL_OPACITY: f64 = 0.35;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in dataset.rs module
+pub enum PlotType {
+    #[default]
+    Line,
+    Scatter,
+    LineAndMarkers,
+    Bars,
+    Box,    // Box and whiskers summarizing the Y values
+    Violin, // Density of the Y values, mirrored around the dataset's position
+}
+
+/// Implementation block defining methods for this type
+impl PlotType {
+/// Function: explain its purpose and key arguments
+    pub fn to_string(&self) -> &'static str {
+        match self {
+            PlotType::Line => "Line",
+            PlotType::Scatter => "Scatter",
+            PlotType::LineAndMarkers => "Line + Markers",
+            PlotType::Bars => "Bars",
+            PlotType::Box => "Box plot",
+            PlotType::Violin => "Violin plot",
+        }
+    }
+
+    // Whether consecutive points are connected by a line
+/// Function: explain its purpose and key arguments
+    pub fn draws_line(&self) -> bool {
+        matches!(self, PlotType::Line | PlotType::LineAndMarkers)
+    }
+
+    // Whether each point is drawn as a marker
+/// Function: explain its purpose and key arguments
+    pub fn draws_markers(&self) -> bool {
+        matches!(self, PlotType::Scatter | PlotType::LineAndMarkers)
+    }
+
+    // Whether each point is drawn as a bar from zero up to its Y value (histograms)
+/// Function: explain its purpose and key arguments
+    pub fn draws_bars(&self) -> bool {
+        matches!(self, PlotType::Bars)
+    }
+
+    // Whether the dataset is drawn as a summary of its Y values instead of point by point
+/// Function: explain its purpose and key arguments
+    pub fn draws_distribution(&self) -> bool {
+        matches!(self, PlotType::Box | PlotType::Violin)
+    }
+}
+
+// Width of a box or violin in X units; distributions sit one unit apart
+pub const DISTRIBUTION_WIDTH: f64 = 0.6;
+
+// Upper edge of a shaded band as points and its lower edge as a baseline (see Dataset::band_edges)
+type BandEdges = (Vec<[f64; 2]>, Vec<Option<f64>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in dataset.rs module
+pub enum LinePattern {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+/// Implementation block defining methods for this type
+impl LinePattern {
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            LinePattern::Solid => "Solid",
+            LinePattern::Dashed => "Dashed",
+            LinePattern::Dotted => "Dotted",
+        }
+    }
+
+    // Equivalent egui_plot line style for the interactive plot
+/// Function: explain its purpose and key arguments
+    pub fn to_plot_style(self) -> LineStyle {
+        match self {
+            LinePattern::Solid => LineStyle::Solid,
+            LinePattern::Dashed => LineStyle::dashed_loose(),
+            LinePattern::Dotted => LineStyle::dotted_dense(),
+        }
+    }
+
+    // On/off run lengths in pixels for rasterizing a line of the given thickness
+/// Function: explain its purpose and key arguments
+    pub fn dash_lengths(self, thickness: u32) -> Option<(f64, f64)> {
+// Variable declaration
+        let unit = thickness.max(1) as f64;
+        match self {
+            LinePattern::Solid => None,
+            LinePattern::Dashed => Some((5.0 * unit, 3.0 * unit)),
+            LinePattern::Dotted => Some((unit, 2.0 * unit)),
+        }
+    }
+}
+
+// Shape drawn at each point of a dataset with markers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in dataset.rs module
+pub enum MarkerStyle {
+    #[default]
+    Circle,
+    Square,
+    Triangle,
+    Diamond,
+    Cross,
+}
+
+/// Implementation block defining methods for this type
+impl MarkerStyle {
+    pub const ALL: [MarkerStyle; 5] = [
+        MarkerStyle::Circle,
+        MarkerStyle::Square,
+        MarkerStyle::Triangle,
+        MarkerStyle::Diamond,
+        MarkerStyle::Cross,
+    ];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            MarkerStyle::Circle => "Circle",
+            MarkerStyle::Square => "Square",
+            MarkerStyle::Triangle => "Triangle",
+            MarkerStyle::Diamond => "Diamond",
+            MarkerStyle::Cross => "Cross",
+        }
+    }
+
+    // Equivalent egui_plot marker for the interactive plot
+/// Function: explain its purpose and key arguments
+    pub fn to_plot_shape(self) -> MarkerShape {
+        match self {
+            MarkerStyle::Circle => MarkerShape::Circle,
+            MarkerStyle::Square => MarkerShape::Square,
+            MarkerStyle::Triangle => MarkerShape::Up,
+            MarkerStyle::Diamond => MarkerShape::Diamond,
+            MarkerStyle::Cross => MarkerShape::Cross,
+        }
+    }
+
+    // Corners of the filled shape of the given radius around (0, 0), with Y pointing down
+    // as in the exports; None for the circle and the cross, which are not polygons
+/// Function: explain its purpose and key arguments
+    pub fn outline(self, radius: f64) -> Option<Vec<(f64, f64)>> {
+        match self {
+            MarkerStyle::Circle | MarkerStyle::Cross => None,
+            MarkerStyle::Square => Some(vec![(-radius, -radius), (radius, -radius), (radius, radius), (-radius, radius)]),
+            MarkerStyle::Triangle => Some(vec![(0.0, -radius), (radius, radius), (-radius, radius)]),
+            MarkerStyle::Diamond => Some(vec![(0.0, -radius), (radius, 0.0), (0.0, radius), (-radius, 0.0)]),
+        }
+    }
+}
+
+// Area shaded under a dataset's line
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in dataset.rs module
+pub enum FillMode {
+    #[default]
+    None,
+    ToZero,
+    ToDataset(String), // Down (or up) to the line of the dataset with this name in the same subplot
+    Stacked, // On top of the stacked datasets listed before it in the subplot
+}
+
+/// Implementation block defining methods for this type
+impl FillMode {
+/// Function: explain its purpose and key arguments
+    pub fn to_string(&self) -> &'static str {
+        match self {
+            FillMode::None => "No fill",
+            FillMode::ToZero => "Fill to zero",
+            FillMode::ToDataset(_) => "Fill to dataset",
+            FillMode::Stacked => "Stacked",
+        }
+    }
+
+    // Whether the filled area reaches down to Y = 0, which the axis range has to include
+/// Function: explain its purpose and key arguments
+    pub fn reaches_zero(&self) -> bool {
+        matches!(self, FillMode::ToZero | FillMode::Stacked)
+    }
+}
+
+// What a line colored by value takes its values from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Enum representing a set of related values in dataset.rs module
+pub enum ColorBy {
+    PointIndex,
+    Values, // The dataset's color_values (a third data column)
+}
+
+/// Implementation block defining methods for this type
+impl ColorBy {
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            ColorBy::PointIndex => "Point index",
+            ColorBy::Values => "Color column",
+        }
+    }
+}
+
+// Source of unique point revisions (see Dataset::revision)
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+/// Function: explain its purpose and key arguments
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+// Revision of points computed from other points (e.g. stacked sums): the same for the same
+// source revisions on every frame, so the display cache keeps them, and with the top bit
+// set so it is never one handed out by next_revision
+/// Function: explain its purpose and key arguments
+fn derived_revision(sources: &[u64]) -> u64 {
+// Variable declaration
+    let mut hasher = DefaultHasher::new();
+    sources.hash(&mut hasher);
+    hasher.finish() | 1 << 63
+}
+
+/// Function: explain its purpose and key arguments
+fn default_line_width() -> f32 {
+    DEFAULT_LINE_WIDTH
+}
+
+/// Function: explain its purpose and key arguments
+fn default_marker_size() -> f32 {
+    DEFAULT_MARKER_SIZE
+}
+
+/// Function: explain its purpose and key arguments
+fn default_visible() -> bool {
+    true
+}
+
+// Where a dataset was loaded from, so it can be read again when the file changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Data structure used in dataset.rs module
+pub struct DataSource {
+    pub path: PathBuf,
+    pub series: usize, // Index of the dataset among the series read from the file
+    #[serde(default)]
+    pub text_import: Option<TextImportSettings>, // Columns chosen in the import preview
+}
+
+/// Implementation block defining methods for this type
+impl DataSource {
+    // Piped data has no file to watch; it updates as lines arrive instead
+/// Function: explain its purpose and key arguments
+    pub fn is_stdin(&self) -> bool {
+        self.path == Path::new(STDIN_PATH)
+    }
+}
+
+// Linear change of one coordinate, value * factor + offset (normalization, unit
+// conversion). Axis 0 is X and 1 is Y.
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in dataset.rs module
+pub struct AxisMap {
+    pub axis: usize,
+    pub factor: f64,
+    pub offset: f64,
+}
+
+/// Implementation block defining methods for this type
+impl AxisMap {
+/// Function: explain its purpose and key arguments
+    pub fn map(&self, value: f64) -> f64 {
+        value * self.factor + self.offset
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn apply(&self, points: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        points
+            .iter()
+            .map(|&p| {
+// Variable declaration
+                let mut mapped = p;
+                mapped[self.axis] = self.map(p[self.axis]);
+                mapped
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone)]
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 /// Data structure used in dataset.rs module
 pub struct Dataset {
     pub name: String,
-    pub points: Vec<[f64; 2]>,
+    pub points: PointStore,
     pub color: [u8; 3], // RGB color for this dataset
+    #[serde(default)]
+    pub plot_type: PlotType,
+    #[serde(default)]
+    pub x_err: Option<Vec<f64>>, // Per-point X uncertainty (same length as points)
+    #[serde(default)]
+    pub y_err: Option<Vec<f64>>, // Per-point Y uncertainty (same length as points)
+    #[serde(default = "default_line_width")]
+    pub line_width: f32,
+    #[serde(default)]
+    pub line_style: LinePattern,
+    #[serde(default)]
+    pub marker: MarkerStyle,
+    #[serde(default = "default_marker_size")]
+    pub marker_size: f32, // Marker radius, in screen points
+    #[serde(default)]
+    pub source: Option<DataSource>, // None for generated or edited-in datasets
+    #[serde(default)]
+    pub follow: bool, // Reload whenever the source file changes
+    #[serde(default)]
+    pub color_values: Option<Vec<f64>>, // Per-point values to color by (same length as points)
+    #[serde(default)]
+    pub color_by: Option<ColorBy>, // Color the line and markers along `colormap` instead of `color`
+    #[serde(default)]
+    pub colormap: Colormap,
+    #[serde(default)]
+    pub fill: FillMode,
+    #[serde(default)]
+    pub secondary_y: bool, // Plotted against the subplot's right-hand Y axis
+    #[serde(default)]
+    pub band: Option<Vec<f64>>, // Half-width of a shaded band around each point (e.g. ±σ of replicas)
+    #[serde(default = "default_visible")]
+    pub visible: bool, // Hidden datasets stay in the subplot but are not drawn or exported
+    #[serde(default)]
+    pub connect_gaps: bool, // Join the line across missing Y values instead of breaking it there
+    #[serde(skip, default = "next_revision")]
+    revision: u64, // Changes whenever `points` is replaced; clones share it
+}
+
+// Equal when every field is. Per-point values compare bit for bit like the points do, so
+// a dataset with blank (NaN) values equals its own clone.
+/// Implementation block defining methods for this type
+impl PartialEq for Dataset {
+/// Function: explain its purpose and key arguments
+    fn eq(&self, other: &Self) -> bool {
+        // Taken apart field by field so a new field cannot be left out here
+// Variable declaration
+        let Dataset {
+            name,
+            points,
+            color,
+            plot_type,
+            x_err,
+            y_err,
+            line_width,
+            line_style,
+            marker,
+            marker_size,
+            source,
+            follow,
+            color_values,
+            color_by,
+            colormap,
+            fill,
+            secondary_y,
+            band,
+            visible,
+            connect_gaps,
+            revision,
+        } = self;
+        *name == other.name
+            && *points == other.points
+            && *color == other.color
+            && *plot_type == other.plot_type
+            && same_values(x_err, &other.x_err)
+            && same_values(y_err, &other.y_err)
+            && *line_width == other.line_width
+            && *line_style == other.line_style
+            && *marker == other.marker
+            && *marker_size == other.marker_size
+            && *source == other.source
+            && *follow == other.follow
+            && same_values(color_values, &other.color_values)
+            && *color_by == other.color_by
+            && *colormap == other.colormap
+            && *fill == other.fill
+            && *secondary_y == other.secondary_y
+            && same_values(band, &other.band)
+            && *visible == other.visible
+            && *connect_gaps == other.connect_gaps
+            && *revision == other.revision
+    }
+}
+
+// Whether two optional lists of per-point values hold the same values, NaN included
+/// Function: explain its purpose and key arguments
+fn same_values(a: &Option<Vec<f64>>, b: &Option<Vec<f64>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits()),
+        _ => a.is_none() && b.is_none(),
+    }
 }
 
 /// Implementation block defining methods for this type
@@ -15,20 +437,388 @@ impl Dataset {
     pub fn new(name: String, points: Vec<[f64; 2]>, color: [u8; 3]) -> Self {
         Self {
             name,
-            points,
+            points: points.into(),
             color,
+            plot_type: PlotType::default(),
+            x_err: None,
+            y_err: None,
+            line_width: DEFAULT_LINE_WIDTH,
+            line_style: LinePattern::default(),
+            marker: MarkerStyle::default(),
+            marker_size: DEFAULT_MARKER_SIZE,
+            source: None,
+            follow: false,
+            color_values: None,
+            color_by: None,
+            colormap: Colormap::default(),
+            fill: FillMode::default(),
+            secondary_y: false,
+            band: None,
+            visible: true,
+            connect_gaps: false,
+            revision: next_revision(),
+        }
+    }
+
+    // Identifier of the current points, used to cache derived display data
+/// Function: explain its purpose and key arguments
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Replace the points; use this instead of assigning `points` so caches notice
+/// Function: explain its purpose and key arguments
+    pub fn set_points(&mut self, points: Vec<[f64; 2]>) {
+        self.points = PointStore::from_pairs(points, self.points.single_precision());
+        self.revision = next_revision();
+    }
+
+    // Replace the points with ones computed from other data; `sources` are the revisions
+    // (and settings) they were computed from, which give the new revision (see derived_revision)
+/// Function: explain its purpose and key arguments
+    pub fn set_derived_points(&mut self, points: Vec<[f64; 2]>, sources: &[u64]) {
+        self.points = PointStore::from_pairs(points, self.points.single_precision());
+        self.revision = derived_revision(sources);
+    }
+
+    // Keep the points as 32-bit floats (half the memory) or go back to 64-bit ones
+/// Function: explain its purpose and key arguments
+    pub fn set_single_precision(&mut self, single_precision: bool) {
+        if single_precision != self.points.single_precision() {
+            self.points.set_single_precision(single_precision);
+            self.revision = next_revision();
+        }
+    }
+
+    // Add points at the end (e.g. lines appended to a followed file) with their uncertainties
+/// Function: explain its purpose and key arguments
+    pub fn extend_points(
+        &mut self,
+        points: &[[f64; 2]],
+        x_err: Option<&[f64]>,
+        y_err: Option<&[f64]>,
+        color_values: Option<&[f64]>,
+    ) {
+        if points.is_empty() {
+            return;
+        }
+        self.points.extend(points);
+        if let (Some(errors), Some(new)) = (self.x_err.as_mut(), x_err) {
+            errors.extend_from_slice(new);
+        }
+        if let (Some(errors), Some(new)) = (self.y_err.as_mut(), y_err) {
+            errors.extend_from_slice(new);
+        }
+        if let (Some(values), Some(new)) = (self.color_values.as_mut(), color_values) {
+            values.extend_from_slice(new);
+        }
+        self.discard_mismatched_errors();
+        self.revision = next_revision();
+    }
+
+    // Move point `i`, keeping its uncertainties and color value
+/// Function: explain its purpose and key arguments
+    pub fn set_point(&mut self, i: usize, point: [f64; 2]) {
+        if i < self.points.len() {
+            self.points.set(i, point);
+            self.revision = next_revision();
+        }
+    }
+
+    // Insert a point where its X belongs, so lines join hand-entered points from left to
+    // right. Returns its index. The new point gets no uncertainty or band and no color value.
+/// Function: explain its purpose and key arguments
+    pub fn add_point(&mut self, point: [f64; 2]) -> usize {
+// Variable declaration
+        let mut points = self.points.to_vec();
+// Variable declaration
+        let index = points.partition_point(|p| p[0] <= point[0]);
+        points.insert(index, point);
+        for (values, blank) in [(&mut self.x_err, 0.0), (&mut self.y_err, 0.0), (&mut self.band, 0.0), (&mut self.color_values, f64::NAN)] {
+            if let Some(values) = values.as_mut().filter(|values| values.len() == points.len() - 1) {
+                values.insert(index, blank);
+            }
+        }
+        self.set_points(points);
+        self.discard_mismatched_errors();
+        index
+    }
+
+    // Remove point `i` together with its uncertainties and color value
+/// Function: explain its purpose and key arguments
+    pub fn remove_point(&mut self, i: usize) {
+        if i >= self.points.len() {
+            return;
+        }
+        self.points.remove(i);
+        for values in [&mut self.x_err, &mut self.y_err, &mut self.color_values, &mut self.band].into_iter().flatten() {
+            if i < values.len() {
+                values.remove(i);
+            }
+        }
+        self.discard_mismatched_errors();
+        self.revision = next_revision();
+    }
+
+    // Whether any point is missing a value (a blank or NaN cell in the source), which
+    // breaks the line there
+/// Function: explain its purpose and key arguments
+    pub fn has_gaps(&self) -> bool {
+        self.points.iter().any(|p| !p[0].is_finite() || !p[1].is_finite())
+    }
+
+    // Copy without the points that are missing a value, so the line joins across them
+/// Function: explain its purpose and key arguments
+    pub fn without_gaps(&self) -> Dataset {
+// Variable declaration
+        let keep: Vec<bool> = self.points.iter().map(|p| p[0].is_finite() && p[1].is_finite()).collect();
+// Variable declaration
+        let mut dataset = self.clone();
+        for values in [&mut dataset.x_err, &mut dataset.y_err, &mut dataset.color_values, &mut dataset.band]
+            .into_iter()
+            .flatten()
+        {
+            if values.len() == keep.len() {
+                *values = values.iter().zip(&keep).filter(|(_, keep)| **keep).map(|(v, _)| *v).collect();
+            }
+        }
+        dataset.points.retain(|p| p[0].is_finite() && p[1].is_finite());
+        dataset.discard_mismatched_errors();
+        // Stable across frames so the display cache keeps the copy, and never equal to a
+        // revision handed out by next_revision
+        dataset.revision = !self.revision;
+        dataset
+    }
+
+    // Change one coordinate of every point; uncertainties (and the band, for Y) along
+    // that axis scale with it
+/// Function: explain its purpose and key arguments
+    pub fn apply_axis_map(&mut self, map: &AxisMap) {
+        self.points = PointStore::from_pairs(map.apply(&self.points.to_vec()), self.points.single_precision());
+// Variable declaration
+        let spreads = if map.axis == 0 {
+            vec![&mut self.x_err]
+        } else {
+            vec![&mut self.y_err, &mut self.band]
+        };
+        for values in spreads.into_iter().flatten() {
+            for value in values.iter_mut() {
+                *value *= map.factor.abs();
+            }
         }
+        self.revision = next_revision();
     }
-    
+
+    // Whether this dataset carries any per-point uncertainties
+/// Function: explain its purpose and key arguments
+    pub fn has_error_bars(&self) -> bool {
+        self.x_err.is_some() || self.y_err.is_some()
+    }
+
+    // Drop uncertainties and color values that no longer line up with the points
+    // (e.g. after editing)
+/// Function: explain its purpose and key arguments
+    pub fn discard_mismatched_errors(&mut self) {
+        if self.x_err.as_ref().is_some_and(|e| e.len() != self.points.len()) {
+            self.x_err = None;
+        }
+        if self.y_err.as_ref().is_some_and(|e| e.len() != self.points.len()) {
+            self.y_err = None;
+        }
+        if self.band.as_ref().is_some_and(|b| b.len() != self.points.len()) {
+            self.band = None;
+        }
+        if self.color_values.as_ref().is_some_and(|v| v.len() != self.points.len()) {
+            self.color_values = None;
+            if self.color_by == Some(ColorBy::Values) {
+                self.color_by = None;
+            }
+        }
+    }
+
+    // Upper edge of the shaded band as points, and its lower edge as a baseline (see
+    // fill_baseline); None without a band
+/// Function: explain its purpose and key arguments
+    pub fn band_edges(&self) -> Option<BandEdges> {
+// Variable declaration
+        let band = self.band.as_ref().filter(|b| b.len() == self.points.len())?;
+        Some(
+            self.points
+                .iter()
+                .zip(band)
+                .map(|(p, w)| ([p[0], p[1] + w.abs()], Some(p[1] - w.abs())))
+                .unzip(),
+        )
+    }
+
+    // Value point `i` is colored by, when the dataset is colored by value
+/// Function: explain its purpose and key arguments
+    pub fn color_value(&self, i: usize) -> Option<f64> {
+        match self.color_by? {
+            ColorBy::PointIndex => Some(i as f64),
+            ColorBy::Values => self.color_values.as_ref()?.get(i).copied().filter(|v| v.is_finite()),
+        }
+    }
+
+    // Lowest and highest coloring value: the ends of the colorbar
+/// Function: explain its purpose and key arguments
+    pub fn color_range(&self) -> Option<(f64, f64)> {
+        (0..self.points.len())
+            .filter_map(|i| self.color_value(i))
+            .fold(None, |range, v| match range {
+                None => Some((v, v)),
+                Some((low, high)) => Some((f64::min(low, v), f64::max(high, v))),
+            })
+    }
+
+    // Color of point `i` on the colormap, stretched over `range`; the dataset color
+    // for points without a value
+/// Function: explain its purpose and key arguments
+    pub fn point_color(&self, i: usize, (low, high): (f64, f64)) -> [u8; 3] {
+        match self.color_value(i) {
+            Some(v) if high > low => self.colormap.sample((v - low) / (high - low)),
+            Some(_) => self.colormap.sample(0.5),
+            None => self.color,
+        }
+    }
+
     // Get display name for the dataset (used in legend and UI)
 /// Function: explain its purpose and key arguments
     pub fn display_name(&self) -> &str {
         &self.name
     }
-    
+
     // Set a new display name
 /// Function: explain its purpose and key arguments
     pub fn set_name(&mut self, new_name: String) {
         self.name = new_name;
     }
-}
\ No newline at end of file
+}
+
+// Y of the line through `points` (sorted by X) at `x`; None outside its X range
+/// Function: explain its purpose and key arguments
+pub fn interpolate_y(points: &[[f64; 2]], x: f64) -> Option<f64> {
+    interpolate_between(points.len(), |i| points[i], points.partition_point(|p| p[0] < x), x)
+}
+
+// interpolate_y for the points of a dataset, without copying them out first
+/// Function: explain its purpose and key arguments
+pub fn interpolate_store_y(points: &PointStore, x: f64) -> Option<f64> {
+    interpolate_between(points.len(), |i| points.point(i), points.partition_x(x), x)
+}
+
+// Linear interpolation over `len` points ordered by X, read through `point`; `below` is
+// the number of points with X less than `x`
+/// Function: explain its purpose and key arguments
+fn interpolate_between(len: usize, point: impl Fn(usize) -> [f64; 2], below: usize, x: f64) -> Option<f64> {
+    if len == 0 {
+        return None;
+    }
+// Variable declaration
+    let (first, last) = (point(0), point(len - 1));
+    if !(first[0]..=last[0]).contains(&x) {
+        return None;
+    }
+    if len == 1 {
+        return Some(first[1]);
+    }
+// Variable declaration
+    let i = below.clamp(1, len - 1);
+// Variable declaration
+    let (a, b) = (point(i - 1), point(i));
+    if b[0] == a[0] {
+        return Some(b[1]);
+    }
+    Some(a[1] + (b[1] - a[1]) * (x - a[0]) / (b[0] - a[0]))
+}
+
+// The datasets that are drawn, leaving out hidden ones and, where gaps are bridged, the
+// points missing a value. Only the bridged datasets are copied; the others are borrowed.
+/// Function: explain its purpose and key arguments
+pub fn visible_datasets(datasets: &[Dataset]) -> Vec<Cow<'_, Dataset>> {
+    datasets
+        .iter()
+        .filter(|ds| ds.visible)
+        .map(|ds| if ds.connect_gaps && ds.has_gaps() { Cow::Owned(ds.without_gaps()) } else { Cow::Borrowed(ds) })
+        .collect()
+}
+
+// Raise each stacked dataset by the stacked ones before it (interpolated at its own X
+// values), as they are drawn. Only the raised datasets are copied.
+/// Function: explain its purpose and key arguments
+pub fn stacked_datasets(datasets: &mut [Cow<'_, Dataset>]) {
+// Variable declaration
+    let mut below: Option<usize> = None;
+    for i in 0..datasets.len() {
+        if datasets[i].fill != FillMode::Stacked {
+            continue;
+        }
+        if let Some(below) = below {
+// Variable declaration
+            let (lower, upper) = datasets.split_at_mut(i);
+// Variable declaration
+            let raised = upper[0]
+                .points
+                .iter()
+                .map(|p| [p[0], p[1] + interpolate_store_y(&lower[below].points, p[0]).unwrap_or(0.0)])
+                .collect();
+// Variable declaration
+            let sources = [upper[0].revision, lower[below].revision];
+            upper[0].to_mut().set_derived_points(raised, &sources);
+        }
+        below = Some(i);
+    }
+}
+
+// Lower edge of the area filled under dataset `index`, one Y per point (None where there
+// is nothing to fill to). `datasets` must already be stacked (see stacked_datasets).
+/// Function: explain its purpose and key arguments
+pub fn fill_baseline(datasets: &[Cow<'_, Dataset>], index: usize) -> Option<Vec<Option<f64>>> {
+// Variable declaration
+    let dataset = &datasets[index];
+    if dataset.plot_type.draws_distribution() {
+        return None;
+    }
+// Variable declaration
+    let below = match &dataset.fill {
+        FillMode::None => return None,
+        FillMode::ToZero => None,
+        FillMode::ToDataset(name) => Some(
+            datasets
+                .iter()
+                .enumerate()
+                .find(|(i, other)| *i != index && other.name == *name)?
+                .1,
+        ),
+        FillMode::Stacked => datasets[..index].iter().rev().find(|other| other.fill == FillMode::Stacked),
+    }
+    .map(|below| &below.points);
+    Some(
+        dataset
+            .points
+            .iter()
+            .map(|p| match below {
+                Some(below) if dataset.fill == FillMode::Stacked => Some(interpolate_store_y(below, p[0]).unwrap_or(0.0)),
+                Some(below) => interpolate_store_y(below, p[0]),
+                None => Some(0.0),
+            })
+            .collect(),
+    )
+}
+
+// X position of each box or violin in a subplot: 1, 2, 3, ... in the order the datasets
+// are listed, None for datasets drawn point by point
+/// Function: explain its purpose and key arguments
+pub fn distribution_positions(datasets: &[impl Borrow<Dataset>]) -> Vec<Option<f64>> {
+// Variable declaration
+    let mut next = 0.0;
+    datasets
+        .iter()
+        .map(|ds: &_| {
+            ds.borrow().plot_type.draws_distribution().then(|| {
+                next += 1.0;
+                next
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+// Import external modules or crates needed in diagnostics.rs
+use eframe::egui;
+// Import external modules or crates needed in diagnostics.rs
+use std::collections::VecDeque;
+
+// Frames within this many seconds of the newest one count towards the rate and averages
+const WINDOW_SECONDS: f64 = 1.0;
+
+// Overlay with the frame rate, the CPU time spent building frames and what asked for the
+// latest repaint. The app only draws when something changed (input, new live data), so
+// with static data and the mouse outside the window the frame counter stands still.
+#[derive(Default)]
+/// Data structure used in diagnostics.rs module
+pub struct FrameDiagnostics {
+    pub visible: bool,
+    frames: VecDeque<(f64, f32)>, // (time the frame started, CPU seconds of the frame before it)
+    frame_count: u64,
+}
+
+/// Implementation block defining methods for this type
+impl FrameDiagnostics {
+    // Note a frame starting at `time` (egui's input time, in seconds). `cpu_usage` comes from
+    // eframe and covers the previous frame, which it only knows once that one is done.
+/// Function: explain its purpose and key arguments
+    pub fn record(&mut self, time: f64, cpu_usage: Option<f32>) {
+        self.frame_count += 1;
+        self.frames.push_back((time, cpu_usage.unwrap_or(0.0)));
+        while self.frames.front().is_some_and(|(start, _)| time - start > WINDOW_SECONDS) {
+            self.frames.pop_front();
+        }
+    }
+
+    // Draw the overlay in the bottom-right corner of the window, above everything else
+/// Function: explain its purpose and key arguments
+    pub fn show(&self, ctx: &egui::Context) {
+        if !self.visible {
+            return;
+        }
+// Variable declaration
+        let cpu_ms: Vec<f32> = self.frames.iter().skip(1).map(|(_, cpu)| cpu * 1000.0).collect();
+// Variable declaration
+        let average_ms = if cpu_ms.is_empty() { 0.0 } else { cpu_ms.iter().sum::<f32>() / cpu_ms.len() as f32 };
+// Variable declaration
+        let max_ms = cpu_ms.iter().copied().fold(0.0, f32::max);
+        // Repaints asked for through request_repaint; input alone leaves no cause behind
+// Variable declaration
+        let mut causes: Vec<String> = ctx.repaint_causes().iter().map(|cause| format!("{:?}", cause)).collect();
+        causes.dedup();
+// Variable declaration
+        let cause = if causes.is_empty() { "input".to_string() } else { causes.join(", ") };
+
+        egui::Area::new(egui::Id::new("frame_diagnostics"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -32.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.small(format!("Frames in the last second: {}", self.frames.len()));
+                    ui.small(format!("CPU per frame: {:.1} ms average, {:.1} ms max", average_ms, max_ms));
+                    ui.small(format!("Frames drawn: {}", self.frame_count));
+                    ui.small(format!("Last repaint: {}", cause));
+                });
+            });
+    }
+}
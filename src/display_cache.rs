@@ -0,0 +1,73 @@
+// Import external modules or crates needed in display_cache.rs
+use crate::app::AxisScale;
+// Import external modules or crates needed in display_cache.rs
+use crate::dataset::{Dataset, PlotType};
+// Import external modules or crates needed in display_cache.rs
+use crate::utils::{distribution, downsample_min_max, Distribution};
+// Import external modules or crates needed in display_cache.rs
+use std::collections::HashMap;
+
+// Points of datasets as [x, y] pairs for drawing, all of them or a downsampled copy of
+// large datasets, keyed by dataset revision and point budget so they are only rebuilt
+// when the points change (PointStore keeps X and Y in separate columns, so it cannot
+// lend out pairs). Box and violin summaries are kept the same way, keyed by revision, Y
+// scale and whether a violin needs its density. Entries not used during a frame are
+// dropped at the start of the next one.
+#[derive(Default)]
+/// Data structure used in display_cache.rs module
+pub struct DisplayCache {
+    current: HashMap<(u64, Option<usize>), Vec<[f64; 2]>>,
+    previous: HashMap<(u64, Option<usize>), Vec<[f64; 2]>>,
+    distributions: HashMap<(u64, AxisScale, bool), Option<Distribution>>,
+    previous_distributions: HashMap<(u64, AxisScale, bool), Option<Distribution>>,
+}
+
+/// Implementation block defining methods for this type
+impl DisplayCache {
+    // Call once per frame before any display_points lookups
+/// Function: explain its purpose and key arguments
+    pub fn begin_frame(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        self.previous_distributions = std::mem::take(&mut self.distributions);
+    }
+
+    // Points to draw for `dataset`: all of its points when no budget applies or it fits
+    // the budget, otherwise a min/max decimation
+/// Function: explain its purpose and key arguments
+    pub fn display_points(&mut self, dataset: &Dataset, max_points: Option<usize>) -> &[[f64; 2]] {
+        self.points(dataset, max_points.filter(|&max_points| dataset.points.len() > max_points))
+    }
+
+    // All points of `dataset`
+/// Function: explain its purpose and key arguments
+    pub fn all_points(&mut self, dataset: &Dataset) -> &[[f64; 2]] {
+        self.points(dataset, None)
+    }
+
+/// Function: explain its purpose and key arguments
+    fn points(&mut self, dataset: &Dataset, max_points: Option<usize>) -> &[[f64; 2]] {
+// Variable declaration
+        let key = (dataset.revision(), max_points);
+// Variable declaration
+        let previous = &mut self.previous;
+        self.current.entry(key).or_insert_with(|| {
+            previous.remove(&key).unwrap_or_else(|| match max_points {
+                Some(max_points) => downsample_min_max(&dataset.points, max_points),
+                None => dataset.points.to_vec(),
+            })
+        })
+    }
+
+    // Box summary and violin density of `dataset` on `y_scale` (see distribution)
+/// Function: explain its purpose and key arguments
+    pub fn distribution(&mut self, dataset: &Dataset, y_scale: AxisScale) -> Option<&Distribution> {
+// Variable declaration
+        let key = (dataset.revision(), y_scale, dataset.plot_type == PlotType::Violin);
+// Variable declaration
+        let previous = &mut self.previous_distributions;
+        self.distributions
+            .entry(key)
+            .or_insert_with(|| previous.remove(&key).unwrap_or_else(|| distribution(dataset, y_scale)))
+            .as_ref()
+    }
+}
@@ -0,0 +1,64 @@
+// Import external modules or crates needed in export_job.rs
+use crate::app::{ExportSettings, FontSize, Subplot, SubplotLayout};
+// Import external modules or crates needed in export_job.rs
+use crate::utils::export_subplots_to_path;
+// Import external modules or crates needed in export_job.rs
+use std::path::PathBuf;
+// Import external modules or crates needed in export_job.rs
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+// Import external modules or crates needed in export_job.rs
+use std::time::Instant;
+
+// A figure being rendered and written on a worker thread. The thread works on a copy of the
+// subplots, so editing the plot while a large export runs does not change what is written.
+/// Data structure used in export_job.rs module
+pub struct ExportJob {
+    pub path: PathBuf,
+    started: Instant,
+    result: Receiver<Result<(), String>>,
+}
+
+/// Implementation block defining methods for this type
+impl ExportJob {
+/// Function: explain its purpose and key arguments
+    pub fn spawn(
+        path: PathBuf,
+        subplots: Vec<Subplot>,
+        layout: SubplotLayout,
+        dark_mode: bool,
+        font_size: FontSize,
+        settings: ExportSettings,
+    ) -> Self {
+// Variable declaration
+        let (sender, result) = channel();
+// Variable declaration
+        let thread_path = path.clone();
+        std::thread::spawn(move || {
+// Variable declaration
+            let outcome = export_subplots_to_path(&thread_path, &subplots, &layout, dark_mode, &font_size, &settings);
+            // Nobody is left to receive the result if the window was closed meanwhile
+            let _ = sender.send(outcome.map_err(|e| e.to_string()));
+        });
+        Self {
+            path,
+            started: Instant::now(),
+            result,
+        }
+    }
+
+    // The outcome once the file has been written, None while the export is still running
+/// Function: explain its purpose and key arguments
+    pub fn poll(&self) -> Option<Result<(), String>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err("the export stopped unexpectedly".to_string())),
+        }
+    }
+
+    // Seconds since the export started, shown next to its spinner
+/// Function: explain its purpose and key arguments
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+}
@@ -0,0 +1,351 @@
+// Small arithmetic expression parser used for user-defined fit models.
+// Grammar: + - * / ^ (right associative), unary minus, parentheses, numbers
+// (including 1e-3 notation), the variable `x`, the constants `pi` and `e`, and the
+// functions exp, ln, log10, sqrt, abs, sin, cos, tan. Any other name is a free
+// parameter, numbered in order of first appearance.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in expression.rs module
+pub enum Function {
+    Exp,
+    Ln,
+    Log10,
+    Sqrt,
+    Abs,
+    Sin,
+    Cos,
+    Tan,
+}
+
+/// Implementation block defining methods for this type
+impl Function {
+/// Function: explain its purpose and key arguments
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "exp" => Some(Function::Exp),
+            "ln" | "log" => Some(Function::Ln),
+            "log10" => Some(Function::Log10),
+            "sqrt" => Some(Function::Sqrt),
+            "abs" => Some(Function::Abs),
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "tan" => Some(Function::Tan),
+            _ => None,
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            Function::Exp => value.exp(),
+            Function::Ln => value.ln(),
+            Function::Log10 => value.log10(),
+            Function::Sqrt => value.sqrt(),
+            Function::Abs => value.abs(),
+            Function::Sin => value.sin(),
+            Function::Cos => value.cos(),
+            Function::Tan => value.tan(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Enum representing a set of related values in expression.rs module
+pub enum Expr {
+    Number(f64),
+    X,
+    Param(usize), // Index into Expression::parameters
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+/// Implementation block defining methods for this type
+impl Expr {
+/// Function: explain its purpose and key arguments
+    fn evaluate(&self, x: f64, params: &[f64]) -> f64 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::X => x,
+            Expr::Param(index) => params.get(*index).copied().unwrap_or(f64::NAN),
+            Expr::Neg(inner) => -inner.evaluate(x, params),
+            Expr::Add(a, b) => a.evaluate(x, params) + b.evaluate(x, params),
+            Expr::Sub(a, b) => a.evaluate(x, params) - b.evaluate(x, params),
+            Expr::Mul(a, b) => a.evaluate(x, params) * b.evaluate(x, params),
+            Expr::Div(a, b) => a.evaluate(x, params) / b.evaluate(x, params),
+            Expr::Pow(a, b) => a.evaluate(x, params).powf(b.evaluate(x, params)),
+            Expr::Call(function, inner) => function.apply(inner.evaluate(x, params)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in expression.rs module
+pub struct Expression {
+    pub text: String,
+    pub parameters: Vec<String>, // Free parameter names in order of first appearance
+    root: Expr,
+}
+
+/// Implementation block defining methods for this type
+impl Expression {
+/// Function: explain its purpose and key arguments
+    pub fn parse(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+// Variable declaration
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            parameters: Vec::new(),
+        };
+        if parser.tokens.is_empty() {
+            return Err("Expression is empty".into());
+        }
+// Variable declaration
+        let root = parser.parse_sum()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("Unexpected {} after end of expression", token.describe()).into());
+        }
+        Ok(Self {
+            text: text.trim().to_string(),
+            parameters: parser.parameters,
+            root,
+        })
+    }
+
+    // Value at `x` with parameter values given in the order of `parameters`
+/// Function: explain its purpose and key arguments
+    pub fn evaluate(&self, x: f64, params: &[f64]) -> f64 {
+        self.root.evaluate(x, params)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Enum representing a set of related values in expression.rs module
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+/// Implementation block defining methods for this type
+impl Token {
+/// Function: explain its purpose and key arguments
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(value) => format!("number {}", value),
+            Token::Name(name) => format!("'{}'", name),
+            Token::Operator(op) => format!("'{}'", op),
+            Token::Open => "'('".to_string(),
+            Token::Close => "')'".to_string(),
+        }
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn tokenize(text: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let chars: Vec<char> = text.chars().collect();
+// Variable declaration
+    let mut tokens = Vec::new();
+// Variable declaration
+    let mut i = 0;
+    while i < chars.len() {
+// Variable declaration
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+// Variable declaration
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent part, e.g. 1.5e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+// Variable declaration
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    while j < chars.len() && chars[j].is_ascii_digit() {
+                        j += 1;
+                    }
+                    i = j;
+                }
+            }
+// Variable declaration
+            let literal: String = chars[start..i].iter().collect();
+// Variable declaration
+            let value = literal
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid number '{}'", literal))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+// Variable declaration
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if "+-*/^".contains(c) {
+            tokens.push(Token::Operator(c));
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::Open);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::Close);
+            i += 1;
+        } else {
+            return Err(format!("Unexpected character '{}'", c).into());
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent parser; each level handles one precedence tier
+/// Data structure used in expression.rs module
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    parameters: Vec<String>,
+}
+
+/// Implementation block defining methods for this type
+impl Parser {
+/// Function: explain its purpose and key arguments
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+/// Function: explain its purpose and key arguments
+    fn next(&mut self) -> Option<Token> {
+// Variable declaration
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    // sum := product (('+' | '-') product)*
+/// Function: explain its purpose and key arguments
+    fn parse_sum(&mut self) -> Result<Expr, Box<dyn std::error::Error>> {
+// Variable declaration
+        let mut left = self.parse_product()?;
+        while let Some(Token::Operator(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.position += 1;
+// Variable declaration
+            let right = self.parse_product()?;
+            left = if op == '+' {
+                Expr::Add(Box::new(left), Box::new(right))
+            } else {
+                Expr::Sub(Box::new(left), Box::new(right))
+            };
+        }
+        Ok(left)
+    }
+
+    // product := unary (('*' | '/') unary)*
+/// Function: explain its purpose and key arguments
+    fn parse_product(&mut self) -> Result<Expr, Box<dyn std::error::Error>> {
+// Variable declaration
+        let mut left = self.parse_unary()?;
+        while let Some(Token::Operator(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.position += 1;
+// Variable declaration
+            let right = self.parse_unary()?;
+            left = if op == '*' {
+                Expr::Mul(Box::new(left), Box::new(right))
+            } else {
+                Expr::Div(Box::new(left), Box::new(right))
+            };
+        }
+        Ok(left)
+    }
+
+    // unary := '-' unary | '+' unary | power
+/// Function: explain its purpose and key arguments
+    fn parse_unary(&mut self) -> Result<Expr, Box<dyn std::error::Error>> {
+        match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.position += 1;
+                Ok(Expr::Neg(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::Operator('+')) => {
+                self.position += 1;
+                self.parse_unary()
+            }
+            _ => self.parse_power(),
+        }
+    }
+
+    // power := atom ('^' unary)?   (so 2^-x and a^b^c = a^(b^c) work)
+/// Function: explain its purpose and key arguments
+    fn parse_power(&mut self) -> Result<Expr, Box<dyn std::error::Error>> {
+// Variable declaration
+        let base = self.parse_atom()?;
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.position += 1;
+// Variable declaration
+            let exponent = self.parse_unary()?;
+            return Ok(Expr::Pow(Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    // atom := number | name | name '(' sum ')' | '(' sum ')'
+/// Function: explain its purpose and key arguments
+    fn parse_atom(&mut self) -> Result<Expr, Box<dyn std::error::Error>> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Open) => {
+// Variable declaration
+                let inner = self.parse_sum()?;
+                self.expect_close()?;
+                Ok(inner)
+            }
+            Some(Token::Name(name)) => {
+                if let Some(function) = Function::from_name(&name) {
+                    if self.next() != Some(Token::Open) {
+                        return Err(format!("Expected '(' after {}", name).into());
+                    }
+// Variable declaration
+                    let argument = self.parse_sum()?;
+                    self.expect_close()?;
+                    return Ok(Expr::Call(function, Box::new(argument)));
+                }
+                Ok(match name.as_str() {
+                    "x" => Expr::X,
+                    "pi" => Expr::Number(std::f64::consts::PI),
+                    "e" => Expr::Number(std::f64::consts::E),
+                    _ => Expr::Param(self.parameter_index(name)),
+                })
+            }
+            Some(token) => Err(format!("Unexpected {}", token.describe()).into()),
+            None => Err("Unexpected end of expression".into()),
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn expect_close(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        match self.next() {
+            Some(Token::Close) => Ok(()),
+            _ => Err("Missing ')'".into()),
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn parameter_index(&mut self, name: String) -> usize {
+        if let Some(index) = self.parameters.iter().position(|p| *p == name) {
+            return index;
+        }
+        self.parameters.push(name);
+        self.parameters.len() - 1
+    }
+}
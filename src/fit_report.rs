@@ -0,0 +1,259 @@
+// Import external modules or crates needed in fit_report.rs
+use crate::data_editor::{FitResult, GlobalFitResult};
+// Import external modules or crates needed in fit_report.rs
+use std::fmt::Write;
+// Import external modules or crates needed in fit_report.rs
+use std::path::{Path, PathBuf};
+
+// A fit report lists every curve fit of the session so the numbers can be archived next
+// to the figure: model, equation, number of points and the X range fitted, goodness of
+// fit and each parameter with its standard error where the fit provides one. Standard
+// errors are always scaled by the reduced χ² of the fit, weighted by error bars or not.
+
+// Layout of the report, chosen by the file extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in fit_report.rs module
+pub enum ReportFormat {
+    Text,
+    Csv,
+    Markdown,
+}
+
+/// Implementation block defining methods for this type
+impl ReportFormat {
+    // Markdown for .md, CSV for .csv and aligned plain text for anything else
+/// Function: explain its purpose and key arguments
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+            Some("csv") => ReportFormat::Csv,
+            Some("md" | "markdown") => ReportFormat::Markdown,
+            _ => ReportFormat::Text,
+        }
+    }
+}
+
+// One fit as it appears in the report
+/// Data structure used in fit_report.rs module
+struct ReportEntry<'a> {
+    label: String, // "Fit 2" or "Global fit 1"
+    fit: &'a FitResult,
+    shared: Option<&'a [bool]>, // Which parameters a global fit shares
+}
+
+/// Implementation block defining methods for this type
+impl ReportEntry<'_> {
+    // Number of points fitted and their X range, from the residuals (one per point)
+/// Function: explain its purpose and key arguments
+    fn points(&self) -> (usize, f64, f64) {
+// Variable declaration
+        let xs = self.fit.residuals.iter().map(|r| r[0]).filter(|x| x.is_finite());
+// Variable declaration
+        let (x_min, x_max) = xs.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), x| (low.min(x), high.max(x)));
+        (self.fit.residuals.len(), x_min, x_max)
+    }
+
+    // Standard error of each parameter, where the fit provides one: from the line statistics
+    // of linear fits, else from the diagonal of the covariance (none for fixed parameters)
+/// Function: explain its purpose and key arguments
+    fn errors(&self) -> Vec<Option<f64>> {
+        match (&self.fit.linear, &self.fit.covariance) {
+            (Some(linear), _) => vec![Some(linear.slope_error), Some(linear.intercept_error)],
+            (None, Some(covariance)) => {
+                covariance.iter().enumerate().map(|(j, row)| Some(row[j].sqrt()).filter(|error| *error > 0.0)).collect()
+            }
+            (None, None) => vec![None; self.fit.parameters.len()],
+        }
+    }
+
+    // Parameter name, marked when a global fit shares it between datasets
+/// Function: explain its purpose and key arguments
+    fn parameter_name(&self, j: usize) -> String {
+// Variable declaration
+        let name = self.fit.parameter_names.get(j).cloned().unwrap_or_else(|| format!("p{}", j));
+        if self.shared.is_some_and(|shared| shared.get(j) == Some(&true)) {
+            format!("{} (shared)", name)
+        } else {
+            name
+        }
+    }
+}
+
+// The single fits in order, then every dataset of each global fit
+/// Function: explain its purpose and key arguments
+fn entries<'a>(fits: &'a [FitResult], global_fits: &'a [GlobalFitResult]) -> Vec<ReportEntry<'a>> {
+// Variable declaration
+    let mut entries: Vec<ReportEntry> = fits
+        .iter()
+        .enumerate()
+        .map(|(i, fit)| ReportEntry { label: format!("Fit {}", i + 1), fit, shared: None })
+        .collect();
+    for (i, global) in global_fits.iter().enumerate() {
+        entries.extend(global.fits.iter().map(|fit| ReportEntry {
+            label: format!("Global fit {}", i + 1),
+            fit,
+            shared: Some(&global.shared),
+        }));
+    }
+    entries
+}
+
+// Value for a CSV cell; commas and quotes are quoted
+/// Function: explain its purpose and key arguments
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// Full precision for CSV, where the numbers are read back by other programs
+/// Function: explain its purpose and key arguments
+fn csv_number(value: Option<f64>) -> String {
+    value.map_or(String::new(), |value| value.to_string())
+}
+
+/// Function: explain its purpose and key arguments
+fn render_csv(entries: &[ReportEntry]) -> String {
+// Variable declaration
+    let mut out = String::from(
+        "fit,dataset,model,points,x_min,x_max,r_squared,chi_square,reduced_chi_square,parameter,value,standard_error\n",
+    );
+    for entry in entries {
+// Variable declaration
+        let (count, x_min, x_max) = entry.points();
+// Variable declaration
+        let (chi_square, reduced) = entry.fit.chi_square.unzip();
+        for (j, (value, error)) in entry.fit.parameters.iter().zip(entry.errors()).enumerate() {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                csv_field(&entry.label),
+                csv_field(&entry.fit.source_name),
+                csv_field(entry.fit.model.to_string()),
+                count,
+                x_min,
+                x_max,
+                entry.fit.r_squared,
+                csv_number(chi_square),
+                csv_number(reduced),
+                csv_field(&entry.parameter_name(j)),
+                value,
+                csv_number(error)
+            );
+        }
+    }
+    out
+}
+
+// Fixed-point for ordinary magnitudes, scientific notation for very small or large ones
+/// Function: explain its purpose and key arguments
+fn number(value: f64) -> String {
+    if value == 0.0 || (1e-3..1e6).contains(&value.abs()) {
+        format!("{:.6}", value)
+    } else {
+        format!("{:.6e}", value)
+    }
+}
+
+// Summary lines shared by the text and Markdown layouts
+/// Function: explain its purpose and key arguments
+fn summary_lines(entry: &ReportEntry) -> Vec<String> {
+// Variable declaration
+    let (count, x_min, x_max) = entry.points();
+// Variable declaration
+    let mut lines = vec![
+        format!("Model: {}", entry.fit.model.to_string()),
+        format!("Equation: {}", entry.fit.equation_string),
+        format!("Points: {} (x = {} to {})", count, number(x_min), number(x_max)),
+        format!("R²: {:.6}", entry.fit.r_squared),
+    ];
+    if let Some((chi_square, reduced)) = entry.fit.chi_square {
+        lines.push(format!("χ²: {} (reduced χ²: {}, weighted by 1/σ²)", number(chi_square), number(reduced)));
+    }
+    lines.push("Standard errors: scaled by the reduced χ² of the fit".to_string());
+    if let Some(linear) = &entry.fit.linear {
+        lines.push(format!(
+            "Slope p-value: {:.3e} ({} degrees of freedom)",
+            linear.p_value, linear.degrees_of_freedom
+        ));
+    }
+    lines
+}
+
+/// Function: explain its purpose and key arguments
+fn render_text(entries: &[ReportEntry]) -> String {
+// Variable declaration
+    let mut out = String::from("Fit report\n==========\n");
+    for entry in entries {
+        let _ = writeln!(out, "\n{}: {}", entry.label, entry.fit.source_name);
+        for line in summary_lines(entry) {
+            let _ = writeln!(out, "  {}", line);
+        }
+        let _ = writeln!(out, "  Parameters:");
+// Variable declaration
+        let width = (0..entry.fit.parameters.len()).map(|j| entry.parameter_name(j).chars().count()).max().unwrap_or(0);
+        for (j, (value, error)) in entry.fit.parameters.iter().zip(entry.errors()).enumerate() {
+// Variable declaration
+            let error = error.map_or(String::new(), |error| format!(" ± {}", number(error)));
+            let _ = writeln!(out, "    {:<width$} = {}{}", entry.parameter_name(j), number(*value), error, width = width);
+        }
+    }
+    out
+}
+
+/// Function: explain its purpose and key arguments
+fn render_markdown(entries: &[ReportEntry]) -> String {
+// Variable declaration
+    let mut out = String::from("# Fit report\n");
+    for entry in entries {
+        let _ = writeln!(out, "\n## {}: {}\n", entry.label, entry.fit.source_name.replace('|', "\\|"));
+        for line in summary_lines(entry) {
+            let _ = writeln!(out, "- {}", line.replace('|', "\\|"));
+        }
+        let _ = writeln!(out, "\n| Parameter | Value | Standard error |\n|---|---:|---:|");
+        for (j, (value, error)) in entry.fit.parameters.iter().zip(entry.errors()).enumerate() {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                entry.parameter_name(j),
+                number(*value),
+                error.map_or("–".to_string(), number)
+            );
+        }
+    }
+    out
+}
+
+// The report of all fits in the given layout
+/// Function: explain its purpose and key arguments
+pub fn render_fit_report(fits: &[FitResult], global_fits: &[GlobalFitResult], format: ReportFormat) -> String {
+// Variable declaration
+    let entries = entries(fits, global_fits);
+    match format {
+        ReportFormat::Text => render_text(&entries),
+        ReportFormat::Csv => render_csv(&entries),
+        ReportFormat::Markdown => render_markdown(&entries),
+    }
+}
+
+// Write the report to `path`, laid out according to its extension
+/// Function: explain its purpose and key arguments
+pub fn save_fit_report(
+    path: &Path,
+    fits: &[FitResult],
+    global_fits: &[GlobalFitResult],
+) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(path, render_fit_report(fits, global_fits, ReportFormat::from_path(path)))?;
+    Ok(())
+}
+
+/// Function: explain its purpose and key arguments
+pub fn pick_fit_report_path() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("Text", &["txt"])
+        .add_filter("CSV", &["csv"])
+        .add_filter("Markdown", &["md"])
+        .set_file_name("fit_report.txt")
+        .save_file()
+}
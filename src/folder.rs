@@ -0,0 +1,244 @@
+// Import external modules or crates needed in folder.rs
+use crate::compression::data_extension;
+// Import external modules or crates needed in folder.rs
+use crate::utils::DATA_FILE_EXTENSIONS;
+// Import external modules or crates needed in folder.rs
+use clap::ValueEnum;
+// Import external modules or crates needed in folder.rs
+use std::path::{Path, PathBuf};
+
+// Whether `name` matches `pattern`, where * stands for any run of characters and ? for
+// any single character
+/// Function: explain its purpose and key arguments
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+// Variable declaration
+    let pattern: Vec<char> = pattern.chars().collect();
+// Variable declaration
+    let name: Vec<char> = name.chars().collect();
+    // Position just after the last * and where in the name that * stopped matching, so
+    // the * can be made to swallow one more character when the rest fails to match
+// Variable declaration
+    let mut backtrack: Option<(usize, usize)> = None;
+// Variable declaration
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Whether `path` has the extension of a file type that can be opened as data, compressed or not
+/// Function: explain its purpose and key arguments
+fn is_data_file(path: &Path) -> bool {
+    data_extension(path).is_some_and(|ext| DATA_FILE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// Data files in `dir` whose names match `pattern`, optionally also in every folder below
+// it, sorted by path so numbered files come in order. Other files (notes, images, scripts
+// next to the data) are skipped even when their names match.
+/// Function: explain its purpose and key arguments
+pub fn matching_files(dir: &Path, pattern: &str, recursive: bool) -> std::io::Result<Vec<PathBuf>> {
+// Variable declaration
+    let mut files = Vec::new();
+    collect_matches(dir, pattern, recursive, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Function: explain its purpose and key arguments
+fn collect_matches(dir: &Path, pattern: &str, recursive: bool, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+// Variable declaration
+        let entry = entry?;
+        // Symbolic links to folders are not followed, so a link loop cannot recurse forever
+// Variable declaration
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if recursive {
+                collect_matches(&entry.path(), pattern, recursive, files)?;
+            }
+        } else if wildcard_match(pattern, &entry.file_name().to_string_lossy()) && is_data_file(&entry.path()) {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+// Files matching a pattern such as data/*.xvg; wildcards are only allowed in the file
+// name, and a pattern without a folder looks in the current one
+/// Function: explain its purpose and key arguments
+pub fn glob_files(glob: &str, recursive: bool) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let path = Path::new(glob);
+// Variable declaration
+    let pattern = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("'{}' does not end in a file name pattern", glob))?;
+// Variable declaration
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if dir.to_string_lossy().contains(['*', '?']) {
+        return Err(format!("wildcards are only supported in the file name, not in '{}'", dir.display()).into());
+    }
+// Variable declaration
+    let files = matching_files(dir, pattern, recursive).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    if files.is_empty() {
+        return Err(format!("no data files match '{}'", glob).into());
+    }
+    Ok(files)
+}
+
+// How the files of a batch are shared out between subplots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+/// Enum representing a set of related values in folder.rs module
+pub enum Assignment {
+    Active,     // Every file into the active subplot
+    RoundRobin, // One file per subplot, in turn
+    ByName,     // One subplot per group of similarly named files (rmsd_* vs gyrate_*)
+}
+
+/// Implementation block defining methods for this type
+impl Assignment {
+    pub const ALL: [Assignment; 3] = [Assignment::Active, Assignment::RoundRobin, Assignment::ByName];
+
+/// Function: explain its purpose and key arguments
+    pub fn label(self) -> &'static str {
+        match self {
+            Assignment::Active => "All into the active subplot",
+            Assignment::RoundRobin => "One file per subplot",
+            Assignment::ByName => "One subplot per file name group",
+        }
+    }
+}
+
+// Start of a file name that names what it holds: everything before the first digit, _, -
+// or space, so rmsd_rep1.xvg and rmsd_rep2.xvg share the group "rmsd". Names that start
+// with one of those form a group of their own.
+/// Function: explain its purpose and key arguments
+pub fn name_group(path: &Path) -> String {
+// Variable declaration
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+// Variable declaration
+    let prefix = stem.split(|c: char| c.is_ascii_digit() || matches!(c, '_' | '-' | ' ')).next().unwrap_or_default();
+    if prefix.is_empty() {
+        stem
+    } else {
+        prefix.to_string()
+    }
+}
+
+// Subplot wanted for each file, in order. With ByName the groups are the given patterns,
+// in the order given, followed by the name groups of the files matching none of them.
+// Indices may run past the existing subplots; the caller adds subplots or wraps around.
+/// Function: explain its purpose and key arguments
+pub fn plan_subplots(paths: &[PathBuf], assignment: Assignment, group_patterns: &[String], active: usize) -> Vec<usize> {
+    match assignment {
+        Assignment::Active => vec![active; paths.len()],
+        Assignment::RoundRobin => (0..paths.len()).collect(),
+        Assignment::ByName => {
+// Variable declaration
+            let mut groups: Vec<String> = group_patterns.to_vec();
+            paths
+                .iter()
+                .map(|path| {
+// Variable declaration
+                    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    if let Some(i) = group_patterns.iter().position(|pattern| wildcard_match(pattern, &name)) {
+                        return i;
+                    }
+// Variable declaration
+                    let group = name_group(path);
+                    match groups.iter().skip(group_patterns.len()).position(|g| *g == group) {
+                        Some(i) => group_patterns.len() + i,
+                        None => {
+                            groups.push(group);
+                            groups.len() - 1
+                        }
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+// Patterns typed as a comma-separated list, e.g. "rmsd_*, gyrate_*"
+/// Function: explain its purpose and key arguments
+pub fn parse_group_patterns(text: &str) -> Vec<String> {
+    text.split(',').map(str::trim).filter(|pattern| !pattern.is_empty()).map(str::to_string).collect()
+}
+
+// Settings of the Open Folder window: which folder, which files in it and where they go
+/// Data structure used in folder.rs module
+pub struct FolderImport {
+    pub dir: PathBuf,
+    pub pattern: String,
+    pub recursive: bool,
+    pub assignment: Assignment,
+    pub group_patterns: String, // Comma-separated, for Assignment::ByName
+    pub add_subplots: bool,     // Grow the layout when the files need more subplots than there are
+    pub matches: Vec<PathBuf>,
+    pub error: Option<String>, // Why the folder could not be read
+}
+
+/// Implementation block defining methods for this type
+impl FolderImport {
+/// Function: explain its purpose and key arguments
+    pub fn open(dir: PathBuf) -> Self {
+// Variable declaration
+        let mut import = Self {
+            dir,
+            pattern: "*".to_string(),
+            recursive: false,
+            assignment: Assignment::Active,
+            group_patterns: String::new(),
+            add_subplots: true,
+            matches: Vec::new(),
+            error: None,
+        };
+        import.refresh();
+        import
+    }
+
+    // List the matching files again after the pattern or recursion changed
+/// Function: explain its purpose and key arguments
+    pub fn refresh(&mut self) {
+// Variable declaration
+        let pattern = if self.pattern.trim().is_empty() { "*" } else { self.pattern.trim() };
+        match matching_files(&self.dir, pattern, self.recursive) {
+            Ok(matches) => {
+                self.matches = matches;
+                self.error = None;
+            }
+            Err(e) => {
+                self.matches.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    // Subplot wanted for each matching file
+/// Function: explain its purpose and key arguments
+    pub fn plan(&self, active: usize) -> Vec<usize> {
+        plan_subplots(&self.matches, self.assignment, &parse_group_patterns(&self.group_patterns), active)
+    }
+}
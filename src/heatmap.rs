@@ -0,0 +1,168 @@
+// Import external modules or crates needed in heatmap.rs
+use crate::palette::Colormap;
+// Import external modules or crates needed in heatmap.rs
+use serde::{Deserialize, Serialize};
+// Import external modules or crates needed in heatmap.rs
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Source of unique value revisions (see Heatmap::revision)
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+/// Function: explain its purpose and key arguments
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+// Values on a rectangular grid (free-energy surface, contact map, ...), drawn as colored
+// cells. `x` and `y` hold the cell centres; `values` is row-major with one row per `y`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Data structure used in heatmap.rs module
+pub struct Heatmap {
+    pub name: String,
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub values: Vec<f64>, // NaN where a cell has no value
+    #[serde(default)]
+    pub colormap: Colormap,
+    #[serde(default)]
+    pub label: String, // What the values are (e.g. "G (kJ/mol)"), from the XPM legend
+    #[serde(skip, default = "next_revision")]
+    revision: u64, // Changes whenever the values change; used to cache the rendered texture
+}
+
+/// Implementation block defining methods for this type
+impl Heatmap {
+    // Descending coordinates are flipped so that both axes increase along the rows
+/// Function: explain its purpose and key arguments
+    pub fn new(
+        name: String,
+        mut x: Vec<f64>,
+        mut y: Vec<f64>,
+        mut values: Vec<f64>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if x.is_empty() || y.is_empty() {
+            return Err("The matrix has no cells".into());
+        }
+        if values.len() != x.len() * y.len() {
+            return Err(format!(
+                "Expected {} x {} values, found {}",
+                y.len(),
+                x.len(),
+                values.len()
+            )
+            .into());
+        }
+        if x.len() > 1 && x[0] > x[x.len() - 1] {
+            x.reverse();
+            for row in values.chunks_mut(x.len()) {
+                row.reverse();
+            }
+        }
+        if y.len() > 1 && y[0] > y[y.len() - 1] {
+            y.reverse();
+// Variable declaration
+            let rows: Vec<&[f64]> = values.chunks(x.len()).rev().collect();
+            values = rows.concat();
+        }
+        if !is_increasing(&x) || !is_increasing(&y) {
+            return Err("The X and Y coordinates of the matrix must be sorted".into());
+        }
+        Ok(Self {
+            name,
+            x,
+            y,
+            values,
+            colormap: Colormap::default(),
+            label: String::new(),
+            revision: next_revision(),
+        })
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn value(&self, row: usize, col: usize) -> f64 {
+        self.values[row * self.x.len() + col]
+    }
+
+    // Lowest and highest value: the ends of the colorbar
+/// Function: explain its purpose and key arguments
+    pub fn value_range(&self) -> Option<(f64, f64)> {
+        self.values
+            .iter()
+            .copied()
+            .filter(|v| v.is_finite())
+            .fold(None, |range, v| match range {
+                None => Some((v, v)),
+                Some((low, high)) => Some((f64::min(low, v), f64::max(high, v))),
+            })
+    }
+
+    // Cell color on the colormap; None for cells without a value
+/// Function: explain its purpose and key arguments
+    pub fn cell_color(&self, row: usize, col: usize, (low, high): (f64, f64)) -> Option<[u8; 3]> {
+// Variable declaration
+        let value = self.value(row, col);
+        if !value.is_finite() {
+            return None;
+        }
+        Some(if high > low {
+            self.colormap.sample((value - low) / (high - low))
+        } else {
+            self.colormap.sample(0.5)
+        })
+    }
+
+    // Edges of the cells along X: halfway between neighbouring centres, and half a
+    // spacing beyond the outer ones
+/// Function: explain its purpose and key arguments
+    pub fn x_edges(&self) -> Vec<f64> {
+        cell_edges(&self.x)
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn y_edges(&self) -> Vec<f64> {
+        cell_edges(&self.y)
+    }
+
+    // (min x, max x, min y, max y) covered by the cells
+/// Function: explain its purpose and key arguments
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+// Variable declaration
+        let (x, y) = (self.x_edges(), self.y_edges());
+// Variable declaration
+        let span = |edges: &[f64]| {
+            edges
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), e| (low.min(*e), high.max(*e)))
+        };
+// Variable declaration
+        let ((min_x, max_x), (min_y, max_y)) = (span(&x), span(&y));
+        (min_x, max_x, min_y, max_y)
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn is_increasing(values: &[f64]) -> bool {
+    values.iter().all(|v| v.is_finite()) && values.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+/// Function: explain its purpose and key arguments
+fn cell_edges(centres: &[f64]) -> Vec<f64> {
+    if centres.len() == 1 {
+        return vec![centres[0] - 0.5, centres[0] + 0.5];
+    }
+// Variable declaration
+    let mut edges = Vec::with_capacity(centres.len() + 1);
+    edges.push(centres[0] - (centres[1] - centres[0]) / 2.0);
+    for pair in centres.windows(2) {
+        edges.push((pair[0] + pair[1]) / 2.0);
+    }
+// Variable declaration
+    let n = centres.len();
+    edges.push(centres[n - 1] + (centres[n - 1] - centres[n - 2]) / 2.0);
+    edges
+}
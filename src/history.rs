@@ -0,0 +1,120 @@
+// Import external modules or crates needed in history.rs
+use crate::app::{Subplot, SubplotLayout};
+// Import external modules or crates needed in history.rs
+use std::time::{Duration, Instant};
+
+// Oldest entries are dropped beyond this many undo steps
+const MAX_HISTORY: usize = 100;
+// Continuous edits of the same kind within this window collapse into one entry
+// (see History::record_coalescing)
+const MERGE_WINDOW: Duration = Duration::from_millis(1000);
+
+#[derive(Debug, Clone)]
+/// Data structure used in history.rs module
+pub struct Snapshot {
+    pub label: String, // Describes the change that was made after this snapshot
+    pub subplots: Vec<Subplot>,
+    pub hidden_subplots: Vec<Subplot>,
+    pub subplot_layout: SubplotLayout,
+    pub active_subplot: usize,
+}
+
+#[derive(Default)]
+/// Data structure used in history.rs module
+pub struct History {
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    last_coalescing: Option<Instant>, // When the last continuous edit was recorded
+    generation: u64, // Bumped on every record/undo/redo so callers can detect history activity
+}
+
+/// Implementation block defining methods for this type
+impl History {
+    // Store the state from before a change as its own entry; clears the redo stack
+/// Function: explain its purpose and key arguments
+    pub fn record(&mut self, snapshot: Snapshot) {
+        self.last_coalescing = None;
+        self.push(snapshot);
+    }
+
+    // Store the state from before one step of a continuous edit (typing a title, dragging a
+    // color picker). Steps with the same `label` within MERGE_WINDOW of each other keep the
+    // entry of the first, so the whole edit is undone at once. `snapshot` is only called
+    // for a step that starts a new entry, as copying the state is costly on large data.
+/// Function: explain its purpose and key arguments
+    pub fn record_coalescing(&mut self, label: &str, snapshot: impl FnOnce() -> Snapshot) {
+// Variable declaration
+        let now = Instant::now();
+// Variable declaration
+        let merge = self.redo_stack.is_empty()
+            && self.last_coalescing.is_some_and(|t| now.duration_since(t) < MERGE_WINDOW)
+            && self.undo_stack.last().is_some_and(|top| top.label == label);
+        self.last_coalescing = Some(now);
+        if merge {
+            self.generation += 1;
+        } else {
+            self.push(snapshot());
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn push(&mut self, snapshot: Snapshot) {
+        self.generation += 1;
+        self.undo_stack.push(snapshot);
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    // Swap the current state for the most recent undo entry
+/// Function: explain its purpose and key arguments
+    pub fn undo(&mut self, mut current: Snapshot) -> Option<Snapshot> {
+// Variable declaration
+        let previous = self.undo_stack.pop()?;
+        current.label = previous.label.clone();
+        self.redo_stack.push(current);
+        self.last_coalescing = None;
+        self.generation += 1;
+        Some(previous)
+    }
+
+    // Swap the current state for the most recent redo entry
+/// Function: explain its purpose and key arguments
+    pub fn redo(&mut self, mut current: Snapshot) -> Option<Snapshot> {
+// Variable declaration
+        let next = self.redo_stack.pop()?;
+        current.label = next.label.clone();
+        self.undo_stack.push(current);
+        self.last_coalescing = None;
+        self.generation += 1;
+        Some(next)
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    // Labels of undoable changes, most recent first
+/// Function: explain its purpose and key arguments
+    pub fn undo_labels(&self) -> Vec<&str> {
+        self.undo_stack.iter().rev().map(|s| s.label.as_str()).collect()
+    }
+
+    // Labels of redoable changes, next redo first
+/// Function: explain its purpose and key arguments
+    pub fn redo_labels(&self) -> Vec<&str> {
+        self.redo_stack.iter().rev().map(|s| s.label.as_str()).collect()
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
@@ -0,0 +1,122 @@
+// Import external modules or crates needed in inset.rs
+use crate::app::{AxisScale, Subplot};
+// Import external modules or crates needed in inset.rs
+use serde::{Deserialize, Serialize};
+
+// Zoomed copy of part of a subplot drawn inside its own plot, like matplotlib's inset_axes.
+// The region is kept in data values so it survives switching an axis between linear and
+// log; `placement` is relative to the parent's plot area so the inset follows resizing.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Data structure used in inset.rs module
+pub struct Inset {
+    pub x_range: [f64; 2], // Zoomed region, in data values
+    pub y_range: [f64; 2],
+    pub placement: [f32; 4], // Left, top, width and height as fractions of the parent's plot area
+}
+
+// Part of the zoomed region picked up with the mouse on the parent plot
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in inset.rs module
+pub enum InsetGrab {
+    Move,   // The inside of the region: drags the whole region
+    Resize, // The bottom-right corner: drags that corner
+}
+
+// Top-right of the plot area, clear of the default legend corner
+const DEFAULT_PLACEMENT: [f32; 4] = [0.55, 0.08, 0.4, 0.4];
+
+// Smallest inset, as a fraction of the parent's plot area
+pub const MIN_PLACEMENT_SIZE: f32 = 0.1;
+
+/// Implementation block defining methods for this type
+impl Inset {
+    // Inset zooming into the middle fifth of `view` (min x, max x, min y, max y in plot
+    // coordinates), which is where a new inset starts out
+/// Function: explain its purpose and key arguments
+    pub fn around(view: (f64, f64, f64, f64), x_scale: AxisScale, y_scale: AxisScale) -> Self {
+// Variable declaration
+        let (min_x, max_x, min_y, max_y) = view;
+// Variable declaration
+        let middle = |min: f64, max: f64, scale: AxisScale| {
+// Variable declaration
+            let (center, half) = ((min + max) / 2.0, (max - min) / 10.0);
+            sorted([scale.invert(center - half), scale.invert(center + half)])
+        };
+        Self {
+            x_range: middle(min_x, max_x, x_scale),
+            y_range: middle(min_y, max_y, y_scale),
+            placement: DEFAULT_PLACEMENT,
+        }
+    }
+
+    // The zoomed region in plot coordinates (log10 of the value on log axes, negated on
+    // reversed axes) as (min x, max x, min y, max y); None when an axis cannot show it
+/// Function: explain its purpose and key arguments
+    pub fn plot_region(&self, x_scale: AxisScale, y_scale: AxisScale) -> Option<(f64, f64, f64, f64)> {
+// Variable declaration
+        let (x0, x1) = x_scale.apply_range(self.x_range[0], self.x_range[1])?;
+// Variable declaration
+        let (y0, y1) = y_scale.apply_range(self.y_range[0], self.y_range[1])?;
+        (x0 < x1 && y0 < y1).then_some((x0, x1, y0, y1))
+    }
+
+    // Move the region, or its bottom-right corner, by `delta` in plot coordinates; a corner
+    // dragged past the opposite edge stops short of it
+/// Function: explain its purpose and key arguments
+    pub fn drag(&mut self, grab: InsetGrab, [dx, dy]: [f64; 2], x_scale: AxisScale, y_scale: AxisScale) {
+// Variable declaration
+        let Some((x0, x1, y0, y1)) = self.plot_region(x_scale, y_scale) else {
+            return;
+        };
+// Variable declaration
+        let (x0, x1, y0, y1) = match grab {
+            InsetGrab::Move => (x0 + dx, x1 + dx, y0 + dy, y1 + dy),
+            InsetGrab::Resize => (x0, (x1 + dx).max(x0 + (x1 - x0) * 0.05), (y0 + dy).min(y1 - (y1 - y0) * 0.05), y1),
+        };
+        self.x_range = sorted([x_scale.invert(x0), x_scale.invert(x1)]);
+        self.y_range = sorted([y_scale.invert(y0), y_scale.invert(y1)]);
+    }
+
+    // Where the inset goes inside a plot area of (left, top, width, height)
+/// Function: explain its purpose and key arguments
+    pub fn rect_in(&self, (left, top, width, height): (f64, f64, f64, f64)) -> (f64, f64, f64, f64) {
+// Variable declaration
+        let [x, y, w, h] = self.placement.map(|fraction| fraction as f64);
+        (left + x * width, top + y * height, w * width, h * height)
+    }
+
+    // Keep the inset inside the plot area and at least MIN_PLACEMENT_SIZE on each side
+/// Function: explain its purpose and key arguments
+    pub fn clamp_placement(&mut self) {
+// Variable declaration
+        let [x, y, w, h] = self.placement;
+// Variable declaration
+        let (w, h) = (w.clamp(MIN_PLACEMENT_SIZE, 1.0), h.clamp(MIN_PLACEMENT_SIZE, 1.0));
+        self.placement = [x.clamp(0.0, 1.0 - w), y.clamp(0.0, 1.0 - h), w, h];
+    }
+
+    // The subplot as the exports draw the inset: the parent's data viewed through the zoomed
+    // region, without the title, axis titles or legend that belong to the parent
+/// Function: explain its purpose and key arguments
+    pub fn subplot(&self, parent: &Subplot) -> Option<Subplot> {
+// Variable declaration
+        let region = self.plot_region(parent.config.x_axis(), parent.config.y_axis())?;
+// Variable declaration
+        let mut subplot = parent.clone();
+        subplot.view_bounds = Some(region);
+        subplot.annotations.clear();
+        subplot.config.inset = None;
+        subplot.config.equal_aspect = false;
+        subplot.config.show_legend = false;
+        subplot.config.x_label.clear();
+        subplot.config.y_label.clear();
+        subplot.config.y2_label.clear();
+        Some(subplot)
+    }
+}
+
+// Data range smallest first; plot coordinates of a reversed axis map back in the other order
+/// Function: explain its purpose and key arguments
+fn sorted([a, b]: [f64; 2]) -> [f64; 2] {
+    [a.min(b), a.max(b)]
+}
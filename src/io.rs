@@ -0,0 +1,1271 @@
+// Import external modules or crates needed in io.rs
+use crate::compression::{data_extension, read_data, read_data_to_string};
+// Import external modules or crates needed in io.rs
+use crate::heatmap::Heatmap;
+// Import external modules or crates needed in io.rs
+use crate::utils::{
+    collect_series, file_display_name, series_columns_from_headers, FileMetadata, LoadedFile, LoadedSeries, SeriesColumns,
+};
+// Import external modules or crates needed in io.rs
+use crate::timestamps::{column_has_timestamps, parse_number_or_timestamp};
+// Import external modules or crates needed in io.rs
+use arrow::array::{Array, Float64Array};
+// Import external modules or crates needed in io.rs
+use arrow::compute::cast;
+// Import external modules or crates needed in io.rs
+use arrow::datatypes::DataType as ArrowType;
+// Import external modules or crates needed in io.rs
+use calamine::{open_workbook_auto, Data, DataType, Range, Reader};
+// Import external modules or crates needed in io.rs
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+// Import external modules or crates needed in io.rs
+use serde::{Deserialize, Serialize};
+// Import external modules or crates needed in io.rs
+use std::path::{Path, PathBuf};
+
+// Workbook formats calamine can read
+pub const SPREADSHEET_EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+/// Function: explain its purpose and key arguments
+pub fn is_spreadsheet(path: &Path) -> bool {
+    data_extension(path).is_some_and(|ext| SPREADSHEET_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// Numeric value of a cell; dates become Excel serial day numbers
+/// Function: explain its purpose and key arguments
+fn cell_value(cell: &Data) -> Option<f64> {
+    match cell {
+        Data::DateTime(date) => Some(date.as_f64()),
+        Data::String(text) => text.trim().parse::<f64>().ok(),
+        other => other.as_f64(),
+    }
+}
+
+// One worksheet as columns of numbers; cells that are empty or not numeric are None
+#[derive(Debug, Clone)]
+/// Data structure used in io.rs module
+pub struct SheetTable {
+    pub name: String,
+    pub headers: Vec<String>, // Empty when the sheet has no header row
+    pub columns: Vec<Vec<Option<f64>>>,
+}
+
+/// Implementation block defining methods for this type
+impl SheetTable {
+/// Function: explain its purpose and key arguments
+    fn from_range(name: String, range: &Range<Data>) -> Self {
+// Variable declaration
+        let mut rows = range.rows().peekable();
+        // The first row is a header if it holds any text that is not a number
+// Variable declaration
+        let has_header = rows.peek().is_some_and(|first| {
+            first
+                .iter()
+                .any(|cell| !cell.is_empty() && cell_value(cell).is_none())
+        });
+// Variable declaration
+        let headers: Vec<String> = if has_header {
+            rows.next()
+                .map(|first| {
+                    first
+                        .iter()
+                        .map(|cell| cell.as_string().unwrap_or_default().trim().to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+// Variable declaration
+        let mut columns = vec![Vec::new(); range.width()];
+        for row in rows {
+            for (col, cell) in row.iter().enumerate() {
+                columns[col].push(cell_value(cell));
+            }
+        }
+
+        Self {
+            name,
+            headers,
+            columns,
+        }
+    }
+
+    // Header text of a column, or "Column N" when there is none
+/// Function: explain its purpose and key arguments
+    pub fn column_label(&self, col: usize) -> String {
+        match self.headers.get(col) {
+            Some(header) if !header.is_empty() => header.clone(),
+            _ => format!("Column {}", col + 1),
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn column_labels(&self) -> Vec<String> {
+        (0..self.columns.len()).map(|col| self.column_label(col)).collect()
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn row_count(&self) -> usize {
+        self.columns.first().map_or(0, Vec::len)
+    }
+
+    // One series per Y column; rows without X are skipped and a missing Y becomes a NaN
+    // point, which breaks the line
+/// Function: explain its purpose and key arguments
+    pub fn series(&self, x_col: usize, y_cols: &[usize]) -> Vec<LoadedSeries> {
+// Variable declaration
+        let Some(xs) = self.columns.get(x_col) else {
+            return Vec::new();
+        };
+        y_cols
+            .iter()
+            .filter_map(|&y_col| {
+// Variable declaration
+                let ys = self.columns.get(y_col)?;
+// Variable declaration
+                let points = xs
+                    .iter()
+                    .zip(ys)
+                    .filter_map(|(x, y)| Some([(*x)?, y.unwrap_or(f64::NAN)]))
+                    .collect();
+                Some(LoadedSeries {
+                    name: Some(self.column_label(y_col)),
+                    points,
+                    x_err: None,
+                    y_err: None,
+                    color_values: None,
+                })
+            })
+            .collect()
+    }
+}
+
+// Read every worksheet of a workbook
+/// Function: explain its purpose and key arguments
+pub fn read_workbook(path: &Path) -> Result<Vec<SheetTable>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut workbook = open_workbook_auto(path)?;
+// Variable declaration
+    let mut sheets = Vec::new();
+    for name in workbook.sheet_names() {
+// Variable declaration
+        let range = workbook.worksheet_range(&name)?;
+        sheets.push(SheetTable::from_range(name, &range));
+    }
+    if sheets.is_empty() {
+        return Err("Workbook has no worksheets".into());
+    }
+    Ok(sheets)
+}
+
+// Import without asking (command line): first sheet with at least two columns,
+// first column as X and every other column as Y
+/// Function: explain its purpose and key arguments
+pub fn load_spreadsheet(path: &Path) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let sheet = read_workbook(path)?
+        .into_iter()
+        .find(|sheet| sheet.columns.len() >= 2)
+        .ok_or("Need a worksheet with at least two columns (X and Y)")?;
+// Variable declaration
+    let y_cols: Vec<usize> = (1..sheet.columns.len()).collect();
+    Ok(sheet.series(0, &y_cols))
+}
+
+// State of the sheet/column picker shown when a workbook is opened in the GUI; a Parquet
+// file is shown the same way, as a workbook with a single sheet
+/// Data structure used in io.rs module
+pub struct SpreadsheetImport {
+    pub path: PathBuf,
+    pub sheets: Vec<SheetTable>,
+    pub sheet: usize,
+    pub x_col: usize,
+    pub y_cols: Vec<bool>, // One flag per column of the selected sheet
+    pub subplot_index: usize, // Subplot that receives the datasets
+}
+
+/// Implementation block defining methods for this type
+impl SpreadsheetImport {
+/// Function: explain its purpose and key arguments
+    pub fn open(path: PathBuf, subplot_index: usize) -> Result<Self, Box<dyn std::error::Error>> {
+// Variable declaration
+        let sheets = if is_parquet(&path) {
+            vec![read_parquet_table(&path)?]
+        } else {
+            read_workbook(&path)?
+        };
+// Variable declaration
+        let mut import = Self {
+            path,
+            sheets,
+            sheet: 0,
+            x_col: 0,
+            y_cols: Vec::new(),
+            subplot_index,
+        };
+        import.select_sheet(0);
+        Ok(import)
+    }
+
+    // Switch sheets and go back to the default columns: first as X, second as Y
+/// Function: explain its purpose and key arguments
+    pub fn select_sheet(&mut self, sheet: usize) {
+        self.sheet = sheet;
+        self.x_col = 0;
+// Variable declaration
+        let width = self.sheets.get(sheet).map_or(0, |s| s.columns.len());
+        self.y_cols = (0..width).map(|col| col == 1).collect();
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn current_sheet(&self) -> Option<&SheetTable> {
+        self.sheets.get(self.sheet)
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn selected_series(&self) -> Vec<LoadedSeries> {
+        self.current_sheet()
+            .map_or_else(Vec::new, |sheet| sheet.series(self.x_col, &selected_columns(&self.y_cols, self.x_col)))
+    }
+}
+
+/// Function: explain its purpose and key arguments
+pub fn is_parquet(path: &Path) -> bool {
+    data_extension(path).is_some_and(|ext| ext == "parquet" || ext == "pq")
+}
+
+// Columns that can be plotted: numbers and booleans as they are, dates and timestamps
+// as their integer value (days or time units since 1970)
+/// Function: explain its purpose and key arguments
+fn parquet_column_type(data_type: &ArrowType) -> Option<ArrowType> {
+    if data_type.is_numeric() || *data_type == ArrowType::Boolean {
+        Some(ArrowType::Float64)
+    } else if data_type.is_temporal() {
+        Some(ArrowType::Int64)
+    } else {
+        None
+    }
+}
+
+// All plottable columns of a Parquet file; nulls and NaN (pandas' missing value) are None
+/// Function: explain its purpose and key arguments
+pub fn read_parquet_table(path: &Path) -> Result<SheetTable, Box<dyn std::error::Error>> {
+// Variable declaration
+    let builder = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(path)?)?;
+// Variable declaration
+    let fields: Vec<(usize, String, ArrowType)> = builder
+        .schema()
+        .fields()
+        .iter()
+        .enumerate()
+        .filter_map(|(i, field)| {
+            parquet_column_type(field.data_type()).map(|via| (i, field.name().clone(), via))
+        })
+        .collect();
+    if fields.is_empty() {
+        return Err("No numeric, boolean or date columns".into());
+    }
+
+// Variable declaration
+    let mut columns: Vec<Vec<Option<f64>>> = vec![Vec::new(); fields.len()];
+    for batch in builder.build()? {
+// Variable declaration
+        let batch = batch?;
+        for ((index, _, via), column) in fields.iter().zip(columns.iter_mut()) {
+// Variable declaration
+            let values = cast(&cast(batch.column(*index), via)?, &ArrowType::Float64)?;
+// Variable declaration
+            let values = values
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .ok_or("Column could not be converted to numbers")?;
+            column.extend((0..values.len()).map(|row| {
+                Some(values.value(row)).filter(|value| values.is_valid(row) && !value.is_nan())
+            }));
+        }
+    }
+
+    Ok(SheetTable {
+        name: path
+            .file_stem()
+            .map_or_else(|| "parquet".to_string(), |stem| stem.to_string_lossy().into_owned()),
+        headers: fields.into_iter().map(|(_, name, _)| name).collect(),
+        columns,
+    })
+}
+
+// Import without asking (command line): first column as X and every other column as Y
+/// Function: explain its purpose and key arguments
+pub fn load_parquet(path: &Path) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let table = read_parquet_table(path)?;
+    if table.columns.len() < 2 {
+        return Err("Need at least two numeric columns (X and Y)".into());
+    }
+// Variable declaration
+    let y_cols: Vec<usize> = (1..table.columns.len()).collect();
+    Ok(table.series(0, &y_cols))
+}
+
+// NumPy array as read from a .npy file, converted to f64 in row-major (C) order
+#[derive(Debug, Clone)]
+/// Data structure used in io.rs module
+struct NpyArray {
+    shape: Vec<usize>,
+    data: Vec<f64>,
+}
+
+/// Implementation block defining methods for this type
+impl NpyArray {
+    // Parse the .npy format: magic, version, a Python dict literal header, then raw data
+/// Function: explain its purpose and key arguments
+    fn parse(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        if bytes.len() < 10 || &bytes[..6] != b"\x93NUMPY" {
+            return Err("Not a NumPy .npy file".into());
+        }
+// Variable declaration
+        let (header_len, header_start) = match bytes[6] {
+            1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+            2 | 3 if bytes.len() >= 12 => (
+                u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+                12,
+            ),
+            version => return Err(format!("Unsupported .npy version {}", version).into()),
+        };
+// Variable declaration
+        let header = bytes
+            .get(header_start..header_start + header_len)
+            .ok_or("Truncated .npy header")?;
+// Variable declaration
+        let header = String::from_utf8_lossy(header);
+
+// Variable declaration
+        let descr = npy_header_value(&header, "descr")
+            .and_then(|value| value.split('\'').nth(1))
+            .ok_or("Missing dtype in .npy header")?;
+// Variable declaration
+        let fortran_order = npy_header_value(&header, "fortran_order").is_some_and(|v| v.starts_with("True"));
+// Variable declaration
+        let shape: Vec<usize> = npy_header_value(&header, "shape")
+            .and_then(|value| value.strip_prefix('('))
+            .and_then(|value| value.split(')').next())
+            .ok_or("Missing shape in .npy header")?
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| dim.parse::<usize>())
+            .collect::<Result<_, _>>()?;
+
+// Variable declaration
+        let count: usize = shape.iter().product();
+// Variable declaration
+        let data = decode_npy_values(&bytes[header_start + header_len..], descr, count)?;
+// Variable declaration
+        let data = match (fortran_order, shape.as_slice()) {
+            // Column-major 2-D data is transposed into row-major order
+            (true, &[rows, cols]) => (0..rows * cols).map(|i| data[(i % cols) * rows + i / cols]).collect(),
+            _ => data,
+        };
+        Ok(Self { shape, data })
+    }
+
+    // Row index and the values of each column: 1-D arrays are one column against the
+    // index, 2-D arrays use column 0 as X when there is more than one column
+/// Function: explain its purpose and key arguments
+    fn series(&self, name: Option<&str>) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+        let series = |label: Option<String>, points: Vec<[f64; 2]>| LoadedSeries {
+            name: label,
+            points,
+            x_err: None,
+            y_err: None,
+            color_values: None,
+        };
+        match *self.shape.as_slice() {
+            [_] => Ok(vec![series(
+                name.map(str::to_string),
+                self.data.iter().enumerate().map(|(i, &y)| [i as f64, y]).collect(),
+            )]),
+            [_, 1] => Ok(vec![series(
+                name.map(str::to_string),
+                self.data.iter().enumerate().map(|(i, &y)| [i as f64, y]).collect(),
+            )]),
+            [_, cols] => Ok((1..cols)
+                .map(|col| {
+// Variable declaration
+                    let label = match name {
+                        Some(name) if cols == 2 => Some(name.to_string()),
+                        Some(name) => Some(format!("{}_y{}", name, col)),
+                        None => None,
+                    };
+                    series(
+                        label,
+                        self.data
+                            .chunks_exact(cols)
+                            .map(|row| [row[0], row[col]])
+                            .collect(),
+                    )
+                })
+                .collect()),
+            _ => Err(format!("Only 1-D and 2-D arrays can be plotted (shape {:?})", self.shape).into()),
+        }
+    }
+}
+
+// Text after `'key':` in a .npy header dict
+/// Function: explain its purpose and key arguments
+fn npy_header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+// Variable declaration
+    let pattern = format!("'{}':", key);
+    header.find(&pattern).map(|start| header[start + pattern.len()..].trim_start())
+}
+
+// Convert raw array data of the given dtype (e.g. "<f8", ">i4", "|u1") to f64
+/// Function: explain its purpose and key arguments
+fn decode_npy_values(raw: &[u8], descr: &str, count: usize) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut chars = descr.chars();
+// Variable declaration
+    let big_endian = match chars.next() {
+        Some('>') => true,
+        Some('<') | Some('|') | Some('=') => false,
+        _ => return Err(format!("Unsupported dtype '{}'", descr).into()),
+    };
+// Variable declaration
+    let kind = chars.next().ok_or("Missing dtype kind")?;
+// Variable declaration
+    let size: usize = chars.as_str().parse().map_err(|_| format!("Unsupported dtype '{}'", descr))?;
+    // Complex numbers, long doubles and strings do not fit in a number column
+    if ![1, 2, 4, 8].contains(&size) {
+        return Err(format!("Unsupported dtype '{}'", descr).into());
+    }
+    if raw.len() < count * size {
+        return Err("Truncated .npy data".into());
+    }
+
+// Variable declaration
+    let decode = |chunk: &[u8]| -> Option<f64> {
+// Variable declaration
+        let mut buf = [0u8; 8];
+        buf[..size].copy_from_slice(chunk);
+        if big_endian {
+            buf[..size].reverse();
+        }
+        Some(match (kind, size) {
+            ('f', 4) => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ('f', 8) => f64::from_le_bytes(buf),
+            ('i', 1) => buf[0] as i8 as f64,
+            ('i', 2) => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ('i', 4) => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ('i', 8) => i64::from_le_bytes(buf) as f64,
+            ('u', 1) | ('b', 1) => buf[0] as f64,
+            ('u', 2) => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ('u', 4) => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ('u', 8) => u64::from_le_bytes(buf) as f64,
+            _ => return None,
+        })
+    };
+
+    raw.chunks_exact(size)
+        .take(count)
+        .map(|chunk| decode(chunk).ok_or_else(|| format!("Unsupported dtype '{}'", descr).into()))
+        .collect()
+}
+
+// Helper function to load a .npy file (see NpyArray::series for the layout)
+/// Function: explain its purpose and key arguments
+pub fn load_npy(path: &Path) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+    NpyArray::parse(&read_data(path)?)?.series(None)
+}
+
+// Helper function to load a .npz archive. A 1-D array named "x" becomes the X values of
+// every other 1-D array of the same length; all other arrays load as in load_npy and
+// are named after their archive entry. Arrays that cannot be read are skipped with a
+// warning.
+/// Function: explain its purpose and key arguments
+pub fn load_npz(path: &Path) -> Result<LoadedFile, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+// Variable declaration
+    let mut arrays = Vec::new();
+// Variable declaration
+    let mut warnings = Vec::new();
+    for i in 0..archive.len() {
+// Variable declaration
+        let mut entry = archive.by_index(i)?;
+// Variable declaration
+        let name = entry.name().trim_end_matches(".npy").to_string();
+// Variable declaration
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut bytes)?;
+        match NpyArray::parse(&bytes) {
+            Ok(array) => arrays.push((name, array)),
+            Err(e) => warnings.push(format!("Skipped array {}: {}", name, e)),
+        }
+    }
+
+// Variable declaration
+    let x = arrays
+        .iter()
+        .find(|(name, array)| name.eq_ignore_ascii_case("x") && array.shape.len() == 1)
+        .map(|(_, array)| array.data.clone());
+// Variable declaration
+    let mut series = Vec::new();
+    for (name, array) in &arrays {
+        match &x {
+            Some(_) if name.eq_ignore_ascii_case("x") && array.shape.len() == 1 => {}
+            Some(xs) if array.shape.len() == 1 && array.data.len() == xs.len() => {
+                series.push(LoadedSeries {
+                    name: Some(name.clone()),
+                    points: xs.iter().zip(&array.data).map(|(&x, &y)| [x, y]).collect(),
+                    x_err: None,
+                    y_err: None,
+                    color_values: None,
+                });
+            }
+            _ => match array.series(Some(name)) {
+                Ok(array_series) => series.extend(array_series),
+                Err(e) => warnings.push(format!("Skipped array {}: {}", name, e)),
+            },
+        }
+    }
+    if series.is_empty() {
+        return Err(warnings.pop().unwrap_or_else(|| "Archive has no arrays to plot".to_string()).into());
+    }
+    Ok(LoadedFile {
+        series,
+        metadata: FileMetadata {
+            warnings,
+            ..FileMetadata::default()
+        },
+    })
+}
+
+// HDF5 file extensions; reading them needs the optional `hdf5` feature
+pub const HDF5_EXTENSIONS: &[&str] = &["h5", "hdf5", "he5", "hdf"];
+
+/// Function: explain its purpose and key arguments
+pub fn is_hdf5(path: &Path) -> bool {
+    data_extension(path).is_some_and(|ext| HDF5_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// A group or dataset in an HDF5 file, with its full path (e.g. "/run1/energy")
+#[derive(Debug, Clone)]
+/// Data structure used in io.rs module
+pub struct Hdf5Node {
+    pub path: String,
+    pub is_group: bool,
+    pub shape: Vec<usize>, // Empty for groups
+    pub children: Vec<Hdf5Node>,
+}
+
+/// Implementation block defining methods for this type
+impl Hdf5Node {
+    // Last component of the path
+/// Function: explain its purpose and key arguments
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    // Depth-first list of this node and everything below it
+/// Function: explain its purpose and key arguments
+    fn flatten(&self) -> Vec<&Hdf5Node> {
+// Variable declaration
+        let mut nodes = vec![self];
+        for child in &self.children {
+            nodes.extend(child.flatten());
+        }
+        nodes
+    }
+}
+
+#[cfg(feature = "hdf5")]
+/// Function: explain its purpose and key arguments
+fn hdf5_node(group: &hdf5::Group) -> Result<Hdf5Node, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut children = Vec::new();
+    for child in group.groups()? {
+        children.push(hdf5_node(&child)?);
+    }
+    for dataset in group.datasets()? {
+        children.push(Hdf5Node {
+            path: dataset.name(),
+            is_group: false,
+            shape: dataset.shape(),
+            children: Vec::new(),
+        });
+    }
+    Ok(Hdf5Node {
+        path: group.name(),
+        is_group: true,
+        shape: Vec::new(),
+        children,
+    })
+}
+
+// Structure of an HDF5 file, starting at the root group "/"
+#[cfg(feature = "hdf5")]
+/// Function: explain its purpose and key arguments
+pub fn read_hdf5_tree(path: &Path) -> Result<Hdf5Node, Box<dyn std::error::Error>> {
+// Variable declaration
+    let file = hdf5::File::open(path)?;
+    hdf5_node(&file)
+}
+
+// Columns of one HDF5 object as a table: a 2-D dataset gives one column per array
+// column, a 1-D dataset gives its row index and values, and a group gives every numeric
+// 1-D dataset directly inside it that has the same length as the first one
+#[cfg(feature = "hdf5")]
+/// Function: explain its purpose and key arguments
+pub fn read_hdf5_table(path: &Path, object_path: &str) -> Result<SheetTable, Box<dyn std::error::Error>> {
+// Variable declaration
+    let file = hdf5::File::open(path)?;
+// Variable declaration
+    let to_column = |values: Vec<f64>| values.into_iter().map(Some).collect::<Vec<_>>();
+
+    if let Ok(group) = file.group(object_path) {
+// Variable declaration
+        let mut headers = Vec::new();
+// Variable declaration
+        let mut columns: Vec<Vec<Option<f64>>> = Vec::new();
+        for dataset in group.datasets()? {
+            if dataset.ndim() != 1 {
+                continue;
+            }
+            // Skip string and compound datasets
+// Variable declaration
+            let Ok(values) = dataset.read_raw::<f64>() else {
+                continue;
+            };
+            if columns.first().is_some_and(|first| first.len() != values.len()) {
+                continue;
+            }
+// Variable declaration
+            let name = dataset.name();
+            headers.push(name.rsplit('/').next().unwrap_or(&name).to_string());
+            columns.push(to_column(values));
+        }
+        if columns.is_empty() {
+            return Err(format!("Group {} has no numeric 1-D datasets", object_path).into());
+        }
+        return Ok(SheetTable {
+            name: object_path.to_string(),
+            headers,
+            columns,
+        });
+    }
+
+// Variable declaration
+    let dataset = file.dataset(object_path)?;
+// Variable declaration
+    let shape = dataset.shape();
+// Variable declaration
+    let values = dataset.read_raw::<f64>()?;
+// Variable declaration
+    let name = object_path.rsplit('/').next().unwrap_or(object_path).to_string();
+    match *shape.as_slice() {
+        [rows] => Ok(SheetTable {
+            name: object_path.to_string(),
+            headers: vec!["index".to_string(), name],
+            columns: vec![to_column((0..rows).map(|i| i as f64).collect()), to_column(values)],
+        }),
+        [_, cols] if cols > 0 => Ok(SheetTable {
+            name: object_path.to_string(),
+            headers: (0..cols).map(|col| format!("{}[:, {}]", name, col)).collect(),
+            columns: (0..cols)
+                .map(|col| values.chunks_exact(cols).map(|row| Some(row[col])).collect())
+                .collect(),
+        }),
+        _ => Err(format!("Only 1-D and 2-D datasets can be plotted (shape {:?})", shape).into()),
+    }
+}
+
+#[cfg(not(feature = "hdf5"))]
+/// Function: explain its purpose and key arguments
+pub fn read_hdf5_tree(_path: &Path) -> Result<Hdf5Node, Box<dyn std::error::Error>> {
+    Err("CactusPlot was built without HDF5 support (rebuild with --features hdf5)".into())
+}
+
+#[cfg(not(feature = "hdf5"))]
+/// Function: explain its purpose and key arguments
+pub fn read_hdf5_table(_path: &Path, _object_path: &str) -> Result<SheetTable, Box<dyn std::error::Error>> {
+    Err("CactusPlot was built without HDF5 support (rebuild with --features hdf5)".into())
+}
+
+// Import without asking (command line): the first object, depth first, that gives at
+// least two columns, with the first column as X and the others as Y
+/// Function: explain its purpose and key arguments
+pub fn load_hdf5(path: &Path) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let tree = read_hdf5_tree(path)?;
+// Variable declaration
+    let table = tree
+        .flatten()
+        .into_iter()
+        .filter(|node| node.is_group || node.shape.len() <= 2)
+        .find_map(|node| read_hdf5_table(path, &node.path).ok().filter(|t| t.columns.len() >= 2))
+        .ok_or("No group or dataset with plottable columns")?;
+// Variable declaration
+    let y_cols: Vec<usize> = (1..table.columns.len()).collect();
+    Ok(table.series(0, &y_cols))
+}
+
+// State of the HDF5 browser: the file tree, the chosen object and its column mapping
+/// Data structure used in io.rs module
+pub struct Hdf5Import {
+    pub path: PathBuf,
+    pub tree: Hdf5Node,
+    pub table: Option<SheetTable>, // Columns of the selected group or dataset
+    pub x_col: usize,
+    pub y_cols: Vec<bool>,
+    pub error: Option<String>, // Why the last selection could not be read
+    pub subplot_index: usize,
+}
+
+/// Implementation block defining methods for this type
+impl Hdf5Import {
+/// Function: explain its purpose and key arguments
+    pub fn open(path: PathBuf, subplot_index: usize) -> Result<Self, Box<dyn std::error::Error>> {
+// Variable declaration
+        let tree = read_hdf5_tree(&path)?;
+        Ok(Self {
+            path,
+            tree,
+            table: None,
+            x_col: 0,
+            y_cols: Vec::new(),
+            error: None,
+            subplot_index,
+        })
+    }
+
+    // Read the columns of a group or dataset; defaults to first column X, second Y
+/// Function: explain its purpose and key arguments
+    pub fn select(&mut self, object_path: &str) {
+        match read_hdf5_table(&self.path, object_path) {
+            Ok(table) => {
+                self.x_col = 0;
+                self.y_cols = (0..table.columns.len()).map(|col| col == 1).collect();
+                self.table = Some(table);
+                self.error = None;
+            }
+            Err(e) => {
+                self.table = None;
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn selected_series(&self) -> Vec<LoadedSeries> {
+        self.table
+            .as_ref()
+            .map_or_else(Vec::new, |table| table.series(self.x_col, &selected_columns(&self.y_cols, self.x_col)))
+    }
+}
+
+// Indices of the checked Y columns, leaving out the X column
+/// Function: explain its purpose and key arguments
+pub fn selected_columns(y_cols: &[bool], x_col: usize) -> Vec<usize> {
+    y_cols
+        .iter()
+        .enumerate()
+        .filter(|(col, selected)| **selected && *col != x_col)
+        .map(|(col, _)| col)
+        .collect()
+}
+
+// Plain-text table formats that go through the import preview in the GUI, with the delimiter
+// sniffed from the content: .out is common for program output and .xy/.xye for two-theta
+// diffraction patterns. XVG files are not listed: their @TYPE and legend lines already
+// describe the columns.
+pub const TEXT_TABLE_EXTENSIONS: &[&str] = &["csv", "tsv", "txt", "dat", "out", "xy", "xye"];
+
+/// Function: explain its purpose and key arguments
+pub fn is_text_table(path: &Path) -> bool {
+    data_extension(path).is_some_and(|ext| TEXT_TABLE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// Lines of a text table that hold data: not blank, not a # comment and not an
+// xmgrace-style @ directive
+/// Function: explain its purpose and key arguments
+pub fn is_data_line(line: &str) -> bool {
+// Variable declaration
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#') && !line.starts_with('@')
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Enum representing a set of related values in io.rs module
+pub enum Delimiter {
+    Comma,
+    Semicolon,
+    Tab,
+    Whitespace,
+}
+
+/// Implementation block defining methods for this type
+impl Delimiter {
+    pub const ALL: [Delimiter; 4] = [Delimiter::Comma, Delimiter::Semicolon, Delimiter::Tab, Delimiter::Whitespace];
+
+/// Function: explain its purpose and key arguments
+    pub fn label(self) -> &'static str {
+        match self {
+            Delimiter::Comma => "Comma",
+            Delimiter::Semicolon => "Semicolon",
+            Delimiter::Tab => "Tab",
+            Delimiter::Whitespace => "Spaces",
+        }
+    }
+
+    // Guess from the extension and the first line that is not a comment
+/// Function: explain its purpose and key arguments
+    fn detect(path: &Path, sample: &str) -> Self {
+// Variable declaration
+        let extension = data_extension(path).unwrap_or_default();
+        if extension == "tsv" || sample.contains('\t') {
+            Delimiter::Tab
+        } else if sample.contains(';') && !sample.contains(',') {
+            Delimiter::Semicolon
+        } else if extension == "csv" || sample.contains(',') {
+            Delimiter::Comma
+        } else {
+            Delimiter::Whitespace
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn split(self, text: &str) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+// Variable declaration
+        let byte = match self {
+            Delimiter::Comma => b',',
+            Delimiter::Semicolon => b';',
+            Delimiter::Tab => b'\t',
+            Delimiter::Whitespace => {
+                return Ok(text
+                    .lines()
+                    .map(|line| line.split_whitespace().map(str::to_string).collect())
+                    .collect());
+            }
+        };
+// Variable declaration
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(byte)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+// Variable declaration
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            rows.push(record?.iter().map(|field| field.trim().to_string()).collect());
+        }
+        Ok(rows)
+    }
+}
+
+// The choices made in the import preview, kept with the datasets so the file can be
+// read the same way again when it changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Data structure used in io.rs module
+pub struct TextImportSettings {
+    pub skip_rows: usize,
+    pub delimiter: Delimiter,
+    pub has_header: bool,
+    pub x_col: usize,
+    pub y_cols: Vec<usize>,
+    #[serde(default)]
+    pub color_col: Option<usize>,
+}
+
+// State of the preview shown before a CSV or other text table is added: how to split
+// it, which rows to skip, which columns are X and Y, and what to call the dataset
+/// Data structure used in io.rs module
+pub struct TextImport {
+    pub path: PathBuf,
+    pub text: String,
+    pub skip_rows: usize, // Lines dropped from the top of the file before parsing
+    pub delimiter: Delimiter,
+    pub has_header: bool,
+    pub name: String,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>, // Data rows after skipping, splitting and the header
+    pub x_col: usize,
+    pub y_cols: Vec<bool>,
+    pub color_col: Option<usize>, // Column that colors the imported lines along a colormap
+    pub subplot_index: usize,
+    detected: Vec<SeriesColumns>, // Series and error columns found from the header
+}
+
+/// Implementation block defining methods for this type
+impl TextImport {
+/// Function: explain its purpose and key arguments
+    pub fn open(path: PathBuf, subplot_index: usize) -> Result<Self, Box<dyn std::error::Error>> {
+// Variable declaration
+        let text = read_data_to_string(&path)?;
+        Self::from_text(path, text, subplot_index)
+    }
+
+    // Same as `open` for text that was already read from `path`
+/// Function: explain its purpose and key arguments
+    pub fn from_text(path: PathBuf, text: String, subplot_index: usize) -> Result<Self, Box<dyn std::error::Error>> {
+// Variable declaration
+        let sample = text
+            .lines()
+            .find(|line| is_data_line(line))
+            .unwrap_or("")
+            .to_string();
+// Variable declaration
+        let mut import = Self {
+            delimiter: Delimiter::detect(&path, &sample),
+            name: path
+                .file_stem()
+                .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+            path,
+            text,
+            skip_rows: 0,
+            has_header: false,
+            headers: Vec::new(),
+            rows: Vec::new(),
+            x_col: 0,
+            y_cols: Vec::new(),
+            color_col: None,
+            subplot_index,
+            detected: Vec::new(),
+        };
+        import.reparse(true)?;
+        Ok(import)
+    }
+
+    // Split the file again after the skip count, delimiter or header flag changed.
+    // `detect_header` guesses the header flag: the first row is a header unless it is
+    // entirely numeric. The column mapping goes back to the automatic one.
+/// Function: explain its purpose and key arguments
+    pub fn reparse(&mut self, detect_header: bool) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+        let body: String = self
+            .text
+            .lines()
+            .skip(self.skip_rows)
+            .filter(|line| is_data_line(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+// Variable declaration
+        let mut rows = self.delimiter.split(&body)?;
+        if detect_header {
+            self.has_header = rows
+                .first()
+                .is_some_and(|first| first.iter().any(|field| parse_number_or_timestamp(field).is_none()));
+        }
+        self.headers = if self.has_header && !rows.is_empty() {
+            rows.remove(0)
+        } else {
+            Vec::new()
+        };
+        self.rows = rows;
+
+// Variable declaration
+        let num_cols = self.column_count();
+        self.detected = series_columns_from_headers(&self.headers, num_cols);
+        self.x_col = 0;
+        self.y_cols = (0..num_cols)
+            .map(|col| self.detected.iter().any(|series| series.y_col == col))
+            .collect();
+        self.color_col = None;
+        Ok(())
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn column_count(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0).max(self.headers.len())
+    }
+
+    // Header text, or "Column N" when there is no header
+/// Function: explain its purpose and key arguments
+    pub fn column_labels(&self) -> Vec<String> {
+        (0..self.column_count())
+            .map(|col| match self.headers.get(col) {
+                Some(header) if !header.is_empty() => header.clone(),
+                _ => format!("Column {}", col + 1),
+            })
+            .collect()
+    }
+
+    // Checked Y columns; keep the error columns found from the header while X is still the
+    // first column and the error column itself is not plotted
+/// Function: explain its purpose and key arguments
+    pub fn series_columns(&self) -> Vec<SeriesColumns> {
+// Variable declaration
+        let plotted = |col: Option<usize>| {
+            col.filter(|&c| c != self.x_col && !self.y_cols.get(c).copied().unwrap_or(false))
+        };
+        selected_columns(&self.y_cols, self.x_col)
+            .into_iter()
+            .map(|col| {
+// Variable declaration
+                let mut series = SeriesColumns::new(col, self.headers.get(col).cloned());
+                if let (0, Some(found)) = (self.x_col, self.detected.iter().find(|s| s.y_col == col)) {
+                    series.x_err_col = plotted(found.x_err_col);
+                    series.y_err_col = plotted(found.y_err_col);
+                }
+                series.color_col = self.color_col;
+                series
+            })
+            .collect()
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn selected_series(&self) -> Vec<LoadedSeries> {
+        collect_series(&self.row_cells(), self.x_col, &self.series_columns())
+    }
+
+    // What the chosen columns tell about the plot: whether X holds dates and times
+/// Function: explain its purpose and key arguments
+    pub fn metadata(&self) -> FileMetadata {
+        FileMetadata {
+            x_is_time: column_has_timestamps(&self.row_cells(), self.x_col),
+            ..FileMetadata::default()
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn row_cells(&self) -> Vec<Vec<&str>> {
+        self.rows.iter().map(|row| row.iter().map(String::as_str).collect()).collect()
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn settings(&self) -> TextImportSettings {
+        TextImportSettings {
+            skip_rows: self.skip_rows,
+            delimiter: self.delimiter,
+            has_header: self.has_header,
+            x_col: self.x_col,
+            y_cols: selected_columns(&self.y_cols, self.x_col),
+            color_col: self.color_col,
+        }
+    }
+
+    // Read a file again with the choices made when it was first imported
+/// Function: explain its purpose and key arguments
+    pub fn reload(path: &Path, settings: &TextImportSettings) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+        let text = read_data_to_string(path)?;
+        Ok(Self::with_settings(path, text, settings)?.selected_series())
+    }
+
+    // Parse `text` with the choices made when `path` was first imported
+/// Function: explain its purpose and key arguments
+    pub fn with_settings(path: &Path, text: String, settings: &TextImportSettings) -> Result<Self, Box<dyn std::error::Error>> {
+// Variable declaration
+        let mut import = Self::from_text(path.to_path_buf(), text, 0)?;
+        import.skip_rows = settings.skip_rows;
+        import.delimiter = settings.delimiter;
+        import.has_header = settings.has_header;
+        import.reparse(false)?;
+        import.x_col = settings.x_col;
+        import.y_cols = (0..import.column_count()).map(|col| settings.y_cols.contains(&col)).collect();
+        import.color_col = settings.color_col.filter(|col| *col < import.column_count());
+        Ok(import)
+    }
+
+    // The whole table as a matrix of values. When the header row holds numbers they are
+    // the X coordinates and the first column holds the Y coordinates; otherwise columns
+    // and rows are numbered from 0 (the first row at Y = 0).
+/// Function: explain its purpose and key arguments
+    pub fn to_heatmap(&self) -> Result<Heatmap, Box<dyn std::error::Error>> {
+// Variable declaration
+        let parse = |cell: &str| cell.trim().parse::<f64>().ok();
+// Variable declaration
+        let header_x = if self.has_header {
+            self.headers
+                .iter()
+                .skip(1)
+                .map(|header| parse(header))
+                .collect::<Option<Vec<f64>>>()
+                .filter(|x| !x.is_empty())
+        } else {
+            None
+        };
+// Variable declaration
+        let (x, first_value_col) = match header_x {
+            Some(x) => (x, 1),
+            None => ((0..self.column_count()).map(|col| col as f64).collect(), 0),
+        };
+
+// Variable declaration
+        let mut y = Vec::new();
+// Variable declaration
+        let mut values = Vec::new();
+        for (index, row) in self.rows.iter().filter(|row| row.iter().any(|cell| !cell.trim().is_empty())).enumerate() {
+            if first_value_col == 1 {
+                y.push(
+                    row.first()
+                        .and_then(|cell| parse(cell))
+                        .ok_or_else(|| format!("Row {} has no Y coordinate in its first column", index + 1))?,
+                );
+            } else {
+                y.push(index as f64);
+            }
+            values.extend(
+                (0..x.len()).map(|col| row.get(first_value_col + col).and_then(|cell| parse(cell)).unwrap_or(f64::NAN)),
+            );
+        }
+// Variable declaration
+        let name = if self.name.trim().is_empty() {
+            file_display_name(&self.path)
+        } else {
+            self.name.trim().to_string()
+        };
+        Heatmap::new(name, x, y, values)
+    }
+}
+
+// Import without asking (command line): the same columns the preview starts with
+// A matrix for the heatmap view: a GROMACS .xpm image or a text table of values
+/// Function: explain its purpose and key arguments
+pub fn load_heatmap(path: &Path) -> Result<(Heatmap, FileMetadata), Box<dyn std::error::Error>> {
+    if is_xpm(path) {
+        return load_xpm(path);
+    }
+// Variable declaration
+    let heatmap = TextImport::open(path.to_path_buf(), 0)?.to_heatmap()?;
+    Ok((heatmap, FileMetadata::default()))
+}
+
+/// Function: explain its purpose and key arguments
+pub fn is_xpm(path: &Path) -> bool {
+    data_extension(path).is_some_and(|ext| ext == "xpm")
+}
+
+// Text of the first "..." in `text`
+/// Function: explain its purpose and key arguments
+fn first_quoted(text: &str) -> Option<&str> {
+// Variable declaration
+    let start = text.find('"')? + 1;
+// Variable declaration
+    let end = start + text[start..].find('"')?;
+    Some(&text[start..end])
+}
+
+// GROMACS matrix image (gmx sham, gmx mdmat, ...). Each color entry carries its value in a
+// trailing comment, e.g. "A  c #FFFFFF " /* "0" */, and the axes are listed in
+// /* x-axis: ... */ comments. Pixel rows run from the highest Y down.
+/// Function: explain its purpose and key arguments
+pub fn load_xpm(path: &Path) -> Result<(Heatmap, FileMetadata), Box<dyn std::error::Error>> {
+// Variable declaration
+    let text = read_data_to_string(path)?;
+// Variable declaration
+    let mut metadata = FileMetadata::default();
+// Variable declaration
+    let mut legend = String::new();
+// Variable declaration
+    let mut x_axis: Vec<f64> = Vec::new();
+// Variable declaration
+    let mut y_axis: Vec<f64> = Vec::new();
+// Variable declaration
+    let mut strings: Vec<(&str, &str)> = Vec::new(); // (quoted text, rest of the line)
+
+    for line in text.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix("/*") {
+// Variable declaration
+            let comment = comment.trim_end_matches("*/").trim();
+// Variable declaration
+            let Some((key, value)) = comment.split_once(':') else {
+                continue;
+            };
+// Variable declaration
+            let quoted = || first_quoted(value).map(str::to_string);
+            match key.trim() {
+                "title" => metadata.title = quoted(),
+                "x-label" => metadata.x_label = quoted(),
+                "y-label" => metadata.y_label = quoted(),
+                "legend" => legend = quoted().unwrap_or_default(),
+                "x-axis" => x_axis.extend(value.split_whitespace().filter_map(|v| v.parse::<f64>().ok())),
+                "y-axis" => y_axis.extend(value.split_whitespace().filter_map(|v| v.parse::<f64>().ok())),
+                _ => {}
+            }
+        } else if line.starts_with('"') {
+// Variable declaration
+            let content = first_quoted(line).ok_or("Unterminated string")?;
+            strings.push((content, &line[content.len() + 2..]));
+        }
+    }
+
+// Variable declaration
+    let (header, rest) = strings.split_first().ok_or("No XPM image data")?;
+// Variable declaration
+    let numbers: Vec<usize> = header.0.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+    // The header may end with a hotspot (x_hot y_hot), which a heatmap has no use for
+// Variable declaration
+    let ([cols, rows, color_count, chars_per_pixel] | [cols, rows, color_count, chars_per_pixel, _, _]) = numbers[..] else {
+        return Err(format!("Unexpected XPM header \"{}\"", header.0).into());
+    };
+    if rest.len() < color_count + rows || chars_per_pixel == 0 {
+        return Err("XPM image is shorter than its header says".into());
+    }
+
+    // Symbol -> value, from the comment after each color (its index when there is none)
+// Variable declaration
+    let colors: std::collections::HashMap<&str, f64> = rest[..color_count]
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (entry, comment))| {
+// Variable declaration
+            let symbol = entry.get(..chars_per_pixel)?;
+// Variable declaration
+            let value = first_quoted(comment).and_then(|v| v.trim().parse().ok()).unwrap_or(index as f64);
+            Some((symbol, value))
+        })
+        .collect();
+
+// Variable declaration
+    let mut values = vec![f64::NAN; rows * cols];
+    for (row, (pixels, _)) in rest[color_count..color_count + rows].iter().enumerate() {
+        // The first row in the file is the top one
+// Variable declaration
+        let target = rows - 1 - row;
+        for col in 0..cols {
+            if let Some(&value) = pixels
+                .get(col * chars_per_pixel..(col + 1) * chars_per_pixel)
+                .and_then(|symbol| colors.get(symbol))
+            {
+                values[target * cols + col] = value;
+            }
+        }
+    }
+
+    // Axes may list cell edges (one more value than cells) instead of centres
+// Variable declaration
+    let centres = |axis: Vec<f64>, count: usize| -> Vec<f64> {
+        if axis.len() == count + 1 {
+            axis.windows(2).map(|pair| (pair[0] + pair[1]) / 2.0).collect()
+        } else if axis.len() == count {
+            axis
+        } else {
+            (0..count).map(|i| i as f64).collect()
+        }
+    };
+// Variable declaration
+    let mut heatmap = Heatmap::new(
+        file_display_name(path),
+        centres(x_axis, cols),
+        centres(y_axis, rows),
+        values,
+    )?;
+    heatmap.label = legend;
+    Ok((heatmap, metadata))
+}
+
+/// Function: explain its purpose and key arguments
+pub fn load_text_table(path: &Path) -> Result<LoadedFile, Box<dyn std::error::Error>> {
+// Variable declaration
+    let import = TextImport::open(path.to_path_buf(), 0)?;
+    if import.column_count() < 2 {
+        return Err("Need at least two columns (X and Y)".into());
+    }
+    Ok(LoadedFile {
+        series: import.selected_series(),
+        metadata: import.metadata(),
+    })
+}
@@ -0,0 +1,171 @@
+// Import external modules or crates needed in loader.rs
+use crate::compression::check_readable;
+// Import external modules or crates needed in loader.rs
+use crate::heatmap::Heatmap;
+// Import external modules or crates needed in loader.rs
+use crate::io::{is_hdf5, is_parquet, is_spreadsheet, is_text_table, is_xpm, load_xpm, Hdf5Import, SpreadsheetImport, TextImport};
+// Import external modules or crates needed in loader.rs
+use crate::remote::{fetch, CACHE_MAX_AGE};
+// Import external modules or crates needed in loader.rs
+use crate::utils::{load_data_file, FileMetadata, LoadedFile};
+// Import external modules or crates needed in loader.rs
+use std::path::PathBuf;
+// Import external modules or crates needed in loader.rs
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+// Import external modules or crates needed in loader.rs
+use std::sync::{Arc, Mutex};
+
+// What reading a file opened in the GUI produced
+/// Enum representing a set of related values in loader.rs module
+pub enum LoadedContent {
+    Datasets(PathBuf, LoadedFile),  // Series added to the subplot straight away, with the file read
+    Heatmap(Heatmap, FileMetadata), // GROMACS matrix
+    Spreadsheet(SpreadsheetImport), // Workbook or Parquet file waiting for sheet and column choice
+    Hdf5(Hdf5Import),               // HDF5 file waiting for a group or dataset to be picked
+    Text(TextImport),               // Text table waiting in the import preview
+}
+
+// A file being read on a worker thread, so opening a large file leaves the window
+// responsive. The UI polls it once per frame and shows a spinner until it is done.
+/// Data structure used in loader.rs module
+pub struct PendingLoad {
+    pub path: PathBuf,
+    pub subplot_index: usize, // Subplot that receives the data
+    result: Receiver<Result<LoadedContent, String>>,
+    outcome: Option<Result<LoadedContent, String>>, // Received but not yet taken
+}
+
+/// Implementation block defining methods for this type
+impl PendingLoad {
+/// Function: explain its purpose and key arguments
+    pub fn spawn(path: PathBuf, subplot_index: usize) -> Self {
+// Variable declaration
+        let thread_path = path.clone();
+        Self::run(path, subplot_index, move || read_file(thread_path, subplot_index))
+    }
+
+    // Read a batch of files, each into the given subplot, straight into datasets (or
+    // heatmaps), without the import dialogs that text tables, workbooks and HDF5 files
+    // would otherwise open; used for whole folders. One worker per core takes the files
+    // in turn, so a large folder does not start a thread for every file.
+/// Function: explain its purpose and key arguments
+    pub fn spawn_direct_batch(loads: Vec<(PathBuf, usize)>) -> Vec<Self> {
+// Variable declaration
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(loads.len());
+// Variable declaration
+        let (job_sender, jobs) = channel::<(PathBuf, Sender<Result<LoadedContent, String>>)>();
+// Variable declaration
+        let pending = loads
+            .into_iter()
+            .map(|(path, subplot_index)| {
+// Variable declaration
+                let (sender, result) = channel();
+                // The queue outlives this loop, so sending cannot fail
+                let _ = job_sender.send((path.clone(), sender));
+                Self {
+                    path,
+                    subplot_index,
+                    result,
+                    outcome: None,
+                }
+            })
+            .collect();
+        // With the sender gone, workers stop once the queue is empty
+        drop(job_sender);
+// Variable declaration
+        let jobs = Arc::new(Mutex::new(jobs));
+        for _ in 0..workers {
+// Variable declaration
+            let jobs = Arc::clone(&jobs);
+            std::thread::spawn(move || loop {
+                // The queue is only locked while taking a file, not while reading it
+// Variable declaration
+                let job = jobs.lock().ok().and_then(|jobs| jobs.recv().ok());
+// Variable declaration
+                let Some((path, sender)) = job else {
+                    break;
+                };
+                let _ = sender.send(read_file_direct(path).map_err(|e| e.to_string()));
+            });
+        }
+        pending
+    }
+
+    // Download the file at `url` (or take the cached copy unless `refresh`) and read it
+    // like a file opened from disk; `path` is then the URL itself
+/// Function: explain its purpose and key arguments
+    pub fn spawn_url(url: String, subplot_index: usize, refresh: bool) -> Self {
+        Self::run(PathBuf::from(&url), subplot_index, move || {
+            read_file(fetch(&url, (!refresh).then_some(CACHE_MAX_AGE))?, subplot_index)
+        })
+    }
+
+/// Function: explain its purpose and key arguments
+    fn run(
+        path: PathBuf,
+        subplot_index: usize,
+        read: impl FnOnce() -> Result<LoadedContent, Box<dyn std::error::Error>> + Send + 'static,
+    ) -> Self {
+// Variable declaration
+        let (sender, result) = channel();
+        std::thread::spawn(move || {
+            // Nobody is left to receive the result if the window was closed meanwhile
+            let _ = sender.send(read().map_err(|e| e.to_string()));
+        });
+        Self {
+            path,
+            subplot_index,
+            result,
+            outcome: None,
+        }
+    }
+
+    // Whether the file has been read; the outcome is kept until `into_outcome`
+/// Function: explain its purpose and key arguments
+    pub fn is_done(&mut self) -> bool {
+        if self.outcome.is_none() {
+            self.outcome = match self.result.try_recv() {
+                Ok(result) => Some(result),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err("reading stopped unexpectedly".to_string())),
+            };
+        }
+        self.outcome.is_some()
+    }
+
+    // The outcome of a load for which `is_done` returned true
+/// Function: explain its purpose and key arguments
+    pub fn into_outcome(self) -> Result<LoadedContent, String> {
+        self.outcome.unwrap_or_else(|| Err("the file is still being read".to_string()))
+    }
+}
+
+// Read a file the way its type calls for: files that need choices made first open their
+// import dialog, everything else becomes datasets or a heatmap
+/// Function: explain its purpose and key arguments
+fn read_file(path: PathBuf, subplot_index: usize) -> Result<LoadedContent, Box<dyn std::error::Error>> {
+    check_readable(&path)?;
+    if is_spreadsheet(&path) || is_parquet(&path) {
+        return Ok(LoadedContent::Spreadsheet(SpreadsheetImport::open(path, subplot_index)?));
+    }
+    if is_text_table(&path) {
+        return Ok(LoadedContent::Text(TextImport::open(path, subplot_index)?));
+    }
+    if is_hdf5(&path) {
+        return Ok(LoadedContent::Hdf5(Hdf5Import::open(path, subplot_index)?));
+    }
+    read_file_direct(path)
+}
+
+// Read a file as datasets, or as a heatmap for GROMACS matrices
+/// Function: explain its purpose and key arguments
+fn read_file_direct(path: PathBuf) -> Result<LoadedContent, Box<dyn std::error::Error>> {
+    if is_xpm(&path) {
+// Variable declaration
+        let (heatmap, metadata) = load_xpm(&path)?;
+        return Ok(LoadedContent::Heatmap(heatmap, metadata));
+    }
+// Variable declaration
+    let loaded = load_data_file(&path)?;
+    Ok(LoadedContent::Datasets(path, loaded))
+}
@@ -3,77 +3,334 @@ mod args;
 // Declare a submodule in main.rs
 mod dataset;
 // Declare a submodule in main.rs
+mod points;
+// Declare a submodule in main.rs
+mod heatmap;
+// Declare a submodule in main.rs
 mod app;
 // Declare a submodule in main.rs
+mod config;
+// Declare a submodule in main.rs
 mod utils;
 // Declare a submodule in main.rs
 mod data_editor;
+// Declare a submodule in main.rs
+mod session;
+// Declare a submodule in main.rs
+mod history;
+// Declare a submodule in main.rs
+mod display_cache;
+// Declare a submodule in main.rs
+mod diagnostics;
+// Declare a submodule in main.rs
+mod loader;
+// Declare a submodule in main.rs
+mod export_job;
+// Declare a submodule in main.rs
+mod notifications;
+// Declare a submodule in main.rs
+mod folder;
+// Declare a submodule in main.rs
+mod remote;
+// Declare a submodule in main.rs
+mod expression;
+// Declare a submodule in main.rs
+mod annotation;
+// Declare a submodule in main.rs
+mod inset;
+// Declare a submodule in main.rs
+mod io;
+// Declare a submodule in main.rs
+mod compression;
+// Declare a submodule in main.rs
+mod palette;
+// Declare a submodule in main.rs
+mod units;
+// Declare a submodule in main.rs
+mod timestamps;
+// Declare a submodule in main.rs
+mod command_palette;
+// Declare a submodule in main.rs
+mod pdf_export;
+// Declare a submodule in main.rs
+mod script_export;
+// Declare a submodule in main.rs
+mod templates;
+// Declare a submodule in main.rs
+mod watch;
+// Declare a submodule in main.rs
+mod stdin;
+// Declare a submodule in main.rs
+mod fit_report;
 
 // Import external modules or crates needed in main.rs
 use clap::Parser;
 // Import external modules or crates needed in main.rs
-use args::Args;
+use args::{Args, SeriesSpec};
 // Import external modules or crates needed in main.rs
 use app::PlotterApp;
 // Import external modules or crates needed in main.rs
-use dataset::Dataset;
+use utils::{load_data_file, datasets_from_series, export_subplots_to_path, LoadedSeries};
+// Import external modules or crates needed in main.rs
+use app::SubplotLayout;
 // Import external modules or crates needed in main.rs
-use utils::{load_csv_points, load_xvg_points, get_default_color};
+use io::{is_text_table, TextImport, TextImportSettings};
 // Import external modules or crates needed in main.rs
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+// Build the initial app state from command line args (shared by GUI and headless export)
+/// Function: explain its purpose and key arguments
+fn build_app_from_args(args: &Args) -> PlotterApp {
+    let mut app = PlotterApp::default();
+
+    // Saved preferences replace the built-in defaults
+    match config::load_preferences() {
+        Ok(preferences) => preferences.apply_to(&mut app),
+        Err(e) => eprintln!("Failed to read preferences: {}", e),
+    }
+
+    // A broken config file was already reported above
+    if let Ok(recent) = config::load_recent_files() {
+        app.recent_files = recent;
+    }
+
+    // Restore a saved session first so files given on the command line are added on top
+    if let Some(session_file) = &args.session {
+        match session::load_session(&PathBuf::from(session_file)) {
+            Ok(session) => session.apply_to(&mut app),
+            Err(e) => eprintln!("Failed to load session {}: {}", session_file, e),
+        }
+    }
+
+    if let Some(layout) = &args.layout {
+        app.subplot_layout = layout.clone();
+        app.ensure_subplots_match_layout();
+    }
+
+    // Set grid and legend visibility based on command line args
+    if let Some(active_subplot) = app.get_active_subplot_mut() {
+        active_subplot.config.show_legend = !args.no_legend;
+        active_subplot.config.show_grid = args.grid;
+    }
+
+    // Files opened last time come first, so the ones given now are drawn on top
+    let reopened: Vec<String> = if args.reopen_last {
+        app.recent_files.last_opened.iter().map(|path| path.display().to_string()).collect()
+    } else {
+        Vec::new()
+    };
+
+    // Load files into the active subplot
+    for file in reopened.iter().chain(&args.files) {
+        // Piped data is read separately, all at once or as it arrives (see main)
+        if file == "-" {
+            continue;
+        }
+        let active = app.active_subplot;
+        load_file(&mut app, Path::new(file), file, active);
+    }
+
+    // Web addresses are downloaded (or taken from the cache) and named after the address
+    for url in &args.urls {
+        match remote::fetch(url, Some(remote::CACHE_MAX_AGE)) {
+            Ok(path) => {
+                let active = app.active_subplot;
+                load_file(&mut app, &path, url, active);
+            }
+            Err(e) => eprintln!("Failed to download {}: {}", url, e),
+        }
+    }
+
+    if let Some(glob) = &args.glob {
+        match folder::glob_files(glob, args.recursive) {
+            Ok(paths) => {
+                let wanted = folder::plan_subplots(&paths, args.assign, &args.groups, app.active_subplot);
+                let needed = wanted.iter().max().map_or(0, |last| last + 1);
+                if needed > app.subplots.len() {
+                    if let Some(layout) = SubplotLayout::fitting(needed) {
+                        app.subplot_layout = layout;
+                        app.ensure_subplots_match_layout();
+                    }
+                }
+                // Beyond the largest layout the files wrap around to the first subplots
+                let subplot_count = app.subplots.len();
+                for (path, subplot_index) in paths.iter().zip(wanted) {
+                    load_file(&mut app, path, &path.display().to_string(), subplot_index % subplot_count);
+                }
+            }
+            Err(e) => eprintln!("Failed to load {}: {}", glob, e),
+        }
+    }
+
+    for file in &args.heatmaps {
+        let active = app.active_subplot;
+        load_heatmap_file(&mut app, file, active);
+    }
+
+    // Files with their own name, color, subplot or columns
+    for spec in &args.series {
+        if let Err(e) = load_series_spec(&mut app, spec) {
+            eprintln!("Failed to load {}: {}", spec.file, e);
+        }
+    }
+
+    for subplot in &mut app.subplots {
+        args.apply_plot_options(subplot);
+    }
+
+    app
+}
+
+// Add a data file given on the command line to a subplot, naming its datasets `name`
+/// Function: explain its purpose and key arguments
+fn load_file(app: &mut PlotterApp, path: &Path, name: &str, subplot_index: usize) {
+    // GROMACS matrices are always heatmaps
+    if io::is_xpm(path) {
+        load_heatmap_file(app, &path.display().to_string(), subplot_index);
+        return;
+    }
+
+    // Determine file type and load accordingly
+    match load_data_file(path) {
+        Ok(loaded) => {
+            let first_color = app.subplots.get(subplot_index).map_or(0, |s| s.datasets.len());
+            let palette = app.subplot_palette(subplot_index);
+            if let Some(subplot) = app.subplots.get_mut(subplot_index) {
+                loaded.metadata.apply_to(&mut subplot.config);
+                for warning in &loaded.metadata.warnings {
+                    eprintln!("{}: {}", name, warning);
+                }
+                let mut datasets = datasets_from_series(loaded.series, name, palette, first_color);
+                watch::set_sources(&mut datasets, path, None);
+                subplot.datasets.extend(datasets);
+                app.next_name_index += 1;
+            }
+        }
+        Err(e) => eprintln!("Failed to load {}: {}", name, e),
+    }
+}
+
+// Add a matrix file to a subplot as a heatmap
+/// Function: explain its purpose and key arguments
+fn load_heatmap_file(app: &mut PlotterApp, file: &str, subplot_index: usize) {
+    match io::load_heatmap(Path::new(file)) {
+        Ok((heatmap, metadata)) => {
+            if let Some(subplot) = app.subplots.get_mut(subplot_index) {
+                metadata.apply_to(&mut subplot.config);
+                subplot.heatmaps.push(heatmap);
+            }
+        }
+        Err(e) => eprintln!("Failed to load {}: {}", file, e),
+    }
+}
+
+// Load one --series file into its subplot, growing the layout if the subplot does not exist yet
+/// Function: explain its purpose and key arguments
+fn load_series_spec(app: &mut PlotterApp, spec: &SeriesSpec) -> Result<(), Box<dyn std::error::Error>> {
+    let path = PathBuf::from(&spec.file);
+    let (series, metadata, text_import) = match &spec.columns {
+        Some(columns) => {
+            let (series, settings) = load_text_columns(&path, columns)?;
+            (series, None, Some(settings))
+        }
+        None => {
+            let loaded = load_data_file(&path)?;
+            for warning in &loaded.metadata.warnings {
+                eprintln!("{}: {}", spec.file, warning);
+            }
+            (loaded.series, Some(loaded.metadata), None)
+        }
+    };
+
+    let subplot_index = spec.subplot.map_or(app.active_subplot, |n| n - 1);
+    if subplot_index >= app.subplots.len() {
+        app.subplot_layout = SubplotLayout::fitting(subplot_index + 1)
+            .ok_or_else(|| format!("subplot {} is beyond the largest layout", subplot_index + 1))?;
+        app.ensure_subplots_match_layout();
+    }
+
+    let palette = app.subplot_palette(subplot_index);
+    let subplot = &mut app.subplots[subplot_index];
+    if let Some(metadata) = metadata {
+        metadata.apply_to(&mut subplot.config);
+    }
+    let name = spec.name.clone().unwrap_or_else(|| spec.file.clone());
+    let mut datasets = datasets_from_series(series, &name, palette, subplot.datasets.len());
+    watch::set_sources(&mut datasets, &path, text_import);
+    if let Some(color) = spec.color {
+        for dataset in &mut datasets {
+            dataset.color = color;
+        }
+    }
+    subplot.datasets.extend(datasets);
+    app.next_name_index += 1;
+    Ok(())
+}
+
+// Chosen columns of a text table, read the way the import preview would read them
+/// Function: explain its purpose and key arguments
+fn load_text_columns(
+    path: &Path,
+    (x_col, y_cols): &(usize, Vec<usize>),
+) -> Result<(Vec<LoadedSeries>, TextImportSettings), Box<dyn std::error::Error>> {
+    let is_xvg = compression::data_extension(path).as_deref() == Some("xvg");
+    if !is_text_table(path) && !is_xvg {
+        return Err("columns= only applies to text files (csv, tsv, txt, dat, out, xy, xye, xvg)".into());
+    }
+    let mut import = TextImport::open(path.to_path_buf(), 0)?;
+    let width = import.column_count();
+    if let Some(col) = std::iter::once(x_col).chain(y_cols).find(|col| **col >= width) {
+        return Err(format!("column {} does not exist (the file has {} columns)", col, width).into());
+    }
+    import.x_col = *x_col;
+    import.y_cols = (0..width).map(|col| y_cols.contains(&col)).collect();
+    Ok((import.selected_series(), import.settings()))
+}
 
 /// Function: explain its purpose and key arguments
 fn main() {
     let args = Args::parse();
+
+    // Headless mode: render straight to a file without creating a window
+    if let Some(export_path) = &args.export {
+        let mut app = build_app_from_args(&args);
+        if let Some(format) = args.stdin_format() {
+            match stdin::read_stdin(format) {
+                Ok(loaded) => app.add_stdin_datasets(loaded),
+                Err(e) => eprintln!("Failed to read standard input: {}", e),
+            }
+        }
+        match export_subplots_to_path(
+            &PathBuf::from(export_path),
+            &app.subplots,
+            &app.subplot_layout,
+            app.dark_mode,
+            &app.tick_font_size,
+            &app.export_settings,
+        ) {
+            Ok(()) => println!("Subplots exported as: {}", export_path),
+            Err(e) => {
+                eprintln!("Failed to export {}: {}", export_path, e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     let mut options = eframe::NativeOptions::default();
     options.default_theme = eframe::Theme::Light;
 
     eframe::run_native(
         "CactusPlot",
         options,
-        Box::new(move |_cc| {
-            let mut app = PlotterApp::default();
-            
-            // Set grid and legend visibility based on command line args
-            if let Some(active_subplot) = app.get_active_subplot_mut() {
-                active_subplot.config.show_legend = !args.no_legend;
-                active_subplot.config.show_grid = args.grid;
-            }
-
-            // Load files into the active subplot
-            for file in args.files {
-                let path = PathBuf::from(&file);
-                
-                // Determine file type and load accordingly
-                let load_result = match path.extension().and_then(|ext| ext.to_str()) {
-                    Some("csv") => load_csv_points(&path).map(|points| (points, file.clone())),
-                    Some("xvg") => load_xvg_points(&path).map(|points| (points, file.clone())),
-                    _ => {
-                        eprintln!("Unsupported file type: {}", file);
-                        continue;
-                    }
-                };
-                
-                if let Ok((points, filename)) = load_result {
-                    let color = get_default_color(
-                        app.get_active_subplot().map_or(0, |s| s.datasets.len()) % 8
-                    );
-                    
-                    if let Some(subplot) = app.get_active_subplot_mut() {
-                        subplot.datasets.push(Dataset {
-                            name: filename,
-                            points,
-                            color,
-                        });
-                        app.next_name_index += 1;
-                    }
-                } else if let Err(e) = load_result {
-                    eprintln!("Failed to load {}: {}", file, e);
-                }
-            }
-
+        Box::new(move |cc| {
+// Variable declaration
+            let mut app = build_app_from_args(&args);
+            app.stdin_stream = args
+                .stdin_format()
+                .map(|format| stdin::StdinStream::spawn(format, cc.egui_ctx.clone()));
             Box::new(app)
         }),
     )
     .unwrap();
-}
\ No newline at end of file
+}
@@ -0,0 +1,150 @@
+// Import external modules or crates needed in notifications.rs
+use eframe::egui;
+// Import external modules or crates needed in notifications.rs
+use std::collections::VecDeque;
+// Import external modules or crates needed in notifications.rs
+use std::time::{Duration, Instant};
+
+// Most toasts on screen at once; older ones make way for new ones
+const MAX_VISIBLE: usize = 5;
+
+// How much a message matters, which sets its color and how long it stays
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in notifications.rs module
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Implementation block defining methods for this type
+impl Level {
+    // Errors and warnings stay up longer so there is time to read what went wrong
+/// Function: explain its purpose and key arguments
+    fn duration(self) -> Duration {
+        match self {
+            Level::Info | Level::Success => Duration::from_secs(4),
+            Level::Warning => Duration::from_secs(6),
+            Level::Error => Duration::from_secs(10),
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn color(self, dark_mode: bool) -> egui::Color32 {
+        match (self, dark_mode) {
+            (Level::Info, _) => egui::Color32::from_rgb(90, 150, 230),
+            (Level::Success, true) => egui::Color32::from_rgb(90, 200, 110),
+            (Level::Success, false) => egui::Color32::from_rgb(30, 140, 60),
+            (Level::Warning, true) => egui::Color32::from_rgb(240, 190, 60),
+            (Level::Warning, false) => egui::Color32::from_rgb(190, 130, 0),
+            (Level::Error, _) => egui::Color32::from_rgb(220, 60, 60),
+        }
+    }
+}
+
+/// Data structure used in notifications.rs module
+struct Toast {
+    level: Level,
+    text: String,
+    expires: Instant,
+}
+
+// Messages about what just happened (files loaded, exports written, things that failed),
+// shown as toasts stacked in the top-right corner that go away by themselves or when
+// clicked, so several outcomes in a row do not overwrite each other
+#[derive(Default)]
+/// Data structure used in notifications.rs module
+pub struct Notifications {
+    toasts: VecDeque<Toast>,
+    last: Option<(Level, String)>, // Kept after its toast is gone, for the status bar
+}
+
+/// Implementation block defining methods for this type
+impl Notifications {
+/// Function: explain its purpose and key arguments
+    pub fn push(&mut self, level: Level, text: impl Into<String>) {
+// Variable declaration
+        let text = text.into();
+        self.last = Some((level, text.clone()));
+        self.toasts.push_back(Toast {
+            level,
+            text,
+            expires: Instant::now() + level.duration(),
+        });
+        while self.toasts.len() > MAX_VISIBLE {
+            self.toasts.pop_front();
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text);
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.push(Level::Success, text);
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.push(Level::Warning, text);
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text);
+    }
+
+    // The most recent message, whether or not its toast is still up
+/// Function: explain its purpose and key arguments
+    pub fn last(&self) -> Option<(Level, &str)> {
+        self.last.as_ref().map(|(level, text)| (*level, text.as_str()))
+    }
+
+    // Drop expired toasts and draw the rest, newest at the bottom, above everything else
+/// Function: explain its purpose and key arguments
+    pub fn show(&mut self, ctx: &egui::Context) {
+// Variable declaration
+        let now = Instant::now();
+        self.toasts.retain(|toast| toast.expires > now);
+// Variable declaration
+        let Some(next_expiry) = self.toasts.iter().map(|toast| toast.expires).min() else {
+            return;
+        };
+        // Wake up again to take the next toast down, even if nothing else happens until then
+        ctx.request_repaint_after(next_expiry - now);
+
+// Variable declaration
+        let dark_mode = ctx.style().visuals.dark_mode;
+// Variable declaration
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-12.0, 40.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(360.0);
+                for (i, toast) in self.toasts.iter().enumerate() {
+// Variable declaration
+                    let color = toast.level.color(dark_mode);
+// Variable declaration
+                    let response = egui::Frame::popup(ui.style())
+                        .stroke(egui::Stroke::new(1.5, color))
+                        .show(ui, |ui| {
+                            ui.colored_label(color, &toast.text);
+                        })
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text("Click to dismiss");
+                    if response.clicked() {
+                        dismissed = Some(i);
+                    }
+                    ui.add_space(4.0);
+                }
+            });
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Import external modules or crates needed in palette.rs
+use serde::{Deserialize, Serialize};
+
+// Named color cycles for new datasets. The app has a global palette; a subplot can
+// override it. Datasets keep the color they were given, so export matches the GUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in palette.rs module
+pub enum Palette {
+    #[default]
+    Tab10,
+    Viridis,
+    OkabeIto,
+    Grayscale,
+}
+
+// matplotlib's default cycle
+const TAB10: [[u8; 3]; 10] = [
+    [31, 119, 180],  // Blue
+    [255, 127, 14],  // Orange
+    [44, 160, 44],   // Green
+    [214, 39, 40],   // Red
+    [148, 103, 189], // Purple
+    [140, 86, 75],   // Brown
+    [227, 119, 194], // Pink
+    [127, 127, 127], // Gray
+    [188, 189, 34],  // Olive
+    [23, 190, 207],  // Cyan
+];
+
+// Eight evenly spaced samples of the viridis colormap, dark purple to yellow
+const VIRIDIS: [[u8; 3]; 8] = [
+    [68, 1, 84],
+    [70, 50, 126],
+    [54, 92, 141],
+    [39, 127, 142],
+    [31, 161, 135],
+    [74, 193, 109],
+    [160, 218, 57],
+    [253, 231, 37],
+];
+
+// Okabe & Ito (2008), distinguishable with the common forms of color blindness.
+// Black comes last so the first series stay visible on the dark theme.
+const OKABE_ITO: [[u8; 3]; 8] = [
+    [230, 159, 0],   // Orange
+    [86, 180, 233],  // Sky blue
+    [0, 158, 115],   // Bluish green
+    [240, 228, 66],  // Yellow
+    [0, 114, 178],   // Blue
+    [213, 94, 0],    // Vermillion
+    [204, 121, 167], // Reddish purple
+    [0, 0, 0],       // Black
+];
+
+// Mid-range grays only, so every series shows on both the dark and the light theme
+const GRAYSCALE: [[u8; 3]; 5] = [
+    [60, 60, 60],
+    [100, 100, 100],
+    [140, 140, 140],
+    [180, 180, 180],
+    [215, 215, 215],
+];
+
+/// Implementation block defining methods for this type
+impl Palette {
+    pub const ALL: [Palette; 4] = [Palette::Tab10, Palette::Viridis, Palette::OkabeIto, Palette::Grayscale];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Palette::Tab10 => "Tab10",
+            Palette::Viridis => "Viridis",
+            Palette::OkabeIto => "Okabe-Ito (colorblind-safe)",
+            Palette::Grayscale => "Grayscale",
+        }
+    }
+
+    // Short name used in the config file
+/// Function: explain its purpose and key arguments
+    pub fn key(self) -> &'static str {
+        match self {
+            Palette::Tab10 => "tab10",
+            Palette::Viridis => "viridis",
+            Palette::OkabeIto => "okabe-ito",
+            Palette::Grayscale => "grayscale",
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn from_key(key: &str) -> Option<Palette> {
+// Variable declaration
+        let key = key.trim().to_lowercase().replace(['_', ' '], "-");
+        Self::ALL
+            .into_iter()
+            .find(|palette| palette.key() == key || (key == "okabeito" && *palette == Palette::OkabeIto))
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn colors(self) -> &'static [[u8; 3]] {
+        match self {
+            Palette::Tab10 => &TAB10,
+            Palette::Viridis => &VIRIDIS,
+            Palette::OkabeIto => &OKABE_ITO,
+            Palette::Grayscale => &GRAYSCALE,
+        }
+    }
+
+    // Color of the `index`-th series, cycling through the palette
+/// Function: explain its purpose and key arguments
+    pub fn color(self, index: usize) -> [u8; 3] {
+// Variable declaration
+        let colors = self.colors();
+        colors[index % colors.len()]
+    }
+}
+
+// Continuous color scales for coloring a line by a value (e.g. time along a trajectory)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// Enum representing a set of related values in palette.rs module
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Plasma,
+    Coolwarm,
+    Grayscale,
+}
+
+/// Implementation block defining methods for this type
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [Colormap::Viridis, Colormap::Plasma, Colormap::Coolwarm, Colormap::Grayscale];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Colormap::Viridis => "Viridis",
+            Colormap::Plasma => "Plasma",
+            Colormap::Coolwarm => "Coolwarm",
+            Colormap::Grayscale => "Grayscale",
+        }
+    }
+
+    // Evenly spaced stops from the low end to the high end
+/// Function: explain its purpose and key arguments
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Plasma => &[
+                [13, 8, 135],
+                [84, 2, 163],
+                [139, 10, 165],
+                [185, 50, 137],
+                [219, 92, 104],
+                [244, 136, 73],
+                [254, 188, 43],
+                [240, 249, 33],
+            ],
+            Colormap::Coolwarm => &[[59, 76, 192], [141, 176, 254], [221, 221, 221], [244, 154, 123], [180, 4, 38]],
+            Colormap::Grayscale => &[[40, 40, 40], [230, 230, 230]],
+        }
+    }
+
+    // Color at `t` in [0, 1] (clamped), interpolated between the neighbouring stops
+/// Function: explain its purpose and key arguments
+    pub fn sample(self, t: f64) -> [u8; 3] {
+// Variable declaration
+        let stops = self.stops();
+// Variable declaration
+        let t = if t.is_finite() { t.clamp(0.0, 1.0) } else { 0.0 };
+// Variable declaration
+        let position = t * (stops.len() - 1) as f64;
+// Variable declaration
+        let low = (position.floor() as usize).min(stops.len() - 2);
+// Variable declaration
+        let fraction = position - low as f64;
+// Variable declaration
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+        [
+            mix(stops[low][0], stops[low + 1][0]),
+            mix(stops[low][1], stops[low + 1][1]),
+            mix(stops[low][2], stops[low + 1][2]),
+        ]
+    }
+}
@@ -0,0 +1,1280 @@
+// Import external modules or crates needed in pdf_export.rs
+use crate::annotation::{Annotation, AnnotationKind};
+// Import external modules or crates needed in pdf_export.rs
+use crate::app::{AxisScale, ExportSettings, FontSize, Subplot, SubplotConfig, SubplotLayout};
+// Import external modules or crates needed in pdf_export.rs
+use crate::dataset::{
+    distribution_positions, fill_baseline, stacked_datasets, visible_datasets, Dataset, MarkerStyle, PlotType,
+    DISTRIBUTION_WIDTH, FILL_OPACITY,
+};
+// Import external modules or crates needed in pdf_export.rs
+use crate::inset::Inset;
+// Import external modules or crates needed in pdf_export.rs
+use crate::palette::Colormap;
+// Import external modules or crates needed in pdf_export.rs
+use crate::utils::{
+    axis_grid_marks, axis_tick_label, bar_width, distribution, export_colors, export_colorbars, export_figure_layout,
+    export_fonts, export_legend_box, export_plot_bounds, export_plot_margins, format_number,
+    mark_spacing_px, measure_text, place_secondary_datasets, scale_point, secondary_axis, secondary_axis_marks,
+    subplot_display_title, with_equal_aspect, ColorbarRect, Distribution, PlotMargins, SecondaryAxis, BASE_FONT_PX, COLORBAR_SPACING, GRID_FULL_STRENGTH_PX,
+    LEGEND_MARKER_MAX_SIZE, SECONDARY_AXIS_SPACING, X_LABEL_SPACING_PX, Y_LABEL_SPACING_PX,
+};
+// Import external modules or crates needed in pdf_export.rs
+use ab_glyph::Font;
+// Import external modules or crates needed in pdf_export.rs
+use egui_plot::GridMark;
+// Import external modules or crates needed in pdf_export.rs
+use pdf_writer::types::{CidFontType, FontFlags, LineCapStyle, LineJoinStyle, SystemInfo, UnicodeCmap};
+// Import external modules or crates needed in pdf_export.rs
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+// Import external modules or crates needed in pdf_export.rs
+use std::borrow::Cow;
+// Import external modules or crates needed in pdf_export.rs
+use std::collections::BTreeMap;
+
+// PDF export draws the same figure as the PNG exporter, laid out in the same pixel grid,
+// but as vector paths and real text on a single page. The page is as large as the PNG
+// would be printed at the export DPI.
+
+// Position on the page in pixels from the top-left corner of the figure
+type Pixel = (f64, f64);
+
+// Colors of an exported figure (see export_colors)
+#[derive(Debug, Clone, Copy)]
+/// Data structure used in pdf_export.rs module
+struct PdfColors {
+    background: [u8; 3],
+    grid: [u8; 3],
+    axis: [u8; 3],
+    text: [u8; 3],
+}
+
+// How a path is stroked: color, opacity, width in pixels and optional (on, off) dashes
+#[derive(Debug, Clone, Copy)]
+/// Data structure used in pdf_export.rs module
+struct PdfStroke {
+    color: [u8; 3],
+    alpha: f32,
+    width: f32,
+    dash: Option<(f64, f64)>,
+}
+
+/// Implementation block defining methods for this type
+impl PdfStroke {
+/// Function: explain its purpose and key arguments
+    fn solid(color: [u8; 3], width: f32) -> Self {
+        Self {
+            color,
+            alpha: 1.0,
+            width,
+            dash: None,
+        }
+    }
+}
+
+// Content stream of the page with the resources it uses. Coordinates are pixels from the
+// top-left corner of the figure, as in the PNG renderer.
+/// Data structure used in pdf_export.rs module
+struct PdfCanvas {
+    content: Content,
+    alphas: Vec<f32>,            // Opacity of the graphics states /A0, /A1, ...
+    glyphs: BTreeMap<u16, char>, // Glyphs shown so far, for the font widths and text extraction
+}
+
+/// Implementation block defining methods for this type
+impl PdfCanvas {
+/// Function: explain its purpose and key arguments
+    fn new(height: u32, pixel_to_pt: f32) -> Self {
+// Variable declaration
+        let mut content = Content::new();
+        // Flip the Y axis so that pixel rows count down from the top of the page
+        content.transform([pixel_to_pt, 0.0, 0.0, -pixel_to_pt, 0.0, height as f32 * pixel_to_pt]);
+        content.set_line_join(LineJoinStyle::RoundJoin);
+        content.set_line_cap(LineCapStyle::ButtCap);
+        Self {
+            content,
+            alphas: Vec::new(),
+            glyphs: BTreeMap::new(),
+        }
+    }
+
+    // Use a graphics state with the given opacity for strokes and fills
+/// Function: explain its purpose and key arguments
+    fn set_alpha(&mut self, alpha: f32) {
+// Variable declaration
+        let alpha = (alpha.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        if alpha >= 1.0 {
+            return;
+        }
+// Variable declaration
+        let index = match self.alphas.iter().position(|&a| a == alpha) {
+            Some(index) => index,
+            None => {
+                self.alphas.push(alpha);
+                self.alphas.len() - 1
+            }
+        };
+        self.content.set_parameters(Name(format!("A{}", index).as_bytes()));
+    }
+
+/// Function: explain its purpose and key arguments
+    fn path(&mut self, points: &[(f64, f64)], closed: bool) {
+        for (i, &(x, y)) in points.iter().enumerate() {
+            if i == 0 {
+                self.content.move_to(x as f32, y as f32);
+            } else {
+                self.content.line_to(x as f32, y as f32);
+            }
+        }
+        if closed {
+            self.content.close_path();
+        }
+    }
+
+    // Straight segments through `points`; `phase` shifts the dash pattern, so that a line
+    // drawn in pieces keeps its dashes continuous
+/// Function: explain its purpose and key arguments
+    fn polyline(&mut self, points: &[(f64, f64)], stroke: PdfStroke, phase: f64) {
+        if points.len() < 2 {
+            return;
+        }
+        self.content.save_state();
+        self.set_alpha(stroke.alpha);
+// Variable declaration
+        let [r, g, b] = rgb(stroke.color);
+        self.content.set_stroke_rgb(r, g, b);
+        self.content.set_line_width(stroke.width);
+        if let Some((on, off)) = stroke.dash {
+            self.content.set_dash_pattern([on as f32, off as f32], phase as f32);
+        }
+        self.path(points, false);
+        self.content.stroke();
+        self.content.restore_state();
+    }
+
+/// Function: explain its purpose and key arguments
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), stroke: PdfStroke) {
+        self.polyline(&[from, to], stroke, 0.0);
+    }
+
+    // Closed shape through `points`, filled with `color` at the given opacity
+/// Function: explain its purpose and key arguments
+    fn polygon(&mut self, points: &[(f64, f64)], color: [u8; 3], alpha: f32) {
+        if points.len() < 3 {
+            return;
+        }
+        self.content.save_state();
+        self.set_alpha(alpha);
+// Variable declaration
+        let [r, g, b] = rgb(color);
+        self.content.set_fill_rgb(r, g, b);
+        self.path(points, true);
+        self.content.fill_nonzero();
+        self.content.restore_state();
+    }
+
+    // Rectangle (left, top, width, height), filled and/or outlined
+/// Function: explain its purpose and key arguments
+    fn rect(&mut self, (x, y, width, height): (f64, f64, f64, f64), fill: Option<([u8; 3], f32)>, outline: Option<PdfStroke>) {
+// Variable declaration
+        let corners = [(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+        if let Some((color, alpha)) = fill {
+            self.polygon(&corners, color, alpha);
+        }
+        if let Some(stroke) = outline {
+// Variable declaration
+            let mut closed = corners.to_vec();
+            closed.push(corners[0]);
+            self.polyline(&closed, stroke, 0.0);
+        }
+    }
+
+    // Filled circle, made of four Bézier arcs
+/// Function: explain its purpose and key arguments
+    fn circle(&mut self, (x, y): (f64, f64), radius: f64, color: [u8; 3]) {
+        // Control point distance for a quarter circle
+// Variable declaration
+        let k = (radius * 0.552_284_75) as f32;
+// Variable declaration
+        let (x, y, r) = (x as f32, y as f32, radius as f32);
+        self.content.save_state();
+// Variable declaration
+        let [red, green, blue] = rgb(color);
+        self.content.set_fill_rgb(red, green, blue);
+        self.content.move_to(x + r, y);
+        self.content.cubic_to(x + r, y + k, x + k, y + r, x, y + r);
+        self.content.cubic_to(x - k, y + r, x - r, y + k, x - r, y);
+        self.content.cubic_to(x - r, y - k, x - k, y - r, x, y - r);
+        self.content.cubic_to(x + k, y - r, x + r, y - k, x + r, y);
+        self.content.fill_nonzero();
+        self.content.restore_state();
+    }
+
+    // Marker of the given shape and radius centred on (x, y), like draw_marker
+/// Function: explain its purpose and key arguments
+    fn marker(&mut self, (x, y): (f64, f64), marker: MarkerStyle, radius: f64, color: [u8; 3]) {
+        match marker.outline(radius) {
+            Some(corners) => {
+// Variable declaration
+                let corners: Vec<(f64, f64)> = corners.into_iter().map(|(dx, dy)| (x + dx, y + dy)).collect();
+                self.polygon(&corners, color, 1.0);
+            }
+            None if marker == MarkerStyle::Cross => {
+// Variable declaration
+                let stroke = PdfStroke::solid(color, (radius / 2.0).max(1.0) as f32);
+                self.line((x - radius, y - radius), (x + radius, y + radius), stroke);
+                self.line((x - radius, y + radius), (x + radius, y - radius), stroke);
+            }
+            None => self.circle((x, y), radius, color),
+        }
+    }
+
+    // Text with the top-left corner of its box at (x, y), like draw_text_scaled; rotated
+    // text reads bottom to top with the box's top-left corner at (x, y), like draw_text_rotated
+/// Function: explain its purpose and key arguments
+    fn text(&mut self, (x, y): (f64, f64), text: &str, color: [u8; 3], scale: f32, rotated: bool) {
+// Variable declaration
+        let Some(font) = export_fonts().first() else {
+            return;
+        };
+        if text.is_empty() {
+            return;
+        }
+        // Exported images scale fonts by their height (ascent to descent), PDF by the em
+// Variable declaration
+        let height = font.height_unscaled();
+// Variable declaration
+        let pixels = BASE_FONT_PX * scale;
+// Variable declaration
+        let size = pixels * font.units_per_em().unwrap_or(1000.0) / height;
+// Variable declaration
+        let ascent = (font.ascent_unscaled() / height * pixels) as f64;
+// Variable declaration
+        let mut encoded = Vec::with_capacity(text.len() * 2);
+        for ch in text.chars() {
+// Variable declaration
+            let glyph = font.glyph_id(ch).0;
+            self.glyphs.entry(glyph).or_insert(ch);
+            encoded.extend(glyph.to_be_bytes());
+        }
+// Variable declaration
+        let matrix = if rotated {
+// Variable declaration
+            let (text_width, _) = measure_text(text, scale);
+            [0.0, -1.0, -1.0, 0.0, (x + ascent) as f32, (y + text_width as f64) as f32]
+        } else {
+            [1.0, 0.0, 0.0, -1.0, x as f32, (y + ascent) as f32]
+        };
+        self.content.save_state();
+// Variable declaration
+        let [r, g, b] = rgb(color);
+        self.content.set_fill_rgb(r, g, b);
+        self.content.begin_text();
+        self.content.set_font(Name(b"F0"), size);
+        self.content.set_text_matrix(matrix);
+        self.content.show(Str(&encoded));
+        self.content.end_text();
+        self.content.restore_state();
+    }
+
+    // Limit drawing to the rectangle (left, top, width, height) until end_clip
+/// Function: explain its purpose and key arguments
+    fn begin_clip(&mut self, (x, y, width, height): (f64, f64, f64, f64)) {
+        self.content.save_state();
+        self.content.rect(x as f32, y as f32, width as f32, height as f32);
+        self.content.clip_nonzero();
+        self.content.end_path();
+    }
+
+/// Function: explain its purpose and key arguments
+    fn end_clip(&mut self) {
+        self.content.restore_state();
+    }
+
+    // Write the page and everything it refers to as a complete PDF file
+/// Function: explain its purpose and key arguments
+    fn finish(self, (width, height): (f32, f32)) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+// Variable declaration
+        let font = export_fonts().first().ok_or("No font available for PDF text")?;
+// Variable declaration
+        let catalog_id = Ref::new(1);
+// Variable declaration
+        let page_tree_id = Ref::new(2);
+// Variable declaration
+        let page_id = Ref::new(3);
+// Variable declaration
+        let content_id = Ref::new(4);
+// Variable declaration
+        let font_id = Ref::new(5);
+// Variable declaration
+        let cid_font_id = Ref::new(6);
+// Variable declaration
+        let descriptor_id = Ref::new(7);
+// Variable declaration
+        let font_file_id = Ref::new(8);
+// Variable declaration
+        let to_unicode_id = Ref::new(9);
+// Variable declaration
+        let info_id = Ref::new(10);
+// Variable declaration
+        let first_state_id = 11;
+
+// Variable declaration
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).kids([page_id]).count(1);
+        pdf.document_info(info_id).creator(TextStr("CactusPlot"));
+
+// Variable declaration
+        let mut page = pdf.page(page_id);
+        page.parent(page_tree_id)
+            .media_box(Rect::new(0.0, 0.0, width, height))
+            .contents(content_id);
+// Variable declaration
+        let mut resources = page.resources();
+        resources.fonts().pair(Name(b"F0"), font_id);
+// Variable declaration
+        let mut states = resources.ext_g_states();
+        for index in 0..self.alphas.len() {
+            states.pair(Name(format!("A{}", index).as_bytes()), Ref::new(first_state_id + index as i32));
+        }
+        states.finish();
+        resources.finish();
+        page.finish();
+        for (index, &alpha) in self.alphas.iter().enumerate() {
+            pdf.ext_graphics(Ref::new(first_state_id + index as i32))
+                .non_stroking_alpha(alpha)
+                .stroking_alpha(alpha);
+        }
+
+        pdf.stream(content_id, &compress(&self.content.finish())).filter(Filter::FlateDecode);
+
+        // The font is embedded as a CID font addressed by glyph ID, so any character it
+        // has can be shown; widths are given for the glyphs used
+// Variable declaration
+        let font_name = Name(b"Ubuntu-Light");
+// Variable declaration
+        let per_em = 1000.0 / font.units_per_em().unwrap_or(1000.0);
+// Variable declaration
+        let system_info = SystemInfo {
+            registry: Str(b"Adobe"),
+            ordering: Str(b"Identity"),
+            supplement: 0,
+        };
+        pdf.type0_font(font_id)
+            .base_font(font_name)
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_font_id)
+            .to_unicode(to_unicode_id);
+// Variable declaration
+        let mut cid_font = pdf.cid_font(cid_font_id);
+        cid_font
+            .subtype(CidFontType::Type2)
+            .base_font(font_name)
+            .system_info(system_info)
+            .font_descriptor(descriptor_id)
+            .cid_to_gid_map_predefined(Name(b"Identity"));
+// Variable declaration
+        let mut widths = cid_font.widths();
+        for &glyph in self.glyphs.keys() {
+            widths.consecutive(glyph, [font.h_advance_unscaled(ab_glyph::GlyphId(glyph)) * per_em]);
+        }
+        widths.finish();
+        cid_font.finish();
+
+// Variable declaration
+        let (ascent, descent) = (font.ascent_unscaled() * per_em, font.descent_unscaled() * per_em);
+        pdf.font_descriptor(descriptor_id)
+            .name(font_name)
+            .flags(FontFlags::NON_SYMBOLIC)
+            .bbox(Rect::new(0.0, descent, 1000.0, ascent))
+            .italic_angle(0.0)
+            .ascent(ascent)
+            .descent(descent)
+            .cap_height(ascent * 0.7)
+            .stem_v(80.0)
+            .font_file2(font_file_id);
+// Variable declaration
+        let font_data = font.font_data();
+        pdf.stream(font_file_id, &compress(font_data))
+            .filter(Filter::FlateDecode)
+            .pair(Name(b"Length1"), font_data.len() as i32);
+
+        // Lets viewers copy and search the text
+// Variable declaration
+        let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+        for (&glyph, &ch) in &self.glyphs {
+            if glyph != 0 {
+                cmap.pair(glyph, ch);
+            }
+        }
+        pdf.cmap(to_unicode_id, &cmap.finish());
+
+        Ok(pdf.finish())
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn rgb([r, g, b]: [u8; 3]) -> [f32; 3] {
+    [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0]
+}
+
+/// Function: explain its purpose and key arguments
+fn compress(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+// Render the subplot grid as a one-page PDF file; the page is the figure size from
+// `settings` printed at its DPI. Subplot titles count up from `first_number`.
+/// Function: explain its purpose and key arguments
+pub fn render_subplots_pdf(
+    subplots: &[Subplot],
+    layout: &SubplotLayout,
+    first_number: usize,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if subplots.is_empty() {
+        return Err("No subplots to export".into());
+    }
+// Variable declaration
+    let figure = export_figure_layout(layout, settings);
+// Variable declaration
+    let (background, grid, axis, text) = export_colors(dark_mode);
+// Variable declaration
+    let colors = PdfColors {
+        background: background.0,
+        grid: grid.0,
+        axis: axis.0,
+        text: text.0,
+    };
+// Variable declaration
+    let pixel_to_pt = 72.0 / settings.dpi.max(1) as f32 * settings.scale;
+// Variable declaration
+    let mut canvas = PdfCanvas::new(figure.size.1, pixel_to_pt);
+    canvas.rect((0.0, 0.0, figure.size.0 as f64, figure.size.1 as f64), Some((colors.background, 1.0)), None);
+
+    for (subplot_idx, subplot) in subplots.iter().enumerate() {
+// Variable declaration
+        let Some(&cell) = figure.cells.get(subplot_idx) else {
+            break;
+        };
+        render_subplot_to_pdf(
+            &mut canvas,
+            subplot,
+            cell,
+            colors,
+            font_size,
+            first_number + subplot_idx,
+        )?;
+    }
+
+    canvas.finish((figure.size.0 as f32 * pixel_to_pt, figure.size.1 as f32 * pixel_to_pt))
+}
+
+// Zoom inset over the plot area (left, top, width, height), as draw_export_inset draws it:
+// the zoomed region outlined on the plot and the inset rendered as a subplot of its own,
+// clipped to its place so its title strip and anything right of its plot are cut off
+/// Function: explain its purpose and key arguments
+fn draw_inset_pdf(
+    canvas: &mut PdfCanvas,
+    subplot: &Subplot,
+    inset: &Inset,
+    plot_area: (f64, f64, f64, f64),
+    to_px: &dyn Fn(f64, f64) -> Pixel,
+    colors: PdfColors,
+    font_size: &FontSize,
+) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let Some(zoomed) = inset.subplot(subplot) else {
+        return Ok(());
+    };
+    if let Some((x0, x1, y0, y1)) = zoomed.view_bounds {
+// Variable declaration
+        let ((left, top), (right, bottom)) = (to_px(x0, y1), to_px(x1, y0));
+        canvas.begin_clip(plot_area);
+        canvas.rect((left, top, right - left, bottom - top), None, Some(PdfStroke::solid(colors.axis, 1.0)));
+        canvas.end_clip();
+    }
+
+// Variable declaration
+    let (left, top, width, height) = inset.rect_in(plot_area);
+// Variable declaration
+    let (left, top, width, height) = (left as u32, top as u32, width as u32, height as u32);
+// Variable declaration
+    let visible = visible_datasets(&zoomed.datasets);
+// Variable declaration
+    let mut stacked = visible.clone();
+    stacked_datasets(&mut stacked);
+// Variable declaration
+    let has_secondary = secondary_axis(&stacked, &zoomed.config).is_some();
+// Variable declaration
+    let margins = export_plot_margins(
+        &zoomed,
+        &visible,
+        has_secondary,
+        export_colorbars(&zoomed, &visible).len(),
+        (width, height),
+        (font_size.to_scale(), 1.0),
+    );
+    if width < margins.left + 40 || height < margins.top + margins.bottom + 20 {
+        return Ok(());
+    }
+// Variable declaration
+    let area = (left as f64, top as f64, width as f64, height as f64);
+    canvas.begin_clip(area);
+    canvas.rect(area, Some((colors.background, 1.0)), None);
+    render_subplot_to_pdf(
+        canvas,
+        &zoomed,
+        (left, top - 30, width + margins.right, height + 30),
+        colors,
+        font_size,
+        0,
+    )?;
+    canvas.end_clip();
+    canvas.rect(area, None, Some(PdfStroke::solid(colors.axis, 1.0)));
+    Ok(())
+}
+
+// Draw one subplot into the cell (left, top, width, height), mirroring render_subplot_to_image
+/// Function: explain its purpose and key arguments
+fn render_subplot_to_pdf(
+    canvas: &mut PdfCanvas,
+    subplot: &Subplot,
+    (x_offset, y_offset, width, height): (u32, u32, u32, u32),
+    colors: PdfColors,
+    font_size: &FontSize,
+    subplot_number: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Title, centred above the plot
+// Variable declaration
+    let title = subplot_display_title(&subplot.config.title, subplot_number);
+// Variable declaration
+    let title_scale = font_size.to_scale() * 1.2;
+// Variable declaration
+    let (title_width, _) = measure_text(&title, title_scale);
+    canvas.text(
+        ((x_offset + width.saturating_sub(title_width) / 2) as f64, (y_offset + 5) as f64),
+        &title,
+        colors.text,
+        title_scale,
+        false,
+    );
+
+// Variable declaration
+    let visible = visible_datasets(&subplot.datasets);
+    if visible.is_empty() && subplot.heatmaps.is_empty() {
+        canvas.rect(
+            (x_offset as f64, (y_offset + 30) as f64, width as f64 - 1.0, height as f64 - 31.0),
+            None,
+            Some(PdfStroke::solid(colors.axis, 1.0)),
+        );
+        return Ok(());
+    }
+
+// Variable declaration
+    let (x_scale, y_scale) = (subplot.config.x_axis(), subplot.config.y_axis());
+// Variable declaration
+    let mut datasets = visible.clone();
+    stacked_datasets(&mut datasets);
+// Variable declaration
+    let secondary = secondary_axis(&datasets, &subplot.config);
+    place_secondary_datasets(&mut datasets, secondary, y_scale);
+// Variable declaration
+    let (min_x, max_x, min_y, max_y) = export_plot_bounds(subplot, &datasets)?;
+
+// Variable declaration
+    let plot_height = height - 30; // Below the title
+// Variable declaration
+    let colorbars = export_colorbars(subplot, &visible);
+// Variable declaration
+    let margins = export_plot_margins(
+        subplot,
+        &visible,
+        secondary.is_some(),
+        colorbars.len(),
+        (width, plot_height),
+        (font_size.to_scale(), 1.0),
+    );
+// Variable declaration
+    let PlotMargins { left, right, top, bottom, secondary_space, legend_space } = margins;
+// Variable declaration
+    let plot_width = width - left - right;
+// Variable declaration
+    let effective_plot_height = plot_height - top - bottom;
+// Variable declaration
+    let (min_x, max_x, min_y, max_y) = if subplot.config.equal_aspect {
+        with_equal_aspect((min_x, max_x, min_y, max_y), (plot_width, effective_plot_height))
+    } else {
+        (min_x, max_x, min_y, max_y)
+    };
+// Variable declaration
+    let plot_left = (x_offset + left) as f64;
+// Variable declaration
+    let plot_right = (x_offset + width - right) as f64;
+// Variable declaration
+    let plot_top = (y_offset + 30 + top) as f64;
+// Variable declaration
+    let x_axis_y = plot_top + effective_plot_height as f64;
+// Variable declaration
+    let plot_area = (plot_left, plot_top, plot_width as f64, effective_plot_height as f64);
+    // Plot coordinates to pixels
+// Variable declaration
+    let to_px = |x: f64, y: f64| {
+        (
+            plot_left + (x - min_x) / (max_x - min_x) * plot_width as f64,
+            x_axis_y - (y - min_y) / (max_y - min_y) * effective_plot_height as f64,
+        )
+    };
+
+// Variable declaration
+    let x_marks = axis_grid_marks((min_x, max_x), x_scale, plot_width, &subplot.config, X_LABEL_SPACING_PX);
+// Variable declaration
+    let y_marks = axis_grid_marks((min_y, max_y), y_scale, effective_plot_height, &subplot.config, Y_LABEL_SPACING_PX);
+
+    // Grid lines at the grid marks, fading out as they get closer together
+    if subplot.config.show_grid {
+// Variable declaration
+        let min_spacing = subplot.config.grid_spacing as f64;
+// Variable declaration
+        let stroke = |spacing: f64| {
+// Variable declaration
+            let strength = ((spacing - min_spacing) / (GRID_FULL_STRENGTH_PX - min_spacing).max(1.0)).clamp(0.0, 1.0);
+            PdfStroke {
+                color: colors.grid,
+                alpha: (strength as f32).sqrt() * subplot.config.grid_opacity,
+                width: 1.0,
+                dash: subplot.config.grid_style.dash_lengths(1),
+            }
+        };
+        for mark in &x_marks {
+// Variable declaration
+            let spacing = mark_spacing_px(mark, (min_x, max_x), plot_width);
+            if spacing > min_spacing {
+// Variable declaration
+                let x = to_px(mark.value, min_y).0;
+                canvas.line((x, plot_top), (x, x_axis_y), stroke(spacing));
+            }
+        }
+        for mark in &y_marks {
+// Variable declaration
+            let spacing = mark_spacing_px(mark, (min_y, max_y), effective_plot_height);
+            if spacing > min_spacing {
+// Variable declaration
+                let y = to_px(min_x, mark.value).1;
+                canvas.line((plot_left, y), (plot_right, y), stroke(spacing));
+            }
+        }
+    }
+
+    // Axes, ticks and tick labels
+// Variable declaration
+    let axis_stroke = PdfStroke::solid(colors.axis, 1.0);
+    canvas.line((plot_left, x_axis_y), (plot_right, x_axis_y), axis_stroke);
+    canvas.line((plot_left, plot_top), (plot_left, x_axis_y), axis_stroke);
+    if let Some(axis) = secondary {
+        draw_secondary_axis_pdf(
+            canvas,
+            axis,
+            (min_y, max_y),
+            (plot_right, plot_top, x_axis_y),
+            &subplot.config,
+            colors,
+            font_size,
+        );
+    }
+    draw_tick_labels_pdf(canvas, (min_x, max_x, x_scale, &x_marks), (min_y, max_y, y_scale, &y_marks), plot_area, colors, font_size);
+
+    // Axis titles
+// Variable declaration
+    let label_scale = font_size.to_scale();
+    if !subplot.config.x_label.is_empty() {
+// Variable declaration
+        let (text_width, _) = measure_text(&subplot.config.x_label, label_scale);
+        canvas.text(
+            (plot_left + (plot_width / 2) as f64 - (text_width / 2) as f64, x_axis_y + 32.0),
+            &subplot.config.x_label,
+            colors.text,
+            label_scale,
+            false,
+        );
+    }
+    if !subplot.config.y_label.is_empty() {
+// Variable declaration
+        let (text_width, _) = measure_text(&subplot.config.y_label, label_scale);
+        canvas.text(
+            ((x_offset + 4) as f64, plot_top + (effective_plot_height / 2) as f64 - (text_width / 2) as f64),
+            &subplot.config.y_label,
+            colors.text,
+            label_scale,
+            true,
+        );
+    }
+
+    // Everything drawn in plot coordinates stays inside the axes
+    canvas.begin_clip(plot_area);
+
+    // Heatmap cells underneath the datasets
+    if !x_scale.is_log() && !y_scale.is_log() {
+        for heatmap in &subplot.heatmaps {
+// Variable declaration
+            let Some(range) = heatmap.value_range() else {
+                continue;
+            };
+// Variable declaration
+            let (x_edges, y_edges) = (heatmap.x_edges(), heatmap.y_edges());
+            for row in 0..heatmap.y.len() {
+                for col in 0..heatmap.x.len() {
+// Variable declaration
+                    let Some(color) = heatmap.cell_color(row, col, range) else {
+                        continue;
+                    };
+                    // Opposite corners of the cell, which may come in either order on reversed axes
+// Variable declaration
+                    let (Some([a_x, a_y]), Some([b_x, b_y])) = (
+                        scale_point(&[x_edges[col], y_edges[row]], x_scale, y_scale),
+                        scale_point(&[x_edges[col + 1], y_edges[row + 1]], x_scale, y_scale),
+                    ) else {
+                        continue;
+                    };
+// Variable declaration
+                    let ((x0, y0), (x1, y1)) = (to_px(a_x, a_y), to_px(b_x, b_y));
+                    canvas.rect((x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs()), Some((color, 1.0)), None);
+                }
+            }
+        }
+    }
+
+    // Shaded bands and filled areas go underneath all the lines
+    for (index, dataset) in datasets.iter().enumerate() {
+// Variable declaration
+        let band = dataset.band_edges();
+// Variable declaration
+        let fill = fill_baseline(&datasets, index).map(|baseline| (dataset.points.to_vec(), baseline));
+        for (points, baseline) in band.into_iter().chain(fill) {
+            // Upper and lower edge at each point; a zero baseline on a log axis is the
+            // bottom of the plot
+// Variable declaration
+            let edges: Vec<Option<(Pixel, Pixel)>> = points
+                .iter()
+                .zip(&baseline)
+                .map(|(point, low)| {
+// Variable declaration
+                    let [x, y] = scale_point(point, x_scale, y_scale)?;
+                    Some((to_px(x, y), to_px(x, y_scale.apply((*low)?).unwrap_or(min_y))))
+                })
+                .collect();
+            // One shape per run of points with edges
+            for run in edges.split(|edge| edge.is_none()) {
+// Variable declaration
+                let run: Vec<(Pixel, Pixel)> = run.iter().flatten().copied().collect();
+// Variable declaration
+                let outline: Vec<(f64, f64)> =
+                    run.iter().map(|edge| edge.0).chain(run.iter().rev().map(|edge| edge.1)).collect();
+                canvas.polygon(&outline, dataset.color, FILL_OPACITY as f32);
+            }
+        }
+    }
+
+    for (dataset, position) in datasets.iter().zip(distribution_positions(&datasets)) {
+        if let Some(position) = position {
+// Variable declaration
+            let half_width = DISTRIBUTION_WIDTH / 2.0 / (max_x - min_x) * plot_width as f64;
+            draw_distribution_pdf(canvas, dataset, position, (x_scale, y_scale), &to_px, half_width);
+            continue;
+        }
+        draw_dataset_pdf(canvas, dataset, (x_scale, y_scale), (min_x, min_y, max_y), &to_px);
+    }
+
+    canvas.end_clip();
+
+    // Annotations (skipping any that fall outside the plot area)
+// Variable declaration
+    let annotation_px = |point: &[f64; 2]| -> Option<(f64, f64)> {
+// Variable declaration
+        let [x, y] = scale_point(point, x_scale, y_scale)?;
+        if x < min_x || x > max_x || y < min_y || y > max_y {
+            return None;
+        }
+        Some(to_px(x, y))
+    };
+    for annotation in &subplot.annotations {
+        draw_annotation_pdf(canvas, annotation, &annotation_px, (plot_top, x_axis_y), font_size);
+    }
+
+    for (k, &colorbar) in colorbars.iter().enumerate() {
+// Variable declaration
+        let rect = ColorbarRect {
+            left: plot_right + (secondary_space + 10 + COLORBAR_SPACING * k as u32) as f64,
+            top: plot_top + 5.0,
+            height: (effective_plot_height * 3 / 5).max(20) as f64,
+        };
+        draw_colorbar_pdf(canvas, rect, colorbar, colors, font_size);
+    }
+
+    if let Some(inset) = &subplot.config.inset {
+        draw_inset_pdf(canvas, subplot, inset, plot_area, &to_px, colors, font_size)?;
+    }
+
+    if subplot.config.show_legend && !visible.is_empty() {
+// Variable declaration
+        let area = if legend_space > 0 {
+            (x_offset + width - legend_space, plot_top as u32, x_offset + width, x_axis_y as u32)
+        } else {
+            (x_offset + left, plot_top as u32, x_offset + width - right, x_axis_y as u32)
+        };
+        draw_legend_pdf(canvas, &visible, subplot, area, colors);
+    }
+
+    Ok(())
+}
+
+// Line, markers, bars and error bars of one dataset; error bars and bars that reach past
+// zero on a log axis end at `min_x` or `min_y`, the edge of the plot
+/// Function: explain its purpose and key arguments
+fn draw_dataset_pdf(
+    canvas: &mut PdfCanvas,
+    dataset: &Dataset,
+    (x_scale, y_scale): (AxisScale, AxisScale),
+    (min_x, min_y, max_y): (f64, f64, f64),
+    to_px: &dyn Fn(f64, f64) -> (f64, f64),
+) {
+// Variable declaration
+    let thin = PdfStroke::solid(dataset.color, 1.0);
+
+    // Error bars with short caps; on log axes a lower end at or below zero is the plot edge
+    if dataset.has_error_bars() {
+        for (i, point) in dataset.points.iter().enumerate() {
+// Variable declaration
+            let Some([sx, sy]) = scale_point(&point, x_scale, y_scale) else {
+                continue;
+            };
+            if let Some(&dy) = dataset.y_err.as_ref().and_then(|e| e.get(i)) {
+// Variable declaration
+                let (x, low) = to_px(sx, y_scale.apply(point[1] - dy).unwrap_or(min_y));
+// Variable declaration
+                let (_, high) = to_px(sx, y_scale.apply(point[1] + dy).unwrap_or(sy));
+                canvas.line((x, low), (x, high), thin);
+                for end in [low, high] {
+                    canvas.line((x - 3.0, end), (x + 3.0, end), thin);
+                }
+            }
+            if let Some(&dx) = dataset.x_err.as_ref().and_then(|e| e.get(i)) {
+// Variable declaration
+                let (low, y) = to_px(x_scale.apply(point[0] - dx).unwrap_or(min_x), sy);
+// Variable declaration
+                let (high, _) = to_px(x_scale.apply(point[0] + dx).unwrap_or(sx), sy);
+                canvas.line((low, y), (high, y), thin);
+                for end in [low, high] {
+                    canvas.line((end, y - 3.0), (end, y + 3.0), thin);
+                }
+            }
+        }
+    }
+
+    // Bars rise from zero, or from the bottom edge on a log Y axis
+    if dataset.plot_type.draws_bars() {
+// Variable declaration
+        let base = if y_scale.is_log() { min_y } else { 0.0f64.clamp(min_y, max_y) };
+// Variable declaration
+        let half_width = bar_width(&dataset.points) / 2.0;
+        for point in &dataset.points {
+// Variable declaration
+            let (Some([_, top]), Some(left), Some(right)) = (
+                scale_point(&point, x_scale, y_scale),
+                x_scale.apply(point[0] - half_width),
+                x_scale.apply(point[0] + half_width),
+            ) else {
+                continue;
+            };
+// Variable declaration
+            let (x0, y0) = to_px(left, top);
+// Variable declaration
+            let (x1, y1) = to_px(right, base);
+            canvas.rect(
+                (x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs()),
+                Some((dataset.color, 0.35)),
+                Some(thin),
+            );
+        }
+    }
+
+    // Color of point `i`: along the colormap for value-colored datasets
+// Variable declaration
+    let value_range = dataset.color_range();
+// Variable declaration
+    let point_color = |i: usize| value_range.map_or(dataset.color, |range| dataset.point_color(i, range));
+
+    if dataset.plot_type.draws_markers() {
+        for (i, point) in dataset.points.iter().enumerate() {
+            if let Some([x, y]) = scale_point(&point, x_scale, y_scale) {
+                canvas.marker(to_px(x, y), dataset.marker, dataset.marker_size as f64, point_color(i));
+            }
+        }
+    }
+
+    if !dataset.plot_type.draws_line() {
+        return;
+    }
+// Variable declaration
+    let stroke = PdfStroke {
+        color: dataset.color,
+        alpha: 1.0,
+        width: dataset.line_width.max(0.5),
+        dash: dataset.line_style.dash_lengths(dataset.line_width.round().max(1.0) as u32),
+    };
+// Variable declaration
+    let points: Vec<Option<(f64, f64)>> = dataset
+        .points
+        .iter()
+        .map(|point| scale_point(&point, x_scale, y_scale).map(|[x, y]| to_px(x, y)))
+        .collect();
+    if value_range.is_none() {
+        // Points a log axis cannot show, or that are missing a value, break the line
+        for run in points.split(|point| point.is_none()) {
+// Variable declaration
+            let run: Vec<(f64, f64)> = run.iter().flatten().copied().collect();
+            canvas.polyline(&run, stroke, 0.0);
+        }
+        return;
+    }
+    // Value-colored lines change color at every point, so they are drawn a segment at a time
+// Variable declaration
+    let mut phase = 0.0;
+    for (i, pair) in points.windows(2).enumerate() {
+        if let (Some(from), Some(to)) = (pair[0], pair[1]) {
+            canvas.polyline(&[from, to], PdfStroke { color: point_color(i), ..stroke }, phase);
+            phase += (to.0 - from.0).hypot(to.1 - from.1);
+        }
+    }
+}
+
+// Box plot or violin of a dataset's Y values centred on `position` (see draw_distribution_to_image)
+/// Function: explain its purpose and key arguments
+fn draw_distribution_pdf(
+    canvas: &mut PdfCanvas,
+    dataset: &Dataset,
+    position: f64,
+    (x_scale, y_scale): (AxisScale, AxisScale),
+    to_px: &dyn Fn(f64, f64) -> (f64, f64),
+    half_width: f64,
+) {
+// Variable declaration
+    let Some(Distribution { summary, density }) = distribution(dataset, y_scale) else {
+        return;
+    };
+// Variable declaration
+    let width = dataset.line_width.round().max(1.0);
+// Variable declaration
+    let stroke = PdfStroke::solid(dataset.color, width);
+// Variable declaration
+    let x = x_scale.slot(position);
+// Variable declaration
+    let cx = to_px(x, 0.0).0;
+// Variable declaration
+    let py = |y: f64| to_px(x, y).1;
+
+    if dataset.plot_type == PlotType::Violin {
+// Variable declaration
+        let peak = density.iter().map(|d| d[1]).fold(0.0, f64::max);
+        if density.len() > 1 && peak > 0.0 {
+// Variable declaration
+            let right: Vec<(f64, f64)> = density.iter().map(|&[y, d]| (cx + d / peak * half_width, py(y))).collect();
+// Variable declaration
+            let left: Vec<(f64, f64)> = density.iter().rev().map(|&[y, d]| (cx - d / peak * half_width, py(y))).collect();
+// Variable declaration
+            let outline: Vec<(f64, f64)> = right.iter().chain(&left).copied().collect();
+            canvas.polygon(&outline, dataset.color, FILL_OPACITY as f32);
+            canvas.polyline(&right, stroke, 0.0);
+            canvas.polyline(&left, stroke, 0.0);
+        }
+        canvas.line((cx, py(summary.q1)), (cx, py(summary.q3)), PdfStroke::solid(dataset.color, width * 3.0));
+        canvas.circle((cx, py(summary.median)), 3.0, [255, 255, 255]);
+        return;
+    }
+
+// Variable declaration
+    let (left, right) = (cx - half_width, cx + half_width);
+// Variable declaration
+    let (q1, q3) = (py(summary.q1), py(summary.q3));
+    canvas.rect((left, q3, right - left, q1 - q3), Some((dataset.color, FILL_OPACITY as f32)), Some(stroke));
+    canvas.line((left, py(summary.median)), (right, py(summary.median)), PdfStroke::solid(dataset.color, width + 1.0));
+    // Whiskers with caps half as wide as the box
+    for (end, whisker) in [(q1, py(summary.lower_whisker)), (q3, py(summary.upper_whisker))] {
+        canvas.line((cx, end), (cx, whisker), stroke);
+        canvas.line((cx - half_width / 2.0, whisker), (cx + half_width / 2.0, whisker), stroke);
+    }
+    for &outlier in &summary.outliers {
+        canvas.circle((cx, py(outlier)), 2.0, dataset.color);
+    }
+}
+
+// Tick marks and labels of both axes around `plot_area` (left, top, width, height), at the
+// grid marks with room for a label (see draw_subplot_axis_labels)
+/// Function: explain its purpose and key arguments
+fn draw_tick_labels_pdf(
+    canvas: &mut PdfCanvas,
+    (min_x, max_x, x_scale, x_marks): (f64, f64, AxisScale, &[GridMark]),
+    (min_y, max_y, y_scale, y_marks): (f64, f64, AxisScale, &[GridMark]),
+    (left, top, width, height): (f64, f64, f64, f64),
+    colors: PdfColors,
+    font_size: &FontSize,
+) {
+// Variable declaration
+    let font_scale = font_size.to_scale();
+// Variable declaration
+    let tick = PdfStroke::solid(colors.axis, 1.0);
+// Variable declaration
+    let bottom = top + height;
+    for mark in x_marks {
+// Variable declaration
+        let text = axis_tick_label(mark, x_scale);
+// Variable declaration
+        let (text_width, _) = measure_text(&text, font_scale);
+// Variable declaration
+        let spacing = mark_spacing_px(mark, (min_x, max_x), width as u32);
+        if spacing <= X_LABEL_SPACING_PX || spacing < text_width as f64 {
+            continue;
+        }
+// Variable declaration
+        let x = left + (mark.value - min_x) / (max_x - min_x) * width;
+        canvas.line((x, bottom), (x, bottom + 5.0), tick);
+        canvas.text(((x - (text_width / 2) as f64).max(0.0), bottom + 8.0), &text, colors.text, font_scale, false);
+    }
+    for mark in y_marks {
+// Variable declaration
+        let text = axis_tick_label(mark, y_scale);
+// Variable declaration
+        let (text_width, text_height) = measure_text(&text, font_scale);
+// Variable declaration
+        let spacing = mark_spacing_px(mark, (min_y, max_y), height as u32);
+        if spacing <= Y_LABEL_SPACING_PX || spacing < text_height as f64 {
+            continue;
+        }
+// Variable declaration
+        let y = bottom - (mark.value - min_y) / (max_y - min_y) * height;
+        canvas.line((left - 5.0, y), (left, y), tick);
+        canvas.text(
+            ((left - text_width as f64 - 10.0).max(0.0), y - (text_height / 2) as f64),
+            &text,
+            colors.text,
+            font_scale,
+            false,
+        );
+    }
+}
+
+// Right-hand Y axis at `axis_x` from `top` to `bottom`, ticked at round values of the
+// secondary range (see draw_secondary_axis)
+/// Function: explain its purpose and key arguments
+fn draw_secondary_axis_pdf(
+    canvas: &mut PdfCanvas,
+    axis: SecondaryAxis,
+    (min_y, max_y): (f64, f64),
+    (axis_x, top, bottom): (f64, f64, f64),
+    config: &SubplotConfig,
+    colors: PdfColors,
+    font_size: &FontSize,
+) {
+// Variable declaration
+    let font_scale = font_size.to_scale();
+// Variable declaration
+    let label = config.y2_label.as_str();
+// Variable declaration
+    let stroke = PdfStroke::solid(colors.axis, 1.0);
+    canvas.line((axis_x, top), (axis_x, bottom), stroke);
+    for (mark, text) in secondary_axis_marks(axis, (min_y, max_y), (bottom - top) as u32, config) {
+// Variable declaration
+        let (_, text_height) = measure_text(&text, font_scale);
+// Variable declaration
+        let spacing = mark_spacing_px(&mark, (min_y, max_y), (bottom - top) as u32);
+        if spacing <= Y_LABEL_SPACING_PX || spacing < text_height as f64 {
+            continue;
+        }
+// Variable declaration
+        let y = bottom - (mark.value - min_y) / (max_y - min_y) * (bottom - top);
+        canvas.line((axis_x, y), (axis_x + 5.0, y), stroke);
+        canvas.text((axis_x + 10.0, y - (text_height / 2) as f64), &text, colors.text, font_scale, false);
+    }
+    if !label.is_empty() {
+// Variable declaration
+        let (text_width, _) = measure_text(label, font_scale);
+        canvas.text(
+            (axis_x + (SECONDARY_AXIS_SPACING + 4) as f64, (top + bottom) / 2.0 - (text_width / 2) as f64),
+            label,
+            colors.text,
+            font_scale,
+            true,
+        );
+    }
+}
+
+// Vertical colorbar filling `rect`, outlined, with the end values to its right (see
+// draw_colorbar)
+/// Function: explain its purpose and key arguments
+fn draw_colorbar_pdf(
+    canvas: &mut PdfCanvas,
+    ColorbarRect { left, top, height }: ColorbarRect<f64>,
+    (colormap, (low, high)): (Colormap, (f64, f64)),
+    colors: PdfColors,
+    font_size: &FontSize,
+) {
+    const BAR_WIDTH: f64 = 12.0;
+    // One band per pixel row; each overlaps the next slightly so no seams show
+// Variable declaration
+    let rows = height.round().max(2.0) as usize;
+    for row in 0..rows {
+// Variable declaration
+        let color = colormap.sample(1.0 - row as f64 / (rows - 1) as f64);
+// Variable declaration
+        let y = top + row as f64 * height / rows as f64;
+        canvas.rect((left, y, BAR_WIDTH, height / rows as f64 + 0.5), Some((color, 1.0)), None);
+    }
+    canvas.rect((left, top, BAR_WIDTH, height), None, Some(PdfStroke::solid(colors.axis, 1.0)));
+
+// Variable declaration
+    let scale = font_size.to_scale() * 0.9;
+    for (value, y) in [(high, top), (low, top + height)] {
+// Variable declaration
+        let label = format_number(value);
+// Variable declaration
+        let (_, text_height) = measure_text(&label, scale);
+        canvas.text((left + BAR_WIDTH + 4.0, y - (text_height / 2) as f64), &label, colors.text, scale, false);
+    }
+}
+
+// Draw one annotation; `to_px` maps data coordinates to pixels, or None when off the plot
+/// Function: explain its purpose and key arguments
+fn draw_annotation_pdf(
+    canvas: &mut PdfCanvas,
+    annotation: &Annotation,
+    to_px: &dyn Fn(&[f64; 2]) -> Option<Pixel>,
+    (top, bottom): (f64, f64),
+    font_size: &FontSize,
+) {
+// Variable declaration
+    let Some((x, y)) = to_px(&annotation.position) else {
+        return;
+    };
+// Variable declaration
+    let stroke = PdfStroke::solid(annotation.color, 2.0);
+    match annotation.kind {
+        AnnotationKind::Text => {}
+        AnnotationKind::Arrow => {
+            if let Some((tail_x, tail_y)) = annotation.tail.as_ref().and_then(to_px) {
+                canvas.line((tail_x, tail_y), (x, y), stroke);
+                // Arrow head: two strokes 25 degrees either side of the shaft
+// Variable declaration
+                let angle = (y - tail_y).atan2(x - tail_x);
+                for side in [-1.0, 1.0] {
+// Variable declaration
+                    let wing = angle + std::f64::consts::PI - side * 25f64.to_radians();
+                    canvas.line((x, y), (x + 10.0 * wing.cos(), y + 10.0 * wing.sin()), stroke);
+                }
+            }
+        }
+        AnnotationKind::Marker => canvas.circle((x, y), 5.0, annotation.color),
+        AnnotationKind::VLine => canvas.line(
+            (x, top),
+            (x, bottom),
+            PdfStroke {
+                dash: Some((8.0, 6.0)),
+                ..PdfStroke::solid(annotation.color, 1.0)
+            },
+        ),
+    }
+
+    if annotation.text.is_empty() {
+        return;
+    }
+// Variable declaration
+    let Some((label_x, label_y)) = to_px(&annotation.label_position()) else {
+        return;
+    };
+// Variable declaration
+    let scale = font_size.to_scale();
+// Variable declaration
+    let (text_width, text_height) = measure_text(&annotation.text, scale);
+    // Plain labels are centred on their point; arrow and marker labels sit beside it
+// Variable declaration
+    let corner = if annotation.kind == AnnotationKind::Text {
+        (label_x - (text_width / 2) as f64, label_y - (text_height / 2) as f64)
+    } else {
+        (label_x + 6.0, label_y - (text_height + 2) as f64)
+    };
+    canvas.text(corner, &annotation.text, annotation.color, scale, false);
+}
+
+// Legend box in a corner of `area` (left, top, right, bottom in pixels), laid out like
+// the PNG legend, with a preview of how each dataset is drawn
+/// Function: explain its purpose and key arguments
+fn draw_legend_pdf(canvas: &mut PdfCanvas, datasets: &[Cow<'_, Dataset>], subplot: &Subplot, area: (u32, u32, u32, u32), colors: PdfColors) {
+// Variable declaration
+    let config = &subplot.config;
+// Variable declaration
+    let (layout, (x0, y0)) = export_legend_box(datasets, config, area, 1.0);
+// Variable declaration
+    let (x0, y0) = (x0 as f64, y0 as f64);
+    canvas.rect(
+        (x0, y0, layout.size.0 as f64, layout.size.1 as f64),
+        Some((colors.background, config.legend_opacity)),
+        config.legend_border.then(|| PdfStroke::solid(colors.axis, 1.0)),
+    );
+
+// Variable declaration
+    let padding = layout.padding as f64;
+// Variable declaration
+    let mut entries_y = y0 + padding;
+    if !config.legend_title.is_empty() {
+        canvas.text((x0 + padding, entries_y), &config.legend_title, colors.text, layout.scale, false);
+        entries_y += layout.title_height as f64;
+    }
+
+// Variable declaration
+    let (swatch_width, line_height) = (layout.swatch_width as f64, layout.line_height as f64);
+// Variable declaration
+    let mut column_x = x0 + padding;
+    for ((names, column_datasets), column_width) in
+        layout.names.chunks(layout.rows).zip(datasets.chunks(layout.rows)).zip(&layout.column_widths)
+    {
+        for (row, (name, dataset)) in names.iter().zip(column_datasets).enumerate() {
+// Variable declaration
+            let entry_y = entries_y + row as f64 * line_height;
+// Variable declaration
+            let middle = entry_y + line_height / 2.0;
+            if dataset.plot_type.draws_line() {
+// Variable declaration
+                let width = dataset.line_width.max(0.5);
+                canvas.line(
+                    (column_x + 2.0, middle),
+                    (column_x + swatch_width - 4.0, middle),
+                    PdfStroke {
+                        dash: dataset.line_style.dash_lengths(width.round().max(1.0) as u32),
+                        ..PdfStroke::solid(dataset.color, width)
+                    },
+                );
+            }
+            if dataset.plot_type.draws_markers() {
+                canvas.marker(
+                    (column_x + (swatch_width - 2.0) / 2.0, middle),
+                    dataset.marker,
+                    dataset.marker_size.min(LEGEND_MARKER_MAX_SIZE) as f64,
+                    dataset.color,
+                );
+            }
+            if dataset.plot_type.draws_bars() || dataset.plot_type.draws_distribution() {
+// Variable declaration
+                let side = line_height * 2.0 / 3.0;
+                canvas.rect(
+                    (column_x + (swatch_width - side) / 2.0, middle - side / 2.0, side, side),
+                    Some((dataset.color, 1.0)),
+                    None,
+                );
+            }
+            canvas.text((column_x + swatch_width, entry_y), name, colors.text, layout.scale, false);
+        }
+        column_x += (column_width + 2 * layout.padding) as f64;
+    }
+}
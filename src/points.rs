@@ -0,0 +1,385 @@
+// Import external modules or crates needed in points.rs
+use serde::{Deserialize, Serialize};
+
+// The values of one coordinate of a dataset. Evenly spaced X values (frame numbers, times
+// of a simulation written at a fixed interval) are kept as a start and a step only; they
+// are recognised when every value is exactly start + i * step, so nothing changes on the
+// way back out.
+#[derive(Debug, Clone)]
+/// Enum representing a set of related values in points.rs module
+enum Column {
+    Double(Vec<f64>),
+    Single(Vec<f32>), // Half the memory, about 7 significant digits
+    Uniform { start: f64, step: f64, len: usize },
+}
+
+/// Implementation block defining methods for this type
+impl Column {
+    // Values stored as given, or in single precision
+/// Function: explain its purpose and key arguments
+    fn listed(values: Vec<f64>, single_precision: bool) -> Self {
+        if single_precision {
+            Column::Single(values.into_iter().map(|v| v as f32).collect())
+        } else {
+            Column::Double(values)
+        }
+    }
+
+    // X values, as a start and a step where they are evenly spaced
+/// Function: explain its purpose and key arguments
+    fn packed(values: Vec<f64>, single_precision: bool) -> Self {
+        if values.len() >= 3 {
+// Variable declaration
+            let (start, step) = (values[0], values[1] - values[0]);
+            if step.is_finite() && step != 0.0 && values.iter().enumerate().all(|(i, v)| *v == start + i as f64 * step) {
+                return Column::Uniform { start, step, len: values.len() };
+            }
+        }
+        Self::listed(values, single_precision)
+    }
+
+/// Function: explain its purpose and key arguments
+    fn len(&self) -> usize {
+        match self {
+            Column::Double(values) => values.len(),
+            Column::Single(values) => values.len(),
+            Column::Uniform { len, .. } => *len,
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn get(&self, i: usize) -> f64 {
+        match self {
+            Column::Double(values) => values[i],
+            Column::Single(values) => values[i] as f64,
+            Column::Uniform { start, step, len } => {
+                assert!(i < *len, "index {} out of range for {} points", i, len);
+                start + i as f64 * step
+            }
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn to_vec(&self) -> Vec<f64> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+
+    // Add a value at the end; evenly spaced values stay packed while the new one keeps the spacing
+/// Function: explain its purpose and key arguments
+    fn push(&mut self, value: f64, single_precision: bool) {
+        match self {
+            Column::Double(values) => values.push(value),
+            Column::Single(values) => values.push(value as f32),
+            Column::Uniform { start, step, len } if value == *start + *len as f64 * *step => *len += 1,
+            Column::Uniform { .. } => {
+// Variable declaration
+                let mut values = self.to_vec();
+                values.push(value);
+                *self = Column::listed(values, single_precision);
+            }
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn set(&mut self, i: usize, value: f64, single_precision: bool) {
+        match self {
+            Column::Double(values) => values[i] = value,
+            Column::Single(values) => values[i] = value as f32,
+            Column::Uniform { .. } if self.get(i) == value => {}
+            Column::Uniform { .. } => {
+// Variable declaration
+                let mut values = self.to_vec();
+                values[i] = value;
+                *self = Column::listed(values, single_precision);
+            }
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn remove(&mut self, i: usize, single_precision: bool) {
+        match self {
+            Column::Double(values) => {
+                values.remove(i);
+            }
+            Column::Single(values) => {
+                values.remove(i);
+            }
+            Column::Uniform { len, .. } if i + 1 == *len => *len -= 1,
+            Column::Uniform { .. } => {
+// Variable declaration
+                let mut values = self.to_vec();
+                values.remove(i);
+                *self = Column::packed(values, single_precision);
+            }
+        }
+    }
+
+    // Bytes taken by the values themselves
+/// Function: explain its purpose and key arguments
+    fn memory_bytes(&self) -> usize {
+        match self {
+            Column::Double(values) => values.len() * std::mem::size_of::<f64>(),
+            Column::Single(values) => values.len() * std::mem::size_of::<f32>(),
+            Column::Uniform { .. } => 0,
+        }
+    }
+}
+
+// Points of a dataset, with the X and Y values in separate arrays. Points are handed out
+// as [x, y] pairs, so code reading them does not depend on how they are kept; changes go
+// through Dataset, which also keeps the uncertainties and caches in step.
+#[derive(Debug, Clone)]
+/// Data structure used in points.rs module
+pub struct PointStore {
+    x: Column,
+    y: Column,
+    single_precision: bool,
+}
+
+/// Implementation block defining methods for this type
+impl Default for PointStore {
+/// Function: explain its purpose and key arguments
+    fn default() -> Self {
+        Self::from_pairs(Vec::new(), false)
+    }
+}
+
+/// Implementation block defining methods for this type
+impl PointStore {
+/// Function: explain its purpose and key arguments
+    pub fn from_pairs(points: Vec<[f64; 2]>, single_precision: bool) -> Self {
+// Variable declaration
+        let (x, y): (Vec<f64>, Vec<f64>) = points.into_iter().map(|[x, y]| (x, y)).unzip();
+        Self {
+            x: Column::packed(x, single_precision),
+            y: Column::listed(y, single_precision),
+            single_precision,
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn len(&self) -> usize {
+        self.y.len()
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Point `i`; panics when there is no such point, like indexing a slice
+/// Function: explain its purpose and key arguments
+    pub fn point(&self, i: usize) -> [f64; 2] {
+        [self.x.get(i), self.y.get(i)]
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn get(&self, i: usize) -> Option<[f64; 2]> {
+        (i < self.len()).then(|| self.point(i))
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn iter(&self) -> PointIter<'_> {
+        PointIter {
+            store: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    // Number of points whose X is below `x`, for points in X order (like
+    // slice::partition_point)
+/// Function: explain its purpose and key arguments
+    pub fn partition_x(&self, x: f64) -> usize {
+// Variable declaration
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+// Variable declaration
+            let middle = (low + high) / 2;
+            if self.x.get(middle) < x {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+
+    // The points as [x, y] pairs, for code that works on slices
+/// Function: explain its purpose and key arguments
+    pub fn to_vec(&self) -> Vec<[f64; 2]> {
+        self.iter().collect()
+    }
+
+    // Whether the values are kept as 32-bit floats
+/// Function: explain its purpose and key arguments
+    pub fn single_precision(&self) -> bool {
+        self.single_precision
+    }
+
+    // Switch between 32-bit and 64-bit floats; going back to 64 bits does not bring back
+    // the digits dropped on the way to 32
+/// Function: explain its purpose and key arguments
+    pub fn set_single_precision(&mut self, single_precision: bool) {
+        if single_precision != self.single_precision {
+            *self = Self::from_pairs(self.to_vec(), single_precision);
+        }
+    }
+
+    // Bytes taken by the stored values
+/// Function: explain its purpose and key arguments
+    pub fn memory_bytes(&self) -> usize {
+        self.x.memory_bytes() + self.y.memory_bytes()
+    }
+
+    // Add points at the end, keeping the precision
+/// Function: explain its purpose and key arguments
+    pub fn extend(&mut self, points: &[[f64; 2]]) {
+        for [x, y] in points {
+            self.x.push(*x, self.single_precision);
+            self.y.push(*y, self.single_precision);
+        }
+    }
+
+    // Replace point `i`; does nothing when there is no such point
+/// Function: explain its purpose and key arguments
+    pub fn set(&mut self, i: usize, point: [f64; 2]) {
+        if i >= self.len() {
+            return;
+        }
+        self.x.set(i, point[0], self.single_precision);
+        self.y.set(i, point[1], self.single_precision);
+    }
+
+    // Remove point `i`; does nothing when there is no such point
+/// Function: explain its purpose and key arguments
+    pub fn remove(&mut self, i: usize) {
+        if i >= self.len() {
+            return;
+        }
+        self.x.remove(i, self.single_precision);
+        self.y.remove(i, self.single_precision);
+    }
+
+    // Keep only the points for which `keep` is true
+/// Function: explain its purpose and key arguments
+    pub fn retain(&mut self, keep: impl Fn(&[f64; 2]) -> bool) {
+        *self = Self::from_pairs(self.iter().filter(|p| keep(p)).collect(), self.single_precision);
+    }
+}
+
+/// Implementation block defining methods for this type
+impl From<Vec<[f64; 2]>> for PointStore {
+/// Function: explain its purpose and key arguments
+    fn from(points: Vec<[f64; 2]>) -> Self {
+        Self::from_pairs(points, false)
+    }
+}
+
+// Equal when the same points are kept with the same precision, however X is stored.
+// Coordinates compare bit for bit, so a store with blank (NaN) values equals its clone.
+/// Implementation block defining methods for this type
+impl PartialEq for PointStore {
+/// Function: explain its purpose and key arguments
+    fn eq(&self, other: &Self) -> bool {
+        self.single_precision == other.single_precision
+            && self.len() == other.len()
+            && self.iter().zip(other.iter()).all(|(a, b)| a.map(f64::to_bits) == b.map(f64::to_bits))
+    }
+}
+
+// Points of a PointStore as [x, y] pairs, from either end
+/// Data structure used in points.rs module
+pub struct PointIter<'a> {
+    store: &'a PointStore,
+    front: usize,
+    back: usize,
+}
+
+/// Implementation block defining methods for this type
+impl Iterator for PointIter<'_> {
+    type Item = [f64; 2];
+
+/// Function: explain its purpose and key arguments
+    fn next(&mut self) -> Option<[f64; 2]> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.store.point(self.front - 1))
+    }
+
+/// Function: explain its purpose and key arguments
+    fn size_hint(&self) -> (usize, Option<usize>) {
+// Variable declaration
+        let remaining = self.back - self.front;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Implementation block defining methods for this type
+impl DoubleEndedIterator for PointIter<'_> {
+/// Function: explain its purpose and key arguments
+    fn next_back(&mut self) -> Option<[f64; 2]> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.store.point(self.back))
+    }
+}
+
+/// Implementation block defining methods for this type
+impl ExactSizeIterator for PointIter<'_> {}
+
+/// Implementation block defining methods for this type
+impl<'a> IntoIterator for &'a PointStore {
+    type Item = [f64; 2];
+    type IntoIter = PointIter<'a>;
+
+/// Function: explain its purpose and key arguments
+    fn into_iter(self) -> PointIter<'a> {
+        self.iter()
+    }
+}
+
+// Points as saved in sessions: a list of [x, y] pairs as before, wrapped together with
+// the precision when they are kept as 32-bit floats. JSON has no NaN, so a missing value
+// is written as null and read back as NaN.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+/// Enum representing a set of related values in points.rs module
+enum StoredPoints {
+    Double(Vec<[Option<f64>; 2]>),
+    Single { single_precision: bool, points: Vec<[Option<f64>; 2]> },
+}
+
+/// Implementation block defining methods for this type
+impl Serialize for PointStore {
+/// Function: explain its purpose and key arguments
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+// Variable declaration
+        let points = self.iter().map(|p| p.map(|v| v.is_finite().then_some(v))).collect();
+        if self.single_precision {
+            StoredPoints::Single { single_precision: true, points }.serialize(serializer)
+        } else {
+            StoredPoints::Double(points).serialize(serializer)
+        }
+    }
+}
+
+/// Implementation block defining methods for this type
+impl<'de> Deserialize<'de> for PointStore {
+/// Function: explain its purpose and key arguments
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+// Variable declaration
+        let (points, single_precision) = match StoredPoints::deserialize(deserializer)? {
+            StoredPoints::Double(points) => (points, false),
+            StoredPoints::Single { single_precision, points } => (points, single_precision),
+        };
+        Ok(Self::from_pairs(
+            points.into_iter().map(|p| p.map(|v| v.unwrap_or(f64::NAN))).collect(),
+            single_precision,
+        ))
+    }
+}
@@ -0,0 +1,122 @@
+// Import external modules or crates needed in remote.rs
+use crate::compression::data_extension;
+// Import external modules or crates needed in remote.rs
+use crate::config::cache_dir;
+// Import external modules or crates needed in remote.rs
+use crate::utils::DATA_FILE_EXTENSIONS;
+// Import external modules or crates needed in remote.rs
+use std::collections::hash_map::DefaultHasher;
+// Import external modules or crates needed in remote.rs
+use std::hash::{Hash, Hasher};
+// Import external modules or crates needed in remote.rs
+use std::path::{Path, PathBuf};
+// Import external modules or crates needed in remote.rs
+use std::time::Duration;
+
+// A downloaded file younger than this is used again instead of fetching the URL anew
+pub const CACHE_MAX_AGE: Duration = Duration::from_secs(15 * 60);
+
+// Longest wait for the server to answer or send more of the file
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Function: explain its purpose and key arguments
+pub fn is_url(text: &str) -> bool {
+// Variable declaration
+    let text = text.trim_start().to_lowercase();
+    text.starts_with("http://") || text.starts_with("https://")
+}
+
+// Name for the downloaded copy: the last part of the URL path when it names a data file,
+// otherwise that name (or "download") with an extension guessed from the content type,
+// so the copy is read with the right parser
+/// Function: explain its purpose and key arguments
+fn download_name(url: &str, content_type: &str) -> String {
+// Variable declaration
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+// Variable declaration
+    let path = without_scheme.split(['?', '#']).next().unwrap_or_default();
+// Variable declaration
+    let last = path.split_once('/').map_or("", |(_, path)| path.rsplit('/').next().unwrap_or_default());
+// Variable declaration
+    let name: String = last
+        .chars()
+        .map(|c| if matches!(c, ':' | '*' | '?' | '"' | '<' | '>' | '|' | '\\') { '_' } else { c })
+        .collect();
+    if data_extension(Path::new(&name)).is_some_and(|ext| DATA_FILE_EXTENSIONS.contains(&ext.as_str())) {
+        return name;
+    }
+// Variable declaration
+    let extension = match content_type {
+        "text/csv" => "csv",
+        "application/json" => "json",
+        _ => "txt", // Read as a text table, with the delimiter sniffed from the content
+    };
+// Variable declaration
+    let base = if name.is_empty() { "download" } else { name.as_str() };
+    format!("{}.{}", base, extension)
+}
+
+// Folder that holds the downloaded copy of `url`, one per URL
+/// Function: explain its purpose and key arguments
+fn cache_folder(url: &str) -> Option<PathBuf> {
+// Variable declaration
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    Some(cache_dir()?.join("downloads").join(format!("{:016x}", hasher.finish())))
+}
+
+// The copy of a URL downloaded earlier, if it is younger than `max_age`
+/// Function: explain its purpose and key arguments
+fn cached_copy(folder: &Path, max_age: Duration) -> Option<PathBuf> {
+    std::fs::read_dir(folder).ok()?.flatten().map(|entry| entry.path()).find(|path| {
+        path.extension().is_none_or(|ext| ext != "part")
+            && std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < max_age))
+    })
+}
+
+// Local copy of the file at `url` (HTTP or HTTPS), downloaded unless a copy younger than
+// `max_age` is cached. None as `max_age` always downloads. Runs until the download is
+// complete, so the GUI calls it from a worker thread.
+/// Function: explain its purpose and key arguments
+pub fn fetch(url: &str, max_age: Option<Duration>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+// Variable declaration
+    let url = url.trim();
+    if !is_url(url) {
+        return Err(format!("'{}' is not an http:// or https:// address", url).into());
+    }
+// Variable declaration
+    let folder = cache_folder(url).ok_or("Cannot find the home directory for the download cache")?;
+    if let Some(cached) = max_age.and_then(|max_age| cached_copy(&folder, max_age)) {
+        return Ok(cached);
+    }
+
+    // The timeouts apply to connecting and to each read, so a large file on a slow but
+    // steady connection still comes through
+// Variable declaration
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(DOWNLOAD_TIMEOUT)
+        .timeout_read(DOWNLOAD_TIMEOUT)
+        .build();
+// Variable declaration
+    let response = agent.get(url).call()?;
+// Variable declaration
+    let name = download_name(url, response.content_type());
+    // Written next to the final name first, so an interrupted download is never taken
+    // for a complete copy
+    std::fs::create_dir_all(&folder)?;
+// Variable declaration
+    let partial = folder.join(format!("{}.part", name));
+    std::io::copy(&mut response.into_reader(), &mut std::fs::File::create(&partial)?)?;
+    // Copies saved under an earlier name (the content type may have changed) go away
+    for entry in std::fs::read_dir(&folder)?.flatten() {
+        if entry.path() != partial {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+// Variable declaration
+    let path = folder.join(name);
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
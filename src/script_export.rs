@@ -0,0 +1,700 @@
+// Import external modules or crates needed in script_export.rs
+use crate::annotation::{Annotation, AnnotationKind};
+// Import external modules or crates needed in script_export.rs
+use crate::app::{ExportSettings, FontSize, LegendPosition, ScaleKind, Subplot, SubplotLayout};
+// Import external modules or crates needed in script_export.rs
+use crate::dataset::{
+    distribution_positions, fill_baseline, stacked_datasets, visible_datasets, AxisMap, Dataset, LinePattern, MarkerStyle,
+    PlotType, DISTRIBUTION_WIDTH, FILL_OPACITY,
+};
+// Import external modules or crates needed in script_export.rs
+use crate::palette::Colormap;
+// Import external modules or crates needed in script_export.rs
+use crate::utils::{
+    bar_width, export_colors, export_plot_bounds, parse_custom_ticks, place_secondary_datasets, secondary_axis,
+    subplot_display_title, BASE_FONT_PX,
+};
+// Import external modules or crates needed in script_export.rs
+use std::fmt::Write;
+
+// Script export writes a standalone Python program that redraws the figure with
+// matplotlib: the data is embedded as NumPy arrays, followed by the same colors, bounds,
+// labels, legend and layout as the exported image. Sizes in pixels of the export are
+// converted to points at the export DPI, so the script's figure prints at the same size.
+
+// Values per line of an embedded data array
+const VALUES_PER_LINE: usize = 8;
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+// Render the subplot grid as a matplotlib script. Subplot titles count up from `first_number`.
+/// Function: explain its purpose and key arguments
+pub fn render_subplots_script(
+    subplots: &[Subplot],
+    layout: &SubplotLayout,
+    first_number: usize,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if subplots.is_empty() {
+        return Err("No subplots to export".into());
+    }
+// Variable declaration
+    let (rows, cols) = layout.dimensions();
+// Variable declaration
+    let dpi = settings.dpi.max(1) as f64;
+// Variable declaration
+    let pt = 72.0 / dpi; // Points per pixel of the export
+// Variable declaration
+    let (background, grid, axis, text) = export_colors(dark_mode);
+
+// Variable declaration
+    let mut out = String::new();
+    writeln!(out, "#!/usr/bin/env python3")?;
+    writeln!(out, "# Figure exported from CactusPlot. Run it to show the figure, or pass a file name")?;
+    writeln!(out, "# (e.g. figure.pdf) to save it there; edit the calls below to restyle it.")?;
+    writeln!(out, "import sys")?;
+    writeln!(out)?;
+    writeln!(out, "import matplotlib.pyplot as plt")?;
+    writeln!(out, "import numpy as np")?;
+    writeln!(out, "from matplotlib.collections import LineCollection")?;
+    writeln!(out)?;
+    writeln!(out, "plt.rcParams.update({{")?;
+    writeln!(out, "    \"font.size\": {},", py_points((BASE_FONT_PX * font_size.to_scale()) as f64, pt))?;
+    writeln!(out, "    \"axes.titlesize\": \"large\",")?;
+    writeln!(out, "    \"figure.facecolor\": {},", py_color(background.0))?;
+    writeln!(out, "    \"axes.facecolor\": {},", py_color(background.0))?;
+    writeln!(out, "    \"legend.facecolor\": {},", py_color(background.0))?;
+    writeln!(out, "    \"axes.edgecolor\": {},", py_color(axis.0))?;
+    writeln!(out, "    \"grid.color\": {},", py_color(grid.0))?;
+    for key in ["text.color", "axes.labelcolor", "xtick.color", "ytick.color"] {
+        writeln!(out, "    \"{}\": {},", key, py_color(text.0))?;
+    }
+    writeln!(out, "}})")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "fig = plt.figure(figsize=({}, {}), dpi={}, layout=\"constrained\")",
+        py_number(settings.width as f64 / dpi),
+        py_number(settings.height as f64 / dpi),
+        py_number(dpi * settings.scale as f64),
+    )?;
+    writeln!(
+        out,
+        "grid = fig.add_gridspec({}, {}, height_ratios={}, width_ratios={})",
+        rows,
+        cols,
+        py_ratios((0..rows).map(|row| layout.row_weight(row))),
+        py_ratios((0..cols).map(|col| layout.col_weight(col))),
+    )?;
+
+    for (index, (subplot, cell)) in subplots.iter().zip(layout.cells()).enumerate() {
+        writeln!(out)?;
+        writeln!(out, "# {}", subplot_display_title(&subplot.config.title, first_number + index))?;
+        writeln!(
+            out,
+            "ax = fig.add_subplot(grid[{}:{}, {}:{}])",
+            cell.row,
+            cell.row + cell.row_span,
+            cell.col,
+            cell.col + cell.col_span
+        )?;
+        writeln!(out, "ax.set_title({})", py_string(&subplot_display_title(&subplot.config.title, first_number + index)))?;
+        write_subplot_script(&mut out, subplot, pt)?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "if len(sys.argv) > 1:")?;
+    writeln!(out, "    fig.savefig(sys.argv[1])")?;
+    writeln!(out, "else:")?;
+    writeln!(out, "    plt.show()")?;
+    Ok(out)
+}
+
+// Statements drawing one subplot on `ax`, below its title; datasets on the right-hand axis go
+// on a twin `ax2`, and a zoom inset on inset axes of its own
+/// Function: explain its purpose and key arguments
+fn write_subplot_script(out: &mut String, subplot: &Subplot, pt: f64) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let time_axes = [subplot.config.x_scale.is_time(), subplot.config.y_scale.is_time()];
+// Variable declaration
+    let in_days;
+// Variable declaration
+    let subplot = if time_axes.contains(&true) {
+        in_days = with_time_axes_in_days(subplot);
+        &in_days
+    } else {
+        subplot
+    };
+// Variable declaration
+    let config = &subplot.config;
+    if time_axes[0] {
+        writeln!(out, "ax.xaxis_date()")?;
+    }
+    if time_axes[1] {
+        writeln!(out, "ax.yaxis_date()")?;
+    }
+    if !config.x_label.is_empty() {
+        writeln!(out, "ax.set_xlabel({})", py_string(&config.x_label))?;
+    }
+    if !config.y_label.is_empty() {
+        writeln!(out, "ax.set_ylabel({})", py_string(&config.y_label))?;
+    }
+
+// Variable declaration
+    let visible = visible_datasets(&subplot.datasets);
+    if visible.is_empty() && subplot.heatmaps.is_empty() {
+        return Ok(());
+    }
+
+// Variable declaration
+    let (x_scale, y_scale) = (config.x_axis(), config.y_axis());
+    if x_scale.is_log() {
+        writeln!(out, "ax.set_xscale(\"log\")")?;
+    }
+    if y_scale.is_log() {
+        writeln!(out, "ax.set_yscale(\"log\")")?;
+    }
+// Variable declaration
+    let mut stacked = visible;
+    stacked_datasets(&mut stacked);
+// Variable declaration
+    let secondary = secondary_axis(&stacked, config);
+    if secondary.is_some() {
+        writeln!(out, "ax2 = ax.twinx()")?;
+        if !config.y2_label.is_empty() {
+            writeln!(out, "ax2.set_ylabel({})", py_string(&config.y2_label))?;
+        }
+    }
+
+    // Heatmaps underneath the datasets, as in the image export
+    if !x_scale.is_log() && !y_scale.is_log() {
+        for heatmap in &subplot.heatmaps {
+// Variable declaration
+            let Some((low, high)) = heatmap.value_range() else {
+                continue;
+            };
+            writeln!(out, "# {}", heatmap.name)?;
+            writeln!(out, "x_edges = {}", py_array(heatmap.x_edges()))?;
+            writeln!(out, "y_edges = {}", py_array(heatmap.y_edges()))?;
+            writeln!(out, "values = {}.reshape({}, {})", py_array(heatmap.values.iter().copied()), heatmap.y.len(), heatmap.x.len())?;
+            writeln!(
+                out,
+                "mesh = ax.pcolormesh(x_edges, y_edges, np.ma.masked_invalid(values), cmap={}, vmin={}, vmax={})",
+                py_colormap(heatmap.colormap),
+                py_number(low),
+                py_number(high)
+            )?;
+            writeln!(out, "fig.colorbar(mesh, ax=ax, label={})", py_string(&heatmap.label))?;
+        }
+    }
+
+    for (index, (dataset, position)) in stacked.iter().zip(distribution_positions(&stacked)).enumerate() {
+// Variable declaration
+        let target = if dataset.secondary_y && secondary.is_some() { "ax2" } else { "ax" };
+        writeln!(out, "# {}", dataset.name)?;
+        match position {
+            Some(position) => write_distribution_script(out, dataset, position, target, pt)?,
+            None => write_dataset_script(out, dataset, fill_baseline(&stacked, index), target, pt)?,
+        }
+    }
+
+    for annotation in &subplot.annotations {
+        write_annotation_script(out, annotation, pt)?;
+    }
+
+    // Same bounds as the exported image; the right axis spans the values lined up with them
+// Variable declaration
+    let mut placed = stacked;
+    place_secondary_datasets(&mut placed, secondary, y_scale);
+// Variable declaration
+    let (min_x, max_x, min_y, max_y) = export_plot_bounds(subplot, &placed)?;
+    writeln!(out, "ax.set_xlim({}, {})", py_number(x_scale.invert(min_x)), py_number(x_scale.invert(max_x)))?;
+    writeln!(out, "ax.set_ylim({}, {})", py_number(y_scale.invert(min_y)), py_number(y_scale.invert(max_y)))?;
+    if let Some(axis) = secondary {
+        writeln!(out, "ax2.set_ylim({}, {})", py_number(axis.to_value(min_y)), py_number(axis.to_value(max_y)))?;
+    }
+    // Reversed axes come out of the limits above, given largest first
+    if config.equal_aspect {
+        writeln!(out, "ax.set_aspect(\"equal\", adjustable=\"datalim\")")?;
+    }
+    for (ticks, enabled, setter) in [
+        (&config.custom_x_ticks, config.use_custom_x_ticks, "set_xticks"),
+        (&config.custom_y_ticks, config.use_custom_y_ticks, "set_yticks"),
+    ] {
+// Variable declaration
+        let ticks = parse_custom_ticks(ticks);
+        if enabled && !ticks.is_empty() {
+            writeln!(out, "ax.{}({})", setter, py_list(ticks))?;
+        }
+    }
+
+    if config.show_grid {
+// Variable declaration
+        let style = py_line_style(config.grid_style);
+        writeln!(
+            out,
+            "ax.grid(True, which=\"major\", linestyle=\"{}\", linewidth={}, alpha={:?})",
+            style,
+            py_points(1.0, pt),
+            config.grid_opacity
+        )?;
+        if config.grid_minor {
+            writeln!(out, "ax.minorticks_on()")?;
+            writeln!(
+                out,
+                "ax.grid(True, which=\"minor\", linestyle=\"{}\", linewidth={}, alpha={:?})",
+                style,
+                py_points(1.0, pt),
+                config.grid_opacity * 0.4
+            )?;
+        }
+    }
+
+    if config.show_legend {
+// Variable declaration
+        let mut options = vec![
+            format!("ncol={}", config.legend_columns.max(1)),
+            format!("fontsize={}", py_points(config.legend_font_size as f64, pt)),
+            format!("framealpha={:?}", config.legend_opacity),
+        ];
+        if !config.legend_title.is_empty() {
+            options.push(format!("title={}", py_string(&config.legend_title)));
+        }
+        if !config.legend_border {
+            options.push("edgecolor=\"none\"".to_string());
+        }
+        options.push(match config.legend_position {
+            LegendPosition::OutsideRight => "loc=\"upper left\", bbox_to_anchor=(1.02, 1.0)".to_string(),
+            position => format!("loc=\"{}\"", legend_location(position)),
+        });
+        if secondary.is_some() {
+            // One legend for the datasets on both axes, drawn above the right axis' lines
+            writeln!(out, "handles, labels = ax.get_legend_handles_labels()")?;
+            writeln!(out, "handles2, labels2 = ax2.get_legend_handles_labels()")?;
+            writeln!(out, "ax2.legend(handles + handles2, labels + labels2, {})", options.join(", "))?;
+        } else {
+            writeln!(out, "ax.legend({})", options.join(", "))?;
+        }
+    }
+
+    // The inset's placement is measured from the top, inset_axes' from the bottom. Fractions
+    // are rounded so the f32 placement does not print as 0.550000011920929.
+    if let Some((inset, zoomed)) = config.inset.and_then(|inset| Some((inset, inset.subplot(subplot)?))) {
+// Variable declaration
+        let [left, top, width, height] = inset.placement.map(|fraction| fraction as f64);
+// Variable declaration
+        let fraction = |value: f64| py_number((value * 1e4).round() / 1e4);
+        writeln!(out, "# Zoom inset")?;
+        writeln!(out, "parent = ax")?;
+        writeln!(
+            out,
+            "ax = parent.inset_axes([{}, {}, {}, {}])",
+            fraction(left),
+            fraction(1.0 - top - height),
+            fraction(width),
+            fraction(height)
+        )?;
+        write_subplot_script(out, &zoomed, pt)?;
+        if time_axes[0] {
+            writeln!(out, "ax.xaxis_date()")?;
+        }
+        if time_axes[1] {
+            writeln!(out, "ax.yaxis_date()")?;
+        }
+        writeln!(out, "parent.indicate_inset_zoom(ax)")?;
+        writeln!(out, "ax = parent")?;
+    }
+    Ok(())
+}
+
+// matplotlib places dates by days since 1970-01-01 rather than seconds, so a copy of the
+// subplot with the values on its time axes in days, drawn as on linear axes
+/// Function: explain its purpose and key arguments
+fn with_time_axes_in_days(subplot: &Subplot) -> Subplot {
+// Variable declaration
+    let mut subplot = subplot.clone();
+    for axis in 0..2 {
+// Variable declaration
+        let scale = if axis == 0 { &mut subplot.config.x_scale } else { &mut subplot.config.y_scale };
+        if !scale.is_time() {
+            continue;
+        }
+        *scale = ScaleKind::Linear;
+// Variable declaration
+        let map = AxisMap { axis, factor: 1.0 / SECONDS_PER_DAY, offset: 0.0 };
+        subplot.map_axis(&map);
+        if let Some((min_x, max_x, min_y, max_y)) = subplot.view_bounds.as_mut() {
+// Variable declaration
+            let (low, high) = if axis == 0 { (min_x, max_x) } else { (min_y, max_y) };
+            *low = map.map(*low);
+            *high = map.map(*high);
+        }
+    }
+    subplot
+}
+
+// Statements drawing a dataset point by point: band, fill, error bars, bars, line and markers
+/// Function: explain its purpose and key arguments
+fn write_dataset_script(
+    out: &mut String,
+    dataset: &Dataset,
+    baseline: Option<Vec<Option<f64>>>,
+    ax: &str,
+    pt: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let color = py_color(dataset.color);
+// Variable declaration
+    let label = py_string(&dataset.name);
+// Variable declaration
+    let line_width = py_points(dataset.line_width.max(0.5) as f64, pt);
+    writeln!(out, "x = {}", py_array(dataset.points.iter().map(|p| p[0])))?;
+    writeln!(out, "y = {}", py_array(dataset.points.iter().map(|p| p[1])))?;
+
+    if let Some((upper, lower)) = dataset.band_edges() {
+        writeln!(
+            out,
+            "{}.fill_between(x, {}, {}, color={}, alpha={}, linewidth=0)",
+            ax,
+            py_array(lower.into_iter().map(|v| v.unwrap_or(f64::NAN))),
+            py_array(upper.iter().map(|p| p[1])),
+            color,
+            FILL_OPACITY
+        )?;
+    }
+    if let Some(baseline) = baseline {
+        writeln!(
+            out,
+            "{}.fill_between(x, y, {}, color={}, alpha={}, linewidth=0)",
+            ax,
+            py_array(baseline.into_iter().map(|v| v.unwrap_or(f64::NAN))),
+            color,
+            FILL_OPACITY
+        )?;
+    }
+    if dataset.has_error_bars() {
+// Variable declaration
+        let errors = |values: &Option<Vec<f64>>| match values {
+            Some(values) => py_array(values.iter().copied()),
+            None => "None".to_string(),
+        };
+        writeln!(
+            out,
+            "{}.errorbar(x, y, xerr={}, yerr={}, fmt=\"none\", ecolor={}, elinewidth={}, capsize={})",
+            ax,
+            errors(&dataset.x_err),
+            errors(&dataset.y_err),
+            color,
+            py_points(1.0, pt),
+            py_points(3.0, pt)
+        )?;
+    }
+    if dataset.plot_type.draws_bars() {
+        writeln!(
+            out,
+            "{}.bar(x, y, width={}, color={}, alpha={}, edgecolor={}, linewidth={}, label={})",
+            ax,
+            py_number(bar_width(&dataset.points)),
+            color,
+            FILL_OPACITY,
+            color,
+            py_points(1.0, pt),
+            label
+        )?;
+        return Ok(());
+    }
+
+// Variable declaration
+    let style = py_line_style(dataset.line_style);
+// Variable declaration
+    let marker_diameter = 2.0 * dataset.marker_size as f64;
+// Variable declaration
+    let marker = py_marker(dataset.marker);
+    // Value-colored datasets: a line collection and scatter markers along the colormap
+    if let Some((low, high)) = dataset.color_range() {
+// Variable declaration
+        let values = (0..dataset.points.len()).map(|i| dataset.color_value(i).unwrap_or(f64::NAN));
+        writeln!(out, "c = {}", py_array(values))?;
+// Variable declaration
+        let colormap = format!("cmap={}, norm=plt.Normalize({}, {})", py_colormap(dataset.colormap), py_number(low), py_number(high));
+// Variable declaration
+        let mut labelled = false;
+        if dataset.plot_type.draws_line() {
+            writeln!(out, "points = np.column_stack([x, y]).reshape(-1, 1, 2)")?;
+            writeln!(
+                out,
+                "line = LineCollection(np.concatenate([points[:-1], points[1:]], axis=1), {}, linewidths={}, linestyles=\"{}\", label={})",
+                colormap, line_width, style, label
+            )?;
+            writeln!(out, "line.set_array(c[:-1])")?;
+            writeln!(out, "{}.add_collection(line)", ax)?;
+            writeln!(out, "fig.colorbar(line, ax=ax)")?;
+            labelled = true;
+        }
+        if dataset.plot_type.draws_markers() {
+            writeln!(
+                out,
+                "markers = {}.scatter(x, y, c=c, {}, marker=\"{}\", s={}, label={})",
+                ax,
+                colormap,
+                marker,
+                py_number((marker_diameter * pt).powi(2).round()), // scatter() takes the area in points²
+                if labelled { "\"_nolegend_\"".to_string() } else { label }
+            )?;
+            if !labelled {
+                writeln!(out, "fig.colorbar(markers, ax=ax)")?;
+            }
+        }
+        return Ok(());
+    }
+
+// Variable declaration
+    let (line, marker) = match (dataset.plot_type.draws_line(), dataset.plot_type.draws_markers()) {
+        (true, true) => (style, marker),
+        (true, false) => (style, ""),
+        (false, _) => ("none", marker),
+    };
+    writeln!(
+        out,
+        "{}.plot(x, y, color={}, linewidth={}, linestyle=\"{}\", marker=\"{}\", markersize={}, label={})",
+        ax,
+        color,
+        line_width,
+        line,
+        marker,
+        py_points(marker_diameter, pt),
+        label
+    )?;
+    Ok(())
+}
+
+// Statements drawing a box plot or violin of a dataset's Y values at X = `position`
+/// Function: explain its purpose and key arguments
+fn write_distribution_script(
+    out: &mut String,
+    dataset: &Dataset,
+    position: f64,
+    ax: &str,
+    pt: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let color = py_color(dataset.color);
+// Variable declaration
+    let line_width = py_points(dataset.line_width.round().max(1.0) as f64, pt);
+    writeln!(out, "y = {}", py_array(dataset.points.iter().map(|p| p[1])))?;
+    writeln!(out, "y = y[np.isfinite(y)]")?;
+    if dataset.plot_type == PlotType::Violin {
+        writeln!(
+            out,
+            "violin = {}.violinplot([y], positions=[{}], widths={}, showmedians=True)",
+            ax,
+            py_number(position),
+            DISTRIBUTION_WIDTH
+        )?;
+        writeln!(out, "for body in violin[\"bodies\"]:")?;
+        writeln!(
+            out,
+            "    body.set(facecolor={}, edgecolor={}, alpha={}, linewidth={}, label={})",
+            color,
+            color,
+            FILL_OPACITY,
+            line_width,
+            py_string(&dataset.name)
+        )?;
+        writeln!(out, "for part in (\"cbars\", \"cmins\", \"cmaxes\", \"cmedians\"):")?;
+        writeln!(out, "    violin[part].set(color={}, linewidth={})", color, line_width)?;
+        return Ok(());
+    }
+// Variable declaration
+    let line = format!("dict(color={}, linewidth={})", color, line_width);
+    writeln!(
+        out,
+        "boxes = {}.boxplot([y], positions=[{}], widths={}, patch_artist=True, manage_ticks=False,",
+        ax,
+        py_number(position),
+        DISTRIBUTION_WIDTH
+    )?;
+    writeln!(
+        out,
+        "    boxprops=dict(facecolor={}, edgecolor={}, linewidth={}), medianprops={}, whiskerprops={}, capprops={},",
+        py_color_alpha(dataset.color, FILL_OPACITY),
+        color,
+        line_width,
+        line,
+        line,
+        line
+    )?;
+    writeln!(out, "    flierprops=dict(markerfacecolor={}, markeredgecolor={}))", color, color)?;
+    writeln!(out, "boxes[\"boxes\"][0].set_label({})", py_string(&dataset.name))?;
+    Ok(())
+}
+
+// Statements drawing an annotation in data coordinates of the left axis
+/// Function: explain its purpose and key arguments
+fn write_annotation_script(out: &mut String, annotation: &Annotation, pt: f64) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let color = py_color(annotation.color);
+// Variable declaration
+    let [x, y] = annotation.position.map(py_number);
+// Variable declaration
+    let text = py_string(&annotation.text);
+    match annotation.kind {
+        AnnotationKind::Text => {
+            writeln!(out, "ax.text({}, {}, {}, color={}, ha=\"center\", va=\"center\", clip_on=True)", x, y, text, color)?;
+            return Ok(());
+        }
+        AnnotationKind::Arrow => {
+// Variable declaration
+            let [tail_x, tail_y] = annotation.label_position().map(py_number);
+            writeln!(
+                out,
+                "ax.annotate({}, xy=({}, {}), xytext=({}, {}), color={}, arrowprops=dict(arrowstyle=\"->\", color={}, linewidth={}))",
+                text,
+                x,
+                y,
+                tail_x,
+                tail_y,
+                color,
+                color,
+                py_points(2.0, pt)
+            )?;
+            return Ok(());
+        }
+        AnnotationKind::Marker => {
+            writeln!(out, "ax.plot([{}], [{}], marker=\"o\", color={}, markersize={})", x, y, color, py_points(10.0, pt))?;
+        }
+        AnnotationKind::VLine => {
+            writeln!(out, "ax.axvline({}, color={}, linestyle=\"--\", linewidth={})", x, color, py_points(1.0, pt))?;
+        }
+    }
+    if !annotation.text.is_empty() {
+        // Beside the marker or line, as in the image export
+        writeln!(
+            out,
+            "ax.annotate({}, xy=({}, {}), xytext=(5, 3), textcoords=\"offset points\", color={})",
+            text, x, y, color
+        )?;
+    }
+    Ok(())
+}
+
+// Python literal for a number; NumPy names the values Python has no literal for
+/// Function: explain its purpose and key arguments
+fn py_number(value: f64) -> String {
+    if value.is_nan() {
+        "np.nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "np.inf" } else { "-np.inf" }.to_string()
+    } else {
+        // Debug formatting is the shortest exact form, with an exponent when large or small
+        format!("{:?}", value)
+    }
+}
+
+// Size in points of `pixels` export pixels, to a hundredth of a point
+/// Function: explain its purpose and key arguments
+fn py_points(pixels: f64, pt: f64) -> String {
+    py_number((pixels * pt * 100.0).round() / 100.0)
+}
+
+// Python list of numbers, wrapped every VALUES_PER_LINE values
+/// Function: explain its purpose and key arguments
+fn py_list(values: impl IntoIterator<Item = f64>) -> String {
+// Variable declaration
+    let values: Vec<String> = values.into_iter().map(py_number).collect();
+    if values.len() <= VALUES_PER_LINE {
+        return format!("[{}]", values.join(", "));
+    }
+// Variable declaration
+    let lines: Vec<String> = values.chunks(VALUES_PER_LINE).map(|chunk| format!("    {},", chunk.join(", "))).collect();
+    format!("[\n{}\n]", lines.join("\n"))
+}
+
+// Row or column weights of the layout; printed as f32 so 1.2 stays 1.2
+/// Function: explain its purpose and key arguments
+fn py_ratios(weights: impl IntoIterator<Item = f32>) -> String {
+// Variable declaration
+    let weights: Vec<String> = weights.into_iter().map(|weight| format!("{:?}", weight)).collect();
+    format!("[{}]", weights.join(", "))
+}
+
+/// Function: explain its purpose and key arguments
+fn py_array(values: impl IntoIterator<Item = f64>) -> String {
+    format!("np.array({}, dtype=float)", py_list(values))
+}
+
+// Double-quoted Python string literal
+/// Function: explain its purpose and key arguments
+fn py_string(text: &str) -> String {
+// Variable declaration
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Function: explain its purpose and key arguments
+fn py_color([r, g, b]: [u8; 3]) -> String {
+    format!("\"#{:02x}{:02x}{:02x}\"", r, g, b)
+}
+
+// Color with opacity as "#rrggbbaa"
+/// Function: explain its purpose and key arguments
+fn py_color_alpha([r, g, b]: [u8; 3], alpha: f64) -> String {
+    format!("\"#{:02x}{:02x}{:02x}{:02x}\"", r, g, b, (alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Function: explain its purpose and key arguments
+fn py_line_style(pattern: LinePattern) -> &'static str {
+    match pattern {
+        LinePattern::Solid => "-",
+        LinePattern::Dashed => "--",
+        LinePattern::Dotted => ":",
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn py_marker(marker: MarkerStyle) -> &'static str {
+    match marker {
+        MarkerStyle::Circle => "o",
+        MarkerStyle::Square => "s",
+        MarkerStyle::Triangle => "^",
+        MarkerStyle::Diamond => "D",
+        MarkerStyle::Cross => "x",
+    }
+}
+
+// Closest matplotlib colormap (its gray map spans black to white, ours stops short of both)
+/// Function: explain its purpose and key arguments
+fn py_colormap(colormap: Colormap) -> &'static str {
+    match colormap {
+        Colormap::Viridis => "\"viridis\"",
+        Colormap::Plasma => "\"plasma\"",
+        Colormap::Coolwarm => "\"coolwarm\"",
+        Colormap::Grayscale => "\"gray\"",
+    }
+}
+
+// Legend location as matplotlib's `loc` names it
+/// Function: explain its purpose and key arguments
+fn legend_location(position: LegendPosition) -> &'static str {
+    match position {
+        LegendPosition::TopLeft => "upper left",
+        LegendPosition::TopRight => "upper right",
+        LegendPosition::BottomLeft => "lower left",
+        LegendPosition::BottomRight => "lower right",
+        LegendPosition::OutsideRight => "upper left",
+    }
+}
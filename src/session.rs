@@ -0,0 +1,103 @@
+// Import external modules or crates needed in session.rs
+use crate::app::{ExportSettings, FontSize, PlotterApp, Subplot, SubplotLayout};
+// Import external modules or crates needed in session.rs
+use crate::palette::Palette;
+// Import external modules or crates needed in session.rs
+use serde::{Deserialize, Serialize};
+// Import external modules or crates needed in session.rs
+use std::path::{Path, PathBuf};
+
+// Bump when the session layout changes in a way older versions cannot read
+pub const SESSION_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Data structure used in session.rs module
+pub struct Session {
+    pub version: u32,
+    pub subplots: Vec<Subplot>,
+    #[serde(default)]
+    pub hidden_subplots: Vec<Subplot>, // Left over from a larger layout
+    pub subplot_layout: SubplotLayout,
+    pub active_subplot: usize,
+    pub dark_mode: bool,
+    pub tick_font_size: FontSize,
+    pub next_name_index: usize,
+    #[serde(default)]
+    pub export_settings: ExportSettings,
+    #[serde(default)]
+    pub palette: Palette,
+}
+
+/// Implementation block defining methods for this type
+impl Session {
+    // Snapshot the persistent parts of the application state
+/// Function: explain its purpose and key arguments
+    pub fn from_app(app: &PlotterApp) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            subplots: app.subplots.clone(),
+            hidden_subplots: app.hidden_subplots.clone(),
+            subplot_layout: app.subplot_layout.clone(),
+            active_subplot: app.active_subplot,
+            dark_mode: app.dark_mode,
+            tick_font_size: app.tick_font_size.clone(),
+            next_name_index: app.next_name_index,
+            export_settings: app.export_settings,
+            palette: app.palette,
+        }
+    }
+
+    // Replace the application state with this session
+/// Function: explain its purpose and key arguments
+    pub fn apply_to(self, app: &mut PlotterApp) {
+        app.subplots = self.subplots;
+        app.hidden_subplots = self.hidden_subplots;
+        app.subplot_layout = self.subplot_layout;
+        app.active_subplot = self.active_subplot;
+        app.dark_mode = self.dark_mode;
+        app.tick_font_size = self.tick_font_size;
+        app.next_name_index = self.next_name_index;
+        app.export_settings = self.export_settings;
+        app.palette = self.palette;
+        app.ensure_subplots_match_layout();
+    }
+}
+
+/// Function: explain its purpose and key arguments
+pub fn save_session(app: &PlotterApp, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let json = serde_json::to_string(&Session::from_app(app))?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+/// Function: explain its purpose and key arguments
+pub fn load_session(path: &Path) -> Result<Session, Box<dyn std::error::Error>> {
+// Variable declaration
+    let contents = std::fs::read_to_string(path)?;
+// Variable declaration
+    let session: Session = serde_json::from_str(&contents)?;
+    if session.version > SESSION_VERSION {
+        return Err(format!(
+            "Session was saved by a newer CactusPlot (version {}, supported {})",
+            session.version, SESSION_VERSION
+        )
+        .into());
+    }
+    Ok(session)
+}
+
+/// Function: explain its purpose and key arguments
+pub fn pick_session_file() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("CactusPlot Session", &["cactus"])
+        .pick_file()
+}
+
+/// Function: explain its purpose and key arguments
+pub fn pick_session_save_path() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("CactusPlot Session", &["cactus"])
+        .set_file_name("session.cactus")
+        .save_file()
+}
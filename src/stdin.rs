@@ -0,0 +1,155 @@
+// Import external modules or crates needed in stdin.rs
+use crate::args::StdinFormat;
+// Import external modules or crates needed in stdin.rs
+use crate::io::{is_data_line, Delimiter, TextImport};
+// Import external modules or crates needed in stdin.rs
+use crate::utils::{collect_series, parse_xvg, LoadedFile, LoadedSeries, SeriesColumns};
+// Import external modules or crates needed in stdin.rs
+use crate::watch::LIVE_REPAINT_DELAY;
+// Import external modules or crates needed in stdin.rs
+use std::io::{BufRead, Read};
+// Import external modules or crates needed in stdin.rs
+use std::path::PathBuf;
+// Import external modules or crates needed in stdin.rs
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+/// Enum representing a set of related values in stdin.rs module
+pub enum StreamUpdate {
+    Started(LoadedFile),         // The first data has arrived: one series per dataset to create
+    Appended(Vec<LoadedSeries>), // Points from lines that arrived since the last poll
+}
+
+// How data lines are split into series, fixed by the lines before the first data row
+/// Data structure used in stdin.rs module
+struct StreamLayout {
+    delimiter: Delimiter,
+    x_col: usize,
+    columns: Vec<SeriesColumns>,
+}
+
+// Parse everything read so far: XVG directives and legends, or a CSV header row
+/// Function: explain its purpose and key arguments
+fn parse_start(format: StdinFormat, text: String) -> Result<(LoadedFile, StreamLayout), Box<dyn std::error::Error>> {
+    match format {
+        StdinFormat::Xvg => {
+// Variable declaration
+            let (loaded, columns) = parse_xvg(text.as_bytes())?;
+// Variable declaration
+            let layout = StreamLayout {
+                delimiter: Delimiter::Whitespace,
+                x_col: 0,
+                columns,
+            };
+            Ok((loaded, layout))
+        }
+        StdinFormat::Csv => {
+// Variable declaration
+            let import = TextImport::from_text(PathBuf::from("stdin.csv"), text, 0)?;
+            if import.column_count() < 2 {
+                return Err("Need at least two columns (X and Y)".into());
+            }
+// Variable declaration
+            let loaded = LoadedFile {
+                series: import.selected_series(),
+                metadata: import.metadata(),
+            };
+// Variable declaration
+            let layout = StreamLayout {
+                delimiter: import.delimiter,
+                x_col: import.x_col,
+                columns: import.series_columns(),
+            };
+            Ok((loaded, layout))
+        }
+    }
+}
+
+// Whole of standard input at once, for headless export
+/// Function: explain its purpose and key arguments
+pub fn read_stdin(format: StdinFormat) -> Result<LoadedFile, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut text = String::new();
+    std::io::stdin().lock().read_to_string(&mut text)?;
+    parse_start(format, text).map(|(loaded, _)| loaded)
+}
+
+// Lines piped on standard input, read on a background thread that wakes the UI shortly
+// after each one, so the plot grows while the producing program is still running
+/// Data structure used in stdin.rs module
+pub struct StdinStream {
+    lines: Receiver<String>,
+    format: StdinFormat,
+    pending: Vec<String>, // Lines before the first data row, needed to work out the layout
+    layout: Option<StreamLayout>,
+    pub finished: bool, // Standard input was closed
+}
+
+/// Implementation block defining methods for this type
+impl StdinStream {
+/// Function: explain its purpose and key arguments
+    pub fn spawn(format: StdinFormat, ctx: egui::Context) -> Self {
+// Variable declaration
+        let (sender, lines) = channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+// Variable declaration
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+                ctx.request_repaint_after(LIVE_REPAINT_DELAY);
+            }
+            // Dropping the sender tells the UI that the pipe is closed
+            ctx.request_repaint();
+        });
+        Self {
+            lines,
+            format,
+            pending: Vec::new(),
+            layout: None,
+            finished: false,
+        }
+    }
+
+    // Turn the lines received since the last call into datasets or new points
+/// Function: explain its purpose and key arguments
+    pub fn poll(&mut self) -> Result<Option<StreamUpdate>, Box<dyn std::error::Error>> {
+// Variable declaration
+        let mut received = Vec::new();
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => received.push(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+
+        if let Some(layout) = &self.layout {
+// Variable declaration
+            let data: Vec<&str> = received.iter().map(String::as_str).filter(|line| is_data_line(line)).collect();
+            if data.is_empty() {
+                return Ok(None);
+            }
+// Variable declaration
+            let rows = layout.delimiter.split(&data.join("\n"))?;
+// Variable declaration
+            let rows: Vec<Vec<&str>> = rows.iter().map(|row| row.iter().map(String::as_str).collect()).collect();
+            return Ok(Some(StreamUpdate::Appended(collect_series(&rows, layout.x_col, &layout.columns))));
+        }
+
+        self.pending.extend(received);
+        // Wait for the first data row (or the end of input) before fixing the layout
+        if (self.pending.is_empty() || !self.finished) && !self.pending.iter().any(|line| is_data_line(line)) {
+            return Ok(None);
+        }
+// Variable declaration
+        let (loaded, layout) = parse_start(self.format, std::mem::take(&mut self.pending).join("\n"))?;
+        self.layout = Some(layout);
+        Ok(Some(StreamUpdate::Started(loaded)))
+    }
+}
@@ -0,0 +1,179 @@
+// Import external modules or crates needed in templates.rs
+use crate::app::{ExportSettings, FontSize, LegendPosition, PlotterApp, SubplotConfig};
+// Import external modules or crates needed in templates.rs
+use crate::config::config_dir;
+// Import external modules or crates needed in templates.rs
+use crate::dataset::LinePattern;
+// Import external modules or crates needed in templates.rs
+use crate::palette::Palette;
+// Import external modules or crates needed in templates.rs
+use serde::{Deserialize, Serialize};
+// Import external modules or crates needed in templates.rs
+use std::path::PathBuf;
+
+// A named figure style kept in ~/.config/cactusplot/templates/<name>.json: theme, fonts,
+// colors, export size and the look of the subplots. It holds nothing about the data, so
+// titles, axis labels, ranges, ticks and units stay with each subplot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Data structure used in templates.rs module
+pub struct StyleTemplate {
+    pub name: String,
+    pub dark_mode: bool,
+    pub font_size: FontSize,
+    pub palette: Palette,
+    pub export_settings: ExportSettings,
+    #[serde(default)]
+    pub subplot: SubplotStyle,
+}
+
+// Settings of a subplot that belong to a template; the ones it leaves out describe the data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// Data structure used in templates.rs module
+pub struct SubplotStyle {
+    pub show_grid: bool,
+    pub grid_minor: bool,
+    pub grid_style: LinePattern,
+    pub grid_opacity: f32,
+    pub grid_spacing: f32,
+    pub show_legend: bool,
+    pub legend_title: String,
+    pub legend_position: LegendPosition,
+    pub legend_opacity: f32,
+    pub legend_border: bool,
+    pub legend_columns: usize,
+    pub legend_font_size: f32,
+    pub x_padding_percent: f64,
+    pub y_padding_percent: f64,
+    pub palette: Option<Palette>,
+}
+
+/// Implementation block defining methods for this type
+impl Default for SubplotStyle {
+/// Function: explain its purpose and key arguments
+    fn default() -> Self {
+        Self::from_config(&SubplotConfig::default())
+    }
+}
+
+/// Implementation block defining methods for this type
+impl SubplotStyle {
+/// Function: explain its purpose and key arguments
+    pub fn from_config(config: &SubplotConfig) -> Self {
+        Self {
+            show_grid: config.show_grid,
+            grid_minor: config.grid_minor,
+            grid_style: config.grid_style,
+            grid_opacity: config.grid_opacity,
+            grid_spacing: config.grid_spacing,
+            show_legend: config.show_legend,
+            legend_title: config.legend_title.clone(),
+            legend_position: config.legend_position,
+            legend_opacity: config.legend_opacity,
+            legend_border: config.legend_border,
+            legend_columns: config.legend_columns,
+            legend_font_size: config.legend_font_size,
+            x_padding_percent: config.x_padding_percent,
+            y_padding_percent: config.y_padding_percent,
+            palette: config.palette,
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn apply_to(&self, config: &mut SubplotConfig) {
+        config.show_grid = self.show_grid;
+        config.grid_minor = self.grid_minor;
+        config.grid_style = self.grid_style;
+        config.grid_opacity = self.grid_opacity;
+        config.grid_spacing = self.grid_spacing;
+        config.show_legend = self.show_legend;
+        config.legend_title = self.legend_title.clone();
+        config.legend_position = self.legend_position;
+        config.legend_opacity = self.legend_opacity;
+        config.legend_border = self.legend_border;
+        config.legend_columns = self.legend_columns;
+        config.legend_font_size = self.legend_font_size;
+        config.x_padding_percent = self.x_padding_percent;
+        config.y_padding_percent = self.y_padding_percent;
+        config.palette = self.palette;
+    }
+}
+
+/// Implementation block defining methods for this type
+impl StyleTemplate {
+    // Style of the current figure; subplot settings come from the active subplot
+/// Function: explain its purpose and key arguments
+    pub fn from_app(name: &str, app: &PlotterApp) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            dark_mode: app.dark_mode,
+            font_size: app.tick_font_size.clone(),
+            palette: app.palette,
+            export_settings: app.export_settings,
+            subplot: app
+                .get_active_subplot()
+                .map(|subplot| SubplotStyle::from_config(&subplot.config))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// $XDG_CONFIG_HOME/cactusplot/templates, next to config.toml
+/// Function: explain its purpose and key arguments
+fn templates_dir() -> Option<PathBuf> {
+    Some(config_dir()?.join("templates"))
+}
+
+// File of the template called `name`; characters that cannot appear in file names are
+// replaced, so names differing only in those share a file
+/// Function: explain its purpose and key arguments
+fn template_path(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+// Variable declaration
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    if stem.trim_matches('.').is_empty() {
+        return Err("The template needs a name".into());
+    }
+// Variable declaration
+    let dir = templates_dir().ok_or("Cannot find the home directory")?;
+    Ok(dir.join(format!("{}.json", stem)))
+}
+
+// Saved templates sorted by name; files that no longer parse are skipped
+/// Function: explain its purpose and key arguments
+pub fn load_templates() -> Result<Vec<StyleTemplate>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let dir = templates_dir().ok_or("Cannot find the home directory")?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+// Variable declaration
+    let mut templates: Vec<StyleTemplate> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok())
+        .collect();
+    templates.sort_by_key(|template| template.name.to_lowercase());
+    Ok(templates)
+}
+
+// Write the template, replacing any saved under the same name
+/// Function: explain its purpose and key arguments
+pub fn save_template(template: &StyleTemplate) -> Result<PathBuf, Box<dyn std::error::Error>> {
+// Variable declaration
+    let path = template_path(&template.name)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(template)?)?;
+    Ok(path)
+}
+
+/// Function: explain its purpose and key arguments
+pub fn delete_template(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::remove_file(template_path(name)?)?;
+    Ok(())
+}
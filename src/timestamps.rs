@@ -0,0 +1,235 @@
+// Import external modules or crates needed in timestamps.rs
+use crate::utils::format_number;
+// Import external modules or crates needed in timestamps.rs
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike};
+// Import external modules or crates needed in timestamps.rs
+use egui_plot::{log_grid_spacer, GridInput, GridMark};
+// Import external modules or crates needed in timestamps.rs
+use std::collections::BTreeMap;
+
+// Timestamps are kept as seconds since 1970-01-01 00:00 UTC, so epoch columns need no
+// conversion and ISO-8601 text is read into the same numbers. Dates are shown in UTC.
+
+const MINUTE: f64 = 60.0;
+const HOUR: f64 = 3600.0;
+const DAY: f64 = 86400.0;
+const WEEK: f64 = 7.0 * DAY;
+
+// Average length of a month, used to compare month steps with steps in seconds
+const MONTH: f64 = 365.2425 / 12.0 * DAY;
+
+// Weeks start on Monday, three days before the epoch (a Thursday)
+const WEEK_ORIGIN: f64 = -3.0 * DAY;
+
+// Formats accepted for text without a UTC offset, which is read as UTC
+const NAIVE_FORMATS: [&str; 4] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"];
+
+// Distances between grid marks on a time axis, from milliseconds to centuries. Steps in
+// seconds count from the epoch; month steps count from January of year 0, so year steps
+// land on round years.
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in timestamps.rs module
+enum TimeStep {
+    Seconds(f64),
+    Months(i32),
+}
+
+const STEPS: [TimeStep; 43] = [
+    TimeStep::Seconds(0.001),
+    TimeStep::Seconds(0.002),
+    TimeStep::Seconds(0.005),
+    TimeStep::Seconds(0.01),
+    TimeStep::Seconds(0.02),
+    TimeStep::Seconds(0.05),
+    TimeStep::Seconds(0.1),
+    TimeStep::Seconds(0.2),
+    TimeStep::Seconds(0.5),
+    TimeStep::Seconds(1.0),
+    TimeStep::Seconds(2.0),
+    TimeStep::Seconds(5.0),
+    TimeStep::Seconds(10.0),
+    TimeStep::Seconds(15.0),
+    TimeStep::Seconds(30.0),
+    TimeStep::Seconds(MINUTE),
+    TimeStep::Seconds(2.0 * MINUTE),
+    TimeStep::Seconds(5.0 * MINUTE),
+    TimeStep::Seconds(10.0 * MINUTE),
+    TimeStep::Seconds(15.0 * MINUTE),
+    TimeStep::Seconds(30.0 * MINUTE),
+    TimeStep::Seconds(HOUR),
+    TimeStep::Seconds(2.0 * HOUR),
+    TimeStep::Seconds(3.0 * HOUR),
+    TimeStep::Seconds(6.0 * HOUR),
+    TimeStep::Seconds(12.0 * HOUR),
+    TimeStep::Seconds(DAY),
+    TimeStep::Seconds(2.0 * DAY),
+    TimeStep::Seconds(WEEK),
+    TimeStep::Seconds(2.0 * WEEK),
+    TimeStep::Months(1),
+    TimeStep::Months(2),
+    TimeStep::Months(3),
+    TimeStep::Months(6),
+    TimeStep::Months(12),
+    TimeStep::Months(24),
+    TimeStep::Months(60),
+    TimeStep::Months(120),
+    TimeStep::Months(240),
+    TimeStep::Months(600),
+    TimeStep::Months(1200),
+    TimeStep::Months(2400),
+    TimeStep::Months(6000),
+];
+
+/// Implementation block defining methods for this type
+impl TimeStep {
+    // Length of the step in seconds (on average, for months)
+/// Function: explain its purpose and key arguments
+    fn seconds(self) -> f64 {
+        match self {
+            TimeStep::Seconds(seconds) => seconds,
+            TimeStep::Months(months) => months as f64 * MONTH,
+        }
+    }
+
+    // Marks of this step within [min, max]
+/// Function: explain its purpose and key arguments
+    fn marks(self, (min, max): (f64, f64)) -> Vec<f64> {
+        match self {
+            TimeStep::Seconds(step) => {
+// Variable declaration
+                let origin = if step % WEEK == 0.0 { WEEK_ORIGIN } else { 0.0 };
+// Variable declaration
+                let (first, last) = (((min - origin) / step).ceil() as i64, ((max - origin) / step).floor() as i64);
+                (first..=last).map(|k| origin + k as f64 * step).collect()
+            }
+            TimeStep::Months(step) => {
+// Variable declaration
+                let (Some(start), Some(end)) = (to_datetime(min), to_datetime(max)) else {
+                    return Vec::new();
+                };
+// Variable declaration
+                let month_index = |date: &NaiveDateTime| date.year() * 12 + date.month0() as i32;
+// Variable declaration
+                let first = month_index(&start).div_euclid(step) * step;
+                (first..=month_index(&end))
+                    .step_by(step as usize)
+                    .filter_map(|index| NaiveDate::from_ymd_opt(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, 1))
+                    .filter_map(|date| date.and_hms_opt(0, 0, 0))
+                    .map(|date| date.and_utc().timestamp() as f64)
+                    .filter(|value| (min..=max).contains(value))
+                    .collect()
+            }
+        }
+    }
+}
+
+// Date and time of a timestamp, or None when it is beyond the dates chrono can represent
+/// Function: explain its purpose and key arguments
+fn to_datetime(seconds: f64) -> Option<NaiveDateTime> {
+    if !seconds.is_finite() {
+        return None;
+    }
+    DateTime::from_timestamp_millis((seconds * 1000.0).round() as i64).map(|time| time.naive_utc())
+}
+
+// Seconds since the epoch of ISO-8601 text: a date, or a date and time with an optional
+// fraction and UTC offset ("2024-03-01", "2024-03-01 12:30", "2024-03-01T12:30:05.25+02:00")
+/// Function: explain its purpose and key arguments
+pub fn parse_timestamp(text: &str) -> Option<f64> {
+// Variable declaration
+    let text = text.trim();
+// Variable declaration
+    let seconds = |time: NaiveDateTime| {
+// Variable declaration
+        let time = time.and_utc();
+        time.timestamp() as f64 + time.timestamp_subsec_nanos() as f64 * 1e-9
+    };
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(seconds(time.naive_utc()));
+    }
+    NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))
+        .map(seconds)
+}
+
+// A number, or a timestamp given as ISO-8601 text (see parse_timestamp)
+/// Function: explain its purpose and key arguments
+pub fn parse_number_or_timestamp(text: &str) -> Option<f64> {
+    text.trim().parse::<f64>().ok().or_else(|| parse_timestamp(text))
+}
+
+// Whether the first readable value in column `col` is written as a date rather than a number
+/// Function: explain its purpose and key arguments
+pub fn column_has_timestamps(rows: &[Vec<&str>], col: usize) -> bool {
+    rows.iter()
+        .filter_map(|row| row.get(col))
+        .find(|cell| parse_number_or_timestamp(cell).is_some())
+        .is_some_and(|cell| cell.trim().parse::<f64>().is_err())
+}
+
+// Grid marks of a time axis: round seconds, minutes, hours, days, weeks, months or years,
+// whichever fit the zoom. Marks of several step sizes are returned like egui_plot's own
+// spacers do, each mark with the largest step it is a multiple of.
+/// Function: explain its purpose and key arguments
+pub fn time_grid_marks(input: GridInput) -> Vec<GridMark> {
+// Variable declaration
+    let (min, max) = input.bounds;
+    if !min.is_finite() || !max.is_finite() || max <= min {
+        return Vec::new();
+    }
+// Variable declaration
+    let Some(first) = STEPS.iter().position(|step| step.seconds() >= input.base_step_size) else {
+        return log_grid_spacer(10)(input);
+    };
+    if input.base_step_size < STEPS[0].seconds() / 10.0 {
+        return log_grid_spacer(10)(input);
+    }
+// Variable declaration
+    let mut marks: BTreeMap<i64, GridMark> = BTreeMap::new();
+    for (i, step) in STEPS.iter().enumerate().skip(first) {
+        if i > first && step.seconds() > max - min {
+            break;
+        }
+        for value in step.marks((min, max)) {
+            marks.insert((value * 1000.0).round() as i64, GridMark { value, step_size: step.seconds() });
+        }
+    }
+    marks.into_values().collect()
+}
+
+// Tick label for a mark of a time axis, showing only as much of the date and time as
+// `step` (the mark's step size in seconds) calls for; midnights on an hourly axis show
+// the date instead
+/// Function: explain its purpose and key arguments
+pub fn format_time_tick(value: f64, step: f64) -> String {
+// Variable declaration
+    let Some(time) = to_datetime(value) else {
+        return format_number(value);
+    };
+// Variable declaration
+    let format = if step < 1.0 {
+        "%H:%M:%S%.3f"
+    } else if step < MINUTE {
+        "%H:%M:%S"
+    } else if step < DAY && time.num_seconds_from_midnight() != 0 {
+        "%H:%M"
+    } else if step < 28.0 * DAY {
+        "%b %d"
+    } else if step < 360.0 * DAY {
+        "%b %Y"
+    } else {
+        "%Y"
+    };
+    time.format(format).to_string()
+}
+
+// Full date and time of a timestamp, for readouts
+/// Function: explain its purpose and key arguments
+pub fn format_timestamp(value: f64) -> String {
+    match to_datetime(value) {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+        None => format_number(value),
+    }
+}
@@ -0,0 +1,124 @@
+// Import external modules or crates needed in units.rs
+use crate::dataset::AxisMap;
+
+// Axis units a subplot can declare. Each unit is stored as the linear map to a base
+// unit of its quantity (ps, nm, kJ/mol, K, bar, degrees), so any two units of the same
+// quantity convert into each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Enum representing a set of related values in units.rs module
+pub enum Quantity {
+    Time,
+    Length,
+    Energy,
+    Temperature,
+    Pressure,
+    Angle,
+}
+
+/// Implementation block defining methods for this type
+impl Quantity {
+    pub const ALL: [Quantity; 6] = [
+        Quantity::Time,
+        Quantity::Length,
+        Quantity::Energy,
+        Quantity::Temperature,
+        Quantity::Pressure,
+        Quantity::Angle,
+    ];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Quantity::Time => "Time",
+            Quantity::Length => "Length",
+            Quantity::Energy => "Energy",
+            Quantity::Temperature => "Temperature",
+            Quantity::Pressure => "Pressure",
+            Quantity::Angle => "Angle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in units.rs module
+pub struct Unit {
+    pub symbol: &'static str,
+    pub quantity: Quantity,
+    scale: f64,  // Value in the base unit = value * scale + offset
+    offset: f64,
+}
+
+/// Function: explain its purpose and key arguments
+const fn unit(symbol: &'static str, quantity: Quantity, scale: f64, offset: f64) -> Unit {
+    Unit {
+        symbol,
+        quantity,
+        scale,
+        offset,
+    }
+}
+
+pub const UNITS: [Unit; 24] = [
+    unit("fs", Quantity::Time, 1e-3, 0.0),
+    unit("ps", Quantity::Time, 1.0, 0.0),
+    unit("ns", Quantity::Time, 1e3, 0.0),
+    unit("µs", Quantity::Time, 1e6, 0.0),
+    unit("ms", Quantity::Time, 1e9, 0.0),
+    unit("s", Quantity::Time, 1e12, 0.0),
+    unit("pm", Quantity::Length, 1e-3, 0.0),
+    unit("Å", Quantity::Length, 0.1, 0.0),
+    unit("nm", Quantity::Length, 1.0, 0.0),
+    unit("µm", Quantity::Length, 1e3, 0.0),
+    unit("J/mol", Quantity::Energy, 1e-3, 0.0),
+    unit("kJ/mol", Quantity::Energy, 1.0, 0.0),
+    unit("kcal/mol", Quantity::Energy, 4.184, 0.0),
+    unit("eV", Quantity::Energy, 96.485_332_12, 0.0), // Per particle, times the Avogadro constant
+    unit("K", Quantity::Temperature, 1.0, 0.0),
+    unit("°C", Quantity::Temperature, 1.0, 273.15),
+    unit("°F", Quantity::Temperature, 5.0 / 9.0, 273.15 - 32.0 * 5.0 / 9.0),
+    unit("Pa", Quantity::Pressure, 1e-5, 0.0),
+    unit("kPa", Quantity::Pressure, 1e-2, 0.0),
+    unit("MPa", Quantity::Pressure, 10.0, 0.0),
+    unit("bar", Quantity::Pressure, 1.0, 0.0),
+    unit("atm", Quantity::Pressure, 1.013_25, 0.0),
+    unit("°", Quantity::Angle, 1.0, 0.0),
+    unit("rad", Quantity::Angle, 180.0 / std::f64::consts::PI, 0.0),
+];
+
+/// Function: explain its purpose and key arguments
+pub fn find_unit(symbol: &str) -> Option<&'static Unit> {
+    UNITS.iter().find(|unit| unit.symbol == symbol.trim())
+}
+
+/// Function: explain its purpose and key arguments
+pub fn units_of(quantity: Quantity) -> impl Iterator<Item = &'static Unit> {
+    UNITS.iter().filter(move |unit| unit.quantity == quantity)
+}
+
+// Map from values in `from` to values in `to` along `axis`; None across quantities
+/// Function: explain its purpose and key arguments
+pub fn conversion(from: &Unit, to: &Unit, axis: usize) -> Option<AxisMap> {
+    (from.quantity == to.quantity).then(|| AxisMap {
+        axis,
+        factor: from.scale / to.scale,
+        offset: (from.offset - to.offset) / to.scale,
+    })
+}
+
+// Axis title with its unit switched: "(ps)" or "[ps]" becomes "(ns)" or "[ns]", and a
+// title without either gets the new unit appended in parentheses (empty titles stay empty)
+/// Function: explain its purpose and key arguments
+pub fn relabel(label: &str, from: &str, to: &str) -> String {
+    for (open, close) in [("(", ")"), ("[", "]")] {
+// Variable declaration
+        let old = format!("{}{}{}", open, from, close);
+        if label.contains(&old) {
+            return label.replace(&old, &format!("{}{}{}", open, to, close));
+        }
+    }
+    if label.trim().is_empty() {
+        String::new()
+    } else {
+        format!("{} ({})", label.trim_end(), to)
+    }
+}
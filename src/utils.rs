@@ -1,13 +1,51 @@
 // Import external modules or crates needed in utils.rs
-use crate::dataset::Dataset;
+use crate::dataset::{
+    distribution_positions, fill_baseline, interpolate_y, stacked_datasets, visible_datasets, AxisMap, ColorBy, Dataset,
+    LinePattern, MarkerStyle, PlotType, DISTRIBUTION_WIDTH, FILL_OPACITY,
+};
 // Import external modules or crates needed in utils.rs
-use crate::app::{FontSize, Subplot, SubplotLayout};
+use crate::annotation::{Annotation, AnnotationKind};
+// Import external modules or crates needed in utils.rs
+use crate::data_editor::{solve_linear_system, split_pasted_line};
+// Import external modules or crates needed in utils.rs
+use crate::expression::Expression;
+// Import external modules or crates needed in utils.rs
+use crate::compression::{check_readable, data_extension, open_data, read_data_to_string, uncompressed_path, COMPRESSED_EXTENSIONS};
+// Import external modules or crates needed in utils.rs
+use crate::heatmap::Heatmap;
+// Import external modules or crates needed in utils.rs
+use crate::inset::Inset;
+// Import external modules or crates needed in utils.rs
+use crate::io::{
+    is_hdf5, is_spreadsheet, is_text_table, load_hdf5, load_npy, load_npz, load_parquet, load_spreadsheet, load_text_table, HDF5_EXTENSIONS,
+    SPREADSHEET_EXTENSIONS, TEXT_TABLE_EXTENSIONS,
+};
+// Import external modules or crates needed in utils.rs
+use crate::app::{AxisScale, ExportFormat, ExportSettings, FontSize, LegendPosition, ScaleKind, Subplot, SubplotConfig, SubplotLayout};
+// Import external modules or crates needed in utils.rs
+use crate::palette::{Colormap, Palette};
+// Import external modules or crates needed in utils.rs
+use crate::points::PointStore;
+// Import external modules or crates needed in utils.rs
+use crate::timestamps::{column_has_timestamps, format_time_tick, parse_number_or_timestamp, time_grid_marks};
+// Import external modules or crates needed in utils.rs
+use crate::pdf_export::render_subplots_pdf;
+// Import external modules or crates needed in utils.rs
+use crate::script_export::render_subplots_script;
+// Import external modules or crates needed in utils.rs
+use std::borrow::{Borrow, Cow};
 // Import external modules or crates needed in utils.rs
 use std::fs::File;
 // Import external modules or crates needed in utils.rs
 use std::io::{BufRead, BufReader};
 // Import external modules or crates needed in utils.rs
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+// Import external modules or crates needed in utils.rs
+use std::sync::OnceLock;
+// Import external modules or crates needed in utils.rs
+use ab_glyph::{Font, FontArc, PxScale, ScaleFont};
+// Import external modules or crates needed in utils.rs
+use egui_plot::{log_grid_spacer, GridInput, GridMark};
 
 /// Data structure used in utils.rs module
 pub struct AxisConfig {
@@ -19,6 +57,8 @@ pub struct AxisConfig {
    pub y_padding_percent: f64,
    pub custom_x_ticks: Option<Vec<f64>>,
    pub custom_y_ticks: Option<Vec<f64>>,
+   pub x_scale: AxisScale,
+   pub y_scale: AxisScale,
 }
 
 // Helper function to parse custom ticks from comma-separated string
@@ -61,387 +101,3530 @@ pub fn compute_rolling_average(points: &[[f64; 2]], window_size: usize) -> Resul
     Ok(result)
 }
 
-// Helper function to get data bounds
+// Savitzky–Golay smoothing: each Y is replaced by the value at that X of a polynomial of
+// degree `order` fitted by least squares to the `window` points around it (shifted
+// inwards near the ends). Fitting against the actual X values handles uneven spacing.
 /// Function: explain its purpose and key arguments
-pub fn get_data_bounds(datasets: &[Dataset]) -> Option<(f64, f64, f64, f64)> {
-    if datasets.is_empty() {
-        return None;
+pub fn compute_savitzky_golay(
+    points: &[[f64; 2]],
+    window: usize,
+    order: usize,
+) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+    if window < 3 || window % 2 != 1 {
+        return Err("Window size must be an odd number of at least 3".into());
+    }
+    if order >= window {
+        return Err("Polynomial order must be smaller than the window size".into());
     }
-    
 // Variable declaration
-    let mut min_x = f64::INFINITY;
+    let sorted = sorted_by_x(points);
+    // Counted without the blank (NaN) points, which sorted_by_x drops
+    if sorted.len() < window {
+        return Err("Window size cannot be larger than dataset size".into());
+    }
 // Variable declaration
-    let mut max_x = f64::NEG_INFINITY;
+    let terms = order + 1;
 // Variable declaration
-    let mut min_y = f64::INFINITY;
+    let mut result = Vec::with_capacity(sorted.len());
+    for (i, &[x, y]) in sorted.iter().enumerate() {
 // Variable declaration
-    let mut max_y = f64::NEG_INFINITY;
-    
-    for dataset in datasets {
-        for point in &dataset.points {
-            min_x = min_x.min(point[0]);
-            max_x = max_x.max(point[0]);
-            min_y = min_y.min(point[1]);
-            max_y = max_y.max(point[1]);
+        let start = i.saturating_sub(window / 2).min(sorted.len() - window);
+// Variable declaration
+        let neighbours = &sorted[start..start + window];
+        // Fit in (x - x_i) / half-width so the normal equations stay well conditioned
+// Variable declaration
+        let scale = neighbours.iter().map(|p| (p[0] - x).abs()).fold(0.0, f64::max);
+        if scale == 0.0 {
+            result.push([x, neighbours.iter().map(|p| p[1]).sum::<f64>() / window as f64]);
+            continue;
+        }
+// Variable declaration
+        let mut normal = vec![vec![0.0; terms]; terms];
+// Variable declaration
+        let mut rhs = vec![0.0; terms];
+        for p in neighbours {
+// Variable declaration
+            let powers: Vec<f64> = (0..terms).map(|k| ((p[0] - x) / scale).powi(k as i32)).collect();
+            for j in 0..terms {
+                rhs[j] += powers[j] * p[1];
+                for k in 0..terms {
+                    normal[j][k] += powers[j] * powers[k];
+                }
+            }
         }
+        // The constant term is the fitted value at x itself
+        result.push([x, solve_linear_system(normal, rhs).map_or(y, |coefficients| coefficients[0])]);
     }
-    
-    Some((min_x, max_x, min_y, max_y))
+    Ok(result)
 }
 
-// New function to export subplots as PNG
+// Gaussian kernel smoothing: each Y becomes the average of the Y values weighted by a
+// Gaussian of their X distance, with standard deviation `sigma` in X units (cut off at 4σ)
 /// Function: explain its purpose and key arguments
-pub fn export_subplots_as_png(
-    subplots: &[Subplot],
-    layout: &SubplotLayout,
-    dark_mode: bool,
-    font_size: &FontSize,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if subplots.is_empty() {
-        return Err("No subplots to export".into());
+pub fn compute_gaussian_smoothing(points: &[[f64; 2]], sigma: f64) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+    if !(sigma > 0.0 && sigma.is_finite()) {
+        return Err("Sigma must be greater than 0".into());
+    }
+    if points.is_empty() {
+        return Err("The dataset has no points".into());
     }
-
-    if let Some(path) = rfd::FileDialog::new()
-        .add_filter("PNG Image", &["png"])
-        .set_file_name("subplots.png")
-        .save_file()
-    {
-// Variable declaration
-        let (rows, cols) = layout.dimensions();
-        
-        // Calculate image dimensions based on subplot layout
 // Variable declaration
-        let subplot_width = 600u32;
+    let sorted = sorted_by_x(points);
 // Variable declaration
-        let subplot_height = 400u32;
+    let mut result = Vec::with_capacity(sorted.len());
+    for &[x, _] in &sorted {
 // Variable declaration
-        let _margin = 80u32;
+        let start = sorted.partition_point(|p| p[0] < x - 4.0 * sigma);
 // Variable declaration
-        let spacing = 40u32;
-        
+        let end = sorted.partition_point(|p| p[0] <= x + 4.0 * sigma);
 // Variable declaration
-        let total_width = cols as u32 * subplot_width + (cols as u32 + 1) * spacing;
+        let (weighted, total) = sorted[start..end].iter().fold((0.0, 0.0), |(weighted, total), p| {
 // Variable declaration
-        let total_height = rows as u32 * subplot_height + (rows as u32 + 1) * spacing + 60; // Extra space for titles
+            let weight = (-0.5 * ((p[0] - x) / sigma).powi(2)).exp();
+            (weighted + weight * p[1], total + weight)
+        });
+        result.push([x, weighted / total]);
+    }
+    Ok(result)
+}
 
+// Finite points ordered by X, for filters that work on neighbours along the X axis
+/// Function: explain its purpose and key arguments
+fn sorted_by_x(points: &[[f64; 2]]) -> Vec<[f64; 2]> {
 // Variable declaration
-        let (bg_color, grid_color, axis_color, text_color) = if dark_mode {
-            (
-                image::Rgb([27, 27, 27]),
-                image::Rgb([60, 60, 60]),
-                image::Rgb([180, 180, 180]),
-                image::Rgb([255, 255, 255]),
-            )
-        } else {
-            (
-                image::Rgb([248, 248, 248]),
-                image::Rgb([200, 200, 200]),
-                image::Rgb([100, 100, 100]),
-                image::Rgb([0, 0, 0]),
-            )
-        };
+    let mut sorted: Vec<[f64; 2]> = points.iter().copied().filter(|p| p[0].is_finite() && p[1].is_finite()).collect();
+    sorted.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    sorted
+}
 
-// Variable declaration
-        let mut img_buffer = image::RgbImage::new(total_width, total_height);
-        for pixel in img_buffer.pixels_mut() {
-            *pixel = bg_color;
-        }
+// Helper function to compute the derivative dy/dx with central differences
+// (one-sided differences at the two end points). X spacing may be non-uniform.
+/// Function: explain its purpose and key arguments
+pub fn compute_derivative(points: &[[f64; 2]]) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+    if points.len() < 2 {
+        return Err("Need at least two points to differentiate".into());
+    }
 
-        // Draw each subplot
-        for (subplot_idx, subplot) in subplots.iter().enumerate() {
-            if subplot_idx >= rows * cols {
-                break;
-            }
-            
 // Variable declaration
-            let row = subplot_idx / cols;
+    let last = points.len() - 1;
 // Variable declaration
-            let col = subplot_idx % cols;
-            
+    let mut result = Vec::with_capacity(points.len());
+    for i in 0..points.len() {
 // Variable declaration
-            let subplot_x = spacing + col as u32 * (subplot_width + spacing);
+        let (before, after) = match i {
+            0 => (0, 1),
+            i if i == last => (last - 1, last),
+            i => (i - 1, i + 1),
+        };
 // Variable declaration
-            let subplot_y = spacing + row as u32 * (subplot_height + spacing);
-            
-            render_subplot_to_image(
-                &mut img_buffer,
-                subplot,
-                subplot_x,
-                subplot_y,
-                subplot_width,
-                subplot_height,
-                bg_color,
-                grid_color,
-                axis_color,
-                text_color,
-                font_size,
-                subplot_idx + 1,
-            )?;
+        let dx = points[after][0] - points[before][0];
+        if dx == 0.0 {
+            return Err(format!("Duplicate X value {} prevents differentiation", points[i][0]).into());
         }
-
-        img_buffer.save(&path)?;
-        println!("Subplots exported as: {}", path.display());
+        result.push([points[i][0], (points[after][1] - points[before][1]) / dx]);
     }
-    Ok(())
+
+    Ok(result)
 }
 
+// Helper function to compute the running integral of y over x with the trapezoidal rule,
+// starting from zero at the first point
 /// Function: explain its purpose and key arguments
-fn render_subplot_to_image(
-    img: &mut image::RgbImage,
-    subplot: &Subplot,
-    x_offset: u32,
-    y_offset: u32,
-    width: u32,
-    height: u32,
-    _bg_color: image::Rgb<u8>,
-    grid_color: image::Rgb<u8>,
-    axis_color: image::Rgb<u8>,
-    text_color: image::Rgb<u8>,
-    font_size: &FontSize,
-    subplot_number: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if subplot.datasets.is_empty() {
-        // Draw empty subplot with title
-        draw_subplot_title(img, x_offset, y_offset, width, &subplot.config.title, subplot_number, text_color, font_size);
-        draw_empty_subplot_frame(img, x_offset, y_offset + 30, width, height - 30, axis_color);
-        return Ok(());
+pub fn compute_cumulative_integral(points: &[[f64; 2]]) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+    if points.len() < 2 {
+        return Err("Need at least two points to integrate".into());
     }
 
-    // Calculate bounds
 // Variable declaration
-    let (min_x, max_x, min_y, max_y) = if subplot.config.use_custom_bounds {
+    let mut total = 0.0;
 // Variable declaration
-        let config = AxisConfig {
-            x_min: subplot.config.custom_x_min.parse().ok(),
-            x_max: subplot.config.custom_x_max.parse().ok(),
-            y_min: subplot.config.custom_y_min.parse().ok(),
-            y_max: subplot.config.custom_y_max.parse().ok(),
-            x_padding_percent: subplot.config.x_padding_percent / 100.0,
-            y_padding_percent: subplot.config.y_padding_percent / 100.0,
-            custom_x_ticks: if subplot.config.use_custom_x_ticks {
-                Some(parse_custom_ticks(&subplot.config.custom_x_ticks))
-            } else {
-                None
-            },
-            custom_y_ticks: if subplot.config.use_custom_y_ticks {
-                Some(parse_custom_ticks(&subplot.config.custom_y_ticks))
-            } else {
-                None
-            },
-        };
-        calculate_custom_bounds(&subplot.datasets, &config)?
-    } else {
-        calculate_auto_bounds(&subplot.datasets)
-    };
+    let mut result = Vec::with_capacity(points.len());
+    result.push([points[0][0], 0.0]);
+    for window in points.windows(2) {
+        total += 0.5 * (window[1][0] - window[0][0]) * (window[0][1] + window[1][1]);
+        result.push([window[1][0], total]);
+    }
 
-    // Draw subplot title
-    draw_subplot_title(img, x_offset, y_offset, width, &subplot.config.title, subplot_number, text_color, font_size);
+    Ok(result)
+}
 
+// Taper applied to a time series before its Fourier transform to reduce spectral leakage
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+pub enum FftWindow {
+    Rectangular,
+    Hann,
+    Hamming,
+}
+
+/// Implementation block defining methods for this type
+impl FftWindow {
+    pub const ALL: [FftWindow; 3] = [FftWindow::Rectangular, FftWindow::Hann, FftWindow::Hamming];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            FftWindow::Rectangular => "None (rectangular)",
+            FftWindow::Hann => "Hann",
+            FftWindow::Hamming => "Hamming",
+        }
+    }
+
+    // Weight of sample `i` out of `n`
+/// Function: explain its purpose and key arguments
+    fn weight(self, i: usize, n: usize) -> f64 {
+        if n < 2 {
+            return 1.0;
+        }
 // Variable declaration
-    let plot_y_offset = y_offset + 30; // Space for title
-// Variable declaration
-    let plot_height = height - 30;
+        let phase = 2.0 * std::f64::consts::PI * i as f64 / (n - 1) as f64;
+        match self {
+            FftWindow::Rectangular => 1.0,
+            FftWindow::Hann => 0.5 - 0.5 * phase.cos(),
+            FftWindow::Hamming => 0.54 - 0.46 * phase.cos(),
+        }
+    }
+}
 
+// One-sided power spectral density of an evenly sampled series, against frequency in
+// inverse X units. The mean is removed and the windowed series is zero-padded to a power
+// of two. Normalized so that the area under the curve is the variance of the series.
+/// Function: explain its purpose and key arguments
+pub fn compute_power_spectrum(points: &[[f64; 2]], window: FftWindow) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
 // Variable declaration
-    let margin_left = 60u32;
+    let n = points.len();
+    if n < 4 {
+        return Err("Need at least four points for a spectrum".into());
+    }
 // Variable declaration
-    let margin_right = 20u32;
+    let dt = (points[n - 1][0] - points[0][0]) / (n - 1) as f64;
+    if !(dt > 0.0 && dt.is_finite()) {
+        return Err("X values must increase".into());
+    }
+    if points
+        .windows(2)
+        .any(|pair| ((pair[1][0] - pair[0][0]) - dt).abs() > 0.01 * dt)
+    {
+        return Err("The dataset must be evenly sampled in X (resample it first)".into());
+    }
 // Variable declaration
-    let margin_top = 20u32;
+    let mean = points.iter().map(|p| p[1]).sum::<f64>() / n as f64;
 // Variable declaration
-    let margin_bottom = 40u32;
+    let weights: Vec<f64> = (0..n).map(|i| window.weight(i, n)).collect();
 // Variable declaration
-    let plot_width = width - margin_left - margin_right;
+    let size = n.next_power_of_two();
 // Variable declaration
-    let effective_plot_height = plot_height - margin_top - margin_bottom;
+    let mut signal = vec![[0.0, 0.0]; size];
+    for (i, (p, w)) in points.iter().zip(&weights).enumerate() {
+        signal[i][0] = (p[1] - mean) * w;
+    }
+    fft_in_place(&mut signal);
 
-    // Draw grid if requested
-    if subplot.config.show_grid {
 // Variable declaration
-        let num_v_lines = 6;
-        for i in 1..num_v_lines {
+    let norm = dt / weights.iter().map(|w| w * w).sum::<f64>();
+    Ok((0..=size / 2)
+        .map(|k| {
 // Variable declaration
-            let x = x_offset + margin_left + (i * plot_width / num_v_lines);
-            for y in (plot_y_offset + margin_top)..(plot_y_offset + plot_height - margin_bottom) {
-                if y % 3 == 0 {
-                    img.put_pixel(x, y, grid_color);
-                }
-            }
-        }
+            let power = (signal[k][0].powi(2) + signal[k][1].powi(2)) * norm;
+            // Negative frequencies fold onto the positive ones, except at DC and Nyquist
 // Variable declaration
-        let num_h_lines = 4;
-        for i in 1..num_h_lines {
+            let one_sided = if k == 0 || k == size / 2 { power } else { 2.0 * power };
+            [k as f64 / (size as f64 * dt), one_sided]
+        })
+        .collect())
+}
+
+// Iterative radix-2 Cooley–Tukey FFT of (re, im) pairs; the length must be a power of two
+/// Function: explain its purpose and key arguments
+fn fft_in_place(data: &mut [[f64; 2]]) {
 // Variable declaration
-            let y = plot_y_offset + margin_top + (i * effective_plot_height / num_h_lines);
-            for x in (x_offset + margin_left)..(x_offset + width - margin_right) {
-                if x % 3 == 0 {
-                    img.put_pixel(x, y, grid_color);
-                }
-            }
+    let n = data.len();
+// Variable declaration
+    let mut j = 0;
+    for i in 1..n {
+// Variable declaration
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
         }
     }
-
-    // Draw axes
 // Variable declaration
-    let x_axis_y = plot_y_offset + plot_height - margin_bottom;
+    let mut len = 2;
+    while len <= n {
 // Variable declaration
-    let y_axis_x = x_offset + margin_left;
-    for x in (x_offset + margin_left)..(x_offset + width - margin_right) {
-        img.put_pixel(x, x_axis_y, axis_color);
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+// Variable declaration
+                let (sin, cos) = (angle * k as f64).sin_cos();
+// Variable declaration
+                let [re, im] = data[start + k + len / 2];
+// Variable declaration
+                let twiddled = [re * cos - im * sin, re * sin + im * cos];
+// Variable declaration
+                let even = data[start + k];
+                data[start + k] = [even[0] + twiddled[0], even[1] + twiddled[1]];
+                data[start + k + len / 2] = [even[0] - twiddled[0], even[1] - twiddled[1]];
+            }
+        }
+        len <<= 1;
     }
-    for y in (plot_y_offset + margin_top)..(plot_y_offset + plot_height - margin_bottom) {
-        img.put_pixel(y_axis_x, y, axis_color);
+}
+
+// Rescaling offered by the Normalization tool
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+pub enum Normalization {
+    MinMax,
+    ZScore,
+    DivideByFirst,
+    DivideByMax,
+    ShiftXToZero,
+}
+
+/// Implementation block defining methods for this type
+impl Normalization {
+    pub const ALL: [Normalization; 5] = [
+        Normalization::MinMax,
+        Normalization::ZScore,
+        Normalization::DivideByFirst,
+        Normalization::DivideByMax,
+        Normalization::ShiftXToZero,
+    ];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Normalization::MinMax => "Min-max (Y from 0 to 1)",
+            Normalization::ZScore => "Z-score (mean 0, std. dev. 1)",
+            Normalization::DivideByFirst => "Divide Y by the first value",
+            Normalization::DivideByMax => "Divide Y by the maximum",
+            Normalization::ShiftXToZero => "Shift X to start at 0",
+        }
     }
 
-    // Draw axis labels
-    draw_subplot_axis_labels(
-        img,
-        min_x,
-        max_x,
-        min_y,
-        max_y,
-        x_offset + margin_left,
-        margin_bottom,
-        plot_width,
-        effective_plot_height,
-        x_offset + width,
-        plot_y_offset + plot_height,
-        text_color,
-        font_size,
-    );
+    // Suffix of the new dataset's name
+/// Function: explain its purpose and key arguments
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Normalization::MinMax => "minmax",
+            Normalization::ZScore => "zscore",
+            Normalization::DivideByFirst => "rel_first",
+            Normalization::DivideByMax => "rel_max",
+            Normalization::ShiftXToZero => "x0",
+        }
+    }
+}
 
-    // Draw datasets
-    for dataset in &subplot.datasets {
-// Variable declaration
-        let rgb_color = image::Rgb(dataset.color);
-        
-        for window in dataset.points.windows(2) {
+// The linear map that normalizes `points`; non-finite values are ignored when
+// computing it
+/// Function: explain its purpose and key arguments
+pub fn normalization_map(points: &[[f64; 2]], normalization: Normalization) -> Result<AxisMap, Box<dyn std::error::Error>> {
 // Variable declaration
-            let p1 = &window[0];
+    let axis = if normalization == Normalization::ShiftXToZero { 0 } else { 1 };
 // Variable declaration
-            let p2 = &window[1];
+    let values: Vec<f64> = points.iter().map(|p| p[axis]).filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return Err("The dataset has no finite values".into());
+    }
 // Variable declaration
-            let x1 = x_offset + margin_left + ((p1[0] - min_x) / (max_x - min_x) * plot_width as f64) as u32;
+    let (min, max) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
 // Variable declaration
-            let y1 = plot_y_offset + plot_height - margin_bottom
-                - ((p1[1] - min_y) / (max_y - min_y) * effective_plot_height as f64) as u32;
+    let divide_by = |divisor: f64, what: &str| -> Result<AxisMap, Box<dyn std::error::Error>> {
+        if divisor == 0.0 {
+            return Err(format!("Cannot divide by {}: it is zero", what).into());
+        }
+        Ok(AxisMap { axis, factor: 1.0 / divisor, offset: 0.0 })
+    };
+    match normalization {
+        Normalization::MinMax => {
+            if max == min {
+                return Err("All Y values are equal".into());
+            }
+            Ok(AxisMap { axis, factor: 1.0 / (max - min), offset: -min / (max - min) })
+        }
+        Normalization::ZScore => {
+            if values.len() < 2 {
+                return Err("Need at least two points".into());
+            }
 // Variable declaration
-            let x2 = x_offset + margin_left + ((p2[0] - min_x) / (max_x - min_x) * plot_width as f64) as u32;
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
 // Variable declaration
-            let y2 = plot_y_offset + plot_height - margin_bottom
-                - ((p2[1] - min_y) / (max_y - min_y) * effective_plot_height as f64) as u32;
-            draw_thick_line(img, x1, y1, x2, y2, rgb_color, 2);
+            let std_dev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64).sqrt();
+            if std_dev == 0.0 {
+                return Err("All Y values are equal".into());
+            }
+            Ok(AxisMap { axis, factor: 1.0 / std_dev, offset: -mean / std_dev })
         }
+        Normalization::DivideByFirst => divide_by(values[0], "the first value"),
+        Normalization::DivideByMax => divide_by(max, "the maximum"),
+        Normalization::ShiftXToZero => Ok(AxisMap { axis, factor: 1.0, offset: -min }),
     }
+}
 
-    // Draw legend if requested
-    if subplot.config.show_legend && !subplot.datasets.is_empty() {
-        draw_subplot_legend(
-            img,
-            &subplot.datasets,
-            &subplot.config.legend_title,
-            x_offset + width - 150,
-            plot_y_offset + margin_top + 10,
-            text_color,
-            font_size,
-        );
+// Interpolation used when resampling a dataset onto other X values
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+pub enum Interpolation {
+    Linear,
+    CubicSpline,
+}
+
+/// Implementation block defining methods for this type
+impl Interpolation {
+    pub const ALL: [Interpolation; 2] = [Interpolation::Linear, Interpolation::CubicSpline];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Interpolation::Linear => "Linear",
+            Interpolation::CubicSpline => "Cubic spline",
+        }
     }
+}
 
-    Ok(())
+// `count` evenly spaced values from `min` to `max`, both included
+/// Function: explain its purpose and key arguments
+pub fn uniform_grid(min: f64, max: f64, count: usize) -> Vec<f64> {
+    match count {
+        0 => Vec::new(),
+        1 => vec![min],
+        _ => (0..count).map(|i| min + (max - min) * i as f64 / (count - 1) as f64).collect(),
+    }
 }
 
+// Y of the series at every X of `grid`. Points are sorted by X and repeated X values
+// after the first are ignored. X values outside the series' range are skipped rather
+// than extrapolated. The cubic spline is a natural one (zero curvature at both ends).
 /// Function: explain its purpose and key arguments
-fn draw_subplot_title(
-    img: &mut image::RgbImage,
-    x_offset: u32,
-    y_offset: u32,
-    width: u32,
-    title: &str,
-    subplot_number: usize,
-    color: image::Rgb<u8>,
-    font_size: &FontSize,
-) {
+pub fn resample_points(
+    points: &[[f64; 2]],
+    grid: &[f64],
+    method: Interpolation,
+) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut sorted: Vec<[f64; 2]> = points.iter().copied().filter(|p| p[0].is_finite() && p[1].is_finite()).collect();
+    sorted.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    sorted.dedup_by(|later, earlier| later[0] == earlier[0]);
+    if sorted.len() < 2 {
+        return Err("Need at least two points with different X to interpolate".into());
+    }
 // Variable declaration
-    let display_title = if title.is_empty() {
-        format!("Subplot {}", subplot_number)
-    } else {
-        format!("Subplot {}: {}", subplot_number, title)
+    let curvature = match method {
+        Interpolation::Linear => vec![0.0; sorted.len()],
+        Interpolation::CubicSpline => spline_second_derivatives(&sorted),
     };
-    
 // Variable declaration
-    let font_scale = font_size.to_scale() * 1.2; // Slightly larger for titles
+    let (first, last) = (sorted[0][0], sorted[sorted.len() - 1][0]);
 // Variable declaration
-    let char_width = (6.0 * font_scale) as u32;
+    let resampled: Vec<[f64; 2]> = grid
+        .iter()
+        .filter(|x| (first..=last).contains(*x))
+        .map(|&x| {
 // Variable declaration
-    let title_width = display_title.len() as u32 * char_width;
+            let i = sorted.partition_point(|p| p[0] < x).clamp(1, sorted.len() - 1) - 1;
 // Variable declaration
-    let title_x = x_offset + (width - title_width) / 2; // Center the title
-    
-    draw_text_scaled(img, title_x, y_offset + 5, &display_title, color, font_scale);
+            let ([x0, y0], [x1, y1]) = (sorted[i], sorted[i + 1]);
+// Variable declaration
+            let h = x1 - x0;
+// Variable declaration
+            let (a, b) = ((x1 - x) / h, (x - x0) / h);
+// Variable declaration
+            let y = a * y0 + b * y1 + ((a.powi(3) - a) * curvature[i] + (b.powi(3) - b) * curvature[i + 1]) * h * h / 6.0;
+            [x, y]
+        })
+        .collect();
+    if resampled.is_empty() {
+        return Err("None of the new X values lie within the dataset's X range".into());
+    }
+    Ok(resampled)
 }
 
+// Second derivatives of the natural cubic spline through `points` (sorted by distinct X),
+// from its tridiagonal system solved with the Thomas algorithm
 /// Function: explain its purpose and key arguments
-fn draw_empty_subplot_frame(
-    img: &mut image::RgbImage,
-    x_offset: u32,
-    y_offset: u32,
-    width: u32,
-    height: u32,
-    color: image::Rgb<u8>,
-) {
-    // Draw border
-    for x in x_offset..(x_offset + width) {
-        img.put_pixel(x, y_offset, color);
-        img.put_pixel(x, y_offset + height - 1, color);
+fn spline_second_derivatives(points: &[[f64; 2]]) -> Vec<f64> {
+// Variable declaration
+    let n = points.len();
+// Variable declaration
+    let mut second = vec![0.0; n];
+    if n < 3 {
+        return second;
+    }
+// Variable declaration
+    let (mut upper, mut rhs) = (vec![0.0; n], vec![0.0; n]);
+    for i in 1..n - 1 {
+// Variable declaration
+        let (h0, h1) = (points[i][0] - points[i - 1][0], points[i + 1][0] - points[i][0]);
+// Variable declaration
+        let d = 6.0 * ((points[i + 1][1] - points[i][1]) / h1 - (points[i][1] - points[i - 1][1]) / h0);
+// Variable declaration
+        let pivot = 2.0 * (h0 + h1) - h0 * upper[i - 1];
+        upper[i] = h1 / pivot;
+        rhs[i] = (d - h0 * rhs[i - 1]) / pivot;
+    }
+    for i in (1..n - 1).rev() {
+        second[i] = rhs[i] - upper[i] * second[i + 1];
+    }
+    second
+}
+
+// Spread drawn as a band around the mean of replicate datasets
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+pub enum ReplicaSpread {
+    StdDev,
+    StdError,
+}
+
+/// Implementation block defining methods for this type
+impl ReplicaSpread {
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            ReplicaSpread::StdDev => "±σ (standard deviation)",
+            ReplicaSpread::StdError => "±SEM (standard error)",
+        }
+    }
+}
+
+// Mean points of replicate series and the spread of the replicas at each of them
+type ReplicaAverage = (Vec<[f64; 2]>, Vec<f64>);
+
+// Mean of replicate series and its spread at every X. Series that share their X values
+// are averaged point by point; otherwise the others are interpolated onto the X values
+// of the first one, limited to the X range that all of them cover.
+/// Function: explain its purpose and key arguments
+pub fn average_replicas(
+    replicas: &[Vec<[f64; 2]>],
+    spread: ReplicaSpread,
+) -> Result<ReplicaAverage, Box<dyn std::error::Error>> {
+    if replicas.len() < 2 {
+        return Err("Select at least two datasets to average".into());
+    }
+    if replicas.iter().any(|points| points.is_empty()) {
+        return Err("Cannot average an empty dataset".into());
+    }
+// Variable declaration
+    let sorted: Vec<Vec<[f64; 2]>> = replicas
+        .iter()
+        .map(|points| {
+// Variable declaration
+            let mut points = points.clone();
+            points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+            points
+        })
+        .collect();
+// Variable declaration
+    let shared_grid = sorted.iter().all(|points| {
+        points.len() == sorted[0].len() && points.iter().zip(&sorted[0]).all(|(a, b)| a[0] == b[0])
+    });
+// Variable declaration
+    let low = sorted.iter().map(|points| points[0][0]).fold(f64::NEG_INFINITY, f64::max);
+// Variable declaration
+    let high = sorted.iter().map(|points| points[points.len() - 1][0]).fold(f64::INFINITY, f64::min);
+// Variable declaration
+    let n = sorted.len() as f64;
+// Variable declaration
+    let mut mean_points = Vec::new();
+// Variable declaration
+    let mut widths = Vec::new();
+    for (i, &[x, _]) in sorted[0].iter().enumerate() {
+// Variable declaration
+        let values: Option<Vec<f64>> = if shared_grid {
+            Some(sorted.iter().map(|points| points[i][1]).collect())
+        } else if (low..=high).contains(&x) {
+            sorted.iter().map(|points| interpolate_y(points, x)).collect()
+        } else {
+            None
+        };
+// Variable declaration
+        let Some(values) = values.filter(|values| values.iter().all(|v| v.is_finite())) else {
+            continue;
+        };
+// Variable declaration
+        let mean = values.iter().sum::<f64>() / n;
+// Variable declaration
+        let std_dev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        mean_points.push([x, mean]);
+        widths.push(match spread {
+            ReplicaSpread::StdDev => std_dev,
+            ReplicaSpread::StdError => std_dev / n.sqrt(),
+        });
+    }
+    if mean_points.is_empty() {
+        return Err("The datasets do not overlap in X".into());
+    }
+    Ok((mean_points, widths))
+}
+
+// Element-wise operation of the Dataset Math tool. Expression applies a formula in
+// `x`, `y` (the first dataset) and optionally `b` (the second one) to every point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+pub enum DatasetOperation {
+    Sum,
+    Difference,
+    Product,
+    Ratio,
+    Expression,
+}
+
+/// Implementation block defining methods for this type
+impl DatasetOperation {
+    pub const ALL: [DatasetOperation; 5] = [
+        DatasetOperation::Sum,
+        DatasetOperation::Difference,
+        DatasetOperation::Product,
+        DatasetOperation::Ratio,
+        DatasetOperation::Expression,
+    ];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            DatasetOperation::Sum => "A + B",
+            DatasetOperation::Difference => "A − B",
+            DatasetOperation::Product => "A × B",
+            DatasetOperation::Ratio => "A / B",
+            DatasetOperation::Expression => "Expression",
+        }
+    }
+
+    // Word used in the name of the new dataset
+/// Function: explain its purpose and key arguments
+    pub fn name_part(self) -> &'static str {
+        match self {
+            DatasetOperation::Sum => "plus",
+            DatasetOperation::Difference => "minus",
+            DatasetOperation::Product => "times",
+            DatasetOperation::Ratio => "over",
+            DatasetOperation::Expression => "expr",
+        }
+    }
+}
+
+// Whether the operation reads the second dataset: always for the arithmetic ones,
+// and for an expression only when it mentions `b`
+/// Function: explain its purpose and key arguments
+pub fn operation_uses_b(operation: DatasetOperation, expression: &str) -> bool {
+    operation != DatasetOperation::Expression
+        || Expression::parse(expression).is_ok_and(|parsed| parsed.parameters.iter().any(|name| name == "b"))
+}
+
+// New series at the X values of `a`. When the second series is used it is linearly
+// interpolated onto those X values, and points of `a` outside its X range are dropped.
+// Results that are not finite (e.g. division by zero) are left out.
+/// Function: explain its purpose and key arguments
+pub fn combine_datasets(
+    a: &[[f64; 2]],
+    b: &[[f64; 2]],
+    operation: DatasetOperation,
+    expression: &str,
+) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+    if a.is_empty() {
+        return Err("Dataset A has no points".into());
+    }
+// Variable declaration
+    let parsed = match operation {
+        DatasetOperation::Expression => Some(Expression::parse(expression)?),
+        _ => None,
+    };
+    if let Some(name) = parsed
+        .iter()
+        .flat_map(|parsed| &parsed.parameters)
+        .find(|name| !["y", "b"].contains(&name.as_str()))
+    {
+        return Err(format!("Unknown variable '{}' (use x, y and b)", name).into());
+    }
+// Variable declaration
+    let aligned_b = if operation_uses_b(operation, expression) {
+// Variable declaration
+        let mut sorted: Vec<[f64; 2]> = b.iter().copied().filter(|p| p[0].is_finite() && p[1].is_finite()).collect();
+        sorted.sort_by(|p, q| p[0].total_cmp(&q[0]));
+        if sorted.is_empty() {
+            return Err("Dataset B has no points".into());
+        }
+        Some(sorted)
+    } else {
+        None
+    };
+// Variable declaration
+    let mut result = Vec::with_capacity(a.len());
+    for &[x, y] in a {
+// Variable declaration
+        let b_value = match &aligned_b {
+            Some(sorted) => match interpolate_y(sorted, x) {
+                Some(value) => value,
+                None => continue,
+            },
+            None => f64::NAN,
+        };
+// Variable declaration
+        let value = match operation {
+            DatasetOperation::Sum => y + b_value,
+            DatasetOperation::Difference => y - b_value,
+            DatasetOperation::Product => y * b_value,
+            DatasetOperation::Ratio => y / b_value,
+            DatasetOperation::Expression => parsed.as_ref().map_or(f64::NAN, |parsed| {
+// Variable declaration
+                let params: Vec<f64> = parsed
+                    .parameters
+                    .iter()
+                    .map(|name| if name == "y" { y } else { b_value })
+                    .collect();
+                parsed.evaluate(x, &params)
+            }),
+        };
+        if x.is_finite() && value.is_finite() {
+            result.push([x, value]);
+        }
+    }
+    if result.is_empty() {
+        return Err(if aligned_b.is_some() {
+            "The datasets do not overlap in X, or every result is undefined".into()
+        } else {
+            "The expression is undefined at every point".into()
+        });
+    }
+    Ok(result)
+}
+
+// How the baseline subtracted by baseline correction is estimated
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+pub enum BaselineMode {
+    Constant,       // Mean Y between the two anchors
+    Linear,         // Straight line through the Y values at the two anchors
+    RollingMinimum, // Lowest Y within a window of points centred on each point
+}
+
+/// Implementation block defining methods for this type
+impl BaselineMode {
+    pub const ALL: [BaselineMode; 3] = [BaselineMode::Constant, BaselineMode::Linear, BaselineMode::RollingMinimum];
+
+/// Function: explain its purpose and key arguments
+    pub fn to_string(self) -> &'static str {
+        match self {
+            BaselineMode::Constant => "Constant (mean of region)",
+            BaselineMode::Linear => "Linear (two anchors)",
+            BaselineMode::RollingMinimum => "Rolling minimum",
+        }
+    }
+}
+
+// The series minus its baseline. `anchors` bound the region averaged by the constant
+// mode and are the two X values the linear baseline passes through; `window` is the
+// number of points of the rolling minimum.
+/// Function: explain its purpose and key arguments
+pub fn subtract_baseline(
+    points: &[[f64; 2]],
+    mode: BaselineMode,
+    anchors: [f64; 2],
+    window: usize,
+) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+    if points.is_empty() {
+        return Err("The dataset has no points".into());
+    }
+// Variable declaration
+    let (low, high) = (anchors[0].min(anchors[1]), anchors[0].max(anchors[1]));
+// Variable declaration
+    let baseline: Vec<f64> = match mode {
+        BaselineMode::Constant => {
+// Variable declaration
+            let region: Vec<f64> = points
+                .iter()
+                .filter(|p| (low..=high).contains(&p[0]) && p[1].is_finite())
+                .map(|p| p[1])
+                .collect();
+            if region.is_empty() {
+                return Err("No points lie between the two anchors".into());
+            }
+            vec![region.iter().sum::<f64>() / region.len() as f64; points.len()]
+        }
+        BaselineMode::Linear => {
+            if low == high {
+                return Err("The two anchors must differ".into());
+            }
+// Variable declaration
+            let mut sorted: Vec<[f64; 2]> = points.iter().copied().filter(|p| p[1].is_finite()).collect();
+            sorted.sort_by(|a, b| a[0].total_cmp(&b[0]));
+// Variable declaration
+            let (Some(y_low), Some(y_high)) = (interpolate_y(&sorted, low), interpolate_y(&sorted, high)) else {
+                return Err("Both anchors must lie within the dataset's X range".into());
+            };
+// Variable declaration
+            let slope = (y_high - y_low) / (high - low);
+            points.iter().map(|p| y_low + slope * (p[0] - low)).collect()
+        }
+        BaselineMode::RollingMinimum => {
+            if window == 0 {
+                return Err("Window size must be greater than 0".into());
+            }
+// Variable declaration
+            let half = window / 2;
+            (0..points.len())
+                .map(|i| {
+                    points[i.saturating_sub(half)..(i + half + 1).min(points.len())]
+                        .iter()
+                        .map(|p| p[1])
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .collect()
+        }
+    };
+    Ok(points.iter().zip(baseline).map(|(p, base)| [p[0], p[1] - base]).collect())
+}
+
+// Thresholds of the peak finder. For minima the series is searched upside down, so the
+// height is a ceiling and the prominence is measured downwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct PeakSettings {
+    pub minima: bool,
+    pub height: Option<f64>, // Lowest peak Y (highest, for minima)
+    pub prominence: f64,     // How far a peak stands out from the higher of its two bases
+    pub distance: f64,       // Smallest X distance between peaks; the taller one wins
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct Peak {
+    pub x: f64,
+    pub y: f64,
+    pub prominence: f64,
+}
+
+// Local maxima (or minima) of the series that pass the thresholds, ordered by X. The
+// prominence follows the usual definition: from the peak, walk each way until the series
+// rises above it (or ends); the higher of the two lowest points on those walks is the base.
+/// Function: explain its purpose and key arguments
+pub fn find_peaks(points: &[[f64; 2]], settings: PeakSettings) -> Result<Vec<Peak>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let sorted = sorted_by_x(points);
+    if sorted.len() < 3 {
+        return Err("Need at least three points to find peaks".into());
+    }
+// Variable declaration
+    let sign = if settings.minima { -1.0 } else { 1.0 };
+// Variable declaration
+    let y: Vec<f64> = sorted.iter().map(|p| sign * p[1]).collect();
+// Variable declaration
+    let mut peaks = Vec::new();
+    for i in 1..y.len() - 1 {
+        // The first point of a flat top counts as the peak, as long as the series falls
+        // after the top; a step that keeps rising is not a peak
+// Variable declaration
+        let after = y[i + 1..].iter().find(|&&value| value != y[i]);
+        if !(y[i] > y[i - 1] && after.is_some_and(|&value| value < y[i])) {
+            continue;
+        }
+        if settings.height.is_some_and(|height| y[i] < sign * height) {
+            continue;
+        }
+// Variable declaration
+        let base = |range: &mut dyn Iterator<Item = usize>| {
+            range
+                .map(|j| y[j])
+                .take_while(|&value| value <= y[i])
+                .fold(y[i], f64::min)
+        };
+// Variable declaration
+        let prominence = y[i] - base(&mut (0..i).rev()).max(base(&mut (i + 1..y.len())));
+        // A zero threshold still needs the peak to stand out at all
+        if prominence > 0.0 && prominence >= settings.prominence {
+            peaks.push(Peak {
+                x: sorted[i][0],
+                y: sorted[i][1],
+                prominence,
+            });
+        }
+    }
+
+    // Keep the tallest peaks first, dropping any that come too close to one already kept
+    peaks.sort_by(|a, b| (sign * b.y).total_cmp(&(sign * a.y)));
+// Variable declaration
+    let mut kept: Vec<Peak> = Vec::new();
+    for peak in peaks {
+        if kept.iter().all(|other| (other.x - peak.x).abs() >= settings.distance) {
+            kept.push(peak);
+        }
+    }
+    kept.sort_by(|a, b| a.x.total_cmp(&b.x));
+    Ok(kept)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct Crossing {
+    pub x: f64,
+    pub rising: bool, // Crossed upwards (below the threshold before, at or above it after)
+}
+
+// Every X where the series (ordered by X) crosses `threshold`, interpolating linearly
+// between the two points on either side. A point exactly at the threshold counts once.
+/// Function: explain its purpose and key arguments
+pub fn find_crossings(points: &[[f64; 2]], threshold: f64) -> Result<Vec<Crossing>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let sorted = sorted_by_x(points);
+    if sorted.len() < 2 {
+        return Err("Need at least two points to find crossings".into());
+    }
+    Ok(sorted
+        .windows(2)
+        .filter_map(|pair| {
+// Variable declaration
+            let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+// Variable declaration
+            let rising = y0 < threshold && y1 >= threshold;
+// Variable declaration
+            let falling = y0 > threshold && y1 <= threshold;
+            if !(rising || falling) {
+                return None;
+            }
+            Some(Crossing {
+                x: x0 + (threshold - y0) * (x1 - x0) / (y1 - y0),
+                rising,
+            })
+        })
+        .collect())
+}
+
+// How histogram bins are chosen: a fixed number of bins spanning the data, or a fixed bin width
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+pub enum HistogramBins {
+    Count(usize),
+    Width(f64),
+}
+
+// Helper function to bin values into a histogram. Returns one (bin center, count) point
+// per bin, starting at the smallest value; non-finite values are ignored.
+/// Function: explain its purpose and key arguments
+pub fn compute_histogram(values: &[f64], bins: HistogramBins) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return Err("No values to bin".into());
+    }
+
+// Variable declaration
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+// Variable declaration
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+// Variable declaration
+    let (bin_count, bin_width) = match bins {
+        HistogramBins::Count(0) => return Err("Bin count must be greater than 0".into()),
+        HistogramBins::Count(count) if max > min => (count, (max - min) / count as f64),
+        HistogramBins::Count(count) => (count, 1.0),
+        HistogramBins::Width(width) if width > 0.0 && width.is_finite() => {
+            (((max - min) / width).floor() as usize + 1, width)
+        }
+        HistogramBins::Width(_) => return Err("Bin width must be greater than 0".into()),
+    };
+    if bin_count > 100_000 {
+        return Err(format!("Bin width is too small ({} bins)", bin_count).into());
+    }
+
+// Variable declaration
+    let mut counts = vec![0usize; bin_count];
+    for value in values {
+        // The maximum lands on the upper edge of the last bin
+// Variable declaration
+        let index = (((value - min) / bin_width).floor() as usize).min(bin_count - 1);
+        counts[index] += 1;
+    }
+
+    Ok(counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| [min + (i as f64 + 0.5) * bin_width, count as f64])
+        .collect())
+}
+
+// Width of the bars drawn for a series: the smallest gap between neighbouring X values
+// (the bin width for histograms), or 1.0 when there is no gap to measure
+/// Function: explain its purpose and key arguments
+pub fn bar_width(points: impl IntoIterator<Item = [f64; 2]>) -> f64 {
+// Variable declaration
+    let xs: Vec<f64> = points.into_iter().map(|p| p[0]).collect();
+    xs.windows(2)
+        .map(|w| (w[1] - w[0]).abs())
+        .filter(|gap| *gap > 0.0)
+        .fold(None, |narrowest: Option<f64>, gap| Some(narrowest.map_or(gap, |n| n.min(gap))))
+        .unwrap_or(1.0)
+}
+
+// Points whose X lies inside `range` (inclusive, in either order); all points when no range is given
+/// Function: explain its purpose and key arguments
+pub fn points_in_x_range(points: &PointStore, range: Option<(f64, f64)>) -> Vec<[f64; 2]> {
+    match range {
+        Some((a, b)) => {
+// Variable declaration
+            let (low, high) = (a.min(b), a.max(b));
+            points.iter().filter(|p| p[0] >= low && p[0] <= high).collect()
+        }
+        None => points.to_vec(),
+    }
+}
+
+// The points in range that calculations can use: blank cells load as NaN points (gaps in
+// the line), which would turn every sum they enter into NaN
+/// Function: explain its purpose and key arguments
+pub fn finite_points_in_x_range(points: &PointStore, range: Option<(f64, f64)>) -> Vec<[f64; 2]> {
+// Variable declaration
+    let mut points = points_in_x_range(points, range);
+    points.retain(|p| p[0].is_finite() && p[1].is_finite());
+    points
+}
+
+// Summary statistics of the Y values of a series
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct SeriesStatistics {
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64, // Sample standard deviation (n - 1)
+    pub min: f64,
+    pub max: f64,
+    pub x_min: f64,
+    pub x_max: f64,
+}
+
+// Result of block averaging a correlated series (see block_average)
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct BlockAverage {
+    pub mean: f64,
+    pub naive_sem: f64,   // Standard error assuming independent samples (block size 1)
+    pub plateau_sem: f64, // Mean of the curve over its largest third of block sizes
+    pub converged: bool,  // Whether the two halves of that last third agree within 10%
+    pub curve: Vec<[f64; 2]>, // (block size in points, standard error of the mean)
+}
+
+// Standard error of the mean against block size: the series is cut into blocks of
+// consecutive values, and the scatter of the block means gives the error. Block sizes
+// grow geometrically up to a sixteenth of the series, keeping enough blocks for the
+// scatter of their means to be meaningful.
+/// Function: explain its purpose and key arguments
+pub fn block_average(values: &[f64]) -> Result<BlockAverage, Box<dyn std::error::Error>> {
+// Variable declaration
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+// Variable declaration
+    let n = values.len();
+    if n < 64 {
+        return Err("Need at least 64 points for block averaging".into());
+    }
+// Variable declaration
+    let mean = values.iter().sum::<f64>() / n as f64;
+// Variable declaration
+    let mut curve = Vec::new();
+// Variable declaration
+    let mut size = 1;
+    while size <= n / 16 {
+// Variable declaration
+        let means: Vec<f64> = values.chunks_exact(size).map(|block| block.iter().sum::<f64>() / size as f64).collect();
+// Variable declaration
+        let m = means.len() as f64;
+// Variable declaration
+        let center = means.iter().sum::<f64>() / m;
+// Variable declaration
+        let variance = means.iter().map(|v| (v - center).powi(2)).sum::<f64>() / (m - 1.0);
+        curve.push([size as f64, (variance / m).sqrt()]);
+        size = (size + 1).max((size as f64 * 1.2).round() as usize);
+    }
+// Variable declaration
+    let tail = &curve[curve.len() - curve.len().div_ceil(3)..];
+// Variable declaration
+    let plateau_sem = tail.iter().map(|p| p[1]).sum::<f64>() / tail.len() as f64;
+// Variable declaration
+    let half_mean = |half: &[[f64; 2]]| half.iter().map(|p| p[1]).sum::<f64>() / half.len() as f64;
+// Variable declaration
+    let (first, last) = tail.split_at(tail.len() / 2);
+    Ok(BlockAverage {
+        mean,
+        naive_sem: curve[0][1],
+        plateau_sem,
+        converged: first.is_empty() || (half_mean(last) - half_mean(first)).abs() <= 0.1 * plateau_sem,
+        curve,
+    })
+}
+
+// Helper function to summarize a series; non-finite points are ignored
+/// Function: explain its purpose and key arguments
+pub fn compute_statistics(points: &[[f64; 2]]) -> Result<SeriesStatistics, Box<dyn std::error::Error>> {
+// Variable declaration
+    let points: Vec<[f64; 2]> = points
+        .iter()
+        .copied()
+        .filter(|p| p[0].is_finite() && p[1].is_finite())
+        .collect();
+    if points.is_empty() {
+        return Err("No finite points to summarize".into());
+    }
+
+// Variable declaration
+    let count = points.len();
+// Variable declaration
+    let mean = points.iter().map(|p| p[1]).sum::<f64>() / count as f64;
+// Variable declaration
+    let variance = if count > 1 {
+        points.iter().map(|p| (p[1] - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+    } else {
+        0.0
+    };
+    Ok(SeriesStatistics {
+        count,
+        mean,
+        std_dev: variance.sqrt(),
+        min: points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min),
+        max: points.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max),
+        x_min: points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min),
+        x_max: points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+// How closely two curves agree, measured at the X values of the first (see compare_datasets)
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct ComparisonMetrics {
+    pub count: usize, // Points of A inside the X range of B
+    pub x_min: f64,
+    pub x_max: f64,
+    pub rmsd: f64,
+    pub pearson: Option<f64>,  // None when either curve is flat over the overlap
+    pub spearman: Option<f64>, // Pearson correlation of the ranks, ties sharing their mean rank
+    pub max_deviation: f64,    // Largest |A - B|
+    pub max_deviation_at: [f64; 2], // Point of A where that deviation occurs
+}
+
+/// Implementation block defining methods for this type
+impl ComparisonMetrics {
+    // One metric per line as name and value separated by a tab, at full precision, so the
+    // results paste into a spreadsheet as two columns
+/// Function: explain its purpose and key arguments
+    pub fn to_tsv(self) -> String {
+// Variable declaration
+        let optional = |value: Option<f64>| value.map_or("undefined".to_string(), |value| value.to_string());
+        [
+            format!("points\t{}", self.count),
+            format!("x_min\t{}", self.x_min),
+            format!("x_max\t{}", self.x_max),
+            format!("rmsd\t{}", self.rmsd),
+            format!("pearson_r\t{}", optional(self.pearson)),
+            format!("spearman_rho\t{}", optional(self.spearman)),
+            format!("max_deviation\t{}", self.max_deviation),
+            format!("max_deviation_x\t{}", self.max_deviation_at[0]),
+        ]
+        .join("\n")
+    }
+
+    // Short form for a plot annotation
+/// Function: explain its purpose and key arguments
+    pub fn summary(&self) -> String {
+// Variable declaration
+        let optional = |value: Option<f64>| value.map_or("–".to_string(), |value| format!("{:.3}", value));
+        format!(
+            "RMSD {}, r {}, ρ {}, max |Δ| {}",
+            format_number(self.rmsd),
+            optional(self.pearson),
+            optional(self.spearman),
+            format_number(self.max_deviation)
+        )
+    }
+}
+
+// Pearson correlation coefficient of two equally long series, or None when either has
+// no spread
+/// Function: explain its purpose and key arguments
+fn pearson_correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+// Variable declaration
+    let n = a.len() as f64;
+// Variable declaration
+    let (mean_a, mean_b) = (a.iter().sum::<f64>() / n, b.iter().sum::<f64>() / n);
+// Variable declaration
+    let (mut covariance, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return None;
+    }
+    Some((covariance / (var_a * var_b).sqrt()).clamp(-1.0, 1.0))
+}
+
+// Rank of each value (1 for the smallest); equal values share the mean of their ranks
+/// Function: explain its purpose and key arguments
+fn ranks(values: &[f64]) -> Vec<f64> {
+// Variable declaration
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&i, &j| values[i].total_cmp(&values[j]));
+// Variable declaration
+    let mut ranks = vec![0.0; values.len()];
+// Variable declaration
+    let mut start = 0;
+    while start < order.len() {
+// Variable declaration
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // Positions start..end hold ranks start + 1 to end
+// Variable declaration
+        let shared = (start + end + 1) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = shared;
+        }
+        start = end;
+    }
+    ranks
+}
+
+// Compare curve A with curve B: B is linearly interpolated onto the X values of A, as in
+// dataset math, and points of A outside the X range of B are left out. Non-finite points
+// are ignored.
+/// Function: explain its purpose and key arguments
+pub fn compare_datasets(a: &[[f64; 2]], b: &[[f64; 2]]) -> Result<ComparisonMetrics, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut sorted_b: Vec<[f64; 2]> = b.iter().copied().filter(|p| p[0].is_finite() && p[1].is_finite()).collect();
+    sorted_b.sort_by(|p, q| p[0].total_cmp(&q[0]));
+    if sorted_b.is_empty() {
+        return Err("Dataset B has no points".into());
+    }
+// Variable declaration
+    let aligned: Vec<([f64; 2], f64)> = a
+        .iter()
+        .filter(|p| p[0].is_finite() && p[1].is_finite())
+        .filter_map(|&p| interpolate_y(&sorted_b, p[0]).filter(|b_value| b_value.is_finite()).map(|b_value| (p, b_value)))
+        .collect();
+    if aligned.len() < 2 {
+        return Err("The datasets overlap in fewer than two points of A".into());
+    }
+
+// Variable declaration
+    let values_a: Vec<f64> = aligned.iter().map(|(p, _)| p[1]).collect();
+// Variable declaration
+    let values_b: Vec<f64> = aligned.iter().map(|(_, b_value)| *b_value).collect();
+// Variable declaration
+    let (max_deviation_at, max_deviation) = aligned
+        .iter()
+        .map(|(p, b_value)| (*p, (p[1] - b_value).abs()))
+        .fold(([f64::NAN; 2], -1.0), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+// Variable declaration
+    let squared_sum: f64 = aligned.iter().map(|(p, b_value)| (p[1] - b_value).powi(2)).sum();
+    Ok(ComparisonMetrics {
+        count: aligned.len(),
+        x_min: aligned.iter().map(|(p, _)| p[0]).fold(f64::INFINITY, f64::min),
+        x_max: aligned.iter().map(|(p, _)| p[0]).fold(f64::NEG_INFINITY, f64::max),
+        rmsd: (squared_sum / aligned.len() as f64).sqrt(),
+        pearson: pearson_correlation(&values_a, &values_b),
+        spearman: pearson_correlation(&ranks(&values_a), &ranks(&values_b)),
+        max_deviation,
+        max_deviation_at,
+    })
+}
+
+// Natural logarithm of the gamma function for x > 0 (Lanczos approximation, relative
+// error below about 1e-10)
+/// Function: explain its purpose and key arguments
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+// Variable declaration
+    let tmp = x + 5.5;
+// Variable declaration
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000000000190015, |sum, (i, c)| sum + c / (x + 1.0 + i as f64));
+    (2.5066282746310005 * series / x).ln() + (x + 0.5) * tmp.ln() - tmp
+}
+
+// Regularized incomplete beta function I_x(a, b), evaluated with its continued fraction
+/// Function: explain its purpose and key arguments
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    // The continued fraction converges quickly only below the mean; above it the
+    // symmetry I_x(a, b) = 1 - I_(1-x)(b, a) is used
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - incomplete_beta(1.0 - x, b, a);
+    }
+// Variable declaration
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+    // Lentz's method
+// Variable declaration
+    let tiny = 1e-300;
+// Variable declaration
+    let (mut c, mut d) = (1.0, 1.0 - (a + b) * x / (a + 1.0));
+    d = 1.0 / if d.abs() < tiny { tiny } else { d };
+// Variable declaration
+    let mut fraction = d;
+    for m in 1..=300 {
+// Variable declaration
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            d = 1.0 / if d.abs() < tiny { tiny } else { d };
+            c = 1.0 + numerator / c;
+            c = if c.abs() < tiny { tiny } else { c };
+            fraction *= c * d;
+        }
+        if (c * d - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    front * fraction
+}
+
+// Probability that a Student's t variable with `df` degrees of freedom lies at least
+// |t| away from zero (the two-sided p-value of t)
+/// Function: explain its purpose and key arguments
+pub fn student_t_p_value(t: f64, df: f64) -> f64 {
+    if t.is_nan() {
+        return f64::NAN;
+    }
+    incomplete_beta(df / (df + t * t), df / 2.0, 0.5)
+}
+
+// The t with `df` degrees of freedom that a two-sided interval of the given
+// `confidence` (e.g. 0.95) reaches out to, found by bisection
+/// Function: explain its purpose and key arguments
+pub fn student_t_critical(confidence: f64, df: f64) -> f64 {
+// Variable declaration
+    let alpha = 1.0 - confidence;
+// Variable declaration
+    let (mut low, mut high) = (0.0, 1.0);
+    while student_t_p_value(high, df) > alpha && high < 1e6 {
+        high *= 2.0;
+    }
+    for _ in 0..100 {
+// Variable declaration
+        let middle = (low + high) / 2.0;
+        if student_t_p_value(middle, df) > alpha {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// Five-number summary behind a box plot. The whiskers reach the most extreme values
+// within 1.5 interquartile ranges of the box (Tukey); values beyond them are outliers.
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct BoxSummary {
+    pub lower_whisker: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub upper_whisker: f64,
+    pub outliers: Vec<f64>,
+}
+
+// Quantile `q` (0 to 1) of ascending values, interpolated linearly between neighbours
+/// Function: explain its purpose and key arguments
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+// Variable declaration
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+// Variable declaration
+    let (low, high) = (position.floor() as usize, position.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (position - low as f64)
+}
+
+// Box plot summary of the finite values; None when there are none
+/// Function: explain its purpose and key arguments
+pub fn box_summary(values: &[f64]) -> Option<BoxSummary> {
+// Variable declaration
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(f64::total_cmp);
+// Variable declaration
+    let (q1, median, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.5), quantile(&sorted, 0.75));
+// Variable declaration
+    let fence = 1.5 * (q3 - q1);
+// Variable declaration
+    let inside = |v: f64| v >= q1 - fence && v <= q3 + fence;
+    Some(BoxSummary {
+        lower_whisker: sorted.iter().copied().find(|v| inside(*v)).unwrap_or(q1),
+        q1,
+        median,
+        q3,
+        upper_whisker: sorted.iter().rev().copied().find(|v| inside(*v)).unwrap_or(q3),
+        outliers: sorted.iter().copied().filter(|v| !inside(*v)).collect(),
+    })
+}
+
+// Gaussian kernel density estimate of the finite values at `samples` evenly spaced points
+// from the lowest to the highest value, as [value, density] pairs. The bandwidth follows
+// Silverman's rule of thumb; the values are first binned so large datasets stay quick.
+/// Function: explain its purpose and key arguments
+pub fn kernel_density(values: &[f64], samples: usize) -> Vec<[f64; 2]> {
+    const BINS: usize = 512;
+// Variable declaration
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() || samples < 2 {
+        return Vec::new();
+    }
+    sorted.sort_by(f64::total_cmp);
+// Variable declaration
+    let n = sorted.len() as f64;
+// Variable declaration
+    let (low, high) = (sorted[0], sorted[sorted.len() - 1]);
+// Variable declaration
+    let mean = sorted.iter().sum::<f64>() / n;
+// Variable declaration
+    let std_dev = (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0)).sqrt();
+// Variable declaration
+    let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+// Variable declaration
+    let spread = if iqr > 0.0 { std_dev.min(iqr / 1.34) } else { std_dev };
+// Variable declaration
+    let bandwidth = 0.9 * spread * n.powf(-0.2);
+    if bandwidth <= 0.0 || high <= low {
+        // All values equal: a single spike
+        return vec![[low, 1.0]];
+    }
+
+// Variable declaration
+    let bin_width = (high - low) / BINS as f64;
+// Variable declaration
+    let mut counts = vec![0.0; BINS];
+    for v in &sorted {
+        counts[(((v - low) / bin_width) as usize).min(BINS - 1)] += 1.0;
+    }
+// Variable declaration
+    let norm = n * bandwidth * (2.0 * std::f64::consts::PI).sqrt();
+    (0..samples)
+        .map(|k| {
+// Variable declaration
+            let y = low + (high - low) * k as f64 / (samples - 1) as f64;
+// Variable declaration
+            let density: f64 = counts
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0.0)
+                .map(|(bin, count)| {
+// Variable declaration
+                    let centre = low + (bin as f64 + 0.5) * bin_width;
+                    count * (-0.5 * ((y - centre) / bandwidth).powi(2)).exp()
+                })
+                .sum();
+            [y, density / norm]
+        })
+        .collect()
+}
+
+// What a box or violin draws for a dataset: the box summary of its Y values in plot
+// coordinates and, for violins, their kernel density (empty for box plots)
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct Distribution {
+    pub summary: BoxSummary,
+    pub density: Vec<[f64; 2]>,
+}
+
+// Distribution of `dataset`'s Y values on `y_scale`; None when the axis can show none of them
+/// Function: explain its purpose and key arguments
+pub fn distribution(dataset: &Dataset, y_scale: AxisScale) -> Option<Distribution> {
+// Variable declaration
+    let values: Vec<f64> = dataset.points.iter().filter_map(|p| y_scale.apply(p[1])).collect();
+// Variable declaration
+    let summary = box_summary(&values)?;
+// Variable declaration
+    let density = if dataset.plot_type == PlotType::Violin { kernel_density(&values, 100) } else { Vec::new() };
+    Some(Distribution { summary, density })
+}
+
+// Write points as a two-column CSV file with an "x,y" header
+/// Function: explain its purpose and key arguments
+pub fn save_points_csv(path: &Path, points: &[[f64; 2]]) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["x", "y"])?;
+    for point in points {
+        writer.write_record([point[0].to_string(), point[1].to_string()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Write detected peaks to CSV with their prominence
+/// Function: explain its purpose and key arguments
+pub fn save_peaks_csv(path: &Path, peaks: &[Peak]) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["x", "y", "prominence"])?;
+    for peak in peaks {
+        writer.write_record([peak.x.to_string(), peak.y.to_string(), peak.prominence.to_string()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Reduce a series to at most `max_points` for display by keeping the minimum and
+// maximum Y of each consecutive bucket (in their original order), so spikes and the
+// overall envelope survive. The first point missing a value in a bucket is kept too so
+// the line still breaks there. Series that are already small enough are returned as is.
+/// Function: explain its purpose and key arguments
+pub fn downsample_min_max(points: &PointStore, max_points: usize) -> Vec<[f64; 2]> {
+    if points.len() <= max_points || max_points < 4 {
+        return points.to_vec();
+    }
+
+// Variable declaration
+    let bucket_count = max_points / 2;
+// Variable declaration
+    let bucket_size = points.len().div_ceil(bucket_count);
+// Variable declaration
+    let mut result = Vec::with_capacity(bucket_count * 2);
+    for start in (0..points.len()).step_by(bucket_size) {
+// Variable declaration
+        let bucket: Vec<[f64; 2]> = (start..(start + bucket_size).min(points.len())).map(|i| points.point(i)).collect();
+// Variable declaration
+        let is_gap = |p: &[f64; 2]| p.iter().any(|v| !v.is_finite());
+// Variable declaration
+        let gap = bucket.iter().position(is_gap);
+// Variable declaration
+        let mut extremes: Option<(usize, usize)> = None;
+        for (i, p) in bucket.iter().enumerate().filter(|(_, p)| !is_gap(p)) {
+// Variable declaration
+            let (min_index, max_index) = extremes.get_or_insert((i, i));
+            if p[1] < bucket[*min_index][1] {
+                *min_index = i;
+            }
+            if p[1] > bucket[*max_index][1] {
+                *max_index = i;
+            }
+        }
+// Variable declaration
+        let mut kept: Vec<usize> = extremes.map_or(Vec::new(), |(min, max)| vec![min, max]);
+        kept.extend(gap);
+        kept.sort_unstable();
+        kept.dedup();
+        result.extend(kept.into_iter().map(|i| bucket[i]));
+    }
+    result
+}
+
+// Step between points so that roughly `max_points` of `len` are visited
+/// Function: explain its purpose and key arguments
+pub fn decimation_stride(len: usize, max_points: usize) -> usize {
+    if max_points == 0 {
+        return 1;
+    }
+    len.div_ceil(max_points).max(1)
+}
+
+// Helper function to get data bounds
+/// Function: explain its purpose and key arguments
+pub fn get_data_bounds(datasets: &[impl Borrow<Dataset>]) -> Option<(f64, f64, f64, f64)> {
+    get_scaled_data_bounds(datasets, AxisScale::LINEAR, AxisScale::LINEAR)
+}
+
+// Data bounds in plot coordinates for the given axis scales. Values that cannot be
+// shown on a log axis are skipped; error bar ends that would cross zero fall back to
+// the point itself.
+/// Function: explain its purpose and key arguments
+pub fn get_scaled_data_bounds(
+    datasets: &[impl Borrow<Dataset>],
+    x_scale: AxisScale,
+    y_scale: AxisScale,
+) -> Option<(f64, f64, f64, f64)> {
+    if datasets.is_empty() {
+        return None;
+    }
+    
+// Variable declaration
+    let mut min_x = f64::INFINITY;
+// Variable declaration
+    let mut max_x = f64::NEG_INFINITY;
+// Variable declaration
+    let mut min_y = f64::INFINITY;
+// Variable declaration
+    let mut max_y = f64::NEG_INFINITY;
+    
+// Variable declaration
+    let positions = distribution_positions(datasets);
+    for (dataset, position) in datasets.iter().map(Borrow::borrow).zip(positions) {
+        // Boxes and violins span their Y values around their slot on the X axis
+        if let Some(position) = position {
+            for x in [x_scale.slot(position - 0.5), x_scale.slot(position + 0.5)] {
+                min_x = min_x.min(x);
+                max_x = max_x.max(x);
+            }
+            for y in dataset.points.iter().filter_map(|p| y_scale.apply(p[1])) {
+                min_y = min_y.min(y);
+                max_y = max_y.max(y);
+            }
+            continue;
+        }
+        // Bars and filled areas reach down to zero; bars also half a bar to either side of their point
+// Variable declaration
+        let half_bar = if dataset.plot_type.draws_bars() { bar_width(&dataset.points) / 2.0 } else { 0.0 };
+        if (dataset.plot_type.draws_bars() || dataset.fill.reaches_zero()) && !y_scale.is_log() && !dataset.points.is_empty() {
+            min_y = min_y.min(0.0);
+            max_y = max_y.max(0.0);
+        }
+        for (i, point) in dataset.points.iter().enumerate() {
+// Variable declaration
+            let (Some(x), Some(y)) = (x_scale.apply(point[0]), y_scale.apply(point[1])) else {
+                continue;
+            };
+            if half_bar > 0.0 {
+                min_x = min_x.min(x_scale.apply(point[0] - half_bar).unwrap_or(x));
+                max_x = max_x.max(x_scale.apply(point[0] + half_bar).unwrap_or(x));
+            }
+            // Include error bar extents so they are not clipped
+// Variable declaration
+            let dx = dataset.x_err.as_ref().and_then(|e| e.get(i)).copied().unwrap_or(0.0);
+// Variable declaration
+            let dy = dataset.y_err.as_ref().and_then(|e| e.get(i)).copied().unwrap_or(0.0);
+            // Shaded bands likewise
+// Variable declaration
+            let dy = dataset.band.as_ref().and_then(|b| b.get(i)).map_or(dy, |w| dy.max(w.abs()));
+            min_x = min_x.min(x_scale.apply(point[0] - dx).unwrap_or(x));
+            max_x = max_x.max(x_scale.apply(point[0] + dx).unwrap_or(x));
+            min_y = min_y.min(y_scale.apply(point[1] - dy).unwrap_or(y));
+            max_y = max_y.max(y_scale.apply(point[1] + dy).unwrap_or(y));
+        }
+    }
+    
+    Some((min_x, max_x, min_y, max_y))
+}
+
+// Data bounds grown to cover the heatmap cells. Heatmaps are left out on log axes,
+// where they are not drawn.
+/// Function: explain its purpose and key arguments
+pub fn get_scaled_bounds_with_heatmaps(
+    datasets: &[impl Borrow<Dataset>],
+    heatmaps: &[Heatmap],
+    x_scale: AxisScale,
+    y_scale: AxisScale,
+) -> Option<(f64, f64, f64, f64)> {
+// Variable declaration
+    let data = get_scaled_data_bounds(datasets, x_scale, y_scale)
+        .filter(|(min_x, max_x, min_y, max_y)| min_x <= max_x && min_y <= max_y);
+    if x_scale.is_log() || y_scale.is_log() {
+        return data;
+    }
+    heatmaps
+        .iter()
+        .filter_map(|heatmap| scaled_heatmap_bounds(heatmap, x_scale, y_scale))
+        .chain(data)
+        .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1), a.2.min(b.2), a.3.max(b.3)))
+}
+
+// Outer edges of a heatmap in plot coordinates, as (min x, max x, min y, max y)
+/// Function: explain its purpose and key arguments
+pub fn scaled_heatmap_bounds(heatmap: &Heatmap, x_scale: AxisScale, y_scale: AxisScale) -> Option<(f64, f64, f64, f64)> {
+// Variable declaration
+    let (min_x, max_x, min_y, max_y) = heatmap.bounds();
+// Variable declaration
+    let ((min_x, max_x), (min_y, max_y)) = (x_scale.apply_range(min_x, max_x)?, y_scale.apply_range(min_y, max_y)?);
+    Some((min_x, max_x, min_y, max_y))
+}
+
+// Right-hand Y axis of a subplot. Its values are drawn in the plot coordinates of the
+// left axis: plot = offset + scale * value, so that both data ranges fill the same height.
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct SecondaryAxis {
+    pub offset: f64,
+    pub scale: f64,
+}
+
+/// Implementation block defining methods for this type
+impl SecondaryAxis {
+/// Function: explain its purpose and key arguments
+    pub fn to_plot(self, value: f64) -> f64 {
+        self.offset + self.scale * value
+    }
+
+/// Function: explain its purpose and key arguments
+    pub fn to_value(self, plot: f64) -> f64 {
+        (plot - self.offset) / self.scale
+    }
+}
+
+// Grid marks of the right axis, with their labels, for a plot showing [min_y, max_y] of the
+// left axis' plot coordinates over `pixels`: round values of the secondary range, chosen
+// like the left axis' marks (see axis_grid_marks) and moved into plot coordinates
+/// Function: explain its purpose and key arguments
+pub fn secondary_axis_marks(
+    axis: SecondaryAxis,
+    (min_y, max_y): (f64, f64),
+    pixels: u32,
+    config: &SubplotConfig,
+) -> Vec<(GridMark, String)> {
+// Variable declaration
+    let (low, high) = (axis.to_value(min_y), axis.to_value(max_y));
+    axis_grid_marks((low.min(high), low.max(high)), AxisScale::LINEAR, pixels, config, Y_LABEL_SPACING_PX)
+        .into_iter()
+        .map(|mark| {
+// Variable declaration
+            let placed = GridMark { value: axis.to_plot(mark.value), step_size: mark.step_size * axis.scale.abs() };
+            (placed, format_grid_mark(mark.value))
+        })
+        .collect()
+}
+
+// The right axis of the subplot, or None when no dataset is on it. The secondary range
+// (custom or fitted to the datasets on it) is lined up with the range of the left axis.
+/// Function: explain its purpose and key arguments
+pub fn secondary_axis(datasets: &[Cow<'_, Dataset>], config: &SubplotConfig) -> Option<SecondaryAxis> {
+// Variable declaration
+    let (secondary, primary): (Vec<&Dataset>, Vec<&Dataset>) =
+        datasets.iter().map(|dataset| dataset.as_ref()).partition(|dataset| dataset.secondary_y);
+    if secondary.is_empty() {
+        return None;
+    }
+// Variable declaration
+    let parse_range = |min: &str, max: &str, scale: AxisScale| {
+        scale.apply_range(min.trim().parse().ok()?, max.trim().parse().ok()?)
+    };
+// Variable declaration
+    let data_range = |datasets: &[&Dataset], y_scale: AxisScale| {
+        get_scaled_data_bounds(datasets, config.x_axis(), y_scale)
+            .map(|(_, _, min_y, max_y)| (min_y, max_y))
+            .filter(|(min_y, max_y)| min_y <= max_y)
+    };
+// Variable declaration
+    let widen = |(low, high): (f64, f64)| if high > low { (low, high) } else { (low - 1.0, high + 1.0) };
+// Variable declaration
+    let (low2, high2) = widen(
+        config
+            .use_custom_y2_bounds
+            .then(|| parse_range(&config.custom_y2_min, &config.custom_y2_max, AxisScale::LINEAR))
+            .flatten()
+            .or_else(|| data_range(&secondary, AxisScale::LINEAR))?,
+    );
+    // Without datasets on the left axis, the right one keeps its own values
+    if primary.is_empty() && !config.use_custom_bounds {
+        return Some(SecondaryAxis { offset: 0.0, scale: 1.0 });
+    }
+// Variable declaration
+    let (low, high) = widen(
+        config
+            .use_custom_bounds
+            .then(|| parse_range(&config.custom_y_min, &config.custom_y_max, config.y_axis()))
+            .flatten()
+            .or_else(|| data_range(&primary, config.y_axis()))
+            .unwrap_or((low2, high2)),
+    );
+// Variable declaration
+    let scale = (high - low) / (high2 - low2);
+    Some(SecondaryAxis { offset: low - scale * low2, scale })
+}
+
+// Move the datasets on the right axis into the plot coordinates of the left axis (see
+// SecondaryAxis). Y errors and bands are scaled along on a linear axis and dropped on a
+// log axis, where they would no longer be symmetric. Only the moved datasets are copied.
+/// Function: explain its purpose and key arguments
+pub fn place_secondary_datasets(datasets: &mut [Cow<'_, Dataset>], axis: Option<SecondaryAxis>, y_scale: AxisScale) {
+// Variable declaration
+    let Some(axis) = axis else {
+        return;
+    };
+    for dataset in datasets.iter_mut().filter(|dataset| dataset.secondary_y) {
+// Variable declaration
+        let points = dataset
+            .points
+            .iter()
+            .map(|p| [p[0], y_scale.invert(axis.to_plot(p[1]))])
+            .collect();
+        // The placed points only change with their source and the axis, so the display
+        // cache keeps them from frame to frame
+// Variable declaration
+        let sources = [
+            dataset.revision(),
+            axis.offset.to_bits(),
+            axis.scale.to_bits(),
+            y_scale.kind as u64,
+            y_scale.reversed as u64,
+        ];
+// Variable declaration
+        let dataset = dataset.to_mut();
+// Variable declaration
+        let y_err = dataset.y_err.take().filter(|_| !y_scale.is_log());
+// Variable declaration
+        let band = dataset.band.take().filter(|_| !y_scale.is_log());
+        dataset.set_derived_points(points, &sources);
+        dataset.y_err = y_err.map(|errors| errors.iter().map(|e| e * axis.scale.abs()).collect());
+        dataset.band = band.map(|widths| widths.iter().map(|w| w * axis.scale.abs()).collect());
+    }
+}
+
+// View that keeps the newest data in sight: the last `window` of X in plot coordinates
+// (0 shows all of it) with Y fitted to the points inside, both padded by 5%
+/// Function: explain its purpose and key arguments
+pub fn newest_data_bounds(
+    datasets: &[Cow<'_, Dataset>],
+    x_scale: AxisScale,
+    y_scale: AxisScale,
+    window: f64,
+) -> Option<(f64, f64, f64, f64)> {
+// Variable declaration
+    let points: Vec<[f64; 2]> = datasets
+        .iter()
+        .flat_map(|dataset| dataset.points.iter())
+        .filter_map(|point| scale_point(&point, x_scale, y_scale))
+        .collect();
+// Variable declaration
+    let mut max_x = points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max);
+// Variable declaration
+    let mut min_x = points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min);
+    // The newest data is on the left of a reversed axis
+    if window > 0.0 && x_scale.reversed {
+        max_x = min_x + window;
+    } else if window > 0.0 {
+        min_x = max_x - window;
+    }
+// Variable declaration
+    let (min_y, max_y) = points
+        .iter()
+        .filter(|p| (min_x..=max_x).contains(&p[0]))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p[1]), hi.max(p[1])));
+    if !min_y.is_finite() || !max_x.is_finite() || !min_x.is_finite() {
+        return None;
+    }
+// Variable declaration
+    let pad = |range: f64| if range > 0.0 { range * 0.05 } else { 1.0 };
+    Some((
+        min_x - pad(max_x - min_x),
+        max_x + pad(max_x - min_x),
+        min_y - pad(max_y - min_y),
+        max_y + pad(max_y - min_y),
+    ))
+}
+
+// (dataset, point) indices of the point closest to `target` (in plot coordinates) on
+// screen, if it is within `max_pixels`. `pixel` is the size of one screen pixel in plot
+// units along X and Y, so both directions count alike. Boxes and violins have no single
+// points and are skipped.
+/// Function: explain its purpose and key arguments
+pub fn nearest_point(
+    datasets: &[Cow<'_, Dataset>],
+    (x_scale, y_scale): (AxisScale, AxisScale),
+    target: [f64; 2],
+    pixel: [f64; 2],
+    max_pixels: f64,
+) -> Option<(usize, usize)> {
+// Variable declaration
+    let mut nearest: Option<((usize, usize), f64)> = None;
+    for (i, dataset) in datasets.iter().enumerate() {
+        if dataset.plot_type.draws_distribution() {
+            continue;
+        }
+        for (j, point) in dataset.points.iter().enumerate() {
+// Variable declaration
+            let Some([x, y]) = scale_point(&point, x_scale, y_scale) else {
+                continue;
+            };
+// Variable declaration
+            let distance = ((x - target[0]) / pixel[0]).powi(2) + ((y - target[1]) / pixel[1]).powi(2);
+            if distance <= max_pixels * max_pixels && nearest.is_none_or(|(_, best)| distance < best) {
+                nearest = Some(((i, j), distance));
+            }
+        }
+    }
+    nearest.map(|(indices, _)| indices)
+}
+
+// Map a data point to plot coordinates, or None when a log axis cannot show it or the
+// point is missing a value (a gap in the line)
+/// Function: explain its purpose and key arguments
+pub fn scale_point(point: &[f64; 2], x_scale: AxisScale, y_scale: AxisScale) -> Option<[f64; 2]> {
+    Some([x_scale.apply(point[0])?, y_scale.apply(point[1])?]).filter(|p| p.iter().all(|v| v.is_finite()))
+}
+
+// Grid spacer for log axes, in the interactive plot and in exports: decades as major marks (every tenth
+// decade stronger still) and 2..9 within each decade as minor marks. egui_plot fades
+// marks out and hides their labels once they get too close together.
+/// Function: explain its purpose and key arguments
+pub fn log_axis_grid_marks(input: GridInput) -> Vec<GridMark> {
+// Variable declaration
+    let (min, max) = input.bounds;
+    if !min.is_finite() || !max.is_finite() || max - min > 1000.0 {
+        return Vec::new();
+    }
+
+// Variable declaration
+    let mut marks = Vec::new();
+    for decade in (min.floor() as i64)..=(max.ceil() as i64) {
+// Variable declaration
+        let step_size = if decade % 10 == 0 { 10.0 } else { 1.0 };
+        marks.push(GridMark { value: decade as f64, step_size });
+        if max - min > 50.0 {
+            continue;
+        }
+        for factor in 2..=9 {
+// Variable declaration
+            let value = decade as f64 + (factor as f64).log10();
+            if value >= min && value <= max {
+                marks.push(GridMark { value, step_size: 0.1 });
+            }
+        }
+    }
+    marks
+}
+
+// Label for a value on a log axis: plain decimals near 1, otherwise compact
+// mantissa/exponent form such as 1e-6 or 2e9
+/// Function: explain its purpose and key arguments
+pub fn format_log_tick(value: f64) -> String {
+    if value <= 0.0 || !value.is_finite() {
+        return format_number(value);
+    }
+// Variable declaration
+    let exponent = (value.log10() + 1e-9).floor() as i32;
+    if (-3..=4).contains(&exponent) {
+// Variable declaration
+        let decimals = (-exponent).max(0) as usize;
+        format!("{:.*}", decimals, value)
+    } else {
+// Variable declaration
+        let mantissa = value / 10f64.powi(exponent);
+        format!("{}e{}", mantissa.round(), exponent)
+    }
+}
+
+// Save dialog offering both figure formats, the preferred one first. A name typed without
+// an extension gets the preferred format's.
+/// Function: explain its purpose and key arguments
+pub fn pick_figure_save_path(file_stem: &str, preferred: ExportFormat) -> Option<PathBuf> {
+// Variable declaration
+    let mut dialog = rfd::FileDialog::new().set_file_name(format!("{}.{}", file_stem, preferred.extension()));
+    for format in std::iter::once(preferred).chain(ExportFormat::ALL.into_iter().filter(|&f| f != preferred)) {
+        dialog = dialog.add_filter(format.to_string(), &[format.extension()]);
+    }
+// Variable declaration
+    let path = dialog.save_file()?;
+    if path.extension().is_none() {
+        return Some(path.with_extension(preferred.extension()));
+    }
+    Some(path)
+}
+
+// Render subplots and write them to `path` without any dialog (used by headless export);
+// a .pdf path gets a PDF document, a .py path a matplotlib script, anything else a PNG image
+/// Function: explain its purpose and key arguments
+pub fn export_subplots_to_path(
+    path: &Path,
+    subplots: &[Subplot],
+    layout: &SubplotLayout,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    save_numbered_subplots(path, subplots, layout, 1, dark_mode, font_size, settings)
+}
+
+// Write the figure in the format `path` names; subplot titles count up from `first_number`
+/// Function: explain its purpose and key arguments
+fn save_numbered_subplots(
+    path: &Path,
+    subplots: &[Subplot],
+    layout: &SubplotLayout,
+    first_number: usize,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match ExportFormat::from_path(path) {
+        Some(ExportFormat::Pdf) => {
+            std::fs::write(path, render_subplots_pdf(subplots, layout, first_number, dark_mode, font_size, settings)?)?;
+            return Ok(());
+        }
+        Some(ExportFormat::PythonScript) => {
+            std::fs::write(path, render_subplots_script(subplots, layout, first_number, dark_mode, font_size, settings)?)?;
+            return Ok(());
+        }
+        Some(ExportFormat::Png) | None => {}
+    }
+// Variable declaration
+    let img = render_numbered_subplots(subplots, layout, first_number, dark_mode, font_size, settings)?;
+    save_png_with_dpi(&img, path, settings.dpi)
+}
+
+// Render the whole subplot grid into one image of the size given by `settings`
+/// Function: explain its purpose and key arguments
+pub fn render_subplots_image(
+    subplots: &[Subplot],
+    layout: &SubplotLayout,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<image::RgbImage, Box<dyn std::error::Error>> {
+    render_numbered_subplots(subplots, layout, 1, dark_mode, font_size, settings)
+}
+
+// Grid rendering shared by the export functions; subplot titles count up from `first_number`
+/// Function: explain its purpose and key arguments
+fn render_numbered_subplots(
+    subplots: &[Subplot],
+    layout: &SubplotLayout,
+    first_number: usize,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<image::RgbImage, Box<dyn std::error::Error>> {
+    if subplots.is_empty() {
+        return Err("No subplots to export".into());
+    }
+
+// Variable declaration
+    let style = ExportStyle::new(dark_mode, font_size, settings.scale);
+    // Laid out at scale 1 and then enlarged, so a scaled figure is drawn at full resolution
+// Variable declaration
+    let figure = export_figure_layout(layout, settings).scaled(settings.scale);
+
+// Variable declaration
+    let mut img_buffer = image::RgbImage::from_pixel(figure.size.0, figure.size.1, style.background);
+
+    // Draw each subplot
+    for (subplot_idx, subplot) in subplots.iter().enumerate() {
+// Variable declaration
+        let Some(&(subplot_x, subplot_y, subplot_width, subplot_height)) = figure.cells.get(subplot_idx) else {
+            break;
+        };
+        render_subplot_to_image(
+            &mut img_buffer,
+            subplot,
+            (subplot_x, subplot_y, subplot_width, subplot_height),
+            &style,
+            first_number + subplot_idx,
+        )?;
+    }
+
+    Ok(img_buffer)
+}
+
+// Where the subplots of an exported figure go, in pixels before scaling
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct FigureLayout {
+    pub size: (u32, u32),
+    pub cells: Vec<(u32, u32, u32, u32)>, // Left, top, width and height of each subplot, title included
+}
+
+/// Implementation block defining methods for this type
+impl FigureLayout {
+    // The same layout with every size multiplied by the export scale factor
+/// Function: explain its purpose and key arguments
+    pub fn scaled(&self, scale: f32) -> Self {
+        FigureLayout {
+            size: (scale_px(self.size.0, scale).max(1), scale_px(self.size.1, scale).max(1)),
+            cells: self
+                .cells
+                .iter()
+                .map(|&(left, top, width, height)| {
+                    (scale_px(left, scale), scale_px(top, scale), scale_px(width, scale), scale_px(height, scale))
+                })
+                .collect(),
+        }
+    }
+}
+
+// Split the figure between the rows and columns of `layout` by their weights; very small
+// figures grow to keep every row and column readable
+/// Function: explain its purpose and key arguments
+pub fn export_figure_layout(layout: &SubplotLayout, settings: &ExportSettings) -> FigureLayout {
+// Variable declaration
+    let (rows, cols) = layout.dimensions();
+// Variable declaration
+    let (rows_u32, cols_u32) = (rows as u32, cols as u32);
+// Variable declaration
+    let spacing = 40u32;
+// Variable declaration
+    let title_space = 60u32; // Extra space for titles
+// Variable declaration
+    let (min_width, min_height) = layout.min_area((300.0, 190.0));
+// Variable declaration
+    let available_width = (settings.width.saturating_sub((cols_u32 + 1) * spacing) as f64).max(min_width.ceil());
+// Variable declaration
+    let available_height =
+        (settings.height.saturating_sub((rows_u32 + 1) * spacing + title_space) as f64).max(min_height.ceil());
+    // Whole pixels, so cells line up with the pixel grid
+// Variable declaration
+    let col_sizes: Vec<f64> = layout.col_sizes(available_width).into_iter().map(f64::floor).collect();
+// Variable declaration
+    let row_sizes: Vec<f64> = layout.row_sizes(available_height).into_iter().map(f64::floor).collect();
+    FigureLayout {
+        size: (
+            settings.width.max(col_sizes.iter().sum::<f64>() as u32 + (cols_u32 + 1) * spacing),
+            settings.height.max(row_sizes.iter().sum::<f64>() as u32 + (rows_u32 + 1) * spacing + title_space),
+        ),
+        cells: layout
+            .cell_rects(&row_sizes, &col_sizes, spacing as f64)
+            .into_iter()
+            .map(|[left, top, width, height]| {
+                (spacing + left as u32, spacing + top as u32, width as u32, height as u32)
+            })
+            .collect(),
+    }
+}
+
+// Render one subplot on its own, filling the whole figure size from `settings`;
+// `subplot_number` is only used for its title
+/// Function: explain its purpose and key arguments
+pub fn render_single_subplot_image(
+    subplot: &Subplot,
+    subplot_number: usize,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<image::RgbImage, Box<dyn std::error::Error>> {
+    render_numbered_subplots(
+        std::slice::from_ref(subplot),
+        &SubplotLayout::default(),
+        subplot_number,
+        dark_mode,
+        font_size,
+        settings,
+    )
+}
+
+// Ask for a file name and save a single subplot as PNG, PDF or a matplotlib script
+/// Function: explain its purpose and key arguments
+pub fn export_single_subplot_as_figure(
+    subplot: &Subplot,
+    subplot_number: usize,
+    dark_mode: bool,
+    font_size: &FontSize,
+    settings: &ExportSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = pick_figure_save_path(&format!("subplot_{}", subplot_number), settings.format) {
+        save_numbered_subplots(
+            &path,
+            std::slice::from_ref(subplot),
+            &SubplotLayout::default(),
+            subplot_number,
+            dark_mode,
+            font_size,
+            settings,
+        )?;
+        println!("Subplot exported as: {}", path.display());
+    }
+    Ok(())
+}
+
+// Put a rendered image on the system clipboard so it can be pasted into other applications
+/// Function: explain its purpose and key arguments
+pub fn copy_image_to_clipboard(img: &image::RgbImage) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let rgba = image::DynamicImage::ImageRgb8(img.clone()).to_rgba8();
+// Variable declaration
+    let mut clipboard = arboard::Clipboard::new()?;
+    clipboard.set_image(arboard::ImageData {
+        width: rgba.width() as usize,
+        height: rgba.height() as usize,
+        bytes: std::borrow::Cow::Owned(rgba.into_raw()),
+    })?;
+    Ok(())
+}
+
+// Series as tab-separated text, two columns ("<name> x", "<name> y") per series side
+// by side; shorter series leave their cells empty. Spreadsheets paste this as a table.
+/// Function: explain its purpose and key arguments
+pub fn series_to_tsv(series: &[(&str, Vec<[f64; 2]>)]) -> String {
+// Variable declaration
+    let mut text = series
+        .iter()
+        .map(|(name, _)| format!("{} x\t{} y", name, name))
+        .collect::<Vec<_>>()
+        .join("\t");
+    text.push('\n');
+// Variable declaration
+    let rows = series.iter().map(|(_, points)| points.len()).max().unwrap_or(0);
+    for row in 0..rows {
+// Variable declaration
+        let cells: Vec<String> = series
+            .iter()
+            .map(|(_, points)| match points.get(row) {
+                Some([x, y]) => format!("{}\t{}", x, y),
+                None => "\t".to_string(),
+            })
+            .collect();
+        text.push_str(&cells.join("\t"));
+        text.push('\n');
+    }
+    text
+}
+
+// Put text on the system clipboard
+/// Function: explain its purpose and key arguments
+pub fn copy_text_to_clipboard(text: String) -> Result<(), Box<dyn std::error::Error>> {
+    arboard::Clipboard::new()?.set_text(text)?;
+    Ok(())
+}
+
+// Write an RGB image as PNG with a pHYs chunk so viewers and journals see the intended DPI
+/// Function: explain its purpose and key arguments
+pub fn save_png_with_dpi(img: &image::RgbImage, path: &Path, dpi: u32) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let writer = std::io::BufWriter::new(File::create(path)?);
+// Variable declaration
+    let mut encoder = png::Encoder::new(writer, img.width(), img.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    // PNG stores pixels per metre
+// Variable declaration
+    let pixels_per_meter = (dpi as f64 / 0.0254).round() as u32;
+    encoder.set_pixel_dims(Some(png::PixelDimensions {
+        xppu: pixels_per_meter,
+        yppu: pixels_per_meter,
+        unit: png::Unit::Meter,
+    }));
+    encoder.write_header()?.write_image_data(img.as_raw())?;
+    Ok(())
+}
+
+// Background, grid, axis and text colors of exported images
+/// Function: explain its purpose and key arguments
+pub fn export_colors(dark_mode: bool) -> (image::Rgb<u8>, image::Rgb<u8>, image::Rgb<u8>, image::Rgb<u8>) {
+    if dark_mode {
+        (
+            image::Rgb([27, 27, 27]),
+            image::Rgb([60, 60, 60]),
+            image::Rgb([180, 180, 180]),
+            image::Rgb([255, 255, 255]),
+        )
+    } else {
+        (
+            image::Rgb([248, 248, 248]),
+            image::Rgb([200, 200, 200]),
+            image::Rgb([100, 100, 100]),
+            image::Rgb([0, 0, 0]),
+        )
+    }
+}
+
+// A size of `pixels` at scale 1 in a figure exported at `scale`
+/// Function: explain its purpose and key arguments
+pub fn scale_px(pixels: u32, scale: f32) -> u32 {
+    (pixels as f32 * scale).round() as u32
+}
+
+// Colors, text size and scale factor of an exported image. Every size in pixels (gaps, tick
+// lengths, line widths) is multiplied by `scale`, so scaled figures keep sharp text and lines.
+#[derive(Debug, Clone, Copy)]
+/// Data structure used in utils.rs module
+struct ExportStyle {
+    background: image::Rgb<u8>,
+    grid: image::Rgb<u8>,
+    axis: image::Rgb<u8>,
+    text: image::Rgb<u8>,
+    font_scale: f32, // Of tick labels, the scale factor included
+    scale: f32,
+}
+
+/// Implementation block defining methods for this type
+impl ExportStyle {
+/// Function: explain its purpose and key arguments
+    fn new(dark_mode: bool, font_size: &FontSize, scale: f32) -> Self {
+// Variable declaration
+        let (background, grid, axis, text) = export_colors(dark_mode);
+        Self { background, grid, axis, text, font_scale: font_size.to_scale() * scale, scale }
+    }
+
+    // A size of `pixels` at scale 1
+/// Function: explain its purpose and key arguments
+    fn px(&self, pixels: u32) -> u32 {
+        scale_px(pixels, self.scale)
+    }
+
+    // How many pixels `pixels` would be at scale 1; grid marks are picked from these so a
+    // scaled figure gets the same ticks
+/// Function: explain its purpose and key arguments
+    fn unscaled(&self, pixels: u32) -> u32 {
+        (pixels as f32 / self.scale).round() as u32
+    }
+
+    // Thickness of a line `width` pixels wide at scale 1, at least one pixel
+/// Function: explain its purpose and key arguments
+    fn line_width(&self, width: f32) -> u32 {
+        (width * self.scale).round().max(1.0) as u32
+    }
+}
+
+// Bounds (in plot coordinates) of an exported subplot: those shown on screen when the subplot
+// has been drawn, otherwise fitted to the data the way the interactive plot fits them.
+// `datasets` are the subplot's visible datasets after stacking and secondary-axis placement.
+/// Function: explain its purpose and key arguments
+pub fn export_plot_bounds(subplot: &Subplot, datasets: &[Cow<'_, Dataset>]) -> Result<(f64, f64, f64, f64), Box<dyn std::error::Error>> {
+// Variable declaration
+    let (x_scale, y_scale) = (subplot.config.x_axis(), subplot.config.y_axis());
+    Ok(if let Some(bounds) = subplot.view_bounds {
+        bounds
+    } else if subplot.config.use_custom_bounds {
+// Variable declaration
+        let config = AxisConfig {
+            x_min: subplot.config.custom_x_min.parse().ok(),
+            x_max: subplot.config.custom_x_max.parse().ok(),
+            y_min: subplot.config.custom_y_min.parse().ok(),
+            y_max: subplot.config.custom_y_max.parse().ok(),
+            x_padding_percent: subplot.config.x_padding_percent / 100.0,
+            y_padding_percent: subplot.config.y_padding_percent / 100.0,
+            custom_x_ticks: if subplot.config.use_custom_x_ticks {
+                Some(parse_custom_ticks(&subplot.config.custom_x_ticks))
+            } else {
+                None
+            },
+            custom_y_ticks: if subplot.config.use_custom_y_ticks {
+                Some(parse_custom_ticks(&subplot.config.custom_y_ticks))
+            } else {
+                None
+            },
+            x_scale,
+            y_scale,
+        };
+        calculate_custom_bounds(datasets, &subplot.heatmaps, &config)?
+    } else {
+        calculate_auto_bounds(datasets, &subplot.heatmaps, x_scale, y_scale)
+    })
+}
+
+// Space around the plot area of an exported subplot, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct PlotMargins {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+    pub secondary_space: u32, // Part of `right` taken by the secondary Y axis
+    pub legend_space: u32,    // Part of `right` taken by a legend outside the plot
+}
+
+// Where a colorbar of an exported subplot goes: its top-left corner and its height, in
+// pixels for images and points for PDF pages
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct ColorbarRect<T> {
+    pub left: T,
+    pub top: T,
+    pub height: T,
+}
+
+// Colormap and value range of each colorbar of an exported subplot: one per heatmap and
+// per value-colored dataset
+/// Function: explain its purpose and key arguments
+pub fn export_colorbars(subplot: &Subplot, visible: &[Cow<'_, Dataset>]) -> Vec<(Colormap, (f64, f64))> {
+    subplot
+        .heatmaps
+        .iter()
+        .filter_map(|heatmap| Some((heatmap.colormap, heatmap.value_range()?)))
+        .chain(visible.iter().filter_map(|ds| Some((ds.colormap, ds.color_range()?))))
+        .collect()
+}
+
+// Margins of an exported subplot `width` pixels wide whose plot (below the title) is
+// `plot_height` pixels high. Axis titles, the secondary axis, colorbars and a legend
+// outside the plot each get room of their own. `font_scale` is the text scale of the tick
+// labels and `scale` the export scale factor (1 for PDF, which scales the page instead).
+/// Function: explain its purpose and key arguments
+pub fn export_plot_margins(
+    subplot: &Subplot,
+    visible: &[Cow<'_, Dataset>],
+    has_secondary: bool,
+    colorbar_count: usize,
+    (width, plot_height): (u32, u32),
+    (font_scale, scale): (f32, f32),
+) -> PlotMargins {
+// Variable declaration
+    let px = |pixels: u32| scale_px(pixels, scale);
+    // Reserve extra room for axis titles when they are set
+// Variable declaration
+    let label_space = measure_text("Xg", font_scale).1 + px(8);
+// Variable declaration
+    let left = if subplot.config.y_label.is_empty() { px(60) } else { px(60) + label_space };
+    // The right axis needs room for its tick labels and title
+// Variable declaration
+    let secondary_space = match has_secondary {
+        false => 0u32,
+        true if subplot.config.y2_label.is_empty() => px(SECONDARY_AXIS_SPACING),
+        true => px(SECONDARY_AXIS_SPACING) + label_space,
+    };
+// Variable declaration
+    let top = px(20);
+// Variable declaration
+    let bottom = if subplot.config.x_label.is_empty() { px(40) } else { px(40) + label_space };
+    // A legend outside the plot gets a strip of its own at the far right
+// Variable declaration
+    let legend_space = if subplot.config.show_legend && subplot.config.legend_position == LegendPosition::OutsideRight {
+        // At most a third of the subplot wide, wrapping into columns over the plot's height
+// Variable declaration
+        let max_size = (width / 3, (plot_height - top - bottom).saturating_sub(px(20)));
+        export_legend_layout(visible, &subplot.config, max_size, scale).size.0 + px(20)
+    } else {
+        0
+    };
+    PlotMargins {
+        left,
+        right: px(20) + secondary_space + px(COLORBAR_SPACING) * colorbar_count as u32 + legend_space,
+        top,
+        bottom,
+        secondary_space,
+        legend_space,
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn render_subplot_to_image(
+    img: &mut image::RgbImage,
+    subplot: &Subplot,
+    (x_offset, y_offset, width, height): (u32, u32, u32, u32),
+    style: &ExportStyle,
+    subplot_number: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let ExportStyle { grid: grid_color, axis: axis_color, text: text_color, .. } = *style;
+// Variable declaration
+    let title_space = style.px(30);
+    // Hidden datasets are left out of the export
+// Variable declaration
+    let visible = visible_datasets(&subplot.datasets);
+    if visible.is_empty() && subplot.heatmaps.is_empty() {
+        // Draw empty subplot with title
+        draw_subplot_title(img, x_offset, y_offset, width, &subplot.config.title, subplot_number, style);
+        draw_empty_subplot_frame(img, (x_offset, y_offset + title_space), (width, height - title_space), style);
+        return Ok(());
+    }
+
+// Variable declaration
+    let x_scale = subplot.config.x_axis();
+// Variable declaration
+    let y_scale = subplot.config.y_axis();
+
+    // Stacked datasets are drawn on top of each other, and datasets on the right axis are
+    // moved into the coordinates of the left one
+// Variable declaration
+    let mut datasets = visible.clone();
+    stacked_datasets(&mut datasets);
+// Variable declaration
+    let secondary = secondary_axis(&datasets, &subplot.config);
+    place_secondary_datasets(&mut datasets, secondary, y_scale);
+
+    // Calculate bounds (in plot coordinates)
+// Variable declaration
+    let (min_x, max_x, min_y, max_y) = export_plot_bounds(subplot, &datasets)?;
+
+    // Draw subplot title
+    draw_subplot_title(img, x_offset, y_offset, width, &subplot.config.title, subplot_number, style);
+
+// Variable declaration
+    let plot_y_offset = y_offset + title_space;
+// Variable declaration
+    let plot_height = height - title_space;
+
+// Variable declaration
+    let label_scale = style.font_scale;
+    // Heatmaps and value-colored datasets get a colorbar each to the right of the plot
+// Variable declaration
+    let colorbars = export_colorbars(subplot, &visible);
+// Variable declaration
+    let PlotMargins {
+        left: margin_left,
+        right: margin_right,
+        top: margin_top,
+        bottom: margin_bottom,
+        secondary_space,
+        legend_space,
+    } = export_plot_margins(
+        subplot,
+        &visible,
+        secondary.is_some(),
+        colorbars.len(),
+        (width, plot_height),
+        (style.font_scale, style.scale),
+    );
+// Variable declaration
+    let plot_width = width - margin_left - margin_right;
+// Variable declaration
+    let effective_plot_height = plot_height - margin_top - margin_bottom;
+// Variable declaration
+    let (min_x, max_x, min_y, max_y) = if subplot.config.equal_aspect {
+        with_equal_aspect((min_x, max_x, min_y, max_y), (plot_width, effective_plot_height))
+    } else {
+        (min_x, max_x, min_y, max_y)
+    };
+
+    // Grid marks in plot coordinates (log10 of the value on log axes), as on screen
+// Variable declaration
+    let x_marks = axis_grid_marks((min_x, max_x), x_scale, style.unscaled(plot_width), &subplot.config, X_LABEL_SPACING_PX);
+// Variable declaration
+    let y_marks =
+        axis_grid_marks((min_y, max_y), y_scale, style.unscaled(effective_plot_height), &subplot.config, Y_LABEL_SPACING_PX);
+// Variable declaration
+    let line_width = style.line_width(1.0);
+
+    // Draw grid if requested: a line at every grid mark, fading out as the lines get closer
+    // together like the interactive grid does
+    if subplot.config.show_grid {
+// Variable declaration
+        let min_spacing = subplot.config.grid_spacing as f64 * style.scale as f64;
+// Variable declaration
+        let opacity = |spacing: f64| {
+// Variable declaration
+            let full_strength = GRID_FULL_STRENGTH_PX * style.scale as f64;
+// Variable declaration
+            let strength = ((spacing - min_spacing) / (full_strength - min_spacing).max(1.0)).clamp(0.0, 1.0);
+            (strength as f32).sqrt() * subplot.config.grid_opacity
+        };
+// Variable declaration
+        let plot_top = plot_y_offset + margin_top;
+        for mark in &x_marks {
+// Variable declaration
+            let spacing = mark_spacing_px(mark, (min_x, max_x), plot_width);
+            if spacing <= min_spacing {
+                continue;
+            }
+// Variable declaration
+            let x = x_offset + margin_left + ((mark.value - min_x) / (max_x - min_x) * plot_width as f64) as u32;
+            draw_grid_line(
+                img,
+                (x, plot_top),
+                effective_plot_height,
+                false,
+                (grid_color, opacity(spacing)),
+                subplot.config.grid_style,
+                line_width,
+            );
+        }
+        for mark in &y_marks {
+// Variable declaration
+            let spacing = mark_spacing_px(mark, (min_y, max_y), effective_plot_height);
+            if spacing <= min_spacing {
+                continue;
+            }
+// Variable declaration
+            let y = plot_y_offset + plot_height - margin_bottom
+                - ((mark.value - min_y) / (max_y - min_y) * effective_plot_height as f64) as u32;
+            draw_grid_line(
+                img,
+                (x_offset + margin_left, y),
+                plot_width,
+                true,
+                (grid_color, opacity(spacing)),
+                subplot.config.grid_style,
+                line_width,
+            );
+        }
+    }
+
+    // Draw axes
+// Variable declaration
+    let x_axis_y = plot_y_offset + plot_height - margin_bottom;
+// Variable declaration
+    let y_axis_x = x_offset + margin_left;
+    // Thicker axes grow outwards, away from the plot
+    fill_rect(img, (y_axis_x, x_axis_y), (plot_width, line_width), axis_color);
+    fill_rect(
+        img,
+        ((y_axis_x + 1).saturating_sub(line_width), plot_y_offset + margin_top),
+        (line_width, effective_plot_height),
+        axis_color,
+    );
+    if let Some(axis) = secondary {
+        draw_secondary_axis(
+            img,
+            axis,
+            (min_y, max_y),
+            (x_offset + width - margin_right, plot_y_offset + margin_top, x_axis_y),
+            &subplot.config,
+            style,
+        );
+    }
+
+    // Draw axis labels
+    draw_subplot_axis_labels(
+        img,
+        (min_x, max_x, x_scale, &x_marks),
+        (min_y, max_y, y_scale, &y_marks),
+        x_offset + margin_left,
+        margin_bottom,
+        plot_width,
+        effective_plot_height,
+        x_offset + width,
+        plot_y_offset + plot_height,
+        style,
+    );
+
+    // Draw axis titles
+    if !subplot.config.x_label.is_empty() {
+// Variable declaration
+        let (text_width, _) = measure_text(&subplot.config.x_label, label_scale);
+// Variable declaration
+        let label_x = (x_offset + margin_left + plot_width / 2).saturating_sub(text_width / 2);
+// Variable declaration
+        let label_y = x_axis_y + style.px(32); // Below the tick labels
+        draw_text_scaled(img, label_x, label_y, &subplot.config.x_label, text_color, label_scale);
+    }
+    if !subplot.config.y_label.is_empty() {
+// Variable declaration
+        let (text_width, _) = measure_text(&subplot.config.y_label, label_scale);
+// Variable declaration
+        let label_y = (plot_y_offset + margin_top + effective_plot_height / 2).saturating_sub(text_width / 2);
+        draw_text_rotated(img, x_offset + style.px(4), label_y, &subplot.config.y_label, text_color, label_scale);
+    }
+
+    // Draw heatmaps underneath the datasets, clipped to the inside of the axes
+    if !x_scale.is_log() && !y_scale.is_log() {
+// Variable declaration
+        let (left, right) = (y_axis_x as i64 + 1, (x_offset + width - margin_right) as i64);
+// Variable declaration
+        let (top, bottom) = ((plot_y_offset + margin_top) as i64, x_axis_y as i64);
+// Variable declaration
+        let to_px_x = |x: f64| (x_offset + margin_left) as f64 + (x - min_x) / (max_x - min_x) * plot_width as f64;
+// Variable declaration
+        let to_px_y = |y: f64| x_axis_y as f64 - (y - min_y) / (max_y - min_y) * effective_plot_height as f64;
+        for heatmap in &subplot.heatmaps {
+// Variable declaration
+            let Some(range) = heatmap.value_range() else {
+                continue;
+            };
+            // Cell edges in pixels; neighbouring cells share an edge so they tile without gaps.
+            // Edges run the other way on reversed axes.
+// Variable declaration
+            let x_edges: Vec<i64> = heatmap.x_edges().iter().filter_map(|&x| x_scale.apply(x)).map(|x| to_px_x(x).round() as i64).collect();
+// Variable declaration
+            let y_edges: Vec<i64> = heatmap.y_edges().iter().filter_map(|&y| y_scale.apply(y)).map(|y| to_px_y(y).round() as i64).collect();
+            for row in 0..heatmap.y.len() {
+// Variable declaration
+                let (y0, y1) = (y_edges[row].min(y_edges[row + 1]).max(top), y_edges[row].max(y_edges[row + 1]).min(bottom));
+                for col in 0..heatmap.x.len() {
+// Variable declaration
+                    let Some(color) = heatmap.cell_color(row, col, range) else {
+                        continue;
+                    };
+// Variable declaration
+                    let (x0, x1) = (x_edges[col].min(x_edges[col + 1]).max(left), x_edges[col].max(x_edges[col + 1]).min(right));
+                    for py in y0..y1 {
+                        for px in x0..x1 {
+                            img.put_pixel(px as u32, py as u32, image::Rgb(color));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Shaded bands and filled areas go underneath all the lines
+// Variable declaration
+    let areas = datasets.iter().enumerate().flat_map(|(index, dataset)| {
+// Variable declaration
+        let band = dataset.band_edges().map(|(upper, lower)| (upper, lower, dataset.color));
+// Variable declaration
+        let fill = fill_baseline(&datasets, index).map(|baseline| (dataset.points.to_vec(), baseline, dataset.color));
+        band.into_iter().chain(fill)
+    });
+    for (points, baseline, color) in areas {
+// Variable declaration
+        let to_px = |[x, y]: [f64; 2]| -> [f64; 2] {
+            [
+                (x_offset + margin_left) as f64 + (x - min_x) / (max_x - min_x) * plot_width as f64,
+                x_axis_y as f64 - (y - min_y) / (max_y - min_y) * effective_plot_height as f64,
+            ]
+        };
+        // Upper and lower edge of the area at each point, in pixels; a zero baseline on a
+        // log axis is the bottom of the plot
+// Variable declaration
+        let edges: Vec<Option<([f64; 2], f64)>> = points
+            .iter()
+            .zip(&baseline)
+            .map(|(point, low)| {
+// Variable declaration
+                let upper = to_px(scale_point(point, x_scale, y_scale)?);
+// Variable declaration
+                let lower = to_px([0.0, y_scale.apply((*low)?).unwrap_or(min_y)])[1];
+                Some((upper, lower))
+            })
+            .collect();
+        fill_between(
+            img,
+            &edges,
+            color,
+            (x_offset + margin_left, x_offset + width - margin_right),
+            (plot_y_offset + margin_top, x_axis_y),
+        );
+    }
+
+    // Draw datasets
+    for (dataset, position) in datasets.iter().zip(distribution_positions(&datasets)) {
+        if let Some(position) = position {
+// Variable declaration
+            let to_px = |x: f64, y: f64| {
+                (
+                    (x_offset + margin_left) as f64 + (x - min_x) / (max_x - min_x) * plot_width as f64,
+                    x_axis_y as f64 - (y - min_y) / (max_y - min_y) * effective_plot_height as f64,
+                )
+            };
+// Variable declaration
+            let half_width = DISTRIBUTION_WIDTH / 2.0 / (max_x - min_x) * plot_width as f64;
+            draw_distribution_to_image(img, dataset, position, (x_scale, y_scale), &to_px, half_width, style.scale);
+            continue;
+        }
+// Variable declaration
+        let rgb_color = image::Rgb(dataset.color);
+        
+        if dataset.has_error_bars() {
+// Variable declaration
+            let bar_size = (style.px(3) as i64, line_width as i64);
+// Variable declaration
+            let to_px = |x: f64, y: f64| -> (i64, i64) {
+// Variable declaration
+                let px = x_offset as f64 + margin_left as f64 + (x - min_x) / (max_x - min_x) * plot_width as f64;
+// Variable declaration
+                let py = (plot_y_offset + plot_height - margin_bottom) as f64
+                    - (y - min_y) / (max_y - min_y) * effective_plot_height as f64;
+                (px as i64, py as i64)
+            };
+            for (i, point) in dataset.points.iter().enumerate() {
+// Variable declaration
+                let Some([sx, sy]) = scale_point(&point, x_scale, y_scale) else {
+                    continue;
+                };
+                // On log axes a lower end at or below zero is clamped to the plot edge
+                if let Some(&dy) = dataset.y_err.as_ref().and_then(|e| e.get(i)) {
+// Variable declaration
+                    let (x, y_low) = to_px(sx, y_scale.apply(point[1] - dy).unwrap_or(min_y));
+// Variable declaration
+                    let (_, y_high) = to_px(sx, y_scale.apply(point[1] + dy).unwrap_or(sy));
+                    draw_error_bar(img, (x, y_low), (x, y_high), rgb_color, bar_size);
+                }
+                if let Some(&dx) = dataset.x_err.as_ref().and_then(|e| e.get(i)) {
+// Variable declaration
+                    let (x_low, y) = to_px(x_scale.apply(point[0] - dx).unwrap_or(min_x), sy);
+// Variable declaration
+                    let (x_high, _) = to_px(x_scale.apply(point[0] + dx).unwrap_or(sx), sy);
+                    draw_error_bar(img, (x_low, y), (x_high, y), rgb_color, bar_size);
+                }
+            }
+        }
+
+        if dataset.plot_type.draws_bars() {
+            // Bars rise from zero, or from the bottom edge on a log Y axis
+// Variable declaration
+            let base = if y_scale.is_log() { min_y } else { 0.0f64.clamp(min_y, max_y) };
+// Variable declaration
+            let half_width = bar_width(&dataset.points) / 2.0;
+// Variable declaration
+            let to_px = |x: f64, y: f64| -> (i64, i64) {
+// Variable declaration
+                let px = x_offset as f64 + margin_left as f64 + (x.clamp(min_x, max_x) - min_x) / (max_x - min_x) * plot_width as f64;
+// Variable declaration
+                let py = (plot_y_offset + plot_height - margin_bottom) as f64
+                    - (y.clamp(min_y, max_y) - min_y) / (max_y - min_y) * effective_plot_height as f64;
+                (px as i64, py as i64)
+            };
+            for point in &dataset.points {
+// Variable declaration
+                let (Some([_, top]), Some(left), Some(right)) = (
+                    scale_point(&point, x_scale, y_scale),
+                    x_scale.apply(point[0] - half_width),
+                    x_scale.apply(point[0] + half_width),
+                ) else {
+                    continue;
+                };
+                draw_bar(img, to_px(left, top), to_px(right, base), rgb_color, line_width as i64);
+            }
+        }
+
+        // Color of point `i`: along the colormap for value-colored datasets
+// Variable declaration
+        let value_range = dataset.color_range();
+// Variable declaration
+        let point_color = |i: usize| value_range.map_or(rgb_color, |range| image::Rgb(dataset.point_color(i, range)));
+
+        if dataset.plot_type.draws_markers() {
+            for (i, point) in dataset.points.iter().enumerate() {
+// Variable declaration
+                let Some(point) = scale_point(&point, x_scale, y_scale).filter(|[x, y]| {
+                    (min_x..=max_x).contains(x) && (min_y..=max_y).contains(y)
+                }) else {
+                    continue;
+                };
+// Variable declaration
+                let px = x_offset as f64 + margin_left as f64 + (point[0] - min_x) / (max_x - min_x) * plot_width as f64;
+// Variable declaration
+                let py = (plot_y_offset + plot_height - margin_bottom) as f64
+                    - (point[1] - min_y) / (max_y - min_y) * effective_plot_height as f64;
+                if px >= 0.0 && py >= 0.0 {
+                    draw_marker(img, (px as u32, py as u32), dataset.marker, dataset.marker_size * style.scale, point_color(i));
+                }
+            }
+        }
+
+        if !dataset.plot_type.draws_line() {
+            continue;
+        }
+
+// Variable declaration
+        let thickness = style.line_width(dataset.line_width);
+// Variable declaration
+        let dash = dataset.line_style.dash_lengths(thickness);
+// Variable declaration
+        let mut dash_phase = 0.0;
+        for (i, window) in dataset.points.to_vec().windows(2).enumerate() {
+            // Segments touching a point a log axis cannot show, or one missing a value, are
+            // left out, and the rest are cut to the plot area (a zoomed view leaves much of
+            // the data outside it)
+// Variable declaration
+            let (Some(p1), Some(p2)) = (
+                scale_point(&window[0], x_scale, y_scale),
+                scale_point(&window[1], x_scale, y_scale),
+            ) else {
+                continue;
+            };
+// Variable declaration
+            let Some((p1, p2)) = clip_segment(p1, p2, (min_x, max_x, min_y, max_y)) else {
+                continue;
+            };
+// Variable declaration
+            let x1 = x_offset + margin_left + ((p1[0] - min_x) / (max_x - min_x) * plot_width as f64) as u32;
+// Variable declaration
+            let y1 = plot_y_offset + plot_height - margin_bottom
+                - ((p1[1] - min_y) / (max_y - min_y) * effective_plot_height as f64) as u32;
+// Variable declaration
+            let x2 = x_offset + margin_left + ((p2[0] - min_x) / (max_x - min_x) * plot_width as f64) as u32;
+// Variable declaration
+            let y2 = plot_y_offset + plot_height - margin_bottom
+                - ((p2[1] - min_y) / (max_y - min_y) * effective_plot_height as f64) as u32;
+            match dash {
+                Some(pattern) => draw_dashed_line(img, (x1, y1), (x2, y2), point_color(i), thickness, pattern, &mut dash_phase),
+                None => draw_thick_line(img, x1, y1, x2, y2, point_color(i), thickness),
+            }
+        }
+    }
+
+    // Draw annotations (skipping any that fall outside the plot area)
+// Variable declaration
+    let plot_left = (x_offset + margin_left) as f64;
+// Variable declaration
+    let plot_bottom = (plot_y_offset + plot_height - margin_bottom) as f64;
+// Variable declaration
+    let annotation_px = |point: &[f64; 2]| -> Option<(u32, u32)> {
+// Variable declaration
+        let [x, y] = scale_point(point, x_scale, y_scale)?;
+        if x < min_x || x > max_x || y < min_y || y > max_y {
+            return None;
+        }
+        Some((
+            (plot_left + (x - min_x) / (max_x - min_x) * plot_width as f64) as u32,
+            (plot_bottom - (y - min_y) / (max_y - min_y) * effective_plot_height as f64) as u32,
+        ))
+    };
+// Variable declaration
+    let plot_rows = ((plot_bottom - effective_plot_height as f64) as u32, plot_bottom as u32);
+    for annotation in &subplot.annotations {
+        draw_annotation(img, annotation, &annotation_px, plot_rows, style);
+    }
+
+    for (k, &colorbar) in colorbars.iter().enumerate() {
+// Variable declaration
+        let rect = ColorbarRect {
+            left: x_offset + width - margin_right + secondary_space + style.px(10) + style.px(COLORBAR_SPACING) * k as u32,
+            top: plot_y_offset + margin_top + style.px(5),
+            height: (effective_plot_height * 3 / 5).max(style.px(20)),
+        };
+        draw_colorbar(img, rect, colorbar, style);
+    }
+
+    if let Some(inset) = &subplot.config.inset {
+        draw_export_inset(
+            img,
+            subplot,
+            inset,
+            (x_offset + margin_left, plot_y_offset + margin_top, plot_width, effective_plot_height),
+            (min_x, max_x, min_y, max_y),
+            style,
+        )?;
+    }
+
+    // Draw legend if requested
+    if subplot.config.show_legend && !visible.is_empty() {
+// Variable declaration
+        let area = if legend_space > 0 {
+            (x_offset + width - legend_space, plot_y_offset + margin_top, x_offset + width, plot_bottom as u32)
+        } else {
+            (x_offset + margin_left, plot_y_offset + margin_top, x_offset + width - margin_right, plot_bottom as u32)
+        };
+        draw_subplot_legend(img, &visible, &subplot.config, area, style);
+    }
+
+    Ok(())
+}
+
+// The part of the segment from `p1` to `p2` inside `bounds` (min x, max x, min y, max y),
+// or None when it misses them; segments already inside come back unchanged
+/// Function: explain its purpose and key arguments
+pub fn clip_segment(p1: [f64; 2], p2: [f64; 2], (min_x, max_x, min_y, max_y): (f64, f64, f64, f64)) -> Option<([f64; 2], [f64; 2])> {
+// Variable declaration
+    let inside = |[x, y]: [f64; 2]| (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y);
+    if inside(p1) && inside(p2) {
+        return Some((p1, p2));
+    }
+    // Liang-Barsky: narrow the segment's parameter range edge by edge
+// Variable declaration
+    let (dx, dy) = (p2[0] - p1[0], p2[1] - p1[1]);
+// Variable declaration
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [(-dx, p1[0] - min_x), (dx, max_x - p1[0]), (-dy, p1[1] - min_y), (dy, max_y - p1[1])] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+// Variable declaration
+        let t = q / p;
+        if p < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+        if t0 > t1 {
+            return None;
+        }
+    }
+    Some(([p1[0] + t0 * dx, p1[1] + t0 * dy], [p1[0] + t1 * dx, p1[1] + t1 * dy]))
+}
+
+// Zoom inset of an exported subplot whose plot area is (left, top, width, height) pixels and
+// shows `bounds` (min x, max x, min y, max y in plot coordinates): the zoomed region is
+// outlined on the plot and the inset is rendered as a subplot of its own, without its title
+// strip, into its place over the plot. Insets too small for their tick labels are left out.
+/// Function: explain its purpose and key arguments
+fn draw_export_inset(
+    img: &mut image::RgbImage,
+    subplot: &Subplot,
+    inset: &Inset,
+    (left, top, width, height): (u32, u32, u32, u32),
+    (min_x, max_x, min_y, max_y): (f64, f64, f64, f64),
+    style: &ExportStyle,
+) -> Result<(), Box<dyn std::error::Error>> {
+// Variable declaration
+    let Some(zoomed) = inset.subplot(subplot) else {
+        return Ok(());
+    };
+// Variable declaration
+    let (x0, x1, y0, y1) = zoomed.view_bounds.unwrap_or((min_x, max_x, min_y, max_y));
+
+    // Outline of the zoomed region, cut to the plot area
+// Variable declaration
+    let px = |x: f64| (left as f64 + (x - min_x) / (max_x - min_x) * width as f64).clamp(left as f64, (left + width) as f64) as u32;
+// Variable declaration
+    let py = |y: f64| (top as f64 + (max_y - y) / (max_y - min_y) * height as f64).clamp(top as f64, (top + height) as f64) as u32;
+// Variable declaration
+    let (rx0, rx1, ry0, ry1) = (px(x0), px(x1), py(y1), py(y0));
+// Variable declaration
+    let (axis_color, line_width) = (style.axis, style.line_width(1.0));
+    draw_thick_line(img, rx0, ry0, rx1, ry0, axis_color, line_width);
+    draw_thick_line(img, rx1, ry0, rx1, ry1, axis_color, line_width);
+    draw_thick_line(img, rx1, ry1, rx0, ry1, axis_color, line_width);
+    draw_thick_line(img, rx0, ry1, rx0, ry0, axis_color, line_width);
+
+// Variable declaration
+    let (inset_left, inset_top, inset_width, inset_height) =
+        inset.rect_in((left as f64, top as f64, width as f64, height as f64));
+// Variable declaration
+    let (inset_left, inset_top, inset_width, inset_height) =
+        (inset_left as u32, inset_top as u32, inset_width as u32, inset_height as u32);
+
+    // Render the inset with room to spare for its title strip and for everything right of its
+    // plot (colorbars, the secondary axis), then keep only the part inside the inset
+// Variable declaration
+    let visible = visible_datasets(&zoomed.datasets);
+// Variable declaration
+    let mut stacked = visible.clone();
+    stacked_datasets(&mut stacked);
+// Variable declaration
+    let has_secondary = secondary_axis(&stacked, &zoomed.config).is_some();
+// Variable declaration
+    let margins = export_plot_margins(
+        &zoomed,
+        &visible,
+        has_secondary,
+        export_colorbars(&zoomed, &visible).len(),
+        (inset_width, inset_height),
+        (style.font_scale, style.scale),
+    );
+    // The plot's right edge lands on the inset's right edge
+// Variable declaration
+    let extra = margins.right;
+    if inset_width < margins.left + style.px(40) || inset_height < margins.top + margins.bottom + style.px(20) {
+        return Ok(());
+    }
+// Variable declaration
+    let title_space = style.px(30);
+// Variable declaration
+    let mut rendered = image::RgbImage::from_pixel(inset_width + extra, inset_height + title_space, style.background);
+    render_subplot_to_image(&mut rendered, &zoomed, (0, 0, inset_width + extra, inset_height + title_space), style, 0)?;
+    for y in 0..inset_height {
+        for x in 0..inset_width {
+            if inset_left + x < img.width() && inset_top + y < img.height() {
+                img.put_pixel(inset_left + x, inset_top + y, *rendered.get_pixel(x, y + title_space));
+            }
+        }
+    }
+    draw_empty_subplot_frame(img, (inset_left, inset_top), (inset_width, inset_height), style);
+    Ok(())
+}
+
+// Width reserved right of the plot for each colorbar and its labels
+pub const COLORBAR_SPACING: u32 = 80;
+
+// Width reserved right of the plot for the tick labels of the secondary Y axis
+pub const SECONDARY_AXIS_SPACING: u32 = 60;
+
+// Right-hand Y axis along the plot's right edge at `axis_x`, spanning `top` to `bottom`
+// in pixels. `min_y` and `max_y` are the plot coordinates of the left axis; like the
+// interactive plot, its ticks are round values of the secondary range (see secondary_axis_marks).
+/// Function: explain its purpose and key arguments
+fn draw_secondary_axis(
+    img: &mut image::RgbImage,
+    axis: SecondaryAxis,
+    (min_y, max_y): (f64, f64),
+    (axis_x, top, bottom): (u32, u32, u32),
+    config: &SubplotConfig,
+    style: &ExportStyle,
+) {
+// Variable declaration
+    let font_scale = style.font_scale;
+// Variable declaration
+    let line_width = style.line_width(1.0);
+// Variable declaration
+    let label = config.y2_label.as_str();
+    fill_rect(img, (axis_x, top), (line_width, bottom - top), style.axis);
+    for (mark, text) in secondary_axis_marks(axis, (min_y, max_y), style.unscaled(bottom - top), config) {
+// Variable declaration
+        let spacing = mark_spacing_px(&mark, (min_y, max_y), bottom - top);
+// Variable declaration
+        let (_, text_height) = measure_text(&text, font_scale);
+        if spacing <= Y_LABEL_SPACING_PX * style.scale as f64 || spacing < text_height as f64 {
+            continue;
+        }
+// Variable declaration
+        let y_pos = bottom as f64 - (mark.value - min_y) / (max_y - min_y) * (bottom - top) as f64;
+// Variable declaration
+        let y_pos = y_pos.round().clamp(top as f64, bottom as f64) as u32;
+        fill_rect(img, (axis_x, y_pos.saturating_sub(line_width / 2)), (style.px(5), line_width), style.axis);
+        draw_text_scaled(img, axis_x + style.px(10), y_pos.saturating_sub(text_height / 2), &text, style.text, font_scale);
+    }
+    if !label.is_empty() {
+// Variable declaration
+        let (text_width, _) = measure_text(label, font_scale);
+// Variable declaration
+        let label_y = ((top + bottom) / 2).saturating_sub(text_width / 2);
+        draw_text_rotated(img, axis_x + style.px(SECONDARY_AXIS_SPACING + 4), label_y, label, style.text, font_scale);
+    }
+}
+
+// Box plot or violin of a dataset's Y values centred on `position`, like the interactive
+// plot draws it. `to_px` turns plot coordinates into pixels; `half_width` is half the
+// width of a box in pixels and `scale` the export scale factor.
+/// Function: explain its purpose and key arguments
+fn draw_distribution_to_image(
+    img: &mut image::RgbImage,
+    dataset: &Dataset,
+    position: f64,
+    (x_scale, y_scale): (AxisScale, AxisScale),
+    to_px: &dyn Fn(f64, f64) -> (f64, f64),
+    half_width: f64,
+    scale: f32,
+) {
+// Variable declaration
+    let Some(Distribution { summary, density }) = distribution(dataset, y_scale) else {
+        return;
+    };
+// Variable declaration
+    let color = image::Rgb(dataset.color);
+// Variable declaration
+    let thickness = (dataset.line_width * scale).round().max(1.0) as u32;
+// Variable declaration
+    let x = x_scale.slot(position);
+// Variable declaration
+    let cx = to_px(x, 0.0).0;
+// Variable declaration
+    let py = |y: f64| to_px(x, y).1;
+// Variable declaration
+    let line = |img: &mut image::RgbImage, (x0, y0): (f64, f64), (x1, y1): (f64, f64), thickness: u32| {
+        draw_thick_line(img, x0.max(0.0) as u32, y0.max(0.0) as u32, x1.max(0.0) as u32, y1.max(0.0) as u32, color, thickness);
+    };
+
+    if dataset.plot_type == PlotType::Violin {
+// Variable declaration
+        let peak = density.iter().map(|d| d[1]).fold(0.0, f64::max);
+        if density.len() > 1 && peak > 0.0 {
+// Variable declaration
+            let width_px = |d: f64| d / peak * half_width;
+            for pair in density.windows(2) {
+// Variable declaration
+                let ([y0, d0], [y1, d1]) = (pair[0], pair[1]);
+// Variable declaration
+                let (top, bottom) = (py(y1), py(y0));
+                for row in top.round().max(0.0) as u32..bottom.round().max(0.0) as u32 {
+// Variable declaration
+                    let t = if bottom > top { (bottom - (row as f64 + 0.5)) / (bottom - top) } else { 0.0 };
+// Variable declaration
+                    let w = width_px(d0 + (d1 - d0) * t.clamp(0.0, 1.0));
+                    for col in (cx - w).round().max(0.0) as u32..(cx + w).round().max(0.0) as u32 {
+                        blend_pixel(img, col as i64, row as i64, color, FILL_OPACITY as f32);
+                    }
+                }
+                for side in [-1.0, 1.0] {
+                    line(img, (cx + side * width_px(d0), bottom), (cx + side * width_px(d1), top), thickness);
+                }
+            }
+        }
+        line(img, (cx, py(summary.q1)), (cx, py(summary.q3)), thickness * 3);
+        draw_filled_circle(img, cx.max(0.0) as u32, py(summary.median).max(0.0) as u32, scale_px(3, scale), image::Rgb([255, 255, 255]));
+        return;
+    }
+
+// Variable declaration
+    let (left, right) = (cx - half_width, cx + half_width);
+// Variable declaration
+    let (q1, q3) = (py(summary.q1), py(summary.q3));
+    for row in q3.round().max(0.0) as u32..q1.round().max(0.0) as u32 {
+        for col in left.round().max(0.0) as u32..right.round().max(0.0) as u32 {
+            blend_pixel(img, col as i64, row as i64, color, FILL_OPACITY as f32);
+        }
+    }
+    line(img, (left, q1), (right, q1), thickness);
+    line(img, (left, q3), (right, q3), thickness);
+    line(img, (left, q1), (left, q3), thickness);
+    line(img, (right, q1), (right, q3), thickness);
+    line(img, (left, py(summary.median)), (right, py(summary.median)), thickness + 1);
+    // Whiskers with caps half as wide as the box
+    for (end, whisker) in [(q1, py(summary.lower_whisker)), (q3, py(summary.upper_whisker))] {
+        line(img, (cx, end), (cx, whisker), thickness);
+        line(img, (cx - half_width / 2.0, whisker), (cx + half_width / 2.0, whisker), thickness);
+    }
+    for &outlier in &summary.outliers {
+        draw_filled_circle(img, cx.max(0.0) as u32, py(outlier).max(0.0) as u32, scale_px(2, scale), color);
+    }
+}
+
+// Blend `color` at FILL_OPACITY into every pixel column between neighbouring points,
+// from the line (`edges[i].0`, in pixels) to the baseline (`edges[i].1`), within the
+// given X and Y pixel ranges. Points without edges leave a gap.
+/// Function: explain its purpose and key arguments
+fn fill_between(
+    img: &mut image::RgbImage,
+    edges: &[Option<([f64; 2], f64)>],
+    color: [u8; 3],
+    (left, right): (u32, u32),
+    (top, bottom): (u32, u32),
+) {
+    for pair in edges.windows(2) {
+// Variable declaration
+        let (Some(([x0, upper0], lower0)), Some(([x1, upper1], lower1))) = (pair[0], pair[1]) else {
+            continue;
+        };
+        if x1 <= x0 {
+            continue;
+        }
+// Variable declaration
+        let first = x0.round().max(left as f64 + 1.0) as u32;
+// Variable declaration
+        let last = x1.round().min(right as f64) as u32;
+        for px in first..last {
+// Variable declaration
+            let t = (px as f64 + 0.5 - x0) / (x1 - x0);
+// Variable declaration
+            let upper = upper0 + (upper1 - upper0) * t;
+// Variable declaration
+            let lower = lower0 + (lower1 - lower0) * t;
+// Variable declaration
+            let from = upper.min(lower).round().max(top as f64) as u32;
+// Variable declaration
+            let to = upper.max(lower).round().min(bottom as f64) as u32;
+            for py in from..to {
+                blend_pixel(img, px as i64, py as i64, image::Rgb(color), FILL_OPACITY as f32);
+            }
+        }
+    }
+}
+
+// Vertical colorbar filling `rect`: the colormap from low (bottom) to high (top),
+// outlined, with the end values to its right
+/// Function: explain its purpose and key arguments
+fn draw_colorbar(
+    img: &mut image::RgbImage,
+    ColorbarRect { left, top, height }: ColorbarRect<u32>,
+    (colormap, (low, high)): (Colormap, (f64, f64)),
+    style: &ExportStyle,
+) {
+    const BAR_WIDTH: u32 = 12;
+// Variable declaration
+    let bar_width = style.px(BAR_WIDTH);
+    for row in 0..height {
+// Variable declaration
+        let color = image::Rgb(colormap.sample(1.0 - row as f64 / (height - 1).max(1) as f64));
+        fill_rect(img, (left, top + row), (bar_width, 1), color);
+    }
+// Variable declaration
+    let (right, bottom) = (left + bar_width, top + height);
+// Variable declaration
+    let (outline, line_width) = (style.axis, style.line_width(1.0));
+    draw_thick_line(img, left, top, right, top, outline, line_width);
+    draw_thick_line(img, left, bottom, right, bottom, outline, line_width);
+    draw_thick_line(img, left, top, left, bottom, outline, line_width);
+    draw_thick_line(img, right, top, right, bottom, outline, line_width);
+
+// Variable declaration
+    let scale = style.font_scale * 0.9;
+    for (value, y) in [(high, top), (low, bottom)] {
+// Variable declaration
+        let label = format_number(value);
+// Variable declaration
+        let (_, text_height) = measure_text(&label, scale);
+        draw_text_scaled(img, right + style.px(4), y.saturating_sub(text_height / 2), &label, style.text, scale);
+    }
+}
+
+// Maps data coordinates to pixels of the exported image, or None when off the plot
+type ToPixel<'a> = &'a dyn Fn(&[f64; 2]) -> Option<(u32, u32)>;
+
+// Draw one annotation; `to_px` maps data coordinates to pixels, or None when off the plot
+/// Function: explain its purpose and key arguments
+fn draw_annotation(
+    img: &mut image::RgbImage,
+    annotation: &Annotation,
+    to_px: ToPixel,
+    plot_rows: (u32, u32), // Top and bottom pixel rows of the plot area
+    style: &ExportStyle,
+) {
+// Variable declaration
+    let color = image::Rgb(annotation.color);
+// Variable declaration
+    let Some((x, y)) = to_px(&annotation.position) else {
+        return;
+    };
+
+    match annotation.kind {
+        AnnotationKind::Text => {}
+        AnnotationKind::Arrow => {
+            if let Some((tail_x, tail_y)) = annotation.tail.as_ref().and_then(to_px) {
+                draw_thick_line(img, tail_x, tail_y, x, y, color, style.line_width(2.0));
+                // Arrow head: two strokes 25 degrees either side of the shaft
+// Variable declaration
+                let angle = (y as f64 - tail_y as f64).atan2(x as f64 - tail_x as f64);
+                for side in [-1.0, 1.0] {
+// Variable declaration
+                    let wing = angle + std::f64::consts::PI - side * 25f64.to_radians();
+// Variable declaration
+                    let length = style.px(10) as f64;
+// Variable declaration
+                    let wing_x = (x as f64 + length * wing.cos()).max(0.0) as u32;
+// Variable declaration
+                    let wing_y = (y as f64 + length * wing.sin()).max(0.0) as u32;
+                    draw_thick_line(img, x, y, wing_x, wing_y, color, style.line_width(2.0));
+                }
+            }
+        }
+        AnnotationKind::Marker => draw_filled_circle(img, x, y, style.px(5), color),
+        AnnotationKind::VLine => {
+// Variable declaration
+            let mut phase = 0.0;
+// Variable declaration
+            let dashes = (style.px(8) as f64, style.px(6) as f64);
+            draw_dashed_line(img, (x, plot_rows.0), (x, plot_rows.1), color, style.line_width(1.0), dashes, &mut phase);
+        }
+    }
+
+    if annotation.text.is_empty() {
+        return;
+    }
+// Variable declaration
+    let Some((label_x, label_y)) = to_px(&annotation.label_position()) else {
+        return;
+    };
+// Variable declaration
+    let scale = style.font_scale;
+// Variable declaration
+    let (text_width, text_height) = measure_text(&annotation.text, scale);
+    // Plain labels are centred on their point; arrow and marker labels sit beside it
+    if annotation.kind == AnnotationKind::Text {
+        draw_text_scaled(
+            img,
+            label_x.saturating_sub(text_width / 2),
+            label_y.saturating_sub(text_height / 2),
+            &annotation.text,
+            color,
+            scale,
+        );
+    } else {
+        draw_text_scaled(
+            img,
+            label_x + style.px(6),
+            label_y.saturating_sub(text_height + style.px(2)),
+            &annotation.text,
+            color,
+            scale,
+        );
+    }
+}
+
+// Title shown above an exported subplot
+/// Function: explain its purpose and key arguments
+pub fn subplot_display_title(title: &str, subplot_number: usize) -> String {
+    if title.is_empty() {
+        format!("Subplot {}", subplot_number)
+    } else {
+        format!("Subplot {}: {}", subplot_number, title)
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn draw_subplot_title(
+    img: &mut image::RgbImage,
+    x_offset: u32,
+    y_offset: u32,
+    width: u32,
+    title: &str,
+    subplot_number: usize,
+    style: &ExportStyle,
+) {
+// Variable declaration
+    let display_title = subplot_display_title(title, subplot_number);
+    
+// Variable declaration
+    let font_scale = style.font_scale * 1.2; // Slightly larger for titles
+// Variable declaration
+    let (title_width, _) = measure_text(&display_title, font_scale);
+// Variable declaration
+    let title_x = x_offset + width.saturating_sub(title_width) / 2; // Center the title
+    
+    draw_text_scaled(img, title_x, y_offset + style.px(5), &display_title, style.text, font_scale);
+}
+
+// Border just inside the box with top-left corner `(x_offset, y_offset)` and the given size
+/// Function: explain its purpose and key arguments
+fn draw_empty_subplot_frame(
+    img: &mut image::RgbImage,
+    (x_offset, y_offset): (u32, u32),
+    (width, height): (u32, u32),
+    style: &ExportStyle,
+) {
+// Variable declaration
+    let line_width = style.line_width(1.0).min(width).min(height);
+    // Draw border
+    fill_rect(img, (x_offset, y_offset), (width, line_width), style.axis);
+    fill_rect(img, (x_offset, y_offset + height - line_width), (width, line_width), style.axis);
+    fill_rect(img, (x_offset, y_offset), (line_width, height), style.axis);
+    fill_rect(img, (x_offset + width - line_width, y_offset), (line_width, height), style.axis);
+}
+
+// Fill the `width` by `height` pixel rectangle with top-left corner (x, y), cut to the image
+/// Function: explain its purpose and key arguments
+fn fill_rect(img: &mut image::RgbImage, (x, y): (u32, u32), (width, height): (u32, u32), color: image::Rgb<u8>) {
+    for py in y..(y + height).min(img.height()) {
+        for px in x..(x + width).min(img.width()) {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+// Spacing in pixels at which the interactive plot draws grid lines at full strength; they
+// fade out towards the subplot's minimum grid spacing
+pub const GRID_FULL_STRENGTH_PX: f64 = 300.0;
+
+// Spacing in pixels the interactive plot needs between tick labels before it shows them.
+// Grid marks closer than this are minor.
+pub const X_LABEL_SPACING_PX: f64 = 60.0;
+pub const Y_LABEL_SPACING_PX: f64 = 20.0;
+
+// Grid marks of one axis, as the interactive plot's grid spacer picks them: multiples of
+// three successive powers of ten on linear axes, decades and their 2..9 multiples on log
+// axes, round dates and times on time axes. Minor marks are left out unless `minor` is
+// set (see X_LABEL_SPACING_PX). Reversed axes get the marks of the values they show,
+// mirrored into their negated plot coordinates.
+/// Function: explain its purpose and key arguments
+pub fn plot_grid_marks(input: GridInput, scale: AxisScale, min_spacing: f64, label_spacing: f64, minor: bool) -> Vec<GridMark> {
+// Variable declaration
+    let base_step_size = input.base_step_size;
+// Variable declaration
+    let (min, max) = input.bounds;
+// Variable declaration
+    let input = if scale.reversed { GridInput { bounds: (-max, -min), base_step_size } } else { input };
+// Variable declaration
+    let marks = match scale.kind {
+        ScaleKind::Linear => log_grid_spacer(10)(input),
+        ScaleKind::Log10 => log_axis_grid_marks(input),
+        ScaleKind::Time => time_grid_marks(input),
+    };
+    marks
+        .into_iter()
+        .filter(|mark| minor || mark.step_size / base_step_size * min_spacing > label_spacing)
+        .map(|mark| if scale.reversed { GridMark { value: -mark.value, ..mark } } else { mark })
+        .collect()
+}
+
+// Grid marks of an exported axis spanning [min, max] over `pixels`, chosen exactly as the
+// interactive plot of the subplot chooses them
+/// Function: explain its purpose and key arguments
+pub fn axis_grid_marks(
+    (min, max): (f64, f64),
+    scale: AxisScale,
+    pixels: u32,
+    config: &SubplotConfig,
+    label_spacing: f64,
+) -> Vec<GridMark> {
+    if max <= min || !min.is_finite() || !max.is_finite() || pixels == 0 {
+        return Vec::new();
+    }
+// Variable declaration
+    let min_spacing = config.grid_spacing as f64;
+// Variable declaration
+    let input = GridInput {
+        bounds: (min, max),
+        base_step_size: (max - min) / pixels as f64 * min_spacing,
+    };
+    // The log spacer also returns the decades just outside the range
+    plot_grid_marks(input, scale, min_spacing, label_spacing, config.grid_minor)
+        .into_iter()
+        .filter(|mark| mark.value >= min && mark.value <= max)
+        .collect()
+}
+
+// Grid line along one axis of an exported plot: from `start` for `length` pixels, along X
+// when `horizontal`, `thickness` pixels wide. Blended with the given opacity, broken up in
+// the grid's line pattern.
+/// Function: explain its purpose and key arguments
+fn draw_grid_line(
+    img: &mut image::RgbImage,
+    start: (u32, u32),
+    length: u32,
+    horizontal: bool,
+    (color, opacity): (image::Rgb<u8>, f32),
+    style: LinePattern,
+    thickness: u32,
+) {
+// Variable declaration
+    let pattern = style.dash_lengths(thickness);
+    for t in 0..length {
+        if let Some((on, off)) = pattern {
+            if t as f64 % (on + off) >= on {
+                continue;
+            }
+        }
+        for offset in 0..thickness {
+            // Centred on the mark
+// Variable declaration
+            let across = offset as i64 - thickness as i64 / 2;
+// Variable declaration
+            let (x, y) = if horizontal {
+                ((start.0 + t) as i64, start.1 as i64 + across)
+            } else {
+                (start.0 as i64 + across, (start.1 + t) as i64)
+            };
+            blend_pixel(img, x, y, color, opacity);
+        }
+    }
+}
+
+// Distance in pixels to the neighbouring marks of the same step size
+/// Function: explain its purpose and key arguments
+pub fn mark_spacing_px(mark: &GridMark, (min, max): (f64, f64), pixels: u32) -> f64 {
+    mark.step_size / (max - min) * pixels as f64
+}
+
+// Tick label for a grid mark in plot coordinates
+/// Function: explain its purpose and key arguments
+pub fn axis_tick_label(mark: &GridMark, scale: AxisScale) -> String {
+// Variable declaration
+    let value = if scale.reversed { -mark.value } else { mark.value };
+    match scale.kind {
+        ScaleKind::Linear => format_grid_mark(value),
+        ScaleKind::Log10 => format_log_tick(10f64.powf(value)),
+        ScaleKind::Time => format_time_tick(value, mark.step_size),
+    }
+}
+
+// Tick label formatted like egui_plot's default axis formatter (at most 5 decimals,
+// scientific notation for very large or very small values)
+/// Function: explain its purpose and key arguments
+fn format_grid_mark(tick: f64) -> String {
+    const MAX_DIGITS: i32 = 5;
+    if tick.abs() > 10f64.powi(MAX_DIGITS) {
+        return format!("{:+e}", tick as isize);
     }
-    for y in y_offset..(y_offset + height) {
-        img.put_pixel(x_offset, y, color);
-        img.put_pixel(x_offset + width - 1, y, color);
+// Variable declaration
+    let rounded = egui::emath::round_to_decimals(tick, MAX_DIGITS as usize);
+    if tick.abs() < 10f64.powi(-MAX_DIGITS) && tick != 0.0 {
+        return format!("{:+e}", rounded);
     }
+    rounded.to_string()
 }
 
+// Each axis is given as (min, max, scale, grid marks) in plot coordinates. Like the
+// interactive plot, only marks far enough apart for their labels get a tick and label.
 /// Function: explain its purpose and key arguments
 fn draw_subplot_axis_labels(
     img: &mut image::RgbImage,
-    min_x: f64,
-    max_x: f64,
-    min_y: f64,
-    max_y: f64,
+    (min_x, max_x, x_scale, x_marks): (f64, f64, AxisScale, &[GridMark]),
+    (min_y, max_y, y_scale, y_marks): (f64, f64, AxisScale, &[GridMark]),
     margin_left: u32,
     margin_bottom: u32,
     plot_width: u32,
     plot_height: u32,
     _total_width: u32,
     total_height: u32,
-    color: image::Rgb<u8>,
-    font_size: &FontSize,
+    style: &ExportStyle,
 ) {
 // Variable declaration
-    let font_scale = font_size.to_scale();
+    let (color, font_scale) = (style.text, style.font_scale);
+// Variable declaration
+    let (tick_length, line_width) = (style.px(5), style.line_width(1.0));
     
-    // X-axis labels (fewer ticks for subplots)
-    for i in 0..=3 {
+    // X-axis labels
+    for mark in x_marks {
+// Variable declaration
+        let tick_value = mark.value;
+// Variable declaration
+        let spacing = mark_spacing_px(mark, (min_x, max_x), plot_width);
 // Variable declaration
-        let tick_value = min_x + (max_x - min_x) * (i as f64 / 3.0);
+        let text = axis_tick_label(mark, x_scale);
+// Variable declaration
+        let (text_width, _) = measure_text(&text, font_scale);
+        if spacing <= X_LABEL_SPACING_PX * style.scale as f64 || spacing < text_width as f64 {
+            continue;
+        }
 // Variable declaration
         let x_pos = margin_left + ((tick_value - min_x) / (max_x - min_x) * plot_width as f64) as u32;
 // Variable declaration
         let tick_y = total_height - margin_bottom;
         
         // Draw tick mark
-        for dy in 0..5 {
-            if tick_y + dy < img.height() {
-                img.put_pixel(x_pos, tick_y + dy, color);
-            }
-        }
+        fill_rect(img, (x_pos.saturating_sub(line_width / 2), tick_y), (line_width, tick_length), color);
         
         // Draw label
-// Variable declaration
-        let char_width = (6.0 * font_scale) as u32;
-// Variable declaration
-        let text = format_number(tick_value);
-// Variable declaration
-        let text_width = text.len() as u32 * char_width;
 // Variable declaration
         let label_x = if x_pos >= text_width / 2 {
             x_pos - text_width / 2
@@ -449,99 +3632,364 @@ fn draw_subplot_axis_labels(
             0
         };
         
-        draw_number_pixels_scaled(img, label_x, tick_y + 8, tick_value, color, font_scale);
+        draw_text_scaled(img, label_x, tick_y + style.px(8), &text, color, font_scale);
     }
 
     // Y-axis labels
-    for i in 0..=3 {
+    for mark in y_marks {
+// Variable declaration
+        let tick_value = mark.value;
+// Variable declaration
+        let spacing = mark_spacing_px(mark, (min_y, max_y), plot_height);
 // Variable declaration
-        let tick_value = min_y + (max_y - min_y) * (i as f64 / 3.0);
+        let text = axis_tick_label(mark, y_scale);
+// Variable declaration
+        let (text_width, text_height) = measure_text(&text, font_scale);
+        if spacing <= Y_LABEL_SPACING_PX * style.scale as f64 || spacing < text_height as f64 {
+            continue;
+        }
 // Variable declaration
         let y_pos = total_height - margin_bottom - ((tick_value - min_y) / (max_y - min_y) * plot_height as f64) as u32;
 // Variable declaration
         let tick_x = margin_left;
         
         // Draw tick mark
-        for dx in 0..5 {
-            if tick_x >= dx {
-                img.put_pixel(tick_x - dx, y_pos, color);
-            }
-        }
+        fill_rect(
+            img,
+            ((tick_x + 1).saturating_sub(tick_length), y_pos.saturating_sub(line_width / 2)),
+            (tick_length.min(tick_x + 1), line_width),
+            color,
+        );
         
         // Draw label
 // Variable declaration
-        let text = format_number(tick_value);
+        let label_x = tick_x.saturating_sub(text_width + style.px(10));
+        
+// Variable declaration
+        let label_y = y_pos.saturating_sub(text_height / 2);
+        
+        draw_text_scaled(img, label_x, label_y, &text, color, font_scale);
+    }
+}
+
+// Legend of an exported subplot: one entry per dataset in columns filled top to bottom
+// under the title. Entries wrap into extra columns when they do not fit the height.
+#[derive(Debug, Clone, PartialEq)]
+/// Data structure used in utils.rs module
+pub struct LegendLayout {
+    pub names: Vec<String>, // Shortened with "…" only where the legend would not fit the width
+    pub rows: usize,
+    pub column_widths: Vec<u32>,
+    pub scale: f32,
+    pub line_height: u32,
+    pub swatch_width: u32, // Room for the marker preview before each name
+    pub title_height: u32,
+    pub padding: u32, // Between the box and its contents, and between columns
+    pub size: (u32, u32), // Of the whole box, padding included
+}
+
+// Space between the legend box and its contents
+pub const LEGEND_PADDING: u32 = 6;
+
+// Lay out the legend of `datasets` to fit within `max_size` (width, height) in pixels of a
+// figure exported at `scale`
+/// Function: explain its purpose and key arguments
+fn export_legend_layout(
+    datasets: &[Cow<'_, Dataset>],
+    config: &SubplotConfig,
+    max_size: (u32, u32),
+    scale: f32,
+) -> LegendLayout {
 // Variable declaration
-        let char_width = (6.0 * font_scale) as u32;
+    let font_scale = config.legend_font_size / BASE_FONT_PX * scale;
 // Variable declaration
-        let text_width = text.len() as u32 * char_width;
+    let padding = scale_px(LEGEND_PADDING, scale);
 // Variable declaration
-        let label_x = if tick_x >= text_width + 10 {
-            tick_x - text_width - 10
-        } else {
-            0
-        };
-        
+    let line_height = measure_text("Xg", font_scale).1 + scale_px(4, scale);
 // Variable declaration
-        let char_height = (7.0 * font_scale) as u32;
+    let swatch_width = line_height * 2;
 // Variable declaration
-        let label_y = y_pos.saturating_sub(char_height / 2);
-        
-        draw_number_pixels_scaled(img, label_x, label_y, tick_value, color, font_scale);
+    let title_height = if config.legend_title.is_empty() { 0 } else { line_height + scale_px(5, scale) };
+
+    // As many rows as fit the height, and at least as many columns as requested
+// Variable declaration
+    let fitting_rows = (max_size.1.saturating_sub(title_height + 2 * padding) / line_height).max(1) as usize;
+// Variable declaration
+    let columns = config.legend_columns.max(1).max(datasets.len().div_ceil(fitting_rows));
+// Variable declaration
+    let rows = datasets.len().div_ceil(columns).max(1);
+
+    // Names longer than their share of the width are shortened to fit
+// Variable declaration
+    let columns_used = datasets.len().div_ceil(rows).max(1) as u32;
+// Variable declaration
+    let name_width = (max_size.0.saturating_sub(2 * padding * columns_used) / columns_used)
+        .saturating_sub(swatch_width);
+// Variable declaration
+    let names: Vec<String> = datasets.iter().map(|dataset| fit_text(&dataset.name, font_scale, name_width)).collect();
+// Variable declaration
+    let column_widths: Vec<u32> = names
+        .chunks(rows)
+        .map(|column| swatch_width + column.iter().map(|name| measure_text(name, font_scale).0).max().unwrap_or(0))
+        .collect();
+// Variable declaration
+    let entries_width = column_widths.iter().sum::<u32>() + 2 * padding * column_widths.len().saturating_sub(1) as u32;
+// Variable declaration
+    let width = entries_width.max(measure_text(&config.legend_title, font_scale).0) + 2 * padding;
+// Variable declaration
+    let height = title_height + rows as u32 * line_height + 2 * padding;
+    LegendLayout {
+        names,
+        rows,
+        column_widths,
+        scale: font_scale,
+        line_height,
+        swatch_width,
+        title_height,
+        padding,
+        size: (width, height),
+    }
+}
+
+// `text` as it is, or cut short with "…" so that it is at most `max_width` pixels wide
+/// Function: explain its purpose and key arguments
+fn fit_text(text: &str, scale: f32, max_width: u32) -> String {
+    if measure_text(text, scale).0 <= max_width {
+        return text.to_string();
+    }
+// Variable declaration
+    let chars: Vec<char> = text.chars().collect();
+    (0..chars.len())
+        .rev()
+        .map(|keep| format!("{}…", chars[..keep].iter().collect::<String>().trim_end()))
+        .find(|shortened| measure_text(shortened, scale).0 <= max_width)
+        .unwrap_or_else(|| "…".to_string())
+}
+
+// Preview of how a dataset is drawn, in the box with top-left (x, y): a short line in its
+// line pattern and width, a marker, or both; bars and distributions get a filled square.
+// Line widths and markers are multiplied by the export `scale`.
+/// Function: explain its purpose and key arguments
+fn draw_legend_marker(img: &mut image::RgbImage, dataset: &Dataset, (x, y): (u32, u32), (width, height): (u32, u32), scale: f32) {
+// Variable declaration
+    let color = image::Rgb(dataset.color);
+// Variable declaration
+    let middle = y + height / 2;
+    if dataset.plot_type.draws_line() {
+// Variable declaration
+        let thickness = (dataset.line_width * scale).round().max(1.0) as u32;
+// Variable declaration
+        let (start, end) = ((x + scale_px(2, scale), middle), (x + width - scale_px(4, scale), middle));
+        match dataset.line_style.dash_lengths(thickness) {
+            Some(pattern) => draw_dashed_line(img, start, end, color, thickness, pattern, &mut 0.0),
+            None => draw_thick_line(img, start.0, start.1, end.0, end.1, color, thickness),
+        }
+    }
+    // Large markers are shrunk to fit the legend line
+    if dataset.plot_type.draws_markers() {
+// Variable declaration
+        let radius = dataset.marker_size.min(LEGEND_MARKER_MAX_SIZE) * scale;
+        draw_marker(img, (x + (width - scale_px(2, scale)) / 2, middle), dataset.marker, radius, color);
+    }
+    if dataset.plot_type.draws_bars() || dataset.plot_type.draws_distribution() {
+// Variable declaration
+        let side = height * 2 / 3;
+        for dy in 0..side {
+            for dx in 0..side {
+                blend_pixel(img, (x + (width - side) / 2 + dx) as i64, (middle - side / 2 + dy) as i64, color, 1.0);
+            }
+        }
     }
 }
 
+// Layout of the legend box and its top-left corner in `area` (left, top, right, bottom in
+// pixels): a corner of the plot area, or the top of the strip right of it for legends
+// outside the plot. `scale` is the export scale factor.
+/// Function: explain its purpose and key arguments
+pub fn export_legend_box(
+    datasets: &[Cow<'_, Dataset>],
+    config: &SubplotConfig,
+    (left, top, right, bottom): (u32, u32, u32, u32),
+    scale: f32,
+) -> (LegendLayout, (u32, u32)) {
+// Variable declaration
+    let margin = scale_px(10, scale);
+// Variable declaration
+    let max_size = ((right - left).saturating_sub(2 * margin), (bottom - top).saturating_sub(2 * margin));
+// Variable declaration
+    let layout = export_legend_layout(datasets, config, max_size, scale);
+// Variable declaration
+    let (width, height) = layout.size;
+// Variable declaration
+    let corner = match config.legend_position {
+        LegendPosition::TopLeft | LegendPosition::OutsideRight => (left + margin, top + margin),
+        LegendPosition::TopRight => (right.saturating_sub(width + margin), top + margin),
+        LegendPosition::BottomLeft => (left + margin, bottom.saturating_sub(height + margin)),
+        LegendPosition::BottomRight => (right.saturating_sub(width + margin), bottom.saturating_sub(height + margin)),
+    };
+    (layout, corner)
+}
+
+// Draw the legend box in a corner of `area` (left, top, right, bottom in pixels): the plot
+// area, or the strip right of it for legends outside the plot, placed like the top left
 /// Function: explain its purpose and key arguments
 fn draw_subplot_legend(
     img: &mut image::RgbImage,
-    datasets: &[Dataset],
-    legend_title: &str,
-    x_offset: u32,
-    y_offset: u32,
-    color: image::Rgb<u8>,
-    font_size: &FontSize,
+    datasets: &[Cow<'_, Dataset>],
+    config: &SubplotConfig,
+    (left, top, right, bottom): (u32, u32, u32, u32),
+    style: &ExportStyle,
 ) {
 // Variable declaration
-    let font_scale = font_size.to_scale();
+    let (layout, (x0, y0)) = export_legend_box(datasets, config, (left, top, right, bottom), style.scale);
 // Variable declaration
-    let line_height = (10.0 * font_scale) as u32;
+    let (width, height) = layout.size;
 // Variable declaration
-    let mut current_y = y_offset;
-    
+    let (color, border) = (style.text, style.line_width(1.0));
+
+    // Background, blended over the plot with the legend opacity, and border
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
+// Variable declaration
+            let edge = x < x0 + border || y < y0 + border || x + border >= x0 + width || y + border >= y0 + height;
+            if edge && config.legend_border {
+                blend_pixel(img, x as i64, y as i64, style.axis, 1.0);
+            } else {
+                blend_pixel(img, x as i64, y as i64, style.background, config.legend_opacity);
+            }
+        }
+    }
+
+// Variable declaration
+    let mut current_y = y0 + layout.padding;
     // Draw legend title if provided
-    if !legend_title.is_empty() {
-        draw_text_scaled(img, x_offset, current_y, legend_title, color, font_scale);
-        current_y += line_height + 5;
+    if !config.legend_title.is_empty() {
+        draw_text_scaled(img, x0 + layout.padding, current_y, &config.legend_title, color, layout.scale);
+        current_y += layout.title_height;
     }
-    
-    // Draw legend entries
-    for dataset in datasets.iter().take(5) { // Limit to 5 entries for space
-        // Draw color square
+
+    // Draw legend entries, column by column
 // Variable declaration
-        let square_size = (8.0 * font_scale) as u32;
+    let mut column_x = x0 + layout.padding;
+    for ((names, column_datasets), column_width) in
+        layout.names.chunks(layout.rows).zip(datasets.chunks(layout.rows)).zip(&layout.column_widths)
+    {
+        for (row, (name, dataset)) in names.iter().zip(column_datasets).enumerate() {
 // Variable declaration
-        let dataset_color = image::Rgb(dataset.color);
-        for dy in 0..square_size {
-            for dx in 0..square_size {
-                if x_offset + dx < img.width() && current_y + dy < img.height() {
-                    img.put_pixel(x_offset + dx, current_y + dy, dataset_color);
-                }
+            let entry_y = current_y + row as u32 * layout.line_height;
+            draw_legend_marker(img, dataset, (column_x, entry_y), (layout.swatch_width, layout.line_height), style.scale);
+            draw_text_scaled(img, column_x + layout.swatch_width, entry_y, name, color, layout.scale);
+        }
+        column_x += column_width + 2 * layout.padding;
+    }
+}
+
+// Font size in pixels for a text scale of 1.0 (tick labels at Medium size)
+pub const BASE_FONT_PX: f32 = 12.0;
+
+// Fonts used for PNG export text, in fallback order (same faces egui uses on screen)
+/// Function: explain its purpose and key arguments
+pub fn export_fonts() -> &'static [FontArc] {
+    static FONTS: OnceLock<Vec<FontArc>> = OnceLock::new();
+    FONTS.get_or_init(|| {
+// Variable declaration
+        let definitions = egui::FontDefinitions::default();
+        ["Ubuntu-Light", "Hack", "NotoEmoji-Regular", "emoji-icon-font"]
+            .iter()
+            .filter_map(|name| definitions.font_data.get(*name))
+            .filter_map(|data| FontArc::try_from_vec(data.font.to_vec()).ok())
+            .collect()
+    })
+}
+
+// Lay out `text` at the given scale, calling `plot` with (x, y, coverage) for every
+// rasterized pixel relative to the top-left of the text box. Returns the box size.
+/// Function: explain its purpose and key arguments
+fn layout_text<F: FnMut(i32, i32, f32)>(text: &str, scale: f32, mut plot: F) -> (u32, u32) {
+// Variable declaration
+    let fonts = export_fonts();
+    if fonts.is_empty() {
+        return (0, 0);
+    }
+
+// Variable declaration
+    let px_scale = PxScale::from(BASE_FONT_PX * scale);
+// Variable declaration
+    let primary = fonts[0].as_scaled(px_scale);
+// Variable declaration
+    let ascent = primary.ascent();
+// Variable declaration
+    let height = (primary.ascent() - primary.descent()).ceil().max(1.0) as u32;
+// Variable declaration
+    let mut caret = 0.0f32;
+// Variable declaration
+    let mut previous: Option<(usize, ab_glyph::GlyphId)> = None;
+
+    for ch in text.chars() {
+        // Pick the first font that actually has this character
+// Variable declaration
+        let font_index = fonts
+            .iter()
+            .position(|font| font.glyph_id(ch).0 != 0)
+            .unwrap_or(0);
+// Variable declaration
+        let font = fonts[font_index].as_scaled(px_scale);
+// Variable declaration
+        let glyph_id = font.glyph_id(ch);
+
+        if let Some((prev_index, prev_id)) = previous {
+            if prev_index == font_index {
+                caret += font.kern(prev_id, glyph_id);
             }
         }
-        
-        // Draw dataset name (truncated if too long)
+
 // Variable declaration
-        let name = if dataset.name.len() > 15 {
-            format!("{}...", &dataset.name[..12])
-        } else {
-            dataset.name.clone()
-        };
-        
-        draw_text_scaled(img, x_offset + square_size + 5, current_y, &name, color, font_scale * 0.8);
-        current_y += line_height;
+        let glyph = glyph_id.with_scale_and_position(px_scale, ab_glyph::point(caret, ascent));
+        if let Some(outlined) = fonts[font_index].outline_glyph(glyph) {
+// Variable declaration
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                plot(
+                    bounds.min.x as i32 + gx as i32,
+                    bounds.min.y as i32 + gy as i32,
+                    coverage,
+                );
+            });
+        }
+
+        caret += font.h_advance(glyph_id);
+        previous = Some((font_index, glyph_id));
+    }
+
+    (caret.ceil().max(0.0) as u32, height)
+}
+
+// Measure the pixel size (width, height) of `text` at the given scale
+/// Function: explain its purpose and key arguments
+pub fn measure_text(text: &str, scale: f32) -> (u32, u32) {
+    layout_text(text, scale, |_, _, _| {})
+}
+
+// Blend `color` into the pixel at (x, y) with the given anti-aliasing coverage
+/// Function: explain its purpose and key arguments
+fn blend_pixel(img: &mut image::RgbImage, x: i64, y: i64, color: image::Rgb<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x as u32 >= img.width() || y as u32 >= img.height() {
+        return;
+    }
+// Variable declaration
+    let alpha = coverage.clamp(0.0, 1.0);
+    if alpha <= 0.0 {
+        return;
+    }
+// Variable declaration
+    let pixel = img.get_pixel_mut(x as u32, y as u32);
+    for channel in 0..3 {
+        pixel[channel] = (pixel[channel] as f32 * (1.0 - alpha) + color[channel] as f32 * alpha)
+            .round() as u8;
     }
 }
 
+// Draw anti-aliased text with its top-left corner at (x, y)
 /// Function: explain its purpose and key arguments
 fn draw_text_scaled(
     img: &mut image::RgbImage,
@@ -550,14 +3998,33 @@ fn draw_text_scaled(
     text: &str,
     color: image::Rgb<u8>,
     scale: f32,
+) {
+    layout_text(text, scale, |px, py, coverage| {
+        blend_pixel(img, x as i64 + px as i64, y as i64 + py as i64, color, coverage);
+    });
+}
+
+// Draw text rotated 90 degrees counter-clockwise (reading bottom to top), e.g. Y-axis titles
+/// Function: explain its purpose and key arguments
+fn draw_text_rotated(
+    img: &mut image::RgbImage,
+    x: u32,
+    y: u32,
+    text: &str,
+    color: image::Rgb<u8>,
+    scale: f32,
 ) {
 // Variable declaration
-    let char_width = (6.0 * scale) as u32;
-    for (i, ch) in text.chars().enumerate() {
-// Variable declaration
-        let char_x = x + (i as u32 * char_width);
-        draw_char_pixels_scaled(img, char_x, y, ch, color, scale);
-    }
+    let (text_width, _) = measure_text(text, scale);
+    layout_text(text, scale, |px, py, coverage| {
+        blend_pixel(
+            img,
+            x as i64 + py as i64,
+            y as i64 + text_width as i64 - 1 - px as i64,
+            color,
+            coverage,
+        );
+    });
 }
 
 // Original single-plot export function (backward compatibility)
@@ -576,6 +4043,10 @@ pub fn export_plot_as_png_with_config(
     subplot.config.show_grid = show_grid;
     
     if let Some(config) = axis_config {
+        subplot.config.x_scale = config.x_scale.kind;
+        subplot.config.y_scale = config.y_scale.kind;
+        subplot.config.reverse_x = config.x_scale.reversed;
+        subplot.config.reverse_y = config.y_scale.reversed;
         subplot.config.use_custom_bounds = true;
         subplot.config.custom_x_min = config.x_min.map_or(String::new(), |v| v.to_string());
         subplot.config.custom_x_max = config.x_max.map_or(String::new(), |v| v.to_string());
@@ -601,23 +4072,37 @@ pub fn export_plot_as_png_with_config(
         }
     }
     
-    export_subplots_as_png(&[subplot], &SubplotLayout::Single, dark_mode, font_size)
+    match pick_figure_save_path("subplots", ExportFormat::Png) {
+        Some(path) => export_subplots_to_path(&path, &[subplot], &SubplotLayout::default(), dark_mode, font_size, &ExportSettings::default()),
+        None => Ok(()),
+    }
 }
 
 /// Function: explain its purpose and key arguments
-pub fn calculate_custom_bounds(datasets: &[Dataset], config: &AxisConfig) -> Result<(f64, f64, f64, f64), Box<dyn std::error::Error>> {
-// Variable declaration
-    let (data_min_x, data_max_x, data_min_y, data_max_y) = get_data_bounds(datasets)
-        .ok_or("No data available")?;
+pub fn calculate_custom_bounds(
+    datasets: &[impl Borrow<Dataset>],
+    heatmaps: &[Heatmap],
+    config: &AxisConfig,
+) -> Result<(f64, f64, f64, f64), Box<dyn std::error::Error>> {
+// Variable declaration
+    let (data_min_x, data_max_x, data_min_y, data_max_y) =
+        get_scaled_bounds_with_heatmaps(datasets, heatmaps, config.x_scale, config.y_scale)
+            .ok_or("No data available")?;
 
+    // Limits are given as data values; ones a log axis cannot show fall back to the data.
+    // On a reversed axis the smallest value is at the largest plot coordinate.
 // Variable declaration
-    let base_min_x = config.x_min.unwrap_or(data_min_x);
+    let limits = |min: Option<f64>, max: Option<f64>, scale: AxisScale, (data_min, data_max): (f64, f64)| {
 // Variable declaration
-    let base_max_x = config.x_max.unwrap_or(data_max_x);
+        let (low, high) = if scale.reversed { (data_max, data_min) } else { (data_min, data_max) };
+// Variable declaration
+        let (a, b) = (min.and_then(|v| scale.apply(v)).unwrap_or(low), max.and_then(|v| scale.apply(v)).unwrap_or(high));
+        (a.min(b), a.max(b))
+    };
 // Variable declaration
-    let base_min_y = config.y_min.unwrap_or(data_min_y);
+    let (base_min_x, base_max_x) = limits(config.x_min, config.x_max, config.x_scale, (data_min_x, data_max_x));
 // Variable declaration
-    let base_max_y = config.y_max.unwrap_or(data_max_y);
+    let (base_min_y, base_max_y) = limits(config.y_min, config.y_max, config.y_scale, (data_min_y, data_max_y));
 
 // Variable declaration
     let x_range = base_max_x - base_min_x;
@@ -638,14 +4123,19 @@ pub fn calculate_custom_bounds(datasets: &[Dataset], config: &AxisConfig) -> Res
 // Variable declaration
     let max_y = base_max_y + y_padding;
 
-    Ok((min_x, max_x, min_y, max_y))
+    Ok(with_plot_margin((min_x, max_x, min_y, max_y)))
 }
 
 /// Function: explain its purpose and key arguments
-pub fn calculate_auto_bounds(datasets: &[Dataset]) -> (f64, f64, f64, f64) {
-// Variable declaration
-    let (mut min_x, mut max_x, mut min_y, mut max_y) = get_data_bounds(datasets)
-        .unwrap_or((0.0, 1.0, 0.0, 1.0));
+pub fn calculate_auto_bounds(
+    datasets: &[impl Borrow<Dataset>],
+    heatmaps: &[Heatmap],
+    x_scale: AxisScale,
+    y_scale: AxisScale,
+) -> (f64, f64, f64, f64) {
+// Variable declaration
+    let (mut min_x, mut max_x, mut min_y, mut max_y) =
+        get_scaled_bounds_with_heatmaps(datasets, heatmaps, x_scale, y_scale).unwrap_or((0.0, 1.0, 0.0, 1.0));
 
     if (max_x - min_x).abs() < f64::EPSILON {
 // Variable declaration
@@ -673,16 +4163,38 @@ pub fn calculate_auto_bounds(datasets: &[Dataset]) -> (f64, f64, f64, f64) {
 // Variable declaration
     let y_padding = y_range * padding_percent;
 
+    with_plot_margin((min_x - x_padding, max_x + x_padding, min_y - y_padding, max_y + y_padding))
+}
+
+// The interactive plot widens fitted bounds by this fraction of their size on each side
+const PLOT_MARGIN_FRACTION: f64 = 0.05;
+
+/// Function: explain its purpose and key arguments
+fn with_plot_margin((min_x, max_x, min_y, max_y): (f64, f64, f64, f64)) -> (f64, f64, f64, f64) {
 // Variable declaration
-    let padded_min_x = min_x - x_padding;
+    let x_margin = (max_x - min_x).max(0.0) * PLOT_MARGIN_FRACTION;
 // Variable declaration
-    let padded_min_y = if min_y > 0.0 {
-        (min_y - y_padding).max(0.0)
-    } else {
-        min_y - y_padding
-    };
+    let y_margin = (max_y - min_y).max(0.0) * PLOT_MARGIN_FRACTION;
+    (min_x - x_margin, max_x + x_margin, min_y - y_margin, max_y + y_margin)
+}
 
-    (padded_min_x, max_x + x_padding, padded_min_y, max_y + y_padding)
+// Bounds grown along one axis so that one plot unit spans as many pixels along X as along Y
+// over a plot area of `size` pixels, as the interactive plot keeps them when the subplot
+// has an equal aspect ratio. Both axes stay centred on the same point.
+/// Function: explain its purpose and key arguments
+pub fn with_equal_aspect((min_x, max_x, min_y, max_y): (f64, f64, f64, f64), (width, height): (u32, u32)) -> (f64, f64, f64, f64) {
+// Variable declaration
+    let (x_per_px, y_per_px) = ((max_x - min_x) / width as f64, (max_y - min_y) / height as f64);
+    if !(x_per_px > 0.0 && y_per_px > 0.0 && x_per_px.is_finite() && y_per_px.is_finite()) {
+        return (min_x, max_x, min_y, max_y);
+    }
+// Variable declaration
+    let per_px = x_per_px.max(y_per_px);
+// Variable declaration
+    let (center_x, center_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+// Variable declaration
+    let (half_x, half_y) = (per_px * width as f64 / 2.0, per_px * height as f64 / 2.0);
+    (center_x - half_x, center_x + half_x, center_y - half_y, center_y + half_y)
 }
 
 // Enhanced axis label drawing with custom ticks and font size support
@@ -741,9 +4253,7 @@ pub fn draw_axis_labels_with_custom_ticks_and_font(
 // Variable declaration
         let text = format_number(tick_value);
 // Variable declaration
-        let char_width = (6.0 * font_scale) as u32;
-// Variable declaration
-        let text_width = text.len() as u32 * char_width;
+        let (text_width, _) = measure_text(&text, font_scale);
 // Variable declaration
         let label_x = if x_pos >= text_width / 2 {
             x_pos - text_width / 2
@@ -751,7 +4261,7 @@ pub fn draw_axis_labels_with_custom_ticks_and_font(
             0
         };
         
-        draw_number_pixels_scaled(img, label_x, tick_y + 20, tick_value, color, font_scale);
+        draw_text_scaled(img, label_x, tick_y + 20, &text, color, font_scale);
     }
 
     // Y-axis ticks and labels
@@ -789,9 +4299,7 @@ pub fn draw_axis_labels_with_custom_ticks_and_font(
 // Variable declaration
         let text = format_number(tick_value);
 // Variable declaration
-        let char_width = (6.0 * font_scale) as u32;
-// Variable declaration
-        let text_width = text.len() as u32 * char_width;
+        let (text_width, text_height) = measure_text(&text, font_scale);
 // Variable declaration
         let label_x = if tick_x >= text_width + 15 {
             tick_x - text_width - 15
@@ -800,11 +4308,9 @@ pub fn draw_axis_labels_with_custom_ticks_and_font(
         };
         
 // Variable declaration
-        let char_height = (7.0 * font_scale) as u32;
-// Variable declaration
-        let label_y = y_pos.saturating_sub(char_height / 2);
+        let label_y = y_pos.saturating_sub(text_height / 2);
         
-        draw_number_pixels_scaled(img, label_x, label_y, tick_value, color, font_scale);
+        draw_text_scaled(img, label_x, label_y, &text, color, font_scale);
     }
 }
 
@@ -833,121 +4339,196 @@ pub fn draw_axis_labels_with_custom_ticks(
     );
 }
 
-// New function with font scaling support
 /// Function: explain its purpose and key arguments
-pub fn draw_number_pixels_scaled(
+pub fn draw_thick_line(
     img: &mut image::RgbImage,
-    x: u32,
-    y: u32,
-    value: f64,
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
     color: image::Rgb<u8>,
-    scale: f32,
+    thickness: u32,
 ) {
+    for offset in 0..thickness {
 // Variable declaration
-    let text = format_number(value);
-// Variable declaration
-    let char_width = (6.0 * scale) as u32;
-    for (i, ch) in text.chars().enumerate() {
-// Variable declaration
-        let char_x = x + (i as u32 * char_width);
-        draw_char_pixels_scaled(img, char_x, y, ch, color, scale);
+        let offset = offset as i32 - (thickness as i32 / 2);
+        draw_line_offset(img, x0, y0, x1, y1, color, offset, 0);
+        if offset != 0 {
+            draw_line_offset(img, x0, y0, x1, y1, color, 0, offset);
+        }
     }
 }
 
+// Draw a thick line as alternating on/off runs. The phase carries over between
+// consecutive segments so the pattern stays continuous along a polyline.
 /// Function: explain its purpose and key arguments
-pub fn draw_char_pixels_scaled(
-    img: &mut image::RgbImage, 
-    x: u32, 
-    y: u32, 
-    ch: char, 
+pub fn draw_dashed_line(
+    img: &mut image::RgbImage,
+    start: (u32, u32),
+    end: (u32, u32),
     color: image::Rgb<u8>,
-    scale: f32
+    thickness: u32,
+    (on, off): (f64, f64),
+    phase: &mut f64,
 ) {
 // Variable declaration
-    let pattern = match ch {
-        '0' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
-        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
-        '2' => [0b01110, 0b10001, 0b00001, 0b00110, 0b01000, 0b10000, 0b11111],
-        '3' => [0b11111, 0b00010, 0b00100, 0b00110, 0b00001, 0b10001, 0b01110],
-        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
-        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
-        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
-        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
-        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
-        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
-        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
-        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
-        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
-        'M' => [0b10001, 0b11011, 0b10101, 0b10001, 0b10001, 0b10001, 0b10001],
-        'e' => [0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b10001, 0b01110],
-        ' ' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
-        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
-        'S' => [0b01110, 0b10001, 0b10000, 0b01110, 0b00001, 0b10001, 0b01110],
-        'u' => [0b00000, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01111],
-        'b' => [0b10000, 0b10000, 0b11110, 0b10001, 0b10001, 0b10001, 0b11110],
-        'p' => [0b00000, 0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000],
-        'l' => [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
-        'o' => [0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
-        't' => [0b00100, 0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00011],
-        _ => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+    let dx = end.0 as f64 - start.0 as f64;
+// Variable declaration
+    let dy = end.1 as f64 - start.1 as f64;
+// Variable declaration
+    let length = (dx * dx + dy * dy).sqrt();
+// Variable declaration
+    let period = on + off;
+    if length == 0.0 || period <= 0.0 {
+        return;
+    }
+
+// Variable declaration
+    let point_at = |t: f64| {
+        (
+            (start.0 as f64 + dx * t / length).round() as u32,
+            (start.1 as f64 + dy * t / length).round() as u32,
+        )
     };
 
 // Variable declaration
-    let pixel_size = scale.max(1.0) as u32;
+    let mut t = 0.0;
+    while t < length {
+// Variable declaration
+        let pos = (*phase + t) % period;
+        if pos < on {
+// Variable declaration
+            let run_end = (t + on - pos).min(length);
+// Variable declaration
+            let (x0, y0) = point_at(t);
+// Variable declaration
+            let (x1, y1) = point_at(run_end);
+            draw_thick_line(img, x0, y0, x1, y1, color, thickness);
+            t = run_end;
+        } else {
+            t += period - pos;
+        }
+    }
+    *phase = (*phase + length) % period;
+}
 
-    for (row, &pattern_row) in pattern.iter().enumerate() {
-        for col in 0..5 {
-            if (pattern_row >> (4 - col)) & 1 == 1 {
-                // Draw scaled pixel as a block
-                for dy in 0..pixel_size {
-                    for dx in 0..pixel_size {
+// Draw a histogram bar between two opposite pixel corners: translucent fill with a solid
+// outline `outline` pixels wide
+/// Function: explain its purpose and key arguments
+fn draw_bar(
+    img: &mut image::RgbImage,
+    corner: (i64, i64),
+    opposite: (i64, i64),
+    color: image::Rgb<u8>,
+    outline: i64,
+) {
 // Variable declaration
-                        let px = x + (col * pixel_size) + dx;
+    let (x0, x1) = (corner.0.min(opposite.0), corner.0.max(opposite.0));
 // Variable declaration
-                        let py = y + (row as u32 * pixel_size) + dy;
-                        if px < img.width() && py < img.height() {
-                            img.put_pixel(px, py, color);
-                        }
-                    }
-                }
-            }
+    let (y0, y1) = (corner.1.min(opposite.1), corner.1.max(opposite.1));
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+// Variable declaration
+            let edge = x < x0 + outline || x + outline > x1 || y < y0 + outline || y + outline > y1;
+            blend_pixel(img, x, y, color, if edge { 1.0 } else { 0.35 });
         }
     }
 }
 
-// Keep the original functions for backward compatibility
+// Draw an error bar between two pixel positions with short caps at both ends; `cap` is
+// how far the caps reach either side of the bar and `thickness` the width of its lines
 /// Function: explain its purpose and key arguments
-pub fn draw_number_pixels(
+fn draw_error_bar(
     img: &mut image::RgbImage,
-    x: u32,
-    y: u32,
-    value: f64,
+    start: (i64, i64),
+    end: (i64, i64),
     color: image::Rgb<u8>,
+    (cap, thickness): (i64, i64),
 ) {
-    draw_number_pixels_scaled(img, x, y, value, color, 1.0);
+// Variable declaration
+    let vertical = start.0 == end.0;
+// Variable declaration
+    // One pixel of a line running along X (`horizontal`) or Y, widened across it to `thickness`
+// Variable declaration
+    let mut put = |x: i64, y: i64, horizontal: bool| {
+        for offset in 0..thickness {
+// Variable declaration
+            let across = offset - thickness / 2;
+// Variable declaration
+            let (x, y) = if horizontal { (x, y + across) } else { (x + across, y) };
+            if x >= 0 && y >= 0 && (x as u32) < img.width() && (y as u32) < img.height() {
+                img.put_pixel(x as u32, y as u32, color);
+            }
+        }
+    };
+
+    if vertical {
+        for y in start.1.min(end.1)..=start.1.max(end.1) {
+            put(start.0, y, false);
+        }
+        for dx in -cap..=cap {
+            put(start.0 + dx, start.1, true);
+            put(start.0 + dx, end.1, true);
+        }
+    } else {
+        for x in start.0.min(end.0)..=start.0.max(end.0) {
+            put(x, start.1, true);
+        }
+        for dy in -cap..=cap {
+            put(start.0, start.1 + dy, false);
+            put(end.0, start.1 + dy, false);
+        }
+    }
 }
 
+// Largest marker radius drawn in legend entries, in pixels
+pub const LEGEND_MARKER_MAX_SIZE: f32 = 5.0;
+
+// Marker of the given shape and radius in pixels, centred on (cx, cy)
 /// Function: explain its purpose and key arguments
-pub fn draw_char_pixels(img: &mut image::RgbImage, x: u32, y: u32, ch: char, color: image::Rgb<u8>) {
-    draw_char_pixels_scaled(img, x, y, ch, color, 1.0);
+pub fn draw_marker(img: &mut image::RgbImage, (cx, cy): (u32, u32), marker: MarkerStyle, radius: f32, color: image::Rgb<u8>) {
+// Variable declaration
+    let r = radius.round().max(1.0) as i32;
+// Variable declaration
+    let inside = |dx: i32, dy: i32| match marker {
+        MarkerStyle::Circle => dx * dx + dy * dy <= r * r,
+        MarkerStyle::Square => true,
+        MarkerStyle::Triangle => 2 * dx.abs() <= dy + r,
+        MarkerStyle::Diamond => dx.abs() + dy.abs() <= r,
+        MarkerStyle::Cross => (dx.abs() - dy.abs()).abs() <= r / 3,
+    };
+    for dy in -r..=r {
+        for dx in (-r..=r).filter(|&dx| inside(dx, dy)) {
+// Variable declaration
+            let (px, py) = (cx as i32 + dx, cy as i32 + dy);
+            if px >= 0 && py >= 0 && (px as u32) < img.width() && (py as u32) < img.height() {
+                img.put_pixel(px as u32, py as u32, color);
+            }
+        }
+    }
 }
 
 /// Function: explain its purpose and key arguments
-pub fn draw_thick_line(
+pub fn draw_filled_circle(
     img: &mut image::RgbImage,
-    x0: u32,
-    y0: u32,
-    x1: u32,
-    y1: u32,
+    cx: u32,
+    cy: u32,
+    radius: u32,
     color: image::Rgb<u8>,
-    thickness: u32,
 ) {
-    for offset in 0..thickness {
 // Variable declaration
-        let offset = offset as i32 - (thickness as i32 / 2);
-        draw_line_offset(img, x0, y0, x1, y1, color, offset, 0);
-        if offset != 0 {
-            draw_line_offset(img, x0, y0, x1, y1, color, 0, offset);
+    let r = radius as i32;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            if dx * dx + dy * dy <= r * r {
+// Variable declaration
+                let px = cx as i32 + dx;
+// Variable declaration
+                let py = cy as i32 + dy;
+                if px >= 0 && py >= 0 && (px as u32) < img.width() && (py as u32) < img.height() {
+                    img.put_pixel(px as u32, py as u32, color);
+                }
+            }
         }
     }
 }
@@ -1005,36 +4586,497 @@ pub fn draw_line_offset(
     }
 }
 
+/// Data structure used in utils.rs module
+pub struct LoadedSeries {
+    pub name: Option<String>, // Column header or XVG legend, if the file provides one
+    pub points: Vec<[f64; 2]>,
+    pub x_err: Option<Vec<f64>>,
+    pub y_err: Option<Vec<f64>>,
+    pub color_values: Option<Vec<f64>>, // Values of a chosen color column, NaN where missing
+}
+
+/// Implementation block defining methods for this type
+impl LoadedSeries {
+    // Build a dataset from the loaded series, keeping any uncertainties; a series with
+    // color values starts out colored by them
+/// Function: explain its purpose and key arguments
+    pub fn into_dataset(self, name: String, color: [u8; 3]) -> Dataset {
+// Variable declaration
+        let mut dataset = Dataset::new(name, self.points, color);
+        dataset.x_err = self.x_err;
+        dataset.y_err = self.y_err;
+        if self.color_values.is_some() {
+            dataset.color_by = Some(ColorBy::Values);
+        }
+        dataset.color_values = self.color_values;
+        dataset
+    }
+
+    // Whether the series has any point to draw, as opposed to only missing values
+/// Function: explain its purpose and key arguments
+    pub fn has_values(&self) -> bool {
+        self.points.iter().any(|p| p[1].is_finite())
+    }
+}
+
+// Plot labels some formats (e.g. GROMACS XVG) carry alongside the data
+#[derive(Debug, Clone, Default)]
+/// Data structure used in utils.rs module
+pub struct FileMetadata {
+    pub title: Option<String>,
+    pub x_label: Option<String>,
+    pub y_label: Option<String>,
+    pub x_is_time: bool, // X was written as dates and times
+    pub warnings: Vec<String>, // Parts of the file that were skipped, e.g. unreadable arrays
+}
+
+/// Implementation block defining methods for this type
+impl FileMetadata {
+    // Fill in subplot title and axis labels that the user has not set yet
+/// Function: explain its purpose and key arguments
+    pub fn apply_to(&self, config: &mut SubplotConfig) {
+        if let (true, Some(title)) = (config.title.is_empty(), &self.title) {
+            config.title = title.clone();
+        }
+        if let (true, Some(x_label)) = (config.x_label.is_empty(), &self.x_label) {
+            config.x_label = x_label.clone();
+        }
+        if let (true, Some(y_label)) = (config.y_label.is_empty(), &self.y_label) {
+            config.y_label = y_label.clone();
+        }
+        if self.x_is_time && config.x_scale == ScaleKind::Linear {
+            config.x_scale = ScaleKind::Time;
+        }
+    }
+}
+
+/// Data structure used in utils.rs module
+pub struct LoadedFile {
+    pub series: Vec<LoadedSeries>,
+    pub metadata: FileMetadata,
+}
+
+// Turn the series loaded from one file into datasets. A single series keeps the file
+// name; multiple series are named after their header/legend (or column number).
+/// Function: explain its purpose and key arguments
+pub fn datasets_from_series(
+    series: Vec<LoadedSeries>,
+    file_name: &str,
+    palette: Palette,
+    first_color_index: usize,
+) -> Vec<Dataset> {
+// Variable declaration
+    let single = series.len() == 1;
+    series
+        .into_iter()
+        .enumerate()
+        .map(|(i, s)| {
+// Variable declaration
+            let name = if single {
+                file_name.to_string()
+            } else {
+                match &s.name {
+                    Some(series_name) if !series_name.trim().is_empty() => series_name.trim().to_string(),
+                    _ => format!("{}_y{}", file_name, i + 1),
+                }
+            };
+            s.into_dataset(name, palette.color(first_color_index + i))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enum representing a set of related values in utils.rs module
+enum ErrorColumn {
+    X,
+    Y,
+}
+
+// Which columns of a table make up one Y series
+#[derive(Debug, Clone)]
+/// Data structure used in utils.rs module
+pub struct SeriesColumns {
+    pub name: Option<String>,
+    pub y_col: usize,
+    pub x_err_col: Option<usize>,
+    pub y_err_col: Option<usize>,
+    pub color_col: Option<usize>, // Column whose values color the line (import preview only)
+}
+
+/// Implementation block defining methods for this type
+impl SeriesColumns {
+/// Function: explain its purpose and key arguments
+    pub fn new(y_col: usize, name: Option<String>) -> Self {
+        Self {
+            name,
+            y_col,
+            x_err_col: None,
+            y_err_col: None,
+            color_col: None,
+        }
+    }
+
+/// Function: explain its purpose and key arguments
+    fn attach_error(&mut self, col: usize, kind: ErrorColumn) {
+        match kind {
+            ErrorColumn::X => self.x_err_col = Some(col),
+            ErrorColumn::Y => self.y_err_col = Some(col),
+        }
+    }
+}
+
+// Recognise uncertainty columns by their header name (e.g. "dy", "y_err", "std")
+/// Function: explain its purpose and key arguments
+fn error_column_from_header(header: &str) -> Option<ErrorColumn> {
+// Variable declaration
+    let name = header.trim().to_lowercase();
+    if name == "dx" || name.contains("xerr") || name.contains("x_err") || name.contains("x err") {
+        Some(ErrorColumn::X)
+    } else if name == "dy"
+        || name.contains("err")
+        || name == "sd"
+        || name.contains("std")
+        || name.contains("sem")
+        || name.contains("uncert")
+    {
+        Some(ErrorColumn::Y)
+    } else {
+        None
+    }
+}
+
+// Work out the series in an XVG table from its "@TYPE" directive and column count
+/// Function: explain its purpose and key arguments
+fn series_columns_from_xvg_type(xvg_type: &str, num_cols: usize) -> Vec<SeriesColumns> {
+// Variable declaration
+    let mut first = SeriesColumns::new(1, None);
+// Variable declaration
+    let first_free_col = match xvg_type {
+        "xydy" => {
+            first.attach_error(2, ErrorColumn::Y);
+            3
+        }
+        "xydx" => {
+            first.attach_error(2, ErrorColumn::X);
+            3
+        }
+        "xydxdy" => {
+            first.attach_error(2, ErrorColumn::X);
+            first.attach_error(3, ErrorColumn::Y);
+            4
+        }
+        _ => 2,
+    };
+
+// Variable declaration
+    let mut columns = vec![first];
+    for col in first_free_col..num_cols {
+        columns.push(SeriesColumns::new(col, None));
+    }
+    columns
+}
+
+// Work out the series in a CSV table from its header row. Error-like headers
+// ("dy", "rmsd_err", "std", ...) attach to the series just before them.
+/// Function: explain its purpose and key arguments
+pub fn series_columns_from_headers(headers: &[String], num_cols: usize) -> Vec<SeriesColumns> {
+// Variable declaration
+    let mut columns: Vec<SeriesColumns> = Vec::new();
+    for col in 1..num_cols {
+// Variable declaration
+        let header = headers.get(col).cloned();
+// Variable declaration
+        let error_kind = header.as_deref().and_then(error_column_from_header);
+        match (error_kind, columns.last_mut()) {
+            (Some(kind), Some(previous)) => previous.attach_error(col, kind),
+            _ => columns.push(SeriesColumns::new(col, header)),
+        }
+    }
+    columns
+}
+
+// Collect x/y points plus any error columns for each series from already-split rows,
+// with X taken from `x_col`, either numbers or ISO-8601 dates (read as seconds since the
+// epoch). Rows without a usable X are dropped; a blank or unreadable Y becomes a NaN point
+// so the series' line breaks there.
+/// Function: explain its purpose and key arguments
+pub fn collect_series(rows: &[Vec<&str>], x_col: usize, columns: &[SeriesColumns]) -> Vec<LoadedSeries> {
+// Variable declaration
+    let parse = |row: &Vec<&str>, col: usize| row.get(col).and_then(|v| v.trim().parse::<f64>().ok());
+// Variable declaration
+    let parse_x = |row: &Vec<&str>| row.get(x_col).and_then(|v| parse_number_or_timestamp(v));
+
+    columns
+        .iter()
+        .map(|series| {
+// Variable declaration
+            let mut points = Vec::new();
+// Variable declaration
+            let mut x_err = Vec::new();
+// Variable declaration
+            let mut y_err = Vec::new();
+// Variable declaration
+            let mut color_values = Vec::new();
+
+            for row in rows {
+                if let Some(x) = parse_x(row) {
+                    points.push([x, parse(row, series.y_col).unwrap_or(f64::NAN)]);
+                    if let Some(col) = series.x_err_col {
+                        x_err.push(parse(row, col).map_or(0.0, f64::abs));
+                    }
+                    if let Some(col) = series.y_err_col {
+                        y_err.push(parse(row, col).map_or(0.0, f64::abs));
+                    }
+                    if let Some(col) = series.color_col {
+                        color_values.push(parse(row, col).unwrap_or(f64::NAN));
+                    }
+                }
+            }
+
+            LoadedSeries {
+                name: series.name.clone(),
+                points,
+                x_err: series.x_err_col.map(|_| x_err),
+                y_err: series.y_err_col.map(|_| y_err),
+                color_values: series.color_col.map(|_| color_values),
+            }
+        })
+        .collect()
+}
+
 /// Function: explain its purpose and key arguments
-pub fn load_csv_points(path: &PathBuf) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+pub fn load_csv_points(path: &Path) -> Result<LoadedFile, Box<dyn std::error::Error>> {
 // Variable declaration
-    let mut rdr = csv::Reader::from_path(path)?;
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(open_data(path)?);
+
 // Variable declaration
-    let mut out = Vec::new();
+    let mut records = Vec::new();
     for result in rdr.records() {
+        records.push(result?);
+    }
+
+    // The first row is a header unless it is entirely numeric (dates count as numbers)
+// Variable declaration
+    let has_header = records
+        .first()
+        .is_some_and(|first| first.iter().any(|field| parse_number_or_timestamp(field).is_none()));
+// Variable declaration
+    let headers: Vec<String> = if has_header {
+        records[0].iter().map(|h| h.trim().to_string()).collect()
+    } else {
+        Vec::new()
+    };
+// Variable declaration
+    let data = if has_header { &records[1..] } else { &records[..] };
+
+// Variable declaration
+    let num_cols = data.iter().map(|r| r.len()).max().unwrap_or(0).max(headers.len());
+    if num_cols < 2 {
+        return Err("Need at least two columns (X and Y)".into());
+    }
+
+// Variable declaration
+    let rows: Vec<Vec<&str>> = data.iter().map(|record| record.iter().collect()).collect();
+    Ok(LoadedFile {
+        series: collect_series(&rows, 0, &series_columns_from_headers(&headers, num_cols)),
+        metadata: FileMetadata {
+            x_is_time: column_has_timestamps(&rows, 0),
+            ..FileMetadata::default()
+        },
+    })
+}
+
+// Series in text pasted from the clipboard, split like the Data Editor's paste buffer.
+// The first row is a header unless it is entirely numeric. A single column is plotted
+// against the row number; otherwise the first column is X and the others are Y series.
+/// Function: explain its purpose and key arguments
+pub fn series_from_pasted_text(text: &str) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let lines: Vec<Vec<&str>> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(split_pasted_line)
+        .collect();
+// Variable declaration
+    let has_header = lines
+        .first()
+        .is_some_and(|first| first.iter().any(|field| parse_number_or_timestamp(field).is_none()));
 // Variable declaration
-        let record = result?;
-        if record.len() < 2 {
+    let mut headers: Vec<String> = if has_header {
+        lines[0].iter().map(|h| h.trim().to_string()).collect()
+    } else {
+        Vec::new()
+    };
+// Variable declaration
+    let data = if has_header { &lines[1..] } else { &lines[..] };
+    if data.is_empty() {
+        return Err("The clipboard holds no rows of data".into());
+    }
+// Variable declaration
+    let num_cols = data.iter().map(|r| r.len()).max().unwrap_or(0).max(headers.len());
+// Variable declaration
+    let row_numbers: Vec<String> = (1..=data.len()).map(|i| i.to_string()).collect();
+// Variable declaration
+    let (rows, num_cols): (Vec<Vec<&str>>, usize) = if num_cols == 1 {
+        if has_header {
+            headers.insert(0, "row".to_string());
+        }
+        (
+            data.iter().zip(&row_numbers).map(|(row, number)| vec![number.as_str(), row[0]]).collect(),
+            2,
+        )
+    } else {
+        (data.to_vec(), num_cols)
+    };
+// Variable declaration
+    let series: Vec<LoadedSeries> = collect_series(&rows, 0, &series_columns_from_headers(&headers, num_cols))
+        .into_iter()
+        .filter(LoadedSeries::has_values)
+        .collect();
+    if series.is_empty() {
+        return Err("The clipboard holds no numeric data".into());
+    }
+    Ok(series)
+}
+
+// Helper function to load JSON data in either of two layouts:
+//   array of rows:    [[x, y], [x, y, y2, ...], ...]
+//   object of arrays: {"x": [...], "y": [...], "dy": [...], ...}
+// In the object layout every key other than "x" is a Y series, except error-like keys
+// ("dy", "rmsd_err", ...), which attach to the series they name or to the only series.
+/// Function: explain its purpose and key arguments
+pub fn load_json_points(path: &Path) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let contents = read_data_to_string(path)?;
+    match serde_json::from_str::<serde_json::Value>(&contents)? {
+        serde_json::Value::Array(rows) => json_rows_to_series(&rows),
+        serde_json::Value::Object(columns) => json_columns_to_series(&columns),
+        _ => Err("Expected an array of [x, y] rows or an object of column arrays".into()),
+    }
+}
+
+// Numbers are passed on as text so JSON tables go through the same parsing as CSV rows
+/// Function: explain its purpose and key arguments
+fn json_number_text(value: &serde_json::Value) -> String {
+    value.as_f64().map_or_else(String::new, |v| v.to_string())
+}
+
+/// Function: explain its purpose and key arguments
+fn json_rows_to_series(rows: &[serde_json::Value]) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut table: Vec<Vec<String>> = Vec::with_capacity(rows.len());
+    for row in rows {
+// Variable declaration
+        let Some(values) = row.as_array() else {
+            return Err("Every row must be an array like [x, y]".into());
+        };
+        table.push(values.iter().map(json_number_text).collect());
+    }
+
+// Variable declaration
+    let num_cols = table.iter().map(|r| r.len()).max().unwrap_or(0);
+    if num_cols < 2 {
+        return Err("Need at least two columns (X and Y)".into());
+    }
+
+// Variable declaration
+    let rows: Vec<Vec<&str>> = table.iter().map(|r| r.iter().map(String::as_str).collect()).collect();
+    Ok(collect_series(&rows, 0, &series_columns_from_headers(&[], num_cols)))
+}
+
+/// Function: explain its purpose and key arguments
+fn json_columns_to_series(
+    columns: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+// Variable declaration
+    let column = |key: &str| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        match &columns[key] {
+            serde_json::Value::Array(values) => Ok(values.iter().map(json_number_text).collect()),
+            _ => Err(format!("Column \"{}\" is not an array", key).into()),
+        }
+    };
+
+// Variable declaration
+    let x_key = columns
+        .keys()
+        .find(|key| key.trim().eq_ignore_ascii_case("x"))
+        .ok_or("Missing \"x\" column")?;
+
+    // Table column 0 is X; the other keys follow in file order
+// Variable declaration
+    let mut table_columns = vec![column(x_key)?];
+// Variable declaration
+    let mut series: Vec<SeriesColumns> = Vec::new();
+// Variable declaration
+    let mut errors: Vec<(String, usize, ErrorColumn)> = Vec::new();
+    for key in columns.keys().filter(|key| *key != x_key) {
+        table_columns.push(column(key)?);
+// Variable declaration
+        let col = table_columns.len() - 1;
+        match error_column_from_header(key) {
+            Some(kind) => errors.push((key.trim().to_lowercase(), col, kind)),
+            None => series.push(SeriesColumns::new(col, Some(key.clone()))),
+        }
+    }
+    if series.is_empty() {
+        return Err("Need at least one Y column besides \"x\"".into());
+    }
+
+    // X errors apply to every series; Y errors go to the only series, or else to the
+    // series with the longest name the error key starts with ("y2_err" -> "y2")
+    for (key, col, kind) in errors {
+        if kind == ErrorColumn::X {
+            series.iter_mut().for_each(|s| s.attach_error(col, kind));
             continue;
         }
-        if let (Ok(x), Ok(y)) = (
-            record.get(0).unwrap().trim().parse::<f64>(),
-            record.get(1).unwrap().trim().parse::<f64>(),
-        ) {
-            out.push([x, y]);
+// Variable declaration
+        let single = series.len() == 1;
+// Variable declaration
+        let target = series
+            .iter_mut()
+            .filter(|s| {
+                single || key.starts_with(&s.name.as_deref().unwrap_or("").trim().to_lowercase())
+            })
+            .max_by_key(|s| s.name.as_deref().map_or(0, |name| name.trim().len()));
+        if let Some(target) = target {
+            target.attach_error(col, kind);
         }
     }
-    Ok(out)
+
+// Variable declaration
+    let row_count = table_columns[0].len();
+// Variable declaration
+    let rows: Vec<Vec<&str>> = (0..row_count)
+        .map(|i| {
+            table_columns
+                .iter()
+                .map(|values| values.get(i).map_or("", String::as_str))
+                .collect()
+        })
+        .collect();
+    Ok(collect_series(&rows, 0, &series))
+}
+
+/// Function: explain its purpose and key arguments
+pub fn load_xvg_with_metadata(path: &Path) -> Result<LoadedFile, Box<dyn std::error::Error>> {
+    parse_xvg(BufReader::new(open_data(path)?)).map(|(loaded, _)| loaded)
 }
 
+// Parse XVG text; also returns the columns of each series so that lines appended to
+// the file later can be read the same way
 /// Function: explain its purpose and key arguments
-pub fn load_xvg_points(path: &PathBuf) -> Result<Vec<[f64; 2]>, Box<dyn std::error::Error>> {
+pub fn parse_xvg(reader: impl BufRead) -> Result<(LoadedFile, Vec<SeriesColumns>), Box<dyn std::error::Error>> {
+// Variable declaration
+    let mut data_lines = Vec::new();
 // Variable declaration
-    let file = File::open(path)?;
+    let mut xvg_type = String::from("xy");
 // Variable declaration
-    let reader = BufReader::new(file);
+    let mut legends: Vec<(usize, String)> = Vec::new();
 // Variable declaration
-    let mut points = Vec::new();
+    let mut metadata = FileMetadata::default();
 
     for line_result in reader.lines() {
 // Variable declaration
@@ -1042,23 +5084,196 @@ pub fn load_xvg_points(path: &PathBuf) -> Result<Vec<[f64; 2]>, Box<dyn std::err
 // Variable declaration
         let line = line.trim();
 
+        if let Some(directive) = line.strip_prefix("@TYPE") {
+            xvg_type = directive.trim().to_lowercase();
+            continue;
+        }
+
+        if let Some(legend) = parse_xvg_legend(line) {
+            legends.push(legend);
+            continue;
+        }
+
+        if let Some((key, value)) = parse_xvg_label(line) {
+            match key.as_str() {
+                "title" => metadata.title = Some(value),
+                "xaxis label" => metadata.x_label = Some(value),
+                "yaxis label" => metadata.y_label = Some(value),
+                _ => {}
+            }
+            continue;
+        }
+
         if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
             continue;
         }
 
+        data_lines.push(line.to_string());
+    }
+
 // Variable declaration
-        let parts: Vec<&str> = line.split_whitespace().collect();
+    let rows: Vec<Vec<&str>> = data_lines.iter().map(|line| line.split_whitespace().collect()).collect();
+// Variable declaration
+    let num_cols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if num_cols < 2 {
+        return Err("Need at least two columns (X and Y)".into());
+    }
 
-        if parts.len() < 2 {
-            continue;
+// Variable declaration
+    let mut columns = series_columns_from_xvg_type(&xvg_type, num_cols);
+    for (set_index, legend) in legends {
+        if let Some(series) = columns.get_mut(set_index) {
+            series.name = Some(legend);
         }
+    }
+
+    Ok((
+        LoadedFile {
+            series: collect_series(&rows, 0, &columns),
+            metadata,
+        },
+        columns,
+    ))
+}
+
+// Parse an XVG label directive such as `@    xaxis  label "Time (ps)"` into
+// ("xaxis label", "Time (ps)"). Only quoted directives are considered.
+/// Function: explain its purpose and key arguments
+fn parse_xvg_label(line: &str) -> Option<(String, String)> {
+// Variable declaration
+    let rest = line.strip_prefix('@')?.trim();
+// Variable declaration
+    let quote_start = rest.find('"')?;
+// Variable declaration
+    let key = rest[..quote_start].split_whitespace().collect::<Vec<_>>().join(" ");
+// Variable declaration
+    let value = rest[quote_start..].trim().trim_matches('"');
+    Some((key, clean_xmgrace_text(value)))
+}
 
-        if let (Ok(x), Ok(y)) = (parts[0].parse::<f64>(), parts[1].parse::<f64>()) {
-            points.push([x, y]);
+// Translate the xmgrace escapes GROMACS uses (\S2\N superscripts, \xl\f{} Greek)
+// into plain Unicode text
+/// Function: explain its purpose and key arguments
+pub fn clean_xmgrace_text(text: &str) -> String {
+// Variable declaration
+    let mut out = String::new();
+// Variable declaration
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
         }
+        match chars.next() {
+            Some('S') => out.push('^'),
+            Some('s') => out.push('_'),
+            Some('N') | Some('n') => {}
+            // Font switch such as \f{} or \f{Symbol}: drop it
+            Some('f') if chars.peek() == Some(&'{') => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            // Numbered font switch such as \f0
+            Some('f') => {
+                chars.next();
+            }
+            Some('x') => {
+                if let Some(letter) = chars.next() {
+                    out.push(greek_letter(letter));
+                }
+            }
+            Some('\\') => out.push('\\'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+// Map a Symbol-font letter to its Greek equivalent (as used by xmgrace "\x")
+/// Function: explain its purpose and key arguments
+fn greek_letter(letter: char) -> char {
+    match letter {
+        'a' => 'α', 'b' => 'β', 'c' => 'χ', 'd' => 'δ', 'e' => 'ε', 'f' => 'φ',
+        'g' => 'γ', 'h' => 'η', 'i' => 'ι', 'k' => 'κ', 'l' => 'λ', 'm' => 'μ',
+        'n' => 'ν', 'p' => 'π', 'q' => 'θ', 'r' => 'ρ', 's' => 'σ', 't' => 'τ',
+        'u' => 'υ', 'w' => 'ω', 'x' => 'ξ', 'y' => 'ψ', 'z' => 'ζ',
+        'D' => 'Δ', 'F' => 'Φ', 'G' => 'Γ', 'L' => 'Λ', 'P' => 'Π', 'Q' => 'Θ',
+        'S' => 'Σ', 'W' => 'Ω', 'Y' => 'Ψ',
+        other => other,
+    }
+}
+
+// Parse a GROMACS legend line such as `@ s1 legend "Coulomb (SR)"` into (set index, text)
+/// Function: explain its purpose and key arguments
+fn parse_xvg_legend(line: &str) -> Option<(usize, String)> {
+// Variable declaration
+    let rest = line.strip_prefix('@')?.trim_start();
+// Variable declaration
+    let rest = rest.strip_prefix('s')?;
+// Variable declaration
+    let digits_end = rest.find(|c: char| !c.is_ascii_digit())?;
+// Variable declaration
+    let set_index = rest[..digits_end].parse::<usize>().ok()?;
+// Variable declaration
+    let rest = rest[digits_end..].trim_start().strip_prefix("legend")?.trim();
+    Some((set_index, clean_xmgrace_text(rest.trim_matches('"'))))
+}
+
+// Load any supported data file, picking the parser from the file extension
+/// Function: explain its purpose and key arguments
+pub fn load_data_file(path: &Path) -> Result<LoadedFile, Box<dyn std::error::Error>> {
+    check_readable(path)?;
+    match data_extension(path).as_deref() {
+        Some("csv") => load_csv_points(path).map_err(|e| format!("CSV error: {}", e).into()),
+        _ if is_text_table(path) => load_text_table(path).map_err(|e| format!("Text table error: {}", e).into()),
+        Some("xvg") => load_xvg_with_metadata(path).map_err(|e| format!("XVG error: {}", e).into()),
+        Some("json") => load_json_points(path)
+            .map(|series| LoadedFile {
+                series,
+                metadata: FileMetadata::default(),
+            })
+            .map_err(|e| format!("JSON error: {}", e).into()),
+        _ if is_spreadsheet(path) => load_spreadsheet(path)
+            .map(|series| LoadedFile {
+                series,
+                metadata: FileMetadata::default(),
+            })
+            .map_err(|e| format!("Spreadsheet error: {}", e).into()),
+        Some("npy") => load_npy(path)
+            .map(|series| LoadedFile {
+                series,
+                metadata: FileMetadata::default(),
+            })
+            .map_err(|e| format!("NumPy error: {}", e).into()),
+        Some("npz") => load_npz(path).map_err(|e| format!("NumPy error: {}", e).into()),
+        Some("parquet") | Some("pq") => load_parquet(path)
+            .map(|series| LoadedFile {
+                series,
+                metadata: FileMetadata::default(),
+            })
+            .map_err(|e| format!("Parquet error: {}", e).into()),
+        _ if is_hdf5(path) => load_hdf5(path)
+            .map(|series| LoadedFile {
+                series,
+                metadata: FileMetadata::default(),
+            })
+            .map_err(|e| format!("HDF5 error: {}", e).into()),
+        _ => Err("Unsupported file type".into()),
     }
+}
 
-    Ok(points)
+// Name used for datasets loaded from `path` (the file stem, ignoring a .gz or .xz)
+/// Function: explain its purpose and key arguments
+pub fn file_display_name(path: &Path) -> String {
+    uncompressed_path(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("unknown")
+        .to_string()
 }
 
 /// Function: explain its purpose and key arguments
@@ -1095,35 +5310,86 @@ pub fn format_number(value: f64) -> String {
     }
 }
 
-// Get default color palette
+// Color from a name ("red", "grey", ...; the named ones match the Tab10 palette) or a
+// hex code such as "#1f78b4"
 /// Function: explain its purpose and key arguments
-pub fn get_default_color(index: usize) -> [u8; 3] {
+pub fn parse_color(text: &str) -> Option<[u8; 3]> {
+// Variable declaration
+    let text = text.trim().to_lowercase();
+// Variable declaration
+    let named = match text.as_str() {
+        "blue" => Some(Palette::Tab10.color(0)),
+        "orange" => Some(Palette::Tab10.color(1)),
+        "green" => Some(Palette::Tab10.color(2)),
+        "red" => Some(Palette::Tab10.color(3)),
+        "purple" => Some(Palette::Tab10.color(4)),
+        "brown" => Some(Palette::Tab10.color(5)),
+        "pink" => Some(Palette::Tab10.color(6)),
+        "gray" | "grey" => Some(Palette::Tab10.color(7)),
+        "olive" => Some(Palette::Tab10.color(8)),
+        "cyan" => Some(Palette::Tab10.color(9)),
+        "black" => Some([0, 0, 0]),
+        "white" => Some([255, 255, 255]),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
 // Variable declaration
-    let colors = [
-        [31, 120, 180],   // Blue
-        [255, 127, 14],   // Orange  
-        [44, 160, 44],    // Green
-        [214, 39, 40],    // Red
-        [148, 103, 189],  // Purple
-        [140, 86, 75],    // Brown
-        [227, 119, 194],  // Pink
-        [127, 127, 127],  // Gray
-    ];
-    colors[index % colors.len()]
+    let hex = text.strip_prefix('#').unwrap_or(&text);
+    if hex.len() != 6 {
+        return None;
+    }
+// Variable declaration
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
 }
 
 /// Function: explain its purpose and key arguments
 pub fn pick_file() -> Option<PathBuf> {
     rfd::FileDialog::new()
         .add_filter("csv", &["csv"])
+        .add_filter("Text table", TEXT_TABLE_EXTENSIONS)
         .add_filter("xvg", &["xvg"])
+        .add_filter("json", &["json"])
+        .add_filter("Spreadsheet", SPREADSHEET_EXTENSIONS)
+        .add_filter("NumPy", &["npy", "npz"])
+        .add_filter("Parquet", &["parquet", "pq"])
+        .add_filter("HDF5", HDF5_EXTENSIONS)
+        .add_filter("XPM matrix", &["xpm"])
         .pick_file()
 }
+// Every file type that can be opened as data
+pub const DATA_FILE_EXTENSIONS: &[&str] = &[
+    "csv", "tsv", "txt", "dat", "out", "xy", "xye", "xvg", "json", "xlsx", "xlsm", "xlsb", "xls", "ods", "npy", "npz",
+    "parquet", "pq", "h5", "hdf5", "he5", "hdf", "xpm", "gz", "xz",
+];
+
 /// Function: explain its purpose and key arguments
 pub fn pick_multiple_files() -> Option<Vec<PathBuf>> {
     rfd::FileDialog::new()
-        .add_filter("Data files", &["csv", "xvg"])
+        .add_filter("Data files", DATA_FILE_EXTENSIONS)
         .add_filter("CSV", &["csv"])
+        .add_filter("Text table", TEXT_TABLE_EXTENSIONS)
         .add_filter("XVG", &["xvg"])
+        .add_filter("JSON", &["json"])
+        .add_filter("Spreadsheet", SPREADSHEET_EXTENSIONS)
+        .add_filter("NumPy", &["npy", "npz"])
+        .add_filter("Parquet", &["parquet", "pq"])
+        .add_filter("HDF5", HDF5_EXTENSIONS)
+        .add_filter("XPM matrix", &["xpm"])
+        .add_filter("Compressed", COMPRESSED_EXTENSIONS)
         .pick_files()
 }
+
+/// Function: explain its purpose and key arguments
+pub fn pick_folder() -> Option<PathBuf> {
+    rfd::FileDialog::new().pick_folder()
+}
+/// Function: explain its purpose and key arguments
+pub fn pick_csv_save_path(file_name: &str) -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("CSV", &["csv"])
+        .set_file_name(file_name)
+        .save_file()
+}
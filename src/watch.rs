@@ -0,0 +1,275 @@
+// Import external modules or crates needed in watch.rs
+use crate::compression::{data_extension, is_compressed};
+// Import external modules or crates needed in watch.rs
+use crate::dataset::{DataSource, Dataset};
+// Import external modules or crates needed in watch.rs
+use crate::io::{is_data_line, is_text_table, Delimiter, TextImport, TextImportSettings};
+// Import external modules or crates needed in watch.rs
+use crate::utils::{collect_series, load_data_file, parse_xvg, LoadedSeries, SeriesColumns};
+// Import external modules or crates needed in watch.rs
+use notify::{EventKind, RecursiveMode, Watcher};
+// Import external modules or crates needed in watch.rs
+use std::collections::{HashMap, HashSet};
+// Import external modules or crates needed in watch.rs
+use std::io::{Read, Seek, SeekFrom};
+// Import external modules or crates needed in watch.rs
+use std::path::{Path, PathBuf};
+// Import external modules or crates needed in watch.rs
+use std::sync::mpsc::{channel, Receiver};
+// Import external modules or crates needed in watch.rs
+use std::sync::{Arc, Mutex};
+// Import external modules or crates needed in watch.rs
+use std::time::Duration;
+
+// Live data (followed files and standard input) wakes the UI at most this long after it
+// arrives, so a burst of writes or lines is drawn in one frame instead of one frame each
+pub const LIVE_REPAINT_DELAY: Duration = Duration::from_millis(50);
+
+// Record where each dataset came from; datasets are in the order of the file's series
+/// Function: explain its purpose and key arguments
+pub fn set_sources(datasets: &mut [Dataset], path: &Path, text_import: Option<TextImportSettings>) {
+    for (series, dataset) in datasets.iter_mut().enumerate() {
+        dataset.source = Some(DataSource {
+            path: path.to_path_buf(),
+            series,
+            text_import: text_import.clone(),
+        });
+    }
+}
+
+// All series of a dataset's source file, read the way it was first imported
+/// Function: explain its purpose and key arguments
+pub fn read_source(source: &DataSource) -> Result<Vec<LoadedSeries>, Box<dyn std::error::Error>> {
+    match &source.text_import {
+        Some(settings) => TextImport::reload(&source.path, settings),
+        None => load_data_file(&source.path).map(|loaded| loaded.series),
+    }
+}
+
+// Watches the directories of followed files (so files replaced by a rename are still
+// seen) and reports which followed files changed. The notify callback runs on its own
+// thread and wakes the UI so changes show up without any user input; changes to other
+// files in the same directories are dropped there and do not cost a frame.
+/// Data structure used in watch.rs module
+pub struct FileWatcher {
+    watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    directories: HashSet<PathBuf>,
+    files: HashMap<PathBuf, PathBuf>, // Canonical path -> path as stored in the dataset
+    requested: HashSet<PathBuf>, // Files as last passed to set_files
+    unresolved: bool, // Some of them could not be canonicalized (they did not exist yet)
+    watched: Arc<Mutex<HashSet<PathBuf>>>, // Canonical paths, shared with the notify callback
+}
+
+// Whether a notify event may change a followed file
+/// Function: explain its purpose and key arguments
+fn is_followed_change(event: &notify::Event, watched: &HashSet<PathBuf>) -> bool {
+    matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) && event.paths.iter().any(|path| watched.contains(path))
+}
+
+/// Implementation block defining methods for this type
+impl FileWatcher {
+/// Function: explain its purpose and key arguments
+    pub fn new(ctx: egui::Context) -> notify::Result<Self> {
+// Variable declaration
+        let (sender, events) = channel();
+// Variable declaration
+        let watched: Arc<Mutex<HashSet<PathBuf>>> = Arc::default();
+// Variable declaration
+        let filter = Arc::clone(&watched);
+// Variable declaration
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            // Errors are passed on so the UI can report them
+// Variable declaration
+            let relevant = match &event {
+                Ok(event) => filter.lock().is_ok_and(|watched| is_followed_change(event, &watched)),
+                Err(_) => true,
+            };
+            if relevant && sender.send(event).is_ok() {
+                ctx.request_repaint_after(LIVE_REPAINT_DELAY);
+            }
+        })?;
+        Ok(Self {
+            watcher,
+            events,
+            directories: HashSet::new(),
+            files: HashMap::new(),
+            requested: HashSet::new(),
+            unresolved: false,
+            watched,
+        })
+    }
+
+    // Follow exactly these files: start and stop directory watches as needed. Called every
+    // frame, so nothing is done while the set stays the same and every file was found.
+/// Function: explain its purpose and key arguments
+    pub fn set_files(&mut self, files: &HashSet<PathBuf>) -> notify::Result<()> {
+        if *files == self.requested && !self.unresolved {
+            return Ok(());
+        }
+        self.requested = files.clone();
+        self.unresolved = false;
+        self.files = files
+            .iter()
+            .map(|path| {
+// Variable declaration
+                let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| {
+                    self.unresolved = true;
+                    path.clone()
+                });
+                (canonical, path.clone())
+            })
+            .collect();
+        if let Ok(mut watched) = self.watched.lock() {
+            *watched = self.files.keys().cloned().collect();
+        }
+// Variable declaration
+        let wanted: HashSet<PathBuf> = self
+            .files
+            .keys()
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect();
+
+        for directory in self.directories.difference(&wanted) {
+            // The directory may already be gone; nothing left to stop then
+            let _ = self.watcher.unwatch(directory);
+        }
+        for directory in wanted.difference(&self.directories) {
+            self.watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+        self.directories = wanted;
+        Ok(())
+    }
+
+    // Followed files written, created or renamed into place since the last call
+/// Function: explain its purpose and key arguments
+    pub fn changed_files(&mut self) -> HashSet<PathBuf> {
+// Variable declaration
+        let mut changed = HashSet::new();
+        while let Ok(event) = self.events.try_recv() {
+// Variable declaration
+            let Ok(event) = event else {
+                continue;
+            };
+            if !matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                continue;
+            }
+            for path in event.paths {
+                if let Some(original) = self.files.get(&path) {
+                    changed.insert(original.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Enum representing a set of related values in watch.rs module
+pub enum TailUpdate {
+    Replaced(Vec<LoadedSeries>), // Every series read from the whole file
+    Appended(Vec<LoadedSeries>), // Only the points on lines added since the last read
+}
+
+// Bytes up to and including the last newline; a trailing partial line is left for later
+/// Function: explain its purpose and key arguments
+fn complete_lines(bytes: &[u8]) -> &[u8] {
+    &bytes[..bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1)]
+}
+
+// Reads a followed CSV, XVG or other text table incrementally: after one full read only
+// the lines appended since are parsed, so growing multi-GB logs stay cheap to follow
+/// Data structure used in watch.rs module
+pub struct TailReader {
+    pub path: PathBuf,
+    pub text_import: Option<TextImportSettings>,
+    offset: u64, // Bytes parsed so far; always just after a newline
+    delimiter: Delimiter,
+    x_col: usize,
+    columns: Vec<SeriesColumns>,
+}
+
+/// Implementation block defining methods for this type
+impl TailReader {
+    // Line-based formats; anything else, and any compressed file, is read whole on every change
+/// Function: explain its purpose and key arguments
+    pub fn can_tail(path: &Path, text_import: Option<&TextImportSettings>) -> bool {
+        !is_compressed(path) && (text_import.is_some() || is_text_table(path) || is_xvg(path))
+    }
+
+    // Full read that also remembers how to parse lines appended later
+/// Function: explain its purpose and key arguments
+    pub fn open(
+        path: &Path,
+        text_import: Option<&TextImportSettings>,
+    ) -> Result<(Self, Vec<LoadedSeries>), Box<dyn std::error::Error>> {
+// Variable declaration
+        let bytes = std::fs::read(path)?;
+// Variable declaration
+        let complete = complete_lines(&bytes);
+// Variable declaration
+        let (delimiter, x_col, columns, series) = if text_import.is_none() && is_xvg(path) {
+// Variable declaration
+            let (loaded, columns) = parse_xvg(complete)?;
+            (Delimiter::Whitespace, 0, columns, loaded.series)
+        } else {
+// Variable declaration
+            let text = String::from_utf8_lossy(complete).into_owned();
+// Variable declaration
+            let import = match text_import {
+                Some(settings) => TextImport::with_settings(path, text, settings)?,
+                None => TextImport::from_text(path.to_path_buf(), text, 0)?,
+            };
+            (import.delimiter, import.x_col, import.series_columns(), import.selected_series())
+        };
+// Variable declaration
+        let reader = Self {
+            path: path.to_path_buf(),
+            text_import: text_import.cloned(),
+            offset: complete.len() as u64,
+            delimiter,
+            x_col,
+            columns,
+        };
+        Ok((reader, series))
+    }
+
+    // Points from complete lines written since the last read. A file that got shorter
+    // was truncated or replaced, so it is read again from the start.
+/// Function: explain its purpose and key arguments
+    pub fn read_new(&mut self) -> Result<TailUpdate, Box<dyn std::error::Error>> {
+// Variable declaration
+        let mut file = std::fs::File::open(&self.path)?;
+        if file.metadata()?.len() < self.offset {
+// Variable declaration
+            let (reader, series) = Self::open(&self.path, self.text_import.as_ref())?;
+            *self = reader;
+            return Ok(TailUpdate::Replaced(series));
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+// Variable declaration
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+// Variable declaration
+        let complete = complete_lines(&bytes);
+        self.offset += complete.len() as u64;
+
+// Variable declaration
+        let text = String::from_utf8_lossy(complete);
+// Variable declaration
+        let data: Vec<&str> = text
+            .lines()
+            .filter(|line| is_data_line(line))
+            .collect();
+// Variable declaration
+        let rows = self.delimiter.split(&data.join("\n"))?;
+// Variable declaration
+        let rows: Vec<Vec<&str>> = rows.iter().map(|row| row.iter().map(String::as_str).collect()).collect();
+        Ok(TailUpdate::Appended(collect_series(&rows, self.x_col, &self.columns)))
+    }
+}
+
+/// Function: explain its purpose and key arguments
+fn is_xvg(path: &Path) -> bool {
+    data_extension(path).as_deref() == Some("xvg")
+}